@@ -0,0 +1,188 @@
+use leptos::prelude::*;
+use turbochat_shared::{ActivityKind, AgentActivity};
+use wasm_bindgen::prelude::*;
+use std::collections::HashMap;
+
+use crate::i18n::T;
+
+/// Đang mở hội thoại → gửi lại "đang xem" theo chu kỳ này (admin khác bỏ sau VIEWING_TTL_MS nếu không nhận được)
+pub(crate) const VIEWING_HEARTBEAT_MS: i32 = 20_000;
+/// Gõ liên tục → gửi "đang gõ" tối đa mỗi chừng này ms
+pub(crate) const TYPING_THROTTLE_MS: f64 = 3_000.0;
+const VIEWING_TTL_MS: f64 = 45_000.0;
+const TYPING_TTL_MS: f64 = 6_000.0;
+/// Admin khác vừa trả lời khách trong chừng này ms → cảnh báo ở ô soạn tin
+pub(crate) const COLLISION_WINDOW_MS: f64 = 20_000.0;
+// Tin admin lạ tới → người gõ trong hội thoại gần nhất (trong khoảng này) được coi là người trả lời
+const AUTHOR_LOOKBACK_MS: f64 = 30_000.0;
+// Tính lại dòng "đang xem / đang gõ" để bỏ mục quá hạn
+const TICK_MS: i32 = 2_000;
+
+#[derive(Clone, Debug)]
+struct Seen {
+    guest_id: u64,
+    agent_id: String,
+    agent_name: String,
+    viewing_at: f64,
+    typing: bool,
+    // Lần gõ gần nhất trong hội thoại này (giữ cả khi đã về "đang xem" - để đoán ai vừa gửi tin), 0 = chưa gõ
+    typed_at: f64,
+}
+
+#[derive(Clone, Debug)]
+struct Reply {
+    // None = không rõ ai (tin hẹn giờ / API / admin không gửi "đang gõ")
+    author: Option<String>,
+    at: f64,
+}
+
+// ============================================================================
+// ADMIN KHÁC ĐANG XEM / ĐANG GÕ - frame AgentActivity theo từng trình duyệt (device_id),
+// mục nào lâu không được gửi lại thì tự hết hạn. Kèm tin trả lời vừa tới từ admin khác
+// (cảnh báo trả lời trùng)
+// ============================================================================
+#[derive(Clone, Copy)]
+pub(crate) struct Colleagues {
+    peers: RwSignal<HashMap<String, Seen>>,
+    replies: RwSignal<HashMap<u64, Reply>>,
+}
+
+impl Colleagues {
+    pub fn new() -> Self {
+        Self { peers: RwSignal::new(HashMap::new()), replies: RwSignal::new(HashMap::new()) }
+    }
+
+    /// Frame từ server; bỏ frame của chính trình duyệt này (`own_device` = agent_id gửi khi kết nối WS)
+    pub fn apply(&self, activity: AgentActivity, own_device: &str) {
+        if activity.device_id.is_empty() || activity.device_id == own_device {
+            return;
+        }
+        let now = js_sys::Date::now();
+        let kind = activity.kind();
+        self.peers.update(|peers| match kind {
+            ActivityKind::Idle => {
+                if activity.guest_id == 0 || peers.get(&activity.device_id).is_some_and(|p| p.guest_id == activity.guest_id) {
+                    peers.remove(&activity.device_id);
+                }
+            }
+            ActivityKind::Viewing | ActivityKind::Typing => {
+                let typing = kind == ActivityKind::Typing;
+                let typed_at = match peers.get(&activity.device_id) {
+                    _ if typing => now,
+                    Some(prev) if prev.guest_id == activity.guest_id => prev.typed_at,
+                    _ => 0.0,
+                };
+                peers.insert(activity.device_id, Seen {
+                    guest_id: activity.guest_id,
+                    agent_id: activity.agent_id,
+                    agent_name: activity.agent_name,
+                    viewing_at: now,
+                    typing,
+                    typed_at,
+                });
+            }
+        });
+    }
+
+    /// (tên, đang gõ) của các admin khác trong hội thoại - nhiều tab của cùng người gộp làm một
+    fn in_conversation(&self, guest_id: u64, now: f64) -> Vec<(String, bool)> {
+        let mut people: Vec<(String, String, bool)> = Vec::new();
+        self.peers.with(|peers| {
+            for seen in peers.values().filter(|p| p.guest_id == guest_id && now - p.viewing_at < VIEWING_TTL_MS) {
+                let typing = seen.typing && now - seen.typed_at < TYPING_TTL_MS;
+                match people.iter_mut().find(|(agent, _, _)| *agent == seen.agent_id) {
+                    Some(person) => person.2 |= typing,
+                    None => people.push((seen.agent_id.clone(), display_name(seen), typing)),
+                }
+            }
+        });
+        people.sort_by(|a, b| a.1.cmp(&b.1));
+        people.into_iter().map(|(_, name, typing)| (name, typing)).collect()
+    }
+
+    /// Tin admin không phải do tab này gửi vừa tới hội thoại
+    pub fn note_reply(&self, guest_id: u64) {
+        let now = js_sys::Date::now();
+        let author = self.peers.with_untracked(|peers| {
+            peers.values()
+                .filter(|p| p.guest_id == guest_id && p.typed_at > 0.0 && now - p.typed_at < AUTHOR_LOOKBACK_MS)
+                .max_by(|a, b| a.typed_at.total_cmp(&b.typed_at))
+                .map(display_name)
+        });
+        self.replies.update(|r| { r.insert(guest_id, Reply { author, at: now }); });
+    }
+
+    fn dismiss_reply(&self, guest_id: u64) {
+        self.replies.update(|r| { r.remove(&guest_id); });
+    }
+
+    /// Some(tác giả) nếu admin khác vừa trả lời hội thoại trong COLLISION_WINDOW_MS
+    fn recent_reply(&self, guest_id: u64, now: f64) -> Option<Option<String>> {
+        self.replies.with(|r| r.get(&guest_id).filter(|reply| now - reply.at < COLLISION_WINDOW_MS).map(|reply| reply.author.clone()))
+    }
+}
+
+fn display_name(seen: &Seen) -> String {
+    if seen.agent_name.is_empty() { T::ShopOwner.text().to_string() } else { seen.agent_name.clone() }
+}
+
+// Đồng hồ (ms) cập nhật mỗi TICK_MS khi component còn hiển thị
+fn clock() -> RwSignal<f64> {
+    let now = RwSignal::new(js_sys::Date::now());
+    let tick = Closure::wrap(Box::new(move || now.set(js_sys::Date::now())) as Box<dyn FnMut()>);
+    let timer = web_sys::window().unwrap()
+        .set_interval_with_callback_and_timeout_and_arguments_0(tick.as_ref().unchecked_ref(), TICK_MS)
+        .ok();
+    tick.forget();
+    on_cleanup(move || {
+        if let Some(handle) = timer {
+            web_sys::window().unwrap().clear_interval_with_handle(handle);
+        }
+    });
+    now
+}
+
+/// "Nam đang gõ… · Lan đang xem" dưới tên khách ở đầu hội thoại
+#[component]
+pub fn ActivityIndicator(colleagues: Colleagues, guest_id: ReadSignal<u64>) -> impl IntoView {
+    let now = clock();
+
+    let text = Memo::new(move |_| {
+        let people = colleagues.in_conversation(guest_id.get(), now.get());
+        let names = |typing: bool| people.iter().filter(|p| p.1 == typing).map(|p| p.0.as_str()).collect::<Vec<_>>().join(", ");
+        let (typing, viewing) = (names(true), names(false));
+        let mut parts = Vec::new();
+        if !typing.is_empty() {
+            parts.push(T::AgentsTyping.fill(&[&typing]));
+        }
+        if !viewing.is_empty() {
+            parts.push(T::AgentsViewing.fill(&[&viewing]));
+        }
+        parts.join(" · ")
+    });
+
+    view! {
+        <Show when=move || !text.with(String::is_empty)>
+            <div class="agent-activity" role="status">{move || text.get()}</div>
+        </Show>
+    }
+}
+
+/// "Linh vừa trả lời khách" trên ô soạn tin - tránh hai người cùng trả lời một câu hỏi
+#[component]
+pub fn CollisionWarning(colleagues: Colleagues, guest_id: ReadSignal<u64>) -> impl IntoView {
+    let now = clock();
+    let recent = Memo::new(move |_| colleagues.recent_reply(guest_id.get(), now.get()));
+
+    view! {
+        {move || recent.get().map(|author| view! {
+            <div class="reply-bar collision-warning" role="alert">
+                <span>{match author {
+                    Some(name) => T::JustReplied.fill(&[&name]),
+                    None => T::AnotherAdminReplied.text().to_string(),
+                }}</span>
+                <button aria-label=T::Close.text() on:click=move |_| colleagues.dismiss_reply(guest_id.get_untracked())>"✕"</button>
+            </div>
+        })}
+    }
+}
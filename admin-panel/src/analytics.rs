@@ -0,0 +1,348 @@
+use leptos::prelude::*;
+use turbochat_shared::{DailyStats, QuotaAction, ReportResponse, ShopUsage};
+use prost::Message as ProstMessage;
+use wasm_bindgen_futures::spawn_local;
+use gloo_net::http::Method;
+use wasm_bindgen::JsCast;
+
+use crate::app::admin_request;
+use crate::i18n::T;
+
+// ============================================================================
+// ANALYTICS PAGE - Biểu đồ từ GET /reports, tải CSV từ GET /reports/export.csv
+// ============================================================================
+#[component]
+pub fn AnalyticsPage(
+    shop_id: String,
+    admin_pin: String,
+    on_close: impl Fn() + 'static + Clone,
+) -> impl IntoView {
+    let (days, set_days) = signal(Vec::<DailyStats>::new());
+    // Khoảng ngày (UTC, gồm cả hai đầu) - chọn nhanh 7/30/90 ngày hoặc tự nhập
+    let range = RwSignal::new(last_days(30));
+    let (preset, set_preset) = signal("30".to_string());
+    let (status, set_status) = signal(String::new());
+    let exporting = RwSignal::new(false);
+
+    let shop = StoredValue::new(shop_id);
+    let pin = StoredValue::new(admin_pin);
+    let (usage, set_usage) = signal(None::<ShopUsage>);
+
+    spawn_local(async move {
+        if let Ok(resp) = admin_request(Method::GET, "/usage", &shop.get_value(), &pin.get_value()).send().await {
+            if let Some(u) = resp.binary().await.ok().and_then(|b| ShopUsage::decode(&b[..]).ok()) {
+                set_usage.set(Some(u));
+            }
+        }
+    });
+
+    Effect::new(move |_| {
+        let (from, to) = range.get();
+        if from.is_empty() || to.is_empty() || from > to {
+            set_status.set(T::InvalidRange.text().to_string());
+            return;
+        }
+        set_status.set(T::Loading.text().to_string());
+        spawn_local(async move {
+            match fetch_report(&shop.get_value(), &pin.get_value(), &from, &to).await {
+                Ok(resp) if resp.success => {
+                    set_days.set(resp.days);
+                    set_status.set(String::new());
+                }
+                Ok(resp) => set_status.set(T::ApiError.fill(&[&resp.error, &resp.request_id])),
+                Err(e) => set_status.set(T::NetworkError.fill(&[&e])),
+            }
+        });
+    });
+
+    let totals = Memo::new(move |_| {
+        let d = days.get();
+        let conversations: u32 = d.iter().map(|s| s.new_conversations).sum();
+        let messages: u32 = d.iter().map(|s| s.guest_messages + s.admin_messages).sum();
+        let mut medians: Vec<u64> = d.iter()
+            .map(|s| s.median_first_response_ms)
+            .filter(|m| *m > 0)
+            .collect();
+        medians.sort_unstable();
+        let median = medians.get(medians.len() / 2).copied().unwrap_or(0);
+        (conversations, messages, median)
+    });
+
+    let messages_per_day = Memo::new(move |_| {
+        days.get().iter()
+            .map(|s| (short_day(&s.day), s.guest_messages + s.admin_messages))
+            .collect::<Vec<_>>()
+    });
+    let conversations_per_day = Memo::new(move |_| {
+        days.get().iter()
+            .map(|s| (short_day(&s.day), s.new_conversations))
+            .collect::<Vec<_>>()
+    });
+    let busiest_hours = Memo::new(move |_| {
+        let mut hours = [0u32; 24];
+        for s in days.get().iter() {
+            for (h, n) in s.hourly_messages.iter().enumerate().take(24) {
+                hours[h] += n;
+            }
+        }
+        hours.iter().enumerate().map(|(h, n)| (format!("{}h", h), *n)).collect::<Vec<_>>()
+    });
+
+    let pick_preset = move |value: String| {
+        if let Ok(n) = value.parse() {
+            range.set(last_days(n));
+        }
+        set_preset.set(value);
+    };
+
+    // Server tạo CSV → tải về qua object URL (không cần dữ liệu thô trên trình duyệt)
+    let download = move |_| {
+        let (from, to) = range.get_untracked();
+        exporting.set(true);
+        spawn_local(async move {
+            let path = format!("/reports/export.csv?from={}&to={}", from, to);
+            match admin_request(Method::GET, &path, &shop.get_value(), &pin.get_value()).send().await {
+                Ok(resp) if resp.ok() => match resp.text().await {
+                    Ok(csv) => save_csv(&csv, &format!("turbochat-{}-{}.csv", from, to)),
+                    Err(e) => set_status.set(T::NetworkError.fill(&[&e])),
+                },
+                Ok(resp) => set_status.set(T::HttpError.fill(&[&resp.status()])),
+                Err(e) => set_status.set(T::NetworkError.fill(&[&e])),
+            }
+            exporting.set(false);
+        });
+    };
+
+    let on_close_click = on_close.clone();
+
+    view! {
+        <div class="settings-panel">
+            <div class="chat-header-bar">
+                <div class="chat-header-info">
+                    <div class="chat-header-name">{T::AnalyticsHeader.text()}</div>
+                    <div class="chat-header-status" role="status">{move || status.get()}</div>
+                </div>
+                <select
+                    class="analytics-range"
+                    aria-label=T::DateRange.text()
+                    prop:value=move || preset.get()
+                    on:change=move |e| pick_preset(event_target_value(&e))
+                >
+                    <option value="7">{T::Days7.text()}</option>
+                    <option value="30">{T::Days30.text()}</option>
+                    <option value="90">{T::Days90.text()}</option>
+                    <option value="custom">{T::CustomRange.text()}</option>
+                </select>
+                <span class="analytics-dates" prop:hidden=move || preset.get() != "custom">
+                    <input
+                        type="date"
+                        aria-label=T::RangeFrom.text()
+                        prop:value=move || range.get().0
+                        on:change=move |e| range.update(|r| r.0 = event_target_value(&e))
+                    />
+                    "–"
+                    <input
+                        type="date"
+                        aria-label=T::RangeTo.text()
+                        prop:value=move || range.get().1
+                        on:change=move |e| range.update(|r| r.1 = event_target_value(&e))
+                    />
+                </span>
+                <button
+                    class="conversation-action analytics-export"
+                    prop:disabled=move || exporting.get()
+                    on:click=download
+                >
+                    {T::DownloadCsv.text()}
+                </button>
+                <button class="settings-close" aria-label=T::Close.text() on:click=move |_| on_close_click()>"✕"</button>
+            </div>
+
+            <div class="settings-body analytics-body">
+                <div class="analytics-cards">
+                    <div class="analytics-card">
+                        <div class="analytics-value">{move || totals.get().0}</div>
+                        <div class="analytics-label">{T::NewConversations.text()}</div>
+                    </div>
+                    <div class="analytics-card">
+                        <div class="analytics-value">{move || totals.get().1}</div>
+                        <div class="analytics-label">{T::Messages.text()}</div>
+                    </div>
+                    <div class="analytics-card">
+                        <div class="analytics-value">{move || format_duration(totals.get().2)}</div>
+                        <div class="analytics-label">{T::FirstResponse.text()}</div>
+                    </div>
+                </div>
+
+                {move || usage.get().map(|u| view! { <UsageCard usage=u /> })}
+
+                <div class="settings-section">
+                    <h3>{T::MessagesPerDay.text()}</h3>
+                    <BarChart data=messages_per_day />
+                </div>
+                <div class="settings-section">
+                    <h3>{T::ConversationsPerDay.text()}</h3>
+                    <BarChart data=conversations_per_day />
+                </div>
+                <div class="settings-section">
+                    <h3>{T::PeakHours.text()}</h3>
+                    <BarChart data=busiest_hours />
+                </div>
+            </div>
+        </div>
+    }
+}
+
+// Mức sử dụng tháng này so với hạn mức (GET /usage)
+#[component]
+fn UsageCard(usage: ShopUsage) -> impl IntoView {
+    let quota = usage.quota.unwrap_or_default();
+    let queue_note = if quota.over_limit() == QuotaAction::Queue {
+        T::OverQuotaQueue.text()
+    } else {
+        T::OverQuotaReject.text()
+    };
+
+    view! {
+        <div class="settings-section">
+            <h3>{T::MonthlyQuota.fill(&[&usage.month])}</h3>
+            <UsageMeter label=T::Messages.text() used=usage.messages.to_string() percent=percent(usage.messages, quota.monthly_messages) limit=format_limit(quota.monthly_messages, |n| n.to_string()) />
+            <UsageMeter label=T::GuestConnections.text() used=usage.connections.to_string() percent=percent(usage.connections as u64, quota.concurrent_connections as u64) limit=format_limit(quota.concurrent_connections as u64, |n| n.to_string()) />
+            <UsageMeter label=T::Storage.text() used=format_bytes(usage.storage_bytes) percent=percent(usage.storage_bytes, quota.storage_bytes) limit=format_limit(quota.storage_bytes, format_bytes) />
+            <div class="settings-hint">
+                {queue_note}
+                {(usage.queued > 0).then(|| T::QueuedCount.fill(&[&usage.queued]))}
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn UsageMeter(label: &'static str, used: String, limit: String, percent: Option<u64>) -> impl IntoView {
+    view! {
+        <div class="usage-row">
+            <div class="usage-label">
+                <span>{label}</span>
+                <span>{used} " / " {limit}</span>
+            </div>
+            {percent.map(|p| {
+                let class = match p {
+                    p if p >= 100 => "usage-bar full",
+                    p if p >= 80 => "usage-bar high",
+                    _ => "usage-bar",
+                };
+                view! {
+                    <div class=class>
+                        <div style=format!("width: {}%", p.min(100))></div>
+                    </div>
+                }
+            })}
+        </div>
+    }
+}
+
+// None = không giới hạn
+fn percent(used: u64, limit: u64) -> Option<u64> {
+    (limit > 0).then(|| used * 100 / limit)
+}
+
+fn format_limit(limit: u64, format: fn(u64) -> String) -> String {
+    if limit == 0 { T::Unlimited.text().to_string() } else { format(limit) }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        b if b < 1024 => format!("{} B", b),
+        b if b < 1024 * 1024 => format!("{:.1} KB", b as f64 / 1024.0),
+        b if b < 1024 * 1024 * 1024 => format!("{:.1} MB", b as f64 / (1024.0 * 1024.0)),
+        b => format!("{:.2} GB", b as f64 / (1024.0 * 1024.0 * 1024.0)),
+    }
+}
+
+// Biểu đồ cột SVG đơn giản
+#[component]
+fn BarChart(data: Memo<Vec<(String, u32)>>) -> impl IntoView {
+    const WIDTH: f64 = 520.0;
+    const HEIGHT: f64 = 140.0;
+
+    view! {
+        <svg class="bar-chart" viewBox=format!("0 0 {} {}", WIDTH, HEIGHT + 16.0)>
+            {move || {
+                let points = data.get();
+                let max = points.iter().map(|(_, v)| *v).max().unwrap_or(0).max(1) as f64;
+                let slot = WIDTH / points.len().max(1) as f64;
+                // Chỉ hiện nhãn thưa để không bị chồng chữ
+                let label_every = (points.len() / 12).max(1);
+                points.into_iter().enumerate().map(|(i, (label, value))| {
+                    let h = value as f64 / max * HEIGHT;
+                    let x = i as f64 * slot;
+                    view! {
+                        <g>
+                            <rect x=x + slot * 0.15 y=HEIGHT - h width=slot * 0.7 height=h>
+                                <title>{format!("{}: {}", label, value)}</title>
+                            </rect>
+                            {(i % label_every == 0).then(|| view! {
+                                <text x=x + slot / 2.0 y=HEIGHT + 12.0 text-anchor="middle">{label.clone()}</text>
+                            })}
+                        </g>
+                    }
+                }).collect_view()
+            }}
+        </svg>
+    }
+}
+
+async fn fetch_report(shop_id: &str, admin_pin: &str, from: &str, to: &str) -> Result<ReportResponse, String> {
+    let resp = admin_request(Method::GET, &format!("/reports?from={}&to={}", from, to), shop_id, admin_pin)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let bytes = resp.binary().await.map_err(|e| e.to_string())?;
+    ReportResponse::decode(&bytes[..]).map_err(|e| e.to_string())
+}
+
+// n ngày gần nhất tới hôm nay, dạng "YYYY-MM-DD" theo UTC như thống kê trên server
+fn last_days(n: u32) -> (String, String) {
+    let day = |date: &js_sys::Date| String::from(date.to_iso_string()).chars().take(10).collect::<String>();
+    let today = js_sys::Date::new_0();
+    let from = js_sys::Date::new(&today.get_time().into());
+    from.set_utc_date(from.get_utc_date() + 1 - n);
+    (day(&from), day(&today))
+}
+
+// Lưu CSV thành file qua thẻ <a download> tạm
+fn save_csv(csv: &str, filename: &str) {
+    let parts = js_sys::Array::of1(&csv.into());
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("text/csv;charset=utf-8");
+    let Ok(blob) = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options) else { return };
+    let Ok(url) = web_sys::Url::create_object_url_with_blob(&blob) else { return };
+    let document = web_sys::window().unwrap().document().unwrap();
+    if let Some(link) = document.create_element("a").ok().and_then(|el| el.dyn_into::<web_sys::HtmlAnchorElement>().ok()) {
+        link.set_href(&url);
+        link.set_download(filename);
+        link.click();
+    }
+    let _ = web_sys::Url::revoke_object_url(&url);
+}
+
+// "2026-03-05" → "05/03"
+fn short_day(day: &str) -> String {
+    let parts: Vec<&str> = day.split('-').collect();
+    match parts.as_slice() {
+        [_, m, d] => format!("{}/{}", d, m),
+        _ => day.to_string(),
+    }
+}
+
+pub(crate) fn format_duration(ms: u64) -> String {
+    if ms == 0 {
+        return "—".to_string();
+    }
+    match ms / 1000 {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m {}s", s / 60, s % 60),
+        s => format!("{}h {}m", s / 3600, (s % 3600) / 60),
+    }
+}
@@ -1,24 +1,79 @@
 use leptos::prelude::*;
-use leptos::html::Div;
-use turbochat_shared::{Message as ChatMessage, AdminAuthRequest, AdminAuthResponse, SyncRequest, SyncResponse, GuestListRequest, GuestListResponse};
+use leptos::html::{Div, Input};
+use turbochat_shared::{ws_envelope, WsEnvelope, CLOSE_SERVER_DRAINING, CLOSE_UNSUPPORTED_PROTOCOL, PROTOCOL_VERSION, EditMessage, Message as ChatMessage, MessageIdGenerator, ReplyPreview, QuickReply, is_safe_url, render_markdown, format_clock, format_datetime, format_day_label, format_list_time, is_new_day, now_us, AdminAuthRequest, AdminAuthResponse, SyncRequest, SyncResponse, GuestListRequest, GuestListResponse, Guest, GuestNote, ConversationTransfer, HandoffReason, MessageHistory, MessageRevision, BlockListResponse, ConversationStatus, ConversationStatusRequest, AdminPreferences, ActivityKind, AgentActivity, AgentStatus, Reminder, SavedView, ScheduledMessage, ServerNotice, SettingsRequest, ShopFlags, ShopSettings, SlaLevel, EmojiPicker, Attachment, ColorScheme, ThemeMode, text_matches};
 use prost::Message as ProstMessage;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::spawn_local;
 use web_sys::{WebSocket, MessageEvent, CloseEvent, ErrorEvent};
-use gloo_net::http::Request;
-use std::collections::HashMap;
+use gloo_net::http::{Method, Request, RequestBuilder};
+use std::collections::{HashMap, HashSet};
+
+use crate::activity::{ActivityIndicator, CollisionWarning, Colleagues, COLLISION_WINDOW_MS, TYPING_THROTTLE_MS, VIEWING_HEARTBEAT_MS};
+use crate::analytics::AnalyticsPage;
+use crate::bulk::BulkBar;
+use crate::campaigns::CampaignsPage;
+use crate::flagged::FlaggedPage;
+use crate::invite::{pending_invite, AcceptInvitePage};
+use crate::live::LivePage;
+use crate::notify;
+use crate::preferences::{load_preferences, PreferencesPage};
+use crate::profile::{apply_note, load_notes, GuestProfile};
+use crate::reminders::{apply_reminder, load_reminders, SnoozeMenu};
+use crate::scheduled::{apply_scheduled, schedule_message, ScheduledBar};
+use crate::search::ConversationSearch;
+use crate::transfer::{transfer_label, TransferPanel};
+use crate::settings::{post_settings, SettingsPanel};
+use crate::upload;
+use crate::views::{load_views, view_matches, ViewEditor};
+use crate::shops::{load_sessions, save_sessions, AdminSession, ShopSwitcher, ShopWatchers};
+use crate::i18n::T;
+
+pub const API_BASE: &str = "http://localhost:8080";
+pub const WS_BASE: &str = "ws://localhost:8080";
+
+/// Request REST có header xác thực admin (x-shop-id / x-admin-pin)
+pub(crate) fn admin_request(method: Method, path: &str, shop_id: &str, admin_pin: &str) -> RequestBuilder {
+    RequestBuilder::new(&format!("{}{}", API_BASE, path))
+        .method(method)
+        .header("x-shop-id", shop_id)
+        .header("x-admin-pin", admin_pin)
+}
+
+// Màn hình bên phải của Dashboard
+#[derive(Clone, Copy, PartialEq)]
+enum DashboardView {
+    Chat,
+    Settings,
+    Analytics,
+    Flagged,
+    Live,
+    Campaigns,
+    Preferences,
+}
 
 #[derive(Clone)]
 struct SendWebSocket(WebSocket);
 unsafe impl Send for SendWebSocket {}
 unsafe impl Sync for SendWebSocket {}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 struct ChatUser {
     guest_id: u64,
-    name: String,
     last_message: String,
     time: String,
+    // timestamp_us của tin gần nhất - frame Guest cũ hơn không ghi đè
+    last_at: u64,
+}
+
+impl ChatUser {
+    fn from_guest(guest: &Guest) -> Self {
+        Self {
+            guest_id: guest.guest_id,
+            last_message: guest.last_message_preview.clone(),
+            time: if guest.last_message_at > 0 { format_list_time(guest.last_message_at, now_us()) } else { String::new() },
+            last_at: guest.last_message_at,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]  // ← THÊM PartialEq cho Memo
@@ -26,46 +81,207 @@ struct DisplayMessage {
     id: u64,
     sender_type: String,
     text: String,
-    time: String,
+    timestamp_us: u64,
     guest_id: u64,
+    edited: bool,
+    deleted: bool,
+    reply: Option<ReplyPreview>,
+    status: SendStatus,
+    quick_replies: Vec<QuickReply>,
+    attachments: Vec<Attachment>,
+    // Ảnh xem trước (object URL) trong lúc đang tải file lên
+    upload_preview: Option<String>,
+}
+
+impl DisplayMessage {
+    // Dòng hệ thống cho ghi chú bàn giao: "Lan → Nam: khách cần đổi size"
+    fn handover(note: &GuestNote, transfer: &ConversationTransfer) -> Self {
+        Self {
+            id: note.note_id,
+            sender_type: "system".to_string(),
+            text: format!("{}: {}", transfer_label(transfer), note.content),
+            timestamp_us: note.created_at,
+            guest_id: note.guest_id,
+            edited: false,
+            deleted: false,
+            reply: None,
+            status: SendStatus::Sent,
+            quick_replies: Vec::new(),
+            attachments: Vec::new(),
+            upload_preview: None,
+        }
+    }
+
+    // Tin tải từ server (/sync, /guests/:id/search) - hội thoại gộp thì xếp vào guest đang xem
+    fn from_server(msg: ChatMessage, guest_id: u64) -> Self {
+        Self {
+            id: msg.message_id,
+            text: message_text(&msg),
+            timestamp_us: msg.timestamp_us,
+            guest_id,
+            edited: msg.edited_at_us > 0,
+            deleted: msg.deleted_at_us > 0,
+            reply: msg.reply_preview,
+            status: SendStatus::Sent,
+            quick_replies: msg.quick_replies,
+            attachments: msg.attachments,
+            upload_preview: None,
+            sender_type: msg.sender_type,
+        }
+    }
+}
+
+// Nội dung hiển thị; dòng hệ thống server gửi mã (Message::SYSTEM_*) → dịch theo ngôn ngữ admin
+fn message_text(msg: &ChatMessage) -> String {
+    match (msg.is_system(), msg.content.as_ref()) {
+        (true, code) if code == ChatMessage::SYSTEM_HANDOFF.as_bytes() => T::HandoffLine.text().to_string(),
+        _ => String::from_utf8_lossy(&msg.content).to_string(),
+    }
+}
+
+fn handoff_reason_label(reason: HandoffReason) -> &'static str {
+    match reason {
+        HandoffReason::FlowEnded => T::HandoffFlowEnded.text(),
+        HandoffReason::HandoffStep => T::HandoffStep.text(),
+        HandoffReason::GuestRequest => T::HandoffGuestRequest.text(),
+        HandoffReason::NoMatch => T::HandoffNoMatch.text(),
+    }
+}
+
+// Tab lọc hội thoại trên sidebar
+#[derive(Clone, Copy, PartialEq)]
+enum ConversationFilter {
+    Open,
+    Closed,
+    All,
+    // Đang tạm ẩn "nhắc lại sau" (các tab khác không hiện)
+    Snoozed,
+}
+
+impl ConversationFilter {
+    fn matches(self, status: ConversationStatus, snoozed: bool) -> bool {
+        match self {
+            ConversationFilter::Snoozed => snoozed,
+            _ if snoozed => false,
+            ConversationFilter::Open => status == ConversationStatus::Open,
+            ConversationFilter::Closed => status == ConversationStatus::Closed,
+            ConversationFilter::All => true,
+        }
+    }
+}
+
+// Trạng thái gửi của tin admin (hiển thị ngay, chờ server phát lại qua Redis)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum SendStatus {
+    Sent,
+    // File đính kèm đang tải lên, chưa gửi tin
+    Uploading,
+    Pending,
+    Failed,
+    // Shop vượt hạn mức, server giữ tin lại gửi sau
+    Queued,
+}
+
+// Quá thời gian này chưa thấy server phát lại → coi như gửi lỗi
+const SEND_ACK_TIMEOUT_MS: i32 = 10_000;
+// Tính lại mức SLA của các hội thoại trên sidebar
+const SLA_TICK_MS: i32 = 30_000;
+// Số hội thoại mỗi lần tải sidebar
+const GUEST_PAGE_SIZE: u32 = 100;
+
+// Bọc đoạn đang chọn trong ô nhập bằng cú pháp markdown
+// (vị trí selection tính theo UTF-16)
+fn wrap_selection(input: &web_sys::HtmlInputElement, before: &str, after: &str, placeholder: &str) -> String {
+    let value: Vec<u16> = input.value().encode_utf16().collect();
+    let len = value.len() as u32;
+    let start = input.selection_start().ok().flatten().unwrap_or(len).min(len) as usize;
+    let end = input.selection_end().ok().flatten().unwrap_or(len).clamp(start as u32, len) as usize;
+
+    let selected = String::from_utf16_lossy(&value[start..end]);
+    format!(
+        "{}{}{}{}{}",
+        String::from_utf16_lossy(&value[..start]),
+        before,
+        if selected.is_empty() { placeholder } else { selected.as_str() },
+        after,
+        String::from_utf16_lossy(&value[end..]),
+    )
+}
+
+// Focus ô soạn tin sau khi giao diện cập nhật (ô nhập còn disabled lúc vừa chọn hội thoại)
+fn focus_soon(input: NodeRef<Input>) {
+    let focus = Closure::once(Box::new(move || {
+        if let Some(el) = input.get_untracked() {
+            let _ = el.focus();
+        }
+    }) as Box<dyn FnOnce()>);
+    let _ = web_sys::window().unwrap().request_animation_frame(focus.as_ref().unchecked_ref());
+    focus.forget();
+}
+
+// Cuộn tới tin được trích dẫn / kết quả tìm kiếm
+pub(crate) fn scroll_to_message(id: u64) {
+    let document = web_sys::window().unwrap().document().unwrap();
+    if let Some(el) = document.get_element_by_id(&format!("msg-{}", id)) {
+        el.scroll_into_view();
+    }
 }
 
 #[component]
 pub fn App() -> impl IntoView {
-    // Auth state
-    let (is_logged_in, set_is_logged_in) = signal(false);
-    let (shop_id, set_shop_id) = signal(String::new());
-    let (shop_name, set_shop_name) = signal(String::new());
-    let (admin_pin, set_admin_pin) = signal(String::new());  // ← LƯU PIN
+    // Auth state - mỗi shop một phiên, shop đang mở = active
+    let (saved_sessions, saved_active) = load_sessions();
+    let sessions = RwSignal::new(saved_sessions);
+    let active = RwSignal::new(saved_active);
+    // Đang đăng nhập thêm shop (vẫn giữ các phiên cũ)
+    let adding_shop = RwSignal::new(false);
     let (pin_input, set_pin_input) = signal(String::new());
     let (shop_id_input, set_shop_id_input) = signal(String::new());
+    // Có email → đăng nhập tài khoản nhân viên (mật khẩu), trống → PIN chủ shop
+    let (email_input, set_email_input) = signal(String::new());
+    // Mở từ link mời (?shop=&invite=) → màn nhận lời mời
+    let invite = RwSignal::new(pending_invite());
     let (login_error, set_login_error) = signal(String::new());
     let (is_loading, set_is_loading) = signal(false);
+    // Sai PIN nhiều lần → server bắt chờ, đếm lùi từng giây
+    let (lockout_secs, set_lockout_secs) = signal(0u32);
 
-    // Check localStorage khi load
+    // Sáng/tối theo hệ điều hành hoặc lựa chọn trong Cài đặt (lưu localStorage) - CSS đọc data-theme trên <html>
+    let color_mode = ThemeMode::new("turbochat_admin_color_scheme", ColorScheme::Auto);
+    provide_context(color_mode);
     Effect::new(move |_| {
-        let window = web_sys::window().unwrap();
-        let storage = window.local_storage().unwrap().unwrap();
-        
-        if let (Some(saved_shop), Some(saved_name), Some(saved_pin)) = (
-            storage.get_item("turbochat_admin_shop").ok().flatten(),
-            storage.get_item("turbochat_admin_name").ok().flatten(),
-            storage.get_item("turbochat_admin_pin").ok().flatten()  // ← LOAD PIN
-        ) {
-            set_shop_id.set(saved_shop);
-            set_shop_name.set(saved_name);
-            set_admin_pin.set(saved_pin);
-            set_is_logged_in.set(true);
+        let theme = color_mode.attr();
+        if let Some(html) = web_sys::window().and_then(|w| w.document()).and_then(|d| d.document_element()) {
+            let _ = html.set_attribute("data-theme", theme);
         }
     });
 
+    // Lưu localStorage + giữ WS nền cho các shop không mở
+    let watchers = ShopWatchers::new();
+    Effect::new(move |_| {
+        let active = active.get();
+        sessions.with(|list| {
+            save_sessions(list, active.as_deref());
+            watchers.sync(list, active.as_deref());
+        });
+    });
+
     // Login handler
     let do_login = move || {
         let shop = shop_id_input.get_untracked();
         let pin = pin_input.get_untracked();
+        let email = email_input.get_untracked().trim().to_string();
         
-        if shop.is_empty() || pin.len() != 6 {
-            set_login_error.set("Shop ID và PIN 6 số là bắt buộc".to_string());
+        if lockout_secs.get_untracked() > 0 {
+            return;
+        }
+        if !email.is_empty() {
+            if shop.is_empty() || pin.is_empty() {
+                set_login_error.set(T::LoginPasswordRequired.text().to_string());
+                return;
+            }
+        } else if shop.is_empty() || pin.len() != 6 {
+            set_login_error.set(T::LoginPinRequired.text().to_string());
             return;
         }
         
@@ -77,9 +293,10 @@ pub fn App() -> impl IntoView {
             let req = AdminAuthRequest {
                 shop_id: shop.clone(),
                 admin_pin: pin_clone.clone(),
+                email,
             };
             
-            let result = Request::post("http://localhost:8080/auth")
+            let result = Request::post(&format!("{}/auth", API_BASE))
                 .header("Content-Type", "application/octet-stream")
                 .body(req.encode_to_vec())
                 .unwrap()
@@ -93,16 +310,30 @@ pub fn App() -> impl IntoView {
                     if let Ok(bytes) = resp.binary().await {
                         if let Ok(auth_resp) = AdminAuthResponse::decode(&bytes[..]) {
                             if auth_resp.success {
-                                let window = web_sys::window().unwrap();
-                                let storage = window.local_storage().unwrap().unwrap();
-                                let _ = storage.set_item("turbochat_admin_shop", &shop);
-                                let _ = storage.set_item("turbochat_admin_name", &auth_resp.shop_name);
-                                let _ = storage.set_item("turbochat_admin_pin", &pin_clone);  // ← LƯU PIN
-                                
-                                set_shop_id.set(shop);
-                                set_shop_name.set(auth_resp.shop_name);
-                                set_admin_pin.set(pin_clone);  // ← SET PIN STATE
-                                set_is_logged_in.set(true);
+                                let session = AdminSession {
+                                    shop_id: shop.clone(),
+                                    shop_name: auth_resp.shop_name,
+                                    // Nhân viên: lưu session token, không lưu mật khẩu
+                                    admin_pin: if auth_resp.session_token.is_empty() { pin_clone } else { auth_resp.session_token },
+                                };
+                                sessions.update(|list| {
+                                    match list.iter_mut().find(|s| s.shop_id == shop) {
+                                        Some(existing) => *existing = session,
+                                        None => list.push(session),
+                                    }
+                                });
+                                set_shop_id_input.set(String::new());
+                                set_pin_input.set(String::new());
+                                set_email_input.set(String::new());
+                                adding_shop.set(false);
+                                active.set(Some(shop));
+                            } else if auth_resp.retry_after_secs > 0 && auth_resp.remaining_attempts == 0 {
+                                set_login_error.set(T::LoginLocked.text().to_string());
+                                start_lockout(lockout_secs, set_lockout_secs, auth_resp.retry_after_secs);
+                            } else if auth_resp.retry_after_secs > 0 || auth_resp.remaining_attempts > 0 {
+                                let what = if email_input.get_untracked().trim().is_empty() { T::WrongPin.text() } else { T::WrongPassword.text() };
+                                set_login_error.set(T::AttemptsLeft.fill(&[&what, &auth_resp.remaining_attempts]));
+                                start_lockout(lockout_secs, set_lockout_secs, auth_resp.retry_after_secs);
                             } else {
                                 set_login_error.set(auth_resp.error);
                             }
@@ -110,50 +341,104 @@ pub fn App() -> impl IntoView {
                     }
                 }
                 Err(e) => {
-                    set_login_error.set(format!("Lỗi kết nối: {}", e));
+                    set_login_error.set(T::LoginNetworkError.fill(&[&e]));
                 }
             }
         });
     };
 
-    // Logout handler
+    // Logout handler - chỉ đăng xuất shop đang mở, chuyển sang shop còn lại (nếu có)
     let do_logout = move || {
-        let window = web_sys::window().unwrap();
-        let storage = window.local_storage().unwrap().unwrap();
-        let _ = storage.remove_item("turbochat_admin_shop");
-        let _ = storage.remove_item("turbochat_admin_name");
-        let _ = storage.remove_item("turbochat_admin_pin");  // ← XÓA PIN
-        set_is_logged_in.set(false);
-        set_shop_id.set(String::new());
-        set_shop_name.set(String::new());
-        set_admin_pin.set(String::new());
+        let current = active.get_untracked();
+        sessions.update(|list| list.retain(|s| Some(&s.shop_id) != current.as_ref()));
+        active.set(sessions.with_untracked(|list| list.first().map(|s| s.shop_id.clone())));
+    };
+
+    let current_session = Memo::new(move |_| {
+        let id = active.get()?;
+        sessions.with(|list| list.iter().find(|s| s.shop_id == id).cloned())
+    });
+
+    // Nhận lời mời xong → đăng nhập luôn vào shop đó
+    let on_invite_accepted = move |session: AdminSession| {
+        let shop = session.shop_id.clone();
+        sessions.update(|list| {
+            list.retain(|s| s.shop_id != shop);
+            list.push(session);
+        });
+        invite.set(None);
+        adding_shop.set(false);
+        active.set(Some(shop));
     };
 
     view! {
-        <Show 
-            when=move || is_logged_in.get()
-            fallback=move || view! { 
-                <LoginPage 
-                    shop_id_input=shop_id_input
-                    set_shop_id_input=set_shop_id_input
-                    pin_input=pin_input
-                    set_pin_input=set_pin_input
-                    login_error=login_error
-                    is_loading=is_loading
-                    on_login=do_login
-                /> 
-            }
-        >
-            <Dashboard 
-                shop_id=shop_id.get() 
-                shop_name=shop_name.get()
-                admin_pin=admin_pin.get()
-                on_logout=do_logout
+        <style>{include_str!("../theme.css")}</style>
+        {move || invite.get().map(|(shop_id, token)| view! {
+            <AcceptInvitePage
+                shop_id=shop_id
+                token=token
+                on_accepted=on_invite_accepted
+                on_cancel=move || invite.set(None)
             />
+        })}
+        <Show when=move || invite.with(Option::is_none)>
+            <Show 
+                when=move || current_session.with(Option::is_some) && !adding_shop.get()
+                fallback=move || view! { 
+                    <LoginPage 
+                        shop_id_input=shop_id_input
+                        set_shop_id_input=set_shop_id_input
+                        pin_input=pin_input
+                        set_pin_input=set_pin_input
+                        email_input=email_input
+                        set_email_input=set_email_input
+                        login_error=login_error
+                        is_loading=is_loading
+                        lockout_secs=lockout_secs
+                        on_login=do_login
+                        on_cancel=move || adding_shop.set(false)
+                        can_cancel=Signal::derive(move || current_session.with(Option::is_some))
+                    /> 
+                }
+            >
+                // Đổi shop → dựng lại Dashboard (WS, state) cho shop mới
+                {move || current_session.get().map(|session| view! {
+                    <Dashboard 
+                        shop_id=session.shop_id
+                        admin_pin=session.admin_pin
+                        on_logout=do_logout
+                        sessions=sessions
+                        active=active
+                        adding_shop=adding_shop
+                        other_unread=watchers.unread
+                    />
+                })}
+            </Show>
         </Show>
     }
 }
 
+// Bắt đầu đếm lùi thời gian chờ đăng nhập (đang đếm thì chỉ cập nhật số giây)
+fn start_lockout(lockout_secs: ReadSignal<u32>, set_lockout_secs: WriteSignal<u32>, secs: u32) {
+    let running = lockout_secs.get_untracked() > 0;
+    set_lockout_secs.set(secs);
+    if secs > 0 && !running {
+        tick_lockout(lockout_secs, set_lockout_secs);
+    }
+}
+
+fn tick_lockout(lockout_secs: ReadSignal<u32>, set_lockout_secs: WriteSignal<u32>) {
+    let tick = Closure::once(Box::new(move || {
+        let left = lockout_secs.get_untracked().saturating_sub(1);
+        set_lockout_secs.set(left);
+        if left > 0 {
+            tick_lockout(lockout_secs, set_lockout_secs);
+        }
+    }) as Box<dyn FnOnce()>);
+    let _ = web_sys::window().unwrap().set_timeout_with_callback_and_timeout_and_arguments_0(tick.as_ref().unchecked_ref(), 1000);
+    tick.forget();
+}
+
 // ============================================================================
 // LOGIN PAGE - GIỮ NGUYÊN
 // ============================================================================
@@ -163,9 +448,15 @@ fn LoginPage(
     set_shop_id_input: WriteSignal<String>,
     pin_input: ReadSignal<String>,
     set_pin_input: WriteSignal<String>,
+    email_input: ReadSignal<String>,
+    set_email_input: WriteSignal<String>,
     login_error: ReadSignal<String>,
     is_loading: ReadSignal<bool>,
+    lockout_secs: ReadSignal<u32>,
     on_login: impl Fn() + 'static + Clone,
+    // Đang thêm shop khi đã có phiên khác → cho quay lại Dashboard
+    on_cancel: impl Fn() + 'static + Clone + Send + Sync,
+    can_cancel: Signal<bool>,
 ) -> impl IntoView {
     let on_login_click = on_login.clone();
     let on_login_enter = on_login.clone();
@@ -185,18 +476,28 @@ fn LoginPage(
                         <label>"Shop ID"</label>
                         <input 
                             type="text" 
-                            placeholder="Ví dụ: demo123"
+                            placeholder=T::ShopIdExample.text()
                             prop:value=move || shop_id_input.get()
                             on:input=move |e| set_shop_id_input.set(event_target_value(&e))
                         />
                     </div>
                     
                     <div class="input-group">
-                        <label>"Mã PIN (6 số)"</label>
+                        <label>{T::StaffEmail.text()}</label>
+                        <input 
+                            type="email" 
+                            placeholder=T::StaffEmailExample.text()
+                            prop:value=move || email_input.get()
+                            on:input=move |e| set_email_input.set(event_target_value(&e))
+                        />
+                    </div>
+                    
+                    <div class="input-group">
+                        <label>{move || if email_input.get().trim().is_empty() { T::PinLabel.text() } else { T::Password.text() }}</label>
                         <input 
                             type="password" 
                             placeholder="••••••"
-                            maxlength="6"
+                            maxlength=move || if email_input.get().trim().is_empty() { "6" } else { "128" }
                             prop:value=move || pin_input.get()
                             on:input=move |e| set_pin_input.set(event_target_value(&e))
                             on:keypress={
@@ -214,11 +515,21 @@ fn LoginPage(
                     
                     <button 
                         class="login-btn"
-                        disabled=move || is_loading.get()
+                        disabled=move || is_loading.get() || (lockout_secs.get() > 0)
                         on:click=move |_| on_login_click()
                     >
-                        {move || if is_loading.get() { "Đang xác thực..." } else { "Đăng nhập" }}
+                        {move || match lockout_secs.get() {
+                            0 if is_loading.get() => T::Authenticating.text().to_string(),
+                            0 => T::SignIn.text().to_string(),
+                            secs => T::RetryIn.fill(&[&format!("{}:{:02}", secs / 60, secs % 60)]),
+                        }}
                     </button>
+                    <Show when=move || can_cancel.get()>
+                        <button class="login-cancel" on:click={
+                            let cancel = on_cancel.clone();
+                            move |_| cancel()
+                        }>{T::Back.text()}</button>
+                    </Show>
                 </div>
                 
                 <div class="login-footer">
@@ -235,20 +546,135 @@ fn LoginPage(
 #[component]
 fn Dashboard(
     shop_id: String,
-    shop_name: String,
     admin_pin: String,  // ← THÊM PIN
     on_logout: impl Fn() + 'static + Clone,
+    sessions: RwSignal<Vec<AdminSession>>,
+    active: RwSignal<Option<String>>,
+    adding_shop: RwSignal<bool>,
+    // Tin chưa đọc của các shop chạy nền
+    other_unread: RwSignal<HashMap<String, u32>>,
 ) -> impl IntoView {
     let (chat_users, set_chat_users) = signal(Vec::<ChatUser>::new());
     let (current_guest_id, set_current_guest_id) = signal(0u64);
     // SỬA: Dùng HashMap để lưu tin theo từng guest
     let (all_messages, set_all_messages) = signal(HashMap::<u64, Vec<DisplayMessage>>::new());
     let (message_input, set_message_input) = signal(String::new());
-    let (connection_status, set_connection_status) = signal("🔴 Đang kết nối...".to_string());
+    // Nháp theo từng guest (lưu localStorage, khôi phục khi chuyển hội thoại)
+    let (drafts, set_drafts) = signal(load_drafts(&shop_id));
+    let input_ref = NodeRef::<Input>::new();
+    let (connection_status, set_connection_status) = signal(T::Connecting.text().to_string());
+    // Tin đang được trả lời
+    let (replying_to, set_replying_to) = signal(None::<ReplyPreview>);
+    let pending_quick_replies = RwSignal::new(Vec::<QuickReply>::new());
+    // Shop đang trong giờ làm việc không (theo frame Presence)
+    let (shop_online, set_shop_online) = signal(true);
+    // Banner bảo trì hệ thống (ServerNotice)
+    let (server_notice, set_server_notice) = signal(None::<ServerNotice>);
     let (send_trigger, set_send_trigger) = signal(0u64);
+    let (view_mode, set_view_mode) = signal(DashboardView::Chat);
+    // Thông tin chi tiết guest (từ /guests) + danh sách target đang bị chặn
+    let (guest_info, set_guest_info) = signal(HashMap::<u64, Guest>::new());
+    let blocked = RwSignal::new(HashSet::<String>::new());
+    let (conv_filter, set_conv_filter) = signal(ConversationFilter::Open);
+    // Admin tự đặt "Vắng mặt" (server lưu theo agent_id, đồng bộ giữa các tab)
+    let agent_id = StoredValue::new(load_agent_id());
+    let (away, set_away) = signal(false);
+    // Ghi chú nội bộ theo guest (tải khi mở hội thoại, đồng bộ qua WS)
+    let notes = RwSignal::new(HashMap::<u64, Vec<GuestNote>>::new());
+    // Tin hẹn giờ đang chờ theo guest_id (tải khi mở khách)
+    let scheduled = RwSignal::new(HashMap::<u64, Vec<ScheduledMessage>>::new());
+    // Hội thoại đang tạm ẩn theo guest_id (cả shop, tải khi vào + kết nối lại)
+    let reminders = RwSignal::new(HashMap::<u64, Reminder>::new());
+    // Âm báo / thông báo của admin đang đăng nhập (trang Tuỳ chọn, lưu server) - agent_id dùng cho tab "của tôi"
+    let prefs = RwSignal::new(AdminPreferences::default());
+    let (show_profile, set_show_profile) = signal(false);
+    let (show_transfer, set_show_transfer) = signal(false);
+    // Số tin chưa đọc theo guest_id
+    let (unread, set_unread) = signal(HashMap::<u64, u32>::new());
+    let total_unread = Memo::new(move |_| unread.with(|u| u.values().sum::<u32>()));
     
     let scrollable_ref = NodeRef::<Div>::new();
     let ws_ref = StoredValue::new(None::<SendWebSocket>);
+    // Sinh id phía client - node ngẫu nhiên cho mỗi tab
+    let ids = StoredValue::new(MessageIdGenerator::new((js_sys::Math::random() * 1024.0) as u16));
+
+    // Tên hiển thị của guest (theo dõi guest_info → đổi ngay khi khách identify)
+    let guest_label = move |gid: u64| {
+        guest_info.with(|info| info.get(&gid).map(Guest::display_name))
+            .unwrap_or_else(|| Guest::fallback_name(gid))
+    };
+
+    // Người phụ trách hội thoại (rỗng = chưa giao)
+    let assignee_of = move |gid: u64| {
+        guest_info.with_untracked(|info| info.get(&gid).map(|g| g.assigned_to.clone())).unwrap_or_default()
+    };
+
+    // Guest chưa có trong /guests (mới nhắn) → đang mở
+    let status_of = move |gid: u64| {
+        guest_info.with(|info| info.get(&gid).map(|g| g.status())).unwrap_or(ConversationStatus::Open)
+    };
+    // Tab lọc đã lưu của admin (GET /views) + tab đang chọn (None = dùng tab trạng thái)
+    let views = RwSignal::new(Vec::<SavedView>::new());
+    let active_view = RwSignal::new(None::<String>);
+    let editing_view = RwSignal::new(None::<SavedView>);
+    let is_snoozed = move |gid: u64| reminders.with(|r| r.contains_key(&gid));
+    let in_view = move |view: &SavedView, gid: u64| {
+        let unread_count = unread.with(|u| u.get(&gid).copied().unwrap_or(0));
+        let me = prefs.with(|p| p.agent_id.clone());
+        !is_snoozed(gid) && guest_info.with(|info| view_matches(view, info.get(&gid), unread_count, &me))
+    };
+    let visible_users = Memo::new(move |_| {
+        let filter = conv_filter.get();
+        let view = active_view.get().and_then(|id| views.with(|v| v.iter().find(|v| v.view_id == id).cloned()));
+        chat_users.get().into_iter()
+            .filter(|u| match &view {
+                Some(view) => in_view(view, u.guest_id),
+                None => filter.matches(status_of(u.guest_id), is_snoozed(u.guest_id)),
+            })
+            .collect::<Vec<_>>()
+    });
+    // Số hội thoại (đã tải) của từng tab - tự cập nhật theo tin mới / frame Guest / chưa đọc
+    let view_counts = Memo::new(move |_| {
+        let users = chat_users.get();
+        views.get().into_iter()
+            .map(|view| (view.view_id.clone(), users.iter().filter(|u| in_view(&view, u.guest_id)).count()))
+            .collect::<HashMap<String, usize>>()
+    });
+    let pick_filter = move |filter: ConversationFilter| {
+        active_view.set(None);
+        set_conv_filter.set(filter);
+    };
+    let filter_active = move |filter: ConversationFilter| active_view.with(Option::is_none) && conv_filter.get() == filter;
+
+    // Chọn nhiều hội thoại cho thao tác hàng loạt: checkbox / Ctrl+click thêm bớt, Shift+click chọn cả dải
+    let selected = RwSignal::new(HashSet::<u64>::new());
+    let select_anchor = StoredValue::new(None::<u64>);
+    let toggle_selected = move |gid: u64| {
+        selected.update(|s| if !s.remove(&gid) { s.insert(gid); });
+        select_anchor.set_value(Some(gid));
+    };
+    let select_range = move |gid: u64| {
+        let ids: Vec<u64> = visible_users.with_untracked(|users| users.iter().map(|u| u.guest_id).collect());
+        let from = select_anchor.get_value().and_then(|a| ids.iter().position(|&id| id == a));
+        let to = ids.iter().position(|&id| id == gid);
+        let (Some(from), Some(to)) = (from, to) else { return toggle_selected(gid) };
+        selected.update(|s| s.extend(&ids[from.min(to)..=from.max(to)]));
+    };
+    // Đổi tab lọc / hội thoại bị đóng bởi admin khác → bỏ chọn những hội thoại không còn hiện
+    Effect::new(move |_| {
+        let shown: HashSet<u64> = visible_users.with(|users| users.iter().map(|u| u.guest_id).collect());
+        if selected.with_untracked(|s| s.iter().any(|id| !shown.contains(id))) {
+            selected.update(|s| s.retain(|id| shown.contains(id)));
+        }
+    });
+
+    // Kết quả thao tác hàng loạt (WS cũng phát lại từng guest, đây chỉ để sidebar đổi ngay)
+    let apply_bulk = move |guests: Vec<Guest>| set_guest_info.update(|info| {
+        for guest in guests {
+            info.insert(guest.guest_id, guest);
+        }
+    });
+    let mark_read = move |ids: Vec<u64>| set_unread.update(|u| u.retain(|gid, _| !ids.contains(gid)));
 
     // SỬA: Memo để lấy tin nhắn của guest đang chọn
     let current_messages = Memo::new(move |_| {
@@ -257,21 +683,124 @@ fn Dashboard(
         all_messages.get().get(&gid).cloned().unwrap_or_default()
     });
 
+    // Tìm trong hội thoại đang mở: id tin đã tải khớp query + kết quả đang chọn
+    let (search_open, set_search_open) = signal(false);
+    let search_query = RwSignal::new(String::new());
+    let search_active = RwSignal::new(None::<u64>);
+    let search_matches = Memo::new(move |_| {
+        let query = search_query.get();
+        if !search_open.get() || query.trim().is_empty() {
+            return Vec::new();
+        }
+        current_messages.with(|msgs| {
+            msgs.iter().filter(|m| !m.deleted && text_matches(&m.text, &query)).map(|m| m.id).collect::<Vec<_>>()
+        })
+    });
+    let oldest_loaded = Memo::new(move |_| current_messages.with(|msgs| msgs.first().map_or(0, |m| m.id)));
+
+    // Tin + dòng "đã chuyển hội thoại" (ghi chú bàn giao, chỉ admin thấy) xếp theo thời gian.
+    // Chỉ chèn bàn giao trong khoảng tin đã tải, tránh dồn cả lịch sử lên đầu
+    let timeline = Memo::new(move |_| {
+        let mut msgs = current_messages.get();
+        let since = msgs.first().map_or(0, |m| m.timestamp_us);
+        let handovers: Vec<DisplayMessage> = notes.with(|n| {
+            n.get(&current_guest_id.get()).into_iter().flatten()
+                .filter(|note| note.created_at >= since)
+                .filter_map(|note| note.transfer.as_ref().map(|t| DisplayMessage::handover(note, t)))
+                .collect()
+        });
+        if handovers.is_empty() {
+            return msgs;
+        }
+        msgs.extend(handovers);
+        msgs.sort_by_key(|m| m.timestamp_us);
+        msgs
+    });
+    let close_search = move || {
+        set_search_open.set(false);
+        search_query.set(String::new());
+        search_active.set(None);
+    };
+    // Đổi hội thoại → đóng ô tìm
+    Effect::new(move |_| {
+        current_guest_id.track();
+        close_search();
+    });
+    // Kết quả server (tin cũ chưa tải) → chèn vào hội thoại theo message_id
+    let add_found = move |gid: u64, found: Vec<ChatMessage>| {
+        set_all_messages.update(|map| {
+            let msgs = map.entry(gid).or_default();
+            for msg in found {
+                if !msgs.iter().any(|m| m.id == msg.message_id) {
+                    msgs.push(DisplayMessage::from_server(msg, gid));
+                }
+            }
+            msgs.sort_by_key(|m| m.id);
+        });
+    };
+
     // ============================================================
     // THÊM MỚI: Load danh sách guests từ DB khi khởi động
     // ============================================================
-    let shop_id_guests = shop_id.clone();
-    let pin_for_guests = admin_pin.clone();  // ← DÙNG PIN THẬT
-    Effect::new(move |_| {
-        let shop = shop_id_guests.clone();
-        let pin = pin_for_guests.clone();
+    let shop_api = StoredValue::new(shop_id.clone());
+    let pin_api = StoredValue::new(admin_pin.clone());  // ← DÙNG PIN THẬT
+    // SLA phản hồi đầu: cấu hình shop + đồng hồ để tô màu hội thoại sắp/đã quá hạn
+    let shop_settings = RwSignal::new(ShopSettings::default());
+    // Feature flag của shop - component con lấy qua use_context
+    let shop_flags = RwSignal::new(ShopFlags::default());
+    provide_context(shop_flags);
+    let sla_now = RwSignal::new(now_us());
+    let load_shop_settings = move || {
+        let req = SettingsRequest { shop_id: shop_api.get_value(), admin_pin: pin_api.get_value(), settings: None };
         spawn_local(async move {
+            if let Ok(resp) = post_settings(req).await {
+                if let Some(settings) = resp.settings.filter(|_| resp.success) {
+                    shop_settings.set(settings);
+                    shop_flags.set(resp.flags.unwrap_or_default());
+                }
+            }
+        });
+    };
+    load_shop_settings();
+    spawn_local(async move {
+        if let Some(loaded) = load_preferences(&shop_api.get_value(), &pin_api.get_value()).await {
+            prefs.set(loaded);
+        }
+    });
+    let sla_tick = Closure::wrap(Box::new(move || sla_now.set(now_us())) as Box<dyn FnMut()>);
+    let sla_timer = web_sys::window().unwrap()
+        .set_interval_with_callback_and_timeout_and_arguments_0(sla_tick.as_ref().unchecked_ref(), SLA_TICK_MS)
+        .ok();
+    sla_tick.forget();
+    on_cleanup(move || {
+        if let Some(handle) = sla_timer {
+            web_sys::window().unwrap().clear_interval_with_handle(handle);
+        }
+    });
+    let sla_level_of = move |gid: u64| {
+        let since = guest_info.with(|info| {
+            info.get(&gid).filter(|g| g.status() == ConversationStatus::Open).map(|g| g.awaiting_since).unwrap_or(0)
+        });
+        shop_settings.with(|s| s.sla_level(since, sla_now.get()))
+    };
+
+    // Danh sách guest theo trang (server sắp theo hoạt động gần nhất)
+    let guest_cursor = RwSignal::new(None::<String>);
+    let active_days = RwSignal::new(0u32);
+    let load_guests = move |cursor: Option<String>| {
+        let shop = shop_api.get_value();
+        let pin = pin_api.get_value();
+        spawn_local(async move {
+            let first_page = cursor.is_none();
             let req = GuestListRequest { 
                 shop_id: shop, 
                 admin_pin: pin,  // ← DÙNG PIN THẬT
+                limit: GUEST_PAGE_SIZE,
+                cursor: cursor.unwrap_or_default(),
+                active_within_days: active_days.get_untracked(),
             };
             
-            if let Ok(resp) = Request::post("http://localhost:8080/guests")
+            if let Ok(resp) = Request::post(&format!("{}/guests", API_BASE))
                 .header("Content-Type", "application/octet-stream")
                 .body(req.encode_to_vec())
                 .unwrap()
@@ -282,31 +811,173 @@ fn Dashboard(
                     if let Ok(list) = GuestListResponse::decode(&bytes[..]) {
                         if list.success {
                             leptos::logging::log!("📥 Loaded {} guests", list.guests.len());
-                            for guest in list.guests {
-                                set_chat_users.update(|users| {
+                            set_guest_info.update(|info| {
+                                for guest in &list.guests {
+                                    info.insert(guest.guest_id, guest.clone());
+                                }
+                            });
+                            // Hội thoại có tin gần nhất lên đầu
+                            set_chat_users.update(|users| {
+                                for guest in &list.guests {
                                     if !users.iter().any(|u| u.guest_id == guest.guest_id) {
-                                        users.push(ChatUser {
-                                            guest_id: guest.guest_id,
-                                            name: guest.guest_name,
-                                            last_message: String::new(),
-                                            time: String::new(),
-                                        });
+                                        users.push(ChatUser::from_guest(guest));
                                     }
-                                });
+                                }
+                                users.sort_by_key(|u| std::cmp::Reverse(u.last_at));
+                            });
+                            // Tải lại trang đầu (kết nối lại) không làm mất con trỏ "tải thêm" đang có
+                            if !first_page || guest_cursor.with_untracked(|c| c.is_none()) {
+                                guest_cursor.set(Some(list.next_cursor).filter(|c| !c.is_empty()));
                             }
                         }
                     }
                 }
             }
         });
+    };
+    // Đổi khoảng hoạt động → tải lại từ đầu
+    Effect::new(move |_| {
+        active_days.track();
+        set_chat_users.set(Vec::new());
+        guest_cursor.set(None);
+        load_guests(None);
+    });
+
+    // Hội thoại đang tạm ẩn (tải lại khi kết nối lại - lỡ frame Reminder lúc mất mạng)
+    let refresh_reminders = move || {
+        spawn_local(async move {
+            if let Some(map) = load_reminders(&shop_api.get_value(), &pin_api.get_value()).await {
+                reminders.set(map);
+            }
+        });
+    };
+    refresh_reminders();
+
+    spawn_local(async move {
+        if let Some(list) = load_views(&shop_api.get_value(), &pin_api.get_value()).await {
+            views.set(list);
+        }
+    });
+
+    // Load danh sách chặn
+    let shop_id_blocks = shop_id.clone();
+    let pin_for_blocks = admin_pin.clone();
+    Effect::new(move |_| {
+        let shop = shop_id_blocks.clone();
+        let pin = pin_for_blocks.clone();
+        spawn_local(async move {
+            if let Ok(resp) = admin_request(Method::GET, "/blocks", &shop, &pin).send().await {
+                if let Ok(bytes) = resp.binary().await {
+                    if let Ok(list) = BlockListResponse::decode(&bytes[..]) {
+                        blocked.set(list.blocks.into_iter().map(|b| b.target).collect());
+                    }
+                }
+            }
+        });
     });
 
     // ============================================================
-    // WebSocket connection
+    // Load tin nhắn của guest (after = 0: lịch sử, > 0: phần còn thiếu)
     // ============================================================
-    let shop_id_ws = shop_id.clone();
+    let sync_guest = move |gid: u64, after: u64| {
+        let shop = shop_api.get_value();
+        leptos::logging::log!("📥 Loading messages for guest {} after {}", gid, after);
+        spawn_local(async move {
+            // Hội thoại dài → đi tiếp theo page_state tới hết
+            let mut page_state = String::new();
+            loop {
+                let req = SyncRequest {
+                    shop_id: shop.clone(),
+                    guest_id: gid,
+                    after_message_id: after,
+                    limit: 200,
+                    page_state: std::mem::take(&mut page_state),
+                    // Admin vẫn xem nội dung tin đã xoá (kiểm duyệt)
+                    include_deleted: true,
+                    admin_pin: pin_api.get_value(),
+                };
+                let Ok(resp) = Request::post(&format!("{}/sync", API_BASE))
+                    .header("Content-Type", "application/octet-stream")
+                    .body(req.encode_to_vec())
+                    .unwrap()
+                    .send()
+                    .await
+                else { break };
+                let Some(sync) = resp.binary().await.ok().and_then(|b| SyncResponse::decode(&b[..]).ok()) else { break };
+                leptos::logging::log!("📥 Loaded {} messages for guest {}", sync.messages.len(), gid);
+                set_all_messages.update(|map| {
+                    let msgs = map.entry(gid).or_insert_with(Vec::new);
+                    for msg in sync.messages {
+                        let dm = DisplayMessage::from_server(msg, gid);
+                        // Đã có → cập nhật (có thể đã sửa/xoá lúc mất kết nối)
+                        match msgs.iter_mut().find(|m| m.id == dm.id) {
+                            Some(existing) => *existing = dm,
+                            None => msgs.push(dm),
+                        }
+                    }
+                    // Sort theo message_id
+                    msgs.sort_by_key(|m| m.id);
+                });
+                if !sync.has_more || sync.page_state.is_empty() {
+                    break;
+                }
+                page_state = sync.page_state;
+            }
+        });
+    };
     Effect::new(move |_| {
-        let url = format!("ws://localhost:8080/ws?shop_id={}", shop_id_ws);
+        let gid = current_guest_id.get();
+        if gid == 0 { return; }
+        sync_guest(gid, 0);
+        set_show_transfer.set(false);
+        // Ghi chú (gồm bàn giao hiện trong hội thoại) - lỗi thì tab ghi chú tự tải lại và báo lỗi
+        if !notes.with_untracked(|n| n.contains_key(&gid)) {
+            spawn_local(async move {
+                if let Some(Ok(list)) = load_notes(&shop_api.get_value(), &pin_api.get_value(), gid).await {
+                    notes.update(|n| { n.entry(gid).or_insert(list); });
+                }
+            });
+        }
+    });
+
+    // Kết nối lại → tải lại danh sách guest + tin còn thiếu của hội thoại đang mở
+    let resync = move || {
+        load_guests(None);
+        refresh_reminders();
+        let gid = current_guest_id.get_untracked();
+        if gid != 0 {
+            let after = all_messages.with_untracked(|map| {
+                // Bỏ qua tin chưa được xác nhận (id sinh ở client, có thể mới hơn tin bị lỡ)
+                map.get(&gid).and_then(|msgs| msgs.iter().filter(|m| m.status == SendStatus::Sent).map(|m| m.id).max())
+            });
+            sync_guest(gid, after.unwrap_or(0));
+        }
+    };
+
+    // ============================================================
+    // WebSocket connection - tự kết nối lại với backoff luỹ thừa
+    // ============================================================
+    // Admin khác đang xem / gõ hội thoại nào + hội thoại mình đang xem (chỉ tính khi ở màn chat)
+    let colleagues = Colleagues::new();
+    let viewing_guest = Memo::new(move |_| if view_mode.get() == DashboardView::Chat { current_guest_id.get() } else { 0 });
+    let send_activity = move |guest_id: u64, kind: ActivityKind| {
+        let Some(ws) = ws_ref.get_value() else { return };
+        if ws.0.ready_state() != WebSocket::OPEN { return; }
+        let mut activity = AgentActivity { guest_id, ..Default::default() };
+        activity.set_kind(kind);
+        let bytes = WsEnvelope::from_client(ws_envelope::Frame::AgentActivity(activity)).encode_to_vec();
+        let arr = js_sys::Uint8Array::from(&bytes[..]);
+        let _ = ws.0.send_with_array_buffer(&arr.buffer());
+    };
+
+    let (reconnect_tick, set_reconnect_tick) = signal(0u32);
+    let reconnect_attempt = StoredValue::new(0u32);
+    Effect::new(move |_| {
+        let _ = reconnect_tick.get();
+        let url = format!("{}/ws?shop_id={}&admin_pin={}&agent_id={}&v={}", WS_BASE,
+            js_sys::encode_uri_component(&shop_api.get_value()),
+            js_sys::encode_uri_component(&pin_api.get_value()),
+            agent_id.get_value(), PROTOCOL_VERSION);
         let ws = match WebSocket::new(&url) {
             Ok(w) => w,
             Err(_) => return,
@@ -315,7 +986,15 @@ fn Dashboard(
         // On open
         {
             let on_open = Closure::wrap(Box::new(move |_: JsValue| {
-                set_connection_status.set("🟢 Đã kết nối".to_string());
+                set_connection_status.set(T::Connected.text().to_string());
+                let gid = viewing_guest.get_untracked();
+                if gid != 0 {
+                    send_activity(gid, ActivityKind::Viewing);
+                }
+                if reconnect_attempt.get_value() > 0 {
+                    reconnect_attempt.set_value(0);
+                    resync();
+                }
             }) as Box<dyn FnMut(JsValue)>);
             ws.set_onopen(Some(on_open.as_ref().unchecked_ref()));
             on_open.forget();
@@ -332,42 +1011,233 @@ fn Dashboard(
                         if let Ok(ab) = fr_clone.result().unwrap().dyn_into::<js_sys::ArrayBuffer>() {
                             let bytes = js_sys::Uint8Array::new(&ab).to_vec();
                             
-                            if let Ok(msg) = ChatMessage::decode(&bytes[..]) {
-                                let guest_id = msg.guest_id;
-                                let msg_id = msg.message_id;
-                                let text = String::from_utf8_lossy(&msg.content).to_string();
-                                let time = format_time(msg.timestamp_us);
+                            let Ok(envelope) = WsEnvelope::decode(&bytes[..]) else { return };
+                            match envelope.frame {
+                                Some(ws_envelope::Frame::Presence(p)) => set_shop_online.set(p.online),
+                                Some(ws_envelope::Frame::AgentStatus(status)) if status.agent_id == agent_id.get_value() => {
+                                    set_away.set(status.away);
+                                }
+                                Some(ws_envelope::Frame::AgentActivity(activity)) => colleagues.apply(activity, &agent_id.get_value()),
+                                Some(ws_envelope::Frame::Message(msg)) => {
+                                    let guest_id = msg.guest_id;
+                                    let msg_id = msg.message_id;
+                                    let text = message_text(&msg);
+                                    let time = format_list_time(msg.timestamp_us, now_us());
                                 
-                                // Cập nhật chat_users
-                                set_chat_users.update(|users| {
-                                    if !users.iter().any(|u| u.guest_id == guest_id) {
-                                        users.push(ChatUser {
-                                            guest_id,
-                                            name: format!("Khách #{}", guest_id % 10000),
-                                            last_message: text.clone(),
-                                            time: time.clone(),
-                                        });
-                                    } else if let Some(user) = users.iter_mut().find(|u| u.guest_id == guest_id) {
+                                    let is_new = all_messages.with_untracked(|map| {
+                                        !map.get(&guest_id).is_some_and(|msgs| msgs.iter().any(|m| m.id == msg_id))
+                                    });
+                                
+                                    // Cập nhật chat_users - hội thoại có tin mới lên đầu danh sách (dòng hệ thống không đổi trích đoạn)
+                                    set_chat_users.update(|users| {
+                                        if msg.is_system() {
+                                            return;
+                                        }
+                                        let mut user = match users.iter().position(|u| u.guest_id == guest_id) {
+                                            Some(i) => users.remove(i),
+                                            None => ChatUser {
+                                                guest_id,
+                                                last_message: String::new(),
+                                                time: String::new(),
+                                                last_at: 0,
+                                            },
+                                        };
                                         user.last_message = text.clone();
                                         user.time = time.clone();
+                                        user.last_at = msg.timestamp_us;
+                                        users.insert(0, user);
+                                    });
+                                
+                                    // Khách nhắn lại → server đã mở lại hội thoại (upsert_guest)
+                                    if msg.sender_type == "guest" && status_of(guest_id) != ConversationStatus::Open {
+                                        set_guest_info.update(|info| {
+                                            if let Some(g) = info.get_mut(&guest_id) {
+                                                g.set_status(ConversationStatus::Open);
+                                            }
+                                        });
                                     }
-                                });
                                 
-                                // SỬA: Thêm tin vào HashMap theo guest_id
-                                let dm = DisplayMessage {
-                                    id: msg_id,
-                                    sender_type: msg.sender_type.clone(),
-                                    text,
-                                    time,
-                                    guest_id,
-                                };
+                                    // Tin admin mới không phải tab này gửi (tab này gửi thì đã có sẵn trong danh sách) → cảnh báo trả lời trùng
+                                    if is_new && msg.sender_type == "admin" && now_us().saturating_sub(msg.timestamp_us) < (COLLISION_WINDOW_MS as u64) * 1000 {
+                                        colleagues.note_reply(guest_id);
+                                    }
+
+                                    // Tin khách gửi vào hội thoại không đang xem → tăng số chưa đọc
+                                    let viewing = current_guest_id.get_untracked() == guest_id
+                                        && view_mode.get_untracked() == DashboardView::Chat;
+                                    if is_new && msg.sender_type == "guest" && !viewing {
+                                        set_unread.update(|u| *u.entry(guest_id).or_insert(0) += 1);
+                                    }
                                 
-                                set_all_messages.update(|map| {
-                                    let msgs = map.entry(guest_id).or_insert_with(Vec::new);
-                                    if !msgs.iter().any(|m| m.id == msg_id) {
-                                        msgs.push(dm);
+                                    // Âm báo + thông báo trình duyệt - im lặng khi "Vắng mặt", ngoài phạm vi hoặc trong giờ yên lặng
+                                    if is_new && msg.sender_type == "guest" && !away.get_untracked() && (!viewing || notify::document_hidden()) {
+                                        let alert = prefs.with_untracked(|p| notify::Alert::for_conversation(p, &assignee_of(guest_id)));
+                                        if alert.sound {
+                                            notify::play_ping();
+                                        }
+                                        if alert.desktop {
+                                            notify::show_notification(&guest_label(guest_id), &text, &format!("turbochat-{}", guest_id), move || {
+                                                set_view_mode.set(DashboardView::Chat);
+                                                set_current_guest_id.set(guest_id);
+                                            });
+                                        }
                                     }
-                                });
+                                
+                                    // SỬA: Thêm tin vào HashMap theo guest_id
+                                    let dm = DisplayMessage {
+                                        id: msg_id,
+                                        sender_type: msg.sender_type.clone(),
+                                        text,
+                                        timestamp_us: msg.timestamp_us,
+                                        guest_id,
+                                        edited: msg.edited_at_us > 0,
+                                        deleted: msg.deleted_at_us > 0,
+                                        reply: msg.reply_preview.clone(),
+                                        status: SendStatus::Sent,
+                                        quick_replies: msg.quick_replies.clone(),
+                                        attachments: msg.attachments.clone(),
+                                        upload_preview: None,
+                                    };
+                                
+                                    set_all_messages.update(|map| {
+                                        let msgs = map.entry(guest_id).or_insert_with(Vec::new);
+                                        if let Some(local) = msgs.iter_mut().find(|m| m.id == msg_id) {
+                                            // Server phát lại tin gửi lạc quan → xác nhận
+                                            local.status = SendStatus::Sent;
+                                        } else {
+                                            msgs.push(dm);
+                                            // Tin đến trễ (lệch thứ tự) → giữ danh sách theo message_id
+                                            if msgs.len() > 1 && msgs[msgs.len() - 2].id > msg_id {
+                                                msgs.sort_by_key(|m| m.id);
+                                            }
+                                        }
+                                    });
+                                }
+                                Some(ws_envelope::Frame::Edit(edit)) => {
+                                    let text = String::from_utf8_lossy(&edit.content).to_string();
+                                    set_all_messages.update(|map| {
+                                        if let Some(m) = map.get_mut(&edit.guest_id).and_then(|msgs| msgs.iter_mut().find(|m| m.id == edit.message_id)) {
+                                            m.text = text;
+                                            m.edited = true;
+                                        }
+                                    });
+                                }
+                                Some(ws_envelope::Frame::Flags(flags)) => shop_flags.set(flags),
+                                Some(ws_envelope::Frame::Guest(guest)) => {
+                                    // Tin cuối bị sửa/xoá → cập nhật trích đoạn trên sidebar
+                                    set_chat_users.update(|users| {
+                                        if let Some(user) = users.iter_mut().find(|u| u.guest_id == guest.guest_id) {
+                                            if guest.last_message_at > 0 && guest.last_message_at >= user.last_at {
+                                                *user = ChatUser::from_guest(&guest);
+                                            }
+                                        }
+                                    });
+                                    set_guest_info.update(|info| { info.insert(guest.guest_id, *guest); });
+                                }
+                                // Hai guest cùng tài khoản website đã gộp → bỏ alias khỏi sidebar, hội thoại chung nằm ở guest gốc
+                                Some(ws_envelope::Frame::GuestMerged(merged)) => {
+                                    let (from, into) = (merged.from_guest_id, merged.into_guest_id);
+                                    set_chat_users.update(|users| users.retain(|u| u.guest_id != from));
+                                    set_all_messages.update(|map| { map.remove(&from); });
+                                    if current_guest_id.get_untracked() == from {
+                                        set_current_guest_id.set(into);
+                                    } else if current_guest_id.get_untracked() == into {
+                                        sync_guest(into, 0);
+                                    }
+                                    load_guests(None);
+                                }
+                                Some(ws_envelope::Frame::Note(note)) => {
+                                    // Hội thoại vừa được chuyển cho mình → báo như tin khách mới
+                                    let gid = note.guest_id;
+                                    let to_me = note.transfer.as_ref().filter(|t| !note.deleted && prefs.with_untracked(|p| t.to_agent == p.agent_id));
+                                    if let Some(transfer) = to_me.filter(|_| !away.get_untracked()) {
+                                        let alert = prefs.with_untracked(|p| notify::Alert::for_conversation(p, &transfer.to_agent));
+                                        if alert.sound {
+                                            notify::play_ping();
+                                        }
+                                        if alert.desktop {
+                                            notify::show_notification(&T::TransferredToYou.fill(&[&guest_label(gid)]), &note.content, &format!("turbochat-transfer-{}", gid), move || {
+                                                set_view_mode.set(DashboardView::Chat);
+                                                set_current_guest_id.set(gid);
+                                            });
+                                        }
+                                    }
+                                    notes.update(|n| apply_note(n, note));
+                                }
+                                Some(ws_envelope::Frame::SlaAlert(alert)) => {
+                                    sla_now.set(now_us());
+                                    let gid = alert.guest_id;
+                                    if !away.get_untracked() && prefs.with_untracked(|p| notify::Alert::for_conversation(p, &assignee_of(gid))).desktop {
+                                        let title = if alert.breached { T::SlaBreached.text() } else { T::SlaWarning.text() };
+                                        notify::show_notification(title, &guest_label(gid), &format!("turbochat-sla-{}", gid), move || {
+                                            set_view_mode.set(DashboardView::Chat);
+                                            set_current_guest_id.set(gid);
+                                        });
+                                    }
+                                }
+                                // Bot chuyển cho nhân viên → báo ưu tiên (cả ngoài phạm vi / giờ yên lặng, trừ khi "Vắng mặt")
+                                Some(ws_envelope::Frame::Handoff(handoff)) => {
+                                    let gid = handoff.guest_id;
+                                    let alert = prefs.with_untracked(notify::Alert::priority);
+                                    if !away.get_untracked() {
+                                        if alert.sound {
+                                            notify::play_ping();
+                                        }
+                                        if alert.desktop {
+                                            let title = T::HandoffTitle.fill(&[&guest_label(gid)]);
+                                            notify::show_priority_notification(&title, handoff_reason_label(handoff.reason()), &format!("turbochat-handoff-{}", gid), move || {
+                                                set_view_mode.set(DashboardView::Chat);
+                                                set_current_guest_id.set(gid);
+                                            });
+                                        }
+                                    }
+                                }
+                                Some(ws_envelope::Frame::Scheduled(item)) => scheduled.update(|m| apply_scheduled(m, item)),
+                                Some(ws_envelope::Frame::Reminder(item)) => {
+                                    // Tới giờ nhắc → báo như tin khách mới (theo phạm vi / giờ yên lặng của admin)
+                                    let gid = item.guest_id;
+                                    if item.due && !away.get_untracked() {
+                                        let alert = prefs.with_untracked(|p| notify::Alert::for_conversation(p, &assignee_of(gid)));
+                                        if alert.sound {
+                                            notify::play_ping();
+                                        }
+                                        if alert.desktop {
+                                            notify::show_notification(&T::ReminderDue.fill(&[&guest_label(gid)]), T::ReminderDueBody.text(), &format!("turbochat-reminder-{}", gid), move || {
+                                                set_view_mode.set(DashboardView::Chat);
+                                                set_current_guest_id.set(gid);
+                                            });
+                                        }
+                                    }
+                                    reminders.update(|m| apply_reminder(m, item));
+                                }
+                                // Tin admin vượt hạn mức (frame khách vượt hạn mức thì bỏ qua)
+                                Some(ws_envelope::Frame::QuotaExceeded(exceeded)) if envelope.admin_only => {
+                                    let status = if exceeded.queued { SendStatus::Queued } else { SendStatus::Failed };
+                                    set_all_messages.update(|map| {
+                                        if let Some(m) = map.get_mut(&envelope.guest_id).and_then(|msgs| msgs.iter_mut().find(|m| m.id == exceeded.message_id)) {
+                                            m.status = status;
+                                        }
+                                    });
+                                }
+                                Some(ws_envelope::Frame::ServerNotice(notice)) => {
+                                    // Tin admin bị từ chối khi bảo trì (tin khách bị từ chối thì chỉ cập nhật banner)
+                                    if notice.rejected_message_id != 0 && envelope.admin_only {
+                                        set_all_messages.update(|map| {
+                                            if let Some(m) = map.get_mut(&envelope.guest_id).and_then(|msgs| msgs.iter_mut().find(|m| m.id == notice.rejected_message_id)) {
+                                                m.status = SendStatus::Failed;
+                                            }
+                                        });
+                                    }
+                                    set_server_notice.set(Some(notice).filter(|n| n.active));
+                                }
+                                Some(ws_envelope::Frame::Delete(delete)) => {
+                                    set_all_messages.update(|map| {
+                                        if let Some(m) = map.get_mut(&delete.guest_id).and_then(|msgs| msgs.iter_mut().find(|m| m.id == delete.message_id)) {
+                                            m.deleted = true;
+                                        }
+                                    });
+                                }
+                                _ => {}
                             }
                         }
                     }) as Box<dyn FnMut(web_sys::ProgressEvent)>);
@@ -383,8 +1253,28 @@ fn Dashboard(
         
         // On close
         {
-            let on_close = Closure::wrap(Box::new(move |_: CloseEvent| {
-                set_connection_status.set("🟡 Mất kết nối".to_string());
+            let on_close = Closure::wrap(Box::new(move |event: CloseEvent| {
+                // Bản admin cũ, server đã bỏ hỗ trợ → thử lại cũng vô ích
+                if event.code() == CLOSE_UNSUPPORTED_PROTOCOL {
+                    set_connection_status.set(T::Outdated.text().to_string());
+                    return;
+                }
+                // Máy chủ đang deploy → nối lại ngay (sang máy chủ khác), không tính là lỗi
+                if event.code() == CLOSE_SERVER_DRAINING {
+                    reconnect_attempt.set_value(0);
+                }
+                let attempt = reconnect_attempt.get_value() + 1;
+                reconnect_attempt.set_value(attempt);
+                if event.code() == CLOSE_SERVER_DRAINING {
+                    set_connection_status.set(T::ServerUpdating.text().to_string());
+                } else {
+                    set_connection_status.set(T::Reconnecting.fill(&[&attempt]));
+                }
+                let delay = reconnect_delay_ms(attempt);
+                let retry = Closure::once(Box::new(move || set_reconnect_tick.update(|t| *t += 1)) as Box<dyn FnOnce()>);
+                let _ = web_sys::window().unwrap().set_timeout_with_callback_and_timeout_and_arguments_0(
+                    retry.as_ref().unchecked_ref(), delay);
+                retry.forget();
             }) as Box<dyn FnMut(CloseEvent)>);
             ws.set_onclose(Some(on_close.as_ref().unchecked_ref()));
             on_close.forget();
@@ -392,59 +1282,162 @@ fn Dashboard(
         
         ws_ref.set_value(Some(SendWebSocket(ws)));
     });
+    // Đổi shop / đăng xuất → đóng WS, không tự kết nối lại cho Dashboard đã huỷ
+    on_cleanup(move || {
+        if let Some(ws) = ws_ref.try_get_value().flatten() {
+            ws.0.set_onclose(None);
+            ws.0.set_onmessage(None);
+            let _ = ws.0.close();
+        }
+    });
 
-    // ============================================================
-    // THÊM MỚI: Load tin nhắn cũ khi chọn guest
-    // ============================================================
-    let shop_id_sync = shop_id.clone();
+
+    // Mở hội thoại (ở màn chat) → đánh dấu đã đọc
     Effect::new(move |_| {
         let gid = current_guest_id.get();
+        if gid != 0 && view_mode.get() == DashboardView::Chat && unread.with_untracked(|u| u.contains_key(&gid)) {
+            set_unread.update(|u| { u.remove(&gid); });
+        }
+    });
+
+    // Đổi hội thoại đang xem → báo rời hội thoại cũ, đang xem hội thoại mới
+    Effect::new(move |prev: Option<u64>| {
+        let gid = viewing_guest.get();
+        if let Some(prev) = prev.filter(|&p| p != 0 && p != gid) {
+            send_activity(prev, ActivityKind::Idle);
+        }
+        if gid != 0 && prev != Some(gid) {
+            send_activity(gid, ActivityKind::Viewing);
+        }
+        gid
+    });
+    // Tab đang hiện → nhắc lại "đang xem"; tab ẩn thì để admin khác tự hết hạn
+    let heartbeat = Closure::wrap(Box::new(move || {
+        let gid = viewing_guest.get_untracked();
+        let hidden = web_sys::window().and_then(|w| w.document()).is_some_and(|d| d.hidden());
+        if gid != 0 && !hidden {
+            send_activity(gid, ActivityKind::Viewing);
+        }
+    }) as Box<dyn FnMut()>);
+    let heartbeat_timer = web_sys::window().unwrap()
+        .set_interval_with_callback_and_timeout_and_arguments_0(heartbeat.as_ref().unchecked_ref(), VIEWING_HEARTBEAT_MS)
+        .ok();
+    heartbeat.forget();
+    on_cleanup(move || {
+        if let Some(handle) = heartbeat_timer {
+            web_sys::window().unwrap().clear_interval_with_handle(handle);
+        }
+    });
+    // Gõ → "đang gõ" (tối đa mỗi TYPING_THROTTLE_MS); xoá trắng / gửi xong → về "đang xem"
+    let last_typing = StoredValue::new(0f64);
+    let note_typing = move |typing: bool| {
+        let gid = current_guest_id.get_untracked();
         if gid == 0 { return; }
-        
-        let shop = shop_id_sync.clone();
-        leptos::logging::log!("📥 Loading messages for guest {}", gid);
-        spawn_local(async move {
-            let req = SyncRequest {
-                shop_id: shop,
-                guest_id: gid,
-                after_message_id: 0,
-                limit: 50,
-            };
-            
-            if let Ok(resp) = Request::post("http://localhost:8080/sync")
-                .header("Content-Type", "application/octet-stream")
-                .body(req.encode_to_vec())
-                .unwrap()
-                .send()
-                .await 
-            {
-                if let Ok(bytes) = resp.binary().await {
-                    if let Ok(sync) = SyncResponse::decode(&bytes[..]) {
-                        leptos::logging::log!("📥 Loaded {} messages for guest {}", sync.messages.len(), gid);
-                        set_all_messages.update(|map| {
-                            let msgs = map.entry(gid).or_insert_with(Vec::new);
-                            for msg in sync.messages {
-                                if !msgs.iter().any(|m| m.id == msg.message_id) {
-                                    msgs.push(DisplayMessage {
-                                        id: msg.message_id,
-                                        sender_type: msg.sender_type,
-                                        text: String::from_utf8_lossy(&msg.content).to_string(),
-                                        time: format_time(msg.timestamp_us),
-                                        guest_id: gid,
-                                    });
-                                }
-                            }
-                            // Sort theo message_id
-                            msgs.sort_by_key(|m| m.id);
-                        });
-                    }
+        let now = js_sys::Date::now();
+        if typing && now - last_typing.get_value() >= TYPING_THROTTLE_MS {
+            last_typing.set_value(now);
+            send_activity(gid, ActivityKind::Typing);
+        } else if !typing && last_typing.get_value() > 0.0 {
+            last_typing.set_value(0.0);
+            send_activity(gid, ActivityKind::Viewing);
+        }
+    };
+    Effect::new(move |_| {
+        current_guest_id.track();
+        last_typing.set_value(0.0);
+    });
+
+    // Chuyển guest → nạp nháp của guest đó vào ô nhập
+    Effect::new(move |_| {
+        let gid = current_guest_id.get();
+        let draft = drafts.with_untracked(|d| d.get(&gid).cloned()).unwrap_or_default();
+        set_message_input.set(draft);
+    });
+    // Gõ / gửi → cập nhật nháp của guest đang mở
+    let shop_drafts = StoredValue::new(shop_id.clone());
+    Effect::new(move |prev: Option<()>| {
+        let text = message_input.get();
+        let gid = current_guest_id.get_untracked();
+        if prev.is_none() || gid == 0 {
+            return;
+        }
+        let changed = drafts.with_untracked(|d| d.get(&gid).map(String::as_str).unwrap_or("") != text);
+        if changed {
+            set_drafts.update(|d| {
+                if text.trim().is_empty() {
+                    d.remove(&gid);
+                } else {
+                    d.insert(gid, text);
                 }
+            });
+            drafts.with_untracked(|d| save_drafts(&shop_drafts.get_value(), d));
+        }
+    });
+
+    // ============================================================
+    // Gửi tin lạc quan: hiện ngay (Pending), server phát lại cùng message_id → Sent,
+    // WS đóng hoặc quá SEND_ACK_TIMEOUT_MS → Failed (cho phép thử lại)
+    // ============================================================
+    let shop_send = StoredValue::new(shop_id.clone());
+    let transmit = move |dm: &DisplayMessage| -> bool {
+        let Some(ws) = ws_ref.get_value() else { return false };
+        if ws.0.ready_state() != WebSocket::OPEN { return false; }
+        let content = dm.text.as_bytes();
+        let msg = ChatMessage {
+            shop_id: shop_send.get_value(),
+            guest_id: dm.guest_id,
+            message_id: dm.id,
+            sender_type: "admin".to_string(),
+            content: content.to_vec().into(),
+            timestamp_us: dm.timestamp_us,
+            content_crc: crc32c::crc32c(content),
+            reply_to_message_id: dm.reply.as_ref().map(|r| r.message_id),
+            quick_replies: dm.quick_replies.clone(),
+            attachments: dm.attachments.clone(),
+            ..Default::default()
+        };
+        let bytes = WsEnvelope::message(msg).with_request_id(new_request_id()).encode_to_vec();
+        let arr = js_sys::Uint8Array::from(&bytes[..]);
+        ws.0.send_with_array_buffer(&arr.buffer()).is_ok()
+    };
+    let set_send_status = move |gid: u64, id: u64, status: SendStatus| {
+        set_all_messages.update(|map| {
+            if let Some(m) = map.get_mut(&gid).and_then(|msgs| msgs.iter_mut().find(|m| m.id == id)) {
+                m.status = status;
             }
         });
-    });
+    };
+    let watch_ack = move |gid: u64, id: u64| {
+        let timeout = Closure::once(Box::new(move || {
+            let still_pending = all_messages.with_untracked(|map| {
+                map.get(&gid).and_then(|msgs| msgs.iter().find(|m| m.id == id)).is_some_and(|m| m.status == SendStatus::Pending)
+            });
+            if still_pending {
+                leptos::logging::log!("❌ Message {} not acknowledged", id);
+                set_send_status(gid, id, SendStatus::Failed);
+            }
+        }) as Box<dyn FnOnce()>);
+        let _ = web_sys::window().unwrap().set_timeout_with_callback_and_timeout_and_arguments_0(
+            timeout.as_ref().unchecked_ref(), SEND_ACK_TIMEOUT_MS);
+        timeout.forget();
+    };
+    // Gửi (lần đầu hoặc thử lại) một tin đã nằm trong danh sách
+    let dispatch = move |dm: DisplayMessage| {
+        if transmit(&dm) {
+            watch_ack(dm.guest_id, dm.id);
+        } else {
+            set_send_status(dm.guest_id, dm.id, SendStatus::Failed);
+        }
+    };
+    let retry_message = move |gid: u64, id: u64| {
+        let Some(dm) = all_messages.with_untracked(|map| {
+            map.get(&gid).and_then(|msgs| msgs.iter().find(|m| m.id == id)).cloned()
+        }) else { return };
+        set_send_status(gid, id, SendStatus::Pending);
+        dispatch(dm);
+    };
 
     // Send message effect
-    let shop_id_send = shop_id.clone();
     Effect::new(move |_| {
         let trigger = send_trigger.get();
         if trigger == 0 { return; }
@@ -455,28 +1448,145 @@ fn Dashboard(
         let guest_id = current_guest_id.get_untracked();
         if guest_id == 0 { return; }
 
-        if let Some(ws) = ws_ref.get_value() {
-            if ws.0.ready_state() == WebSocket::OPEN {
-                let ts = js_sys::Date::now() as u64 * 1000;
-                let content = text.as_bytes();
-                
-                let msg = ChatMessage {
-                    shop_id: shop_id_send.clone(),
-                    guest_id,
-                    message_id: ts,
-                    sender_type: "admin".to_string(),
-                    content: content.to_vec().into(),
-                    timestamp_us: ts,
-                    content_crc: crc32c::crc32c(content),
-                };
-                
-                let bytes = msg.encode_to_vec();
-                let arr = js_sys::Uint8Array::from(&bytes[..]);
-                let _ = ws.0.send_with_array_buffer(&arr.buffer());
+        let id = ids.with_value(|g| g.next_id());
+        let dm = DisplayMessage {
+            id: id.0,
+            sender_type: "admin".to_string(),
+            text,
+            timestamp_us: id.timestamp() * 1000,
+            guest_id,
+            edited: false,
+            deleted: false,
+            reply: replying_to.get_untracked(),
+            status: SendStatus::Pending,
+            quick_replies: pending_quick_replies.get_untracked(),
+            attachments: Vec::new(),
+            upload_preview: None,
+        };
+        set_all_messages.update(|map| map.entry(guest_id).or_default().push(dm.clone()));
+        set_message_input.set(String::new());
+        note_typing(false);
+        set_replying_to.set(None);
+        pending_quick_replies.set(Vec::new());
+        dispatch(dm);
+    });
+
+    // Ảnh dán / file kéo thả → mỗi file một tin: hiện ngay (đang tải lên), tải xong thì gửi kèm Attachment
+    let shop_upload = StoredValue::new(shop_id.clone());
+    let send_files = move |files: Vec<web_sys::File>| {
+        let guest_id = current_guest_id.get_untracked();
+        if guest_id == 0 || files.is_empty() { return; }
+        if !shop_flags.get_untracked().attachments {
+            let _ = web_sys::window().unwrap().alert_with_message(T::AttachmentsDisabled.text());
+            return;
+        }
+        for file in files {
+            let id = ids.with_value(|g| g.next_id());
+            let preview = upload::preview_url(&file);
+            let dm = DisplayMessage {
+                id: id.0,
+                sender_type: "admin".to_string(),
+                text: String::new(),
+                timestamp_us: id.timestamp() * 1000,
+                guest_id,
+                edited: false,
+                deleted: false,
+                reply: None,
+                status: SendStatus::Uploading,
+                quick_replies: Vec::new(),
+                attachments: Vec::new(),
+                upload_preview: preview.clone(),
+            };
+            set_all_messages.update(|map| map.entry(guest_id).or_default().push(dm));
+            spawn_local(async move {
+                let result = upload::upload(&shop_upload.get_value(), guest_id, &file).await;
+                if let Some(url) = &preview {
+                    upload::revoke_preview(url);
+                }
+                match result {
+                    Ok(attachment) => {
+                        let mut ready = None;
+                        set_all_messages.update(|map| {
+                            if let Some(m) = map.get_mut(&guest_id).and_then(|msgs| msgs.iter_mut().find(|m| m.id == id.0)) {
+                                m.attachments = vec![attachment];
+                                m.upload_preview = None;
+                                m.status = SendStatus::Pending;
+                                ready = Some(m.clone());
+                            }
+                        });
+                        if let Some(dm) = ready {
+                            dispatch(dm);
+                        }
+                    }
+                    Err(e) => {
+                        leptos::logging::log!("❌ Upload {} failed: {}", file.name(), e);
+                        set_all_messages.update(|map| {
+                            if let Some(msgs) = map.get_mut(&guest_id) {
+                                msgs.retain(|m| m.id != id.0);
+                            }
+                        });
+                        let _ = web_sys::window().unwrap().alert_with_message(&T::UploadFailedFor.fill(&[&file.name(), &e]));
+                    }
+                }
+            });
+        }
+    };
+    let (drag_over, set_drag_over) = signal(false);
+
+    // Bật/tắt vắng mặt - server lưu lại và báo widget nếu mọi admin đều vắng
+    let toggle_away = move || {
+        let Some(ws) = ws_ref.get_value() else { return };
+        if ws.0.ready_state() != WebSocket::OPEN { return; }
+        let next = !away.get_untracked();
+        let status = AgentStatus { agent_id: agent_id.get_value(), away: next };
+        let bytes = WsEnvelope::from_client(ws_envelope::Frame::AgentStatus(status)).with_request_id(new_request_id()).encode_to_vec();
+        let arr = js_sys::Uint8Array::from(&bytes[..]);
+        if ws.0.send_with_array_buffer(&arr.buffer()).is_ok() {
+            set_away.set(next);
+        }
+    };
+
+    // Toolbar định dạng cho ô nhập
+    let format_input = move |before: &str, after: &str, placeholder: &str| {
+        if let Some(input) = input_ref.get_untracked() {
+            set_message_input.set(wrap_selection(&input, before, after, placeholder));
+            let _ = input.focus();
+        }
+    };
+    // Lựa chọn nhanh cho tin sắp gửi: "Có | Không | Gặp nhân viên=handoff"
+    let edit_quick_replies = move || {
+        let window = web_sys::window().unwrap();
+        let current = pending_quick_replies.with_untracked(|opts| format_quick_replies(opts));
+        let Ok(Some(raw)) = window.prompt_with_message_and_default(
+            T::QuickRepliesPrompt.text(), &current) else { return };
+        pending_quick_replies.set(parse_quick_replies(&raw));
+    };
+
+    // Hẹn giờ tin đang soạn thay vì gửi ngay
+    let shop_schedule = StoredValue::new(shop_id.clone());
+    let pin_schedule = StoredValue::new(admin_pin.clone());
+    let schedule_current = move || {
+        schedule_message(
+            shop_schedule.get_value(),
+            pin_schedule.get_value(),
+            current_guest_id.get_untracked(),
+            message_input.get_untracked(),
+            pending_quick_replies.get_untracked(),
+            scheduled,
+            move |ok| if ok {
                 set_message_input.set(String::new());
-            }
+                pending_quick_replies.set(Vec::new());
+            },
+        );
+    };
+
+    let insert_link = move || {
+        let window = web_sys::window().unwrap();
+        let Ok(Some(url)) = window.prompt_with_message_and_default(T::LinkPrompt.text(), "https://") else { return };
+        if is_safe_url(&url) {
+            format_input("[", &format!("]({})", url.trim()), T::LinkPlaceholder.text());
         }
-    });
+    };
 
     // Auto-scroll
     Effect::new(move |_| {
@@ -488,103 +1598,684 @@ fn Dashboard(
         });
     });
 
-    let shop_name_display = shop_name.clone();
+    // Badge tổng: shop đang mở + các shop chạy nền
+    let merged_unread = Memo::new(move |_| total_unread.get() + other_unread.with(|u| u.values().sum::<u32>()));
     let on_logout_click = on_logout.clone();
+    let shop_id_settings = shop_id.clone();
+    let pin_settings = admin_pin.clone();
+
+    // Sửa tin admin / xoá (kiểm duyệt) tin qua REST
+    let shop_mod = StoredValue::new(shop_id.clone());
+    let pin_mod = StoredValue::new(admin_pin.clone());
+    let edit_message = move |guest_id: u64, id: u64, old_text: String| {
+        let window = web_sys::window().unwrap();
+        let Ok(Some(text)) = window.prompt_with_message_and_default(T::EditPrompt.text(), &old_text) else { return };
+        if text.trim().is_empty() || text == old_text { return; }
+        let edit = EditMessage::new(shop_mod.get_value(), guest_id, id, text.into_bytes().into());
+        let path = format!("/messages/{}/{}", guest_id, id);
+        spawn_local(async move {
+            let req = admin_request(Method::PATCH, &path, &shop_mod.get_value(), &pin_mod.get_value())
+                .header("Content-Type", "application/octet-stream")
+                .body(edit.encode_to_vec());
+            if let Ok(req) = req {
+                if let Ok(resp) = req.send().await {
+                    if !resp.ok() {
+                        leptos::logging::log!("❌ Edit failed: {}", resp.status());
+                    }
+                }
+            }
+        });
+    };
+    // Đóng / lưu trữ / mở lại hội thoại (PUT /guests/:id/status)
+    let set_conversation_status = move |guest_id: u64, status: ConversationStatus| {
+        let path = format!("/guests/{}/status", guest_id);
+        spawn_local(async move {
+            let mut req = ConversationStatusRequest::default();
+            req.set_status(status);
+            let Ok(req) = admin_request(Method::PUT, &path, &shop_mod.get_value(), &pin_mod.get_value())
+                .header("Content-Type", "application/octet-stream")
+                .body(req.encode_to_vec()) else { return };
+            match req.send().await {
+                Ok(resp) if resp.ok() => set_guest_info.update(|info| {
+                    if let Some(g) = info.get_mut(&guest_id) {
+                        g.set_status(status);
+                    }
+                }),
+                Ok(resp) => leptos::logging::log!("❌ Set status failed: {}", resp.status()),
+                Err(e) => leptos::logging::log!("❌ Set status failed: {}", e),
+            }
+        });
+    };
+    let delete_message = move |guest_id: u64, id: u64| {
+        let window = web_sys::window().unwrap();
+        if !window.confirm_with_message(T::DeleteConfirm.text()).unwrap_or(false) { return; }
+        let path = format!("/messages/{}/{}", guest_id, id);
+        spawn_local(async move {
+            if let Ok(resp) = admin_request(Method::DELETE, &path, &shop_mod.get_value(), &pin_mod.get_value()).send().await {
+                if !resp.ok() {
+                    leptos::logging::log!("❌ Delete failed: {}", resp.status());
+                }
+            }
+        });
+    };
+    // Lịch sử sửa của một tin (message_id, các bản cũ) - bấm "đã sửa" để mở/đóng
+    let edit_history = RwSignal::new(None::<(u64, Vec<MessageRevision>)>);
+    let toggle_history = move |guest_id: u64, id: u64| {
+        if edit_history.with_untracked(|h| h.as_ref().is_some_and(|(open, _)| *open == id)) {
+            edit_history.set(None);
+            return;
+        }
+        let path = format!("/messages/{}/{}/history", guest_id, id);
+        spawn_local(async move {
+            if let Ok(resp) = admin_request(Method::GET, &path, &shop_mod.get_value(), &pin_mod.get_value()).send().await {
+                if let Ok(history) = resp.binary().await.map(|b| MessageHistory::decode(&b[..])) {
+                    match history {
+                        Ok(history) => edit_history.set(Some((id, history.revisions))),
+                        Err(e) => leptos::logging::log!("❌ Bad history response: {:?}", e),
+                    }
+                }
+            }
+        });
+    };
+    let shop_id_analytics = shop_id.clone();
+    let pin_analytics = admin_pin.clone();
+    let shop_id_flagged = shop_id.clone();
+    let pin_flagged = admin_pin.clone();
+    let shop_id_live = shop_id.clone();
+    let pin_live = admin_pin.clone();
+    let shop_id_campaigns = shop_id.clone();
+    let pin_campaigns = admin_pin.clone();
+    let shop_id_prefs = shop_id.clone();
+    let pin_prefs = admin_pin.clone();
+    let shop_id_search = shop_id.clone();
+    let shop_id_bulk = shop_id.clone();
+    let pin_bulk = admin_pin.clone();
+    let pin_search = admin_pin.clone();
+    let shop_id_profile = shop_id.clone();
+    let shop_id_scheduled = shop_id.clone();
+    let pin_scheduled = admin_pin.clone();
+    let pin_profile = admin_pin.clone();
+    let shop_id_transfer = shop_id.clone();
+    let shop_id_snooze = shop_id.clone();
+    let shop_id_views = shop_id.clone();
+    let pin_views = admin_pin.clone();
+    let view_saved = move |id: Option<String>| active_view.set(id);
+    let pin_snooze = admin_pin.clone();
+    let pin_transfer = admin_pin.clone();
+    let me = Signal::derive(move || prefs.with(|p| p.agent_id.clone()));
+    let transferred = move |guest: Guest| {
+        set_guest_info.update(|info| { info.insert(guest.guest_id, guest); });
+    };
+    let toggle_view = move |target: DashboardView| {
+        set_view_mode.update(|v| *v = if *v == target { DashboardView::Chat } else { target });
+    };
+    // Esc đóng lớp trên cùng: hồ sơ khách → chuyển hội thoại → bảng cài đặt / thống kê → ô tìm → thanh trả lời → bỏ chọn hội thoại
+    let on_escape = move |e: web_sys::KeyboardEvent| {
+        if e.key() != "Escape" || e.default_prevented() {
+            return;
+        }
+        if show_profile.get_untracked() {
+            set_show_profile.set(false);
+        } else if show_transfer.get_untracked() {
+            set_show_transfer.set(false);
+        } else if view_mode.get_untracked() != DashboardView::Chat {
+            set_view_mode.set(DashboardView::Chat);
+        } else if search_open.get_untracked() {
+            close_search();
+        } else if replying_to.with_untracked(|r| r.is_some()) {
+            set_replying_to.set(None);
+        } else if selected.with_untracked(|s| !s.is_empty()) {
+            selected.set(HashSet::new());
+        } else {
+            return;
+        }
+        focus_soon(input_ref);
+    };
 
     view! {
         <style>{include_str!("../telegram_style.css")}</style>
 
-        <div class="app-container">
+        {move || server_notice.get().map(|notice| view! {
+            <div class="maintenance-banner" role="alert">{notice.banner_text()}</div>
+        })}
+        <div class="app-container" on:keydown=on_escape>
             // SIDEBAR
             <div class="sidebar">
                 <div class="sidebar-header">
                     <div class="shop-info">
-                        <span class="shop-name">{shop_name_display}</span>
-                        <button class="logout-btn" on:click=move |_| on_logout_click()>"Đăng xuất"</button>
+                        <span class="shop-name">
+                            <ShopSwitcher sessions=sessions active=active adding_shop=adding_shop unread=other_unread/>
+                            {move || (merged_unread.get() > 0).then(|| view! {
+                                <span class="unread-badge total" title=T::UnreadAllShops.text()>{unread_label(merged_unread.get())}</span>
+                            })}
+                            <span class="shop-status" title=T::OpeningHoursStatus.text()>
+                                {move || if shop_online.get() { " 🟢" } else { T::OutsideHours.text() }}
+                            </span>
+                        </span>
+                        <button
+                            class="settings-btn"
+                            title=move || if away.get() { T::AwayToggleOn.text() } else { T::AwayToggleOff.text() }
+                            on:click=move |_| toggle_away()
+                        >
+                            {move || if away.get() { "💤" } else { "🙋" }}
+                        </button>
+                        <button class="settings-btn" title=T::LiveTitle.text() on:click=move |_| toggle_view(DashboardView::Live)>"🟢"</button>
+                        <button class="settings-btn" title=T::CampaignsTitle.text() on:click=move |_| toggle_view(DashboardView::Campaigns)>"📣"</button>
+                        <button class="settings-btn" title=T::FlaggedTitle.text() on:click=move |_| toggle_view(DashboardView::Flagged)>"🚩"</button>
+                        <button class="settings-btn" title=T::AnalyticsTitle.text() on:click=move |_| toggle_view(DashboardView::Analytics)>"📊"</button>
+                        <button class="settings-btn" title=T::PreferencesTitle.text() on:click=move |_| toggle_view(DashboardView::Preferences)>"🔔"</button>
+                        <button class="settings-btn" title=T::SettingsTitle.text() on:click=move |_| toggle_view(DashboardView::Settings)>"⚙️"</button>
+                        <button class="logout-btn" on:click=move |_| on_logout_click()>{T::SignOut.text()}</button>
                     </div>
                 </div>
 
-                <div class="chat-list">
-                    <Show when=move || chat_users.get().is_empty()>
-                        <div class="empty-state">"Chưa có khách nào nhắn tin"</div>
+                <div class="conversation-tabs" role="tablist" aria-label=T::FilterConversations.text()>
+                    <button role="tab" aria-selected=move || filter_active(ConversationFilter::Open).to_string() class:active=move || filter_active(ConversationFilter::Open) on:click=move |_| pick_filter(ConversationFilter::Open)>{T::FilterOpen.text()}</button>
+                    <button role="tab" aria-selected=move || filter_active(ConversationFilter::Closed).to_string() class:active=move || filter_active(ConversationFilter::Closed) on:click=move |_| pick_filter(ConversationFilter::Closed)>{T::FilterClosed.text()}</button>
+                    <button role="tab" aria-selected=move || filter_active(ConversationFilter::All).to_string() class:active=move || filter_active(ConversationFilter::All) on:click=move |_| pick_filter(ConversationFilter::All)>{T::FilterAll.text()}</button>
+                    <button role="tab" title=T::FilterSnoozedTitle.text() aria-selected=move || filter_active(ConversationFilter::Snoozed).to_string() class:active=move || filter_active(ConversationFilter::Snoozed) on:click=move |_| pick_filter(ConversationFilter::Snoozed)>
+                        {move || T::FilterSnoozed.fill(&[&reminders.with(|r| r.len())])}
+                    </button>
+                    <select class="activity-filter" title=T::RecentActivity.text()
+                        on:change=move |ev| active_days.set(event_target_value(&ev).parse().unwrap_or(0))
+                    >
+                        <option value="0" selected=move || active_days.get() == 0>{T::AnyTime.text()}</option>
+                        <option value="1" selected=move || active_days.get() == 1>{T::Hours24.text()}</option>
+                        <option value="7" selected=move || active_days.get() == 7>{T::Days7.text()}</option>
+                        <option value="30" selected=move || active_days.get() == 30>{T::Days30.text()}</option>
+                    </select>
+                </div>
+
+                <div class="view-tabs" role="tablist" aria-label=T::SavedViews.text()>
+                    <For
+                        each=move || views.get()
+                        key=|v| (v.view_id.clone(), v.name.clone())
+                        children=move |view: SavedView| {
+                            let id = view.view_id.clone();
+                            let (id_active, id_selected, id_click, id_count) = (id.clone(), id.clone(), id.clone(), id);
+                            let is_active = move || active_view.with(|a| a.as_deref() == Some(id_active.as_str()));
+                            view! {
+                                <button
+                                    role="tab"
+                                    aria-selected=move || active_view.with(|a| a.as_deref() == Some(id_selected.as_str())).to_string()
+                                    class:active=is_active
+                                    on:click=move |_| active_view.set(Some(id_click.clone()))
+                                    on:dblclick=move |_| editing_view.set(Some(view.clone()))
+                                    title=T::ViewEditHint.text()
+                                >
+                                    {view.name.clone()}
+                                    <span class="view-count">{move || view_counts.with(|c| c.get(&id_count).copied().unwrap_or(0))}</span>
+                                </button>
+                            }
+                        }
+                    />
+                    <button class="view-add" title=T::NewView.text() aria-label=T::NewView.text() on:click=move |_| editing_view.set(Some(SavedView::default()))>"＋"</button>
+                </div>
+                {move || editing_view.get().map(|editing| view! {
+                    <ViewEditor
+                        shop_id=shop_id_views.clone()
+                        admin_pin=pin_views.clone()
+                        views=views
+                        editing=editing
+                        on_saved=view_saved
+                        on_close=move || editing_view.set(None)
+                    />
+                })}
+
+                <BulkBar
+                    shop_id=shop_id_bulk
+                    admin_pin=pin_bulk
+                    selected=selected
+                    visible=Signal::derive(move || visible_users.with(|users| users.iter().map(|u| u.guest_id).collect()))
+                    on_updated=apply_bulk
+                    on_mark_read=mark_read
+                />
+
+                <div class="chat-list" class:selecting=move || !selected.with(|s| s.is_empty()) role="region" aria-label=T::ConversationList.text()>
+                    <Show when=move || visible_users.get().is_empty()>
+                        <div class="empty-state">
+                            {move || if chat_users.get().is_empty() { T::NoGuests.text() } else { T::NoConversations.text() }}
+                        </div>
                     </Show>
                     
                     <For
-                        each=move || chat_users.get()
-                        key=|chat| chat.guest_id
+                        each=move || visible_users.get()
+                        key=|chat| (chat.guest_id, chat.last_message.clone(), chat.time.clone())
                         children=move |chat: ChatUser| {
                             let guest_id = chat.guest_id;
+                            let unread_count = move || unread.with(|u| u.get(&guest_id).copied().unwrap_or(0));
                             let is_active = move || current_guest_id.get() == guest_id;
+                            let is_selected = move || selected.with(|s| s.contains(&guest_id));
+                            let last_message = chat.last_message.clone();
+                            let open_chat = move || {
+                                notify::request_permission();
+                                notify::unlock_audio();
+                                set_current_guest_id.set(guest_id);
+                                focus_soon(input_ref);
+                            };
                             
                             view! {
                                 <div 
                                     class="chat-item" 
+                                    role="button"
+                                    tabindex="0"
+                                    aria-current=move || is_active().then_some("true")
+                                    aria-label=move || match unread_count() {
+                                        0 => guest_label(guest_id),
+                                        n => T::GuestUnread.fill(&[&guest_label(guest_id), &n]),
+                                    }
                                     class:active=is_active
-                                    on:click=move |_| set_current_guest_id.set(guest_id)
+                                    class:selected=is_selected
+                                    class:sla-warning=move || sla_level_of(guest_id) == SlaLevel::Warning
+                                    class:sla-breached=move || sla_level_of(guest_id) == SlaLevel::Breached
+                                    on:click=move |e: web_sys::MouseEvent| {
+                                        if e.shift_key() {
+                                            e.prevent_default();
+                                            select_range(guest_id);
+                                        } else if e.ctrl_key() || e.meta_key() {
+                                            toggle_selected(guest_id);
+                                        } else {
+                                            open_chat();
+                                        }
+                                    }
+                                    on:keydown=move |e: web_sys::KeyboardEvent| {
+                                        if e.key() == "Enter" || e.key() == " " {
+                                            e.prevent_default();
+                                            open_chat();
+                                        }
+                                    }
                                 >
-                                    <div class="avatar green">"K"</div>
+                                    <input
+                                        type="checkbox"
+                                        class="chat-select"
+                                        aria-label=move || T::SelectConversation.fill(&[&guest_label(guest_id)])
+                                        prop:checked=is_selected
+                                        on:click=move |e: web_sys::MouseEvent| {
+                                            e.stop_propagation();
+                                            if e.shift_key() { select_range(guest_id) } else { toggle_selected(guest_id) }
+                                        }
+                                    />
+                                    <div class="avatar green" aria-hidden="true">{move || guest_label(guest_id).chars().next().unwrap_or('K').to_uppercase().to_string()}</div>
                                     <div class="chat-info">
                                         <div class="chat-header">
-                                            <span class="chat-name">{chat.name.clone()}</span>
+                                            <span class="chat-name">{move || guest_label(guest_id)}</span>
+                                            {move || guest_info.with(|info| info.get(&guest_id).and_then(|g| g.geo.clone())).map(|geo| view! {
+                                                <span class="chat-flag" title=geo.label()>{geo.flag()}</span>
+                                            })}
+                                            {move || guest_info.with(|info| info.get(&guest_id).and_then(|g| g.mood())).map(|mood| view! {
+                                                <span class="chat-mood" title=T::Mood.fill(&[&mood.label()])>{mood.emoji()}</span>
+                                            })}
                                         </div>
-                                        <div class="chat-message">{chat.last_message.clone()}</div>
+                                        {move || {
+                                            let draft = drafts.with(|d| d.get(&guest_id).cloned()).filter(|_| !is_active());
+                                            match draft {
+                                                Some(text) => view! {
+                                                    <div class="chat-message"><span class="chat-draft">{T::Draft.text()}</span><bdi>{text}</bdi></div>
+                                                }.into_any(),
+                                                None => view! { <div class="chat-message" dir="auto">{last_message.clone()}</div> }.into_any(),
+                                            }
+                                        }}
+                                    </div>
+                                    <div class="chat-meta">
+                                        <span class="chat-time">{chat.time.clone()}</span>
+                                        {move || guest_info.with(|info| info.get(&guest_id).is_some_and(|g| g.needs_human)).then(|| view! {
+                                            <span class="chat-needs-human" title=T::NeedsHuman.text() aria-label=T::NeedsHuman.text()>"🙋"</span>
+                                        })}
+                                        {move || reminders.with(|r| r.get(&guest_id).map(|r| r.remind_at_us)).map(|at| view! {
+                                            <span class="chat-snoozed" title=T::SnoozedUntil.fill(&[&format_datetime(at)])>{format!("⏰ {}", format_list_time(at, now_us()))}</span>
+                                        })}
+                                        {move || (unread_count() > 0).then(|| view! {
+                                            <span class="unread-badge">{unread_label(unread_count())}</span>
+                                        })}
                                     </div>
-                                    <span class="chat-time">{chat.time.clone()}</span>
                                 </div>
                             }
                         }
                     />
+                    <Show when=move || guest_cursor.with(|c| c.is_some())>
+                        <button class="load-more" on:click=move |_| load_guests(guest_cursor.get_untracked())>{T::LoadMoreConversations.text()}</button>
+                    </Show>
                 </div>
             </div>
 
+            // SETTINGS
+            <Show when=move || view_mode.get() == DashboardView::Settings>
+                <SettingsPanel
+                    shop_id=shop_id_settings.clone()
+                    admin_pin=pin_settings.clone()
+                    on_close=move || {
+                        load_shop_settings();
+                        set_view_mode.set(DashboardView::Chat);
+                    }
+                />
+            </Show>
+
+            // ANALYTICS
+            <Show when=move || view_mode.get() == DashboardView::Analytics>
+                <AnalyticsPage
+                    shop_id=shop_id_analytics.clone()
+                    admin_pin=pin_analytics.clone()
+                    on_close=move || set_view_mode.set(DashboardView::Chat)
+                />
+            </Show>
+
+            // FLAGGED
+            <Show when=move || view_mode.get() == DashboardView::Flagged>
+                <FlaggedPage
+                    shop_id=shop_id_flagged.clone()
+                    admin_pin=pin_flagged.clone()
+                    on_open_guest=move |gid| {
+                        set_current_guest_id.set(gid);
+                        set_view_mode.set(DashboardView::Chat);
+                    }
+                    on_close=move || set_view_mode.set(DashboardView::Chat)
+                />
+            </Show>
+
+            // LIVE VISITORS
+            <Show when=move || view_mode.get() == DashboardView::Live>
+                <LivePage
+                    shop_id=shop_id_live.clone()
+                    admin_pin=pin_live.clone()
+                    on_open_guest=move |guest: Guest| {
+                        let gid = guest.guest_id;
+                        // Khách chưa từng nhắn chưa có trong /guests → thêm để header có tên / trang đang xem
+                        set_guest_info.update(|info| { info.entry(gid).or_insert(guest); });
+                        set_current_guest_id.set(gid);
+                        set_view_mode.set(DashboardView::Chat);
+                        focus_soon(input_ref);
+                    }
+                    on_close=move || set_view_mode.set(DashboardView::Chat)
+                />
+            </Show>
+
+            // CAMPAIGNS
+            <Show when=move || view_mode.get() == DashboardView::Campaigns>
+                <CampaignsPage
+                    shop_id=shop_id_campaigns.clone()
+                    admin_pin=pin_campaigns.clone()
+                    on_close=move || set_view_mode.set(DashboardView::Chat)
+                />
+            </Show>
+
+            // PREFERENCES
+            <Show when=move || view_mode.get() == DashboardView::Preferences>
+                <PreferencesPage
+                    shop_id=shop_id_prefs.clone()
+                    admin_pin=pin_prefs.clone()
+                    prefs=prefs
+                    on_close=move || set_view_mode.set(DashboardView::Chat)
+                />
+            </Show>
+
             // CHAT AREA
-            <div class="chat-area">
+            <div
+                class="chat-area"
+                class:hidden=move || view_mode.get() != DashboardView::Chat
+                class:drag-over=move || drag_over.get()
+                on:dragover=move |e: web_sys::DragEvent| {
+                    if current_guest_id.get_untracked() != 0 {
+                        e.prevent_default();
+                        set_drag_over.set(true);
+                    }
+                }
+                on:dragleave=move |_| set_drag_over.set(false)
+                on:drop=move |e: web_sys::DragEvent| {
+                    e.prevent_default();
+                    set_drag_over.set(false);
+                    send_files(upload::dropped_files(&e));
+                }
+            >
                 <div class="chat-header-bar">
                     <div class="chat-header-info">
                         <div class="chat-header-name">
                             {move || if current_guest_id.get() == 0 { 
-                                "Chọn cuộc trò chuyện".to_string() 
+                                T::PickConversation.text().to_string() 
                             } else { 
-                                format!("Khách #{}", current_guest_id.get() % 10000) 
+                                guest_label(current_guest_id.get())
                             }}
                         </div>
                         <div class="chat-header-status">{move || connection_status.get()}</div>
+                        <ActivityIndicator colleagues=colleagues guest_id=current_guest_id/>
                     </div>
+                    <Show when=move || current_guest_id.get() != 0>
+                        {move || {
+                            let gid = current_guest_id.get();
+                            let status = status_of(gid);
+                            view! {
+                                {(status == ConversationStatus::Open).then(|| view! {
+                                    <button class="conversation-action" on:click=move |_| set_conversation_status(gid, ConversationStatus::Closed)>{T::CloseConversation.text()}</button>
+                                })}
+                                {(status != ConversationStatus::Open).then(|| view! {
+                                    <button class="conversation-action" on:click=move |_| set_conversation_status(gid, ConversationStatus::Open)>{T::Reopen.text()}</button>
+                                })}
+                                {(status != ConversationStatus::Archived).then(|| view! {
+                                    <button class="conversation-action" on:click=move |_| set_conversation_status(gid, ConversationStatus::Archived)>{T::Archive.text()}</button>
+                                })}
+                            }
+                        }}
+                        <button class="settings-btn" title=T::SearchConversation.text() on:click=move |_| if search_open.get_untracked() { close_search() } else { set_search_open.set(true) }>"🔍"</button>
+                        <SnoozeMenu shop_id=shop_id_snooze.clone() admin_pin=pin_snooze.clone() guest_id=current_guest_id reminders=reminders/>
+                        <button class="settings-btn" title=T::TransferTitle.text() on:click=move |_| set_show_transfer.update(|v| *v = !*v)>"↪️"</button>
+                        <button class="settings-btn" title=T::GuestInfo.text() on:click=move |_| set_show_profile.update(|v| *v = !*v)>"ℹ️"</button>
+                    </Show>
                 </div>
+                <Show when=move || show_transfer.get() && current_guest_id.get() != 0>
+                    <TransferPanel
+                        shop_id=shop_id_transfer.clone()
+                        admin_pin=pin_transfer.clone()
+                        guest_id=current_guest_id
+                        me=me
+                        on_done=transferred
+                        on_close=move || set_show_transfer.set(false)
+                    />
+                </Show>
+                <Show when=move || search_open.get() && current_guest_id.get() != 0>
+                    <ConversationSearch
+                        shop_id=shop_id_search.clone()
+                        admin_pin=pin_search.clone()
+                        guest_id=current_guest_id
+                        query=search_query
+                        matches=search_matches
+                        oldest_loaded=oldest_loaded
+                        active=search_active
+                        on_found=add_found
+                        on_close=close_search
+                    />
+                </Show>
 
                 <div class="scrollable-content" node_ref=scrollable_ref>
-                    <div class="messages-container">
+                    // Tin mới được trình đọc màn hình đọc lên (role=log ~ aria-live=polite)
+                    <div class="messages-container" role="log" aria-live="polite" aria-label=T::Messages.text()>
                         <For
-                            each=move || current_messages.get()
-                            key=|msg| msg.id
-                            children=move |msg: DisplayMessage| {
-                                let class = if msg.sender_type == "admin" { 
+                            each=move || with_day_breaks(timeline.get())
+                            key=|(new_day, msg)| (msg.id, msg.text.clone(), msg.deleted, msg.status, *new_day)
+                            children=move |(new_day, msg): (bool, DisplayMessage)| {
+                                let ts = msg.timestamp_us;
+                                let day_break = new_day.then(|| view! {
+                                    <div class="day-separator"><span>{format_day_label(ts, now_us())}</span></div>
+                                });
+                                if msg.sender_type == "system" {
+                                    return view! {
+                                        {day_break}
+                                        <div class="system-line" title=format_datetime(ts)><span dir="auto">"↪️ "{msg.text}</span></div>
+                                    }.into_any();
+                                }
+                                // Tin bot (luật tự động) hiện bên phía shop
+                                let class = if msg.sender_type != "guest" { 
                                     "message sent" 
                                 } else { 
                                     "message received" 
                                 };
+                                let is_bot = msg.sender_type == "bot";
+                                let (gid, id, text) = (msg.guest_id, msg.id, msg.text.clone());
+                                let is_admin = msg.sender_type == "admin";
+                                let deleted = msg.deleted;
+                                let status = msg.status;
+                                let preview = ReplyPreview {
+                                    message_id: msg.id,
+                                    sender_type: msg.sender_type.clone(),
+                                    snippet: msg.text.chars().take(ReplyPreview::SNIPPET_CHARS).collect(),
+                                    deleted,
+                                };
+                                let quote = msg.reply.clone().map(|r| {
+                                    let quoted_id = r.message_id;
+                                    view! {
+                                        <div
+                                            class="message-quote"
+                                            role="button"
+                                            tabindex="0"
+                                            aria-label=T::ViewQuoted.text()
+                                            on:click=move |_| scroll_to_message(quoted_id)
+                                            on:keydown=move |e: web_sys::KeyboardEvent| if e.key() == "Enter" { scroll_to_message(quoted_id) }
+                                        >
+                                            <b>{if r.sender_type == "admin" { T::SenderYou.text() } else { T::SenderGuest.text() }}</b>
+                                            <span>{if r.deleted { T::QuoteDeleted.text().to_string() } else { r.snippet }}</span>
+                                        </div>
+                                    }
+                                });
+                                let quick_replies = (!msg.quick_replies.is_empty()).then(|| view! {
+                                    <div class="message-quick-replies">
+                                        {msg.quick_replies.iter().map(|q| view! {
+                                            <span class="quick-reply-chip" title=q.payload.clone()>{q.label.clone()}</span>
+                                        }).collect_view()}
+                                    </div>
+                                });
                                 view! {
-                                    <div class=class>
-                                        <div class="message-bubble">
-                                            <div class="message-text">{msg.text.clone()}</div>
-                                            <div class="message-meta"><span>{msg.time.clone()}</span></div>
+                                    {day_break}
+                                    <div
+                                        class=class
+                                        id=format!("msg-{}", id)
+                                        class:search-match=move || search_matches.with(|ids| ids.contains(&id))
+                                        class:search-active=move || search_active.get() == Some(id)
+                                    >
+                                        <div class="message-bubble" class:deleted=deleted tabindex="0">
+                                            {quote}
+                                            {upload::attachments_view(msg.attachments.clone())}
+                                            {msg.upload_preview.clone().map(|src| view! { <img class="message-image uploading" src=src/> })}
+                                            <div class="message-text" dir="auto" inner_html={
+                                                let html = render_markdown(&msg.text);
+                                                if deleted { T::DeletedPrefix.fill(&[&html]) } else { html }
+                                            }></div>
+                                            {quick_replies}
+                                            <div class="message-meta">
+                                                {(!deleted && status == SendStatus::Sent).then(|| view! {
+                                                    <span class="message-actions">
+                                                        <button title=T::Reply.text() aria-label=T::Reply.text() on:click=move |_| set_replying_to.set(Some(preview.clone()))>"↩️"</button>
+                                                        {is_admin.then(|| view! {
+                                                            <button title=T::Edit.text() aria-label=T::Edit.text() on:click=move |_| edit_message(gid, id, text.clone())>"✏️"</button>
+                                                        })}
+                                                        <button title=T::Delete.text() aria-label=T::Delete.text() on:click=move |_| delete_message(gid, id)>"🗑️"</button>
+                                                    </span>
+                                                })}
+                                                {is_bot.then(|| view! { <span class="message-edited" title=T::AutoReply.text()>"🤖 · "</span> })}
+                                                {msg.edited.then(|| view! {
+                                                    <button class="message-edited history-toggle" title=T::ShowRevisions.text() on:click=move |_| toggle_history(gid, id)>{T::Edited.text()}</button>
+                                                    " · "
+                                                })}
+                                                <span title=format_datetime(ts)>{format_clock(ts)}</span>
+                                                {(status == SendStatus::Uploading).then(|| view! {
+                                                    <span class="message-status" title=T::UploadingTitle.text()>{T::Uploading.text()}</span>
+                                                })}
+                                                {(status == SendStatus::Pending).then(|| view! {
+                                                    <span class="message-status" title=T::SendingTitle.text()>" 🕓"</span>
+                                                })}
+                                                {(status == SendStatus::Queued).then(|| view! {
+                                                    <span class="message-status" title=T::QuotaQueuedTitle.text()>{T::QuotaQueued.text()}</span>
+                                                })}
+                                                {(status == SendStatus::Failed).then(|| view! {
+                                                    <span class="message-status failed">
+                                                        {T::SendFailed.text()}
+                                                        <button on:click=move |_| retry_message(gid, id)>{T::Retry.text()}</button>
+                                                    </span>
+                                                })}
+                                            </div>
+                                            {move || edit_history.with(|h| h.as_ref().filter(|(open, _)| *open == id).map(|(_, revisions)| {
+                                                let rows = revisions.iter().map(|r| {
+                                                    let who = if r.editor == "guest" { T::SenderGuest.text() } else { T::SenderAdmin.text() };
+                                                    view! {
+                                                        <li>
+                                                            <span class="history-when" title=format_datetime(r.replaced_at_us)>
+                                                                {T::EditedBy.fill(&[&who, &format_clock(r.replaced_at_us)])}
+                                                            </span>
+                                                            <span class="history-text">{String::from_utf8_lossy(&r.content).to_string()}</span>
+                                                        </li>
+                                                    }
+                                                }).collect_view();
+                                                view! {
+                                                    <div class="edit-history">
+                                                        <div class="edit-history-title">{T::Revisions.text()}</div>
+                                                        {revisions.is_empty().then(|| view! { <div class="history-empty">{T::NoRevisions.text()}</div> })}
+                                                        <ul>{rows}</ul>
+                                                    </div>
+                                                }
+                                            }))}
                                         </div>
                                     </div>
-                                }
+                                }.into_any()
                             }
                         />
                     </div>
                 </div>
 
                 <div class="input-area">
+                    <ScheduledBar
+                        shop_id=shop_id_scheduled.clone()
+                        admin_pin=pin_scheduled.clone()
+                        guest_id=current_guest_id
+                        scheduled=scheduled
+                    />
+                    <CollisionWarning colleagues=colleagues guest_id=current_guest_id/>
+                    {move || replying_to.get().map(|r| view! {
+                        <div class="reply-bar">
+                            <span>
+                                {T::ReplyingTo.text()}
+                                <b>{if r.sender_type == "admin" { T::SenderYou.text() } else { T::SenderGuest.text() }}</b>
+                                ": " {r.snippet}
+                            </span>
+                            <button aria-label=T::CancelReply.text() on:click=move |_| set_replying_to.set(None)>"✕"</button>
+                        </div>
+                    })}
+                    {move || {
+                        let options = pending_quick_replies.get();
+                        (!options.is_empty()).then(|| view! {
+                            <div class="reply-bar">
+                                <span>{T::QuickRepliesPending.text()} {format_quick_replies(&options)}</span>
+                                <button aria-label=T::RemoveQuickReplies.text() on:click=move |_| pending_quick_replies.set(Vec::new())>"✕"</button>
+                            </div>
+                        })
+                    }}
+                    <div class="format-toolbar" role="toolbar" aria-label=T::FormatToolbar.text()>
+                        <button title=T::Bold.text() on:click=move |_| format_input("**", "**", T::BoldPlaceholder.text())><b>"B"</b></button>
+                        <button title=T::Italic.text() on:click=move |_| format_input("*", "*", T::ItalicPlaceholder.text())><i>"I"</i></button>
+                        <button title="Code" on:click=move |_| format_input("`", "`", "code")><code>"</>"</code></button>
+                        <button title=T::Link.text() on:click=move |_| insert_link()>"🔗"</button>
+                        <button title=T::QuickReplies.text() on:click=move |_| edit_quick_replies()>"⚡"</button>
+                        <button title=T::Schedule.text() disabled=move || current_guest_id.get() == 0 on:click=move |_| schedule_current()>"⏰"</button>
+                        <EmojiPicker input=input_ref on_input=move |value| set_message_input.set(value) storage_key="turbochat_admin_recent_emojis"/>
+                    </div>
                     <div class="input-bubble">
                         <input 
                             type="text" 
                             class="message-input" 
-                            placeholder="Nhập tin nhắn..."
+                            node_ref=input_ref
+                            dir="auto"
+                            aria-label=T::InputLabel.text()
+                            placeholder=T::InputPlaceholder.text()
                             disabled=move || current_guest_id.get() == 0
                             prop:value=move || message_input.get()
-                            on:input=move |e| set_message_input.set(event_target_value(&e))
+                            on:input=move |e| {
+                                let text = event_target_value(&e);
+                                note_typing(!text.trim().is_empty());
+                                set_message_input.set(text);
+                            }
+                            on:paste=move |e: web_sys::Event| {
+                                let images = upload::pasted_images(&e);
+                                if !images.is_empty() {
+                                    e.prevent_default();
+                                    send_files(images);
+                                }
+                            }
                             on:keypress=move |e: web_sys::KeyboardEvent| { 
                                 if e.key() == "Enter" { 
                                     set_send_trigger.set(js_sys::Date::now() as u64); 
@@ -593,6 +2284,7 @@ fn Dashboard(
                         />
                         <button 
                             class="send-button" 
+                            aria-label=T::Send.text()
                             disabled=move || current_guest_id.get() == 0
                             on:click=move |_| set_send_trigger.set(js_sys::Date::now() as u64)
                         >
@@ -601,12 +2293,93 @@ fn Dashboard(
                     </div>
                 </div>
             </div>
+
+            // GUEST PROFILE
+            <Show when=move || show_profile.get() && current_guest_id.get() != 0 && view_mode.get() == DashboardView::Chat>
+                <GuestProfile
+                    shop_id=shop_id_profile.clone()
+                    admin_pin=pin_profile.clone()
+                    guest_id=current_guest_id
+                    guest_info=guest_info
+                    blocked=blocked
+                    notes=notes
+                    on_close=move || set_show_profile.set(false)
+                />
+            </Show>
         </div>
     }
 }
 
-fn format_time(timestamp_us: u64) -> String {
-    let secs = (timestamp_us / 1_000_000) as f64;
-    let datetime = js_sys::Date::new(&(secs * 1000.0).into());
-    format!("{:02}:{:02}", datetime.get_hours(), datetime.get_minutes())
+// Đánh dấu tin mở đầu mỗi ngày (chèn phân cách ngày)
+// "Có | Gặp nhân viên=handoff" → [Có→Có, Gặp nhân viên→handoff]
+fn parse_quick_replies(raw: &str) -> Vec<QuickReply> {
+    QuickReply::sanitize(raw.split('|').map(|part| {
+        let (label, payload) = part.split_once('=').unwrap_or((part, ""));
+        QuickReply { label: label.to_string(), payload: payload.trim().to_string() }
+    }).collect())
+}
+
+fn format_quick_replies(options: &[QuickReply]) -> String {
+    options.iter().map(|q| {
+        if q.payload == q.label { q.label.clone() } else { format!("{}={}", q.label, q.payload) }
+    }).collect::<Vec<_>>().join(" | ")
+}
+
+fn with_day_breaks(messages: Vec<DisplayMessage>) -> Vec<(bool, DisplayMessage)> {
+    let mut prev = None;
+    messages.into_iter().map(|msg| {
+        let new_day = is_new_day(prev, msg.timestamp_us);
+        prev = Some(msg.timestamp_us);
+        (new_day, msg)
+    }).collect()
+}
+
+// 1s, 2s, 4s ... tối đa 30s, cộng thêm tối đa 1s ngẫu nhiên (tránh dồn kết nối)
+fn reconnect_delay_ms(attempt: u32) -> i32 {
+    let base = 1000 * 2i32.pow(attempt.saturating_sub(1).min(5));
+    base.min(30_000) + (js_sys::Math::random() * 1000.0) as i32
+}
+
+// Id lần theo frame qua log server (x-request-id)
+fn new_request_id() -> String {
+    format!("{:016x}", (js_sys::Math::random() * u64::MAX as f64) as u64)
+}
+
+// Huy hiệu chưa đọc: quá 99 thì hiện "99+"
+fn unread_label(count: u32) -> String {
+    if count > 99 { "99+".to_string() } else { count.to_string() }
+}
+
+// id ổn định cho trình duyệt admin này (trạng thái vắng mặt lưu theo id)
+fn load_agent_id() -> String {
+    let storage = web_sys::window().and_then(|w| w.local_storage().ok().flatten());
+    if let Some(id) = storage.as_ref().and_then(|s| s.get_item("turbochat_agent_id").ok().flatten()) {
+        return id;
+    }
+    let id = format!("{:08x}{:08x}", (js_sys::Math::random() * u32::MAX as f64) as u32, (js_sys::Math::random() * u32::MAX as f64) as u32);
+    if let Some(storage) = storage {
+        let _ = storage.set_item("turbochat_agent_id", &id);
+    }
+    id
+}
+
+fn drafts_key(shop_id: &str) -> String {
+    format!("turbochat_admin_drafts_{}", shop_id)
+}
+
+fn load_drafts(shop_id: &str) -> HashMap<u64, String> {
+    web_sys::window()
+        .and_then(|w| w.local_storage().ok().flatten())
+        .and_then(|s| s.get_item(&drafts_key(shop_id)).ok().flatten())
+        .and_then(|raw| serde_json::from_str::<HashMap<u64, String>>(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_drafts(shop_id: &str, drafts: &HashMap<u64, String>) {
+    if let (Some(storage), Ok(raw)) = (
+        web_sys::window().and_then(|w| w.local_storage().ok().flatten()),
+        serde_json::to_string(drafts),
+    ) {
+        let _ = storage.set_item(&drafts_key(shop_id), &raw);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,168 @@
+use leptos::prelude::*;
+use turbochat_shared::{bulk_conversation_request::Action, AgentListResponse, AgentSummary, BulkConversationRequest, BulkConversationResponse, ConversationStatus, Guest};
+use prost::Message as ProstMessage;
+use wasm_bindgen_futures::spawn_local;
+use gloo_net::http::Method;
+use std::collections::HashSet;
+
+use crate::app::admin_request;
+use crate::i18n::T;
+
+/// GET /admins - nhân viên của shop để giao hội thoại (lỗi → danh sách rỗng, vẫn giao được cho chủ shop)
+pub(crate) async fn load_agents(shop_id: &str, admin_pin: &str) -> Vec<AgentSummary> {
+    let Ok(resp) = admin_request(Method::GET, "/admins", shop_id, admin_pin).send().await else { return Vec::new() };
+    if !resp.ok() {
+        return Vec::new();
+    }
+    let bytes = resp.binary().await.unwrap_or_default();
+    AgentListResponse::decode(&bytes[..]).map(|r| r.agents).unwrap_or_default()
+}
+
+// ============================================================================
+// THAO TÁC HÀNG LOẠT - đóng / lưu trữ / mở lại, gắn tag, giao người phụ trách cho các
+// hội thoại đang chọn trên sidebar bằng một request POST /conversations/bulk.
+// "Đã đọc" chỉ xoá badge phía trình duyệt (server không lưu trạng thái đọc)
+// ============================================================================
+#[component]
+pub fn BulkBar(
+    shop_id: String,
+    admin_pin: String,
+    selected: RwSignal<HashSet<u64>>,
+    // id hội thoại đang hiện trên sidebar (cho "chọn tất cả")
+    visible: Signal<Vec<u64>>,
+    on_updated: impl Fn(Vec<Guest>) + 'static + Copy,
+    on_mark_read: impl Fn(Vec<u64>) + 'static + Copy,
+) -> impl IntoView {
+    let (status, set_status) = signal(String::new());
+    let busy = RwSignal::new(false);
+    let agents = RwSignal::new(Vec::<AgentSummary>::new());
+    let assign_choice = RwSignal::new(String::new());
+
+    let shop = StoredValue::new(shop_id);
+    let pin = StoredValue::new(admin_pin);
+
+    // Danh sách nhân viên chỉ cần khi bắt đầu chọn
+    let agents_requested = StoredValue::new(false);
+    Effect::new(move |_| {
+        if selected.with(|s| s.is_empty()) || agents_requested.get_value() {
+            return;
+        }
+        agents_requested.set_value(true);
+        spawn_local(async move {
+            agents.set(load_agents(&shop.get_value(), &pin.get_value()).await);
+        });
+    });
+
+    let run = move |action: Action| {
+        let guest_ids: Vec<u64> = selected.with_untracked(|s| s.iter().copied().collect());
+        if guest_ids.is_empty() || busy.get_untracked() {
+            return;
+        }
+        busy.set(true);
+        set_status.set(T::BulkWorking.fill(&[&guest_ids.len()]));
+        let req = BulkConversationRequest { guest_ids, action: Some(action) };
+        spawn_local(async move {
+            let result = match admin_request(Method::POST, "/conversations/bulk", &shop.get_value(), &pin.get_value())
+                .header("Content-Type", "application/octet-stream")
+                .body(req.encode_to_vec())
+            {
+                Ok(r) => r.send().await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(resp) if resp.ok() => {
+                    let bytes = resp.binary().await.unwrap_or_default();
+                    match BulkConversationResponse::decode(&bytes[..]) {
+                        Ok(done) => {
+                            let failed = done.failed_guest_ids.len();
+                            on_updated(done.guests);
+                            set_status.set(if failed == 0 { String::new() } else { T::BulkFailed.fill(&[&failed]) });
+                            // Giữ lại các hội thoại lỗi để thử lại
+                            selected.set(done.failed_guest_ids.into_iter().collect());
+                        }
+                        Err(_) => set_status.set(T::InvalidResponse.text().to_string()),
+                    }
+                }
+                Ok(resp) => set_status.set(T::HttpError.fill(&[&resp.status()])),
+                Err(e) => set_status.set(T::NetworkError.fill(&[&e])),
+            }
+            busy.set(false);
+        });
+    };
+
+    let add_tag = move |_| {
+        let window = web_sys::window().unwrap();
+        let Ok(Some(tag)) = window.prompt_with_message(T::BulkTagPrompt.text()) else { return };
+        let tag = tag.trim().to_string();
+        if !tag.is_empty() {
+            run(Action::AddTag(tag));
+        }
+    };
+
+    let mark_read = move |_| {
+        on_mark_read(selected.with_untracked(|s| s.iter().copied().collect()));
+        set_status.set(String::new());
+        selected.set(HashSet::new());
+    };
+
+    let all_selected = move || {
+        let ids = visible.get();
+        !ids.is_empty() && selected.with(|s| ids.iter().all(|id| s.contains(id)))
+    };
+    let toggle_all = move |_| {
+        if all_selected() {
+            selected.set(HashSet::new());
+        } else {
+            selected.set(visible.get_untracked().into_iter().collect());
+        }
+    };
+
+    view! {
+        <div class="bulk-bar" role="toolbar" aria-label=T::BulkActions.text() prop:hidden=move || selected.with(|s| s.is_empty())>
+            <div class="bulk-row">
+                <label class="bulk-count">
+                    <input type="checkbox" prop:checked=all_selected on:change=toggle_all aria-label=T::SelectAll.text()/>
+                    {move || T::SelectedCount.fill(&[&selected.with(|s| s.len())])}
+                </label>
+                <span class="bulk-status" role="status">{move || status.get()}</span>
+                <button class="settings-close" title=T::ClearSelection.text() aria-label=T::ClearSelection.text() on:click=move |_| selected.set(HashSet::new())>"✕"</button>
+            </div>
+            <div class="bulk-row">
+                <button class="conversation-action" prop:disabled=move || busy.get() on:click=move |_| run(Action::Status(ConversationStatus::Closed as i32))>{T::CloseConversation.text()}</button>
+                <button class="conversation-action" prop:disabled=move || busy.get() on:click=move |_| run(Action::Status(ConversationStatus::Open as i32))>{T::Reopen.text()}</button>
+                <button class="conversation-action" prop:disabled=move || busy.get() on:click=move |_| run(Action::Status(ConversationStatus::Archived as i32))>{T::Archive.text()}</button>
+            </div>
+            <div class="bulk-row">
+                <button class="conversation-action" prop:disabled=move || busy.get() on:click=add_tag>{T::BulkAddTag.text()}</button>
+                <button class="conversation-action" on:click=mark_read>{T::MarkRead.text()}</button>
+                <select
+                    class="bulk-assign"
+                    aria-label=T::BulkAssign.text()
+                    prop:disabled=move || busy.get()
+                    prop:value=move || assign_choice.get()
+                    on:change=move |e| {
+                        let choice = event_target_value(&e);
+                        // Chọn xong thì trả ô chọn về dòng "Giao cho..." (dùng lại được cho lần sau)
+                        assign_choice.set(choice.clone());
+                        if !choice.is_empty() {
+                            run(Action::AssignTo(if choice == "-" { String::new() } else { choice }));
+                        }
+                        assign_choice.set(String::new());
+                    }
+                >
+                    <option value="">{T::BulkAssign.text()}</option>
+                    <option value="owner">{T::ShopOwner.text()}</option>
+                    <For
+                        each=move || agents.get()
+                        key=|a| a.agent_id.clone()
+                        children=|a: AgentSummary| {
+                            let label = if a.name.is_empty() { a.email.clone() } else { a.name.clone() };
+                            view! { <option value=a.agent_id>{label}</option> }
+                        }
+                    />
+                    <option value="-">{T::Unassign.text()}</option>
+                </select>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,275 @@
+use leptos::prelude::*;
+use turbochat_shared::{format_datetime, Campaign, CampaignListResponse, CampaignPreview, CampaignSegment, CampaignStatus};
+use prost::Message as ProstMessage;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+use gloo_net::http::Method;
+
+use crate::app::admin_request;
+use crate::i18n::T;
+use crate::scheduled::parse_local_input;
+
+// Tải lại danh sách (tiến độ gửi, bộ đếm đã nhận / đã xem) khi trang đang mở
+const REFRESH_MS: i32 = 10_000;
+// Lựa chọn "hoạt động trong N ngày" (0 = mọi khách)
+const ACTIVE_DAYS: [u32; 4] = [0, 7, 30, 90];
+
+fn status_label(status: CampaignStatus) -> &'static str {
+    match status {
+        CampaignStatus::Scheduled => T::CampaignScheduled.text(),
+        CampaignStatus::Sending => T::CampaignSending.text(),
+        CampaignStatus::Sent => T::CampaignSent.text(),
+        CampaignStatus::Cancelled => T::CampaignCancelled.text(),
+    }
+}
+
+// "vip, mới" → ["vip", "mới"]
+fn parse_tags(raw: &str) -> Vec<String> {
+    raw.split(',').map(str::trim).filter(|t| !t.is_empty()).map(str::to_string).collect()
+}
+
+// ============================================================================
+// CAMPAIGNS PAGE - Gửi một tin tới nhóm khách (ngay hoặc hẹn giờ) + bộ đếm đã gửi / đã nhận / đã xem
+// ============================================================================
+#[component]
+pub fn CampaignsPage(
+    shop_id: String,
+    admin_pin: String,
+    on_close: impl Fn() + 'static + Clone,
+) -> impl IntoView {
+    let (items, set_items) = signal(Vec::<Campaign>::new());
+    let (status, set_status) = signal(T::Loading.text().to_string());
+    let (name, set_name) = signal(String::new());
+    let (text, set_text) = signal(String::new());
+    let (active_days, set_active_days) = signal(30u32);
+    let (tags, set_tags) = signal(String::new());
+    let (send_at, set_send_at) = signal(String::new());
+    let (preview, set_preview) = signal(None::<u32>);
+    let (busy, set_busy) = signal(false);
+
+    let shop = StoredValue::new(shop_id);
+    let pin = StoredValue::new(admin_pin);
+
+    let refresh = move || {
+        spawn_local(async move {
+            let result = admin_request(Method::GET, "/campaigns", &shop.get_value(), &pin.get_value()).send().await;
+            match result {
+                Ok(resp) => {
+                    let bytes = resp.binary().await.unwrap_or_default();
+                    match CampaignListResponse::decode(&bytes[..]) {
+                        Ok(list) if list.success => {
+                            set_items.set(list.items);
+                            set_status.set(String::new());
+                        }
+                        Ok(list) => set_status.set(T::ApiError.fill(&[&list.error, &list.request_id])),
+                        Err(e) => set_status.set(format!("❌ {}", e)),
+                    }
+                }
+                Err(e) => set_status.set(T::NetworkError.fill(&[&e])),
+            }
+        });
+    };
+    refresh();
+
+    let tick = Closure::wrap(Box::new(refresh) as Box<dyn FnMut()>);
+    let timer = web_sys::window().unwrap()
+        .set_interval_with_callback_and_timeout_and_arguments_0(tick.as_ref().unchecked_ref(), REFRESH_MS)
+        .ok();
+    tick.forget();
+    on_cleanup(move || {
+        if let Some(handle) = timer {
+            web_sys::window().unwrap().clear_interval_with_handle(handle);
+        }
+    });
+
+    let segment = move || CampaignSegment { active_within_days: active_days.get_untracked(), tags: parse_tags(&tags.get_untracked()) };
+
+    // Đổi điều kiện → số khách cũ không còn đúng
+    Effect::new(move |_| {
+        active_days.track();
+        tags.track();
+        set_preview.set(None);
+    });
+
+    let on_preview = move |_| {
+        let body = segment().encode_to_vec();
+        spawn_local(async move {
+            let result = match admin_request(Method::POST, "/campaigns/preview", &shop.get_value(), &pin.get_value())
+                .header("Content-Type", "application/octet-stream")
+                .body(body)
+            {
+                Ok(r) => r.send().await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(resp) if resp.ok() => {
+                    let bytes = resp.binary().await.unwrap_or_default();
+                    set_preview.set(CampaignPreview::decode(&bytes[..]).ok().map(|p| p.recipients));
+                }
+                Ok(resp) => set_status.set(T::CampaignFailed.fill(&[&resp.status()])),
+                Err(e) => set_status.set(T::NetworkError.fill(&[&e])),
+            }
+        });
+    };
+
+    let on_create = move |_| {
+        let window = web_sys::window().unwrap();
+        if name.get_untracked().trim().is_empty() || text.get_untracked().trim().is_empty() {
+            let _ = window.alert_with_message(T::CampaignEmpty.text());
+            return;
+        }
+        // Để trống → gửi ngay
+        let raw = send_at.get_untracked();
+        let send_at_us = if raw.trim().is_empty() {
+            0
+        } else {
+            match parse_local_input(&raw).filter(|&t| t as f64 > js_sys::Date::now() * 1000.0) {
+                Some(t) => t,
+                None => {
+                    let _ = window.alert_with_message(T::ScheduleInvalid.text());
+                    return;
+                }
+            }
+        };
+        if !window.confirm_with_message(T::CampaignConfirm.text()).unwrap_or(false) {
+            return;
+        }
+
+        let req = Campaign {
+            name: name.get_untracked(),
+            content: text.get_untracked().into_bytes().into(),
+            segment: Some(segment()),
+            send_at_us,
+            ..Default::default()
+        };
+        set_busy.set(true);
+        spawn_local(async move {
+            let result = match admin_request(Method::POST, "/campaigns", &shop.get_value(), &pin.get_value())
+                .header("Content-Type", "application/octet-stream")
+                .body(req.encode_to_vec())
+            {
+                Ok(r) => r.send().await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(resp) if resp.ok() => {
+                    if let Some(created) = resp.binary().await.ok().and_then(|b| Campaign::decode(&b[..]).ok()) {
+                        set_items.update(|list| list.insert(0, created));
+                    }
+                    set_name.set(String::new());
+                    set_text.set(String::new());
+                    set_send_at.set(String::new());
+                }
+                Ok(resp) => set_status.set(T::CampaignFailed.fill(&[&resp.status()])),
+                Err(e) => set_status.set(T::NetworkError.fill(&[&e])),
+            }
+            set_busy.set(false);
+        });
+    };
+
+    let cancel = move |campaign_id: u64| {
+        if !web_sys::window().unwrap().confirm_with_message(T::CampaignCancelConfirm.text()).unwrap_or(false) {
+            return;
+        }
+        spawn_local(async move {
+            let path = format!("/campaigns/{}", campaign_id);
+            match admin_request(Method::DELETE, &path, &shop.get_value(), &pin.get_value()).send().await {
+                Ok(resp) if resp.ok() => refresh(),
+                Ok(resp) => set_status.set(T::CampaignFailed.fill(&[&resp.status()])),
+                Err(e) => set_status.set(T::NetworkError.fill(&[&e])),
+            }
+        });
+    };
+
+    let on_close_click = on_close.clone();
+
+    view! {
+        <div class="settings-panel">
+            <div class="chat-header-bar">
+                <div class="chat-header-info">
+                    <div class="chat-header-name">{T::CampaignsHeader.text()}</div>
+                    <div class="chat-header-status" role="status">{move || status.get()}</div>
+                </div>
+                <button class="settings-close" aria-label=T::Close.text() on:click=move |_| on_close_click()>"✕"</button>
+            </div>
+
+            <div class="settings-body">
+                <div class="settings-section">
+                    <h3>{T::NewCampaign.text()}</h3>
+                    <label class="settings-row">
+                        <span>{T::CampaignName.text()}</span>
+                        <input type="text" maxlength="80" prop:value=name on:input=move |e| set_name.set(event_target_value(&e)) />
+                    </label>
+                    <label class="settings-row settings-column">
+                        <span>{T::CampaignMessage.text()}</span>
+                        <textarea rows="4" prop:value=text on:input=move |e| set_text.set(event_target_value(&e))></textarea>
+                    </label>
+                    <label class="settings-row">
+                        <span>{T::ActiveWithin.text()}</span>
+                        <select on:change=move |e| set_active_days.set(event_target_value(&e).parse().unwrap_or(0))>
+                            {ACTIVE_DAYS.into_iter().map(|days| {
+                                let label = if days == 0 { T::AnyTime.text().to_string() } else { T::LastDays.fill(&[&days]) };
+                                view! { <option value=days.to_string() selected=move || active_days.get() == days>{label}</option> }
+                            }).collect_view()}
+                        </select>
+                    </label>
+                    <label class="settings-row">
+                        <span>{T::CampaignTags.text()}</span>
+                        <input type="text" placeholder="VIP" prop:value=tags on:input=move |e| set_tags.set(event_target_value(&e)) />
+                    </label>
+                    <label class="settings-row">
+                        <span>{T::SendAt.text()}</span>
+                        <input
+                            type="text"
+                            placeholder=T::SendNowHint.text()
+                            prop:value=send_at
+                            on:input=move |e| set_send_at.set(event_target_value(&e))
+                        />
+                    </label>
+                    <div class="settings-row">
+                        <button class="settings-save" on:click=on_preview>{T::CountRecipients.text()}</button>
+                        <span class="settings-hint">
+                            {move || preview.get().map(|n| T::RecipientCount.fill(&[&n]))}
+                        </span>
+                        <button class="settings-save" disabled=busy on:click=on_create>{T::CreateCampaign.text()}</button>
+                    </div>
+                </div>
+
+                <Show when=move || items.with(Vec::is_empty) && status.with(String::is_empty)>
+                    <div class="empty-state">{T::NoCampaigns.text()}</div>
+                </Show>
+                <For
+                    each=move || items.get()
+                    key=|c| (c.campaign_id, c.status, c.sent, c.delivered, c.read)
+                    children=move |campaign: Campaign| {
+                        let campaign_id = campaign.campaign_id;
+                        let state = campaign.status();
+                        let segment = campaign.segment.clone().unwrap_or_default();
+                        let who = match (segment.active_within_days, segment.tags.is_empty()) {
+                            (0, true) => T::AllGuests.text().to_string(),
+                            (0, false) => segment.tags.join(", "),
+                            (days, true) => T::LastDays.fill(&[&days]),
+                            (days, false) => format!("{} · {}", T::LastDays.fill(&[&days]), segment.tags.join(", ")),
+                        };
+                        let counters = T::CampaignCounters.fill(&[&campaign.recipients, &campaign.sent, &campaign.delivered, &campaign.read]);
+                        let cancellable = matches!(state, CampaignStatus::Scheduled | CampaignStatus::Sending);
+                        view! {
+                            <div class="settings-section campaign-item">
+                                <div class="campaign-head">
+                                    <b>{campaign.name.clone()}</b>
+                                    <span class=format!("campaign-status campaign-{}", state.as_str_name().to_lowercase())>{status_label(state)}</span>
+                                </div>
+                                <div class="settings-hint">{format_datetime(campaign.send_at_us)} " · " {who}</div>
+                                <div class="campaign-text">{String::from_utf8_lossy(&campaign.content).into_owned()}</div>
+                                <div class="campaign-counters">{counters}</div>
+                                <Show when=move || cancellable>
+                                    <button class="settings-save" on:click=move |_| cancel(campaign_id)>{T::CancelCampaign.text()}</button>
+                                </Show>
+                            </div>
+                        }
+                    }
+                />
+            </div>
+        </div>
+    }
+}
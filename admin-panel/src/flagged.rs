@@ -0,0 +1,101 @@
+use leptos::prelude::*;
+use turbochat_shared::{format_datetime, FlaggedListResponse, FlaggedMessage, Guest};
+use prost::Message as ProstMessage;
+use wasm_bindgen_futures::spawn_local;
+use gloo_net::http::Method;
+
+use crate::app::admin_request;
+use crate::i18n::T;
+
+// ============================================================================
+// FLAGGED PAGE - Duyệt tin bị bộ lọc spam đánh dấu
+// ============================================================================
+#[component]
+pub fn FlaggedPage(
+    shop_id: String,
+    admin_pin: String,
+    on_open_guest: impl Fn(u64) + 'static + Clone + Send + Sync,
+    on_close: impl Fn() + 'static + Clone,
+) -> impl IntoView {
+    let (items, set_items) = signal(Vec::<FlaggedMessage>::new());
+    let (status, set_status) = signal(String::new());
+
+    let shop = StoredValue::new(shop_id);
+    let pin = StoredValue::new(admin_pin);
+
+    Effect::new(move |_| {
+        spawn_local(async move {
+            let result = admin_request(Method::GET, "/flagged", &shop.get_value(), &pin.get_value())
+                .send()
+                .await;
+            match result {
+                Ok(resp) => {
+                    let bytes = resp.binary().await.unwrap_or_default();
+                    match FlaggedListResponse::decode(&bytes[..]) {
+                        Ok(list) if list.success => set_items.set(list.items),
+                        Ok(list) => set_status.set(T::ApiError.fill(&[&list.error, &list.request_id])),
+                        Err(e) => set_status.set(format!("❌ {}", e)),
+                    }
+                }
+                Err(e) => set_status.set(T::NetworkError.fill(&[&e])),
+            }
+        });
+    });
+
+    let dismiss = move |message_id: u64| {
+        spawn_local(async move {
+            let path = format!("/flagged/{}", message_id);
+            match admin_request(Method::DELETE, &path, &shop.get_value(), &pin.get_value()).send().await {
+                Ok(resp) if resp.ok() => set_items.update(|list| {
+                    list.retain(|f| f.message.as_ref().map(|m| m.message_id) != Some(message_id));
+                }),
+                Ok(resp) => set_status.set(T::HttpError.fill(&[&resp.status()])),
+                Err(e) => set_status.set(T::NetworkError.fill(&[&e])),
+            }
+        });
+    };
+
+    let on_close_click = on_close.clone();
+
+    view! {
+        <div class="settings-panel">
+            <div class="chat-header-bar">
+                <div class="chat-header-info">
+                    <div class="chat-header-name">{T::FlaggedHeader.text()}</div>
+                    <div class="chat-header-status" role="status">{move || status.get()}</div>
+                </div>
+                <button class="settings-close" aria-label=T::Close.text() on:click=move |_| on_close_click()>"✕"</button>
+            </div>
+
+            <div class="settings-body">
+                <Show when=move || items.get().is_empty()>
+                    <div class="empty-state">{T::FlaggedEmpty.text()}</div>
+                </Show>
+                <For
+                    each=move || items.get()
+                    key=|item| item.message.as_ref().map(|m| m.message_id).unwrap_or(0)
+                    children=move |item: FlaggedMessage| {
+                        let msg = item.message.unwrap_or_default();
+                        let message_id = msg.message_id;
+                        let guest_id = msg.guest_id;
+                        let open = on_open_guest.clone();
+                        view! {
+                            <div class="settings-section flagged-item">
+                                <div class="flagged-meta">
+                                    <span>{Guest::fallback_name(guest_id)}</span>
+                                    <span>{format_datetime(msg.timestamp_us)}</span>
+                                </div>
+                                <div class="flagged-text">{String::from_utf8_lossy(&msg.content).to_string()}</div>
+                                <div class="settings-hint">{item.reason.clone()}</div>
+                                <div class="flagged-actions">
+                                    <button class="settings-save" on:click=move |_| open(guest_id)>{T::OpenConversation.text()}</button>
+                                    <button class="block-btn unblock" on:click=move |_| dismiss(message_id)>{T::Approve.text()}</button>
+                                </div>
+                            </div>
+                        }
+                    }
+                />
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,456 @@
+// ============================================================================
+// CHUỖI GIAO DIỆN ADMIN - vi (gốc) + en. Locale: cài đặt admin (localStorage, Cài đặt → Ngôn ngữ giao diện)
+// → ngôn ngữ trình duyệt (navigator.languages) → tiếng Việt.
+// Đổi ngôn ngữ thì tải lại trang (chuỗi không phải signal)
+// ============================================================================
+
+use turbochat_shared::{string_table, Locale};
+
+const LOCALE_KEY: &str = "turbochat_admin_locale";
+
+fn storage() -> Option<web_sys::Storage> {
+    web_sys::window().and_then(|w| w.local_storage().ok().flatten())
+}
+
+/// Locale admin đã chọn, nếu chưa chọn thì theo trình duyệt
+pub fn detect() -> Locale {
+    let mut preferred: Vec<String> = storage().and_then(|s| s.get_item(LOCALE_KEY).ok().flatten()).into_iter().collect();
+    if let Some(navigator) = web_sys::window().map(|w| w.navigator()) {
+        preferred.extend(navigator.languages().iter().filter_map(|l| l.as_string()));
+        preferred.extend(navigator.language());
+    }
+    Locale::negotiate(preferred.iter().map(String::as_str))
+}
+
+/// Lưu lựa chọn của admin rồi tải lại trang
+pub fn choose(locale: Locale) {
+    if let Some(storage) = storage() {
+        let _ = storage.set_item(LOCALE_KEY, locale.code());
+    }
+    if let Some(window) = web_sys::window() {
+        let _ = window.location().reload();
+    }
+}
+
+string_table! {
+    pub enum T {
+        // Trạng thái chung
+        Loading { vi: "Đang tải...", en: "Loading..." }
+        Sending { vi: "Đang gửi...", en: "Sending..." }
+        Saved { vi: "✅ Đã lưu", en: "✅ Saved" }
+        NetworkError { vi: "❌ Lỗi kết nối: {0}", en: "❌ Connection error: {0}" }
+        HttpError { vi: "❌ Lỗi {0}", en: "❌ Error {0}" }
+        ApiError { vi: "❌ {0} (mã tra cứu {1})", en: "❌ {0} (reference {1})" }
+        Close { vi: "Đóng", en: "Close" }
+        Edit { vi: "Sửa", en: "Edit" }
+        Delete { vi: "Xoá", en: "Delete" }
+        Unlimited { vi: "Không giới hạn", en: "Unlimited" }
+        Days7 { vi: "7 ngày", en: "7 days" }
+        Days30 { vi: "30 ngày", en: "30 days" }
+        Days90 { vi: "90 ngày", en: "90 days" }
+
+        // Đăng nhập
+        LoginPasswordRequired { vi: "Shop ID và mật khẩu là bắt buộc", en: "Shop ID and password are required" }
+        LoginPinRequired { vi: "Shop ID và PIN 6 số là bắt buộc", en: "Shop ID and a 6-digit PIN are required" }
+        LoginLocked { vi: "Đăng nhập tạm khoá do nhập sai quá nhiều lần", en: "Sign-in is temporarily locked after too many failed attempts" }
+        WrongPin { vi: "Sai PIN", en: "Wrong PIN" }
+        WrongPassword { vi: "Sai email hoặc mật khẩu", en: "Wrong email or password" }
+        AttemptsLeft { vi: "{0} - còn {1} lần thử trước khi bị khoá tạm thời", en: "{0} - {1} attempts left before a temporary lock" }
+        LoginNetworkError { vi: "Lỗi kết nối: {0}", en: "Connection error: {0}" }
+        ShopIdExample { vi: "Ví dụ: demo123", en: "e.g. demo123" }
+        StaffEmail { vi: "Email nhân viên (để trống nếu dùng PIN chủ shop)", en: "Staff email (leave empty to use the owner PIN)" }
+        StaffEmailExample { vi: "nhanvien@shop.com", en: "staff@shop.com" }
+        PinLabel { vi: "Mã PIN (6 số)", en: "PIN (6 digits)" }
+        Password { vi: "Mật khẩu", en: "Password" }
+        Authenticating { vi: "Đang xác thực...", en: "Signing in..." }
+        SignIn { vi: "Đăng nhập", en: "Sign in" }
+        RetryIn { vi: "Thử lại sau {0}", en: "Try again in {0}" }
+        Back { vi: "← Quay lại", en: "← Back" }
+
+        // Kết nối
+        Connecting { vi: "🔴 Đang kết nối...", en: "🔴 Connecting..." }
+        Connected { vi: "🟢 Đã kết nối", en: "🟢 Connected" }
+        Outdated { vi: "⚠️ Phiên bản cũ - vui lòng tải lại trang", en: "⚠️ Outdated version - please reload the page" }
+        ServerUpdating { vi: "🟡 Máy chủ đang cập nhật, đang kết nối lại…", en: "🟡 Server is updating, reconnecting…" }
+        Reconnecting { vi: "🟡 Đang kết nối lại… (lần {0})", en: "🟡 Reconnecting… (attempt {0})" }
+        SlaBreached { vi: "⏰ Quá hạn phản hồi", en: "⏰ Response overdue" }
+        SlaWarning { vi: "⏱️ Sắp quá hạn phản hồi", en: "⏱️ Response due soon" }
+
+        // Thanh trên + danh sách hội thoại
+        UnreadAllShops { vi: "Tin chưa đọc (mọi shop)", en: "Unread (all shops)" }
+        OpeningHoursStatus { vi: "Trạng thái theo giờ làm việc", en: "Status by business hours" }
+        OutsideHours { vi: " 🌙 Ngoài giờ", en: " 🌙 After hours" }
+        AwayToggleOn { vi: "Đang vắng mặt - bấm để trực tuyến", en: "Away - click to go online" }
+        AwayToggleOff { vi: "Đang trực tuyến - bấm để vắng mặt", en: "Online - click to go away" }
+        FlaggedTitle { vi: "Tin cần duyệt", en: "Messages to review" }
+        LiveTitle { vi: "Khách đang online", en: "Live visitors" }
+        CampaignsTitle { vi: "Chiến dịch", en: "Campaigns" }
+        AnalyticsTitle { vi: "Thống kê", en: "Analytics" }
+        SettingsTitle { vi: "Cài đặt", en: "Settings" }
+        PreferencesTitle { vi: "Âm báo & thông báo", en: "Sound & notifications" }
+        SignOut { vi: "Đăng xuất", en: "Sign out" }
+        FilterConversations { vi: "Lọc hội thoại", en: "Filter conversations" }
+        FilterOpen { vi: "Đang mở", en: "Open" }
+        FilterClosed { vi: "Đã đóng", en: "Closed" }
+        FilterAll { vi: "Tất cả", en: "All" }
+        FilterSnoozed { vi: "💤 {0}", en: "💤 {0}" }
+        FilterSnoozedTitle { vi: "Hội thoại đang tạm ẩn (nhắc lại sau)", en: "Snoozed conversations" }
+        RecentActivity { vi: "Hoạt động gần đây", en: "Recent activity" }
+        AnyTime { vi: "Mọi lúc", en: "Any time" }
+        Hours24 { vi: "24 giờ", en: "24 hours" }
+        ConversationList { vi: "Danh sách hội thoại", en: "Conversations" }
+        NoGuests { vi: "Chưa có khách nào nhắn tin", en: "No guest has written yet" }
+        NoConversations { vi: "Không có hội thoại nào", en: "No conversations" }
+        GuestUnread { vi: "{0}, {1} tin chưa đọc", en: "{0}, {1} unread" }
+        Mood { vi: "Cảm xúc: {0}", en: "Mood: {0}" }
+        Draft { vi: "Nháp: ", en: "Draft: " }
+        LoadMoreConversations { vi: "Tải thêm hội thoại", en: "Load more conversations" }
+        SwitchShop { vi: "Chuyển shop", en: "Switch shop" }
+        AddShop { vi: "+ Thêm shop...", en: "+ Add shop..." }
+
+        // Khung chat
+        PickConversation { vi: "Chọn cuộc trò chuyện", en: "Select a conversation" }
+        CloseConversation { vi: "✓ Đóng", en: "✓ Close" }
+        Reopen { vi: "↺ Mở lại", en: "↺ Reopen" }
+        Archive { vi: "🗄️ Lưu trữ", en: "🗄️ Archive" }
+        GuestInfo { vi: "Thông tin khách", en: "Guest details" }
+        Messages { vi: "Tin nhắn", en: "Messages" }
+        ViewQuoted { vi: "Xem tin được trả lời", en: "Show the quoted message" }
+        SenderYou { vi: "Bạn", en: "You" }
+        SenderGuest { vi: "Khách", en: "Guest" }
+        SenderAdmin { vi: "Admin", en: "Admin" }
+        QuoteDeleted { vi: "Tin nhắn đã bị xoá", en: "Message deleted" }
+        DeletedPrefix { vi: "🚫 Đã xoá: {0}", en: "🚫 Deleted: {0}" }
+        Reply { vi: "Trả lời", en: "Reply" }
+        AutoReply { vi: "Trả lời tự động", en: "Automatic reply" }
+        ShowRevisions { vi: "Xem các bản trước", en: "Show earlier versions" }
+        Edited { vi: "đã sửa", en: "edited" }
+        UploadingTitle { vi: "Đang tải file lên", en: "Uploading file" }
+        Uploading { vi: " ⏫ Đang tải lên", en: " ⏫ Uploading" }
+        SendingTitle { vi: "Đang gửi", en: "Sending" }
+        QuotaQueuedTitle { vi: "Shop đã hết hạn mức, tin sẽ được gửi khi có hạn mức mới", en: "The shop is over quota; the message will be sent when quota frees up" }
+        QuotaQueued { vi: " ⏳ Chờ hạn mức", en: " ⏳ Waiting for quota" }
+        SendFailed { vi: " ⚠️ Gửi lỗi · ", en: " ⚠️ Not sent · " }
+        Retry { vi: "Thử lại", en: "Retry" }
+        EditedBy { vi: "{0} sửa lúc {1}", en: "{0} edited at {1}" }
+        Revisions { vi: "Các bản trước", en: "Earlier versions" }
+        NoRevisions { vi: "Không có bản lưu", en: "No saved versions" }
+        ReplyingTo { vi: "↩️ Trả lời ", en: "↩️ Replying to " }
+        CancelReply { vi: "Huỷ trả lời", en: "Cancel reply" }
+        QuickRepliesPending { vi: "⚡ Lựa chọn nhanh: ", en: "⚡ Quick replies: " }
+        RemoveQuickReplies { vi: "Bỏ lựa chọn nhanh", en: "Remove quick replies" }
+        QuickReplies { vi: "Lựa chọn nhanh", en: "Quick replies" }
+        QuickRepliesPrompt { vi: "Lựa chọn nhanh, cách nhau bởi | (nhãn hoặc nhãn=nội dung gửi lại)", en: "Quick replies separated by | (label or label=text sent back)" }
+        FormatToolbar { vi: "Định dạng tin nhắn", en: "Message formatting" }
+        Bold { vi: "Đậm", en: "Bold" }
+        BoldPlaceholder { vi: "chữ đậm", en: "bold text" }
+        Italic { vi: "Nghiêng", en: "Italic" }
+        ItalicPlaceholder { vi: "chữ nghiêng", en: "italic text" }
+        Link { vi: "Liên kết", en: "Link" }
+        LinkPrompt { vi: "Địa chỉ liên kết", en: "Link address" }
+        LinkPlaceholder { vi: "liên kết", en: "link" }
+        Schedule { vi: "Hẹn giờ gửi", en: "Schedule" }
+        InputLabel { vi: "Nhập tin nhắn", en: "Type a message" }
+        InputPlaceholder { vi: "Nhập tin nhắn...", en: "Type a message..." }
+        Send { vi: "Gửi", en: "Send" }
+        EditPrompt { vi: "Sửa tin nhắn", en: "Edit message" }
+        DeleteConfirm { vi: "Xoá tin nhắn này?", en: "Delete this message?" }
+        AttachmentsDisabled { vi: "Shop chưa bật gửi tệp đính kèm (Cài đặt → Tính năng)", en: "Attachments are off for this shop (Settings → Features)" }
+        UploadFailedFor { vi: "Không gửi được {0}: {1}", en: "Couldn't send {0}: {1}" }
+
+        // Admin khác trong hội thoại (activity.rs)
+        AgentsTyping { vi: "{0} đang gõ…", en: "{0} is typing…" }
+        AgentsViewing { vi: "{0} đang xem", en: "{0} is viewing" }
+        JustReplied { vi: "⚠️ {0} vừa trả lời khách - xem lại trước khi gửi", en: "⚠️ {0} just replied - check before sending" }
+        AnotherAdminReplied { vi: "⚠️ Một admin khác vừa trả lời khách - xem lại trước khi gửi", en: "⚠️ Another admin just replied - check before sending" }
+
+        // Tìm trong hội thoại (search.rs)
+        SearchConversation { vi: "Tìm trong hội thoại", en: "Search this conversation" }
+        SearchPlaceholder { vi: "Tìm tin nhắn...", en: "Search messages..." }
+        SearchOlder { vi: "Kết quả cũ hơn (Enter)", en: "Older match (Enter)" }
+        SearchNewer { vi: "Kết quả mới hơn (Shift+Enter)", en: "Newer match (Shift+Enter)" }
+        SearchCounter { vi: "{0}/{1}", en: "{0} of {1}" }
+        SearchCount { vi: "{0} kết quả", en: "{0} matches" }
+        SearchingOlder { vi: "Đang tìm tin cũ hơn...", en: "Searching older messages..." }
+        SearchNoMore { vi: "Không còn kết quả cũ hơn", en: "No older matches" }
+
+        // Thao tác hàng loạt (bulk.rs)
+        SelectConversation { vi: "Chọn {0} (Shift+click chọn cả dải)", en: "Select {0} (Shift+click selects a range)" }
+        BulkActions { vi: "Thao tác với hội thoại đã chọn", en: "Actions for selected conversations" }
+        SelectAll { vi: "Chọn tất cả hội thoại đang hiện", en: "Select all shown conversations" }
+        SelectedCount { vi: "Đã chọn {0}", en: "{0} selected" }
+        ClearSelection { vi: "Bỏ chọn", en: "Clear selection" }
+        BulkWorking { vi: "Đang cập nhật {0} hội thoại...", en: "Updating {0} conversations..." }
+        BulkFailed { vi: "⚠️ {0} hội thoại chưa cập nhật được (vẫn đang chọn)", en: "⚠️ {0} conversations weren't updated (still selected)" }
+        BulkAddTag { vi: "🏷️ Gắn tag", en: "🏷️ Add tag" }
+        BulkTagPrompt { vi: "Tag cho các hội thoại đã chọn", en: "Tag for the selected conversations" }
+        MarkRead { vi: "✓ Đã đọc", en: "✓ Mark read" }
+        BulkAssign { vi: "Giao cho...", en: "Assign to..." }
+        Unassign { vi: "Bỏ giao", en: "Unassign" }
+
+        // Chuyển hội thoại (transfer.rs)
+        TransferTitle { vi: "Chuyển hội thoại", en: "Transfer conversation" }
+        TransferTo { vi: "Chuyển cho...", en: "Transfer to..." }
+        HandoverPlaceholder { vi: "Ghi chú bàn giao (bắt buộc): khách cần gì, đã làm đến đâu...", en: "Handover note (required): what the guest needs, what's been done..." }
+        Transfer { vi: "↪️ Chuyển", en: "↪️ Transfer" }
+        TransferredBy { vi: "{0} đã chuyển hội thoại cho {1}", en: "{0} transferred the conversation to {1}" }
+        TransferredToYou { vi: "↪️ {0} vừa được chuyển cho bạn", en: "↪️ {0} was transferred to you" }
+
+        // Bot chuyển hội thoại cho nhân viên
+        NeedsHuman { vi: "Đang chờ nhân viên", en: "Waiting for an agent" }
+        HandoffLine { vi: "Bot đã chuyển hội thoại cho nhân viên", en: "The bot handed this conversation to an agent" }
+        HandoffTitle { vi: "🙋 {0} cần nhân viên", en: "🙋 {0} needs an agent" }
+        HandoffFlowEnded { vi: "Kịch bản bot đã kết thúc", en: "The bot flow has ended" }
+        HandoffStep { vi: "Kịch bản bot chuyển cho nhân viên", en: "The bot flow handed off to an agent" }
+        HandoffGuestRequest { vi: "Khách yêu cầu gặp nhân viên", en: "The guest asked for an agent" }
+        HandoffNoMatch { vi: "Bot không hiểu câu trả lời của khách", en: "The bot couldn't understand the guest" }
+
+        // Tab lọc đã lưu (views.rs)
+        SavedViews { vi: "Bộ lọc đã lưu", en: "Saved views" }
+        NewView { vi: "Lưu bộ lọc mới", en: "New saved view" }
+        ViewEditHint { vi: "Nhấp đúp để sửa", en: "Double-click to edit" }
+        ViewEditorTitle { vi: "Bộ lọc hội thoại", en: "Conversation view" }
+        ViewName { vi: "Tên tab (vd. Sales chưa giao)", en: "Tab name (e.g. Unassigned sales)" }
+        ViewNameRequired { vi: "Đặt tên cho bộ lọc", en: "Give the view a name" }
+        ViewTag { vi: "Tag (để trống = mọi tag)", en: "Tag (empty = any tag)" }
+        ViewAssignee { vi: "Người phụ trách", en: "Assignee" }
+        ViewAnyAssignee { vi: "Mọi người phụ trách", en: "Any assignee" }
+        ViewUnassigned { vi: "Chưa giao", en: "Unassigned" }
+        ViewMine { vi: "Giao cho tôi", en: "Assigned to me" }
+        ViewStatus { vi: "Trạng thái", en: "Status" }
+        ViewAnyStatus { vi: "Mọi trạng thái", en: "Any status" }
+        ViewArchived { vi: "Đã lưu trữ", en: "Archived" }
+        ViewUnreadOnly { vi: "Có tin chưa đọc", en: "Has unread messages" }
+        ViewAwaiting { vi: "Khách đang chờ trả lời", en: "Guest awaiting reply" }
+        ViewDeleteConfirm { vi: "Xoá bộ lọc này?", en: "Delete this view?" }
+        SaveView { vi: "Lưu", en: "Save" }
+        Cancel { vi: "Huỷ", en: "Cancel" }
+
+        // Nhắc lại sau (reminders.rs)
+        Snooze { vi: "Tạm ẩn, nhắc lại sau", en: "Snooze" }
+        SnoozeHour { vi: "Nhắc sau 1 giờ", en: "Remind me in 1 hour" }
+        SnoozeHours { vi: "Nhắc sau {0} giờ", en: "Remind me in {0} hours" }
+        SnoozeTomorrow { vi: "9:00 sáng mai", en: "Tomorrow 9:00" }
+        SnoozeCustom { vi: "Chọn giờ...", en: "Pick a time..." }
+        SnoozePrompt { vi: "Nhắc lúc (YYYY-MM-DD HH:MM)", en: "Remind me at (YYYY-MM-DD HH:MM)" }
+        SnoozeFailed { vi: "Không cập nhật được lời nhắc", en: "Couldn't update the reminder" }
+        SnoozedUntil { vi: "⏰ Nhắc lúc {0}", en: "⏰ Until {0}" }
+        Unsnooze { vi: "Bỏ tạm ẩn", en: "Unsnooze" }
+        ReminderDue { vi: "⏰ Nhắc lại: {0}", en: "⏰ Reminder: {0}" }
+        ReminderDueBody { vi: "Hội thoại đã trở lại danh sách", en: "The conversation is back in your list" }
+
+        // Tải file (upload.rs)
+        FileUnreadable { vi: "Không đọc được file", en: "Couldn't read the file" }
+        ServerUnreachable { vi: "Mất kết nối tới máy chủ", en: "Can't reach the server" }
+        InvalidResponse { vi: "Phản hồi không hợp lệ", en: "Invalid server response" }
+        AttachmentsForbidden { vi: "Shop chưa bật gửi tệp đính kèm", en: "Attachments are off for this shop" }
+        FileTooLarge { vi: "File quá lớn", en: "File is too large" }
+        FileTypeUnsupported { vi: "Loại file không được hỗ trợ", en: "File type not supported" }
+        FileInfected { vi: "File có virus ({0}), đã bị chặn", en: "File contains a virus ({0}) and was blocked" }
+        UploadFailed { vi: "Tải lên thất bại (HTTP {0})", en: "Upload failed (HTTP {0})" }
+
+        // Tin hẹn giờ (scheduled.rs)
+        ScheduleEmpty { vi: "Nhập nội dung tin trước khi hẹn giờ", en: "Type the message before scheduling it" }
+        SchedulePrompt { vi: "Gửi lúc (YYYY-MM-DD HH:MM)", en: "Send at (YYYY-MM-DD HH:MM)" }
+        ScheduleInvalid { vi: "Thời điểm không hợp lệ hoặc đã qua", en: "That time is invalid or already past" }
+        ScheduleFailed { vi: "❌ Không hẹn giờ được tin nhắn", en: "❌ Couldn't schedule the message" }
+        ScheduleCancelConfirm { vi: "Huỷ tin hẹn giờ này?", en: "Cancel this scheduled message?" }
+        ScheduleCancel { vi: "Huỷ hẹn giờ", en: "Cancel schedule" }
+
+        // Tin cần duyệt (flagged.rs)
+        FlaggedHeader { vi: "🚩 Tin cần duyệt", en: "🚩 Messages to review" }
+        FlaggedEmpty { vi: "Không có tin nào cần duyệt", en: "Nothing to review" }
+        OpenConversation { vi: "Mở hội thoại", en: "Open conversation" }
+        Approve { vi: "Đã duyệt", en: "Approve" }
+
+        // Khách đang online (live.rs)
+        LiveHeader { vi: "🟢 Đang online", en: "🟢 Live now" }
+        LiveCount { vi: "{0} khách trên website", en: "{0} on the website" }
+        LiveEmpty { vi: "Không có khách nào đang mở website", en: "Nobody is on the website right now" }
+        TimeOnSite { vi: "Thời gian trên trang", en: "Time on site" }
+        MessageFirst { vi: "💬 Nhắn trước", en: "💬 Start chat" }
+
+        // Chiến dịch (campaigns.rs)
+        CampaignsHeader { vi: "📣 Chiến dịch", en: "📣 Campaigns" }
+        NewCampaign { vi: "Chiến dịch mới", en: "New campaign" }
+        CampaignName { vi: "Tên chiến dịch", en: "Campaign name" }
+        CampaignMessage { vi: "Nội dung tin", en: "Message" }
+        ActiveWithin { vi: "Khách hoạt động trong", en: "Guests active in" }
+        LastDays { vi: "{0} ngày qua", en: "last {0} days" }
+        AllGuests { vi: "Mọi khách", en: "All guests" }
+        CampaignTags { vi: "Có tag (phân cách bằng dấu phẩy)", en: "Tagged (comma separated)" }
+        SendAt { vi: "Gửi lúc (YYYY-MM-DD HH:MM)", en: "Send at (YYYY-MM-DD HH:MM)" }
+        SendNowHint { vi: "Để trống = gửi ngay", en: "Leave empty to send now" }
+        CountRecipients { vi: "Đếm khách", en: "Count guests" }
+        RecipientCount { vi: "{0} khách khớp điều kiện", en: "{0} matching guests" }
+        CreateCampaign { vi: "📣 Tạo chiến dịch", en: "📣 Create campaign" }
+        CampaignEmpty { vi: "Nhập tên và nội dung chiến dịch", en: "Enter a name and a message for the campaign" }
+        CampaignConfirm { vi: "Gửi tin này tới mọi khách khớp điều kiện?", en: "Send this message to every matching guest?" }
+        CampaignFailed { vi: "❌ Không lưu được chiến dịch (HTTP {0})", en: "❌ Couldn't save the campaign (HTTP {0})" }
+        CampaignCancelConfirm { vi: "Huỷ chiến dịch này? Khách đã nhận tin vẫn giữ tin.", en: "Cancel this campaign? Guests who already got it keep the message." }
+        CancelCampaign { vi: "Huỷ chiến dịch", en: "Cancel campaign" }
+        NoCampaigns { vi: "Chưa có chiến dịch nào", en: "No campaigns yet" }
+        CampaignScheduled { vi: "Đã hẹn giờ", en: "Scheduled" }
+        CampaignSending { vi: "Đang gửi", en: "Sending" }
+        CampaignSent { vi: "Đã gửi xong", en: "Sent" }
+        CampaignCancelled { vi: "Đã huỷ", en: "Cancelled" }
+        CampaignCounters { vi: "👥 {0} khách · 📤 {1} đã gửi · 📬 {2} đã nhận · 👀 {3} đã xem", en: "👥 {0} guests · 📤 {1} sent · 📬 {2} delivered · 👀 {3} read" }
+
+        // Thống kê (analytics.rs)
+        AnalyticsHeader { vi: "📊 Thống kê", en: "📊 Analytics" }
+        NewConversations { vi: "Cuộc trò chuyện mới", en: "New conversations" }
+        FirstResponse { vi: "Phản hồi đầu tiên (trung vị)", en: "First response (median)" }
+        MessagesPerDay { vi: "Tin nhắn mỗi ngày", en: "Messages per day" }
+        ConversationsPerDay { vi: "Cuộc trò chuyện mới mỗi ngày", en: "New conversations per day" }
+        PeakHours { vi: "Giờ cao điểm (UTC)", en: "Peak hours (UTC)" }
+        OverQuotaQueue { vi: "Vượt hạn mức: tin được giữ lại và gửi khi có hạn mức mới.", en: "Over quota: messages are held and sent when quota frees up." }
+        OverQuotaReject { vi: "Vượt hạn mức: tin bị từ chối.", en: "Over quota: messages are rejected." }
+        MonthlyQuota { vi: "Hạn mức tháng {0}", en: "Quota for {0}" }
+        GuestConnections { vi: "Kết nối khách", en: "Guest connections" }
+        Storage { vi: "Dung lượng", en: "Storage" }
+        QueuedCount { vi: " Đang chờ gửi: {0} tin.", en: " Waiting to send: {0}." }
+        DateRange { vi: "Khoảng thời gian", en: "Date range" }
+        CustomRange { vi: "Tự chọn…", en: "Custom…" }
+        RangeFrom { vi: "Từ ngày", en: "From" }
+        RangeTo { vi: "Đến ngày", en: "To" }
+        InvalidRange { vi: "❌ Khoảng ngày không hợp lệ", en: "❌ Invalid date range" }
+        DownloadCsv { vi: "⬇️ Tải CSV", en: "⬇️ Download CSV" }
+
+        // Thông tin khách (profile.rs)
+        LlmNotConfigured { vi: "❌ Server chưa cấu hình LLM", en: "❌ The server has no LLM configured" }
+        NothingToSummarize { vi: "Chưa có tin nhắn để tóm tắt", en: "No messages to summarize yet" }
+        NotesLoadFailed { vi: "❌ Không tải được ghi chú", en: "❌ Couldn't load notes" }
+        NoteEditPrompt { vi: "Sửa ghi chú", en: "Edit note" }
+        NoteDeleteConfirm { vi: "Xoá ghi chú này?", en: "Delete this note?" }
+        TabInfo { vi: "Thông tin", en: "Details" }
+        TabNotes { vi: "Ghi chú", en: "Notes" }
+        NotesHint { vi: "Chỉ admin thấy ghi chú, khách không bao giờ nhận được.", en: "Notes are visible to admins only and never sent to the guest." }
+        NotePlaceholder { vi: "Thêm ghi chú...", en: "Add a note..." }
+        SaveNote { vi: "Lưu ghi chú", en: "Save note" }
+        NoteEdited { vi: " · đã sửa", en: " · edited" }
+        Account { vi: "Tài khoản", en: "Account" }
+        VerifiedBySite { vi: "Đã xác minh bằng chữ ký của website", en: "Verified by the website's signature" }
+        LastSeen { vi: "Hoạt động gần nhất", en: "Last active" }
+        Viewing { vi: "Đang xem", en: "Viewing" }
+        Referrer { vi: "Đến từ", en: "Came from" }
+        DirectVisit { vi: "Truy cập trực tiếp", en: "Direct visit" }
+        Screen { vi: "Màn hình", en: "Screen" }
+        Assignee { vi: "Phụ trách", en: "Assigned to" }
+        ShopOwner { vi: "Chủ shop", en: "Shop owner" }
+        Location { vi: "Vị trí", en: "Location" }
+        Summary { vi: "Tóm tắt", en: "Summary" }
+        Summarizing { vi: "Đang tóm tắt...", en: "Summarizing..." }
+        Summarize { vi: "✨ Tóm tắt", en: "✨ Summarize" }
+        UpdatedAt { vi: "Cập nhật {0}", en: "Updated {0}" }
+        BlockIp { vi: "Chặn cả IP", en: "Also block the IP" }
+        Unblock { vi: "Bỏ chặn", en: "Unblock" }
+        BlockGuest { vi: "🚫 Chặn khách", en: "🚫 Block guest" }
+
+        // Xác minh danh tính (identity.rs)
+        SecretLoadFailed { vi: "❌ Không tải được secret", en: "❌ Couldn't load the secret" }
+        RotateConfirm { vi: "Tạo secret mới? Chữ ký cũ trên website sẽ hết hiệu lực ngay.", en: "Generate a new secret? Existing signatures on your website stop working immediately." }
+        IdentityTitle { vi: "Xác minh danh tính khách", en: "Guest identity verification" }
+        IdentityHintBefore { vi: "Website tính user_hash = HMAC-SHA256(secret, user_id) ở server rồi gắn vào thẻ script: ", en: "Your website computes user_hash = HMAC-SHA256(secret, user_id) on its server and adds it to the script tag: " }
+        IdentityHintAfter { vi: " - khách đăng nhập được nhận ra trên mọi thiết bị. Không đưa secret vào mã chạy trên trình duyệt.", en: " - signed-in guests are recognized on every device. Never put the secret in code that runs in the browser." }
+        SecretCreatedAt { vi: "Secret (tạo lúc {0})", en: "Secret (created {0})" }
+        RotateSecret { vi: "Tạo secret mới", en: "Generate new secret" }
+        ShowSecret { vi: "Hiện secret", en: "Show secret" }
+
+        // Lời mời nhân viên (invite.rs)
+        DisplayNameRequired { vi: "Vui lòng nhập tên hiển thị", en: "Please enter a display name" }
+        PasswordTooShort { vi: "Mật khẩu tối thiểu {0} ký tự", en: "Password must be at least {0} characters" }
+        PasswordMismatch { vi: "Mật khẩu nhập lại không khớp", en: "Passwords don't match" }
+        InviteInvalid { vi: "Lời mời không hợp lệ", en: "This invitation is not valid" }
+        InviteTitle { vi: "🤝 Lời mời tham gia", en: "🤝 You're invited" }
+        DisplayName { vi: "Tên hiển thị", en: "Display name" }
+        DisplayNameExample { vi: "Ví dụ: Lan - CSKH", en: "e.g. Lan - Support" }
+        PasswordMin { vi: "Mật khẩu (tối thiểu {0} ký tự)", en: "Password (at least {0} characters)" }
+        PasswordRepeat { vi: "Nhập lại mật khẩu", en: "Repeat password" }
+        CreatingAccount { vi: "Đang tạo tài khoản...", en: "Creating account..." }
+        Join { vi: "Tham gia", en: "Join" }
+        Skip { vi: "← Bỏ qua", en: "← Skip" }
+        InviteSent { vi: "✅ Đã gửi lời mời", en: "✅ Invitation sent" }
+        InviteFailed { vi: "❌ Không gửi được lời mời", en: "❌ Couldn't send the invitation" }
+        InviteStaff { vi: "Mời nhân viên", en: "Invite staff" }
+        InviteEmail { vi: "Email nhân viên (link mời có hạn, dùng được một lần)", en: "Staff email (the invite link expires and works once)" }
+        SendInvite { vi: "Gửi lời mời", en: "Send invitation" }
+        InviteLink { vi: "Link mời (hết hạn {0})", en: "Invite link (expires {0})" }
+
+        // Tuỳ chọn thông báo của admin (preferences.rs)
+        PreferencesHeader { vi: "🔔 Âm báo & thông báo", en: "🔔 Sound & notifications" }
+        PreferencesHint {
+            vi: "Chỉ áp dụng cho tài khoản của bạn, trên mọi trình duyệt bạn đăng nhập.",
+            en: "Applies to your account only, on every browser you sign in to.",
+        }
+        SoundAlerts { vi: "Phát âm báo khi khách nhắn", en: "Play a sound when a guest writes" }
+        DesktopAlerts { vi: "Hiện thông báo trên màn hình", en: "Show desktop notifications" }
+        DesktopBlocked {
+            vi: "Trình duyệt đang chặn thông báo - hãy cho phép trong cài đặt trang web.",
+            en: "Notifications are blocked by the browser - allow them in the site settings.",
+        }
+        NotifyScopeLabel { vi: "Báo cho hội thoại", en: "Notify me about" }
+        ScopeAll { vi: "Mọi hội thoại", en: "All conversations" }
+        ScopeAssigned { vi: "Chỉ hội thoại giao cho tôi", en: "Only conversations assigned to me" }
+        QuietHours { vi: "Giờ yên lặng", en: "Quiet hours" }
+        QuietHoursEnabled { vi: "Không báo trong khoảng giờ này (giờ máy bạn)", en: "Stay silent during these hours (your local time)" }
+        TestAlert { vi: "Thử âm báo", en: "Test alert" }
+        TestAlertBody { vi: "Thông báo sẽ hiện như thế này", en: "This is how alerts will look" }
+
+        // Cài đặt shop (settings.rs)
+        Sunday { vi: "Chủ nhật", en: "Sunday" }
+        Monday { vi: "Thứ hai", en: "Monday" }
+        Tuesday { vi: "Thứ ba", en: "Tuesday" }
+        Wednesday { vi: "Thứ tư", en: "Wednesday" }
+        Thursday { vi: "Thứ năm", en: "Thursday" }
+        Friday { vi: "Thứ sáu", en: "Friday" }
+        Saturday { vi: "Thứ bảy", en: "Saturday" }
+        FlagE2ee { vi: "Mã hoá đầu cuối (E2EE)", en: "End-to-end encryption (E2EE)" }
+        FlagAiReply { vi: "Trả lời bằng AI", en: "AI replies" }
+        FlagAttachments { vi: "Gửi tệp đính kèm", en: "File attachments" }
+        FlagTranslations { vi: "Dịch tin nhắn", en: "Message translation" }
+        FlagVoiceNotes { vi: "Tin nhắn thoại", en: "Voice messages" }
+        FlagsSaved { vi: "✅ Đã cập nhật tính năng", en: "✅ Features updated" }
+        SettingsHeader { vi: "⚙️ Cài đặt shop", en: "⚙️ Shop settings" }
+        Retention { vi: "Lưu trữ tin nhắn", en: "Message retention" }
+        RetentionDays { vi: "Giữ tin nhắn (ngày, 0 = vĩnh viễn)", en: "Keep messages (days, 0 = forever)" }
+        ArchiveInsteadOfDelete { vi: "Lưu trữ (archive) thay vì xoá", en: "Archive instead of deleting" }
+        LastSweep { vi: "Lần quét gần nhất {0}: xoá {1} tin, lưu trữ {2} tin", en: "Last sweep {0}: deleted {1}, archived {2}" }
+        NoSweep { vi: "Chưa có lần quét nào", en: "No sweep has run yet" }
+        SpamFilter { vi: "Lọc spam", en: "Spam filter" }
+        BannedWords { vi: "Từ cấm (mỗi dòng một từ)", en: "Banned words (one per line)" }
+        OnBannedWord { vi: "Khi gặp từ cấm", en: "When a banned word appears" }
+        ActionMask { vi: "Che bằng ***", en: "Mask with ***" }
+        ActionReject { vi: "Chặn tin", en: "Reject the message" }
+        ActionFlag { vi: "Đánh dấu để duyệt", en: "Flag for review" }
+        MaxLinks { vi: "Số link tối đa mỗi phút (0 = không giới hạn)", en: "Max links per minute (0 = unlimited)" }
+        MaxDuplicates { vi: "Số tin trùng lặp liên tiếp tối đa (0 = tắt)", en: "Max consecutive duplicate messages (0 = off)" }
+        BusinessHours { vi: "Giờ làm việc", en: "Business hours" }
+        BusinessHoursEnabled { vi: "Bật giờ làm việc (ngoài giờ = để lại lời nhắn)", en: "Use business hours (after hours = leave a message)" }
+        Timezone { vi: "Múi giờ", en: "Time zone" }
+        OfflineMessage { vi: "Lời nhắn ngoài giờ", en: "After-hours message" }
+        OfflineMessagePlaceholder { vi: "Chúng tôi hiện không trực tuyến. Hãy để lại lời nhắn...", en: "We're offline right now. Leave us a message..." }
+        OfflineEmail { vi: "Email nhận thông báo tin ngoài giờ", en: "Email for after-hours message alerts" }
+        ResponseSla { vi: "SLA phản hồi", en: "Response SLA" }
+        FirstResponseMinutes { vi: "Phản hồi đầu trong (phút, 0 = tắt)", en: "First response within (minutes, 0 = off)" }
+        SlaEmail { vi: "Email khi quá hạn (tới email nhận thông báo)", en: "Email when overdue (to the alert address)" }
+        Features { vi: "Tính năng", en: "Features" }
+        VoiceMaxSecs { vi: "Tin thoại dài tối đa (giây, tối đa {0})", en: "Max voice message length (seconds, up to {0})" }
+        WidgetAppearance { vi: "Giao diện widget", en: "Widget appearance" }
+        PrimaryColor { vi: "Màu chủ đạo", en: "Primary color" }
+        Position { vi: "Vị trí", en: "Position" }
+        PositionRight { vi: "Góc phải", en: "Bottom right" }
+        PositionLeft { vi: "Góc trái", en: "Bottom left" }
+        WidgetTitle { vi: "Tiêu đề", en: "Title" }
+        WidgetTitlePlaceholder { vi: "Chat với chúng tôi", en: "Chat with us" }
+        EmbedCode { vi: "Mã nhúng (dán vào website)", en: "Embed code (paste into your website)" }
+        AllowedOrigins { vi: "Website được nhúng widget (mỗi dòng một tên miền, vd. shop.com hoặc *.shop.com; để trống = mọi website)", en: "Websites allowed to embed the widget (one domain per line, e.g. shop.com or *.shop.com; empty = any website)" }
+        SaveSettings { vi: "Lưu cài đặt", en: "Save settings" }
+        ThisBrowser { vi: "Trên trình duyệt này", en: "On this browser" }
+        InterfaceLanguage { vi: "Ngôn ngữ giao diện", en: "Interface language" }
+        ColorSchemeLabel { vi: "Giao diện sáng/tối", en: "Light/dark appearance" }
+        SchemeAuto { vi: "Theo hệ thống", en: "Match system" }
+        SchemeLight { vi: "Sáng", en: "Light" }
+        SchemeDark { vi: "Tối", en: "Dark" }
+        WidgetColorScheme { vi: "Sáng/tối mặc định (khách vẫn tự đổi được)", en: "Default light/dark (guests can still switch)" }
+    }
+}
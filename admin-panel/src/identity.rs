@@ -0,0 +1,77 @@
+use leptos::prelude::*;
+use turbochat_shared::{format_datetime, IdentitySecretResponse};
+use prost::Message as ProstMessage;
+use wasm_bindgen_futures::spawn_local;
+use gloo_net::http::Method;
+
+use crate::app::admin_request;
+use crate::i18n::T;
+
+// ============================================================================
+// XÁC MINH DANH TÍNH - secret để website ký user_id (gắn khách với tài khoản đăng nhập)
+// Chỉ chủ shop thấy; secret chỉ tải khi bấm "Hiện" (không nằm sẵn trên màn hình)
+// ============================================================================
+#[component]
+pub fn IdentitySection(shop_id: String, admin_pin: String) -> impl IntoView {
+    let (secret, set_secret) = signal(None::<IdentitySecretResponse>);
+    let (status, set_status) = signal(String::new());
+
+    let shop = StoredValue::new(shop_id);
+    let pin = StoredValue::new(admin_pin);
+
+    let load = move |method: Method, path: &'static str| {
+        set_status.set(T::Loading.text().to_string());
+        spawn_local(async move {
+            let resp = match admin_request(method, path, &shop.get_value(), &pin.get_value()).send().await {
+                Ok(resp) => resp.binary().await.ok().and_then(|bytes| IdentitySecretResponse::decode(&bytes[..]).ok()),
+                Err(e) => {
+                    set_status.set(T::NetworkError.fill(&[&e]));
+                    return;
+                }
+            };
+            match resp {
+                Some(resp) if resp.success => {
+                    set_status.set(String::new());
+                    set_secret.set(Some(resp));
+                }
+                Some(resp) => set_status.set(T::ApiError.fill(&[&resp.error, &resp.request_id])),
+                None => set_status.set(T::SecretLoadFailed.text().to_string()),
+            }
+        });
+    };
+
+    let rotate = move |_| {
+        let confirmed = web_sys::window()
+            .and_then(|w| w.confirm_with_message(T::RotateConfirm.text()).ok())
+            .unwrap_or(false);
+        if confirmed {
+            load(Method::POST, "/identity-secret/rotate");
+        }
+    };
+
+    view! {
+        <div class="settings-section">
+            <h3>{T::IdentityTitle.text()}</h3>
+            <div class="settings-hint">
+                {T::IdentityHintBefore.text()}
+                <code>"data-user-id=\"42\" data-user-email=\"...\" data-user-hash=\"...\""</code>
+                {T::IdentityHintAfter.text()}
+            </div>
+            {move || match secret.get() {
+                Some(resp) => view! {
+                    <label class="settings-row settings-column">
+                        <span>{T::SecretCreatedAt.fill(&[&format_datetime(resp.rotated_at)])}</span>
+                        <input type="text" readonly=true class="settings-wide" prop:value=resp.secret/>
+                    </label>
+                    <button class="settings-save" on:click=rotate>{T::RotateSecret.text()}</button>
+                }.into_any(),
+                None => view! {
+                    <button class="settings-save" on:click=move |_| load(Method::GET, "/identity-secret")>{T::ShowSecret.text()}</button>
+                }.into_any(),
+            }}
+            <Show when=move || !status.get().is_empty()>
+                <div class="settings-hint">{move || status.get()}</div>
+            </Show>
+        </div>
+    }
+}
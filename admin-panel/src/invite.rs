@@ -0,0 +1,239 @@
+use leptos::prelude::*;
+use turbochat_shared::{format_datetime, AdminAcceptRequest, AdminAuthResponse, AdminInviteRequest, AdminInviteResponse};
+use prost::Message as ProstMessage;
+use wasm_bindgen_futures::spawn_local;
+use gloo_net::http::{Method, Request};
+
+use crate::app::{admin_request, API_BASE};
+use crate::shops::AdminSession;
+use crate::i18n::T;
+
+const MIN_PASSWORD_CHARS: usize = 8;
+
+/// Phiên đăng nhập bằng tài khoản nhân viên (session token) thay vì PIN chủ shop
+pub(crate) fn is_agent_session(admin_pin: &str) -> bool {
+    admin_pin.starts_with("agt.")
+}
+
+/// Link mời mở admin với ?shop=&invite= → (shop_id, token)
+pub(crate) fn pending_invite() -> Option<(String, String)> {
+    let search = web_sys::window()?.location().search().ok()?;
+    let params = web_sys::UrlSearchParams::new_with_str(&search).ok()?;
+    Some((params.get("shop")?, params.get("invite")?)).filter(|(shop, token)| !shop.is_empty() && !token.is_empty())
+}
+
+// Bỏ ?shop=&invite= khỏi thanh địa chỉ (reload không mở lại màn nhận lời mời)
+fn clear_invite_query() {
+    let window = web_sys::window().unwrap();
+    let path = window.location().pathname().unwrap_or_default();
+    if let Ok(history) = window.history() {
+        let _ = history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&path));
+    }
+}
+
+// ============================================================================
+// NHẬN LỜI MỜI - nhân viên đặt tên + mật khẩu, xong là đăng nhập luôn
+// ============================================================================
+#[component]
+pub fn AcceptInvitePage(
+    shop_id: String,
+    token: String,
+    on_accepted: impl Fn(AdminSession) + 'static + Clone + Send + Sync,
+    on_cancel: impl Fn() + 'static + Clone + Send + Sync,
+) -> impl IntoView {
+    let (name, set_name) = signal(String::new());
+    let (password, set_password) = signal(String::new());
+    let (confirm, set_confirm) = signal(String::new());
+    let (error, set_error) = signal(String::new());
+    let (is_loading, set_is_loading) = signal(false);
+
+    let shop = StoredValue::new(shop_id);
+    let token = StoredValue::new(token);
+
+    let submit = move || {
+        let name = name.get_untracked().trim().to_string();
+        let password = password.get_untracked();
+        if name.is_empty() {
+            set_error.set(T::DisplayNameRequired.text().to_string());
+            return;
+        }
+        if password.chars().count() < MIN_PASSWORD_CHARS {
+            set_error.set(T::PasswordTooShort.fill(&[&MIN_PASSWORD_CHARS]));
+            return;
+        }
+        if password != confirm.get_untracked() {
+            set_error.set(T::PasswordMismatch.text().to_string());
+            return;
+        }
+
+        set_is_loading.set(true);
+        set_error.set(String::new());
+        let on_accepted = on_accepted.clone();
+        spawn_local(async move {
+            let req = AdminAcceptRequest { shop_id: shop.get_value(), token: token.get_value(), name, password };
+            let result = match Request::post(&format!("{}/admins/accept", API_BASE))
+                .header("Content-Type", "application/octet-stream")
+                .body(req.encode_to_vec())
+            {
+                Ok(r) => r.send().await,
+                Err(e) => Err(e),
+            };
+            set_is_loading.set(false);
+            let resp = match result {
+                Ok(resp) => resp.binary().await.ok().and_then(|bytes| AdminAuthResponse::decode(&bytes[..]).ok()),
+                Err(e) => {
+                    set_error.set(T::LoginNetworkError.fill(&[&e]));
+                    return;
+                }
+            };
+            match resp {
+                Some(resp) if resp.success => {
+                    clear_invite_query();
+                    on_accepted(AdminSession {
+                        shop_id: shop.get_value(),
+                        shop_name: resp.shop_name,
+                        admin_pin: resp.session_token,
+                    });
+                }
+                Some(resp) => set_error.set(T::ApiError.fill(&[&resp.error, &resp.request_id])),
+                None => set_error.set(T::InviteInvalid.text().to_string()),
+            }
+        });
+    };
+    let submit_click = submit.clone();
+
+    view! {
+        <style>{include_str!("../login_style.css")}</style>
+
+        <div class="login-container">
+            <div class="login-box">
+                <div class="login-header">
+                    <h1>{T::InviteTitle.text()}</h1>
+                    <p>{move || format!("Shop: {}", shop.get_value())}</p>
+                </div>
+
+                <div class="login-form">
+                    <div class="input-group">
+                        <label>{T::DisplayName.text()}</label>
+                        <input
+                            type="text"
+                            placeholder=T::DisplayNameExample.text()
+                            prop:value=move || name.get()
+                            on:input=move |e| set_name.set(event_target_value(&e))
+                        />
+                    </div>
+                    <div class="input-group">
+                        <label>{T::PasswordMin.fill(&[&MIN_PASSWORD_CHARS])}</label>
+                        <input
+                            type="password"
+                            prop:value=move || password.get()
+                            on:input=move |e| set_password.set(event_target_value(&e))
+                        />
+                    </div>
+                    <div class="input-group">
+                        <label>{T::PasswordRepeat.text()}</label>
+                        <input
+                            type="password"
+                            prop:value=move || confirm.get()
+                            on:input=move |e| set_confirm.set(event_target_value(&e))
+                            on:keypress={
+                                let submit = submit.clone();
+                                move |e: web_sys::KeyboardEvent| {
+                                    if e.key() == "Enter" { submit(); }
+                                }
+                            }
+                        />
+                    </div>
+
+                    <Show when=move || !error.get().is_empty()>
+                        <div class="error-message">{move || error.get()}</div>
+                    </Show>
+
+                    <button class="login-btn" disabled=move || is_loading.get() on:click=move |_| submit_click()>
+                        {move || if is_loading.get() { T::CreatingAccount.text() } else { T::Join.text() }}
+                    </button>
+                    <button class="login-cancel" on:click={
+                        let cancel = on_cancel.clone();
+                        move |_| {
+                            clear_invite_query();
+                            cancel();
+                        }
+                    }>{T::Skip.text()}</button>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+// ============================================================================
+// MỜI NHÂN VIÊN - mục trong Cài đặt, chỉ chủ shop (đăng nhập bằng PIN) thấy
+// ============================================================================
+#[component]
+pub fn InviteSection(shop_id: String, admin_pin: String) -> impl IntoView {
+    let (email, set_email) = signal(String::new());
+    let (status, set_status) = signal(String::new());
+    // Link vừa tạo - chủ shop có thể tự gửi nếu email không tới
+    let (invite, set_invite) = signal(None::<AdminInviteResponse>);
+
+    let shop = StoredValue::new(shop_id);
+    let pin = StoredValue::new(admin_pin);
+
+    let send = move |_| {
+        let email = email.get_untracked().trim().to_string();
+        if email.is_empty() {
+            return;
+        }
+        set_status.set(T::Sending.text().to_string());
+        spawn_local(async move {
+            let req = AdminInviteRequest { email };
+            let result = match admin_request(Method::POST, "/admins/invite", &shop.get_value(), &pin.get_value())
+                .header("Content-Type", "application/octet-stream")
+                .body(req.encode_to_vec())
+            {
+                Ok(r) => r.send().await,
+                Err(e) => Err(e),
+            };
+            let resp = match result {
+                Ok(resp) => resp.binary().await.ok().and_then(|bytes| AdminInviteResponse::decode(&bytes[..]).ok()),
+                Err(e) => {
+                    set_status.set(T::NetworkError.fill(&[&e]));
+                    return;
+                }
+            };
+            match resp {
+                Some(resp) if resp.success => {
+                    set_status.set(T::InviteSent.text().to_string());
+                    set_email.set(String::new());
+                    set_invite.set(Some(resp));
+                }
+                Some(resp) => set_status.set(T::ApiError.fill(&[&resp.error, &resp.request_id])),
+                None => set_status.set(T::InviteFailed.text().to_string()),
+            }
+        });
+    };
+
+    view! {
+        <div class="settings-section">
+            <h3>{T::InviteStaff.text()}</h3>
+            <label class="settings-row settings-column">
+                <span>{T::InviteEmail.text()}</span>
+                <input
+                    type="email"
+                    placeholder="nhanvien@shop.com"
+                    prop:value=move || email.get()
+                    on:input=move |e| set_email.set(event_target_value(&e))
+                />
+            </label>
+            <button class="settings-save" on:click=send>{T::SendInvite.text()}</button>
+            <Show when=move || !status.get().is_empty()>
+                <div class="settings-hint">{move || status.get()}</div>
+            </Show>
+            {move || invite.get().map(|resp| view! {
+                <label class="settings-row settings-column">
+                    <span>{T::InviteLink.fill(&[&format_datetime(resp.expires_at)])}</span>
+                    <input type="text" readonly=true class="settings-wide" prop:value=resp.invite_url/>
+                </label>
+            })}
+        </div>
+    }
+}
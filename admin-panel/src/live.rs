@@ -0,0 +1,124 @@
+use leptos::prelude::*;
+use turbochat_shared::{Guest, LiveVisitor, LiveVisitorList};
+use prost::Message as ProstMessage;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+use gloo_net::http::Method;
+
+use crate::analytics::format_duration;
+use crate::app::admin_request;
+use crate::i18n::T;
+
+// Tải lại danh sách (khách vào / rời / chuyển trang) khi trang đang mở
+const REFRESH_MS: i32 = 10_000;
+
+// ============================================================================
+// LIVE PAGE - Khách đang ở trên website (GET /live): trang đang xem, thời gian trên trang,
+// nhắn trước cho khách chưa bắt chuyện
+// ============================================================================
+#[component]
+pub fn LivePage(
+    shop_id: String,
+    admin_pin: String,
+    on_open_guest: impl Fn(Guest) + 'static + Clone + Send + Sync,
+    on_close: impl Fn() + 'static + Clone,
+) -> impl IntoView {
+    let (visitors, set_visitors) = signal(Vec::<LiveVisitor>::new());
+    let (status, set_status) = signal(T::Loading.text().to_string());
+    let now_ms = RwSignal::new(js_sys::Date::now());
+
+    let shop = StoredValue::new(shop_id);
+    let pin = StoredValue::new(admin_pin);
+
+    let refresh = move || {
+        spawn_local(async move {
+            let result = admin_request(Method::GET, "/live", &shop.get_value(), &pin.get_value()).send().await;
+            match result {
+                Ok(resp) => {
+                    let bytes = resp.binary().await.unwrap_or_default();
+                    match LiveVisitorList::decode(&bytes[..]) {
+                        Ok(list) if list.success => {
+                            set_visitors.set(list.visitors);
+                            set_status.set(String::new());
+                        }
+                        Ok(list) => set_status.set(T::ApiError.fill(&[&list.error, &list.request_id])),
+                        Err(e) => set_status.set(format!("❌ {}", e)),
+                    }
+                }
+                Err(e) => set_status.set(T::NetworkError.fill(&[&e])),
+            }
+            now_ms.set(js_sys::Date::now());
+        });
+    };
+    refresh();
+
+    let tick = Closure::wrap(Box::new(refresh) as Box<dyn FnMut()>);
+    let timer = web_sys::window().unwrap()
+        .set_interval_with_callback_and_timeout_and_arguments_0(tick.as_ref().unchecked_ref(), REFRESH_MS)
+        .ok();
+    tick.forget();
+    on_cleanup(move || {
+        if let Some(handle) = timer {
+            web_sys::window().unwrap().clear_interval_with_handle(handle);
+        }
+    });
+
+    let on_close_click = on_close.clone();
+
+    view! {
+        <div class="settings-panel">
+            <div class="chat-header-bar">
+                <div class="chat-header-info">
+                    <div class="chat-header-name">{T::LiveHeader.text()}</div>
+                    <div class="chat-header-status" role="status">
+                        {move || {
+                            let text = status.get();
+                            if text.is_empty() { T::LiveCount.fill(&[&visitors.with(Vec::len)]) } else { text }
+                        }}
+                    </div>
+                </div>
+                <button class="settings-close" aria-label=T::Close.text() on:click=move |_| on_close_click()>"✕"</button>
+            </div>
+
+            <div class="settings-body">
+                <Show when=move || visitors.with(Vec::is_empty)>
+                    <div class="empty-state">{T::LiveEmpty.text()}</div>
+                </Show>
+                <For
+                    each=move || visitors.get()
+                    key=|v| v.guest.as_ref().map(|g| (g.guest_id, g.context.as_ref().map(|c| c.page_url.clone())))
+                    children=move |visitor: LiveVisitor| {
+                        let guest = visitor.guest.unwrap_or_default();
+                        let context = guest.context.clone().unwrap_or_default();
+                        let page = (!context.page_url.is_empty()).then(|| {
+                            let url = context.page_url.clone();
+                            view! {
+                                <a class="profile-link" href=url.clone() target="_blank" rel="noopener" title=url>{context.page_path().to_string()}</a>
+                            }
+                        });
+                        let place = guest.geo.as_ref().map(|geo| format!("{} {}", geo.flag(), geo.label()));
+                        let since_us = visitor.connected_at_us;
+                        let on_site = move || match since_us {
+                            0 => "—".to_string(),
+                            us => format_duration((now_ms.get() - (us / 1000) as f64).max(0.0) as u64),
+                        };
+                        // Đã có hội thoại → mở lại, chưa có → admin bắt chuyện trước
+                        let action = if guest.last_message_at > 0 { T::OpenConversation.text() } else { T::MessageFirst.text() };
+                        let open = on_open_guest.clone();
+                        view! {
+                            <div class="settings-section live-item">
+                                <div class="live-who">
+                                    <b>{guest.display_name()}</b>
+                                    {place.map(|p| view! { <span class="settings-hint">{p}</span> })}
+                                </div>
+                                <div class="live-page">{page}</div>
+                                <div class="live-time" title=T::TimeOnSite.text()>"⏱ " {on_site}</div>
+                                <button class="settings-save" on:click=move |_| open(guest.clone())>{action}</button>
+                            </div>
+                        }
+                    }
+                />
+            </div>
+        </div>
+    }
+}
@@ -1,8 +1,35 @@
+mod activity;
+mod analytics;
 mod app;
+mod bulk;
+mod campaigns;
+mod flagged;
+mod i18n;
+mod identity;
+mod invite;
+mod live;
+mod notify;
+mod preferences;
+mod profile;
+mod reminders;
+mod scheduled;
+mod search;
+mod shops;
+mod settings;
+mod transfer;
+mod upload;
+mod views;
 
 use leptos::prelude::*;
 
 fn main() {
     console_error_panic_hook::set_once();
+    let locale = i18n::detect();
+    turbochat_shared::i18n::set_locale(locale);
+    if let Some(html) = web_sys::window().and_then(|w| w.document()).and_then(|d| d.document_element()) {
+        let _ = html.set_attribute("lang", locale.code());
+        // ar/he: cả trang lật phải-trái (CSS dùng thuộc tính logic)
+        let _ = html.set_attribute("dir", locale.direction().attr());
+    }
     mount_to_body(app::App)
 }
\ No newline at end of file
@@ -0,0 +1,120 @@
+// ============================================================================
+// THÔNG BÁO - Âm báo + Notification của trình duyệt khi khách nhắn
+// Tắt khi admin "Vắng mặt"; bật/tắt, phạm vi, giờ yên lặng theo AdminPreferences (trang Tuỳ chọn).
+// AudioContext chỉ được tạo/resume sau thao tác của admin (unlock_audio gọi trong on:click)
+// ============================================================================
+use std::cell::RefCell;
+use turbochat_shared::{local_minute, now_us, AdminPreferences};
+use wasm_bindgen::prelude::*;
+use web_sys::{AudioContext, AudioContextState, Notification, NotificationOptions, NotificationPermission, OscillatorType};
+
+thread_local! {
+    static AUDIO: RefCell<Option<AudioContext>> = const { RefCell::new(None) };
+}
+
+/// Cách báo cho một sự kiện sau khi xét tuỳ chọn của admin
+#[derive(Clone, Copy, Default)]
+pub struct Alert {
+    pub sound: bool,
+    pub desktop: bool,
+}
+
+impl Alert {
+    /// Hội thoại giao cho `assigned_to`; giờ yên lặng / ngoài phạm vi → không báo gì
+    pub fn for_conversation(prefs: &AdminPreferences, assigned_to: &str) -> Self {
+        if !prefs.covers(assigned_to) || prefs.is_quiet_at(local_minute(now_us())) {
+            return Self::default();
+        }
+        Self { sound: !prefs.sound_off, desktop: !prefs.desktop_off }
+    }
+
+    /// Báo ưu tiên (bot chuyển cho nhân viên): bỏ qua phạm vi và giờ yên lặng, chỉ theo bật/tắt
+    pub fn priority(prefs: &AdminPreferences) -> Self {
+        Self { sound: !prefs.sound_off, desktop: !prefs.desktop_off }
+    }
+}
+
+/// Gọi trong handler click để lần sau được phép phát âm thanh
+pub fn unlock_audio() {
+    AUDIO.with(|audio| {
+        let mut audio = audio.borrow_mut();
+        if audio.is_none() {
+            *audio = AudioContext::new().ok();
+        }
+        if let Some(ctx) = audio.as_ref() {
+            if ctx.state() == AudioContextState::Suspended {
+                let _ = ctx.resume();
+            }
+        }
+    });
+}
+
+/// Tiếng "ping" ngắn - bỏ qua nếu trình duyệt chưa cho phát
+pub fn play_ping() {
+    AUDIO.with(|audio| {
+        let audio = audio.borrow();
+        let Some(ctx) = audio.as_ref().filter(|ctx| ctx.state() == AudioContextState::Running) else { return };
+        let (Ok(osc), Ok(gain)) = (ctx.create_oscillator(), ctx.create_gain()) else { return };
+        let now = ctx.current_time();
+        osc.set_type(OscillatorType::Sine);
+        osc.frequency().set_value(660.0);
+        let _ = gain.gain().set_value_at_time(0.15, now);
+        let _ = gain.gain().exponential_ramp_to_value_at_time(0.001, now + 0.3);
+        let _ = osc.connect_with_audio_node(&gain);
+        let _ = gain.connect_with_audio_node(&ctx.destination());
+        let _ = osc.start();
+        let _ = osc.stop_with_when(now + 0.3);
+    });
+}
+
+fn notifications_supported() -> bool {
+    let window = web_sys::window().unwrap();
+    js_sys::Reflect::has(&window, &"Notification".into()).unwrap_or(false)
+}
+
+/// Xin quyền Notification (chỉ hỏi khi chưa chọn) - gọi trong handler click
+pub fn request_permission() {
+    if notifications_supported() && Notification::permission() == NotificationPermission::Default {
+        let _ = Notification::request_permission();
+    }
+}
+
+/// Admin đã chặn Notification cho trang này
+pub fn permission_denied() -> bool {
+    notifications_supported() && Notification::permission() == NotificationPermission::Denied
+}
+
+/// Hiện Notification nếu đã được cấp quyền; click → focus tab và gọi on_click
+pub fn show_notification(title: &str, body: &str, tag: &str, on_click: impl Fn() + 'static) {
+    notify(title, body, tag, false, on_click);
+}
+
+/// Như show_notification nhưng giữ trên màn hình tới khi admin bấm / đóng
+pub fn show_priority_notification(title: &str, body: &str, tag: &str, on_click: impl Fn() + 'static) {
+    notify(title, body, tag, true, on_click);
+}
+
+fn notify(title: &str, body: &str, tag: &str, require_interaction: bool, on_click: impl Fn() + 'static) {
+    if !notifications_supported() || Notification::permission() != NotificationPermission::Granted {
+        return;
+    }
+    let options = NotificationOptions::new();
+    options.set_body(body);
+    options.set_tag(tag);
+    options.set_require_interaction(require_interaction);
+    let Ok(notification) = Notification::new_with_options(title, &options) else { return };
+
+    let notification_clone = notification.clone();
+    let onclick = Closure::wrap(Box::new(move || {
+        let _ = web_sys::window().unwrap().focus();
+        on_click();
+        notification_clone.close();
+    }) as Box<dyn FnMut()>);
+    notification.set_onclick(Some(onclick.as_ref().unchecked_ref()));
+    onclick.forget();
+}
+
+/// Tab admin đang bị ẩn (chuyển tab / thu nhỏ)
+pub fn document_hidden() -> bool {
+    web_sys::window().and_then(|w| w.document()).is_some_and(|d| d.hidden())
+}
@@ -0,0 +1,172 @@
+use leptos::prelude::*;
+use turbochat_shared::{AdminPreferences, NotifyScope};
+use prost::Message as ProstMessage;
+use wasm_bindgen_futures::spawn_local;
+use gloo_net::http::Method;
+
+use crate::app::admin_request;
+use crate::i18n::T;
+use crate::notify;
+use crate::settings::{format_minute, parse_minute};
+
+/// GET /preferences - tuỳ chọn của admin đang đăng nhập (lỗi → giữ mặc định, không chặn dashboard)
+pub(crate) async fn load_preferences(shop_id: &str, admin_pin: &str) -> Option<AdminPreferences> {
+    let resp = admin_request(Method::GET, "/preferences", shop_id, admin_pin).send().await.ok()?;
+    if !resp.ok() {
+        return None;
+    }
+    let bytes = resp.binary().await.ok()?;
+    AdminPreferences::decode(&bytes[..]).ok()
+}
+
+// ============================================================================
+// PREFERENCES PAGE - Âm báo, thông báo trình duyệt, phạm vi, giờ yên lặng của từng admin
+// ============================================================================
+#[component]
+pub fn PreferencesPage(
+    shop_id: String,
+    admin_pin: String,
+    prefs: RwSignal<AdminPreferences>,
+    on_close: impl Fn() + 'static + Clone,
+) -> impl IntoView {
+    let (status, set_status) = signal(String::new());
+
+    let shop = StoredValue::new(shop_id);
+    let pin = StoredValue::new(admin_pin);
+
+    // Lưu ngay mỗi lần đổi (như feature flag) - lỗi thì giữ giá trị cũ
+    let save = move |update: Box<dyn FnOnce(&mut AdminPreferences)>| {
+        let mut next = prefs.get_untracked();
+        update(&mut next);
+        spawn_local(async move {
+            let result = match admin_request(Method::PUT, "/preferences", &shop.get_value(), &pin.get_value())
+                .header("Content-Type", "application/octet-stream")
+                .body(next.encode_to_vec())
+            {
+                Ok(r) => r.send().await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(resp) if resp.ok() => {
+                    let bytes = resp.binary().await.unwrap_or_default();
+                    prefs.set(AdminPreferences::decode(&bytes[..]).unwrap_or(next));
+                    set_status.set(T::Saved.text().to_string());
+                }
+                Ok(resp) => set_status.set(T::HttpError.fill(&[&resp.status()])),
+                Err(e) => set_status.set(T::NetworkError.fill(&[&e])),
+            }
+        });
+    };
+
+    let test_alert = move |_| {
+        notify::unlock_audio();
+        notify::request_permission();
+        let current = prefs.get_untracked();
+        if !current.sound_off {
+            notify::play_ping();
+        }
+        if !current.desktop_off {
+            notify::show_notification(T::PreferencesTitle.text(), T::TestAlertBody.text(), "turbochat-test", || {});
+        }
+    };
+
+    let on_close_click = on_close.clone();
+
+    view! {
+        <div class="settings-panel">
+            <div class="chat-header-bar">
+                <div class="chat-header-info">
+                    <div class="chat-header-name">{T::PreferencesHeader.text()}</div>
+                    <div class="chat-header-status" role="status">{move || status.get()}</div>
+                </div>
+                <button class="settings-close" aria-label=T::Close.text() on:click=move |_| on_close_click()>"✕"</button>
+            </div>
+
+            <div class="settings-body">
+                <div class="settings-section">
+                    <div class="settings-hint">{T::PreferencesHint.text()}</div>
+                    <label class="settings-row">
+                        <span>{T::SoundAlerts.text()}</span>
+                        <input
+                            type="checkbox"
+                            prop:checked=move || !prefs.get().sound_off
+                            on:change=move |e| {
+                                let on = event_target_checked(&e);
+                                if on {
+                                    notify::unlock_audio();
+                                }
+                                save(Box::new(move |p| p.sound_off = !on));
+                            }
+                        />
+                    </label>
+                    <label class="settings-row">
+                        <span>{T::DesktopAlerts.text()}</span>
+                        <input
+                            type="checkbox"
+                            prop:checked=move || !prefs.get().desktop_off
+                            on:change=move |e| {
+                                let on = event_target_checked(&e);
+                                if on {
+                                    notify::request_permission();
+                                }
+                                save(Box::new(move |p| p.desktop_off = !on));
+                            }
+                        />
+                    </label>
+                    <Show when=move || !prefs.get().desktop_off && notify::permission_denied()>
+                        <div class="settings-hint">{T::DesktopBlocked.text()}</div>
+                    </Show>
+                    <label class="settings-row">
+                        <span>{T::NotifyScopeLabel.text()}</span>
+                        <select on:change=move |e| {
+                            let scope = if event_target_value(&e) == "assigned" { NotifyScope::Assigned } else { NotifyScope::All };
+                            save(Box::new(move |p| p.set_scope(scope)));
+                        }>
+                            <option value="all" selected=move || prefs.get().scope() == NotifyScope::All>{T::ScopeAll.text()}</option>
+                            <option value="assigned" selected=move || prefs.get().scope() == NotifyScope::Assigned>{T::ScopeAssigned.text()}</option>
+                        </select>
+                    </label>
+                    <button class="settings-save" on:click=test_alert>{T::TestAlert.text()}</button>
+                </div>
+
+                <div class="settings-section">
+                    <h3>{T::QuietHours.text()}</h3>
+                    <label class="settings-row">
+                        <span>{T::QuietHoursEnabled.text()}</span>
+                        <input
+                            type="checkbox"
+                            prop:checked=move || prefs.get().quiet_hours
+                            on:change=move |e| {
+                                let on = event_target_checked(&e);
+                                save(Box::new(move |p| p.quiet_hours = on));
+                            }
+                        />
+                    </label>
+                    <div class="settings-row">
+                        <span class="hours-range">
+                            <input
+                                type="time"
+                                prop:disabled=move || !prefs.get().quiet_hours
+                                prop:value=move || format_minute(prefs.get().quiet_start_minute)
+                                on:change=move |e| {
+                                    let minute = parse_minute(&event_target_value(&e)) % (24 * 60);
+                                    save(Box::new(move |p| p.quiet_start_minute = minute));
+                                }
+                            />
+                            " – "
+                            <input
+                                type="time"
+                                prop:disabled=move || !prefs.get().quiet_hours
+                                prop:value=move || format_minute(prefs.get().quiet_end_minute)
+                                on:change=move |e| {
+                                    let minute = parse_minute(&event_target_value(&e)) % (24 * 60);
+                                    save(Box::new(move |p| p.quiet_end_minute = minute));
+                                }
+                            />
+                        </span>
+                    </div>
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,380 @@
+use leptos::prelude::*;
+use turbochat_shared::{format_datetime, now_us, BlockRequest, Guest, GuestNote, NoteListResponse, NoteRequest};
+use prost::Message as ProstMessage;
+use wasm_bindgen_futures::spawn_local;
+use gloo_net::http::Method;
+use std::collections::{HashMap, HashSet};
+
+use crate::app::admin_request;
+use crate::i18n::T;
+use crate::transfer::transfer_label;
+
+#[derive(Clone, Copy, PartialEq)]
+enum ProfileTab {
+    Info,
+    Notes,
+}
+
+/// Áp dụng ghi chú từ frame WS (admin khác thêm/sửa/xoá).
+/// Chỉ cập nhật khách đã tải ghi chú - khách chưa mở sẽ tải đủ khi mở.
+pub(crate) fn apply_note(notes: &mut HashMap<u64, Vec<GuestNote>>, note: GuestNote) {
+    let Some(list) = notes.get_mut(&note.guest_id) else { return };
+    if note.deleted {
+        list.retain(|n| n.note_id != note.note_id);
+    } else if let Some(existing) = list.iter_mut().find(|n| n.note_id == note.note_id) {
+        existing.content = note.content;
+        existing.updated_at = note.updated_at;
+    } else {
+        list.push(note);
+        list.sort_by_key(|n| std::cmp::Reverse(n.note_id));
+    }
+}
+
+/// GET /guests/:id/notes - None khi mất kết nối (thử lại ở lần mở sau)
+pub(crate) async fn load_notes(shop_id: &str, admin_pin: &str, guest_id: u64) -> Option<Result<Vec<GuestNote>, String>> {
+    let path = format!("/guests/{}/notes", guest_id);
+    let resp = admin_request(Method::GET, &path, shop_id, admin_pin).send().await.ok()?;
+    let bytes = resp.binary().await.ok()?;
+    Some(match NoteListResponse::decode(&bytes[..]) {
+        Ok(list) if list.success => Ok(list.notes),
+        Ok(list) => Err(T::ApiError.fill(&[&list.error, &list.request_id])),
+        Err(_) => Err(T::NotesLoadFailed.text().to_string()),
+    })
+}
+
+// ============================================================================
+// GUEST PROFILE - Thông tin khách + chặn / bỏ chặn + ghi chú nội bộ
+// ============================================================================
+#[component]
+pub fn GuestProfile(
+    shop_id: String,
+    admin_pin: String,
+    guest_id: ReadSignal<u64>,
+    guest_info: ReadSignal<HashMap<u64, Guest>>,
+    blocked: RwSignal<HashSet<String>>,
+    notes: RwSignal<HashMap<u64, Vec<GuestNote>>>,
+    on_close: impl Fn() + 'static + Clone,
+) -> impl IntoView {
+    let (block_ip, set_block_ip) = signal(false);
+    let (status, set_status) = signal(String::new());
+    let (tab, set_tab) = signal(ProfileTab::Info);
+    let (note_input, set_note_input) = signal(String::new());
+    let (note_status, set_note_status) = signal(String::new());
+    let (summarizing, set_summarizing) = signal(false);
+    let (summary_status, set_summary_status) = signal(String::new());
+
+    let shop = StoredValue::new(shop_id);
+    let pin = StoredValue::new(admin_pin);
+
+    let guest = Memo::new(move |_| guest_info.get().get(&guest_id.get()).cloned());
+    let is_blocked = Memo::new(move |_| blocked.get().contains(&format!("guest:{}", guest_id.get())));
+
+    let toggle_block = move |_| {
+        let gid = guest_id.get_untracked();
+        let last_ip = guest.get_untracked().map(|g| g.last_ip).unwrap_or_default();
+        let unblock = is_blocked.get_untracked();
+        let include_ip = block_ip.get_untracked();
+        set_status.set(String::new());
+
+        spawn_local(async move {
+            let path = format!("/guests/{}/block", gid);
+            let result = if unblock {
+                admin_request(Method::DELETE, &path, &shop.get_value(), &pin.get_value())
+                    .send()
+                    .await
+            } else {
+                let req = BlockRequest { include_ip, disconnect: true, reason: String::new() };
+                match admin_request(Method::POST, &path, &shop.get_value(), &pin.get_value())
+                    .header("Content-Type", "application/octet-stream")
+                    .body(req.encode_to_vec())
+                {
+                    Ok(r) => r.send().await,
+                    Err(e) => Err(e),
+                }
+            };
+
+            match result {
+                Ok(resp) if resp.ok() => {
+                    blocked.update(|set| {
+                        let guest_key = format!("guest:{}", gid);
+                        let ip_key = format!("ip:{}", last_ip);
+                        if unblock {
+                            set.remove(&guest_key);
+                            set.remove(&ip_key);
+                        } else {
+                            set.insert(guest_key);
+                            if include_ip && !last_ip.is_empty() {
+                                set.insert(ip_key);
+                            }
+                        }
+                    });
+                }
+                Ok(resp) => set_status.set(T::HttpError.fill(&[&resp.status()])),
+                Err(e) => set_status.set(T::NetworkError.fill(&[&e])),
+            }
+        });
+    };
+
+    // Tóm tắt bằng LLM - kết quả về qua frame guest như các cập nhật khác
+    let summarize = move |_| {
+        let gid = guest_id.get_untracked();
+        set_summarizing.set(true);
+        set_summary_status.set(String::new());
+        spawn_local(async move {
+            let path = format!("/conversations/{}/summary", gid);
+            match admin_request(Method::POST, &path, &shop.get_value(), &pin.get_value()).send().await {
+                Ok(resp) if resp.ok() => {}
+                Ok(resp) if resp.status() == 503 => set_summary_status.set(T::LlmNotConfigured.text().to_string()),
+                Ok(resp) if resp.status() == 400 => set_summary_status.set(T::NothingToSummarize.text().to_string()),
+                Ok(resp) => set_summary_status.set(T::HttpError.fill(&[&resp.status()])),
+                Err(e) => set_summary_status.set(T::NetworkError.fill(&[&e])),
+            }
+            set_summarizing.set(false);
+        });
+    };
+
+    // Mở tab ghi chú lần đầu cho khách này → tải từ server
+    Effect::new(move |_| {
+        let gid = guest_id.get();
+        if tab.get() != ProfileTab::Notes || gid == 0 || notes.with_untracked(|n| n.contains_key(&gid)) {
+            return;
+        }
+        spawn_local(async move {
+            match load_notes(&shop.get_value(), &pin.get_value(), gid).await {
+                Some(Ok(list)) => notes.update(|n| { n.insert(gid, list); }),
+                Some(Err(e)) => set_note_status.set(e),
+                None => {}
+            }
+        });
+    });
+
+    let add_note = move |_| {
+        let gid = guest_id.get_untracked();
+        let content = note_input.get_untracked();
+        if content.trim().is_empty() { return; }
+        set_note_status.set(String::new());
+        spawn_local(async move {
+            let path = format!("/guests/{}/notes", gid);
+            let req = NoteRequest { content };
+            let result = match admin_request(Method::POST, &path, &shop.get_value(), &pin.get_value())
+                .header("Content-Type", "application/octet-stream")
+                .body(req.encode_to_vec())
+            {
+                Ok(r) => r.send().await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(resp) if resp.ok() => {
+                    if let Some(note) = resp.binary().await.ok().and_then(|b| GuestNote::decode(&b[..]).ok()) {
+                        notes.update(|n| apply_note(n, note));
+                    }
+                    set_note_input.set(String::new());
+                }
+                Ok(resp) => set_note_status.set(T::HttpError.fill(&[&resp.status()])),
+                Err(e) => set_note_status.set(T::NetworkError.fill(&[&e])),
+            }
+        });
+    };
+    let edit_note = move |note: GuestNote| {
+        let window = web_sys::window().unwrap();
+        let Ok(Some(content)) = window.prompt_with_message_and_default(T::NoteEditPrompt.text(), &note.content) else { return };
+        if content.trim().is_empty() || content == note.content { return; }
+        spawn_local(async move {
+            let path = format!("/guests/{}/notes/{}", note.guest_id, note.note_id);
+            let req = NoteRequest { content: content.clone() };
+            if let Ok(req) = admin_request(Method::PATCH, &path, &shop.get_value(), &pin.get_value())
+                .header("Content-Type", "application/octet-stream")
+                .body(req.encode_to_vec())
+            {
+                match req.send().await {
+                    Ok(resp) if resp.ok() => {
+                        let updated = GuestNote { content, updated_at: now_us(), ..note };
+                        notes.update(|n| apply_note(n, updated));
+                    }
+                    Ok(resp) => set_note_status.set(T::HttpError.fill(&[&resp.status()])),
+                    Err(e) => set_note_status.set(T::NetworkError.fill(&[&e])),
+                }
+            }
+        });
+    };
+    let delete_note = move |guest_id: u64, note_id: u64| {
+        let window = web_sys::window().unwrap();
+        if !window.confirm_with_message(T::NoteDeleteConfirm.text()).unwrap_or(false) { return; }
+        spawn_local(async move {
+            let path = format!("/guests/{}/notes/{}", guest_id, note_id);
+            match admin_request(Method::DELETE, &path, &shop.get_value(), &pin.get_value()).send().await {
+                Ok(resp) if resp.ok() => {
+                    let removed = GuestNote { note_id, guest_id, deleted: true, ..Default::default() };
+                    notes.update(|n| apply_note(n, removed));
+                }
+                Ok(resp) => set_note_status.set(T::HttpError.fill(&[&resp.status()])),
+                Err(e) => set_note_status.set(T::NetworkError.fill(&[&e])),
+            }
+        });
+    };
+
+    let on_close_click = on_close.clone();
+
+    view! {
+        <div class="profile-panel">
+            <div class="profile-header">
+                <span>{T::GuestInfo.text()}</span>
+                <button class="settings-close" aria-label=T::Close.text() on:click=move |_| on_close_click()>"✕"</button>
+            </div>
+
+            <div class="profile-tabs">
+                <button class:active=move || tab.get() == ProfileTab::Info on:click=move |_| set_tab.set(ProfileTab::Info)>{T::TabInfo.text()}</button>
+                <button class:active=move || tab.get() == ProfileTab::Notes on:click=move |_| set_tab.set(ProfileTab::Notes)>
+                    {T::TabNotes.text()}
+                    {move || {
+                        let count = notes.with(|n| n.get(&guest_id.get()).map(Vec::len).unwrap_or(0));
+                        (count > 0).then(|| format!(" ({})", count))
+                    }}
+                </button>
+            </div>
+
+            <div class="profile-body" class:hidden=move || tab.get() != ProfileTab::Notes>
+                <div class="settings-hint">{T::NotesHint.text()}</div>
+                <textarea
+                    class="note-input"
+                    rows="3"
+                    placeholder=T::NotePlaceholder.text()
+                    prop:value=move || note_input.get()
+                    on:input=move |e| set_note_input.set(event_target_value(&e))
+                ></textarea>
+                <button class="settings-save" on:click=add_note>{T::SaveNote.text()}</button>
+                <div class="settings-hint">{move || note_status.get()}</div>
+                <For
+                    each=move || notes.with(|n| n.get(&guest_id.get()).cloned().unwrap_or_default())
+                    key=|note| (note.note_id, note.updated_at)
+                    children=move |note: GuestNote| {
+                        let (gid, note_id) = (note.guest_id, note.note_id);
+                        let edited = note.updated_at > note.created_at;
+                        let stamp = format_datetime(note.updated_at);
+                        let content = note.content.clone();
+                        let is_transfer = note.transfer.is_some();
+                        let handover = note.transfer.as_ref().map(|t| view! { <div class="note-transfer">{transfer_label(t)}</div> });
+                        view! {
+                            <div class="note-item" class:transfer=is_transfer>
+                                {handover}
+                                <div class="note-text">{content}</div>
+                                <div class="note-meta">
+                                    <span>{stamp}{edited.then_some(T::NoteEdited.text())}</span>
+                                    <span>
+                                        <button title=T::Edit.text() on:click=move |_| edit_note(note.clone())>"✏️"</button>
+                                        <button title=T::Delete.text() on:click=move |_| delete_note(gid, note_id)>"🗑️"</button>
+                                    </span>
+                                </div>
+                            </div>
+                        }
+                    }
+                />
+            </div>
+
+            <div class="profile-body" class:hidden=move || tab.get() != ProfileTab::Info>
+                <div class="profile-name">
+                    {move || guest.get()
+                        .map(|g| g.display_name())
+                        .unwrap_or_else(|| Guest::fallback_name(guest_id.get()))}
+                </div>
+                <div class="profile-row">
+                    <span>"ID"</span>
+                    <span>{move || guest_id.get().to_string()}</span>
+                </div>
+                <div class="profile-row">
+                    <span>"Email"</span>
+                    <span>{move || guest.get().map(|g| g.email).filter(|e| !e.is_empty()).unwrap_or_else(|| "—".to_string())}</span>
+                </div>
+                {move || guest.get().map(|g| g.user_id).filter(|id| !id.is_empty()).map(|user_id| view! {
+                    <div class="profile-row">
+                        <span>{T::Account.text()}</span>
+                        <span title=T::VerifiedBySite.text()>{format!("✔ {}", user_id)}</span>
+                    </div>
+                })}
+                <div class="profile-row">
+                    <span>{T::LastSeen.text()}</span>
+                    <span>{move || guest.get().map(|g| format_datetime(g.last_seen)).unwrap_or_default()}</span>
+                </div>
+                {move || guest.get().and_then(|g| g.context).map(|c| {
+                    let viewport = format!("{} × {}", c.viewport_width, c.viewport_height);
+                    view! {
+                        <div class="profile-row">
+                            <span>{T::Viewing.text()}</span>
+                            <a class="profile-link" href=c.page_url.clone() target="_blank" rel="noopener" title=c.page_url.clone()>{c.page_path().to_string()}</a>
+                        </div>
+                        <div class="profile-row">
+                            <span>{T::Referrer.text()}</span>
+                            <span class="profile-link" title=c.referrer.clone()>{if c.referrer.is_empty() { T::DirectVisit.text().to_string() } else { c.referrer.clone() }}</span>
+                        </div>
+                        <div class="profile-row">
+                            <span>{T::Screen.text()}</span>
+                            <span title=c.user_agent.clone()>{viewport}</span>
+                        </div>
+                    }
+                })}
+                {move || guest.get().filter(|g| !g.tags.is_empty()).map(|g| view! {
+                    <div class="profile-row">
+                        <span>"Tag"</span>
+                        <span class="profile-tags">
+                            {g.tags.into_iter().map(|t| view! { <span class="quick-reply-chip">{t}</span> }).collect_view()}
+                        </span>
+                    </div>
+                })}
+                {move || guest.get().map(|g| g.assigned_to).filter(|a| !a.is_empty()).map(|agent| view! {
+                    <div class="profile-row">
+                        <span>{T::Assignee.text()}</span>
+                        <span>{if agent == "owner" { T::ShopOwner.text().to_string() } else { agent }}</span>
+                    </div>
+                })}
+                {move || guest.get().and_then(|g| g.geo).map(|geo| view! {
+                    <div class="profile-row">
+                        <span>{T::Location.text()}</span>
+                        <span>{geo.flag()} " " {geo.label()}</span>
+                    </div>
+                })}
+                <div class="profile-row">
+                    <span>"IP"</span>
+                    <span>{move || guest.get().map(|g| g.last_ip).filter(|ip| !ip.is_empty()).unwrap_or_else(|| "—".to_string())}</span>
+                </div>
+
+                <div class="profile-summary">
+                    <div class="profile-summary-header">
+                        <span>{T::Summary.text()}</span>
+                        <button on:click=summarize disabled=move || summarizing.get()>
+                            {move || if summarizing.get() { T::Summarizing.text() } else { T::Summarize.text() }}
+                        </button>
+                    </div>
+                    {move || guest.get().and_then(|g| g.summary).map(|s| view! {
+                        <div class="profile-summary-text">{s.text}</div>
+                        <div class="profile-tags">
+                            {s.suggested_tags.into_iter().map(|t| view! { <span class="quick-reply-chip">{t}</span> }).collect_view()}
+                        </div>
+                        <div class="settings-hint">{T::UpdatedAt.fill(&[&format_datetime(s.generated_at)])}</div>
+                    })}
+                    <div class="settings-hint">{move || summary_status.get()}</div>
+                </div>
+
+                <div class="profile-actions">
+                    <Show when=move || !is_blocked.get()>
+                        <label class="profile-check">
+                            <input
+                                type="checkbox"
+                                prop:checked=move || block_ip.get()
+                                on:change=move |e| set_block_ip.set(event_target_checked(&e))
+                            />
+                            {T::BlockIp.text()}
+                        </label>
+                    </Show>
+                    <button
+                        class="block-btn"
+                        class:unblock=move || is_blocked.get()
+                        on:click=toggle_block
+                    >
+                        {move || if is_blocked.get() { T::Unblock.text() } else { T::BlockGuest.text() }}
+                    </button>
+                    <div class="settings-hint">{move || status.get()}</div>
+                </div>
+            </div>
+        </div>
+    }
+}
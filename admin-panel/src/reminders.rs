@@ -0,0 +1,139 @@
+use leptos::prelude::*;
+use turbochat_shared::{format_datetime, Reminder, ReminderListResponse};
+use prost::Message as ProstMessage;
+use wasm_bindgen_futures::spawn_local;
+use gloo_net::http::Method;
+use std::collections::HashMap;
+
+use crate::app::admin_request;
+use crate::i18n::T;
+use crate::scheduled::{format_local_input, parse_local_input};
+
+// ============================================================================
+// NHẮC LẠI SAU - tạm ẩn hội thoại khỏi danh sách tới giờ nhắc (server lưu, dùng chung cả shop).
+// Tới giờ / khách nhắn lại / bỏ tạm ẩn → frame Reminder{cancelled}, hội thoại trở lại danh sách
+// ============================================================================
+
+/// Áp dụng lời nhắc từ frame WS / phản hồi API (cancelled = đã huỷ hoặc đã tới hạn)
+pub(crate) fn apply_reminder(map: &mut HashMap<u64, Reminder>, item: Reminder) {
+    if item.cancelled {
+        map.remove(&item.guest_id);
+    } else {
+        map.insert(item.guest_id, item);
+    }
+}
+
+/// GET /reminders - mọi hội thoại đang tạm ẩn của shop (None khi lỗi, giữ danh sách cũ)
+pub(crate) async fn load_reminders(shop_id: &str, admin_pin: &str) -> Option<HashMap<u64, Reminder>> {
+    let resp = admin_request(Method::GET, "/reminders", shop_id, admin_pin).send().await.ok()?;
+    let list = ReminderListResponse::decode(&resp.binary().await.ok()?[..]).ok()?;
+    list.success.then(|| list.items.into_iter().map(|r| (r.guest_id, r)).collect())
+}
+
+// Giờ nhắc theo lựa chọn trong menu (giờ máy admin), None = huỷ / nhập sai
+fn remind_at(choice: &str) -> Option<u64> {
+    let now = js_sys::Date::new_0();
+    match choice {
+        "custom" => {
+            let window = web_sys::window().unwrap();
+            let later = js_sys::Date::new(&now.get_time().into());
+            later.set_hours(later.get_hours() + 2);
+            let raw = window.prompt_with_message_and_default(T::SnoozePrompt.text(), &format_local_input(&later)).ok()??;
+            let at = parse_local_input(&raw).filter(|&t| t as f64 > js_sys::Date::now() * 1000.0);
+            if at.is_none() {
+                let _ = window.alert_with_message(T::ScheduleInvalid.text());
+            }
+            at
+        }
+        "tomorrow" => {
+            now.set_date(now.get_date() + 1);
+            now.set_hours(9);
+            now.set_minutes(0);
+            Some(now.get_time() as u64 * 1000)
+        }
+        minutes => minutes.parse::<u64>().ok().map(|m| (now.get_time() as u64 + m * 60_000) * 1000),
+    }
+}
+
+/// Nút "💤" trên đầu hội thoại: chọn giờ nhắc, đang tạm ẩn thì hiện giờ nhắc + bỏ tạm ẩn
+#[component]
+pub fn SnoozeMenu(
+    shop_id: String,
+    admin_pin: String,
+    guest_id: ReadSignal<u64>,
+    reminders: RwSignal<HashMap<u64, Reminder>>,
+) -> impl IntoView {
+    let choice = RwSignal::new(String::new());
+    let shop = StoredValue::new(shop_id);
+    let pin = StoredValue::new(admin_pin);
+
+    let current = Memo::new(move |_| reminders.with(|m| m.get(&guest_id.get()).cloned()));
+
+    let snooze = move |remind_at_us: u64| {
+        let gid = guest_id.get_untracked();
+        let req = Reminder { remind_at_us, ..Default::default() };
+        spawn_local(async move {
+            let path = format!("/guests/{}/reminder", gid);
+            let result = match admin_request(Method::PUT, &path, &shop.get_value(), &pin.get_value())
+                .header("Content-Type", "application/octet-stream")
+                .body(req.encode_to_vec())
+            {
+                Ok(r) => r.send().await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(resp) if resp.ok() => {
+                    if let Some(item) = resp.binary().await.ok().and_then(|b| Reminder::decode(&b[..]).ok()) {
+                        reminders.update(|m| apply_reminder(m, item));
+                    }
+                }
+                _ => { let _ = web_sys::window().unwrap().alert_with_message(T::SnoozeFailed.text()); }
+            }
+        });
+    };
+
+    let wake = move |_| {
+        let gid = guest_id.get_untracked();
+        spawn_local(async move {
+            let path = format!("/guests/{}/reminder", gid);
+            match admin_request(Method::DELETE, &path, &shop.get_value(), &pin.get_value()).send().await {
+                Ok(resp) if resp.ok() => reminders.update(|m| apply_reminder(m, Reminder { guest_id: gid, cancelled: true, ..Default::default() })),
+                _ => { let _ = web_sys::window().unwrap().alert_with_message(T::SnoozeFailed.text()); }
+            }
+        });
+    };
+
+    view! {
+        {move || match current.get() {
+            Some(reminder) => view! {
+                <button class="conversation-action snoozed" title=T::Unsnooze.text() on:click=wake>
+                    {T::SnoozedUntil.fill(&[&format_datetime(reminder.remind_at_us)])}
+                </button>
+            }.into_any(),
+            None => view! {
+                <select
+                    class="snooze-select"
+                    title=T::Snooze.text()
+                    aria-label=T::Snooze.text()
+                    prop:value=move || choice.get()
+                    on:change=move |e| {
+                        let picked = event_target_value(&e);
+                        choice.set(picked.clone());
+                        if let Some(at) = remind_at(&picked) {
+                            snooze(at);
+                        }
+                        // Trả ô chọn về "💤" (dùng lại được cho lần sau)
+                        choice.set(String::new());
+                    }
+                >
+                    <option value="">"💤"</option>
+                    <option value="60">{T::SnoozeHour.text()}</option>
+                    <option value="120">{T::SnoozeHours.fill(&[&2])}</option>
+                    <option value="240">{T::SnoozeHours.fill(&[&4])}</option>
+                    <option value="tomorrow">{T::SnoozeTomorrow.text()}</option>
+                    <option value="custom">{T::SnoozeCustom.text()}</option>
+                </select>
+            }.into_any(),
+        }}
+    }
+}
@@ -0,0 +1,159 @@
+use leptos::prelude::*;
+use turbochat_shared::{format_datetime, QuickReply, ScheduledListResponse, ScheduledMessage};
+use prost::Message as ProstMessage;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::spawn_local;
+use gloo_net::http::Method;
+use std::collections::HashMap;
+
+use crate::app::admin_request;
+use crate::i18n::T;
+
+// ============================================================================
+// TIN HẸN GIỜ - lên lịch từ ô soạn tin, danh sách chờ + huỷ ngay trên ô soạn
+// ============================================================================
+
+/// Áp dụng tin hẹn giờ từ frame WS / phản hồi API (cancelled = đã huỷ hoặc đã gửi).
+/// Chỉ cập nhật khách đã tải danh sách.
+pub(crate) fn apply_scheduled(map: &mut HashMap<u64, Vec<ScheduledMessage>>, item: ScheduledMessage) {
+    let Some(list) = map.get_mut(&item.guest_id) else { return };
+    list.retain(|s| s.scheduled_id != item.scheduled_id);
+    if !item.cancelled {
+        list.push(item);
+        list.sort_by_key(|s| s.send_at_us);
+    }
+}
+
+// "YYYY-MM-DD HH:MM" giờ máy admin
+pub(crate) fn format_local_input(date: &js_sys::Date) -> String {
+    format!("{:04}-{:02}-{:02} {:02}:{:02}",
+        date.get_full_year(), date.get_month() + 1, date.get_date(), date.get_hours(), date.get_minutes())
+}
+
+// Chuỗi ISO không có múi giờ → trình duyệt hiểu là giờ địa phương
+pub(crate) fn parse_local_input(raw: &str) -> Option<u64> {
+    let date = js_sys::Date::new(&JsValue::from_str(&raw.trim().replacen(' ', "T", 1)));
+    let ms = date.get_time();
+    (!ms.is_nan()).then(|| ms as u64 * 1000)
+}
+
+/// Hỏi giờ gửi (mặc định 9:00 sáng mai) rồi lưu tin hẹn giờ.
+/// `on_done(true)` khi đã lên lịch → xoá ô soạn tin.
+pub(crate) fn schedule_message(
+    shop_id: String,
+    admin_pin: String,
+    guest_id: u64,
+    text: String,
+    quick_replies: Vec<QuickReply>,
+    scheduled: RwSignal<HashMap<u64, Vec<ScheduledMessage>>>,
+    on_done: impl Fn(bool) + 'static,
+) {
+    let window = web_sys::window().unwrap();
+    if guest_id == 0 || text.trim().is_empty() {
+        let _ = window.alert_with_message(T::ScheduleEmpty.text());
+        return;
+    }
+
+    let tomorrow = js_sys::Date::new_0();
+    tomorrow.set_date(tomorrow.get_date() + 1);
+    tomorrow.set_hours(9);
+    tomorrow.set_minutes(0);
+    let Ok(Some(raw)) = window.prompt_with_message_and_default(
+        T::SchedulePrompt.text(), &format_local_input(&tomorrow)) else { return };
+    let Some(send_at_us) = parse_local_input(&raw).filter(|&t| t as f64 > js_sys::Date::now() * 1000.0) else {
+        let _ = window.alert_with_message(T::ScheduleInvalid.text());
+        return;
+    };
+
+    spawn_local(async move {
+        let path = format!("/guests/{}/scheduled", guest_id);
+        let req = ScheduledMessage {
+            content: text.into_bytes().into(),
+            send_at_us,
+            quick_replies,
+            ..Default::default()
+        };
+        let result = match admin_request(Method::POST, &path, &shop_id, &admin_pin)
+            .header("Content-Type", "application/octet-stream")
+            .body(req.encode_to_vec())
+        {
+            Ok(r) => r.send().await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(resp) if resp.ok() => {
+                if let Some(item) = resp.binary().await.ok().and_then(|b| ScheduledMessage::decode(&b[..]).ok()) {
+                    scheduled.update(|m| apply_scheduled(m, item));
+                }
+                on_done(true);
+            }
+            _ => {
+                let _ = web_sys::window().unwrap().alert_with_message(T::ScheduleFailed.text());
+                on_done(false);
+            }
+        }
+    });
+}
+
+// ============================================================================
+// SCHEDULED BAR - tin đang chờ gửi của khách đang mở (trên ô soạn tin)
+// ============================================================================
+#[component]
+pub fn ScheduledBar(
+    shop_id: String,
+    admin_pin: String,
+    guest_id: ReadSignal<u64>,
+    scheduled: RwSignal<HashMap<u64, Vec<ScheduledMessage>>>,
+) -> impl IntoView {
+    let shop = StoredValue::new(shop_id);
+    let pin = StoredValue::new(admin_pin);
+
+    // Mở khách lần đầu → tải danh sách chờ
+    Effect::new(move |_| {
+        let gid = guest_id.get();
+        if gid == 0 || scheduled.with_untracked(|m| m.contains_key(&gid)) {
+            return;
+        }
+        spawn_local(async move {
+            let path = format!("/guests/{}/scheduled", gid);
+            if let Ok(resp) = admin_request(Method::GET, &path, &shop.get_value(), &pin.get_value()).send().await {
+                if let Some(list) = resp.binary().await.ok().and_then(|b| ScheduledListResponse::decode(&b[..]).ok()) {
+                    if list.success {
+                        scheduled.update(|m| { m.insert(gid, list.items); });
+                    }
+                }
+            }
+        });
+    });
+
+    let cancel = move |gid: u64, scheduled_id: u64| {
+        let window = web_sys::window().unwrap();
+        if !window.confirm_with_message(T::ScheduleCancelConfirm.text()).unwrap_or(false) { return; }
+        spawn_local(async move {
+            let path = format!("/guests/{}/scheduled/{}", gid, scheduled_id);
+            if let Ok(resp) = admin_request(Method::DELETE, &path, &shop.get_value(), &pin.get_value()).send().await {
+                if resp.ok() {
+                    let removed = ScheduledMessage { scheduled_id, guest_id: gid, cancelled: true, ..Default::default() };
+                    scheduled.update(|m| apply_scheduled(m, removed));
+                }
+            }
+        });
+    };
+
+    view! {
+        {move || {
+            let items = scheduled.with(|m| m.get(&guest_id.get()).cloned().unwrap_or_default());
+            items.into_iter().map(|s| {
+                let (gid, id) = (s.guest_id, s.scheduled_id);
+                let text = String::from_utf8_lossy(&s.content).to_string();
+                let title = text.clone();
+                view! {
+                    <div class="reply-bar scheduled-bar">
+                        <span title=title>"⏰ " <b>{format_datetime(s.send_at_us)}</b> ": " {text}</span>
+                        <button title=T::ScheduleCancel.text() aria-label=T::ScheduleCancel.text() on:click=move |_| cancel(gid, id)>"✕"</button>
+                    </div>
+                }
+            }).collect_view()
+        }}
+    }
+}
@@ -0,0 +1,188 @@
+use leptos::prelude::*;
+use leptos::html::Input;
+use turbochat_shared::{Message as ChatMessage, MessageSearchResponse};
+use prost::Message as ProstMessage;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+use gloo_net::http::Method;
+
+use crate::app::{admin_request, scroll_to_message};
+use crate::i18n::T;
+
+// Một lần bấm "cũ hơn" gọi server tối đa chừng này lượt (mỗi lượt server chỉ quét một phần hội thoại)
+const MAX_SERVER_ROUNDS: usize = 10;
+
+// ============================================================================
+// TÌM TRONG HỘI THOẠI - lọc tin đã tải ngay trên trình duyệt; hết kết quả thì hỏi
+// GET /guests/:id/search cho phần lịch sử cũ hơn (tin tìm được chèn vào hội thoại)
+// ============================================================================
+#[component]
+pub fn ConversationSearch(
+    shop_id: String,
+    admin_pin: String,
+    guest_id: ReadSignal<u64>,
+    query: RwSignal<String>,
+    // id các tin đã tải khớp với query (tăng dần)
+    matches: Memo<Vec<u64>>,
+    // id tin cũ nhất đã tải (0 = chưa có tin)
+    oldest_loaded: Memo<u64>,
+    // Kết quả đang chọn (App tô đậm)
+    active: RwSignal<Option<u64>>,
+    on_found: impl Fn(u64, Vec<ChatMessage>) + 'static + Copy,
+    on_close: impl Fn() + 'static + Copy,
+) -> impl IntoView {
+    let (status, set_status) = signal(String::new());
+    // Server báo đã quét hết phần cũ hơn cho query hiện tại
+    let exhausted = RwSignal::new(false);
+    let searching = RwSignal::new(false);
+    let input_ref = NodeRef::<Input>::new();
+
+    let shop = StoredValue::new(shop_id);
+    let pin = StoredValue::new(admin_pin);
+
+    let select = move |id: u64| {
+        active.set(Some(id));
+        set_status.set(String::new());
+        scroll_soon(id);
+    };
+
+    // Tìm tin cũ hơn `before` trên server, lặp tới khi có kết quả hoặc hết hội thoại
+    let search_server = move |before: u64| {
+        if searching.get_untracked() {
+            return;
+        }
+        if exhausted.get_untracked() {
+            set_status.set(T::SearchNoMore.text().to_string());
+            return;
+        }
+        let (gid, q) = (guest_id.get_untracked(), query.get_untracked());
+        searching.set(true);
+        set_status.set(T::SearchingOlder.text().to_string());
+        spawn_local(async move {
+            let mut before = before;
+            for _ in 0..MAX_SERVER_ROUNDS {
+                let path = format!("/guests/{}/search?q={}&before={}", gid, js_sys::encode_uri_component(&q), before);
+                let resp = match admin_request(Method::GET, &path, &shop.get_value(), &pin.get_value()).send().await {
+                    Ok(resp) if resp.ok() => resp,
+                    Ok(resp) => {
+                        set_status.set(T::HttpError.fill(&[&resp.status()]));
+                        break;
+                    }
+                    Err(e) => {
+                        set_status.set(T::NetworkError.fill(&[&e]));
+                        break;
+                    }
+                };
+                let bytes = resp.binary().await.unwrap_or_default();
+                let Ok(found) = MessageSearchResponse::decode(&bytes[..]) else { break };
+                // Người dùng đã đổi query / hội thoại trong lúc chờ → bỏ kết quả
+                if query.get_untracked() != q || guest_id.get_untracked() != gid {
+                    break;
+                }
+                let newest = found.messages.first().map(|m| m.message_id);
+                if !found.messages.is_empty() {
+                    on_found(gid, found.messages);
+                }
+                if found.next_before == 0 {
+                    exhausted.set(true);
+                }
+                match newest {
+                    Some(id) => {
+                        select(id);
+                        break;
+                    }
+                    None if found.next_before == 0 => {
+                        set_status.set(T::SearchNoMore.text().to_string());
+                        break;
+                    }
+                    None => before = found.next_before,
+                }
+            }
+            searching.set(false);
+        });
+    };
+
+    // Cũ hơn (lên trên) - hết tin đã tải thì hỏi server
+    let go_older = move || {
+        if query.with_untracked(|q| q.trim().is_empty()) {
+            return;
+        }
+        let ids = matches.get_untracked();
+        let older = match active.get_untracked() {
+            Some(current) => ids.iter().rev().find(|&&id| id < current).copied(),
+            None => ids.last().copied(),
+        };
+        match older {
+            Some(id) => select(id),
+            None => search_server(active.get_untracked().or(ids.first().copied()).unwrap_or_else(|| oldest_loaded.get_untracked())),
+        }
+    };
+
+    // Mới hơn (xuống dưới) - chỉ trong tin đã tải
+    let go_newer = move || {
+        let current = active.get_untracked();
+        if let Some(id) = matches.with_untracked(|ids| ids.iter().find(|&&id| current.is_some_and(|c| id > c)).copied()) {
+            select(id);
+        }
+    };
+
+    // Đổi query → bắt đầu lại từ kết quả mới nhất
+    Effect::new(move |_| {
+        query.track();
+        exhausted.set(false);
+        set_status.set(String::new());
+        active.set(None);
+        if let Some(id) = matches.get_untracked().last().copied() {
+            select(id);
+        }
+    });
+
+    Effect::new(move |_| {
+        if let Some(input) = input_ref.get() {
+            let _ = input.focus();
+        }
+    });
+
+    let counter = move || {
+        let ids = matches.get();
+        match active.get().and_then(|a| ids.iter().position(|&id| id == a)) {
+            Some(i) => T::SearchCounter.fill(&[&(i + 1), &ids.len()]),
+            None if !ids.is_empty() => T::SearchCount.fill(&[&ids.len()]),
+            None => String::new(),
+        }
+    };
+
+    view! {
+        <div class="conversation-search" role="search">
+            <input
+                type="search"
+                node_ref=input_ref
+                dir="auto"
+                aria-label=T::SearchConversation.text()
+                placeholder=T::SearchPlaceholder.text()
+                prop:value=move || query.get()
+                on:input=move |e| query.set(event_target_value(&e))
+                on:keydown=move |e: web_sys::KeyboardEvent| match e.key().as_str() {
+                    "Enter" if e.shift_key() => go_newer(),
+                    "Enter" => go_older(),
+                    "Escape" => {
+                        e.prevent_default();
+                        on_close();
+                    }
+                    _ => {}
+                }
+            />
+            <span class="search-counter" role="status">{move || if status.get().is_empty() { counter() } else { status.get() }}</span>
+            <button title=T::SearchOlder.text() aria-label=T::SearchOlder.text() prop:disabled=move || searching.get() on:click=move |_| go_older()>"▲"</button>
+            <button title=T::SearchNewer.text() aria-label=T::SearchNewer.text() on:click=move |_| go_newer()>"▼"</button>
+            <button class="settings-close" aria-label=T::Close.text() on:click=move |_| on_close()>"✕"</button>
+        </div>
+    }
+}
+
+// Đợi danh sách tin vẽ lại (kết quả từ server vừa được chèn) rồi mới cuộn
+fn scroll_soon(id: u64) {
+    let scroll = Closure::once(Box::new(move || scroll_to_message(id)) as Box<dyn FnOnce()>);
+    let _ = web_sys::window().unwrap().request_animation_frame(scroll.as_ref().unchecked_ref());
+    scroll.forget();
+}
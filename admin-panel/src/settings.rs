@@ -0,0 +1,524 @@
+use leptos::prelude::*;
+use turbochat_shared::{format_datetime, BusinessHours, FilterAction, RetentionStats, SettingsRequest, SettingsResponse, ShopFlags, ShopSettings, ColorScheme, Locale, ThemeMode};
+use prost::Message as ProstMessage;
+use wasm_bindgen_futures::spawn_local;
+use gloo_net::http::{Method, Request};
+
+use crate::app::{admin_request, API_BASE};
+use crate::identity::IdentitySection;
+use crate::invite::{is_agent_session, InviteSection};
+use crate::i18n::{self, T};
+
+const WEEKDAYS: [T; 7] = [T::Sunday, T::Monday, T::Tuesday, T::Wednesday, T::Thursday, T::Friday, T::Saturday];
+
+// (nhãn, đọc, ghi) cho từng feature flag
+type FlagRow = (T, fn(&ShopFlags) -> bool, fn(&mut ShopFlags, bool));
+const FLAG_ROWS: [FlagRow; 5] = [
+    (T::FlagE2ee, |f| f.e2ee, |f, on| f.e2ee = on),
+    (T::FlagAiReply, |f| f.ai_reply, |f, on| f.ai_reply = on),
+    (T::FlagAttachments, |f| f.attachments, |f, on| f.attachments = on),
+    (T::FlagTranslations, |f| f.translations, |f, on| f.translations = on),
+    (T::FlagVoiceNotes, |f| f.voice_notes, |f, on| f.voice_notes = on),
+];
+
+// ============================================================================
+// SETTINGS PANEL - Cấu hình shop (retention, ...)
+// ============================================================================
+#[component]
+pub fn SettingsPanel(
+    shop_id: String,
+    admin_pin: String,
+    on_close: impl Fn() + 'static + Clone,
+) -> impl IntoView {
+    let (settings, set_settings) = signal(ShopSettings::default());
+    let (retention, set_retention) = signal(None::<RetentionStats>);
+    let (flags, set_flags) = signal(ShopFlags::default());
+    let (status, set_status) = signal(String::new());
+
+    let shop = StoredValue::new(shop_id);
+    let pin = StoredValue::new(admin_pin);
+    let color_mode = expect_context::<ThemeMode>();
+
+    // Gửi SettingsRequest: None = chỉ đọc, Some = lưu
+    let submit = move |update: Option<ShopSettings>| {
+        let is_update = update.is_some();
+        let req = SettingsRequest {
+            shop_id: shop.get_value(),
+            admin_pin: pin.get_value(),
+            settings: update,
+        };
+        spawn_local(async move {
+            match post_settings(req).await {
+                Ok(resp) if resp.success => {
+                    set_settings.set(resp.settings.unwrap_or_default());
+                    set_retention.set(resp.retention);
+                    set_flags.set(resp.flags.unwrap_or_default());
+                    if is_update {
+                        set_status.set(T::Saved.text().to_string());
+                    }
+                }
+                Ok(resp) => set_status.set(T::ApiError.fill(&[&resp.error, &resp.request_id])),
+                Err(e) => set_status.set(T::NetworkError.fill(&[&e])),
+            }
+        });
+    };
+
+    // Load khi mở panel
+    Effect::new(move |_| submit(None));
+
+    // Flag lưu ngay khi bật/tắt (endpoint riêng, không qua nút "Lưu cài đặt")
+    let save_flags = move |next: ShopFlags| {
+        spawn_local(async move {
+            let result = match admin_request(Method::PUT, "/flags", &shop.get_value(), &pin.get_value())
+                .header("Content-Type", "application/octet-stream")
+                .body(next.encode_to_vec())
+            {
+                Ok(r) => r.send().await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(resp) if resp.ok() => {
+                    set_flags.set(next);
+                    set_status.set(T::FlagsSaved.text().to_string());
+                }
+                Ok(resp) => set_status.set(T::HttpError.fill(&[&resp.status()])),
+                Err(e) => set_status.set(T::NetworkError.fill(&[&e])),
+            }
+        });
+    };
+    let toggle_flag = move |update: fn(&mut ShopFlags, bool), on: bool| {
+        let mut next = flags.get_untracked();
+        update(&mut next, on);
+        save_flags(next);
+    };
+
+    let on_close_click = on_close.clone();
+
+    view! {
+        <div class="settings-panel">
+            <div class="chat-header-bar">
+                <div class="chat-header-info">
+                    <div class="chat-header-name">{T::SettingsHeader.text()}</div>
+                    <div class="chat-header-status" role="status">{move || status.get()}</div>
+                </div>
+                <button class="settings-close" aria-label=T::Close.text() on:click=move |_| on_close_click()>"✕"</button>
+            </div>
+
+            <div class="settings-body">
+                <div class="settings-section">
+                    <h3>{T::ThisBrowser.text()}</h3>
+                    <label class="settings-row">
+                        <span>{T::InterfaceLanguage.text()}</span>
+                        <select on:change=move |e| {
+                            if let Some(locale) = Locale::parse(&event_target_value(&e)) {
+                                i18n::choose(locale);
+                            }
+                        }>
+                            {Locale::ALL.into_iter().map(|l| view! {
+                                <option value=l.code() selected=l == turbochat_shared::i18n::locale()>{l.native_name()}</option>
+                            }).collect_view()}
+                        </select>
+                    </label>
+                    <label class="settings-row">
+                        <span>{T::ColorSchemeLabel.text()}</span>
+                        <select on:change=move |e| {
+                            if let Some(scheme) = ColorScheme::parse(&event_target_value(&e)) {
+                                color_mode.set(scheme);
+                            }
+                        }>
+                            {ColorScheme::ALL.into_iter().map(|scheme| view! {
+                                <option value=scheme.code() selected=move || color_mode.preference() == scheme>{scheme_label(scheme)}</option>
+                            }).collect_view()}
+                        </select>
+                    </label>
+                </div>
+                <div class="settings-section">
+                    <h3>{T::Retention.text()}</h3>
+                    <label class="settings-row">
+                        <span>{T::RetentionDays.text()}</span>
+                        <input
+                            type="number"
+                            min="0"
+                            prop:value=move || settings.get().retention_days.to_string()
+                            on:input=move |e| {
+                                let days = event_target_value(&e).parse().unwrap_or(0);
+                                set_settings.update(|s| s.retention_days = days);
+                            }
+                        />
+                    </label>
+                    <label class="settings-row">
+                        <span>{T::ArchiveInsteadOfDelete.text()}</span>
+                        <input
+                            type="checkbox"
+                            prop:checked=move || settings.get().retention_archive
+                            on:change=move |e| {
+                                let checked = event_target_checked(&e);
+                                set_settings.update(|s| s.retention_archive = checked);
+                            }
+                        />
+                    </label>
+                    <div class="settings-hint">
+                        {move || match retention.get() {
+                            Some(r) if r.last_run_us > 0 => T::LastSweep.fill(&[
+                                &format_datetime(r.last_run_us), &r.messages_deleted, &r.messages_archived,
+                            ]),
+                            _ => T::NoSweep.text().to_string(),
+                        }}
+                    </div>
+                </div>
+
+                <div class="settings-section">
+                    <h3>{T::SpamFilter.text()}</h3>
+                    <label class="settings-row settings-column">
+                        <span>{T::BannedWords.text()}</span>
+                        <textarea
+                            rows="4"
+                            prop:value=move || settings.get().banned_words.join("\n")
+                            on:change=move |e| {
+                                let words = event_target_value(&e)
+                                    .lines()
+                                    .map(|w| w.trim().to_string())
+                                    .filter(|w| !w.is_empty())
+                                    .collect();
+                                set_settings.update(|s| s.banned_words = words);
+                            }
+                        ></textarea>
+                    </label>
+                    <label class="settings-row">
+                        <span>{T::OnBannedWord.text()}</span>
+                        <select on:change=move |e| {
+                            let action = match event_target_value(&e).as_str() {
+                                "reject" => FilterAction::Reject,
+                                "flag" => FilterAction::Flag,
+                                _ => FilterAction::Mask,
+                            };
+                            set_settings.update(|s| s.set_banned_words_action(action));
+                        }>
+                            <option value="mask" selected=move || settings.get().banned_words_action() == FilterAction::Mask>{T::ActionMask.text()}</option>
+                            <option value="reject" selected=move || settings.get().banned_words_action() == FilterAction::Reject>{T::ActionReject.text()}</option>
+                            <option value="flag" selected=move || settings.get().banned_words_action() == FilterAction::Flag>{T::ActionFlag.text()}</option>
+                        </select>
+                    </label>
+                    <label class="settings-row">
+                        <span>{T::MaxLinks.text()}</span>
+                        <input
+                            type="number"
+                            min="0"
+                            prop:value=move || settings.get().max_links_per_minute.to_string()
+                            on:input=move |e| {
+                                let n = event_target_value(&e).parse().unwrap_or(0);
+                                set_settings.update(|s| s.max_links_per_minute = n);
+                            }
+                        />
+                    </label>
+                    <label class="settings-row">
+                        <span>{T::MaxDuplicates.text()}</span>
+                        <input
+                            type="number"
+                            min="0"
+                            prop:value=move || settings.get().max_duplicate_messages.to_string()
+                            on:input=move |e| {
+                                let n = event_target_value(&e).parse().unwrap_or(0);
+                                set_settings.update(|s| s.max_duplicate_messages = n);
+                            }
+                        />
+                    </label>
+                </div>
+
+                <div class="settings-section">
+                    <h3>{T::BusinessHours.text()}</h3>
+                    <label class="settings-row">
+                        <span>{T::BusinessHoursEnabled.text()}</span>
+                        <input
+                            type="checkbox"
+                            prop:checked=move || settings.get().business_hours_enabled
+                            on:change=move |e| {
+                                let checked = event_target_checked(&e);
+                                set_settings.update(|s| s.business_hours_enabled = checked);
+                            }
+                        />
+                    </label>
+                    <label class="settings-row">
+                        <span>{T::Timezone.text()}</span>
+                        <select on:change=move |e| {
+                            let offset = event_target_value(&e).parse().unwrap_or(0);
+                            set_settings.update(|s| s.timezone_offset_minutes = offset);
+                        }>
+                            {(-12..=14).map(|h: i32| {
+                                let minutes = h * 60;
+                                view! {
+                                    <option
+                                        value=minutes.to_string()
+                                        selected=move || settings.get().timezone_offset_minutes == minutes
+                                    >
+                                        {format!("UTC{:+}", h)}
+                                    </option>
+                                }
+                            }).collect_view()}
+                        </select>
+                    </label>
+                    {(0..7u32).map(|day| view! {
+                        <div class="settings-row">
+                            <label class="profile-check">
+                                <input
+                                    type="checkbox"
+                                    prop:checked=move || hours_for(&settings.get(), day).is_some()
+                                    on:change=move |e| {
+                                        let checked = event_target_checked(&e);
+                                        set_settings.update(|s| {
+                                            s.business_hours.retain(|h| h.weekday != day);
+                                            if checked {
+                                                s.business_hours.push(BusinessHours { weekday: day, open_minute: 8 * 60, close_minute: 17 * 60 });
+                                            }
+                                        });
+                                    }
+                                />
+                                {WEEKDAYS[day as usize].text()}
+                            </label>
+                            <span class="hours-range">
+                                <input
+                                    type="time"
+                                    prop:disabled=move || hours_for(&settings.get(), day).is_none()
+                                    prop:value=move || hours_for(&settings.get(), day).map(|h| format_minute(h.open_minute)).unwrap_or_default()
+                                    on:change=move |e| {
+                                        let minute = parse_minute(&event_target_value(&e));
+                                        set_hours(set_settings, day, |h| h.open_minute = minute);
+                                    }
+                                />
+                                " – "
+                                <input
+                                    type="time"
+                                    prop:disabled=move || hours_for(&settings.get(), day).is_none()
+                                    prop:value=move || hours_for(&settings.get(), day).map(|h| format_minute(h.close_minute)).unwrap_or_default()
+                                    on:change=move |e| {
+                                        let minute = parse_minute(&event_target_value(&e));
+                                        set_hours(set_settings, day, |h| h.close_minute = minute);
+                                    }
+                                />
+                            </span>
+                        </div>
+                    }).collect_view()}
+                    <label class="settings-row settings-column">
+                        <span>{T::OfflineMessage.text()}</span>
+                        <textarea
+                            rows="2"
+                            placeholder=T::OfflineMessagePlaceholder.text()
+                            prop:value=move || settings.get().offline_message
+                            on:change=move |e| {
+                                let text = event_target_value(&e);
+                                set_settings.update(|s| s.offline_message = text);
+                            }
+                        ></textarea>
+                    </label>
+                    <label class="settings-row">
+                        <span>{T::OfflineEmail.text()}</span>
+                        <input
+                            type="text"
+                            class="settings-wide"
+                            placeholder="shop@example.com"
+                            prop:value=move || settings.get().notify_email
+                            on:change=move |e| {
+                                let email = event_target_value(&e).trim().to_string();
+                                set_settings.update(|s| s.notify_email = email);
+                            }
+                        />
+                    </label>
+                </div>
+
+                <div class="settings-section">
+                    <h3>{T::ResponseSla.text()}</h3>
+                    <label class="settings-row">
+                        <span>{T::FirstResponseMinutes.text()}</span>
+                        <input
+                            type="number"
+                            min="0"
+                            prop:value=move || (settings.get().sla_first_response_secs / 60).to_string()
+                            on:input=move |e| {
+                                let minutes: u32 = event_target_value(&e).parse().unwrap_or(0);
+                                set_settings.update(|s| s.sla_first_response_secs = minutes.saturating_mul(60));
+                            }
+                        />
+                    </label>
+                    <label class="settings-row">
+                        <span>{T::SlaEmail.text()}</span>
+                        <input
+                            type="checkbox"
+                            prop:checked=move || settings.get().sla_email_alerts
+                            on:change=move |e| {
+                                let checked = event_target_checked(&e);
+                                set_settings.update(|s| s.sla_email_alerts = checked);
+                            }
+                        />
+                    </label>
+                </div>
+
+                <div class="settings-section">
+                    <h3>{T::Features.text()}</h3>
+                    {FLAG_ROWS.into_iter().map(|(label, get, set)| view! {
+                        <label class="settings-row">
+                            <span>{label.text()}</span>
+                            <input
+                                type="checkbox"
+                                prop:checked=move || flags.with(get)
+                                on:change=move |e| toggle_flag(set, event_target_checked(&e))
+                            />
+                        </label>
+                    }).collect_view()}
+                    <Show when=move || flags.with(|f| f.voice_notes)>
+                        <label class="settings-row">
+                            <span>{T::VoiceMaxSecs.fill(&[&ShopFlags::MAX_VOICE_MAX_SECS])}</span>
+                            <input
+                                type="number"
+                                min="1"
+                                max=ShopFlags::MAX_VOICE_MAX_SECS
+                                prop:value=move || flags.with(|f| f.voice_limit_secs()).to_string()
+                                on:change=move |e| {
+                                    let secs = event_target_value(&e).parse().unwrap_or(0);
+                                    save_flags(ShopFlags { voice_max_secs: secs, ..flags.get_untracked() });
+                                }
+                            />
+                        </label>
+                    </Show>
+                </div>
+
+                <div class="settings-section">
+                    <h3>{T::WidgetAppearance.text()}</h3>
+                    <label class="settings-row">
+                        <span>{T::PrimaryColor.text()}</span>
+                        <input
+                            type="color"
+                            prop:value=move || {
+                                let color = settings.get().widget_color;
+                                if color.is_empty() { DEFAULT_WIDGET_COLOR.to_string() } else { color }
+                            }
+                            on:change=move |e| {
+                                let color = event_target_value(&e);
+                                set_settings.update(|s| s.widget_color = color);
+                            }
+                        />
+                    </label>
+                    <label class="settings-row">
+                        <span>{T::Position.text()}</span>
+                        <select on:change=move |e| {
+                            let position = event_target_value(&e);
+                            set_settings.update(|s| s.widget_position = position);
+                        }>
+                            <option value="right" selected=move || settings.get().widget_position != "left">{T::PositionRight.text()}</option>
+                            <option value="left" selected=move || settings.get().widget_position == "left">{T::PositionLeft.text()}</option>
+                        </select>
+                    </label>
+                    <label class="settings-row">
+                        <span>{T::WidgetColorScheme.text()}</span>
+                        <select on:change=move |e| {
+                            let scheme = event_target_value(&e);
+                            set_settings.update(|s| s.widget_theme = scheme);
+                        }>
+                            {ColorScheme::ALL.into_iter().map(|scheme| view! {
+                                <option
+                                    value=scheme.code()
+                                    selected=move || ColorScheme::parse(&settings.get().widget_theme).unwrap_or_default() == scheme
+                                >
+                                    {scheme_label(scheme)}
+                                </option>
+                            }).collect_view()}
+                        </select>
+                    </label>
+                    <label class="settings-row">
+                        <span>{T::WidgetTitle.text()}</span>
+                        <input
+                            type="text"
+                            class="settings-wide"
+                            placeholder=T::WidgetTitlePlaceholder.text()
+                            prop:value=move || settings.get().widget_title
+                            on:change=move |e| {
+                                let title = event_target_value(&e).trim().to_string();
+                                set_settings.update(|s| s.widget_title = title);
+                            }
+                        />
+                    </label>
+                    <label class="settings-row settings-column">
+                        <span>{T::EmbedCode.text()}</span>
+                        <textarea rows="2" readonly=true>
+                            {move || embed_snippet(&shop.get_value())}
+                        </textarea>
+                    </label>
+                    <label class="settings-row settings-column">
+                        <span>{T::AllowedOrigins.text()}</span>
+                        <textarea
+                            rows="3"
+                            prop:value=move || settings.get().allowed_domains.join("\n")
+                            on:change=move |e| {
+                                let domains = event_target_value(&e)
+                                    .lines()
+                                    .map(|d| d.trim().to_ascii_lowercase())
+                                    .filter(|d| !d.is_empty())
+                                    .collect();
+                                set_settings.update(|s| s.allowed_domains = domains);
+                            }
+                        ></textarea>
+                    </label>
+                </div>
+
+                <button class="settings-save" on:click=move |_| submit(Some(settings.get_untracked()))>
+                    {T::SaveSettings.text()}
+                </button>
+
+                <Show when=move || !is_agent_session(&pin.get_value())>
+                    <InviteSection shop_id=shop.get_value() admin_pin=pin.get_value()/>
+                    <IdentitySection shop_id=shop.get_value() admin_pin=pin.get_value()/>
+                </Show>
+            </div>
+        </div>
+    }
+}
+
+const DEFAULT_WIDGET_COLOR: &str = "#3390EC";
+
+fn embed_snippet(shop_id: &str) -> String {
+    format!("<script src=\"{}/widget.js?shop_id={}\" async></script>", API_BASE, shop_id)
+}
+
+fn scheme_label(scheme: ColorScheme) -> &'static str {
+    match scheme {
+        ColorScheme::Auto => T::SchemeAuto.text(),
+        ColorScheme::Light => T::SchemeLight.text(),
+        ColorScheme::Dark => T::SchemeDark.text(),
+    }
+}
+
+fn hours_for(settings: &ShopSettings, weekday: u32) -> Option<BusinessHours> {
+    settings.business_hours.iter().find(|h| h.weekday == weekday).cloned()
+}
+
+fn set_hours(set_settings: WriteSignal<ShopSettings>, weekday: u32, f: impl FnOnce(&mut BusinessHours)) {
+    set_settings.update(|s| {
+        if let Some(h) = s.business_hours.iter_mut().find(|h| h.weekday == weekday) {
+            f(h);
+        }
+    });
+}
+
+// Phút trong ngày <-> "HH:MM"
+pub(crate) fn format_minute(minute: u32) -> String {
+    format!("{:02}:{:02}", minute / 60, minute % 60)
+}
+
+pub(crate) fn parse_minute(value: &str) -> u32 {
+    let mut parts = value.split(':').map(|p| p.parse::<u32>().unwrap_or(0));
+    let hour = parts.next().unwrap_or(0).min(24);
+    let minute = parts.next().unwrap_or(0).min(59);
+    (hour * 60 + minute).min(24 * 60)
+}
+
+pub(crate) async fn post_settings(req: SettingsRequest) -> Result<SettingsResponse, String> {
+    let resp = Request::post(&format!("{}/settings", API_BASE))
+        .header("Content-Type", "application/octet-stream")
+        .body(req.encode_to_vec())
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let bytes = resp.binary().await.map_err(|e| e.to_string())?;
+    SettingsResponse::decode(&bytes[..]).map_err(|e| e.to_string())
+}
@@ -0,0 +1,208 @@
+use leptos::prelude::*;
+use turbochat_shared::{ws_envelope, WsEnvelope, PROTOCOL_VERSION};
+use prost::Message as ProstMessage;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use web_sys::{BinaryType, MessageEvent, WebSocket};
+use std::collections::HashMap;
+
+use crate::app::WS_BASE;
+use crate::i18n::T;
+
+// ============================================================================
+// NHIỀU SHOP - mỗi shop một phiên (shop + PIN), chuyển qua lại trên header
+// ============================================================================
+
+const SESSIONS_KEY: &str = "turbochat_admin_sessions";
+const ACTIVE_KEY: &str = "turbochat_admin_active";
+// Shop nền mất kết nối → thử lại sau
+const WATCH_RETRY_MS: i32 = 5_000;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AdminSession {
+    pub shop_id: String,
+    pub shop_name: String,
+    pub admin_pin: String,
+}
+
+fn storage() -> Option<web_sys::Storage> {
+    web_sys::window().and_then(|w| w.local_storage().ok().flatten())
+}
+
+/// Các phiên đã lưu + shop đang mở (chuyển dữ liệu đăng nhập kiểu cũ nếu có)
+pub fn load_sessions() -> (Vec<AdminSession>, Option<String>) {
+    let Some(storage) = storage() else { return (Vec::new(), None) };
+    let mut sessions: Vec<AdminSession> = storage.get_item(SESSIONS_KEY).ok().flatten()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+    let mut active = storage.get_item(ACTIVE_KEY).ok().flatten();
+
+    if let (Some(shop_id), Some(shop_name), Some(admin_pin)) = (
+        storage.get_item("turbochat_admin_shop").ok().flatten(),
+        storage.get_item("turbochat_admin_name").ok().flatten(),
+        storage.get_item("turbochat_admin_pin").ok().flatten(),
+    ) {
+        if !sessions.iter().any(|s| s.shop_id == shop_id) {
+            sessions.push(AdminSession { shop_id: shop_id.clone(), shop_name, admin_pin });
+        }
+        active.get_or_insert(shop_id);
+        for key in ["turbochat_admin_shop", "turbochat_admin_name", "turbochat_admin_pin"] {
+            let _ = storage.remove_item(key);
+        }
+        save_sessions(&sessions, active.as_deref());
+    }
+
+    let active = active.filter(|id| sessions.iter().any(|s| &s.shop_id == id));
+    (sessions, active)
+}
+
+pub fn save_sessions(sessions: &[AdminSession], active: Option<&str>) {
+    let Some(storage) = storage() else { return };
+    if let Ok(raw) = serde_json::to_string(sessions) {
+        let _ = storage.set_item(SESSIONS_KEY, &raw);
+    }
+    let _ = match active {
+        Some(id) => storage.set_item(ACTIVE_KEY, id),
+        None => storage.remove_item(ACTIVE_KEY),
+    };
+}
+
+// ============================================================================
+// THEO DÕI SHOP NỀN - một WS cho mỗi shop không mở, chỉ đếm tin mới của khách
+// ============================================================================
+
+#[derive(Clone)]
+struct SendWs(WebSocket);
+unsafe impl Send for SendWs {}
+unsafe impl Sync for SendWs {}
+
+#[derive(Clone, Copy)]
+pub struct ShopWatchers {
+    sockets: StoredValue<HashMap<String, SendWs>>,
+    // Shop cần chạy nền (chặn lần thử lại của shop vừa được mở / đăng xuất)
+    wanted: StoredValue<Vec<String>>,
+    /// Tin chưa đọc của các shop đang chạy nền
+    pub unread: RwSignal<HashMap<String, u32>>,
+}
+
+impl ShopWatchers {
+    pub fn new() -> Self {
+        Self {
+            sockets: StoredValue::new(HashMap::new()),
+            wanted: StoredValue::new(Vec::new()),
+            unread: RwSignal::new(HashMap::new()),
+        }
+    }
+
+    /// Mở WS cho shop nền mới, đóng WS của shop vừa được mở / đã đăng xuất
+    pub fn sync(self, sessions: &[AdminSession], active: Option<&str>) {
+        let wanted: Vec<&AdminSession> = sessions.iter().filter(|s| Some(s.shop_id.as_str()) != active).collect();
+        self.wanted.set_value(wanted.iter().map(|s| s.shop_id.clone()).collect());
+
+        let stale: Vec<String> = self.sockets.with_value(|sockets| {
+            sockets.keys().filter(|id| !wanted.iter().any(|s| &s.shop_id == *id)).cloned().collect()
+        });
+        for shop_id in stale {
+            if let Some(ws) = self.sockets.try_update_value(|sockets| sockets.remove(&shop_id)).flatten() {
+                let _ = ws.0.close();
+            }
+        }
+        if let Some(id) = active {
+            self.unread.update(|u| { u.remove(id); });
+        }
+        self.unread.update(|u| u.retain(|id, _| sessions.iter().any(|s| &s.shop_id == id)));
+
+        for session in wanted {
+            if !self.sockets.with_value(|sockets| sockets.contains_key(&session.shop_id)) {
+                self.watch(session.clone());
+            }
+        }
+    }
+
+    fn watch(self, session: AdminSession) {
+        let url = format!("{}/ws?shop_id={}&admin_pin={}&v={}", WS_BASE,
+            js_sys::encode_uri_component(&session.shop_id),
+            js_sys::encode_uri_component(&session.admin_pin), PROTOCOL_VERSION);
+        let Ok(ws) = WebSocket::new(&url) else { return };
+        ws.set_binary_type(BinaryType::Arraybuffer);
+
+        let key = session.shop_id.clone();
+        let shop_id = session.shop_id.clone();
+        let on_message = Closure::wrap(Box::new(move |event: MessageEvent| {
+            let Ok(buf) = event.data().dyn_into::<js_sys::ArrayBuffer>() else { return };
+            let bytes = js_sys::Uint8Array::new(&buf).to_vec();
+            let Ok(envelope) = WsEnvelope::decode(&bytes[..]) else { return };
+            if let Some(ws_envelope::Frame::Message(msg)) = envelope.frame {
+                if msg.sender_type == "guest" {
+                    self.unread.update(|u| *u.entry(shop_id.clone()).or_insert(0) += 1);
+                }
+            }
+        }) as Box<dyn FnMut(MessageEvent)>);
+        ws.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        on_message.forget();
+
+        // Mất kết nối → thử lại nếu shop vẫn đang chạy nền (không phải đã đóng chủ động)
+        let ws_clone = ws.clone();
+        let on_close = Closure::wrap(Box::new(move |_: JsValue| {
+            let still_watched = self.sockets.with_value(|sockets| {
+                sockets.get(&session.shop_id).is_some_and(|s| s.0 == ws_clone)
+            });
+            if !still_watched { return; }
+            self.sockets.update_value(|sockets| { sockets.remove(&session.shop_id); });
+            let session = session.clone();
+            let retry = Closure::once(Box::new(move || {
+                let wanted = self.wanted.with_value(|w| w.contains(&session.shop_id));
+                let open = self.sockets.with_value(|sockets| sockets.contains_key(&session.shop_id));
+                if wanted && !open {
+                    self.watch(session);
+                }
+            }) as Box<dyn FnOnce()>);
+            let _ = web_sys::window().unwrap().set_timeout_with_callback_and_timeout_and_arguments_0(
+                retry.as_ref().unchecked_ref(), WATCH_RETRY_MS);
+            retry.forget();
+        }) as Box<dyn FnMut(JsValue)>);
+        ws.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+        on_close.forget();
+
+        self.sockets.update_value(|sockets| { sockets.insert(key, SendWs(ws)); });
+    }
+}
+
+// ============================================================================
+// SHOP SWITCHER - dropdown trên header Dashboard
+// ============================================================================
+#[component]
+pub fn ShopSwitcher(
+    sessions: RwSignal<Vec<AdminSession>>,
+    active: RwSignal<Option<String>>,
+    adding_shop: RwSignal<bool>,
+    unread: RwSignal<HashMap<String, u32>>,
+) -> impl IntoView {
+    let label = move |s: &AdminSession| {
+        match unread.with(|u| u.get(&s.shop_id).copied().unwrap_or(0)) {
+            0 => s.shop_name.clone(),
+            n => format!("{} ({})", s.shop_name, n),
+        }
+    };
+
+    view! {
+        <select
+            class="shop-switcher"
+            title=T::SwitchShop.text()
+            on:change=move |e| {
+                let value = event_target_value(&e);
+                if value.is_empty() {
+                    adding_shop.set(true);
+                } else {
+                    active.set(Some(value));
+                }
+            }
+        >
+            {move || sessions.get().into_iter().map(|s| {
+                let selected = active.with(|a| a.as_deref() == Some(s.shop_id.as_str()));
+                view! { <option value=s.shop_id.clone() selected=selected>{label(&s)}</option> }
+            }).collect_view()}
+            <option value="">{T::AddShop.text()}</option>
+        </select>
+    }
+}
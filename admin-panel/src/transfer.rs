@@ -0,0 +1,122 @@
+use leptos::prelude::*;
+use turbochat_shared::{AgentSummary, ConversationTransfer, Guest, TransferRequest};
+use prost::Message as ProstMessage;
+use wasm_bindgen_futures::spawn_local;
+use gloo_net::http::Method;
+
+use crate::app::admin_request;
+use crate::bulk::load_agents;
+use crate::i18n::T;
+
+/// "Lan → Nam" của ghi chú bàn giao (tên rỗng = chủ shop)
+pub(crate) fn transfer_label(transfer: &ConversationTransfer) -> String {
+    let name = |name: &str| if name.is_empty() { T::ShopOwner.text().to_string() } else { name.to_string() };
+    T::TransferredBy.fill(&[&name(&transfer.from_name), &name(&transfer.to_name)])
+}
+
+// ============================================================================
+// CHUYỂN HỘI THOẠI - chọn người nhận + ghi chú bàn giao bắt buộc, POST /guests/:id/transfer.
+// Server đổi người phụ trách, lưu ghi chú (chỉ admin thấy) và báo người nhận qua WS
+// ============================================================================
+#[component]
+pub fn TransferPanel(
+    shop_id: String,
+    admin_pin: String,
+    guest_id: ReadSignal<u64>,
+    // agent_id của admin đang đăng nhập - không tự chuyển cho mình
+    me: Signal<String>,
+    on_done: impl Fn(Guest) + 'static + Copy,
+    on_close: impl Fn() + 'static + Copy,
+) -> impl IntoView {
+    let (status, set_status) = signal(String::new());
+    let agents = RwSignal::new(Vec::<AgentSummary>::new());
+    let to_agent = RwSignal::new(String::new());
+    let note = RwSignal::new(String::new());
+    let sending = RwSignal::new(false);
+
+    let shop = StoredValue::new(shop_id);
+    let pin = StoredValue::new(admin_pin);
+
+    spawn_local(async move {
+        agents.set(load_agents(&shop.get_value(), &pin.get_value()).await);
+    });
+
+    // Người nhận: mọi nhân viên trừ chính mình
+    let others = move || {
+        let me = me.get();
+        agents.get().into_iter().filter(|a| a.agent_id != me).collect::<Vec<_>>()
+    };
+
+    let can_send = move || !sending.get() && !to_agent.with(String::is_empty) && !note.with(|n| n.trim().is_empty());
+
+    let submit = move |_| {
+        if !can_send() {
+            return;
+        }
+        let gid = guest_id.get_untracked();
+        let req = TransferRequest { to_agent: to_agent.get_untracked(), note: note.get_untracked() };
+        sending.set(true);
+        set_status.set(T::Sending.text().to_string());
+        spawn_local(async move {
+            let path = format!("/guests/{}/transfer", gid);
+            let result = match admin_request(Method::POST, &path, &shop.get_value(), &pin.get_value())
+                .header("Content-Type", "application/octet-stream")
+                .body(req.encode_to_vec())
+            {
+                Ok(r) => r.send().await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(resp) if resp.ok() => {
+                    let bytes = resp.binary().await.unwrap_or_default();
+                    if let Ok(guest) = Guest::decode(&bytes[..]) {
+                        on_done(guest);
+                    }
+                    on_close();
+                }
+                Ok(resp) => set_status.set(T::HttpError.fill(&[&resp.status()])),
+                Err(e) => set_status.set(T::NetworkError.fill(&[&e])),
+            }
+            sending.set(false);
+        });
+    };
+
+    view! {
+        <div class="transfer-panel" role="dialog" aria-label=T::TransferTitle.text()>
+            <div class="transfer-row">
+                <b>{T::TransferTitle.text()}</b>
+                <select
+                    aria-label=T::TransferTo.text()
+                    prop:value=move || to_agent.get()
+                    on:change=move |e| to_agent.set(event_target_value(&e))
+                >
+                    <option value="">{T::TransferTo.text()}</option>
+                    <Show when=move || me.get() != "owner">
+                        <option value="owner">{T::ShopOwner.text()}</option>
+                    </Show>
+                    <For
+                        each=others
+                        key=|a| a.agent_id.clone()
+                        children=|a: AgentSummary| {
+                            let label = if a.name.is_empty() { a.email.clone() } else { a.name.clone() };
+                            view! { <option value=a.agent_id>{label}</option> }
+                        }
+                    />
+                </select>
+                <button class="settings-close" aria-label=T::Close.text() on:click=move |_| on_close()>"✕"</button>
+            </div>
+            <textarea
+                class="note-input"
+                rows="3"
+                dir="auto"
+                placeholder=T::HandoverPlaceholder.text()
+                prop:value=move || note.get()
+                on:input=move |e| note.set(event_target_value(&e))
+            ></textarea>
+            <div class="transfer-row">
+                <span class="settings-hint" role="status">{move || status.get()}</span>
+                <button class="settings-save" prop:disabled=move || !can_send() on:click=submit>{T::Transfer.text()}</button>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,107 @@
+use leptos::prelude::*;
+use turbochat_shared::Attachment;
+use prost::Message as ProstMessage;
+use gloo_net::http::Request;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::app::API_BASE;
+use crate::i18n::T;
+
+// ============================================================================
+// GỬI ẢNH TỪ Ô SOẠN TIN - dán ảnh (Ctrl+V) hoặc kéo thả file vào khung chat
+// File đi qua POST /attachments (server nhận diện loại file, quét virus) rồi gửi tin kèm Attachment.
+// Trong lúc tải lên tin hiện ảnh xem trước từ object URL của trình duyệt
+// ============================================================================
+
+/// File ảnh trong clipboard (sự kiện paste) - text thường thì để ô nhập tự xử lý
+pub(crate) fn pasted_images(event: &web_sys::Event) -> Vec<web_sys::File> {
+    let Some(event) = event.dyn_ref::<web_sys::ClipboardEvent>() else { return Vec::new() };
+    files(event.clipboard_data()).into_iter().filter(|f| f.type_().starts_with("image/")).collect()
+}
+
+/// File được thả vào khung chat
+pub(crate) fn dropped_files(event: &web_sys::DragEvent) -> Vec<web_sys::File> {
+    files(event.data_transfer())
+}
+
+fn files(data: Option<web_sys::DataTransfer>) -> Vec<web_sys::File> {
+    let Some(list) = data.and_then(|d| d.files()) else { return Vec::new() };
+    (0..list.length()).filter_map(|i| list.get(i)).collect()
+}
+
+/// Ảnh xem trước trong lúc tải lên (thu hồi bằng revoke_preview khi xong)
+pub(crate) fn preview_url(file: &web_sys::File) -> Option<String> {
+    file.type_().starts_with("image/").then(|| web_sys::Url::create_object_url_with_blob(file).ok()).flatten()
+}
+
+pub(crate) fn revoke_preview(url: &str) {
+    let _ = web_sys::Url::revoke_object_url(url);
+}
+
+/// Tải file lên cuộc chat của guest; Err = câu báo lỗi cho admin
+pub(crate) async fn upload(shop_id: &str, guest_id: u64, file: &web_sys::File) -> Result<Attachment, String> {
+    let buffer = JsFuture::from(file.array_buffer()).await.map_err(|_| T::FileUnreadable.text().to_string())?;
+    let bytes = js_sys::Uint8Array::new(buffer.unchecked_ref()).to_vec();
+    let url = format!(
+        "{}/attachments?shop_id={}&guest_id={}&name={}",
+        API_BASE,
+        js_sys::encode_uri_component(shop_id),
+        guest_id,
+        js_sys::encode_uri_component(&file.name()),
+    );
+    let resp = Request::post(&url)
+        .header("Content-Type", "application/octet-stream")
+        .body(bytes)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(|_| T::ServerUnreachable.text().to_string())?;
+    let body = resp.binary().await.unwrap_or_default();
+    match resp.status() {
+        200 => Attachment::decode(&body[..]).map_err(|_| T::InvalidResponse.text().to_string()),
+        403 => Err(T::AttachmentsForbidden.text().to_string()),
+        413 => Err(T::FileTooLarge.text().to_string()),
+        415 => Err(T::FileTypeUnsupported.text().to_string()),
+        422 => {
+            let signature = Attachment::decode(&body[..]).ok().and_then(|a| a.scan).map(|s| s.signature).unwrap_or_default();
+            Err(T::FileInfected.fill(&[&signature]))
+        }
+        status => Err(T::UploadFailed.fill(&[&status])),
+    }
+}
+
+/// File đính kèm trong bong bóng tin: ảnh hiện thu nhỏ, tin thoại có trình phát, file khác là link tải
+pub(crate) fn attachments_view(attachments: Vec<Attachment>) -> impl IntoView {
+    (!attachments.is_empty()).then(|| view! {
+        <div class="message-attachments">
+            {attachments.into_iter().map(|a| {
+                if a.is_image() {
+                    let thumb = if a.thumbnail_url.is_empty() { a.url.clone() } else { a.thumbnail_url.clone() };
+                    // Ảnh thu nhỏ chưa tạo xong (vừa tải lên) → dùng ảnh gốc
+                    let full = a.url.clone();
+                    let fallback = move |e: web_sys::ErrorEvent| {
+                        if let Some(img) = e.target().and_then(|t| t.dyn_into::<web_sys::HtmlImageElement>().ok()) {
+                            if img.src() != full {
+                                img.set_src(&full);
+                            }
+                        }
+                    };
+                    view! {
+                        <a href=a.url target="_blank" rel="noopener">
+                            <img class="message-image" src=thumb alt=a.file_name.clone() on:error=fallback/>
+                        </a>
+                    }.into_any()
+                } else if a.is_audio() {
+                    view! {
+                        <audio class="message-audio" controls preload="metadata" src=a.url></audio>
+                    }.into_any()
+                } else {
+                    view! {
+                        <a class="message-file" href=a.url target="_blank" rel="noopener">{format!("📎 {}", a.file_name)}</a>
+                    }.into_any()
+                }
+            }).collect_view()}
+        </div>
+    })
+}
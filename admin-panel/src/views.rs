@@ -0,0 +1,207 @@
+use leptos::prelude::*;
+use turbochat_shared::{AgentSummary, AssigneeFilter, ConversationStatus, Guest, SavedView, SavedViewList};
+use prost::Message as ProstMessage;
+use wasm_bindgen_futures::spawn_local;
+use gloo_net::http::Method;
+
+use crate::app::admin_request;
+use crate::bulk::load_agents;
+use crate::i18n::T;
+
+/// GET /views - tab đã lưu của admin đang đăng nhập (lỗi → không có tab, danh sách vẫn dùng được)
+pub(crate) async fn load_views(shop_id: &str, admin_pin: &str) -> Option<Vec<SavedView>> {
+    let resp = admin_request(Method::GET, "/views", shop_id, admin_pin).send().await.ok()?;
+    if !resp.ok() {
+        return None;
+    }
+    let bytes = resp.binary().await.ok()?;
+    SavedViewList::decode(&bytes[..]).ok().map(|list| list.views)
+}
+
+/// Hội thoại có khớp mọi điều kiện của tab không. Guest chưa có trong /guests (mới nhắn) → đang mở, chưa giao
+pub(crate) fn view_matches(view: &SavedView, guest: Option<&Guest>, unread: u32, me: &str) -> bool {
+    let status = guest.map(|g| g.status()).unwrap_or(ConversationStatus::Open);
+    let assigned_to = guest.map(|g| g.assigned_to.as_str()).unwrap_or("");
+    let assignee_ok = match view.assignee() {
+        AssigneeFilter::Any => true,
+        AssigneeFilter::Unassigned => assigned_to.is_empty(),
+        AssigneeFilter::Mine => !me.is_empty() && assigned_to == me,
+        AssigneeFilter::Agent => assigned_to == view.agent_id,
+    };
+    assignee_ok
+        && view.status.is_none_or(|_| view.status() == status)
+        && (view.tag.is_empty() || guest.is_some_and(|g| g.tags.iter().any(|t| t.eq_ignore_ascii_case(&view.tag))))
+        && (!view.unread_only || unread > 0)
+        && (!view.awaiting_reply || guest.is_some_and(|g| g.awaiting_since > 0))
+}
+
+// id tab mới - chỉ cần khác các tab của cùng admin
+fn new_view_id() -> String {
+    format!("{:012x}", (js_sys::Math::random() * (1u64 << 48) as f64) as u64)
+}
+
+// ============================================================================
+// VIEW EDITOR - tạo / sửa / xoá tab lọc hội thoại. Lưu cả danh sách bằng PUT /views
+// ============================================================================
+#[component]
+pub fn ViewEditor(
+    shop_id: String,
+    admin_pin: String,
+    views: RwSignal<Vec<SavedView>>,
+    // Tab đang sửa (view_id rỗng = tab mới)
+    editing: SavedView,
+    on_saved: impl Fn(Option<String>) + 'static + Copy,
+    on_close: impl Fn() + 'static + Copy,
+) -> impl IntoView {
+    let (status, set_status) = signal(String::new());
+    let agents = RwSignal::new(Vec::<AgentSummary>::new());
+    let is_new = editing.view_id.is_empty();
+    let draft = RwSignal::new(editing);
+    let saving = RwSignal::new(false);
+
+    let shop = StoredValue::new(shop_id);
+    let pin = StoredValue::new(admin_pin);
+
+    spawn_local(async move {
+        agents.set(load_agents(&shop.get_value(), &pin.get_value()).await);
+    });
+
+    // Thay danh sách trên server; thành công → cập nhật tab + chọn tab vừa lưu (None = vừa xoá)
+    let persist = move |next: Vec<SavedView>, selected: Option<String>| {
+        saving.set(true);
+        set_status.set(String::new());
+        spawn_local(async move {
+            let body = SavedViewList { views: next }.encode_to_vec();
+            let result = match admin_request(Method::PUT, "/views", &shop.get_value(), &pin.get_value())
+                .header("Content-Type", "application/octet-stream")
+                .body(body)
+            {
+                Ok(r) => r.send().await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(resp) if resp.ok() => {
+                    let bytes = resp.binary().await.unwrap_or_default();
+                    match SavedViewList::decode(&bytes[..]) {
+                        Ok(list) => {
+                            views.set(list.views);
+                            on_saved(selected);
+                            on_close();
+                        }
+                        Err(_) => set_status.set(T::InvalidResponse.text().to_string()),
+                    }
+                }
+                Ok(resp) => set_status.set(T::HttpError.fill(&[&resp.status()])),
+                Err(e) => set_status.set(T::NetworkError.fill(&[&e])),
+            }
+            saving.set(false);
+        });
+    };
+
+    let save = move |_| {
+        let mut view = draft.get_untracked();
+        if view.name.trim().is_empty() {
+            set_status.set(T::ViewNameRequired.text().to_string());
+            return;
+        }
+        if view.view_id.is_empty() {
+            view.view_id = new_view_id();
+        }
+        let mut next = views.get_untracked();
+        match next.iter_mut().find(|v| v.view_id == view.view_id) {
+            Some(existing) => *existing = view.clone(),
+            None => next.push(view.clone()),
+        }
+        persist(next, Some(view.view_id));
+    };
+
+    let delete = move |_| {
+        let window = web_sys::window().unwrap();
+        if !window.confirm_with_message(T::ViewDeleteConfirm.text()).unwrap_or(false) { return; }
+        let id = draft.with_untracked(|d| d.view_id.clone());
+        persist(views.get_untracked().into_iter().filter(|v| v.view_id != id).collect(), None);
+    };
+
+    // Ô chọn người phụ trách: "any" / "unassigned" / "mine" / agent_id
+    let assignee_value = move || draft.with(|d| match d.assignee() {
+        AssigneeFilter::Any => String::new(),
+        AssigneeFilter::Unassigned => "-".to_string(),
+        AssigneeFilter::Mine => "@me".to_string(),
+        AssigneeFilter::Agent => d.agent_id.clone(),
+    });
+    let set_assignee = move |value: String| draft.update(|d| {
+        let (filter, agent) = match value.as_str() {
+            "" => (AssigneeFilter::Any, String::new()),
+            "-" => (AssigneeFilter::Unassigned, String::new()),
+            "@me" => (AssigneeFilter::Mine, String::new()),
+            _ => (AssigneeFilter::Agent, value.clone()),
+        };
+        d.set_assignee(filter);
+        d.agent_id = agent;
+    });
+    let status_value = move || draft.with(|d| d.status.map(|_| d.status().as_db_str().to_string()).unwrap_or_default());
+    let set_status_filter = move |value: String| draft.update(|d| {
+        d.status = match value.as_str() {
+            "open" => Some(ConversationStatus::Open as i32),
+            "closed" => Some(ConversationStatus::Closed as i32),
+            "archived" => Some(ConversationStatus::Archived as i32),
+            _ => None,
+        };
+    });
+
+    view! {
+        <div class="view-editor" role="dialog" aria-label=T::ViewEditorTitle.text()>
+            <input
+                type="text"
+                maxlength="40"
+                placeholder=T::ViewName.text()
+                aria-label=T::ViewName.text()
+                prop:value=move || draft.with(|d| d.name.clone())
+                on:input=move |e| draft.update(|d| d.name = event_target_value(&e))
+            />
+            <input
+                type="text"
+                placeholder=T::ViewTag.text()
+                aria-label=T::ViewTag.text()
+                prop:value=move || draft.with(|d| d.tag.clone())
+                on:input=move |e| draft.update(|d| d.tag = event_target_value(&e))
+            />
+            <select aria-label=T::ViewAssignee.text() prop:value=assignee_value on:change=move |e| set_assignee(event_target_value(&e))>
+                <option value="">{T::ViewAnyAssignee.text()}</option>
+                <option value="-">{T::ViewUnassigned.text()}</option>
+                <option value="@me">{T::ViewMine.text()}</option>
+                <option value="owner">{T::ShopOwner.text()}</option>
+                <For
+                    each=move || agents.get()
+                    key=|a| a.agent_id.clone()
+                    children=|a: AgentSummary| {
+                        let label = if a.name.is_empty() { a.email.clone() } else { a.name.clone() };
+                        view! { <option value=a.agent_id>{label}</option> }
+                    }
+                />
+            </select>
+            <select aria-label=T::ViewStatus.text() prop:value=status_value on:change=move |e| set_status_filter(event_target_value(&e))>
+                <option value="">{T::ViewAnyStatus.text()}</option>
+                <option value="open">{T::FilterOpen.text()}</option>
+                <option value="closed">{T::FilterClosed.text()}</option>
+                <option value="archived">{T::ViewArchived.text()}</option>
+            </select>
+            <label>
+                <input type="checkbox" prop:checked=move || draft.with(|d| d.unread_only) on:change=move |e| draft.update(|d| d.unread_only = event_target_checked(&e))/>
+                {T::ViewUnreadOnly.text()}
+            </label>
+            <label>
+                <input type="checkbox" prop:checked=move || draft.with(|d| d.awaiting_reply) on:change=move |e| draft.update(|d| d.awaiting_reply = event_target_checked(&e))/>
+                {T::ViewAwaiting.text()}
+            </label>
+            <div class="view-editor-actions">
+                <span class="settings-hint" role="status">{move || status.get()}</span>
+                {(!is_new).then(|| view! {
+                    <button class="conversation-action" prop:disabled=move || saving.get() on:click=delete>{T::Delete.text()}</button>
+                })}
+                <button class="conversation-action" on:click=move |_| on_close()>{T::Cancel.text()}</button>
+                <button class="settings-save" prop:disabled=move || saving.get() on:click=save>{T::SaveView.text()}</button>
+            </div>
+        </div>
+    }
+}
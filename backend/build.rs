@@ -1,6 +1,8 @@
 fn main() {
     let mut config = prost_build::Config::new();
     config.bytes(&["."]); 
+    // Guest lớn hơn hẳn các frame khác → box để WsEnvelope không phình
+    config.boxed(".turbochat.v1.WsEnvelope.frame.guest");
     
     config.compile_protos(&["proto/chat.proto"], &["proto/"]).unwrap();
 }
\ No newline at end of file
@@ -0,0 +1,53 @@
+// Admin đang xem / đang gõ trong hội thoại ("Nam đang xem") - chỉ relay qua broker tới các admin khác, không lưu
+use crate::agents;
+use crate::contract::{ActivityKind, AgentActivity, WsEnvelope};
+use crate::websocket::{publish_envelope, WebSocketState};
+
+/// Người đứng sau một kết nối admin - ghi vào mọi frame hoạt động của kết nối đó
+#[derive(Clone, Debug)]
+pub struct Viewer {
+    pub device_id: String,
+    pub agent_id: String,
+    pub agent_name: String,
+}
+
+/// Tra tên nhân viên theo session token; PIN chủ shop → "owner", tên rỗng (client hiện "Chủ shop")
+pub async fn viewer(state: &WebSocketState, shop_id: &str, credential: &str, device_id: &str) -> Viewer {
+    let agent_id = agents::session_agent(credential).unwrap_or(agents::OWNER_ID).to_string();
+    let mut agent_name = String::new();
+    if agent_id != agents::OWNER_ID {
+        match state.repo.get_agent(shop_id, &agent_id).await {
+            Ok(Some(agent)) => agent_name = if agent.name.trim().is_empty() { agent.email } else { agent.name },
+            Ok(None) => {}
+            Err(e) => eprintln!("❌ Load agent {} failed: {:?}", agent_id, e),
+        }
+    }
+    Viewer { device_id: device_id.to_string(), agent_id, agent_name }
+}
+
+/// Frame hoạt động từ client: chỉ tin guest_id + kind, người gửi lấy theo kết nối
+pub async fn relay(state: &WebSocketState, shop_id: &str, viewer: &Viewer, activity: AgentActivity) {
+    if activity.guest_id == 0 {
+        return;
+    }
+    publish(state, shop_id, viewer, activity.guest_id, activity.kind()).await;
+}
+
+/// Kết nối đóng → các admin khác bỏ ngay "đang xem" thay vì chờ quá hạn
+pub async fn left(state: &WebSocketState, shop_id: &str, viewer: &Viewer) {
+    publish(state, shop_id, viewer, 0, ActivityKind::Idle).await;
+}
+
+async fn publish(state: &WebSocketState, shop_id: &str, viewer: &Viewer, guest_id: u64, kind: ActivityKind) {
+    let mut activity = AgentActivity {
+        guest_id,
+        device_id: viewer.device_id.clone(),
+        agent_id: viewer.agent_id.clone(),
+        agent_name: viewer.agent_name.clone(),
+        ..Default::default()
+    };
+    activity.set_kind(kind);
+    if let Err(e) = publish_envelope(state, &WsEnvelope::agent_activity(shop_id.to_string(), activity)).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+}
@@ -0,0 +1,317 @@
+// ============================================================================
+// NHÂN VIÊN ĐƯỢC MỜI - chủ shop (PIN) mời qua email, nhân viên tự đặt tên + mật khẩu.
+// Link mời: <ADMIN_URL>?shop=<shop_id>&invite=<invite_id>.<hết hạn>.<HMAC> - ký bằng INVITE_SIGNING_KEY,
+// dùng một lần (bảng admin_invites). Nhân viên đăng nhập bằng email + mật khẩu, nhận session token
+// "agt.<agent_id>.<secret>" dùng thay PIN ở mọi chỗ (header x-admin-pin, body admin_pin, WS)
+// ============================================================================
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
+};
+use pbkdf2::pbkdf2_hmac;
+use prost::Message as ProstMessage;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use turbochat_shared::crypto;
+
+use crate::auth::{AdminAuth, OwnerAuth};
+use crate::blob;
+use crate::contract::{AdminAcceptRequest, AdminAuthResponse, AdminInviteRequest, AdminInviteResponse, AgentListResponse, AgentSummary, ContractError};
+use crate::db::{AdminInvite, AgentAccount, AstraRepo, now_us};
+use crate::request_id;
+use crate::state::AppState;
+use crate::websocket;
+
+pub const SESSION_PREFIX: &str = "agt";
+/// agent_id của chủ shop (đăng nhập bằng PIN) - cùng giá trị với Guest.assigned_to
+pub const OWNER_ID: &str = "owner";
+const INVITE_TTL_DAYS: u64 = 7;
+const PBKDF2_ROUNDS: u32 = 100_000;
+const MIN_PASSWORD_CHARS: usize = 8;
+const MAX_NAME_CHARS: usize = 100;
+
+/// Ký / kiểm link mời
+pub struct InviteLinks {
+    signing_key: Vec<u8>,
+    admin_url: String,
+}
+
+impl InviteLinks {
+    /// ADMIN_URL (mặc định PUBLIC_URL/admin/), INVITE_SIGNING_KEY (bắt buộc, giống nhau trên mọi node -
+    /// khoá ngẫu nhiên theo tiến trình thì link mời hỏng ở node khác và sau khi restart → không đặt thì dừng)
+    pub fn from_env() -> Self {
+        let admin_url = std::env::var("ADMIN_URL").ok().filter(|u| !u.is_empty()).unwrap_or_else(|| {
+            let public = std::env::var("PUBLIC_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
+            format!("{}/admin/", public.trim_end_matches('/'))
+        });
+        let signing_key = match std::env::var("INVITE_SIGNING_KEY") {
+            Ok(key) if !key.is_empty() => key.into_bytes(),
+            _ => panic!("❌ INVITE_SIGNING_KEY not set (use the same random string on every node, e.g. `openssl rand -hex 32`)"),
+        };
+        Self { signing_key, admin_url }
+    }
+
+    fn token(&self, shop_id: &str, invite_id: &str, expires_secs: u64) -> String {
+        let signature = crypto::sign_hex(&self.signing_key, signed_data(shop_id, invite_id, expires_secs).as_bytes());
+        format!("{}.{}.{}", invite_id, expires_secs, signature)
+    }
+
+    fn url(&self, shop_id: &str, token: &str) -> String {
+        match reqwest::Url::parse_with_params(&self.admin_url, &[("shop", shop_id), ("invite", token)]) {
+            Ok(url) => url.to_string(),
+            Err(_) => format!("{}?shop={}&invite={}", self.admin_url, shop_id, token),
+        }
+    }
+
+    /// invite_id của token còn hạn, đúng chữ ký
+    fn verify(&self, shop_id: &str, token: &str) -> Result<String, InviteError> {
+        let mut parts = token.splitn(3, '.');
+        let (Some(invite_id), Some(expires), Some(signature)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(InviteError::Invalid);
+        };
+        let expires_secs: u64 = expires.parse().map_err(|_| InviteError::Invalid)?;
+        if !crypto::verify_hex(&self.signing_key, signed_data(shop_id, invite_id, expires_secs).as_bytes(), signature) {
+            return Err(InviteError::Invalid);
+        }
+        if expires_secs < now_us() / 1_000_000 {
+            return Err(InviteError::Expired);
+        }
+        Ok(invite_id.to_string())
+    }
+}
+
+enum InviteError {
+    Invalid,
+    Expired,
+}
+
+fn signed_data(shop_id: &str, invite_id: &str, expires_secs: u64) -> String {
+    format!("{}\n{}\n{}", shop_id, invite_id, expires_secs)
+}
+
+// ============================================================================
+// MẬT KHẨU + PHIÊN
+// ============================================================================
+
+/// "pbkdf2-sha256$<vòng>$<salt hex>$<hash hex>"
+fn hash_password(password: &str) -> String {
+    let salt = &blob::random_key()[..16];
+    let mut hash = [0u8; 32];
+    pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, PBKDF2_ROUNDS, &mut hash);
+    format!("pbkdf2-sha256${}${}${}", PBKDF2_ROUNDS, hex::encode(salt), hex::encode(hash))
+}
+
+fn verify_password(password: &str, stored: &str) -> bool {
+    let mut parts = stored.split('$');
+    let (Some("pbkdf2-sha256"), Some(rounds), Some(salt), Some(expected)) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+    let (Ok(rounds), Ok(salt), Ok(expected)) = (rounds.parse::<u32>(), hex::decode(salt), hex::decode(expected)) else {
+        return false;
+    };
+    let mut hash = vec![0u8; expected.len()];
+    pbkdf2_hmac::<Sha256>(password.as_bytes(), &salt, rounds, &mut hash);
+    hash.ct_eq(&expected).into()
+}
+
+fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// Credential là session token của nhân viên (không phải PIN chủ shop)
+pub fn is_session(credential: &str) -> bool {
+    parse_session(credential).is_some()
+}
+
+/// agent_id trong session token của nhân viên
+pub fn session_agent(credential: &str) -> Option<&str> {
+    parse_session(credential).map(|(agent_id, _)| agent_id)
+}
+
+fn parse_session(credential: &str) -> Option<(&str, &str)> {
+    let rest = credential.strip_prefix(SESSION_PREFIX)?.strip_prefix('.')?;
+    let (agent_id, secret) = rest.split_once('.')?;
+    (!agent_id.is_empty() && !secret.is_empty()).then_some((agent_id, secret))
+}
+
+// Phiên mới cho nhân viên (phiên cũ trên máy khác hết hiệu lực)
+async fn start_session(repo: &AstraRepo, shop_id: &str, agent_id: &str) -> Result<String, ContractError> {
+    let secret = hex::encode(blob::random_key());
+    repo.set_agent_session(shop_id, agent_id, &hash_secret(&secret)).await?;
+    Ok(format!("{}.{}.{}", SESSION_PREFIX, agent_id, secret))
+}
+
+async fn shop_name(repo: &AstraRepo, shop_id: &str) -> Result<Option<String>, ContractError> {
+    Ok(repo.get_shop_row(shop_id).await?.map(|row| row["shop_name"].as_str().unwrap_or("").to_string()))
+}
+
+/// PIN chủ shop hoặc session token nhân viên → tên shop
+pub async fn verify_credential(repo: &AstraRepo, shop_id: &str, credential: &str) -> Result<Option<String>, ContractError> {
+    if credential.is_empty() {
+        return Ok(None);
+    }
+    let Some((agent_id, secret)) = parse_session(credential) else {
+        return repo.verify_admin(shop_id, credential).await;
+    };
+    match repo.get_agent(shop_id, agent_id).await? {
+        Some(agent) if !agent.session_hash.is_empty() && bool::from(agent.session_hash.as_bytes().ct_eq(hash_secret(secret).as_bytes())) => shop_name(repo, shop_id).await,
+        _ => Ok(None),
+    }
+}
+
+/// Email + mật khẩu đúng → (tên shop, session token)
+pub async fn login(repo: &AstraRepo, shop_id: &str, email: &str, password: &str) -> Result<Option<(String, String)>, ContractError> {
+    let agents = repo.list_agents(shop_id).await?;
+    let Some(agent) = agents.iter().find(|a| a.email.eq_ignore_ascii_case(email.trim())) else { return Ok(None) };
+    if !verify_password(password, &agent.password_hash) {
+        return Ok(None);
+    }
+    let token = start_session(repo, shop_id, &agent.agent_id).await?;
+    let name = shop_name(repo, shop_id).await?.unwrap_or_default();
+    println!("🙋 Agent {} ({}) signed in to shop {}", agent.agent_id, agent.email, shop_id);
+    Ok(Some((name, token)))
+}
+
+fn is_valid_email(email: &str) -> bool {
+    email.len() <= 254
+        && !email.chars().any(char::is_whitespace)
+        && email.split_once('@').is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.'))
+}
+
+// ============================================================================
+// HANDLERS
+// ============================================================================
+
+// POST /admins/invite - chỉ chủ shop (PIN), gửi link mời qua email và trả lại link
+pub async fn invite_handler(State(state): State<Arc<AppState>>, OwnerAuth(admin): OwnerAuth, body: Bytes) -> impl IntoResponse {
+    let Ok(req) = AdminInviteRequest::decode(&body[..]) else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+    let email = req.email.trim().to_ascii_lowercase();
+    if !is_valid_email(&email) {
+        return invite_error(StatusCode::BAD_REQUEST, "Invalid email");
+    }
+    let (agents, name) = match tokio::try_join!(state.repo.list_agents(&admin.shop_id), shop_name(&state.repo, &admin.shop_id)) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("❌ Load agents for {} failed: {:?}", admin.shop_id, e);
+            return invite_error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string());
+        }
+    };
+    if agents.iter().any(|a| a.email.eq_ignore_ascii_case(&email)) {
+        return invite_error(StatusCode::CONFLICT, "This email is already an agent of the shop");
+    }
+
+    let expires_secs = now_us() / 1_000_000 + INVITE_TTL_DAYS * 86_400;
+    let invite = AdminInvite {
+        invite_id: hex::encode(&blob::random_key()[..8]),
+        email: email.clone(),
+        expires_at: expires_secs * 1_000_000,
+        accepted_at: 0,
+    };
+    if let Err(e) = state.repo.insert_invite(&admin.shop_id, &invite).await {
+        eprintln!("❌ Insert invite failed: {:?}", e);
+        return invite_error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string());
+    }
+    let token = state.invites.token(&admin.shop_id, &invite.invite_id, expires_secs);
+    let invite_url = state.invites.url(&admin.shop_id, &token);
+
+    let shop_name = name.unwrap_or_else(|| admin.shop_id.clone());
+    state.ws_state.notifier.send_invite(&email, &admin.shop_id, &shop_name, &invite_url, INVITE_TTL_DAYS).await;
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "admin.invite", 0, &admin.agent_id, &email).await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("✉️ Invited {} to shop {} (invite {})", email, admin.shop_id, invite.invite_id);
+
+    let resp = AdminInviteResponse { success: true, invite_url, expires_at: invite.expires_at, ..Default::default() };
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+}
+
+// GET /admins - nhân viên của shop (để giao hội thoại); chủ shop không có trong bảng admins
+pub async fn list_agents_handler(State(state): State<Arc<AppState>>, admin: AdminAuth) -> impl IntoResponse {
+    match state.repo.list_agents(&admin.shop_id).await {
+        Ok(agents) => {
+            let agents = agents.into_iter()
+                .map(|a| AgentSummary { agent_id: a.agent_id, name: a.name, email: a.email })
+                .collect();
+            (StatusCode::OK, Bytes::from(AgentListResponse { agents }.encode_to_vec()))
+        }
+        Err(e) => {
+            eprintln!("❌ Load agents for {} failed: {:?}", admin.shop_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new())
+        }
+    }
+}
+
+// POST /admins/accept - nhận lời mời: tạo tài khoản nhân viên, đăng nhập luôn (trả session token)
+pub async fn accept_handler(State(state): State<Arc<AppState>>, body: Bytes) -> impl IntoResponse {
+    let Ok(req) = AdminAcceptRequest::decode(&body[..]) else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+    let invite_id = match state.invites.verify(&req.shop_id, &req.token) {
+        Ok(id) => id,
+        Err(InviteError::Invalid) => return accept_error(StatusCode::BAD_REQUEST, "Invalid invite link"),
+        Err(InviteError::Expired) => return accept_error(StatusCode::GONE, "Invite link has expired"),
+    };
+    let name: String = req.name.trim().chars().take(MAX_NAME_CHARS).collect();
+    if name.is_empty() {
+        return accept_error(StatusCode::BAD_REQUEST, "Name is required");
+    }
+    if req.password.chars().count() < MIN_PASSWORD_CHARS {
+        return accept_error(StatusCode::BAD_REQUEST, "Password must be at least 8 characters");
+    }
+
+    let invite = match state.repo.get_invite(&req.shop_id, &invite_id).await {
+        Ok(Some(invite)) if invite.accepted_at == 0 => invite,
+        Ok(_) => return accept_error(StatusCode::GONE, "Invite has already been used"),
+        Err(e) => return accept_error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    };
+    // Bấm nhận 2 lần / 2 tab cùng lúc → chỉ một lần tạo tài khoản
+    let claim_ttl = INVITE_TTL_DAYS * 86_400;
+    if !websocket::claim_once(&state.ws_state, &format!("invite:{}:{}", req.shop_id, invite_id), claim_ttl).await {
+        return accept_error(StatusCode::GONE, "Invite has already been used");
+    }
+
+    let agent = AgentAccount {
+        agent_id: hex::encode(&blob::random_key()[..8]),
+        email: invite.email.clone(),
+        name,
+        password_hash: hash_password(&req.password),
+        session_hash: String::new(),
+        created_at: now_us(),
+    };
+    let created = async {
+        state.repo.insert_agent(&req.shop_id, &agent).await?;
+        state.repo.accept_invite(&req.shop_id, &invite_id).await?;
+        let token = start_session(&state.repo, &req.shop_id, &agent.agent_id).await?;
+        Ok::<_, ContractError>((shop_name(&state.repo, &req.shop_id).await?.unwrap_or_default(), token))
+    }.await;
+    let (shop_name, session_token) = match created {
+        Ok(created) => created,
+        Err(e) => {
+            eprintln!("❌ Accept invite {} failed: {:?}", invite_id, e);
+            return accept_error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string());
+        }
+    };
+    if let Err(e) = state.repo.insert_audit(&req.shop_id, "admin.invite_accept", 0, &agent.agent_id, &agent.email).await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("🤝 {} joined shop {} as agent {}", agent.email, req.shop_id, agent.agent_id);
+
+    let resp = AdminAuthResponse { success: true, shop_name, session_token, ..Default::default() };
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+}
+
+fn invite_error(status: StatusCode, error: &str) -> (StatusCode, Bytes) {
+    let resp = AdminInviteResponse { success: false, error: error.to_string(), request_id: request_id::current(), ..Default::default() };
+    (status, Bytes::from(resp.encode_to_vec()))
+}
+
+fn accept_error(status: StatusCode, error: &str) -> (StatusCode, Bytes) {
+    let resp = AdminAuthResponse { success: false, error: error.to_string(), request_id: request_id::current(), ..Default::default() };
+    (status, Bytes::from(resp.encode_to_vec()))
+}
@@ -0,0 +1,288 @@
+// Analytics - job tổng hợp thống kê theo ngày + endpoint GET /reports, GET /reports/export.csv
+use axum::{
+    body::{Body, Bytes},
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, Timelike, Utc};
+use prost::Message as ProstMessage;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::auth::AdminAuth;
+use crate::contract::{ContractError, DailyStats, Message, MessageId, ReportResponse};
+use crate::db::AstraRepo;
+use crate::request_id;
+use crate::state::AppState;
+use crate::websocket::{claim_once, WebSocketState};
+
+// Lần chạy đầu tổng hợp lại 30 ngày, các lần sau chỉ hôm qua + hôm nay
+const BACKFILL_DAYS: i64 = 30;
+const REFRESH_DAYS: i64 = 2;
+
+pub async fn analytics_task(state: Arc<WebSocketState>) {
+    let interval_secs = std::env::var("ANALYTICS_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3600);
+    println!("📊 Analytics job started (every {}s)", interval_secs);
+
+    let mut days = BACKFILL_DAYS;
+    loop {
+        // Mỗi lượt chỉ một node tổng hợp (node khác đã chạy thì bỏ, kể cả lượt tổng hợp lại 30 ngày)
+        if !claim_once(&state, "analytics:run", interval_secs).await {
+            days = REFRESH_DAYS;
+            tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+            continue;
+        }
+        match aggregate_all(&state.repo, days).await {
+            Ok(n) => {
+                println!("📊 Analytics aggregated {} shop(s) over {} day(s)", n, days);
+                days = REFRESH_DAYS;
+            }
+            Err(e) => eprintln!("❌ Analytics job failed: {:?}", e),
+        }
+        tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+    }
+}
+
+async fn aggregate_all(repo: &AstraRepo, days: i64) -> Result<usize, ContractError> {
+    let shops = repo.list_shop_ids().await?;
+    for shop_id in &shops {
+        if let Err(e) = aggregate_shop(repo, shop_id, days).await {
+            eprintln!("❌ Analytics for {} failed: {:?}", shop_id, e);
+        }
+    }
+    Ok(shops.len())
+}
+
+async fn aggregate_shop(repo: &AstraRepo, shop_id: &str, days: i64) -> Result<(), ContractError> {
+    let today = Utc::now().date_naive();
+    let first_day = today - ChronoDuration::days(days - 1);
+    let window_start_us = first_day.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp_micros() as u64;
+
+    let mut buckets: BTreeMap<NaiveDate, DayBucket> = BTreeMap::new();
+    for i in 0..days {
+        buckets.insert(first_day + ChronoDuration::days(i), DayBucket::default());
+    }
+
+    for guest in repo.get_guests(shop_id).await? {
+        if guest.created_at >= window_start_us {
+            if let Some(b) = buckets.get_mut(&day_of(guest.created_at)) {
+                b.new_conversations += 1;
+            }
+        }
+        // Không có tin trong cửa sổ → khỏi đọc tin (guest trước khi có last_message_at thì xét last_seen)
+        let last_active = if guest.last_message_at > 0 { guest.last_message_at } else { guest.last_seen };
+        if last_active < window_start_us {
+            continue;
+        }
+
+        // message_id tăng theo thời gian → lấy từ đầu cửa sổ (id cũ µs + Snowflake id)
+        let mut messages = repo
+            .fetch_messages_since(shop_id, guest.guest_id, window_start_us, MessageId::LEGACY_MAX)
+            .await?;
+        let window_start_id = MessageId::lower_bound(window_start_us / 1000).0;
+        messages.extend(repo
+            .fetch_messages_since(shop_id, guest.guest_id, window_start_id.max(MessageId::LEGACY_MAX - 1), i64::MAX as u64)
+            .await?);
+        collect_guest(&messages, &mut buckets);
+    }
+
+    for (day, bucket) in buckets {
+        repo.upsert_daily_stats(shop_id, &bucket.into_stats(day)).await?;
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct DayBucket {
+    new_conversations: u32,
+    guest_messages: u32,
+    admin_messages: u32,
+    first_responses_ms: Vec<u64>,
+    hourly: [u32; 24],
+}
+
+impl DayBucket {
+    fn into_stats(mut self, day: NaiveDate) -> DailyStats {
+        self.first_responses_ms.sort_unstable();
+        let median = self.first_responses_ms
+            .get(self.first_responses_ms.len() / 2)
+            .copied()
+            .unwrap_or(0);
+        DailyStats {
+            day: day.format("%Y-%m-%d").to_string(),
+            new_conversations: self.new_conversations,
+            guest_messages: self.guest_messages,
+            admin_messages: self.admin_messages,
+            median_first_response_ms: median,
+            hourly_messages: self.hourly.to_vec(),
+        }
+    }
+}
+
+/// Đếm tin theo ngày/giờ và thời gian phản hồi đầu tiên:
+/// mỗi ngày, tin đầu tiên của khách → tin admin đầu tiên sau đó.
+fn collect_guest(messages: &[Message], buckets: &mut BTreeMap<NaiveDate, DayBucket>) {
+    let mut current_day: Option<NaiveDate> = None;
+    let mut pending_since: Option<u64> = None;
+    let mut answered = false;
+
+    for msg in messages {
+        let day = day_of(msg.timestamp_us);
+        let Some(bucket) = buckets.get_mut(&day) else { continue };
+
+        if current_day != Some(day) {
+            current_day = Some(day);
+            pending_since = None;
+            answered = false;
+        }
+
+        if msg.sender_type == "guest" {
+            bucket.guest_messages += 1;
+            bucket.hourly[to_datetime(msg.timestamp_us).hour() as usize] += 1;
+            if pending_since.is_none() && !answered {
+                pending_since = Some(msg.timestamp_us);
+            }
+        } else if msg.sender_type == "admin" {
+            // Bot tự trả lời không tính là phản hồi của shop
+            bucket.admin_messages += 1;
+            if let Some(since) = pending_since.take() {
+                bucket.first_responses_ms.push(msg.timestamp_us.saturating_sub(since) / 1000);
+                answered = true;
+            }
+        }
+    }
+}
+
+fn to_datetime(timestamp_us: u64) -> DateTime<Utc> {
+    DateTime::from_timestamp_micros(timestamp_us as i64).unwrap_or_default()
+}
+
+fn day_of(timestamp_us: u64) -> NaiveDate {
+    to_datetime(timestamp_us).date_naive()
+}
+
+// Báo cáo / file xuất dài nhất 1 năm
+const MAX_RANGE_DAYS: i64 = 366;
+
+#[derive(Deserialize)]
+pub struct ReportQuery {
+    pub days: Option<i64>,
+    // Khoảng ngày "YYYY-MM-DD" (UTC, gồm cả hai đầu) - có thì bỏ qua `days`
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+impl ReportQuery {
+    /// (ngày đầu, ngày cuối); None = ngày sai định dạng / ngược / quá MAX_RANGE_DAYS
+    fn range(&self) -> Option<(NaiveDate, NaiveDate)> {
+        let today = Utc::now().date_naive();
+        let parse = |day: &str| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok();
+        let (from, to) = match (&self.from, &self.to) {
+            (Some(from), to) => (parse(from)?, to.as_deref().map_or(Some(today), parse)?.min(today)),
+            (None, _) => {
+                let days = self.days.unwrap_or(30).clamp(1, 365);
+                (today - ChronoDuration::days(days - 1), today)
+            }
+        };
+        (from <= to && (to - from).num_days() < MAX_RANGE_DAYS).then_some((from, to))
+    }
+}
+
+// Thống kê đã tổng hợp trong khoảng, sắp theo ngày
+async fn stats_between(state: &AppState, shop_id: &str, from: NaiveDate, to: NaiveDate) -> Result<Vec<DailyStats>, ContractError> {
+    let last = to.format("%Y-%m-%d").to_string();
+    let mut days = state.repo.get_daily_stats(shop_id, &from.format("%Y-%m-%d").to_string()).await?;
+    days.retain(|d| d.day <= last);
+    days.sort_by(|a, b| a.day.cmp(&b.day));
+    Ok(days)
+}
+
+// GET /reports?days=30 | ?from=YYYY-MM-DD&to=YYYY-MM-DD - Thống kê đã tổng hợp
+pub async fn reports_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Query(query): Query<ReportQuery>,
+) -> impl IntoResponse {
+    let Some((from, to)) = query.range() else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+
+    let resp = match stats_between(&state, &admin.shop_id, from, to).await {
+        Ok(days) => ReportResponse { success: true, days, ..Default::default() },
+        Err(e) => ReportResponse { success: false, days: vec![], error: e.to_string(), request_id: request_id::current() },
+    };
+
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+}
+
+// GET /reports/export.csv?from=&to= - CSV mỗi ngày một dòng (kể cả ngày không có tin), tạo dần khi gửi
+pub async fn export_csv_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Query(query): Query<ReportQuery>,
+) -> Response {
+    let Some((from, to)) = query.range() else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let stats: HashMap<String, DailyStats> = match stats_between(&state, &admin.shop_id, from, to).await {
+        Ok(days) => days.into_iter().map(|d| (d.day.clone(), d)).collect(),
+        Err(e) => {
+            eprintln!("❌ Load stats for export of {} failed: {:?}", admin.shop_id, e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let detail = format!("{}..{}", from, to);
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "analytics.export", 0, &admin.agent_id, &detail).await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("📊 Analytics CSV export for {} ({})", admin.shop_id, detail);
+
+    let rows = from.iter_days().take_while(move |day| *day <= to).map(move |day| {
+        let key = day.format("%Y-%m-%d").to_string();
+        let empty = DailyStats { day: key.clone(), ..Default::default() };
+        csv_row(stats.get(&key).unwrap_or(&empty))
+    });
+    let lines = std::iter::once(csv_header()).chain(rows).map(|line| Ok::<_, Infallible>(Bytes::from(line)));
+    let filename = format!("turbochat-{}-{}-{}.csv", admin.shop_id, from, to);
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+            (header::CACHE_CONTROL, "no-store".to_string()),
+        ],
+        Body::from_stream(futures::stream::iter(lines)),
+    ).into_response()
+}
+
+// Giờ theo UTC như DailyStats.hourly_messages.
+// Chưa có cột CSAT: hệ thống chưa thu đánh giá của khách, thêm khi có nguồn dữ liệu
+fn csv_header() -> String {
+    let hours = (0..24).map(|h| format!(",guest_messages_{:02}h_utc", h)).collect::<String>();
+    format!("day,new_conversations,guest_messages,admin_messages,total_messages,median_first_response_seconds{}\r\n", hours)
+}
+
+fn csv_row(stats: &DailyStats) -> String {
+    let median = match stats.median_first_response_ms {
+        0 => String::new(),
+        ms => format!("{:.1}", ms as f64 / 1000.0),
+    };
+    let hours = (0..24).map(|h| format!(",{}", stats.hourly_messages.get(h).copied().unwrap_or(0))).collect::<String>();
+    format!(
+        "{},{},{},{},{},{}{}\r\n",
+        stats.day,
+        stats.new_conversations,
+        stats.guest_messages,
+        stats.admin_messages,
+        stats.guest_messages + stats.admin_messages,
+        median,
+        hours,
+    )
+}
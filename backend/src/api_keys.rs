@@ -0,0 +1,304 @@
+// ============================================================================
+// API KEY - tích hợp server-to-server (CRM, ERP...) gọi các endpoint /v1 bằng
+// `Authorization: Bearer tck.<shop_id>.<key_id>.<secret>`. Tách khỏi PIN admin: khoá không mở được
+// endpoint admin, PIN không dùng được cho /v1. Mỗi khoá chỉ làm được việc trong scope của nó.
+// DB chỉ giữ sha256(secret); token đầy đủ chỉ trả một lần lúc tạo / rotate
+// ============================================================================
+
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{FromRequestParts, Path, State},
+    http::{header, request::Parts, StatusCode},
+    response::IntoResponse,
+};
+use prost::Message as ProstMessage;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use crate::auth::{AdminAuth, OwnerAuth};
+use crate::blob;
+use crate::contract::{ApiKey, ApiKeyListResponse, ApiKeyRequest, ApiKeyResponse, ContractError};
+use crate::db::{AstraRepo, now_us};
+use crate::request_id;
+use crate::state::AppState;
+
+const TOKEN_PREFIX: &str = "tck";
+const MAX_NAME_CHARS: usize = 100;
+const MAX_KEYS_PER_SHOP: usize = 50;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Scope {
+    MessagesRead,
+    MessagesWrite,
+    GuestsRead,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 3] = [Scope::MessagesRead, Scope::MessagesWrite, Scope::GuestsRead];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::MessagesRead => "messages:read",
+            Scope::MessagesWrite => "messages:write",
+            Scope::GuestsRead => "guests:read",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.as_str() == value.trim())
+    }
+}
+
+/// Token → (shop_id, key_id, secret). shop_id có thể chứa '.', nên tách từ bên phải
+pub fn parse_token(token: &str) -> Option<(&str, &str, &str)> {
+    let rest = token.strip_prefix(TOKEN_PREFIX)?.strip_prefix('.')?;
+    let mut parts = rest.rsplitn(3, '.');
+    let secret = parts.next()?;
+    let key_id = parts.next()?;
+    let shop_id = parts.next()?;
+    (!shop_id.is_empty() && !key_id.is_empty() && !secret.is_empty()).then_some((shop_id, key_id, secret))
+}
+
+/// shop_id trong header Authorization (không kiểm tra khoá) - để phân vùng Idempotency-Key
+pub fn bearer_shop(authorization: &str) -> Option<&str> {
+    parse_token(authorization.strip_prefix("Bearer ")?.trim()).map(|(shop_id, _, _)| shop_id)
+}
+
+fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+// Secret mới → (token gửi cho client, hash để lưu)
+fn new_secret(shop_id: &str, key_id: &str) -> (String, String) {
+    let secret = hex::encode(blob::random_key());
+    (format!("{}.{}.{}.{}", TOKEN_PREFIX, shop_id, key_id, secret), hash_secret(&secret))
+}
+
+/// Scope lạ → Err(tên scope)
+pub fn parse_scopes(values: &[String]) -> Result<Vec<String>, String> {
+    let mut scopes: Vec<String> = Vec::new();
+    for value in values {
+        let scope = Scope::parse(value).ok_or_else(|| format!("Unknown scope '{}'", value))?;
+        if !scopes.iter().any(|s| s == scope.as_str()) {
+            scopes.push(scope.as_str().to_string());
+        }
+    }
+    Ok(scopes)
+}
+
+/// Tạo khoá mới, trả (khoá, token)
+pub async fn issue(repo: &AstraRepo, shop_id: &str, name: &str, scopes: Vec<String>) -> Result<(ApiKey, String), ContractError> {
+    let now = now_us();
+    let key = ApiKey {
+        key_id: hex::encode(&blob::random_key()[..8]),
+        name: name.chars().take(MAX_NAME_CHARS).collect(),
+        scopes,
+        created_at: now,
+        rotated_at: now,
+        revoked_at: 0,
+    };
+    let (token, key_hash) = new_secret(shop_id, &key.key_id);
+    repo.insert_api_key(shop_id, &key, &key_hash).await?;
+    Ok((key, token))
+}
+
+/// Đổi secret (giữ key_id + scope); token cũ hết hiệu lực ngay. None = không có / đã thu hồi
+pub async fn rotate(repo: &AstraRepo, shop_id: &str, key_id: &str) -> Result<Option<(ApiKey, String)>, ContractError> {
+    let Some((mut key, _)) = repo.get_api_key(shop_id, key_id).await? else { return Ok(None) };
+    if key.revoked_at > 0 {
+        return Ok(None);
+    }
+    let (token, key_hash) = new_secret(shop_id, key_id);
+    key.rotated_at = now_us();
+    repo.rotate_api_key(shop_id, key_id, &key_hash, key.rotated_at).await?;
+    Ok(Some((key, token)))
+}
+
+// ============================================================================
+// EXTRACTOR cho endpoint /v1
+// ============================================================================
+
+/// Khoá hợp lệ (đúng secret, chưa thu hồi) lấy từ `Authorization: Bearer`
+pub struct ApiKeyAuth {
+    pub shop_id: String,
+    pub key_id: String,
+    scopes: Vec<String>,
+}
+
+impl ApiKeyAuth {
+    /// Thiếu scope → 403
+    pub fn require(&self, scope: Scope) -> Result<(), (StatusCode, &'static str)> {
+        if self.scopes.iter().any(|s| s == scope.as_str()) {
+            Ok(())
+        } else {
+            Err((StatusCode::FORBIDDEN, "API key lacks the required scope"))
+        }
+    }
+
+    /// Tên người thực hiện trong audit log
+    pub fn actor(&self) -> String {
+        format!("api_key:{}", self.key_id)
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for ApiKeyAuth {
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let token = parts.headers.get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::trim)
+            .ok_or((StatusCode::UNAUTHORIZED, "Missing API key"))?;
+        let (shop_id, key_id, secret) = parse_token(token).ok_or((StatusCode::UNAUTHORIZED, "Malformed API key"))?;
+
+        match state.repo.get_api_key(shop_id, key_id).await {
+            Ok(Some((key, key_hash))) if key.revoked_at == 0 && key_hash == hash_secret(secret) => Ok(ApiKeyAuth {
+                shop_id: shop_id.to_string(),
+                key_id: key.key_id,
+                scopes: key.scopes,
+            }),
+            Ok(_) => {
+                println!("🚫 Rejected API key {} for shop {}", key_id, shop_id);
+                Err((StatusCode::UNAUTHORIZED, "Invalid API key"))
+            }
+            Err(e) => {
+                eprintln!("❌ API key lookup failed: {:?}", e);
+                Err((StatusCode::SERVICE_UNAVAILABLE, "Auth backend unavailable"))
+            }
+        }
+    }
+}
+
+// ============================================================================
+// QUẢN LÝ KHOÁ (admin)
+// ============================================================================
+
+// GET /api-keys - mọi khoá của shop (kể cả đã thu hồi), không có secret
+pub async fn list_keys_handler(State(state): State<Arc<AppState>>, OwnerAuth(admin): OwnerAuth) -> impl IntoResponse {
+    let resp = match state.repo.list_api_keys(&admin.shop_id).await {
+        Ok(keys) => ApiKeyListResponse { success: true, keys, ..Default::default() },
+        Err(e) => ApiKeyListResponse { success: false, error: e.to_string(), request_id: request_id::current(), ..Default::default() },
+    };
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+}
+
+// POST /api-keys - tạo khoá, token chỉ trả lần này
+pub async fn create_key_handler(State(state): State<Arc<AppState>>, OwnerAuth(admin): OwnerAuth, body: Bytes) -> impl IntoResponse {
+    let Ok(req) = ApiKeyRequest::decode(&body[..]) else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+    let scopes = match parse_scopes(&req.scopes) {
+        Ok(scopes) if !scopes.is_empty() => scopes,
+        Ok(_) => return error_response(StatusCode::BAD_REQUEST, "At least one scope is required".to_string()),
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, e),
+    };
+    let name = req.name.trim();
+    if name.is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, "Name is required".to_string());
+    }
+    match state.repo.list_api_keys(&admin.shop_id).await {
+        Ok(keys) if keys.iter().filter(|k| k.revoked_at == 0).count() >= MAX_KEYS_PER_SHOP => {
+            return error_response(StatusCode::CONFLICT, format!("At most {} active API keys per shop", MAX_KEYS_PER_SHOP));
+        }
+        Ok(_) => {}
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+
+    let (key, token) = match issue(&state.repo, &admin.shop_id, name, scopes).await {
+        Ok(issued) => issued,
+        Err(e) => {
+            eprintln!("❌ Create API key failed: {:?}", e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+        }
+    };
+    println!("🔑 API key {} ({}) created for shop {}", key.key_id, key.scopes.join(","), admin.shop_id);
+    audit(&state, &admin, "api_key.create", &key.key_id).await;
+    let resp = ApiKeyResponse { success: true, key: Some(key), token, ..Default::default() };
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+}
+
+// POST /api-keys/:key_id/rotate - secret mới, secret cũ hết hiệu lực ngay
+pub async fn rotate_key_handler(
+    State(state): State<Arc<AppState>>,
+    OwnerAuth(admin): OwnerAuth,
+    Path(key_id): Path<String>,
+) -> impl IntoResponse {
+    let (key, token) = match rotate(&state.repo, &admin.shop_id, &key_id).await {
+        Ok(Some(rotated)) => rotated,
+        Ok(None) => return error_response(StatusCode::NOT_FOUND, "API key not found or revoked".to_string()),
+        Err(e) => {
+            eprintln!("❌ Rotate API key failed: {:?}", e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+        }
+    };
+    println!("🔑 API key {} rotated for shop {}", key_id, admin.shop_id);
+    audit(&state, &admin, "api_key.rotate", &key_id).await;
+    let resp = ApiKeyResponse { success: true, key: Some(key), token, ..Default::default() };
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+}
+
+// DELETE /api-keys/:key_id - thu hồi (giữ dòng để còn lịch sử)
+pub async fn revoke_key_handler(
+    State(state): State<Arc<AppState>>,
+    OwnerAuth(admin): OwnerAuth,
+    Path(key_id): Path<String>,
+) -> impl IntoResponse {
+    match state.repo.get_api_key(&admin.shop_id, &key_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return StatusCode::NOT_FOUND,
+        Err(e) => {
+            eprintln!("❌ Load API key failed: {:?}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    }
+    if let Err(e) = state.repo.revoke_api_key(&admin.shop_id, &key_id).await {
+        eprintln!("❌ Revoke API key failed: {:?}", e);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+    println!("🔒 API key {} revoked for shop {}", key_id, admin.shop_id);
+    audit(&state, &admin, "api_key.revoke", &key_id).await;
+    StatusCode::NO_CONTENT
+}
+
+fn error_response(status: StatusCode, error: String) -> (StatusCode, Bytes) {
+    let resp = ApiKeyResponse { success: false, error, request_id: request_id::current(), ..Default::default() };
+    (status, Bytes::from(resp.encode_to_vec()))
+}
+
+async fn audit(state: &AppState, admin: &AdminAuth, action: &str, key_id: &str) {
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, action, 0, &admin.agent_id, key_id).await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_token_splits_parts() {
+        assert_eq!(parse_token("tck.shop1.key1.secret"), Some(("shop1", "key1", "secret")));
+        // shop_id có thể chứa dấu chấm - key_id và secret thì không
+        assert_eq!(parse_token("tck.shop.example.com.key1.secret"), Some(("shop.example.com", "key1", "secret")));
+    }
+
+    #[test]
+    fn parse_token_rejects_malformed() {
+        for token in ["", "tck", "tck.", "tck.shop.key", "xyz.shop.key.secret", "tckshop.key.secret", "tck..key.secret", "tck.shop..secret", "tck.shop.key."] {
+            assert_eq!(parse_token(token), None, "{:?}", token);
+        }
+    }
+
+    #[test]
+    fn new_secret_round_trips() {
+        let (token, hash) = new_secret("shop1", "key1");
+        let (shop_id, key_id, secret) = parse_token(&token).unwrap();
+        assert_eq!((shop_id, key_id), ("shop1", "key1"));
+        assert_eq!(hash_secret(secret), hash);
+        assert_eq!(bearer_shop(&format!("Bearer {}", token)), Some("shop1"));
+        assert_eq!(bearer_shop(&token), None);
+    }
+}
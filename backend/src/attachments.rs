@@ -0,0 +1,282 @@
+// Upload / tải file đính kèm - nội dung nằm ở blob store (blob.rs), tin nhắn chỉ giữ key
+use axum::{
+    body::Bytes,
+    extract::{ConnectInfo, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use prost::Message as ProstMessage;
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::auth::client_ip;
+use crate::blob::BlobStore;
+use crate::contract::{Attachment, Message, ScanResult, ScanStatus};
+use crate::db::now_us;
+use crate::scan::{ScanError, Verdict};
+use crate::state::AppState;
+use crate::thumbnail;
+
+// Link tải có hạn - trả lại tin (sync) thì ký link mới
+const LINK_TTL: Duration = Duration::from_secs(24 * 3600);
+const DEFAULT_MAX_MB: usize = 10;
+
+// (magic bytes ở offset 0, content-type, đuôi file) - chỉ nhận các loại này
+const SIGNATURES: &[(&[u8], &str, &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png", "png"),
+    (b"\xff\xd8\xff", "image/jpeg", "jpg"),
+    (b"GIF87a", "image/gif", "gif"),
+    (b"GIF89a", "image/gif", "gif"),
+    (b"%PDF-", "application/pdf", "pdf"),
+    (b"OggS", "audio/ogg", "ogg"),
+    (b"\x1a\x45\xdf\xa3", "audio/webm", "webm"),
+    (b"ID3", "audio/mpeg", "mp3"),
+];
+
+/// Nhận diện loại file từ nội dung (không tin header Content-Type của client).
+/// None = loại không được phép.
+pub fn sniff_content_type(data: &[u8]) -> Option<&'static str> {
+    sniff(data).map(|(content_type, _)| content_type)
+}
+
+fn sniff(data: &[u8]) -> Option<(&'static str, &'static str)> {
+    if let Some((_, content_type, ext)) = SIGNATURES.iter().find(|(magic, _, _)| data.starts_with(magic)) {
+        return Some((content_type, ext));
+    }
+    // RIFF....WEBP
+    if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        return Some(("image/webp", "webp"));
+    }
+    // ....ftyp<brand> (MP4 / M4A)
+    if data.len() >= 12 && &data[4..8] == b"ftyp" {
+        return Some(if &data[8..12] == b"M4A " { ("audio/mp4", "m4a") } else { ("video/mp4", "mp4") });
+    }
+    // MP3 không có ID3 tag: frame sync 0xFFE
+    if data.len() >= 2 && data[0] == 0xff && data[1] & 0xe0 == 0xe0 {
+        return Some(("audio/mpeg", "mp3"));
+    }
+    None
+}
+
+/// ATTACHMENT_MAX_MB (mặc định 10) - dùng cho DefaultBodyLimit của route upload
+pub fn max_upload_bytes() -> usize {
+    std::env::var("ATTACHMENT_MAX_MB").ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MAX_MB)
+        .max(1) * 1024 * 1024
+}
+
+/// Ký link tải (và link ảnh thu nhỏ) cho file đính kèm của các tin trước khi trả client
+pub fn sign_urls(store: &dyn BlobStore, messages: &mut [Message]) {
+    for attachment in messages.iter_mut().flat_map(|m| m.attachments.iter_mut()) {
+        sign(store, attachment);
+    }
+}
+
+fn sign(store: &dyn BlobStore, attachment: &mut Attachment) {
+    attachment.url = store.presign(&attachment.key, LINK_TTL);
+    if attachment.is_image() {
+        attachment.thumbnail_url = store.presign(&thumbnail::thumbnail_key(&attachment.key), LINK_TTL);
+    }
+}
+
+/// Xoá file đính kèm (và ảnh thu nhỏ) của các tin đã xoá hẳn - lỗi chỉ log
+pub async fn delete_files(store: &dyn BlobStore, messages: &[Message]) {
+    for attachment in messages.iter().flat_map(|m| &m.attachments) {
+        let mut keys = vec![attachment.key.clone()];
+        if attachment.is_image() {
+            keys.push(thumbnail::thumbnail_key(&attachment.key));
+        }
+        for key in keys {
+            if let Err(e) = store.delete(&key).await {
+                eprintln!("❌ Delete attachment {} failed: {}", key, e);
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UploadQuery {
+    pub shop_id: String,
+    pub guest_id: u64,
+    #[serde(default)]
+    pub name: String,
+}
+
+// POST /attachments?shop_id=&guest_id=&name= - body là nội dung file, trả về Attachment (protobuf)
+pub async fn upload_handler(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(query): Query<UploadQuery>,
+    body: Bytes,
+) -> impl IntoResponse {
+    if query.shop_id.is_empty() || query.shop_id.len() > 64 || query.guest_id == 0 {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    }
+    // Chỉ bật tin thoại (voice_notes) → vẫn nhận file, nhưng chỉ file âm thanh (kiểm sau sniff)
+    let flags = match state.repo.get_flags(&query.shop_id).await {
+        Ok(flags) if flags.attachments || flags.voice_notes => flags,
+        Ok(_) => return (StatusCode::FORBIDDEN, Bytes::new()),
+        Err(e) => {
+            eprintln!("❌ Load flags for {} failed: {:?}", query.shop_id, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new());
+        }
+    };
+    let ip = client_ip(&headers, &peer);
+    if state.ws_state.blocks.is_blocked(&query.shop_id, Some(query.guest_id), &ip).await {
+        return (StatusCode::FORBIDDEN, Bytes::new());
+    }
+
+    // Quá ATTACHMENT_MAX_MB đã bị DefaultBodyLimit chặn (413)
+    if body.is_empty() {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    }
+    let Some((content_type, ext)) = sniff(&body) else {
+        println!("🚫 Rejected upload from guest {}: unsupported file type", query.guest_id);
+        return (StatusCode::UNSUPPORTED_MEDIA_TYPE, Bytes::new());
+    };
+    if !flags.attachments && !content_type.starts_with("audio/") {
+        return (StatusCode::FORBIDDEN, Bytes::new());
+    }
+    // File tính vào hạn mức dung lượng của shop như nội dung tin
+    if let Err(exceeded) = state.ws_state.quotas.check_send(&query.shop_id, body.len()).await {
+        println!("📊 Rejected upload from guest {}: shop {} at {}/{} bytes", query.guest_id, query.shop_id, exceeded.used, exceeded.limit);
+        return (StatusCode::TOO_MANY_REQUESTS, Bytes::from(exceeded.encode_to_vec()));
+    }
+
+    let key = format!("{}/{}/{}.{}", query.shop_id, query.guest_id, state.repo.ids.next_id().0, ext);
+    let size = body.len() as u64;
+    let file_name = match query.name.trim() {
+        "" => format!("file.{}", ext),
+        name => name.chars().take(Attachment::MAX_FILE_NAME_CHARS).collect(),
+    };
+
+    let scan = match scan_quarantined(&state, &key, content_type, &body).await {
+        Ok(scan) => scan,
+        Err(ScanError::Infected(signature)) => {
+            println!("🦠 Rejected upload {} from guest {}: {}", key, query.guest_id, signature);
+            let detail = format!("{} ({})", file_name, signature);
+            if let Err(e) = state.repo.insert_audit(&query.shop_id, "attachment.infected", query.guest_id, "scanner", &detail).await {
+                eprintln!("❌ Audit insert failed: {:?}", e);
+            }
+            // Trả Attachment kèm kết quả quét để client báo đúng lý do
+            let rejected = Attachment {
+                content_type: content_type.to_string(),
+                size,
+                file_name,
+                scan: Some(ScanResult {
+                    status: ScanStatus::Infected as i32,
+                    engine: state.scanner.as_ref().map(|s| s.engine()).unwrap_or_default().to_string(),
+                    signature,
+                    scanned_at_us: now_us(),
+                }),
+                ..Default::default()
+            };
+            return (StatusCode::UNPROCESSABLE_ENTITY, Bytes::from(rejected.encode_to_vec()));
+        }
+        Err(ScanError::Unavailable(e)) => {
+            eprintln!("❌ Scan of {} failed: {}", key, e);
+            return (StatusCode::SERVICE_UNAVAILABLE, Bytes::new());
+        }
+    };
+
+    if let Err(e) = state.ws_state.blobs.put(&key, content_type, body.clone()).await {
+        eprintln!("❌ Store attachment {} failed: {}", key, e);
+        return (StatusCode::BAD_GATEWAY, Bytes::new());
+    }
+    println!("📎 Stored attachment {} ({}, {} bytes)", key, content_type, size);
+    state.ws_state.quotas.record_storage(&query.shop_id, size).await;
+    if state.scanner.is_some() {
+        // Đã có bản sạch ở key thật → bỏ bản cách ly
+        if let Err(e) = state.ws_state.blobs.delete(&quarantine_key(&key)).await {
+            eprintln!("❌ Delete quarantined {} failed: {}", key, e);
+        }
+    }
+    if content_type.starts_with("image/") {
+        thumbnail::spawn(state.ws_state.blobs.clone(), key.clone(), body);
+    }
+
+    let mut attachment = Attachment {
+        key,
+        content_type: content_type.to_string(),
+        size,
+        file_name,
+        scan: Some(scan),
+        ..Default::default()
+    };
+    sign(state.ws_state.blobs.as_ref(), &mut attachment);
+    (StatusCode::OK, Bytes::from(attachment.encode_to_vec()))
+}
+
+// File chờ quét nằm dưới quarantine/ - không bao giờ được ký link hay gắn vào tin
+fn quarantine_key(key: &str) -> String {
+    format!("quarantine/{}", key)
+}
+
+/// Ghi file vào quarantine rồi quét. File nhiễm giữ lại trong quarantine để kiểm tra,
+/// lỗi quét thì xoá bản cách ly. Không bật AV_SCANNER → Skipped.
+async fn scan_quarantined(state: &AppState, key: &str, content_type: &str, body: &Bytes) -> Result<ScanResult, ScanError> {
+    let Some(scanner) = &state.scanner else {
+        return Ok(ScanResult::default());
+    };
+    let quarantined = quarantine_key(key);
+    state.ws_state.blobs.put(&quarantined, content_type, body.clone()).await
+        .map_err(|e| ScanError::Unavailable(format!("quarantine failed: {}", e)))?;
+
+    match scanner.scan(body).await {
+        Ok(Verdict::Clean) => Ok(ScanResult {
+            status: ScanStatus::Clean as i32,
+            engine: scanner.engine().to_string(),
+            signature: String::new(),
+            scanned_at_us: now_us(),
+        }),
+        Ok(Verdict::Infected(signature)) => Err(ScanError::Infected(signature)),
+        Err(e) => {
+            if let Err(e) = state.ws_state.blobs.delete(&quarantined).await {
+                eprintln!("❌ Delete quarantined {} failed: {}", key, e);
+            }
+            Err(ScanError::Unavailable(e))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct LinkQuery {
+    pub expires: u64,
+    pub sig: String,
+}
+
+// GET /attachments/*key?expires=&sig= - link do kho local ký (S3 thì client tải thẳng từ bucket)
+pub async fn download_handler(
+    State(state): State<Arc<AppState>>,
+    Path(key): Path<String>,
+    Query(link): Query<LinkQuery>,
+) -> Response {
+    if !crate::blob::is_valid_key(&key) || !state.ws_state.blobs.verify_link(&key, link.expires, &link.sig) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    match state.ws_state.blobs.get(&key).await {
+        Ok(Some(blob)) => {
+            // Ảnh/âm thanh xem ngay trên trang, loại khác tải về
+            let inline = blob.content_type.starts_with("image/") || blob.content_type.starts_with("audio/");
+            let disposition = if inline { "inline" } else { "attachment" };
+            (
+                [
+                    (header::CONTENT_TYPE, blob.content_type),
+                    (header::CONTENT_DISPOSITION, disposition.to_string()),
+                    (header::CACHE_CONTROL, "private, max-age=3600".to_string()),
+                    (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+                ],
+                blob.data,
+            ).into_response()
+        }
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            eprintln!("❌ Read attachment {} failed: {}", key, e);
+            StatusCode::BAD_GATEWAY.into_response()
+        }
+    }
+}
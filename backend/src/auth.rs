@@ -0,0 +1,288 @@
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header, request::Parts, HeaderMap, StatusCode},
+};
+use sha2::{Digest, Sha256};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use crate::agents;
+use crate::config;
+use crate::contract::origin_host;
+use crate::lockout::{self, PinCheck};
+use crate::state::AppState;
+
+/// Admin đã xác thực qua header `x-shop-id` + `x-admin-pin`.
+/// Dùng cho các endpoint dạng REST (GET/DELETE) không có body protobuf.
+pub struct AdminAuth {
+    pub shop_id: String,
+    // Đăng nhập bằng PIN chủ shop (không phải session nhân viên được mời)
+    pub is_owner: bool,
+    // Nhân viên: agent_id trong session; chủ shop: agents::OWNER_ID
+    pub agent_id: String,
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for AdminAuth {
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let header = |name: &str| {
+            parts.headers.get(name)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("")
+                .to_string()
+        };
+        let shop_id = header("x-shop-id");
+        let pin = header("x-admin-pin");
+
+        if shop_id.is_empty() || pin.is_empty() {
+            return Err((StatusCode::UNAUTHORIZED, "Missing admin credentials"));
+        }
+
+        let ip = match parts.extensions.get::<ConnectInfo<SocketAddr>>() {
+            Some(ConnectInfo(peer)) => client_ip(&parts.headers, peer),
+            None => "unknown".to_string(),
+        };
+        match lockout::check_pin(&state.ws_state, &shop_id, &pin, &ip).await {
+            Ok(PinCheck::Valid(_)) => {
+                let agent_id = agents::session_agent(&pin).unwrap_or(agents::OWNER_ID).to_string();
+                Ok(AdminAuth { is_owner: !agents::is_session(&pin), shop_id, agent_id })
+            }
+            Ok(PinCheck::Invalid { .. }) => Err((StatusCode::UNAUTHORIZED, "Unauthorized")),
+            Ok(PinCheck::Locked { .. }) => Err((StatusCode::TOO_MANY_REQUESTS, "Too many failed PIN attempts")),
+            Err(e) => {
+                eprintln!("❌ Admin auth failed: {:?}", e);
+                Err((StatusCode::SERVICE_UNAVAILABLE, "Auth backend unavailable"))
+            }
+        }
+    }
+}
+
+/// Chỉ chủ shop (PIN) - khoá API, xoá dữ liệu khách, ghi cài đặt / feature flag. Nhân viên được mời → 403
+pub struct OwnerAuth(pub AdminAuth);
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for OwnerAuth {
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let admin = AdminAuth::from_request_parts(parts, state).await?;
+        if !admin.is_owner {
+            return Err((StatusCode::FORBIDDEN, "Only the shop owner can do this"));
+        }
+        Ok(OwnerAuth(admin))
+    }
+}
+
+/// Vận hành (không gắn với shop) - `Authorization: Bearer <OPS_TOKEN>`. Không đặt OPS_TOKEN → mọi endpoint /ops trả 404
+pub struct OpsAuth;
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for OpsAuth {
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        if !state.ops_token.is_enabled() {
+            return Err((StatusCode::NOT_FOUND, "Not found"));
+        }
+        let token = parts.headers.get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .unwrap_or("");
+        if state.ops_token.matches(token.trim()) {
+            Ok(OpsAuth)
+        } else {
+            Err((StatusCode::UNAUTHORIZED, "Unauthorized"))
+        }
+    }
+}
+
+/// PIN gửi trong body protobuf (/guests, /sync, /settings) - chung bộ đếm lần sai với đăng nhập
+pub async fn verify_body_pin(state: &AppState, shop_id: &str, pin: &str, ip: &str) -> Result<(), StatusCode> {
+    match lockout::check_pin(&state.ws_state, shop_id, pin, ip).await {
+        Ok(PinCheck::Valid(_)) => Ok(()),
+        Ok(PinCheck::Locked { .. }) => Err(StatusCode::TOO_MANY_REQUESTS),
+        Ok(PinCheck::Invalid { .. }) | Err(_) => Err(StatusCode::UNAUTHORIZED),
+    }
+}
+
+/// IP thật của client: địa chỉ TCP, trừ khi nó là proxy tin cậy (TRUSTED_PROXIES) → lấy hop phải nhất
+/// trong X-Forwarded-For không thuộc proxy tin cậy (không có XFF thì X-Real-IP).
+/// Client tự gửi header thì các hop bên trái là giả → không tin
+pub fn client_ip(headers: &HeaderMap, peer: &SocketAddr) -> String {
+    let config = config::current();
+    let trusted = &config.trusted_proxies;
+    let peer_ip = peer.ip().to_canonical();
+    if !trusted.contains(peer_ip) {
+        return peer_ip.to_string();
+    }
+
+    let header = |name: &str| headers.get_all(name).iter().filter_map(|v| v.to_str().ok()).collect::<Vec<_>>().join(",");
+    let hops: Vec<IpAddr> = header("x-forwarded-for")
+        .split(',')
+        .filter_map(|hop| hop.trim().parse::<IpAddr>().ok())
+        .map(|ip| ip.to_canonical())
+        .collect();
+    if let Some(ip) = hops.iter().rev().find(|ip| !trusted.contains(**ip)) {
+        return ip.to_string();
+    }
+    // Mọi hop đều là proxy tin cậy → hop trái nhất
+    hops.first().copied()
+        .or_else(|| header("x-real-ip").trim().parse::<IpAddr>().ok().map(|ip| ip.to_canonical()))
+        .unwrap_or(peer_ip)
+        .to_string()
+}
+
+/// Proxy tin cậy - TRUSTED_PROXIES (IP hoặc CIDR, cách nhau dấu phẩy, vd. 127.0.0.1,10.0.0.0/8), nạp qua config.rs.
+/// Trống = không tin header IP nào (chạy thẳng không qua nginx)
+pub struct TrustedProxies {
+    nets: Vec<(IpAddr, u8)>,
+}
+
+impl TrustedProxies {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let nets = value
+            .split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| Self::parse_net(v).ok_or_else(|| format!("TRUSTED_PROXIES entry is not an IP or CIDR: {}", v)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { nets })
+    }
+
+    // "10.0.0.0/8" → (10.0.0.0, 8); "::1" → (::1, 128)
+    fn parse_net(value: &str) -> Option<(IpAddr, u8)> {
+        let (addr, prefix) = value.split_once('/').map_or((value, None), |(a, p)| (a, Some(p)));
+        let addr = addr.parse::<IpAddr>().ok()?.to_canonical();
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(p) => p.parse::<u8>().ok().filter(|&p| p <= max)?,
+            None => max,
+        };
+        Some((addr, prefix))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nets.is_empty()
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.nets.iter().any(|&(net, prefix)| match (net, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        })
+    }
+
+    pub fn summary(&self) -> String {
+        self.nets.iter().map(|(net, prefix)| format!("{}/{}", net, prefix)).collect::<Vec<_>>().join(",")
+    }
+}
+
+/// Origin của trang mở kết nối: header Origin, không có thì lấy từ Referer
+pub fn request_origin(headers: &HeaderMap) -> Option<String> {
+    let header = |name| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim).filter(|v| !v.is_empty() && *v != "null");
+    header(header::ORIGIN).or_else(|| header(header::REFERER)).map(str::to_string)
+}
+
+/// Origin của admin panel - ADMIN_ORIGINS (cách nhau dấu phẩy, vd. https://admin.turbochat.vn), nạp qua config.rs.
+/// Kết nối từ đây không bị giới hạn bởi tên miền nhúng widget của shop
+pub struct AdminOrigins {
+    hosts: Vec<String>,
+}
+
+impl AdminOrigins {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let hosts = value
+            .split(',')
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| origin_host(v).ok_or_else(|| format!("ADMIN_ORIGINS entry is not a URL: {}", v)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { hosts })
+    }
+
+    pub fn hosts(&self) -> &[String] {
+        &self.hosts
+    }
+
+    pub fn contains(&self, origin: &str) -> bool {
+        origin_host(origin).is_some_and(|host| self.hosts.contains(&host))
+    }
+}
+
+/// OPS_TOKEN - chỉ giữ sha256 (so sánh hash, không so chuỗi bí mật trực tiếp)
+pub struct OpsToken {
+    hash: Option<[u8; 32]>,
+}
+
+impl OpsToken {
+    pub fn from_env() -> Self {
+        let hash = std::env::var("OPS_TOKEN").ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| Sha256::digest(v.trim().as_bytes()).into());
+        Self { hash }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.hash.is_some()
+    }
+
+    fn matches(&self, token: &str) -> bool {
+        let digest: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+        self.hash.is_some_and(|hash| hash == digest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn trusted_proxies_match_cidr() {
+        let proxies = TrustedProxies::parse("10.0.0.0/8, 192.168.1.5, fd00::/8").unwrap();
+        assert!(proxies.contains(ip("10.1.2.3")));
+        assert!(proxies.contains(ip("192.168.1.5")));
+        assert!(!proxies.contains(ip("192.168.1.6")));
+        assert!(!proxies.contains(ip("11.0.0.1")));
+        assert!(proxies.contains(ip("fd12::1")));
+        assert!(!proxies.contains(ip("fe80::1")));
+    }
+
+    #[test]
+    fn trusted_proxies_prefix_edges() {
+        let any = TrustedProxies::parse("0.0.0.0/0").unwrap();
+        assert!(any.contains(ip("8.8.8.8")));
+        // Prefix 0 của IPv4 không khớp IPv6
+        assert!(!any.contains(ip("::1")));
+        assert!(TrustedProxies::parse("::1").unwrap().contains(ip("::1")));
+    }
+
+    #[test]
+    fn trusted_proxies_ipv4_mapped() {
+        // ::ffff:10.0.0.1 được chuẩn hoá về IPv4 lúc parse
+        let proxies = TrustedProxies::parse("::ffff:10.0.0.1").unwrap();
+        assert!(proxies.contains(ip("10.0.0.1")));
+    }
+
+    #[test]
+    fn trusted_proxies_parse_errors() {
+        assert!(TrustedProxies::parse("").unwrap().is_empty());
+        assert!(TrustedProxies::parse(" , ").unwrap().is_empty());
+        assert!(TrustedProxies::parse("10.0.0.0/33").is_err());
+        assert!(TrustedProxies::parse("::/129").is_err());
+        assert!(TrustedProxies::parse("nginx").is_err());
+        assert!(TrustedProxies::parse("10.0.0.0/x").is_err());
+    }
+}
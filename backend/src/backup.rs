@@ -0,0 +1,311 @@
+// Sao lưu / khôi phục dữ liệu shop ra file .ndjson.zst - đi qua AstraRepo nên file không phụ thuộc DB,
+// dùng để chuyển sang backend DB khác.
+//
+//   backend backup  [--shop X] --out file.ndjson.zst [--checkpoint file]
+//   backend restore --in file.ndjson.zst [--checkpoint file]
+//
+// Mỗi dòng là một bản ghi JSON: header / shop (nguyên dòng) / guest, message (protobuf base64).
+// Mỗi guest là một zstd frame riêng → chạy lại sau khi bị ngắt sẽ cắt file về frame trọn vẹn cuối rồi làm tiếp.
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use prost::Message as ProstMessage;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::contract::{Guest, Message};
+use crate::db::{AstraRepo, now_us};
+
+const FORMAT_VERSION: u64 = 1;
+const ZSTD_LEVEL: i32 = 3;
+// Restore: lưu checkpoint sau chừng này dòng (và ở mỗi guest)
+const RESTORE_CHECKPOINT_EVERY: u64 = 500;
+
+pub enum Command {
+    Backup { shop_id: Option<String>, out: PathBuf, checkpoint: PathBuf },
+    Restore { input: PathBuf, checkpoint: PathBuf },
+}
+
+impl Command {
+    /// `backup ...` / `restore ...` ở đầu args → Some, còn lại None (chạy server)
+    pub fn from_args(args: &[String]) -> Result<Option<Self>, String> {
+        let Some(name) = args.first() else { return Ok(None) };
+        let rest = &args[1..];
+        let command = match name.as_str() {
+            "backup" => {
+                let out = PathBuf::from(flag(rest, "--out").ok_or("backup needs --out <file.ndjson.zst>")?);
+                Command::Backup {
+                    shop_id: flag(rest, "--shop"),
+                    checkpoint: checkpoint_path(rest, &out),
+                    out,
+                }
+            }
+            "restore" => {
+                let input = PathBuf::from(flag(rest, "--in").ok_or("restore needs --in <file.ndjson.zst>")?);
+                Command::Restore {
+                    checkpoint: checkpoint_path(rest, &input),
+                    input,
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(command))
+    }
+}
+
+// "--name value" hoặc "--name=value"
+fn flag(args: &[String], name: &str) -> Option<String> {
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == name {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix(name)?.strip_prefix('=').map(String::from)
+        }
+    })
+}
+
+// Mặc định "<file>.checkpoint" cạnh file backup
+fn checkpoint_path(args: &[String], file: &Path) -> PathBuf {
+    flag(args, "--checkpoint").map(PathBuf::from).unwrap_or_else(|| {
+        let mut path = file.as_os_str().to_owned();
+        path.push(".checkpoint");
+        PathBuf::from(path)
+    })
+}
+
+pub async fn run(repo: &AstraRepo, command: Command) -> Result<(), String> {
+    match command {
+        Command::Backup { shop_id, out, checkpoint } => backup(repo, shop_id, &out, &checkpoint).await,
+        Command::Restore { input, checkpoint } => restore(repo, &input, &checkpoint).await,
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Record {
+    Header { format: u64, created_at_us: u64 },
+    Shop { row: serde_json::Value },
+    Guest { data: String },
+    Message { data: String },
+}
+
+// ========== BACKUP ==========
+// Đã ghi trọn mọi thứ tới (shop_id, guest_id) - guest_id 0 = mới ghi dòng shop, shop_id "" = mới ghi header
+#[derive(Serialize, Deserialize)]
+struct BackupCheckpoint {
+    shop_id: String,
+    guest_id: u64,
+    offset: u64,
+}
+
+async fn backup(repo: &AstraRepo, shop_id: Option<String>, out: &Path, checkpoint_file: &Path) -> Result<(), String> {
+    let resume: Option<BackupCheckpoint> = load_checkpoint(checkpoint_file)?;
+    let mut writer = match &resume {
+        Some(cp) => {
+            println!("↩️ Resuming backup after {}/{} ({} bytes)", cp.shop_id, cp.guest_id, cp.offset);
+            FrameWriter::resume(out, cp.offset)?
+        }
+        None => {
+            let mut writer = FrameWriter::create(out)?;
+            let header = Record::Header { format: FORMAT_VERSION, created_at_us: now_us() };
+            writer.write_frame(&[header])?;
+            save_checkpoint(checkpoint_file, &BackupCheckpoint { shop_id: String::new(), guest_id: 0, offset: writer.offset })?;
+            writer
+        }
+    };
+
+    let mut shop_ids = match shop_id {
+        Some(id) => vec![id],
+        None => repo.list_shop_ids().await.map_err(|e| format!("List shops failed: {:?}", e))?,
+    };
+    // Thứ tự cố định để checkpoint có nghĩa
+    shop_ids.sort();
+
+    let (mut guests_done, mut messages_done) = (0usize, 0usize);
+    for shop_id in shop_ids {
+        let (resume_shop, resume_guest) = match &resume {
+            Some(cp) if shop_id < cp.shop_id => continue,
+            Some(cp) if shop_id == cp.shop_id => (true, cp.guest_id),
+            _ => (false, 0),
+        };
+
+        if !resume_shop {
+            let row = repo.get_shop_row(&shop_id).await
+                .map_err(|e| format!("Read shop {} failed: {:?}", shop_id, e))?
+                .ok_or_else(|| format!("Shop {} not found", shop_id))?;
+            writer.write_frame(&[Record::Shop { row }])?;
+            save_checkpoint(checkpoint_file, &BackupCheckpoint { shop_id: shop_id.clone(), guest_id: 0, offset: writer.offset })?;
+        }
+
+        let mut guests = repo.get_guests(&shop_id).await
+            .map_err(|e| format!("List guests of {} failed: {:?}", shop_id, e))?;
+        guests.sort_by_key(|g| g.guest_id);
+
+        for guest in guests.into_iter().filter(|g| g.guest_id > resume_guest) {
+            let guest_id = guest.guest_id;
+            let mut records = vec![Record::Guest { data: BASE64.encode(guest.encode_to_vec()) }];
+            let mut page_state: Option<String> = None;
+            loop {
+                let page = repo.fetch_messages(&shop_id, guest_id, 0, 0, page_state.as_deref()).await
+                    .map_err(|e| format!("Read messages of {}/{} failed: {:?}", shop_id, guest_id, e))?;
+                messages_done += page.messages.len();
+                records.extend(page.messages.iter().map(|m| Record::Message { data: BASE64.encode(m.encode_to_vec()) }));
+                match page.page_state {
+                    Some(ps) => page_state = Some(ps),
+                    None => break,
+                }
+            }
+
+            writer.write_frame(&records)?;
+            save_checkpoint(checkpoint_file, &BackupCheckpoint { shop_id: shop_id.clone(), guest_id, offset: writer.offset })?;
+            guests_done += 1;
+        }
+        println!("💾 Backed up shop {}", shop_id);
+    }
+
+    remove_checkpoint(checkpoint_file);
+    println!("✅ Backup written to {} ({} guests, {} messages, {} bytes)", out.display(), guests_done, messages_done, writer.offset);
+    Ok(())
+}
+
+// Ghi file theo từng zstd frame - offset luôn nằm ở cuối frame trọn vẹn cuối cùng
+struct FrameWriter {
+    file: File,
+    offset: u64,
+}
+
+impl FrameWriter {
+    fn create(path: &Path) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("Create {} failed: {}", path.display(), e))?;
+        Ok(Self { file, offset: 0 })
+    }
+
+    // Bỏ phần frame dở dang của lần chạy bị ngắt
+    fn resume(path: &Path, offset: u64) -> Result<Self, String> {
+        let io = |e: std::io::Error| format!("Reopen {} failed: {}", path.display(), e);
+        let mut file = OpenOptions::new().write(true).open(path).map_err(io)?;
+        file.set_len(offset).map_err(io)?;
+        file.seek(SeekFrom::Start(offset)).map_err(io)?;
+        Ok(Self { file, offset })
+    }
+
+    fn write_frame(&mut self, records: &[Record]) -> Result<(), String> {
+        let io = |e: std::io::Error| format!("Write backup failed: {}", e);
+        let mut encoder = zstd::Encoder::new(BufWriter::new(&mut self.file), ZSTD_LEVEL).map_err(io)?;
+        for record in records {
+            serde_json::to_writer(&mut encoder, record).map_err(|e| format!("Encode record failed: {}", e))?;
+            encoder.write_all(b"\n").map_err(io)?;
+        }
+        encoder.finish().map_err(io)?.flush().map_err(io)?;
+        // Frame phải nằm trên đĩa trước khi checkpoint trỏ qua nó
+        self.file.sync_data().map_err(io)?;
+        self.offset = self.file.stream_position().map_err(io)?;
+        Ok(())
+    }
+}
+
+// ========== RESTORE ==========
+// Số dòng đã áp dụng - ghi lại là upsert nên áp lại vài dòng cũng không sao
+#[derive(Serialize, Deserialize)]
+struct RestoreCheckpoint {
+    lines: u64,
+}
+
+async fn restore(repo: &AstraRepo, input: &Path, checkpoint_file: &Path) -> Result<(), String> {
+    let file = File::open(input).map_err(|e| format!("Open {} failed: {}", input.display(), e))?;
+    // Decoder đọc nối tiếp mọi frame
+    let decoder = zstd::Decoder::new(file).map_err(|e| format!("Open zstd stream failed: {}", e))?;
+    let skip = load_checkpoint::<RestoreCheckpoint>(checkpoint_file)?.map_or(0, |cp| cp.lines);
+    if skip > 0 {
+        println!("↩️ Resuming restore after line {}", skip);
+    }
+
+    // Tin cuối của guest đang khôi phục → feed hội thoại
+    let mut last_message: Option<Message> = None;
+    let (mut lines, mut guests, mut messages) = (0u64, 0usize, 0usize);
+    for line in BufReader::new(decoder).lines() {
+        let line = line.map_err(|e| format!("Read backup failed at line {}: {}", lines + 1, e))?;
+        lines += 1;
+        if lines <= skip || line.is_empty() {
+            continue;
+        }
+        let record: Record = serde_json::from_str(&line).map_err(|e| format!("Bad record at line {}: {}", lines, e))?;
+
+        match record {
+            Record::Header { format, .. } => {
+                if format > FORMAT_VERSION {
+                    return Err(format!("Backup format {} is newer than supported ({})", format, FORMAT_VERSION));
+                }
+            }
+            Record::Shop { row } => {
+                flush_feed(repo, &mut last_message).await?;
+                let shop_id = row["shop_id"].as_str().unwrap_or("").to_string();
+                repo.put_shop_row(&row).await.map_err(|e| format!("Restore shop {} failed: {:?}", shop_id, e))?;
+                println!("🏪 Restoring shop {}", shop_id);
+            }
+            Record::Guest { data } => {
+                flush_feed(repo, &mut last_message).await?;
+                let guest = decode::<Guest>(&data, lines)?;
+                repo.restore_guest(&guest).await
+                    .map_err(|e| format!("Restore guest {}/{} failed: {:?}", guest.shop_id, guest.guest_id, e))?;
+                guests += 1;
+                save_checkpoint(checkpoint_file, &RestoreCheckpoint { lines })?;
+            }
+            Record::Message { data } => {
+                let msg = decode::<Message>(&data, lines)?;
+                repo.insert_message(&msg).await
+                    .map_err(|e| format!("Restore message {} failed: {:?}", msg.message_id, e))?;
+                messages += 1;
+                last_message = Some(msg);
+                if lines % RESTORE_CHECKPOINT_EVERY == 0 {
+                    save_checkpoint(checkpoint_file, &RestoreCheckpoint { lines })?;
+                }
+            }
+        }
+    }
+    flush_feed(repo, &mut last_message).await?;
+
+    remove_checkpoint(checkpoint_file);
+    println!("✅ Restored {} guests, {} messages from {}", guests, messages, input.display());
+    Ok(())
+}
+
+async fn flush_feed(repo: &AstraRepo, last_message: &mut Option<Message>) -> Result<(), String> {
+    let Some(msg) = last_message.take() else { return Ok(()) };
+    repo.set_feed_message(&msg).await
+        .map_err(|e| format!("Update feed for {}/{} failed: {:?}", msg.shop_id, msg.guest_id, e))
+}
+
+fn decode<T: ProstMessage + Default>(data: &str, line: u64) -> Result<T, String> {
+    let bytes = BASE64.decode(data).map_err(|e| format!("Bad base64 at line {}: {}", line, e))?;
+    T::decode(&bytes[..]).map_err(|e| format!("Bad protobuf at line {}: {}", line, e))
+}
+
+// ========== CHECKPOINT ==========
+fn load_checkpoint<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<Option<T>, String> {
+    match std::fs::read(path) {
+        Ok(raw) => serde_json::from_slice(&raw)
+            .map(Some)
+            .map_err(|e| format!("Bad checkpoint {}: {} (delete it to start over)", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Read checkpoint {} failed: {}", path.display(), e)),
+    }
+}
+
+// Ghi file tạm rồi rename để checkpoint không bao giờ dở dang
+fn save_checkpoint<T: Serialize>(path: &Path, checkpoint: &T) -> Result<(), String> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let raw = serde_json::to_vec(checkpoint).map_err(|e| format!("Encode checkpoint failed: {}", e))?;
+    std::fs::write(&tmp, raw)
+        .and_then(|_| std::fs::rename(&tmp, path))
+        .map_err(|e| format!("Save checkpoint {} failed: {}", path.display(), e))
+}
+
+fn remove_checkpoint(path: &Path) {
+    if let Err(e) = std::fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            eprintln!("⚠️ Remove checkpoint {} failed: {}", path.display(), e);
+        }
+    }
+}
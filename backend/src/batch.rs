@@ -0,0 +1,169 @@
+// Gom insert tin nhắn trong một cửa sổ ngắn rồi ghi song song (Astra REST không có insert nhiều dòng)
+use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::contract::{ContractError, Message};
+use crate::db::AstraRepo;
+
+const QUEUE_CAPACITY: usize = 4096;
+
+// Tin chờ ghi + nơi báo kết quả cho người gọi
+type Pending = (Box<Message>, oneshot::Sender<Result<(), ContractError>>);
+
+enum Command {
+    Insert(Box<Message>, oneshot::Sender<Result<(), ContractError>>),
+    // Ghi hết tin đang chờ rồi dừng
+    Shutdown(oneshot::Sender<()>),
+}
+
+/// INSERT_BATCH_MS (cửa sổ gom, mặc định 5ms, 0 = ghi thẳng), INSERT_BATCH_MAX (tối đa tin/lô, 64),
+/// INSERT_BATCH_CONCURRENCY (số request song song mỗi lô, 16).
+pub struct InsertBatcher {
+    repo: Arc<AstraRepo>,
+    tx: Option<mpsc::Sender<Command>>,
+}
+
+impl InsertBatcher {
+    pub fn from_env(repo: Arc<AstraRepo>) -> Self {
+        let env = |key: &str, default: u64| std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        let window_ms = env("INSERT_BATCH_MS", 5);
+        let max_batch = env("INSERT_BATCH_MAX", 64).max(1) as usize;
+        let concurrency = env("INSERT_BATCH_CONCURRENCY", 16).max(1) as usize;
+
+        if window_ms == 0 {
+            println!("📦 Insert batching disabled (INSERT_BATCH_MS=0)");
+            return Self { repo, tx: None };
+        }
+        println!("📦 Insert batching: {}ms window, up to {} messages, {} in flight", window_ms, max_batch, concurrency);
+
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(batch_task(repo.clone(), rx, Duration::from_millis(window_ms), max_batch, concurrency));
+        Self { repo, tx: Some(tx) }
+    }
+
+    /// Lưu một tin - chờ tới khi lô chứa nó ghi xong, lỗi trả về đúng cho tin đó
+    #[tracing::instrument(name = "db.insert_message", skip_all, fields(shop_id = %msg.shop_id))]
+    pub async fn insert(&self, msg: &Message) -> Result<(), ContractError> {
+        let Some(tx) = &self.tx else {
+            return insert_direct(&self.repo, msg).await;
+        };
+        let (done_tx, done_rx) = oneshot::channel();
+        if tx.send(Command::Insert(Box::new(msg.clone()), done_tx)).await.is_err() {
+            // Đã tắt batcher (đang shutdown) → ghi thẳng
+            return insert_direct(&self.repo, msg).await;
+        }
+        done_rx.await
+            .unwrap_or_else(|_| Err(ContractError::DbError("Insert batcher stopped".into())))
+    }
+
+    /// Gọi khi tắt server: ghi nốt các tin đang gom, tin đến sau đó ghi thẳng
+    pub async fn shutdown(&self) {
+        let Some(tx) = &self.tx else { return };
+        let (ack_tx, ack_rx) = oneshot::channel();
+        if tx.send(Command::Shutdown(ack_tx)).await.is_ok() {
+            let _ = ack_rx.await;
+        }
+    }
+}
+
+async fn batch_task(
+    repo: Arc<AstraRepo>,
+    mut rx: mpsc::Receiver<Command>,
+    window: Duration,
+    max_batch: usize,
+    concurrency: usize,
+) {
+    let mut batch = Vec::with_capacity(max_batch);
+    while let Some(cmd) = rx.recv().await {
+        let mut shutdown = None;
+        match cmd {
+            Command::Insert(msg, done) => batch.push((msg, done)),
+            Command::Shutdown(ack) => shutdown = Some(ack),
+        }
+
+        // Gom thêm tới khi hết cửa sổ hoặc đủ lô
+        let deadline = tokio::time::Instant::now() + window;
+        while shutdown.is_none() && batch.len() < max_batch {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(Command::Insert(msg, done))) => batch.push((msg, done)),
+                Ok(Some(Command::Shutdown(ack))) => shutdown = Some(ack),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        if let Some(ack) = shutdown {
+            // Không nhận thêm; lấy nốt các tin đã xếp hàng
+            rx.close();
+            while let Some(cmd) = rx.recv().await {
+                if let Command::Insert(msg, done) = cmd {
+                    batch.push((msg, done));
+                }
+            }
+            let count = batch.len();
+            flush(&repo, &mut batch, concurrency).await;
+            println!("📦 Insert batcher flushed {} pending messages on shutdown", count);
+            let _ = ack.send(());
+            return;
+        }
+        flush(&repo, &mut batch, concurrency).await;
+    }
+    flush(&repo, &mut batch, concurrency).await;
+}
+
+async fn insert_direct(repo: &AstraRepo, msg: &Message) -> Result<(), ContractError> {
+    repo.insert_message(msg).await?;
+    touch_guest(repo, msg).await;
+    Ok(())
+}
+
+#[tracing::instrument(name = "db.insert_batch", skip_all, fields(size = batch.len()))]
+async fn flush(repo: &AstraRepo, batch: &mut Vec<Pending>, concurrency: usize) {
+    if batch.is_empty() {
+        return;
+    }
+    let size = batch.len();
+    let results: Vec<(Box<Message>, bool)> = stream::iter(batch.drain(..))
+        .map(|(msg, done)| async move {
+            let result = repo.insert_message(&msg).await;
+            if let Err(e) = &result {
+                eprintln!("❌ Batched insert of message {} (guest {}) failed: {:?}", msg.message_id, msg.guest_id, e);
+            }
+            let saved = result.is_ok();
+            // Người gọi đã bỏ đi thì thôi
+            let _ = done.send(result);
+            (msg, saved)
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+
+    let failed = results.iter().filter(|(_, saved)| !saved).count();
+    if failed > 0 {
+        eprintln!("❌ Insert batch: {}/{} messages failed", failed, size);
+    }
+
+    // Mỗi hội thoại chỉ ghi tin mới nhất của lô (ghi song song có thể về không theo thứ tự), bỏ qua dòng hệ thống
+    let mut latest: HashMap<(&str, u64), &Message> = HashMap::new();
+    for (msg, _) in results.iter().filter(|(msg, saved)| *saved && !msg.is_system()) {
+        let entry = latest.entry((msg.shop_id.as_str(), msg.guest_id)).or_insert(msg);
+        if msg.message_id > entry.message_id {
+            *entry = msg;
+        }
+    }
+    stream::iter(latest.into_values())
+        .for_each_concurrent(concurrency, |msg| touch_guest(repo, msg))
+        .await;
+}
+
+// Tin cuối: trích đoạn trên guest + dòng của hội thoại trong conversation_feed
+async fn touch_guest(repo: &AstraRepo, msg: &Message) {
+    if let Err(e) = repo.set_guest_last_message(msg).await {
+        eprintln!("❌ Update last message of guest {} failed: {:?}", msg.guest_id, e);
+    }
+    if let Err(e) = repo.set_feed_message(msg).await {
+        eprintln!("❌ Update feed for guest {} failed: {:?}", msg.guest_id, e);
+    }
+}
@@ -0,0 +1,246 @@
+// loadgen - tải thử WebSocket: N khách mỗi shop gửi tin theo nhịp, đo độ trễ tới lúc nhận lại tin (echo)
+// và kiểm tra CRC nội dung. Dùng để ước lượng tải và kiểm tra thay đổi đường broadcast.
+//
+//   loadgen --url ws://localhost:8080/ws --shops demo123 --guests 50 --rate 1 --duration 60
+use backend::contract::{ws_envelope::Frame, Message, WsEnvelope};
+use futures::{SinkExt, StreamExt};
+use prost::Message as ProstMessage;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+const USAGE: &str = "\
+Usage: loadgen [options]
+
+  --url <ws url>        default ws://localhost:8080/ws
+  --shops <a,b,...>     default demo123
+  --guests <n>          connections per shop, default 10
+  --rate <msg/s>        per connection, default 1
+  --duration <s>        sending time, default 30
+  --size <bytes>        message size, default 64";
+
+// Sau khi ngừng gửi, chờ thêm để nhận nốt echo
+const DRAIN_GRACE: Duration = Duration::from_secs(5);
+// Mở kết nối rải đều thay vì dồn một lúc
+const CONNECT_SPACING: Duration = Duration::from_millis(5);
+
+struct Options {
+    url: String,
+    shops: Vec<String>,
+    guests: u32,
+    rate: f64,
+    duration: Duration,
+    size: usize,
+}
+
+impl Options {
+    fn from_args(args: &[String]) -> Result<Self, String> {
+        let number = |name: &str, default: f64| -> Result<f64, String> {
+            match flag(args, name) {
+                Some(v) => v.parse::<f64>().ok().filter(|n| *n > 0.0).ok_or_else(|| format!("{} must be a positive number", name)),
+                None => Ok(default),
+            }
+        };
+        Ok(Self {
+            url: flag(args, "--url").unwrap_or_else(|| "ws://localhost:8080/ws".to_string()),
+            shops: flag(args, "--shops").unwrap_or_else(|| "demo123".to_string())
+                .split(',').map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect(),
+            guests: number("--guests", 10.0)? as u32,
+            rate: number("--rate", 1.0)?,
+            duration: Duration::from_secs_f64(number("--duration", 30.0)?),
+            size: number("--size", 64.0)? as usize,
+        })
+    }
+}
+
+#[derive(Default)]
+struct Stats {
+    connected: u32,
+    connect_errors: u32,
+    sent: u64,
+    received: u64,
+    crc_errors: u64,
+    latencies_us: Vec<u64>,
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    let options = match Options::from_args(&args) {
+        Ok(options) => Arc::new(options),
+        Err(e) => {
+            eprintln!("❌ {}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let total = options.shops.len() as u32 * options.guests;
+    println!(
+        "🚀 {} connections ({} shops × {}), {} msg/s each for {}s, {} byte messages",
+        total, options.shops.len(), options.guests, options.rate, options.duration.as_secs(), options.size
+    );
+
+    // guest_id riêng cho mỗi lần chạy để không lẫn với lịch sử cũ
+    let run_id = chrono::Utc::now().timestamp_millis().max(0) as u64 * 1000;
+    let stats = Arc::new(Mutex::new(Stats::default()));
+    let started = Instant::now();
+    let mut tasks = Vec::new();
+    for shop_id in &options.shops {
+        for i in 0..options.guests {
+            let (options, stats, shop_id) = (options.clone(), stats.clone(), shop_id.clone());
+            let guest_id = run_id + i as u64;
+            tasks.push(tokio::spawn(async move { run_guest(&options, &shop_id, guest_id, &stats).await }));
+            tokio::time::sleep(CONNECT_SPACING).await;
+        }
+    }
+    for task in tasks {
+        let _ = task.await;
+    }
+
+    report(&stats.lock().unwrap(), started.elapsed());
+}
+
+async fn run_guest(options: &Options, shop_id: &str, guest_id: u64, stats: &Mutex<Stats>) {
+    let url = format!("{}?shop_id={}&guest_id={}", options.url, shop_id, guest_id);
+    let socket = match tokio_tungstenite::connect_async(&url).await {
+        Ok((socket, _)) => socket,
+        Err(e) => {
+            eprintln!("❌ Guest {} connect failed: {}", guest_id, e);
+            stats.lock().unwrap().connect_errors += 1;
+            return;
+        }
+    };
+    stats.lock().unwrap().connected += 1;
+    let (mut sink, mut stream) = socket.split();
+
+    // seq → lúc gửi
+    let pending: Arc<Mutex<HashMap<u64, Instant>>> = Arc::default();
+
+    let sender = {
+        let pending = pending.clone();
+        let (shop_id, size) = (shop_id.to_string(), options.size);
+        let (period, duration) = (Duration::from_secs_f64(1.0 / options.rate), options.duration);
+        async move {
+            let mut ticker = tokio::time::interval(period);
+            let deadline = Instant::now() + duration;
+            let mut sent = 0u64;
+            for seq in 0.. {
+                ticker.tick().await;
+                if Instant::now() >= deadline {
+                    break;
+                }
+                let content = payload(guest_id, seq, size);
+                let msg = Message {
+                    shop_id: shop_id.clone(),
+                    guest_id,
+                    sender_type: "guest".to_string(),
+                    content_crc: crc32c::crc32c(&content),
+                    content: content.into(),
+                    ..Default::default()
+                };
+                pending.lock().unwrap().insert(seq, Instant::now());
+                if sink.send(WsMessage::Binary(WsEnvelope::message(msg).encode_to_vec())).await.is_err() {
+                    break;
+                }
+                sent += 1;
+            }
+            tokio::time::sleep(DRAIN_GRACE).await;
+            let _ = sink.close().await;
+            sent
+        }
+    };
+
+    let receiver = async {
+        let (mut received, mut crc_errors, mut latencies) = (0u64, 0u64, Vec::new());
+        while let Some(Ok(frame)) = stream.next().await {
+            let WsMessage::Binary(data) = frame else { continue };
+            let Ok(envelope) = WsEnvelope::decode(&data[..]) else { continue };
+            let Some(Frame::Message(msg)) = envelope.frame else { continue };
+            if msg.guest_id != guest_id || msg.sender_type != "guest" {
+                continue;
+            }
+            let Some(seq) = parse_seq(&msg.content) else { continue };
+            let Some(sent_at) = pending.lock().unwrap().remove(&seq) else { continue };
+            latencies.push(sent_at.elapsed().as_micros() as u64);
+            received += 1;
+            if crc32c::crc32c(&msg.content) != msg.content_crc || msg.content[..] != payload(guest_id, seq, msg.content.len())[..] {
+                crc_errors += 1;
+            }
+        }
+        (received, crc_errors, latencies)
+    };
+
+    let (sent, (received, crc_errors, latencies)) = tokio::join!(sender, receiver);
+    let mut stats = stats.lock().unwrap();
+    stats.sent += sent;
+    stats.received += received;
+    stats.crc_errors += crc_errors;
+    stats.latencies_us.extend(latencies);
+}
+
+// "lg <guest> <seq> " rồi đệm 'x' cho đủ `size` byte
+fn payload(guest_id: u64, seq: u64, size: usize) -> Vec<u8> {
+    let mut content = format!("lg {} {} ", guest_id, seq).into_bytes();
+    if content.len() < size {
+        content.resize(size, b'x');
+    }
+    content
+}
+
+fn parse_seq(content: &[u8]) -> Option<u64> {
+    let text = std::str::from_utf8(content).ok()?;
+    let mut parts = text.strip_prefix("lg ")?.split(' ');
+    parts.next()?;
+    parts.next()?.parse().ok()
+}
+
+fn report(stats: &Stats, elapsed: Duration) {
+    let mut latencies = stats.latencies_us.clone();
+    latencies.sort_unstable();
+    let lost = stats.sent.saturating_sub(stats.received);
+
+    println!();
+    println!("🔌 Connections: {} ok, {} failed", stats.connected, stats.connect_errors);
+    println!(
+        "📨 Messages: {} sent, {} echoed, {} lost ({:.2}%), {} CRC mismatches",
+        stats.sent, stats.received, lost,
+        if stats.sent > 0 { lost as f64 * 100.0 / stats.sent as f64 } else { 0.0 },
+        stats.crc_errors
+    );
+    println!("⏱️ Throughput: {:.1} msg/s over {:.1}s", stats.received as f64 / elapsed.as_secs_f64(), elapsed.as_secs_f64());
+    if latencies.is_empty() {
+        println!("⚠️ No echoes received");
+        return;
+    }
+    let ms = |us: u64| us as f64 / 1000.0;
+    println!(
+        "📊 Echo latency (ms): p50 {:.1}  p90 {:.1}  p99 {:.1}  p99.9 {:.1}  max {:.1}",
+        ms(percentile(&latencies, 50.0)),
+        ms(percentile(&latencies, 90.0)),
+        ms(percentile(&latencies, 99.0)),
+        ms(percentile(&latencies, 99.9)),
+        ms(*latencies.last().unwrap_or(&0)),
+    );
+}
+
+// `sorted` đã sắp tăng dần, khác rỗng
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    let rank = (p / 100.0 * (sorted.len() - 1) as f64).round() as usize;
+    sorted[rank.min(sorted.len() - 1)]
+}
+
+// "--name value" hoặc "--name=value"
+fn flag(args: &[String], name: &str) -> Option<String> {
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == name {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix(name)?.strip_prefix('=').map(String::from)
+        }
+    })
+}
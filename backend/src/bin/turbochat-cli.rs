@@ -0,0 +1,242 @@
+// turbochat-cli - việc vận hành không cần giao diện, dùng chung AstraRepo và proto với server.
+// Đọc cùng .env với backend (ASTRA_*, REDIS_URL / MESSAGE_BUS cho `tail`).
+use backend::api_keys;
+use backend::backup;
+use backend::blob;
+use backend::bus;
+use backend::contract::{ws_envelope::Frame, Message, WsEnvelope};
+use backend::db::AstraRepo;
+use backend::gdpr;
+use futures::StreamExt;
+use prost::Message as ProstMessage;
+use serde_json::json;
+use std::path::PathBuf;
+
+const USAGE: &str = "\
+Usage: turbochat-cli <command>
+
+  shops list
+  shops create <shop_id> <name> [--pin 123456]
+  pin rotate <shop_id>
+  api-keys list <shop_id>
+  api-keys create <shop_id> <name> --scopes messages:write,guests:read
+  api-keys rotate <shop_id> <key_id>
+  api-keys revoke <shop_id> <key_id>
+  guests list <shop_id> [--limit 50]
+  tail <shop_id>
+  export guest <shop_id> <guest_id> [--out file.json]
+  export shop <shop_id> --out file.ndjson.zst";
+
+const PIN_DIGITS: usize = 6;
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    dotenvy::dotenv().ok();
+
+    if let Err(e) = run(&args).await {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(args: &[String]) -> Result<(), String> {
+    // Tham số vị trí (bỏ "--flag value")
+    let words = positional(args);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+
+    // tail chỉ cần broker
+    if let ["tail", shop_id] = words.as_slice() {
+        return tail(shop_id).await;
+    }
+
+    let repo = AstraRepo::new().await.map_err(|e| format!("AstraDB connection failed: {:?}", e))?;
+    match words.as_slice() {
+        ["shops", "list"] => {
+            let mut ids = repo.list_shop_ids().await.map_err(db)?;
+            ids.sort();
+            for id in ids {
+                println!("{}", id);
+            }
+        }
+        ["shops", "create", shop_id, name] => {
+            if shop_id.is_empty() || shop_id.len() > 64 {
+                return Err("shop_id must be 1-64 characters".to_string());
+            }
+            if repo.get_shop_row(shop_id).await.map_err(db)?.is_some() {
+                return Err(format!("Shop {} already exists", shop_id));
+            }
+            let pin = flag(args, "--pin").unwrap_or_else(random_pin);
+            repo.create_shop(shop_id, name, &pin).await.map_err(db)?;
+            println!("✅ Created shop {} ({}), admin PIN {}", shop_id, name, pin);
+        }
+        ["pin", "rotate", shop_id] => {
+            if repo.get_shop_row(shop_id).await.map_err(db)?.is_none() {
+                return Err(format!("Shop {} not found", shop_id));
+            }
+            let pin = random_pin();
+            repo.put_shop_row(&json!({ "shop_id": shop_id, "admin_pin": pin })).await.map_err(db)?;
+            audit(&repo, shop_id, "shop.pin_rotate", 0, "").await;
+            println!("🔑 New admin PIN for {}: {}", shop_id, pin);
+        }
+        ["api-keys", "list", shop_id] => {
+            for key in repo.list_api_keys(shop_id).await.map_err(db)? {
+                let state = if key.revoked_at > 0 { format!("revoked {}", format_us(key.revoked_at)) } else { "active".to_string() };
+                println!("{:<18} {:<24} {:<40} rotated {:<20} {}", key.key_id, key.name, key.scopes.join(","), format_us(key.rotated_at), state);
+            }
+        }
+        ["api-keys", "create", shop_id, name] => {
+            let scopes: Vec<String> = flag(args, "--scopes").unwrap_or_default().split(',').filter(|s| !s.is_empty()).map(String::from).collect();
+            let scopes = api_keys::parse_scopes(&scopes)?;
+            if scopes.is_empty() {
+                let known: Vec<&str> = api_keys::Scope::ALL.iter().map(|s| s.as_str()).collect();
+                return Err(format!("--scopes is required ({})", known.join(", ")));
+            }
+            if repo.get_shop_row(shop_id).await.map_err(db)?.is_none() {
+                return Err(format!("Shop {} not found", shop_id));
+            }
+            let (key, token) = api_keys::issue(&repo, shop_id, name, scopes).await.map_err(db)?;
+            audit(&repo, shop_id, "api_key.create", 0, &key.key_id).await;
+            println!("🔑 API key {} ({}) for {}:\n{}", key.key_id, key.scopes.join(","), shop_id, token);
+        }
+        ["api-keys", "rotate", shop_id, key_id] => {
+            let (_, token) = api_keys::rotate(&repo, shop_id, key_id).await.map_err(db)?
+                .ok_or_else(|| format!("API key {} not found or revoked in {}", key_id, shop_id))?;
+            audit(&repo, shop_id, "api_key.rotate", 0, key_id).await;
+            println!("🔑 New token for API key {} (old one stops working now):\n{}", key_id, token);
+        }
+        ["api-keys", "revoke", shop_id, key_id] => {
+            if repo.get_api_key(shop_id, key_id).await.map_err(db)?.is_none() {
+                return Err(format!("API key {} not found in {}", key_id, shop_id));
+            }
+            repo.revoke_api_key(shop_id, key_id).await.map_err(db)?;
+            audit(&repo, shop_id, "api_key.revoke", 0, key_id).await;
+            println!("🔒 Revoked API key {} of {}", key_id, shop_id);
+        }
+        ["guests", "list", shop_id] => {
+            let limit = flag(args, "--limit").and_then(|v| v.parse().ok()).unwrap_or(50);
+            let page = repo.list_guests_page(shop_id, limit, None, 0).await.map_err(db)?;
+            for guest in page.guests {
+                let name = if guest.guest_name.is_empty() { "-" } else { guest.guest_name.as_str() };
+                println!(
+                    "{:<20} {:<24} {:<8} {:<20} {}",
+                    guest.guest_id,
+                    name,
+                    guest.status().as_db_str(),
+                    format_us(guest.last_message_at.max(guest.last_seen)),
+                    guest.last_message_preview,
+                );
+            }
+        }
+        ["export", "guest", shop_id, guest_id] => {
+            let guest_id: u64 = guest_id.parse().map_err(|_| format!("Bad guest_id '{}'", guest_id))?;
+            let guest = repo.get_guest(shop_id, guest_id).await.map_err(db)?
+                .ok_or_else(|| format!("Guest {} not found in {}", guest_id, shop_id))?;
+            // Guest gốc + các alias đã gộp (như GET /guests/:id/data)
+            let canonical = if guest.merged_into != 0 { guest.merged_into } else { guest_id };
+            let mut guests = vec![canonical];
+            guests.extend(repo.guest_aliases(shop_id, canonical).await.map_err(db)?.into_iter().filter(|&id| id != canonical));
+            let export = gdpr::collect_export(&repo, &guest, &guests).await.map_err(db)?;
+            let json = serde_json::to_string_pretty(&export.to_json())
+                .map_err(|e| format!("Encode export failed: {}", e))?;
+            match flag(args, "--out") {
+                Some(out) => {
+                    std::fs::write(&out, json).map_err(|e| format!("Write {} failed: {}", out, e))?;
+                    println!("✅ Exported {} messages of guest {} to {}", export.message_count(), guest_id, out);
+                }
+                None => println!("{}", json),
+            }
+            audit(&repo, shop_id, "guest.export", guest_id, &format!("exported {} messages of guests {:?}", export.message_count(), guests)).await;
+        }
+        ["export", "shop", shop_id] => {
+            let out = PathBuf::from(flag(args, "--out").ok_or("export shop needs --out <file.ndjson.zst>")?);
+            let mut checkpoint = out.as_os_str().to_owned();
+            checkpoint.push(".checkpoint");
+            let command = backup::Command::Backup {
+                shop_id: Some(shop_id.to_string()),
+                out,
+                checkpoint: PathBuf::from(checkpoint),
+            };
+            backup::run(&repo, command).await?;
+        }
+        _ => return Err(format!("Unknown command\n\n{}", USAGE)),
+    }
+    Ok(())
+}
+
+// Theo dõi tin trực tiếp của shop qua broker của server (MESSAGE_BUS, mặc định Redis chat:<shop_id>)
+async fn tail(shop_id: &str) -> Result<(), String> {
+    let redis_url = std::env::var("REDIS_URL").unwrap_or_default();
+    let bus = bus::from_env(&redis_url, None).await;
+    let mut frames = bus.subscribe(Some(shop_id)).await.map_err(|e| format!("Subscribe failed: {}", e))?;
+    eprintln!("👀 Tailing {} (Ctrl+C to stop)", shop_id);
+
+    while let Some(payload) = frames.next().await {
+        let Ok(envelope) = WsEnvelope::decode(&payload[..]) else { continue };
+        match envelope.frame {
+            Some(Frame::Message(m)) => print_message(&m),
+            Some(Frame::Edit(edit)) => println!("{} ✏️ message {} edited", format_us(edit.edited_at_us), edit.message_id),
+            Some(Frame::Delete(delete)) => println!("{} 🗑️ message {} deleted", format_us(delete.deleted_at_us), delete.message_id),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn print_message(m: &Message) {
+    let mut text = String::from_utf8_lossy(&m.content).into_owned();
+    for attachment in &m.attachments {
+        text.push_str(&format!(" 📎 {}", attachment.file_name));
+    }
+    println!("{} [{}] {:<6} {}", format_us(m.timestamp_us), m.guest_id, m.sender_type, text);
+}
+
+async fn audit(repo: &AstraRepo, shop_id: &str, action: &str, guest_id: u64, detail: &str) {
+    if let Err(e) = repo.insert_audit(shop_id, action, guest_id, "cli", detail).await {
+        eprintln!("⚠️ Audit insert failed: {:?}", e);
+    }
+}
+
+fn random_pin() -> String {
+    blob::random_key().iter().take(PIN_DIGITS).map(|b| char::from(b'0' + b % 10)).collect()
+}
+
+fn format_us(us: u64) -> String {
+    chrono::DateTime::from_timestamp_micros(us as i64)
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn db(e: backend::contract::ContractError) -> String {
+    format!("{:?}", e)
+}
+
+// "--name value" hoặc "--name=value"
+fn flag(args: &[String], name: &str) -> Option<String> {
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == name {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix(name)?.strip_prefix('=').map(String::from)
+        }
+    })
+}
+
+fn positional(args: &[String]) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg.starts_with("--") {
+            if !arg.contains('=') {
+                iter.next();
+            }
+        } else {
+            words.push(arg.clone());
+        }
+    }
+    words
+}
@@ -0,0 +1,341 @@
+// Kho file đính kèm: ổ đĩa local hoặc S3/MinIO (chọn bằng BLOB_STORE)
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use turbochat_shared::crypto;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const S3_TIMEOUT: Duration = Duration::from_secs(30);
+// Link S3 presign tối đa 7 ngày
+const S3_MAX_PRESIGN: Duration = Duration::from_secs(7 * 24 * 3600);
+
+pub struct Blob {
+    pub data: Bytes,
+    pub content_type: String,
+}
+
+/// Thao tác mọi backend lưu file phải có. Lỗi trả về dạng chuỗi để log.
+pub trait BlobStore: Send + Sync {
+    fn put<'a>(&'a self, key: &'a str, content_type: &'a str, data: Bytes) -> BoxFuture<'a, Result<(), String>>;
+    /// None = không có file
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Blob>, String>>;
+    /// Link tải không cần đăng nhập, hết hạn sau `ttl`
+    fn presign(&self, key: &str, ttl: Duration) -> String;
+    /// Xoá file không tồn tại không tính là lỗi
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), String>>;
+    /// Kiểm tra link presign trỏ về chính backend (GET /attachments/*key) - chỉ kho local dùng
+    fn verify_link(&self, _key: &str, _expires: u64, _sig: &str) -> bool {
+        false
+    }
+}
+
+/// BLOB_STORE=local (mặc định) | s3. Cấu hình sai thì dừng server luôn.
+pub fn from_env() -> Arc<dyn BlobStore> {
+    match std::env::var("BLOB_STORE").unwrap_or_default().as_str() {
+        "s3" => {
+            let store = S3BlobStore::from_env().unwrap_or_else(|e| panic!("❌ S3 blob store misconfigured: {}", e));
+            println!("🪣 Attachments stored in S3 bucket {} ({})", store.bucket, store.endpoint);
+            Arc::new(store)
+        }
+        "" | "local" => {
+            let store = LocalBlobStore::from_env();
+            println!("🪣 Attachments stored on disk at {}", store.dir.display());
+            Arc::new(store)
+        }
+        other => panic!("❌ Unknown BLOB_STORE '{}' (expected local or s3)", other),
+    }
+}
+
+/// Key chỉ gồm chữ/số và . _ - / (không có "..") - không thoát được khỏi thư mục gốc
+pub fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.len() <= 512
+        && !key.starts_with('/')
+        && key.split('/').all(|part| !part.is_empty() && part != "." && part != "..")
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '/'))
+}
+
+// ========== LOCAL ==========
+
+/// Chỉ hợp với 1 node - chạy nhiều backend sau nginx thì dùng S3.
+/// BLOB_DIR (mặc định ./data/blobs). Link tải trỏ về PUBLIC_URL/attachments/<key>,
+/// ký HMAC bằng BLOB_SIGNING_KEY (không đặt → khoá ngẫu nhiên, link cũ hết hiệu lực khi restart).
+pub struct LocalBlobStore {
+    dir: PathBuf,
+    public_base: String,
+    signing_key: Vec<u8>,
+}
+
+impl LocalBlobStore {
+    pub fn from_env() -> Self {
+        let dir = std::env::var("BLOB_DIR").unwrap_or_else(|_| "./data/blobs".to_string());
+        let public_base = std::env::var("PUBLIC_URL")
+            .map(|url| url.trim_end_matches('/').to_string())
+            .unwrap_or_else(|_| "http://localhost:8080".to_string());
+        let signing_key = match std::env::var("BLOB_SIGNING_KEY") {
+            Ok(key) if !key.is_empty() => key.into_bytes(),
+            _ => {
+                println!("⚠️ BLOB_SIGNING_KEY not set, attachment links expire on restart");
+                random_key()
+            }
+        };
+        Self { dir: PathBuf::from(dir), public_base, signing_key }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, String> {
+        if !is_valid_key(key) {
+            return Err(format!("invalid key {:?}", key));
+        }
+        Ok(self.dir.join(key))
+    }
+
+    fn signature(&self, key: &str, expires: u64) -> String {
+        crypto::sign_hex(&self.signing_key, format!("{}\n{}", key, expires).as_bytes())
+    }
+}
+
+impl BlobStore for LocalBlobStore {
+    fn put<'a>(&'a self, key: &'a str, _content_type: &'a str, data: Bytes) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let path = self.path(key)?;
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await.map_err(|e| format!("mkdir failed: {}", e))?;
+            }
+            // Ghi ra file tạm rồi đổi tên - không ai đọc được file ghi dở
+            let tmp = path.with_extension("part");
+            tokio::fs::write(&tmp, &data).await.map_err(|e| format!("write failed: {}", e))?;
+            tokio::fs::rename(&tmp, &path).await.map_err(|e| format!("rename failed: {}", e))
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Blob>, String>> {
+        Box::pin(async move {
+            match tokio::fs::read(self.path(key)?).await {
+                Ok(data) => {
+                    // Đĩa không lưu content-type → nhận diện lại từ nội dung
+                    let content_type = crate::attachments::sniff_content_type(&data)
+                        .unwrap_or("application/octet-stream")
+                        .to_string();
+                    Ok(Some(Blob { data: Bytes::from(data), content_type }))
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(format!("read failed: {}", e)),
+            }
+        })
+    }
+
+    fn presign(&self, key: &str, ttl: Duration) -> String {
+        let expires = chrono::Utc::now().timestamp().max(0) as u64 + ttl.as_secs();
+        format!(
+            "{}/attachments/{}?expires={}&sig={}",
+            self.public_base, key, expires, self.signature(key, expires)
+        )
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            match tokio::fs::remove_file(self.path(key)?).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!("delete failed: {}", e)),
+                _ => Ok(()),
+            }
+        })
+    }
+
+    fn verify_link(&self, key: &str, expires: u64, sig: &str) -> bool {
+        if expires < chrono::Utc::now().timestamp().max(0) as u64 {
+            return false;
+        }
+        crypto::verify_hex(&self.signing_key, format!("{}\n{}", key, expires).as_bytes(), sig)
+    }
+}
+
+/// 32 byte ngẫu nhiên (khoá ký link, PIN sinh bởi turbochat-cli)
+pub fn random_key() -> Vec<u8> {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    let mut hasher = Sha256::new();
+    for _ in 0..4 {
+        let mut h = RandomState::new().build_hasher();
+        h.write_u128(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos());
+        hasher.update(h.finish().to_le_bytes());
+    }
+    hasher.finalize().to_vec()
+}
+
+// ========== S3 ==========
+
+/// S3 hoặc dịch vụ tương thích (MinIO, R2...), địa chỉ kiểu path: <endpoint>/<bucket>/<key>.
+/// S3_ENDPOINT (vd. https://s3.ap-southeast-1.amazonaws.com hoặc http://minio:9000), S3_BUCKET,
+/// S3_REGION (mặc định us-east-1), S3_ACCESS_KEY, S3_SECRET_KEY.
+pub struct S3BlobStore {
+    client: reqwest::Client,
+    endpoint: String,
+    host: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+}
+
+impl S3BlobStore {
+    pub fn from_env() -> Result<Self, String> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty()).ok_or(format!("{} not set", name));
+        let endpoint = var("S3_ENDPOINT")?.trim_end_matches('/').to_string();
+        let url = reqwest::Url::parse(&endpoint).map_err(|e| format!("bad S3_ENDPOINT: {}", e))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err("S3_ENDPOINT has no host".into()),
+        };
+        Ok(Self {
+            client: reqwest::Client::builder().timeout(S3_TIMEOUT).build().unwrap_or_default(),
+            endpoint,
+            host,
+            bucket: var("S3_BUCKET")?,
+            region: std::env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            access_key: var("S3_ACCESS_KEY")?,
+            secret_key: var("S3_SECRET_KEY")?,
+        })
+    }
+
+    fn path(&self, key: &str) -> String {
+        format!("/{}/{}", uri_encode(&self.bucket, false), uri_encode(key, true))
+    }
+
+    // Request ký SigV4 qua header Authorization
+    fn signed(&self, method: reqwest::Method, key: &str, payload_hash: &str) -> reqwest::RequestBuilder {
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let scope = format!("{}/{}/s3/aws4_request", now.format("%Y%m%d"), self.region);
+        let path = self.path(key);
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, self.host, payload_hash, amz_date, signed_headers, payload_hash
+        );
+        let signature = self.signature(&amz_date, &scope, &canonical);
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key, scope, signed_headers, signature
+        );
+
+        self.client.request(method, format!("{}{}", self.endpoint, path))
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header("authorization", authorization)
+    }
+
+    fn signature(&self, amz_date: &str, scope: &str, canonical_request: &str) -> String {
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date, scope, hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        // scope = <ngày>/<region>/s3/aws4_request
+        let mut key = format!("AWS4{}", self.secret_key).into_bytes();
+        for part in scope.split('/') {
+            key = hmac(&key, part.as_bytes());
+        }
+        hex::encode(hmac(&key, string_to_sign.as_bytes()))
+    }
+}
+
+impl BlobStore for S3BlobStore {
+    fn put<'a>(&'a self, key: &'a str, content_type: &'a str, data: Bytes) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let payload_hash = hex::encode(Sha256::digest(&data));
+            let resp = self.signed(reqwest::Method::PUT, key, &payload_hash)
+                .header("content-type", content_type)
+                .body(data)
+                .send()
+                .await
+                .map_err(|e| format!("S3 put failed: {}", e))?;
+            if !resp.status().is_success() {
+                return Err(format!("S3 put returned {}", resp.status()));
+            }
+            Ok(())
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<Blob>, String>> {
+        Box::pin(async move {
+            let resp = self.signed(reqwest::Method::GET, key, EMPTY_SHA256)
+                .send()
+                .await
+                .map_err(|e| format!("S3 get failed: {}", e))?;
+            if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            if !resp.status().is_success() {
+                return Err(format!("S3 get returned {}", resp.status()));
+            }
+            let content_type = resp.headers().get("content-type")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("application/octet-stream")
+                .to_string();
+            let data = resp.bytes().await.map_err(|e| format!("S3 read failed: {}", e))?;
+            Ok(Some(Blob { data, content_type }))
+        })
+    }
+
+    // Query string ký SigV4, payload không ký
+    fn presign(&self, key: &str, ttl: Duration) -> String {
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let scope = format!("{}/{}/s3/aws4_request", now.format("%Y%m%d"), self.region);
+        let path = self.path(key);
+
+        // Tham số đã xếp theo tên
+        let query = format!(
+            "X-Amz-Algorithm=AWS4-HMAC-SHA256&X-Amz-Credential={}&X-Amz-Date={}&X-Amz-Expires={}&X-Amz-SignedHeaders=host",
+            uri_encode(&format!("{}/{}", self.access_key, scope), false),
+            amz_date,
+            ttl.min(S3_MAX_PRESIGN).as_secs().max(1),
+        );
+        let canonical = format!("GET\n{}\n{}\nhost:{}\n\nhost\nUNSIGNED-PAYLOAD", path, query, self.host);
+        let signature = self.signature(&amz_date, &scope, &canonical);
+        format!("{}{}?{}&X-Amz-Signature={}", self.endpoint, path, query, signature)
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let resp = self.signed(reqwest::Method::DELETE, key, EMPTY_SHA256)
+                .send()
+                .await
+                .map_err(|e| format!("S3 delete failed: {}", e))?;
+            // S3 trả 204 cả khi key không tồn tại
+            if !resp.status().is_success() && resp.status() != reqwest::StatusCode::NOT_FOUND {
+                return Err(format!("S3 delete returned {}", resp.status()));
+            }
+            Ok(())
+        })
+    }
+}
+
+// sha256("")
+const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+// URI-encode kiểu AWS: giữ A-Z a-z 0-9 - _ . ~ (và / nếu là path)
+fn uri_encode(input: &str, keep_slash: bool) -> String {
+    let mut out = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(byte as char),
+            b'/' if keep_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
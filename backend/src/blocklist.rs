@@ -0,0 +1,175 @@
+// Chặn guest / IP - cache theo shop + endpoint cho admin
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use prost::Message as ProstMessage;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::auth::AdminAuth;
+use crate::contract::{BlockListResponse, BlockRequest, ContractError};
+use crate::db::AstraRepo;
+use crate::request_id;
+use crate::state::AppState;
+
+// Node khác có thể chặn/bỏ chặn → cache chỉ sống ngắn
+const CACHE_TTL: Duration = Duration::from_secs(60);
+
+pub fn guest_target(guest_id: u64) -> String {
+    format!("guest:{}", guest_id)
+}
+
+pub fn ip_target(ip: &str) -> String {
+    format!("ip:{}", ip)
+}
+
+struct CachedShop {
+    loaded_at: Instant,
+    targets: HashSet<String>,
+}
+
+pub struct BlockList {
+    repo: Arc<AstraRepo>,
+    cache: RwLock<HashMap<String, CachedShop>>,
+}
+
+impl BlockList {
+    pub fn new(repo: Arc<AstraRepo>) -> Self {
+        Self { repo, cache: RwLock::new(HashMap::new()) }
+    }
+
+    /// Guest (nếu có) hoặc IP có đang bị chặn trong shop không
+    pub async fn is_blocked(&self, shop_id: &str, guest_id: Option<u64>, ip: &str) -> bool {
+        let targets = self.targets(shop_id).await;
+        guest_id.is_some_and(|id| targets.contains(&guest_target(id)))
+            || (!ip.is_empty() && targets.contains(&ip_target(ip)))
+    }
+
+    pub async fn block(&self, shop_id: &str, target: &str, reason: &str) -> Result<(), ContractError> {
+        self.repo.add_block(shop_id, target, reason).await?;
+        if let Some(shop) = self.cache.write().unwrap().get_mut(shop_id) {
+            shop.targets.insert(target.to_string());
+        }
+        Ok(())
+    }
+
+    pub async fn unblock(&self, shop_id: &str, target: &str) -> Result<(), ContractError> {
+        self.repo.remove_block(shop_id, target).await?;
+        if let Some(shop) = self.cache.write().unwrap().get_mut(shop_id) {
+            shop.targets.remove(target);
+        }
+        Ok(())
+    }
+
+    async fn targets(&self, shop_id: &str) -> HashSet<String> {
+        if let Some(shop) = self.cache.read().unwrap().get(shop_id) {
+            if shop.loaded_at.elapsed() < CACHE_TTL {
+                return shop.targets.clone();
+            }
+        }
+
+        let targets: HashSet<String> = match self.repo.list_blocks(shop_id).await {
+            Ok(blocks) => blocks.into_iter().map(|b| b.target).collect(),
+            Err(e) => {
+                eprintln!("❌ Load blocks for {} failed: {:?}", shop_id, e);
+                // Giữ cache cũ (nếu có) khi DB lỗi
+                return self.cache.read().unwrap()
+                    .get(shop_id)
+                    .map(|s| s.targets.clone())
+                    .unwrap_or_default();
+            }
+        };
+
+        self.cache.write().unwrap().insert(shop_id.to_string(), CachedShop {
+            loaded_at: Instant::now(),
+            targets: targets.clone(),
+        });
+        targets
+    }
+}
+
+// GET /blocks - Danh sách guest/IP đang bị chặn
+pub async fn list_blocks_handler(State(state): State<Arc<AppState>>, admin: AdminAuth) -> impl IntoResponse {
+    let resp = match state.repo.list_blocks(&admin.shop_id).await {
+        Ok(blocks) => BlockListResponse { success: true, blocks, ..Default::default() },
+        Err(e) => BlockListResponse { success: false, blocks: vec![], error: e.to_string(), request_id: request_id::current() },
+    };
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+}
+
+// POST /guests/:id/block - Chặn guest (+ IP gần nhất nếu include_ip)
+pub async fn block_guest_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path(guest_id): Path<u64>,
+    body: Bytes,
+) -> impl IntoResponse {
+    let req = match BlockRequest::decode(&body[..]) {
+        Ok(r) => r,
+        Err(_) => return StatusCode::BAD_REQUEST,
+    };
+    let blocks = &state.ws_state.blocks;
+
+    if let Err(e) = blocks.block(&admin.shop_id, &guest_target(guest_id), &req.reason).await {
+        eprintln!("❌ Block guest failed: {:?}", e);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    let mut detail = req.reason.clone();
+    if req.include_ip {
+        match state.repo.get_guest(&admin.shop_id, guest_id).await {
+            Ok(Some(guest)) if !guest.last_ip.is_empty() => {
+                if let Err(e) = blocks.block(&admin.shop_id, &ip_target(&guest.last_ip), &req.reason).await {
+                    eprintln!("❌ Block IP failed: {:?}", e);
+                    return StatusCode::INTERNAL_SERVER_ERROR;
+                }
+                detail = format!("{} (ip {})", req.reason, guest.last_ip);
+            }
+            Ok(_) => println!("⚠️ Guest {} has no known IP, skipping IP ban", guest_id),
+            Err(e) => eprintln!("❌ Load guest for IP ban failed: {:?}", e),
+        }
+    }
+
+    if req.disconnect {
+        if let Err(e) = state.ws_state.cluster.kick_guest(&admin.shop_id, guest_id).await {
+            eprintln!("❌ Disconnect blocked guest failed: {:?}", e);
+        }
+    }
+
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "guest.block", guest_id, &admin.agent_id, &detail).await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("🚫 Blocked guest {} of shop {}", guest_id, admin.shop_id);
+
+    StatusCode::NO_CONTENT
+}
+
+// DELETE /guests/:id/block - Bỏ chặn guest và IP gần nhất của guest
+pub async fn unblock_guest_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path(guest_id): Path<u64>,
+) -> impl IntoResponse {
+    let blocks = &state.ws_state.blocks;
+
+    if let Err(e) = blocks.unblock(&admin.shop_id, &guest_target(guest_id)).await {
+        eprintln!("❌ Unblock guest failed: {:?}", e);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+    if let Ok(Some(guest)) = state.repo.get_guest(&admin.shop_id, guest_id).await {
+        if !guest.last_ip.is_empty() {
+            let _ = blocks.unblock(&admin.shop_id, &ip_target(&guest.last_ip)).await;
+        }
+    }
+
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "guest.unblock", guest_id, &admin.agent_id, "").await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("✅ Unblocked guest {} of shop {}", guest_id, admin.shop_id);
+
+    StatusCode::NO_CONTENT
+}
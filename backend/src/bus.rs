@@ -0,0 +1,198 @@
+// Broker phát frame giữa các node theo shop: Redis pub/sub (mặc định) hoặc NATS JetStream (chọn bằng MESSAGE_BUS)
+use futures::future::BoxFuture;
+use futures::stream::{BoxStream, StreamExt};
+use redis::AsyncCommands;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_nats::jetstream::{self, consumer, stream};
+
+use crate::redis_conn::RedisConn;
+
+// JetStream: giữ frame đủ lâu để node mất kết nối ngắn nhận bù
+const NATS_MAX_AGE: Duration = Duration::from_secs(3600);
+// Consumer bền của node không còn chạy tự bị xoá sau khoảng này
+const NATS_CONSUMER_INACTIVE: Duration = Duration::from_secs(120);
+
+/// Bảo đảm giao frame của broker.
+/// Client đã chịu được cả hai: tin trùng bỏ qua theo message_id, tin thiếu lấy lại qua /sync khi kết nối lại
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Delivery {
+    /// Redis pub/sub: node không đang subscribe lúc publish thì mất frame đó
+    AtMostOnce,
+    /// JetStream: frame chưa được node ack sẽ giao lại → một frame có thể tới hai lần
+    AtLeastOnce,
+}
+
+/// Frame (WsEnvelope đã encode) nhận từ broker. Stream kết thúc khi mất kết nối → subscribe lại
+pub type Frames = BoxStream<'static, Vec<u8>>;
+
+/// Publish/subscribe theo shop. Lỗi trả về dạng chuỗi để log.
+pub trait MessageBus: Send + Sync {
+    fn delivery(&self) -> Delivery;
+    fn publish<'a>(&'a self, shop_id: &'a str, payload: Vec<u8>) -> BoxFuture<'a, Result<(), String>>;
+    /// None = mọi shop
+    fn subscribe<'a>(&'a self, shop_id: Option<&'a str>) -> BoxFuture<'a, Result<Frames, String>>;
+}
+
+/// MESSAGE_BUS=redis (mặc định) | nats. `consumer`: tên consumer bền của node (JetStream),
+/// None = consumer tạm (turbochat-cli tail). Cấu hình sai thì dừng luôn.
+pub async fn from_env(redis_url: &str, consumer: Option<String>) -> Arc<dyn MessageBus> {
+    match std::env::var("MESSAGE_BUS").unwrap_or_default().as_str() {
+        "" | "redis" => {
+            println!("📡 Message bus: Redis pub/sub ({:?})", Delivery::AtMostOnce);
+            let redis = RedisConn::open(redis_url).unwrap_or_else(|e| panic!("❌ Redis message bus misconfigured: {}", e));
+            Arc::new(RedisBus { redis })
+        }
+        "nats" => {
+            let bus = NatsBus::from_env(consumer).await.unwrap_or_else(|e| panic!("❌ NATS message bus misconfigured: {}", e));
+            println!("📡 Message bus: NATS JetStream stream {} ({:?})", bus.stream, Delivery::AtLeastOnce);
+            Arc::new(bus)
+        }
+        other => panic!("❌ Unknown MESSAGE_BUS '{}' (expected redis or nats)", other),
+    }
+}
+
+// ========== REDIS ==========
+
+/// Kênh chat:<shop_id>
+pub struct RedisBus {
+    redis: RedisConn,
+}
+
+impl MessageBus for RedisBus {
+    fn delivery(&self) -> Delivery {
+        Delivery::AtMostOnce
+    }
+
+    fn publish<'a>(&'a self, shop_id: &'a str, payload: Vec<u8>) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let result: redis::RedisResult<()> = async {
+                self.redis.get().await?.publish(format!("chat:{}", shop_id), payload).await
+            }.await;
+            self.redis.checked(result).await.map_err(|e| e.to_string())
+        })
+    }
+
+    fn subscribe<'a>(&'a self, shop_id: Option<&'a str>) -> BoxFuture<'a, Result<Frames, String>> {
+        Box::pin(async move {
+            let result: redis::RedisResult<Frames> = async {
+                let mut pubsub = self.redis.client().get_async_pubsub().await?;
+                match shop_id {
+                    Some(shop_id) => pubsub.subscribe(format!("chat:{}", shop_id)).await?,
+                    None => pubsub.psubscribe("chat:*").await?,
+                }
+                Ok(pubsub.into_on_message().map(|msg| msg.get_payload_bytes().to_vec()).boxed())
+            }.await;
+            result.map_err(|e| e.to_string())
+        })
+    }
+}
+
+// ========== NATS JETSTREAM ==========
+
+/// NATS_URL (mặc định nats://localhost:4222), NATS_STREAM (mặc định TURBOCHAT).
+/// Subject chat.<shop_id>; stream được tạo nếu chưa có (giữ frame 1 giờ)
+pub struct NatsBus {
+    jetstream: jetstream::Context,
+    stream: String,
+    consumer: Option<String>,
+}
+
+impl NatsBus {
+    async fn from_env(consumer: Option<String>) -> Result<Self, String> {
+        let url = std::env::var("NATS_URL").unwrap_or_else(|_| "nats://localhost:4222".to_string());
+        let stream = std::env::var("NATS_STREAM").unwrap_or_else(|_| "TURBOCHAT".to_string());
+        let client = async_nats::connect(&url).await.map_err(|e| format!("connect {} failed: {}", url, e))?;
+        let jetstream = jetstream::new(client);
+        jetstream
+            .get_or_create_stream(stream::Config {
+                name: stream.clone(),
+                subjects: vec!["chat.>".to_string()],
+                max_age: NATS_MAX_AGE,
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| format!("create stream {} failed: {}", stream, e))?;
+        Ok(Self { jetstream, stream, consumer })
+    }
+}
+
+// shop_id thành một token subject: ký tự ngoài chữ/số/_/- (vd. '.', '*', '>') → ~XX
+fn subject(shop_id: &str) -> String {
+    let mut subject = String::from("chat.");
+    for b in shop_id.bytes() {
+        if b.is_ascii_alphanumeric() || b == b'_' || b == b'-' {
+            subject.push(b as char);
+        } else {
+            subject.push_str(&format!("~{:02X}", b));
+        }
+    }
+    subject
+}
+
+impl MessageBus for NatsBus {
+    fn delivery(&self) -> Delivery {
+        Delivery::AtLeastOnce
+    }
+
+    fn publish<'a>(&'a self, shop_id: &'a str, payload: Vec<u8>) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            // Chờ server xác nhận đã ghi vào stream
+            let ack = self.jetstream.publish(subject(shop_id), payload.into()).await.map_err(|e| e.to_string())?;
+            ack.await.map(|_| ()).map_err(|e| e.to_string())
+        })
+    }
+
+    fn subscribe<'a>(&'a self, shop_id: Option<&'a str>) -> BoxFuture<'a, Result<Frames, String>> {
+        Box::pin(async move {
+            let stream = self.jetstream.get_stream(&self.stream).await.map_err(|e| e.to_string())?;
+            let filter_subject = shop_id.map(subject).unwrap_or_else(|| "chat.>".to_string());
+
+            match (&self.consumer, shop_id) {
+                // Node: consumer bền, ack từng frame ngay khi node nhận. Node crash trước khi fan-out thì
+                // các kết nối của nó cũng mất theo và tự /sync khi nối lại node khác
+                (Some(name), None) => {
+                    let consumer = stream
+                        .get_or_create_consumer(name, consumer::pull::Config {
+                            durable_name: Some(name.clone()),
+                            filter_subject,
+                            deliver_policy: consumer::DeliverPolicy::New,
+                            ack_policy: consumer::AckPolicy::Explicit,
+                            inactive_threshold: NATS_CONSUMER_INACTIVE,
+                            ..Default::default()
+                        })
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    let messages = consumer.messages().await.map_err(|e| e.to_string())?;
+                    Ok(messages
+                        .take_while(|msg| futures::future::ready(msg.is_ok()))
+                        .filter_map(|msg| async move {
+                            let msg = msg.ok()?;
+                            if let Err(e) = msg.ack().await {
+                                eprintln!("❌ NATS ack failed: {}", e);
+                            }
+                            Some(msg.payload.to_vec())
+                        })
+                        .boxed())
+                }
+                // Theo dõi tạm: consumer có thứ tự, không ack
+                _ => {
+                    let consumer = stream
+                        .create_consumer(consumer::pull::OrderedConfig {
+                            filter_subject,
+                            deliver_policy: consumer::DeliverPolicy::New,
+                            ..Default::default()
+                        })
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    let messages = consumer.messages().await.map_err(|e| e.to_string())?;
+                    Ok(messages
+                        .take_while(|msg| futures::future::ready(msg.is_ok()))
+                        .filter_map(|msg| async move { msg.ok().map(|msg| msg.payload.to_vec()) })
+                        .boxed())
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,382 @@
+// Chiến dịch: admin gửi một tin tới nhóm khách (segment: hoạt động gần đây / có tag) - ngay hoặc hẹn giờ.
+// Scheduler gửi dần từng khách qua pipeline thường (CAMPAIGN_RATE_PER_SEC), dừng được giữa chừng.
+// Bộ đếm đã gửi / đã nhận / đã xem là các set trên Redis (như bộ đếm hạn mức): widget báo về không phải ghi DB
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use prost::Message as ProstMessage;
+use redis::AsyncCommands;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::auth::AdminAuth;
+use crate::contract::{
+    Campaign, CampaignListResponse, CampaignPreview, CampaignSegment, CampaignStatus, ContractError, Message as ChatMessage,
+    MessageId, ReadReceipt,
+};
+use crate::db::now_us;
+use crate::request_id;
+use crate::state::AppState;
+use crate::websocket::{self, WebSocketState, MAX_CONTENT_BYTES};
+
+// Hẹn xa nhất 1 năm
+const MAX_AHEAD_US: u64 = 365 * 86_400 * 1_000_000;
+const MAX_NAME_CHARS: usize = 80;
+const MAX_TAGS: usize = 10;
+// Khoá Redis giữ chiến dịch đang gửi - nhiều node cùng quét không gửi trùng; node chết thì node khác gửi tiếp sau chừng này
+const CLAIM_TTL_SECS: u64 = 6 * 3600;
+// Set đã gửi / đã nhận / đã xem giữ chừng này rồi tự hết hạn
+const COUNTER_TTL_SECS: i64 = 180 * 86_400;
+// Mỗi chừng này khách: ghi tiến độ, xem đã bị huỷ chưa, cập nhật danh sách khách đang online
+const PROGRESS_EVERY: usize = 50;
+
+// Khách đã được gửi / widget đã nhận / đã xem (kind = "sent" / "delivered" / "read")
+fn members_key(shop_id: &str, campaign_id: u64, kind: &str) -> String {
+    format!("campaign:{}:{}:{}", shop_id, campaign_id, kind)
+}
+
+// Chiến dịch đã gửi khi khách không online - tính "đã nhận" ở lần kết nối sau
+fn pending_key(shop_id: &str, guest_id: u64) -> String {
+    format!("campaign:pending:{}:{}", shop_id, guest_id)
+}
+
+// campaign_id → lúc gửi (ms) của các tin chiến dịch khách chưa xem
+fn inbox_key(shop_id: &str, guest_id: u64) -> String {
+    format!("campaign:inbox:{}:{}", shop_id, guest_id)
+}
+
+// GET /campaigns - Mọi chiến dịch của shop kèm bộ đếm (mới nhất trước)
+pub async fn list_campaigns_handler(State(state): State<Arc<AppState>>, admin: AdminAuth) -> impl IntoResponse {
+    let resp = match state.repo.list_campaigns(&admin.shop_id).await {
+        Ok(mut items) => {
+            fill_counters(&state.ws_state, &admin.shop_id, &mut items).await;
+            CampaignListResponse { success: true, items, ..Default::default() }
+        }
+        Err(e) => CampaignListResponse { success: false, items: vec![], error: e.to_string(), request_id: request_id::current() },
+    };
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+}
+
+// POST /campaigns - Tạo chiến dịch (send_at_us = 0 → gửi ở lượt quét tới), trả lại bản đã lưu
+pub async fn create_campaign_handler(State(state): State<Arc<AppState>>, admin: AdminAuth, body: Bytes) -> impl IntoResponse {
+    let Ok(req) = Campaign::decode(&body[..]) else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+    let now = now_us();
+    let name = req.name.trim().chars().take(MAX_NAME_CHARS).collect::<String>();
+    let text = String::from_utf8_lossy(&req.content);
+    if name.is_empty() || text.trim().is_empty() || req.content.len() > MAX_CONTENT_BYTES {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    }
+    if req.send_at_us != 0 && (req.send_at_us <= now || req.send_at_us > now + MAX_AHEAD_US) {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    }
+    let Some(segment) = normalize_segment(req.segment.unwrap_or_default()) else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+
+    let campaign = Campaign {
+        campaign_id: state.repo.ids.next_id().0,
+        name,
+        content: req.content,
+        segment: Some(segment),
+        send_at_us: if req.send_at_us == 0 { now } else { req.send_at_us },
+        status: CampaignStatus::Scheduled as i32,
+        created_by: admin.agent_id.clone(),
+        created_at: now,
+        ..Default::default()
+    };
+    if let Err(e) = state.repo.upsert_campaign(&admin.shop_id, &campaign).await {
+        eprintln!("❌ Save campaign failed: {:?}", e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new());
+    }
+
+    let detail = format!("{} send_at={}", campaign.campaign_id, campaign.send_at_us);
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "campaign.create", 0, &admin.agent_id, &detail).await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("📣 Campaign {} ({:?}) scheduled for shop {} at {}", campaign.campaign_id, campaign.name, admin.shop_id, campaign.send_at_us);
+
+    (StatusCode::OK, Bytes::from(campaign.encode_to_vec()))
+}
+
+// POST /campaigns/preview - Số khách đang khớp segment (body: CampaignSegment)
+pub async fn preview_handler(State(state): State<Arc<AppState>>, admin: AdminAuth, body: Bytes) -> impl IntoResponse {
+    let Some(segment) = CampaignSegment::decode(&body[..]).ok().and_then(normalize_segment) else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+    match state.repo.segment_guests(&admin.shop_id, &segment).await {
+        Ok(guests) => {
+            let preview = CampaignPreview { recipients: guests.len() as u32 };
+            (StatusCode::OK, Bytes::from(preview.encode_to_vec()))
+        }
+        Err(e) => {
+            eprintln!("❌ Load segment of {} failed: {:?}", admin.shop_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new())
+        }
+    }
+}
+
+// DELETE /campaigns/:id - Huỷ chiến dịch chưa gửi xong (đang gửi → dừng ở mốc tiến độ kế tiếp)
+pub async fn cancel_campaign_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path(campaign_id): Path<u64>,
+) -> impl IntoResponse {
+    let mut campaign = match state.repo.get_campaign(&admin.shop_id, campaign_id).await {
+        Ok(Some(campaign)) => campaign,
+        Ok(None) => return StatusCode::NOT_FOUND,
+        Err(e) => {
+            eprintln!("❌ Load campaign {} failed: {:?}", campaign_id, e);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    };
+    if !matches!(campaign.status(), CampaignStatus::Scheduled | CampaignStatus::Sending) {
+        return StatusCode::CONFLICT;
+    }
+    campaign.set_status(CampaignStatus::Cancelled);
+    campaign.finished_at = now_us();
+    if let Err(e) = state.repo.upsert_campaign(&admin.shop_id, &campaign).await {
+        eprintln!("❌ Cancel campaign failed: {:?}", e);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "campaign.cancel", 0, &admin.agent_id, &campaign_id.to_string()).await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("📣 Campaign {} of shop {} cancelled", campaign_id, admin.shop_id);
+    StatusCode::NO_CONTENT
+}
+
+// Tag: bỏ khoảng trắng, bỏ trùng; quá MAX_TAGS → None
+fn normalize_segment(mut segment: CampaignSegment) -> Option<CampaignSegment> {
+    let mut seen = HashSet::new();
+    segment.tags = segment.tags.iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty() && seen.insert(t.to_lowercase()))
+        .collect();
+    (segment.tags.len() <= MAX_TAGS).then_some(segment)
+}
+
+// Điền sent / delivered / read từ Redis (set hết hạn → giữ số đã lưu)
+async fn fill_counters(state: &WebSocketState, shop_id: &str, items: &mut [Campaign]) {
+    if items.is_empty() {
+        return;
+    }
+    let counts: redis::RedisResult<Vec<u32>> = async {
+        let mut conn = state.redis.get().await?;
+        let mut pipe = redis::pipe();
+        for campaign in items.iter() {
+            for kind in ["sent", "delivered", "read"] {
+                pipe.scard(members_key(shop_id, campaign.campaign_id, kind));
+            }
+        }
+        pipe.query_async(&mut conn).await
+    }.await;
+
+    match state.redis.checked(counts).await {
+        Ok(counts) => {
+            for (campaign, c) in items.iter_mut().zip(counts.chunks(3)) {
+                campaign.sent = campaign.sent.max(c[0]);
+                campaign.delivered = campaign.delivered.max(c[1]);
+                campaign.read = campaign.read.max(c[2]);
+            }
+        }
+        Err(e) => eprintln!("❌ Load campaign counters of {} failed: {:?}", shop_id, e),
+    }
+}
+
+// ============================================================================
+// BIÊN NHẬN - widget đã nhận (khách kết nối) / đã xem (frame ReadReceipt)
+// ============================================================================
+
+/// Khách vừa kết nối → các tin chiến dịch gửi lúc khách vắng đã tới widget
+pub async fn on_guest_connected(state: &WebSocketState, shop_id: &str, guest_id: u64) {
+    let result: redis::RedisResult<()> = async {
+        let mut conn = state.redis.get().await?;
+        let pending: Vec<u64> = conn.smembers(pending_key(shop_id, guest_id)).await?;
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let mut pipe = redis::pipe();
+        for campaign_id in &pending {
+            pipe.sadd(members_key(shop_id, *campaign_id, "delivered"), guest_id).ignore();
+        }
+        pipe.del(pending_key(shop_id, guest_id)).ignore();
+        pipe.query_async(&mut conn).await
+    }.await;
+    if let Err(e) = state.redis.checked(result).await {
+        eprintln!("❌ Campaign delivery for guest {} failed: {:?}", guest_id, e);
+    }
+}
+
+/// Khách mở khung chat, thấy tới up_to_message_id → tin chiến dịch gửi trước đó tính là đã xem
+pub async fn on_read(state: &WebSocketState, shop_id: &str, guest_id: u64, receipt: ReadReceipt) {
+    let seen_up_to_ms = MessageId(receipt.up_to_message_id).timestamp();
+    let result: redis::RedisResult<()> = async {
+        let mut conn = state.redis.get().await?;
+        let inbox: HashMap<u64, u64> = conn.hgetall(inbox_key(shop_id, guest_id)).await?;
+        let read: Vec<u64> = inbox.into_iter().filter(|&(_, sent_ms)| sent_ms <= seen_up_to_ms).map(|(id, _)| id).collect();
+        if read.is_empty() {
+            return Ok(());
+        }
+        let mut pipe = redis::pipe();
+        for campaign_id in &read {
+            pipe.sadd(members_key(shop_id, *campaign_id, "delivered"), guest_id).ignore()
+                .sadd(members_key(shop_id, *campaign_id, "read"), guest_id).ignore()
+                .srem(pending_key(shop_id, guest_id), *campaign_id).ignore();
+        }
+        pipe.hdel(inbox_key(shop_id, guest_id), &read).ignore();
+        pipe.query_async(&mut conn).await
+    }.await;
+    if let Err(e) = state.redis.checked(result).await {
+        eprintln!("❌ Campaign read receipt for guest {} failed: {:?}", guest_id, e);
+    }
+}
+
+// ============================================================================
+// SENDER - quét định kỳ, chiến dịch tới giờ (hoặc đang gửi dở của node đã chết) → gửi dần
+// ============================================================================
+pub async fn campaign_task(state: Arc<WebSocketState>) {
+    let interval_secs = std::env::var("CAMPAIGN_TICK_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(15);
+    println!("📣 Campaign sender started (every {}s)", interval_secs);
+
+    loop {
+        if let Err(e) = start_due(&state).await {
+            eprintln!("❌ Campaign tick failed: {:?}", e);
+        }
+        tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+    }
+}
+
+async fn start_due(state: &Arc<WebSocketState>) -> Result<(), ContractError> {
+    let now = now_us();
+    for shop_id in state.repo.list_shop_ids().await? {
+        let due: Vec<Campaign> = match state.repo.list_campaigns(&shop_id).await {
+            Ok(items) => items.into_iter()
+                .filter(|c| match c.status() {
+                    CampaignStatus::Scheduled => c.send_at_us <= now,
+                    CampaignStatus::Sending => true,
+                    _ => false,
+                })
+                .collect(),
+            Err(e) => {
+                eprintln!("❌ Load campaigns for {} failed: {:?}", shop_id, e);
+                continue;
+            }
+        };
+
+        for campaign in due {
+            let key = format!("campaign:claim:{}:{}", shop_id, campaign.campaign_id);
+            if !websocket::claim_once(state, &key, CLAIM_TTL_SECS).await {
+                continue;
+            }
+            let (state, shop_id) = (state.clone(), shop_id.clone());
+            tokio::spawn(async move {
+                let campaign_id = campaign.campaign_id;
+                if let Err(e) = send_campaign(&state, &shop_id, campaign).await {
+                    eprintln!("❌ Campaign {} of {} stopped: {:?}", campaign_id, shop_id, e);
+                }
+            });
+        }
+    }
+    Ok(())
+}
+
+// Gửi cho từng khách của segment, bỏ qua khách đã gửi (chạy tiếp sau khi node trước chết giữa chừng)
+async fn send_campaign(state: &Arc<WebSocketState>, shop_id: &str, mut campaign: Campaign) -> Result<(), ContractError> {
+    let rate = std::env::var("CAMPAIGN_RATE_PER_SEC")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .filter(|&r| r > 0)
+        .unwrap_or(10);
+    let pause = Duration::from_secs(1) / rate;
+
+    let recipients = state.repo.segment_guests(shop_id, &campaign.segment.clone().unwrap_or_default()).await?;
+    if campaign.status() == CampaignStatus::Scheduled {
+        campaign.set_status(CampaignStatus::Sending);
+        campaign.recipients = recipients.len() as u32;
+        state.repo.upsert_campaign(shop_id, &campaign).await?;
+    }
+    println!("📣 Sending campaign {} of shop {} to {} guests", campaign.campaign_id, shop_id, recipients.len());
+
+    let mut conn = redis_checked(state, state.redis.get().await).await?;
+    let sent_key = members_key(shop_id, campaign.campaign_id, "sent");
+    let mut online = HashMap::new();
+    for (i, guest_id) in recipients.into_iter().enumerate() {
+        if i % PROGRESS_EVERY == 0 {
+            // Admin huỷ giữa chừng → dừng
+            match state.repo.get_campaign(shop_id, campaign.campaign_id).await? {
+                Some(latest) if latest.status() == CampaignStatus::Cancelled => {
+                    println!("📣 Campaign {} cancelled after {} guests", campaign.campaign_id, campaign.sent);
+                    return Ok(());
+                }
+                _ => {}
+            }
+            if i > 0 {
+                campaign.sent = redis_checked(state, conn.scard(&sent_key).await).await?;
+                state.repo.upsert_campaign(shop_id, &campaign).await?;
+            }
+            online = state.cluster.live_guests(shop_id).await.unwrap_or_else(|e| {
+                eprintln!("❌ Load live visitors of {} failed: {:?}", shop_id, e);
+                HashMap::new()
+            });
+        }
+
+        let already: bool = redis_checked(state, conn.sismember(&sent_key, guest_id).await).await?;
+        if already || state.blocks.is_blocked(shop_id, Some(guest_id), "").await {
+            continue;
+        }
+
+        let msg = ChatMessage {
+            shop_id: shop_id.to_string(),
+            guest_id,
+            sender_type: "admin".to_string(),
+            content_crc: crc32c::crc32c(&campaign.content),
+            content: campaign.content.clone(),
+            ..Default::default()
+        };
+        websocket::ingest_message(state, msg, "").await;
+
+        let now_ms = now_us() / 1000;
+        let mut pipe = redis::pipe();
+        pipe.sadd(&sent_key, guest_id).ignore()
+            .expire(&sent_key, COUNTER_TTL_SECS).ignore()
+            .hset(inbox_key(shop_id, guest_id), campaign.campaign_id, now_ms).ignore()
+            .expire(inbox_key(shop_id, guest_id), COUNTER_TTL_SECS).ignore();
+        // Đang mở widget → tin tới ngay; không thì tính khi khách kết nối lại
+        if online.contains_key(&guest_id) {
+            let delivered = members_key(shop_id, campaign.campaign_id, "delivered");
+            pipe.sadd(&delivered, guest_id).ignore().expire(&delivered, COUNTER_TTL_SECS).ignore();
+        } else {
+            pipe.sadd(pending_key(shop_id, guest_id), campaign.campaign_id).ignore()
+                .expire(pending_key(shop_id, guest_id), COUNTER_TTL_SECS).ignore();
+        }
+        let _: () = redis_checked(state, pipe.query_async(&mut conn).await).await?;
+
+        tokio::time::sleep(pause).await;
+    }
+
+    // Set delivered / read có thể chưa tồn tại lúc gửi (chưa ai nhận) → đặt hạn khi xong
+    for kind in ["delivered", "read"] {
+        let _: bool = redis_checked(state, conn.expire(members_key(shop_id, campaign.campaign_id, kind), COUNTER_TTL_SECS).await).await?;
+    }
+    campaign.sent = redis_checked(state, conn.scard(&sent_key).await).await?;
+    campaign.set_status(CampaignStatus::Sent);
+    campaign.finished_at = now_us();
+    state.repo.upsert_campaign(shop_id, &campaign).await?;
+    println!("📣 Campaign {} of shop {} sent to {} guests", campaign.campaign_id, shop_id, campaign.sent);
+    Ok(())
+}
+
+// Lỗi kết nối → bỏ kết nối dùng chung (redis_conn.rs) trước khi đổi sang ContractError
+async fn redis_checked<T>(state: &WebSocketState, result: redis::RedisResult<T>) -> Result<T, ContractError> {
+    state.redis.checked(result).await.map_err(|e| ContractError::DbError(format!("Redis error: {}", e)))
+}
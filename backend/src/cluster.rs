@@ -0,0 +1,446 @@
+// ============================================================================
+// CLUSTER - nhiều backend chạy sau load balancer, chung một Redis
+// - Mỗi node giữ NODE_ID riêng (khoá cluster:node:<id>, gia hạn định kỳ) → Snowflake id không trùng
+// - Sổ kết nối trên Redis (hash cluster:conns:<shop>): kết nối nào ở node nào, khách hay admin,
+//   admin có đang vắng mặt → trạng thái Presence tính trên cả cụm
+// - Giới hạn kết nối đồng thời theo IP khách trong một shop (đếm trên sổ, cả cụm) chống dội kết nối
+// - Frame chỉ dành cho một khách (ngắt kết nối, báo vượt hạn mức) đi thẳng tới node giữ kết nối
+//   qua kênh node:<id>:* thay vì phát cho mọi node
+// ============================================================================
+
+use futures::StreamExt;
+use prost::Message as ProstMessage;
+use redis::aio::MultiplexedConnection;
+use redis::AsyncCommands;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::{self, ConnectionLimits};
+use crate::contract::WsEnvelope;
+use crate::maintenance::NOTICE_CHANNEL;
+use crate::redis_conn::RedisConn;
+use crate::websocket::WebSocketState;
+
+// Node không gia hạn trong khoảng này coi như đã chết, kết nối của nó bị dọn khỏi sổ
+const NODE_TTL_SECS: u64 = 30;
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+// Khởi động lại nhanh với cùng NODE_ID: chờ khoá của lần chạy trước hết hạn
+const CLAIM_RETRY: Duration = Duration::from_secs(2);
+
+fn node_key(node_id: u16) -> String {
+    format!("cluster:node:{}", node_id)
+}
+
+fn conns_key(shop_id: &str) -> String {
+    format!("cluster:conns:{}", shop_id)
+}
+
+// Kết nối khách theo IP trong một shop: trường giống sổ kết nối
+fn ip_key(shop_id: &str, ip: &str) -> String {
+    format!("cluster:ip:{}:{}", shop_id, ip)
+}
+
+fn frame_channel(node_id: u16) -> String {
+    format!("node:{}:frame", node_id)
+}
+
+fn kick_channel(node_id: u16) -> String {
+    format!("node:{}:kick", node_id)
+}
+
+// Trường "<node>/<boot>/<seq>" → "<node>/<boot>/" (lần chạy của node giữ kết nối)
+fn owner(field: &str) -> String {
+    field.rsplit_once('/').map(|(prefix, _)| format!("{}/", prefix)).unwrap_or_default()
+}
+
+// Các lần chạy còn sống (khoá cluster:node:<id> còn và đúng boot) trong số các trường
+async fn alive_owners<'a>(conn: &mut MultiplexedConnection, fields: impl Iterator<Item = &'a String>) -> redis::RedisResult<HashSet<String>> {
+    let mut nodes: Vec<u16> = fields
+        .filter_map(|field| field.split('/').next()?.parse().ok())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    if nodes.is_empty() {
+        return Ok(HashSet::new());
+    }
+    nodes.sort_unstable();
+    // MGET tường minh: AsyncCommands::mget đổi sang GET khi chỉ có một khoá
+    let boots: Vec<Option<String>> = redis::cmd("MGET").arg(nodes.iter().map(|n| node_key(*n)).collect::<Vec<_>>()).query_async(conn).await?;
+    Ok(nodes.iter().zip(boots)
+        .filter_map(|(node, boot)| Some(format!("{}/{}/", node, boot?)))
+        .collect())
+}
+
+/// Một kết nối WS trong sổ
+#[derive(Clone, Debug, PartialEq)]
+pub enum Peer {
+    // since_us: lúc mở kết nối (thời gian khách ở trên trang)
+    Guest { guest_id: u64, since_us: u64 },
+    Admin { agent_id: Option<String>, away: bool },
+}
+
+impl Peer {
+    // "guest:<id>:<since_us>" | "admin:<agent_id>:<0|1>" (agent_id rỗng = admin không gửi agent_id)
+    fn encode(&self) -> String {
+        match self {
+            Peer::Guest { guest_id, since_us } => format!("guest:{}:{}", guest_id, since_us),
+            Peer::Admin { agent_id, away } => format!("admin:{}:{}", agent_id.as_deref().unwrap_or(""), *away as u8),
+        }
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        // Node bản cũ ghi "guest:<id>" (không có since_us)
+        if let Some(rest) = value.strip_prefix("guest:") {
+            let (guest_id, since_us) = rest.split_once(':').unwrap_or((rest, "0"));
+            return Some(Peer::Guest { guest_id: guest_id.parse().ok()?, since_us: since_us.parse().unwrap_or(0) });
+        }
+        let (agent_id, away) = value.strip_prefix("admin:")?.rsplit_once(':')?;
+        Some(Peer::Admin {
+            agent_id: (!agent_id.is_empty()).then(|| agent_id.to_string()),
+            away: away == "1",
+        })
+    }
+}
+
+/// Kết nối đã đăng ký - giữ lại để cập nhật / huỷ khi ngắt
+pub struct Registration {
+    shop_id: String,
+    ip: Option<String>,
+    field: String,
+}
+
+/// Giới hạn kết nối bị vượt
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Limit {
+    Ip,
+}
+
+impl Limit {
+    /// Reason của close frame
+    pub fn reason(self) -> &'static str {
+        match self {
+            Limit::Ip => "ip_limit",
+        }
+    }
+}
+
+pub struct Cluster {
+    pub node_id: u16,
+    redis: RedisConn,
+    // Giá trị khoá cluster:node:<id> của lần chạy này (phân biệt với lần chạy trước cùng NODE_ID)
+    boot: String,
+    next_conn: AtomicU64,
+}
+
+impl Cluster {
+    /// Giành NODE_ID trên Redis. Node khác đang giữ cùng id → lỗi (hai node trùng id sinh trùng message_id)
+    pub async fn join(redis_url: &str, node_id: u16) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let boot = format!("{}-{}", std::process::id(), chrono::Utc::now().timestamp_micros());
+        let cluster = Self { node_id, redis: RedisConn::open(redis_url)?, boot, next_conn: AtomicU64::new(0) };
+
+        let mut conn = cluster.redis.get().await?;
+        let mut waited = Duration::ZERO;
+        loop {
+            let claimed: Option<String> = redis::cmd("SET").arg(node_key(node_id)).arg(&cluster.boot)
+                .arg("NX").arg("EX").arg(NODE_TTL_SECS)
+                .query_async(&mut conn)
+                .await?;
+            if claimed.is_some() {
+                break;
+            }
+            if waited >= Duration::from_secs(NODE_TTL_SECS + 5) {
+                return Err(format!("NODE_ID {} is held by another running node", node_id).into());
+            }
+            if waited.is_zero() {
+                println!("⏳ NODE_ID {} still claimed, waiting for it to expire...", node_id);
+            }
+            tokio::time::sleep(CLAIM_RETRY).await;
+            waited += CLAIM_RETRY;
+        }
+        println!("🧩 Joined cluster as node {}", node_id);
+        Ok(cluster)
+    }
+
+    // Trường trong hash: "<node>/<boot>/<số thứ tự>"
+    fn field(&self, seq: u64) -> String {
+        format!("{}/{}/{}", self.node_id, self.boot, seq)
+    }
+
+    // ========== SỔ KẾT NỐI ==========
+
+    /// Ghi kết nối vào sổ (khách kèm IP). Vượt giới hạn IP → huỷ đăng ký, trả giới hạn bị vượt.
+    /// Ghi trước rồi mới đếm → nhiều kết nối mở cùng lúc vẫn thấy nhau, không lọt quá giới hạn.
+    /// Giới hạn lấy từ cấu hình hiện tại (nạp lại nóng được, xem config.rs). Chưa đặt TRUSTED_PROXIES thì
+    /// IP có thể là của nginx (mọi khách chung một IP) → không giới hạn theo IP
+    pub async fn register(&self, shop_id: &str, peer: &Peer, ip: Option<&str>) -> Result<Registration, Limit> {
+        let config = config::current();
+        let per_ip = if config.trusted_proxies.is_empty() { 0 } else { config.connection_limits.per_ip };
+        let registration = Registration {
+            shop_id: shop_id.to_string(),
+            ip: ip.map(str::to_string),
+            field: self.field(self.next_conn.fetch_add(1, Ordering::Relaxed)),
+        };
+        let ip_count: redis::RedisResult<u64> = async {
+            let mut conn = self.redis.get().await?;
+            let mut pipe = redis::pipe();
+            pipe.atomic().hset(conns_key(shop_id), &registration.field, peer.encode()).ignore();
+            let Some(ip) = &registration.ip else {
+                return pipe.query_async::<()>(&mut conn).await.map(|_| 0);
+            };
+            let ip_key = ip_key(shop_id, ip);
+            let (ip_count,): (u64,) = pipe.hset(&ip_key, &registration.field, 1).ignore().hlen(&ip_key).query_async(&mut conn).await?;
+            // Sổ có thể còn kết nối của node đã chết → chỉ đếm lại kỹ khi tưởng như đã vượt
+            if ConnectionLimits::exceeded(per_ip, ip_count) {
+                self.live_count(&mut conn, &ip_key).await
+            } else {
+                Ok(ip_count)
+            }
+        }.await;
+
+        let exceeded = match self.redis.checked(ip_count).await {
+            Ok(ip_count) => ConnectionLimits::exceeded(per_ip, ip_count).then_some(Limit::Ip),
+            // Redis lỗi → vẫn cho kết nối (giống hạn mức), chỉ log
+            Err(e) => {
+                eprintln!("❌ Register connection for {} failed: {:?}", shop_id, e);
+                None
+            }
+        };
+        match exceeded {
+            Some(limit) => {
+                self.unregister(&registration).await;
+                Err(limit)
+            }
+            None => Ok(registration),
+        }
+    }
+
+    /// Đổi trạng thái của kết nối (admin bật/tắt vắng mặt)
+    pub async fn update(&self, registration: &Registration, peer: &Peer) {
+        self.write(registration, peer).await;
+    }
+
+    async fn write(&self, registration: &Registration, peer: &Peer) {
+        let result: redis::RedisResult<()> = async {
+            let mut conn = self.redis.get().await?;
+            conn.hset(conns_key(&registration.shop_id), &registration.field, peer.encode()).await
+        }.await;
+        if let Err(e) = self.redis.checked(result).await {
+            eprintln!("❌ Register connection for {} failed: {:?}", registration.shop_id, e);
+        }
+    }
+
+    pub async fn unregister(&self, registration: &Registration) {
+        let result: redis::RedisResult<()> = async {
+            let mut conn = self.redis.get().await?;
+            let mut pipe = redis::pipe();
+            pipe.hdel(conns_key(&registration.shop_id), &registration.field).ignore();
+            if let Some(ip) = &registration.ip {
+                pipe.hdel(ip_key(&registration.shop_id, ip), &registration.field).ignore();
+            }
+            pipe.query_async(&mut conn).await
+        }.await;
+        if let Err(e) = self.redis.checked(result).await {
+            eprintln!("❌ Unregister connection for {} failed: {:?}", registration.shop_id, e);
+        }
+    }
+
+    /// Mọi kết nối của shop trên các node còn sống: (node_id, peer).
+    /// Kết nối của node đã chết (hết hạn khoá hoặc đã khởi động lại) bị xoá khỏi sổ
+    pub async fn peers(&self, shop_id: &str) -> redis::RedisResult<Vec<(u16, Peer)>> {
+        let result = self.load_peers(shop_id).await;
+        self.redis.checked(result).await
+    }
+
+    async fn load_peers(&self, shop_id: &str) -> redis::RedisResult<Vec<(u16, Peer)>> {
+        let mut conn = self.redis.get().await?;
+        let entries: HashMap<String, String> = conn.hgetall(conns_key(shop_id)).await?;
+        let alive = alive_owners(&mut conn, entries.keys()).await?;
+
+        let mut peers = Vec::new();
+        let mut dead = Vec::new();
+        for (field, value) in entries {
+            let owner = owner(&field);
+            match (alive.contains(&owner), Peer::decode(&value)) {
+                (true, Some(peer)) => {
+                    let node = owner.split('/').next().and_then(|n| n.parse().ok()).unwrap_or(0);
+                    peers.push((node, peer));
+                }
+                _ => dead.push(field),
+            }
+        }
+        if !dead.is_empty() {
+            println!("🧹 Removing {} stale connection(s) of shop {}", dead.len(), shop_id);
+            conn.hdel::<_, _, ()>(conns_key(shop_id), dead).await?;
+        }
+        Ok(peers)
+    }
+
+    // Số kết nối còn sống trong hash (sổ shop / IP), xoá các kết nối của node đã chết
+    async fn live_count(&self, conn: &mut MultiplexedConnection, key: &str) -> redis::RedisResult<u64> {
+        let fields: Vec<String> = conn.hkeys(key).await?;
+        let alive = alive_owners(conn, fields.iter()).await?;
+        let (live, dead): (Vec<String>, Vec<String>) = fields.into_iter().partition(|field| alive.contains(&owner(field)));
+        if !dead.is_empty() {
+            println!("🧹 Removing {} stale connection(s) from {}", dead.len(), key);
+            conn.hdel::<_, _, ()>(key, dead).await?;
+        }
+        Ok(live.len() as u64)
+    }
+
+    /// Có admin (kèm agent_id) đang kết nối ở bất kỳ node nào và tất cả đều vắng mặt
+    /// (không có admin nào → giữ hành vi cũ, chỉ theo giờ làm việc)
+    pub async fn all_away(&self, shop_id: &str) -> bool {
+        match self.peers(shop_id).await {
+            Ok(peers) => {
+                let mut admins = peers.iter().filter_map(|(_, peer)| match peer {
+                    Peer::Admin { agent_id: Some(_), away } => Some(*away),
+                    _ => None,
+                }).peekable();
+                admins.peek().is_some() && admins.all(|away| away)
+            }
+            Err(e) => {
+                eprintln!("❌ Load connections of {} failed: {:?}", shop_id, e);
+                false
+            }
+        }
+    }
+
+    /// Khách đang kết nối ở bất kỳ node nào: guest_id → lúc mở kết nối sớm nhất (nhiều tab tính một, 0 = không rõ)
+    pub async fn live_guests(&self, shop_id: &str) -> redis::RedisResult<HashMap<u64, u64>> {
+        let mut guests = HashMap::new();
+        for (_, peer) in self.peers(shop_id).await? {
+            if let Peer::Guest { guest_id, since_us } = peer {
+                let since = guests.entry(guest_id).or_insert(since_us);
+                if *since == 0 || (since_us != 0 && since_us < *since) {
+                    *since = since_us;
+                }
+            }
+        }
+        Ok(guests)
+    }
+
+    /// Số kết nối khách đang mở của shop trên cả cụm (hạn mức kết nối, quota.rs)
+    pub async fn guest_connections(&self, shop_id: &str) -> redis::RedisResult<u64> {
+        Ok(self.peers(shop_id).await?.iter().filter(|(_, peer)| matches!(peer, Peer::Guest { .. })).count() as u64)
+    }
+
+    // Các node đang giữ kết nối của khách
+    async fn guest_nodes(&self, shop_id: &str, guest_id: u64) -> redis::RedisResult<HashSet<u16>> {
+        Ok(self.peers(shop_id).await?
+            .into_iter()
+            .filter(|(_, peer)| matches!(peer, Peer::Guest { guest_id: id, .. } if *id == guest_id))
+            .map(|(node, _)| node)
+            .collect())
+    }
+
+    // ========== ĐỊNH TUYẾN TỚI NODE ==========
+
+    /// Frame chỉ dành cho một khách → chỉ node giữ kết nối của khách nhận
+    #[tracing::instrument(name = "cluster.send_to_guest", skip_all, fields(shop_id = %envelope.shop_id))]
+    pub async fn send_to_guest(&self, envelope: &WsEnvelope) -> redis::RedisResult<()> {
+        let nodes = self.guest_nodes(&envelope.shop_id, envelope.guest_id).await?;
+        let payload = envelope.encode_to_vec();
+        let result = async {
+            let mut conn = self.redis.get().await?;
+            for node in nodes {
+                conn.publish::<_, _, ()>(frame_channel(node), &payload).await?;
+            }
+            Ok(())
+        }.await;
+        self.redis.checked(result).await
+    }
+
+    /// Ngắt mọi kết nối của khách trên cả cụm (khách bị chặn)
+    #[tracing::instrument(name = "cluster.kick_guest", skip(self))]
+    pub async fn kick_guest(&self, shop_id: &str, guest_id: u64) -> redis::RedisResult<()> {
+        let nodes = self.guest_nodes(shop_id, guest_id).await?;
+        let payload = format!("{}\n{}", shop_id, guest_id);
+        let result = async {
+            let mut conn = self.redis.get().await?;
+            for node in nodes {
+                conn.publish::<_, _, ()>(kick_channel(node), &payload).await?;
+            }
+            Ok(())
+        }.await;
+        self.redis.checked(result).await
+    }
+
+    // Gia hạn khoá node; mất khoá (Redis mất dữ liệu, bị node khác giành) thì giành lại
+    async fn heartbeat(&self) -> redis::RedisResult<()> {
+        let result = self.renew_lease().await;
+        self.redis.checked(result).await
+    }
+
+    async fn renew_lease(&self) -> redis::RedisResult<()> {
+        let mut conn = self.redis.get().await?;
+        let current: Option<String> = conn.get(node_key(self.node_id)).await?;
+        match current {
+            Some(boot) if boot == self.boot => conn.expire(node_key(self.node_id), NODE_TTL_SECS as i64).await,
+            Some(_) => {
+                eprintln!("❌ NODE_ID {} was claimed by another node - message ids may collide", self.node_id);
+                Ok(())
+            }
+            None => {
+                println!("⚠️ Cluster lease of node {} expired, reclaiming", self.node_id);
+                conn.set_ex(node_key(self.node_id), &self.boot, NODE_TTL_SECS).await
+            }
+        }
+    }
+}
+
+// ============================================================================
+// BACKGROUND TASK - gia hạn khoá node + nhận frame định tuyến tới node này
+// ============================================================================
+
+pub async fn heartbeat_task(state: Arc<WebSocketState>) {
+    let mut ticker = tokio::time::interval(HEARTBEAT_INTERVAL);
+    loop {
+        ticker.tick().await;
+        if let Err(e) = state.cluster.heartbeat().await {
+            eprintln!("❌ Cluster heartbeat failed: {:?}", e);
+        }
+        // Lỡ frame bật/tắt bảo trì (mất kết nối pub/sub) → phát lại banner cho kết nối trên node này
+        if state.maintenance.refresh().await {
+            let _ = state.tx.send(state.maintenance.banner_frame().encode_to_vec());
+        }
+    }
+}
+
+pub async fn node_subscriber_task(state: Arc<WebSocketState>) {
+    loop {
+        if let Err(e) = run_node_subscriber(&state).await {
+            eprintln!("❌ Node channel error: {:?}", e);
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+}
+
+async fn run_node_subscriber(state: &Arc<WebSocketState>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let node_id = state.cluster.node_id;
+    let mut pubsub = state.redis.client().get_async_pubsub().await?;
+    pubsub.subscribe(&[frame_channel(node_id), kick_channel(node_id), NOTICE_CHANNEL.to_string()]).await?;
+    println!("✅ Redis subscribed to node {} channels", node_id);
+
+    let mut stream = pubsub.on_message();
+    while let Some(msg) = stream.next().await {
+        let channel: String = msg.get_channel()?;
+        let payload: Vec<u8> = msg.get_payload()?;
+        if channel == kick_channel(node_id) {
+            let text = String::from_utf8_lossy(&payload);
+            if let Some((shop_id, guest_id)) = text.split_once('\n') {
+                if let Ok(guest_id) = guest_id.parse() {
+                    let _ = state.kick_tx.send((shop_id.to_string(), guest_id));
+                }
+            }
+        } else {
+            if channel == NOTICE_CHANNEL {
+                state.maintenance.on_broadcast(&payload);
+            }
+            // Cùng đường với frame từ chat:* - send task của từng kết nối tự lọc
+            let _ = state.tx.send(payload);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,252 @@
+// ============================================================================
+// CẤU HÌNH NẠP LẠI NÓNG - không cần khởi động lại node
+// Nạp lại khi nhận SIGHUP, hoặc khi file CONFIG_FILE (mặc định .env) đổi (kiểm tra mỗi CONFIG_WATCH_SECS,
+// mặc định 10, 0 = chỉ SIGHUP). Biến có trong file thắng biến môi trường (sửa file là có hiệu lực).
+// Mỗi lần nạp dựng snapshot mới rồi thay cả khối: nơi dùng lấy current() một lần → không thấy nửa cũ nửa mới.
+// Giá trị sai → giữ nguyên snapshot cũ, chỉ log (lúc khởi động thì dừng luôn)
+//   MAX_CONNECTIONS_PER_IP   kết nối khách cùng IP trong một shop (sổ kết nối cluster; chỉ áp dụng khi có TRUSTED_PROXIES).
+//                            Giới hạn theo shop là hạn mức QUOTA_CONNECTIONS (quota.rs)
+//   CORS_ORIGINS        origin được gọi API từ trình duyệt, cách nhau dấu phẩy (trống = mọi origin)
+//   ADMIN_ORIGINS       origin admin panel (bỏ qua tên miền nhúng widget)
+//   TRUSTED_PROXIES     IP / CIDR của nginx, load balancer - chỉ tin X-Forwarded-For / X-Real-IP / X-Forwarded-Proto từ đây (trống = không tin)
+//   DISABLED_FEATURES   tắt khẩn tính năng trên mọi shop (e2ee, ai_reply, attachments, translations, voice_notes)
+//   LOG_LEVEL           info | debug (debug = log từng frame WS / broker, mặc định)
+// ============================================================================
+
+use axum::http::HeaderValue;
+use prost::Message as ProstMessage;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, SystemTime};
+
+use crate::auth::{AdminOrigins, TrustedProxies};
+use crate::contract::{origin_host, ShopFlags, WsEnvelope};
+use crate::websocket::WebSocketState;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogLevel {
+    Info,
+    Debug,
+}
+
+/// 0 = không giới hạn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConnectionLimits {
+    /// Kết nối khách cùng IP trong một shop
+    pub per_ip: u64,
+}
+
+impl ConnectionLimits {
+    pub fn exceeded(limit: u64, count: u64) -> bool {
+        limit > 0 && count > limit
+    }
+}
+
+pub struct RuntimeConfig {
+    pub connection_limits: ConnectionLimits,
+    // None = mọi origin
+    cors_hosts: Option<Vec<String>>,
+    pub admin_origins: AdminOrigins,
+    pub trusted_proxies: TrustedProxies,
+    pub disabled_features: ShopFlags,
+    pub log_level: LogLevel,
+}
+
+impl RuntimeConfig {
+    fn load(source: &Source) -> Result<Self, String> {
+        let number = |name: &str, default: u64| match source.get(name) {
+            Some(v) => v.trim().parse().map_err(|_| format!("{} is not a number: {}", name, v)),
+            None => Ok(default),
+        };
+        let connection_limits = ConnectionLimits {
+            per_ip: number("MAX_CONNECTIONS_PER_IP", 5)?,
+        };
+
+        let cors_hosts = list(source.get("CORS_ORIGINS"))
+            .map(|v| origin_host(&v).ok_or_else(|| format!("CORS_ORIGINS entry is not a URL: {}", v)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let admin_origins = AdminOrigins::parse(&source.get("ADMIN_ORIGINS").unwrap_or_default())?;
+        let trusted_proxies = TrustedProxies::parse(&source.get("TRUSTED_PROXIES").unwrap_or_default())?;
+
+        let features: Vec<String> = list(source.get("DISABLED_FEATURES")).collect();
+        if let Some(unknown) = features.iter().find(|f| !ShopFlags::NAMES.contains(&f.as_str())) {
+            return Err(format!("DISABLED_FEATURES has unknown feature: {}", unknown));
+        }
+        let log_level = match source.get("LOG_LEVEL").unwrap_or_default().trim() {
+            "" | "debug" => LogLevel::Debug,
+            "info" => LogLevel::Info,
+            other => return Err(format!("LOG_LEVEL must be info or debug, got {}", other)),
+        };
+
+        Ok(Self {
+            connection_limits,
+            cors_hosts: Some(cors_hosts).filter(|hosts| !hosts.is_empty()),
+            admin_origins,
+            trusted_proxies,
+            disabled_features: ShopFlags::from_names(features),
+            log_level,
+        })
+    }
+
+    /// Request từ origin này được CORS cho phép không
+    pub fn allows_cors(&self, origin: &HeaderValue) -> bool {
+        match &self.cors_hosts {
+            None => true,
+            Some(hosts) => origin.to_str().ok().and_then(origin_host).is_some_and(|host| hosts.contains(&host)),
+        }
+    }
+
+    /// Cờ của shop sau khi áp tắt khẩn toàn hệ thống
+    pub fn restrict(&self, mut flags: ShopFlags) -> ShopFlags {
+        let off = &self.disabled_features;
+        flags.e2ee &= !off.e2ee;
+        flags.ai_reply &= !off.ai_reply;
+        flags.attachments &= !off.attachments;
+        flags.translations &= !off.translations;
+        flags.voice_notes &= !off.voice_notes;
+        flags
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "connections {}/IP, CORS {}, admin origins [{}], trusted proxies [{}], disabled features [{}], log {:?}",
+            self.connection_limits.per_ip,
+            self.cors_hosts.as_ref().map_or("any".to_string(), |h| h.join(",")),
+            self.admin_origins.hosts().join(","),
+            self.trusted_proxies.summary(),
+            self.disabled_features.names().join(","),
+            self.log_level,
+        )
+    }
+}
+
+// "a, b,,c" → a, b, c
+fn list(value: Option<String>) -> impl Iterator<Item = String> {
+    value.unwrap_or_default()
+        .split(',')
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+// ========== NGUỒN GIÁ TRỊ ==========
+
+fn config_path() -> PathBuf {
+    std::env::var("CONFIG_FILE").unwrap_or_else(|_| ".env".to_string()).into()
+}
+
+// Biến trong file cấu hình (đọc lại mỗi lần nạp) + biến môi trường
+struct Source {
+    file: HashMap<String, String>,
+}
+
+impl Source {
+    fn read() -> Result<Self, String> {
+        let path = config_path();
+        if !path.exists() {
+            return Ok(Self { file: HashMap::new() });
+        }
+        let iter = dotenvy::from_path_iter(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let file = iter.collect::<Result<_, _>>().map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(Self { file })
+    }
+
+    fn get(&self, name: &str) -> Option<String> {
+        self.file.get(name).cloned().or_else(|| std::env::var(name).ok())
+    }
+}
+
+// ========== SNAPSHOT ==========
+
+static CURRENT: OnceLock<RwLock<Arc<RuntimeConfig>>> = OnceLock::new();
+
+fn cell() -> &'static RwLock<Arc<RuntimeConfig>> {
+    CURRENT.get_or_init(|| {
+        let config = Source::read()
+            .and_then(|source| RuntimeConfig::load(&source))
+            .unwrap_or_else(|e| panic!("❌ Invalid configuration: {}", e));
+        println!("⚙️ Config: {}", config.summary());
+        RwLock::new(Arc::new(config))
+    })
+}
+
+/// Snapshot cấu hình hiện tại (lần đầu gọi thì nạp; sai thì dừng)
+pub fn current() -> Arc<RuntimeConfig> {
+    cell().read().unwrap().clone()
+}
+
+/// Log chi tiết (từng frame) có bật không
+pub fn debug_logs() -> bool {
+    current().log_level == LogLevel::Debug
+}
+
+/// Nạp lại; Err = giữ nguyên cấu hình cũ. Trả về (cũ, mới)
+pub fn reload() -> Result<(Arc<RuntimeConfig>, Arc<RuntimeConfig>), String> {
+    let next = Arc::new(RuntimeConfig::load(&Source::read()?)?);
+    let previous = std::mem::replace(&mut *cell().write().unwrap(), next.clone());
+    Ok((previous, next))
+}
+
+// ============================================================================
+// BACKGROUND TASK - SIGHUP / file cấu hình đổi → nạp lại
+// ============================================================================
+pub async fn reload_task(state: Arc<WebSocketState>) {
+    let watch_secs = std::env::var("CONFIG_WATCH_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(10u64);
+    #[cfg(unix)]
+    let mut hangup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()).ok();
+    let mut modified = file_modified();
+    let mut ticker = tokio::time::interval(Duration::from_secs(watch_secs.max(1)));
+    println!("⚙️ Config reload on SIGHUP{}", if watch_secs > 0 { format!(" or {} change (every {}s)", config_path().display(), watch_secs) } else { String::new() });
+
+    loop {
+        #[cfg(unix)]
+        let sighup = async {
+            match hangup.as_mut() {
+                Some(signal) => { signal.recv().await; }
+                None => std::future::pending().await,
+            }
+        };
+        #[cfg(not(unix))]
+        let sighup = std::future::pending::<()>();
+
+        tokio::select! {
+            _ = sighup => println!("⚙️ SIGHUP received, reloading config..."),
+            _ = ticker.tick(), if watch_secs > 0 => {
+                let now = file_modified();
+                if now == modified {
+                    continue;
+                }
+                modified = now;
+                println!("⚙️ {} changed, reloading config...", config_path().display());
+            }
+        }
+        match reload() {
+            Ok((previous, next)) => {
+                println!("✅ Config reloaded: {}", next.summary());
+                if previous.disabled_features != next.disabled_features {
+                    push_flags(&state).await;
+                }
+            }
+            Err(e) => eprintln!("❌ Config reload failed, keeping previous config: {}", e),
+        }
+    }
+}
+
+fn file_modified() -> Option<SystemTime> {
+    std::fs::metadata(config_path()).and_then(|m| m.modified()).ok()
+}
+
+// Tắt khẩn đổi → gửi lại cờ cho các shop đang kết nối tới node này (mỗi node tự nạp lại → tự gửi)
+async fn push_flags(state: &WebSocketState) {
+    for shop_id in state.connected_shops() {
+        match state.repo.get_flags(&shop_id).await {
+            Ok(flags) => {
+                let frame = WsEnvelope::flags(shop_id.clone(), flags);
+                let _ = state.tx.send(frame.encode_to_vec());
+            }
+            Err(e) => eprintln!("❌ Load flags for {} failed: {:?}", shop_id, e),
+        }
+    }
+}
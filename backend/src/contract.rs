@@ -8,7 +8,111 @@ pub use turbochat_shared::{
     SyncResponse,
     GuestListRequest, 
     GuestListResponse,
+    FeedResponse,
+    MessageSearchResponse,
     AdminAuthRequest, 
     AdminAuthResponse,
+    AdminInviteRequest,
+    AdminInviteResponse,
+    AdminAcceptRequest,
+    AgentSummary,
+    AgentListResponse,
+    IdentitySecretResponse,
+    GuestMerged,
+    ShopSettings,
+    RetentionStats,
+    SettingsRequest,
+    SettingsResponse,
+    DailyStats,
+    ReportResponse,
+    BlockEntry,
+    BlockRequest,
+    BlockListResponse,
+    FilterAction,
+    FlaggedMessage,
+    FlaggedListResponse,
+    LiveVisitor,
+    LiveVisitorList,
+    WsEnvelope,
+    ws_envelope,
+    Presence,
+    AgentStatus,
+    AgentActivity,
+    ActivityKind,
+    MessageId,
+    MessageIdGenerator,
+    EditMessage,
+    DeleteMessage,
+    ReplyPreview,
+    QuickReply,
+    Attachment,
+    ScanResult,
+    ScanStatus,
+    Identify,
+    TrackEvent,
+    VisitorContext,
+    GeoLocation,
+    RoutingRule,
+    RoutingRules,
+    ProactiveTrigger,
+    ProactiveTriggers,
+    SlaAlert,
+    SlaLevel,
+    ConversationSummary,
+    Mood,
+    ShopQuota,
+    ShopUsage,
+    QuotaKind,
+    QuotaAction,
+    QuotaExceeded,
+    ShopFlags,
+    AdminPreferences,
+    SavedViewList,
+    MessageRevision,
+    MessageHistory,
+    rule_condition,
+    rule_action,
+    GuestNote,
+    ConversationStatus,
+    ConversationStatusRequest,
+    BulkConversationRequest,
+    BulkConversationResponse,
+    bulk_conversation_request,
+    TransferRequest,
+    ConversationTransfer,
+    NoteRequest,
+    NoteListResponse,
+    ScheduledMessage,
+    Campaign,
+    CampaignSegment,
+    CampaignStatus,
+    CampaignListResponse,
+    CampaignPreview,
+    ReadReceipt,
+    BotFlow,
+    BotFlows,
+    FlowStep,
+    FlowState,
+    Handoff,
+    HandoffReason,
+    ScheduledListResponse,
+    Reminder,
+    ReminderListResponse,
+    Session,
+    NoticeKind,
+    ServerNotice,
+    ApiKey,
+    ApiKeyRequest,
+    ApiKeyResponse,
+    ApiKeyListResponse,
+    IntegrationMessageRequest,
+    negotiate,
+    origin_host,
+    text_matches,
+    supported_range,
+    CLOSE_SERVER_DRAINING,
+    CLOSE_TOO_MANY_CONNECTIONS,
+    CLOSE_UNSUPPORTED_PROTOCOL,
+    PROTOCOL_VERSION,
     ContractError
 };
\ No newline at end of file
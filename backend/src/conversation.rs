@@ -0,0 +1,221 @@
+// Vòng đời hội thoại: đóng / lưu trữ / mở lại (khách nhắn lại thì tự mở - xem AstraRepo::upsert_guest)
+// + thao tác hàng loạt từ sidebar admin (trạng thái, tag, người phụ trách) + chuyển hội thoại kèm ghi chú bàn giao
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use futures::stream::{self, StreamExt};
+use prost::Message as ProstMessage;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::agents::OWNER_ID;
+use crate::auth::AdminAuth;
+use crate::contract::{bulk_conversation_request::Action, BulkConversationRequest, BulkConversationResponse, ContractError, ConversationStatus, ConversationStatusRequest, ConversationTransfer, Guest, TransferRequest, WsEnvelope};
+use crate::notes::{self, MAX_NOTE_CHARS};
+use crate::state::AppState;
+use crate::websocket::publish_envelope;
+
+const MAX_BULK_GUESTS: usize = 200;
+// Số hội thoại ghi DB song song trong một request hàng loạt
+const BULK_CONCURRENCY: usize = 8;
+const MAX_TAG_CHARS: usize = 50;
+
+// PUT /guests/:id/status - Đổi trạng thái hội thoại, báo các admin khác qua WS
+pub async fn set_status_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path(guest_id): Path<u64>,
+    body: Bytes,
+) -> impl IntoResponse {
+    let Ok(req) = ConversationStatusRequest::decode(&body[..]) else {
+        return StatusCode::BAD_REQUEST;
+    };
+    let status = req.status();
+
+    if let Err(e) = state.repo.set_conversation_status(&admin.shop_id, guest_id, status).await {
+        eprintln!("❌ Set conversation status failed: {:?}", e);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    let action = match status {
+        ConversationStatus::Open => "conversation.reopen",
+        ConversationStatus::Closed => "conversation.close",
+        ConversationStatus::Archived => "conversation.archive",
+    };
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, action, guest_id, &admin.agent_id, "").await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("📁 Guest {} of shop {} → {}", guest_id, admin.shop_id, status.as_db_str());
+
+    match state.repo.get_guest(&admin.shop_id, guest_id).await {
+        Ok(Some(guest)) => {
+            if let Err(e) = publish_envelope(&state.ws_state, &WsEnvelope::guest(guest)).await {
+                eprintln!("❌ Redis publish failed: {:?}", e);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("❌ Load guest {} failed: {:?}", guest_id, e),
+    }
+
+    StatusCode::NO_CONTENT
+}
+
+// POST /conversations/bulk - Một thao tác cho nhiều hội thoại trong một request
+pub async fn bulk_handler(State(state): State<Arc<AppState>>, admin: AdminAuth, body: Bytes) -> impl IntoResponse {
+    let Ok(req) = BulkConversationRequest::decode(&body[..]) else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+    let Some(action) = req.action.map(normalize) else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+    let mut seen = HashSet::new();
+    let guest_ids: Vec<u64> = req.guest_ids.into_iter().filter(|id| seen.insert(*id)).collect();
+    let tag_invalid = matches!(&action, Action::AddTag(tag) if tag.is_empty() || tag.chars().count() > MAX_TAG_CHARS);
+    if guest_ids.is_empty() || guest_ids.len() > MAX_BULK_GUESTS || tag_invalid {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    }
+
+    let results: Vec<(u64, Result<Option<Guest>, ContractError>)> = stream::iter(guest_ids)
+        .map(|guest_id| {
+            let (state, shop_id, action) = (&state, &admin.shop_id, &action);
+            async move { (guest_id, apply(state, shop_id, guest_id, action).await) }
+        })
+        .buffer_unordered(BULK_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut resp = BulkConversationResponse::default();
+    for (guest_id, result) in results {
+        match result {
+            Ok(Some(guest)) => resp.guests.push(guest),
+            Ok(None) => resp.failed_guest_ids.push(guest_id),
+            Err(e) => {
+                eprintln!("❌ Bulk update of guest {} failed: {:?}", guest_id, e);
+                resp.failed_guest_ids.push(guest_id);
+            }
+        }
+    }
+
+    let (audit_action, detail) = match &action {
+        Action::Status(status) => ("conversation.bulk_status", ConversationStatus::try_from(*status).unwrap_or_default().as_db_str().to_string()),
+        Action::AddTag(tag) => ("conversation.bulk_tag", tag.clone()),
+        Action::AssignTo(agent) => ("conversation.bulk_assign", agent.clone()),
+    };
+    let detail = format!("{} ({} guests)", detail, resp.guests.len());
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, audit_action, 0, &admin.agent_id, &detail).await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("📁 Bulk {} in shop {}: {} done, {} failed", audit_action, admin.shop_id, resp.guests.len(), resp.failed_guest_ids.len());
+
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+}
+
+fn normalize(action: Action) -> Action {
+    match action {
+        Action::AddTag(tag) => Action::AddTag(tag.trim().to_string()),
+        Action::AssignTo(agent) => Action::AssignTo(agent.trim().to_string()),
+        status => status,
+    }
+}
+
+// Đổi một hội thoại rồi báo các admin khác; None = không có guest này
+async fn apply(state: &AppState, shop_id: &str, guest_id: u64, action: &Action) -> Result<Option<Guest>, ContractError> {
+    let Some(mut guest) = state.repo.get_guest(shop_id, guest_id).await? else {
+        return Ok(None);
+    };
+    match action {
+        Action::Status(status) => {
+            let status = ConversationStatus::try_from(*status).unwrap_or_default();
+            state.repo.set_conversation_status(shop_id, guest_id, status).await?;
+            guest.set_status(status);
+        }
+        Action::AddTag(tag) => {
+            if guest.tags.contains(tag) {
+                return Ok(Some(guest));
+            }
+            guest.tags.push(tag.clone());
+            state.repo.set_guest_routing(shop_id, guest_id, &guest.tags, &guest.assigned_to).await?;
+        }
+        Action::AssignTo(agent) => {
+            guest.assigned_to = agent.clone();
+            state.repo.set_guest_routing(shop_id, guest_id, &guest.tags, &guest.assigned_to).await?;
+        }
+    }
+    if let Err(e) = publish_envelope(&state.ws_state, &WsEnvelope::guest(guest.clone())).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+    Ok(Some(guest))
+}
+
+// POST /guests/:id/transfer - Giao hội thoại cho người khác kèm ghi chú bàn giao.
+// Ghi chú lưu như ghi chú nội bộ (chỉ admin thấy); frame Note báo cho người nhận
+pub async fn transfer_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path(guest_id): Path<u64>,
+    body: Bytes,
+) -> impl IntoResponse {
+    let Ok(req) = TransferRequest::decode(&body[..]) else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+    let to_agent = req.to_agent.trim().to_string();
+    let note = req.note.trim();
+    if to_agent.is_empty() || to_agent == admin.agent_id || note.is_empty() || note.chars().count() > MAX_NOTE_CHARS {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    }
+
+    // Tên chụp lúc chuyển (đổi tên sau này không sửa lịch sử); chủ shop không có trong bảng admins
+    let agents = match state.repo.list_agents(&admin.shop_id).await {
+        Ok(agents) => agents,
+        Err(e) => {
+            eprintln!("❌ Load agents for {} failed: {:?}", admin.shop_id, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new());
+        }
+    };
+    let name_of = |agent_id: &str| agents.iter().find(|a| a.agent_id == agent_id)
+        .map(|a| if a.name.trim().is_empty() { a.email.clone() } else { a.name.clone() });
+    let to_name = match name_of(&to_agent) {
+        Some(name) => name,
+        None if to_agent == OWNER_ID => String::new(),
+        None => return (StatusCode::BAD_REQUEST, Bytes::new()),
+    };
+
+    let mut guest = match state.repo.get_guest(&admin.shop_id, guest_id).await {
+        Ok(Some(guest)) => guest,
+        Ok(None) => return (StatusCode::NOT_FOUND, Bytes::new()),
+        Err(e) => {
+            eprintln!("❌ Load guest {} failed: {:?}", guest_id, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new());
+        }
+    };
+    guest.assigned_to = to_agent.clone();
+    if let Err(e) = state.repo.set_guest_routing(&admin.shop_id, guest_id, &guest.tags, &guest.assigned_to).await {
+        eprintln!("❌ Transfer guest {} failed: {:?}", guest_id, e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new());
+    }
+    if let Err(e) = publish_envelope(&state.ws_state, &WsEnvelope::guest(guest.clone())).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+
+    let transfer = ConversationTransfer {
+        from_agent: admin.agent_id.clone(),
+        from_name: name_of(&admin.agent_id).unwrap_or_default(),
+        to_agent: to_agent.clone(),
+        to_name,
+    };
+    match state.repo.insert_note(&admin.shop_id, guest_id, note, Some(transfer)).await {
+        Ok(note) => notes::broadcast(&state, &admin.shop_id, note).await,
+        Err(e) => eprintln!("❌ Insert handover note failed: {:?}", e),
+    }
+
+    let detail = format!("{} → {}", admin.agent_id, to_agent);
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "conversation.transfer", guest_id, &admin.agent_id, &detail).await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("↪️ Guest {} of shop {} transferred {}", guest_id, admin.shop_id, detail);
+
+    (StatusCode::OK, Bytes::from(guest.encode_to_vec()))
+}
@@ -1,41 +1,129 @@
-use crate::contract::{ContractError, Message, Guest};
+use crate::config;
+use crate::contract::{ContractError, Message, MessageIdGenerator, ReplyPreview, QuickReply, Guest, ShopSettings, DailyStats, BlockEntry, FlaggedMessage, TrackEvent, VisitorContext, GeoLocation, ConversationSummary, GuestNote, ConversationTransfer, ConversationStatus, ScheduledMessage, Reminder, ApiKey, ShopQuota, QuotaAction, ShopFlags, MessageRevision, Attachment, ScanResult, ScanStatus, MessageId, AdminPreferences, SavedViewList, Campaign, CampaignSegment, FlowState, text_matches};
 use bytes::Bytes;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
 use reqwest::Client;
 use serde_json::json;
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use prost::Message as ProstMessage;
+
+// /sync: limit = 0 → mặc định, lớn hơn → cắt
+const DEFAULT_PAGE_LIMIT: u32 = 50;
+const MAX_PAGE_LIMIT: u32 = 500;
+
+/// Một trang tin nhắn; `page_state` (opaque - message_id cuối trang) = còn trang sau
+pub struct MessagePage {
+    pub messages: Vec<Message>,
+    pub page_state: Option<String>,
+}
+
+const MS_PER_DAY: u64 = 86_400_000;
+// page-size tối đa khi đọc một bucket ngày
+const BUCKET_PAGE: usize = 1000;
+// Giới hạn bộ nhớ đệm bucket đã ghi vào message_buckets
+const MAX_KNOWN_BUCKETS: usize = 100_000;
+
+// POST /guests: limit = 0 → mặc định
+const DEFAULT_GUEST_PAGE: u32 = 100;
+const MAX_GUEST_PAGE: u32 = 500;
+
+/// Một trang guest; `next_cursor` (opaque) = còn trang sau
+pub struct GuestPage {
+    pub guests: Vec<Guest>,
+    pub next_cursor: Option<String>,
+}
+
+/// Nhân viên đã nhận lời mời (bảng admins)
+pub struct AgentAccount {
+    pub agent_id: String,
+    pub email: String,
+    pub name: String,
+    pub password_hash: String,
+    // sha256 secret của phiên đăng nhập gần nhất, rỗng = chưa đăng nhập
+    pub session_hash: String,
+    pub created_at: u64,
+}
+
+/// Lời mời (bảng admin_invites); accepted_at = 0 → chưa dùng
+pub struct AdminInvite {
+    pub invite_id: String,
+    pub email: String,
+    pub expires_at: u64,
+    pub accepted_at: u64,
+}
 
 pub struct AstraRepo {
     client: Client,
     base_url: String,
+    // REST schema API (tạo bảng / thêm cột khi migrate)
+    schema_url: String,
     token: String,
+    // Hạn cho cả thao tác nhiều request (đọc theo trang)
+    op_timeout: Duration,
+    // Sinh message_id / event_id (Snowflake, node lấy từ NODE_ID)
+    pub ids: MessageIdGenerator,
+    // (shop_id, guest_id, day_bucket) đã có trong message_buckets - khỏi ghi lại mỗi tin
+    known_buckets: Mutex<HashSet<(String, u64, i32)>>,
 }
 
 impl AstraRepo {
+    #[tracing::instrument(name = "db.new", skip_all)]
     pub async fn new() -> Result<Self, ContractError> {
-        let db_id = std::env::var("ASTRA_DB_ID")
-            .map_err(|_| ContractError::DbError("Missing ASTRA_DB_ID".into()))?;
-        let region = std::env::var("ASTRA_DB_REGION")
-            .map_err(|_| ContractError::DbError("Missing ASTRA_DB_REGION".into()))?;
         let keyspace = std::env::var("ASTRA_DB_KEYSPACE")
             .map_err(|_| ContractError::DbError("Missing ASTRA_DB_KEYSPACE".into()))?;
         let token = std::env::var("ASTRA_DB_TOKEN")
             .map_err(|_| ContractError::DbError("Missing ASTRA_DB_TOKEN".into()))?;
 
-        let base_url = format!(
-            "https://{}-{}.apps.astra.datastax.com/api/rest/v2/keyspaces/{}",
-            db_id, region, keyspace
-        );
+        // ASTRA_REST_URL: Stargate tự host (vd. http://localhost:8082) thay cho Astra - dùng cho dev/test
+        let rest_url = match std::env::var("ASTRA_REST_URL") {
+            Ok(url) if !url.is_empty() => url.trim_end_matches('/').to_string(),
+            _ => {
+                let db_id = std::env::var("ASTRA_DB_ID")
+                    .map_err(|_| ContractError::DbError("Missing ASTRA_DB_ID".into()))?;
+                let region = std::env::var("ASTRA_DB_REGION")
+                    .map_err(|_| ContractError::DbError("Missing ASTRA_DB_REGION".into()))?;
+                format!("https://{}-{}.apps.astra.datastax.com/api/rest", db_id, region)
+            }
+        };
+
+        let base_url = format!("{}/v2/keyspaces/{}", rest_url, keyspace);
+        let schema_url = format!("{}/v2/schemas/keyspaces/{}", rest_url, keyspace);
 
         println!("✅ AstraDB URL: {}", base_url);
 
+        let node_id = std::env::var("NODE_ID")
+            .ok()
+            .and_then(|v| v.parse::<u16>().ok())
+            .unwrap_or(0);
+        println!("🆔 Snowflake node id: {}", node_id);
+
+        // DB_TIMEOUT_MS: mỗi request Astra, DB_OP_TIMEOUT_MS: cả thao tác nhiều trang
+        let env_ms = |key: &str, default: u64| std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        let request_timeout = Duration::from_millis(env_ms("DB_TIMEOUT_MS", 5_000));
+        let op_timeout = Duration::from_millis(env_ms("DB_OP_TIMEOUT_MS", 30_000));
+        let client = Client::builder()
+            .timeout(request_timeout)
+            .connect_timeout(request_timeout)
+            .build()
+            .map_err(|e| ContractError::DbError(format!("HTTP client build failed: {}", e)))?;
+        println!("⏱️ DB timeouts: {:?} per request, {:?} per operation", request_timeout, op_timeout);
+
         Ok(Self {
-            client: Client::new(),
+            client,
             base_url,
+            schema_url,
             token,
+            op_timeout,
+            ids: MessageIdGenerator::new(node_id),
+            known_buckets: Mutex::new(HashSet::new()),
         })
     }
 
     // ========== SHOP ==========
+    #[tracing::instrument(name = "db.verify_admin", skip_all, fields(shop_id = %shop_id))]
     pub async fn verify_admin(&self, shop_id: &str, pin: &str) -> Result<Option<String>, ContractError> {
         let url = format!("{}/shops/{}", self.base_url, shop_id);
         
@@ -44,14 +132,14 @@ impl AstraRepo {
             .header("X-Cassandra-Token", &self.token)
             .send()
             .await
-            .map_err(|e| ContractError::DbError(format!("Request failed: {}", e)))?;
+            .map_err(db_error("Request"))?;
 
         if resp.status().as_u16() == 404 {
             return Ok(None);
         }
 
         let body: serde_json::Value = resp.json().await
-            .map_err(|e| ContractError::DbError(format!("Parse failed: {}", e)))?;
+            .map_err(db_error("Parse"))?;
 
         let data = &body["data"][0];
         let stored_pin = data["admin_pin"].as_str().unwrap_or("");
@@ -64,8 +152,154 @@ impl AstraRepo {
         }
     }
 
+    #[tracing::instrument(name = "db.list_shop_ids", skip_all)]
+    pub async fn list_shop_ids(&self) -> Result<Vec<String>, ContractError> {
+        let mut ids = Vec::new();
+        let mut page_state: Option<String> = None;
+
+        self.bounded("List shops", async {
+            loop {
+                let url = format!("{}/shops/rows?fields=shop_id&page-size=100", self.base_url);
+                let url = with_page_state(&url, page_state.as_deref());
+
+                let body = self.get_json(&url).await?;
+                if let Some(rows) = body["data"].as_array() {
+                    for row in rows {
+                        if let Some(id) = row["shop_id"].as_str() {
+                            ids.push(id.to_string());
+                        }
+                    }
+                }
+
+                match body["pageState"].as_str() {
+                    Some(ps) if !ps.is_empty() => page_state = Some(ps.to_string()),
+                    _ => break,
+                }
+            }
+            Ok(())
+        }).await?;
+
+        Ok(ids)
+    }
+
+    /// Tạo shop mới (ghi đè tên/PIN nếu shop_id đã có)
+    #[tracing::instrument(name = "db.create_shop", skip_all, fields(shop_id = %shop_id))]
+    pub async fn create_shop(&self, shop_id: &str, shop_name: &str, admin_pin: &str) -> Result<(), ContractError> {
+        self.put_shop_row(&json!({
+            "shop_id": shop_id,
+            "shop_name": shop_name,
+            "admin_pin": admin_pin,
+            "created_at": now_us()
+        })).await
+    }
+
+    /// Nguyên dòng shop (bỏ cột null) - dùng cho backup
+    #[tracing::instrument(name = "db.get_shop_row", skip_all, fields(shop_id = %shop_id))]
+    pub async fn get_shop_row(&self, shop_id: &str) -> Result<Option<serde_json::Value>, ContractError> {
+        let url = format!("{}/shops/{}", self.base_url, shop_id);
+        let body = self.get_json(&url).await?;
+        Ok(body["data"].as_array().and_then(|rows| rows.first()).map(|row| {
+            let columns = row.as_object().into_iter().flatten().filter(|(_, v)| !v.is_null());
+            serde_json::Value::Object(columns.map(|(k, v)| (k.clone(), v.clone())).collect())
+        }))
+    }
+
+    /// Ghi dòng shop (backup / turbochat-cli) - chỉ ghi đè các cột có trong `row`
+    #[tracing::instrument(name = "db.put_shop_row", skip_all)]
+    pub async fn put_shop_row(&self, row: &serde_json::Value) -> Result<(), ContractError> {
+        let url = format!("{}/shops", self.base_url);
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(row)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(db_error("Write shop"))?;
+
+        Ok(())
+    }
+
+    // ========== SETTINGS ==========
+    #[tracing::instrument(name = "db.get_settings", skip_all, fields(shop_id = %shop_id))]
+    pub async fn get_settings(&self, shop_id: &str) -> Result<ShopSettings, ContractError> {
+        let url = format!("{}/shops/{}?fields=settings", self.base_url, shop_id);
+        let body = self.get_json(&url).await?;
+
+        let b64 = body["data"][0]["settings"].as_str().unwrap_or("");
+        let bytes = BASE64.decode(b64)
+            .map_err(|e| ContractError::DbError(format!("Base64 decode failed: {}", e)))?;
+
+        Ok(ShopSettings::decode(&bytes[..])?)
+    }
+
+    /// Hạn mức của shop - cột nào null thì lấy theo `defaults`
+    #[tracing::instrument(name = "db.get_quota", skip_all, fields(shop_id = %shop_id))]
+    pub async fn get_quota(&self, shop_id: &str, defaults: &ShopQuota) -> Result<ShopQuota, ContractError> {
+        let url = format!(
+            "{}/shops/{}?fields=quota_monthly_messages,quota_connections,quota_storage_bytes,quota_over_limit",
+            self.base_url, shop_id
+        );
+        let body = self.get_json(&url).await?;
+        let row = &body["data"][0];
+
+        let over_limit = match row["quota_over_limit"].as_str() {
+            Some("queue") => QuotaAction::Queue,
+            Some("reject") => QuotaAction::Reject,
+            _ => defaults.over_limit(),
+        };
+        Ok(ShopQuota {
+            monthly_messages: row["quota_monthly_messages"].as_u64().unwrap_or(defaults.monthly_messages),
+            concurrent_connections: row["quota_connections"].as_u64().map_or(defaults.concurrent_connections, |v| v as u32),
+            storage_bytes: row["quota_storage_bytes"].as_u64().unwrap_or(defaults.storage_bytes),
+            over_limit: over_limit as i32,
+        })
+    }
+
+    #[tracing::instrument(name = "db.get_flags", skip_all, fields(shop_id = %shop_id))]
+    pub async fn get_flags(&self, shop_id: &str) -> Result<ShopFlags, ContractError> {
+        let url = format!("{}/shops/{}?fields=flags,voice_max_secs", self.base_url, shop_id);
+        let body = self.get_json(&url).await?;
+        let row = &body["data"][0];
+        let names = row["flags"].as_array().cloned().unwrap_or_default();
+        let flags = ShopFlags {
+            voice_max_secs: row["voice_max_secs"].as_u64().unwrap_or(0) as u32,
+            ..ShopFlags::from_names(names.iter().filter_map(|n| n.as_str()))
+        };
+        // Tính năng bị tắt khẩn toàn hệ thống (DISABLED_FEATURES) coi như tắt với mọi shop
+        Ok(config::current().restrict(flags))
+    }
+
+    #[tracing::instrument(name = "db.save_flags", skip_all, fields(shop_id = %shop_id))]
+    pub async fn save_flags(&self, shop_id: &str, flags: &ShopFlags) -> Result<(), ContractError> {
+        self.patch_row(&format!("shops/{}", shop_id), &json!({ "flags": flags.names(), "voice_max_secs": flags.voice_max_secs })).await
+    }
+
+    #[tracing::instrument(name = "db.save_settings", skip_all, fields(shop_id = %shop_id))]
+    pub async fn save_settings(&self, shop_id: &str, settings: &ShopSettings) -> Result<(), ContractError> {
+        let url = format!("{}/shops/{}", self.base_url, shop_id);
+
+        let payload = json!({
+            "settings": BASE64.encode(settings.encode_to_vec())
+        });
+
+        self.client
+            .patch(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(db_error("Save settings"))?;
+
+        Ok(())
+    }
+
     // ========== GUEST ==========
-    pub async fn upsert_guest(&self, shop_id: &str, guest_id: u64, name: &str) -> Result<(), ContractError> {
+    // Không ghi guest_name để không đè tên khách đã identify
+    #[tracing::instrument(name = "db.upsert_guest", skip_all, fields(shop_id = %shop_id))]
+    pub async fn upsert_guest(&self, shop_id: &str, guest_id: u64, ip: &str) -> Result<(), ContractError> {
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
@@ -76,9 +310,11 @@ impl AstraRepo {
         let payload = json!({
             "shop_id": shop_id,
             "guest_id": guest_id as i64,
-            "guest_name": name,
             "created_at": now,
-            "last_seen": now
+            "last_seen": now,
+            "last_ip": ip,
+            // Khách nhắn (lại) → hội thoại đã đóng/lưu trữ tự mở lại
+            "status": ConversationStatus::Open.as_db_str()
         });
 
         self.client
@@ -88,55 +324,336 @@ impl AstraRepo {
             .json(&payload)
             .send()
             .await
-            .map_err(|e| ContractError::DbError(format!("Insert guest failed: {}", e)))?;
+            .map_err(db_error("Insert guest"))?;
 
         Ok(())
     }
 
-    pub async fn get_guests(&self, shop_id: &str) -> Result<Vec<Guest>, ContractError> {
-        let url = format!("{}/guests?where={{\"shop_id\":{{\"$eq\":\"{}\"}}}}", self.base_url, shop_id);
-        
-        let resp = self.client
-            .get(&url)
+    /// Lưu tên/email khách từ TurboChat.identify() - trường rỗng giữ nguyên
+    #[tracing::instrument(name = "db.identify_guest", skip_all, fields(shop_id = %shop_id))]
+    pub async fn identify_guest(&self, shop_id: &str, guest_id: u64, name: &str, email: &str) -> Result<(), ContractError> {
+        let mut payload = serde_json::Map::new();
+        if !name.is_empty() {
+            payload.insert("guest_name".into(), json!(name));
+        }
+        if !email.is_empty() {
+            payload.insert("email".into(), json!(email));
+        }
+        self.patch_row(&format!("guests/{}/{}", shop_id, guest_id as i64), &payload.into()).await
+    }
+
+    // ========== ĐỊNH DANH (tài khoản website) ==========
+
+    #[tracing::instrument(name = "db.get_identity_secret", skip_all, fields(shop_id = %shop_id))]
+    pub async fn get_identity_secret(&self, shop_id: &str) -> Result<Option<(String, u64)>, ContractError> {
+        let url = format!("{}/shops/{}?fields=identity_secret,identity_secret_at", self.base_url, shop_id);
+        let body = self.get_json(&url).await?;
+        let row = &body["data"][0];
+        Ok(row["identity_secret"].as_str()
+            .filter(|s| !s.is_empty())
+            .map(|s| (s.to_string(), row["identity_secret_at"].as_i64().unwrap_or(0) as u64)))
+    }
+
+    #[tracing::instrument(name = "db.set_identity_secret", skip_all, fields(shop_id = %shop_id))]
+    pub async fn set_identity_secret(&self, shop_id: &str, secret: &str, rotated_at: u64) -> Result<(), ContractError> {
+        self.patch_row(&format!("shops/{}", shop_id), &json!({ "identity_secret": secret, "identity_secret_at": rotated_at as i64 })).await
+    }
+
+    /// Gắn guest với tài khoản website đã xác minh (guests.user_id + guest_identities)
+    #[tracing::instrument(name = "db.link_guest_identity", skip_all, fields(shop_id = %shop_id))]
+    pub async fn link_guest_identity(&self, shop_id: &str, user_id: &str, guest_id: u64) -> Result<(), ContractError> {
+        let url = format!("{}/guest_identities", self.base_url);
+        let payload = json!({
+            "shop_id": shop_id,
+            "user_id": user_id,
+            "guest_id": guest_id as i64,
+            "linked_at": now_us()
+        });
+        self.client
+            .post(&url)
             .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
             .send()
             .await
-            .map_err(|e| ContractError::DbError(format!("Get guests failed: {}", e)))?;
+            .and_then(|r| r.error_for_status())
+            .map_err(db_error("Link guest identity"))?;
+        self.patch_row(&format!("guests/{}/{}", shop_id, guest_id as i64), &json!({ "user_id": user_id })).await
+    }
 
-        let body: serde_json::Value = resp.json().await
-            .map_err(|e| ContractError::DbError(format!("Parse failed: {}", e)))?;
+    /// Mọi guest đã gắn với một tài khoản website (thiết bị / trình duyệt khác nhau), cũ nhất trước.
+    /// user_id đã qua identity::is_valid_user_id (không cần encode trên path)
+    #[tracing::instrument(name = "db.identity_guests", skip_all, fields(shop_id = %shop_id))]
+    pub async fn identity_guests(&self, shop_id: &str, user_id: &str) -> Result<Vec<u64>, ContractError> {
+        let url = format!("{}/guest_identities/{}/{}?page-size=1000", self.base_url, shop_id, user_id);
+        let body = self.get_json(&url).await?;
+        let mut rows: Vec<(u64, u64)> = body["data"].as_array()
+            .map(|rows| rows.iter().map(|r| (r["linked_at"].as_i64().unwrap_or(0) as u64, r["guest_id"].as_i64().unwrap_or(0) as u64)).collect())
+            .unwrap_or_default();
+        rows.sort_unstable();
+        Ok(rows.into_iter().map(|(_, guest_id)| guest_id).collect())
+    }
 
-        let mut guests = Vec::new();
-        if let Some(rows) = body["data"].as_array() {
-            for row in rows {
-                guests.push(Guest {
-                    shop_id: row["shop_id"].as_str().unwrap_or("").to_string(),
-                    guest_id: row["guest_id"].as_i64().unwrap_or(0) as u64,
-                    guest_name: row["guest_name"].as_str().unwrap_or("").to_string(),
-                    created_at: row["created_at"].as_i64().unwrap_or(0) as u64,
-                    last_seen: row["last_seen"].as_i64().unwrap_or(0) as u64,
-                });
+    // ========== GỘP GUEST ==========
+
+    /// Ghi alias → guest gốc (guest_aliases + guests.merged_into)
+    #[tracing::instrument(name = "db.merge_guest", skip_all, fields(shop_id = %shop_id))]
+    pub async fn merge_guest(&self, shop_id: &str, alias_guest_id: u64, canonical_guest_id: u64) -> Result<(), ContractError> {
+        let url = format!("{}/guest_aliases", self.base_url);
+        let payload = json!({
+            "shop_id": shop_id,
+            "canonical_guest_id": canonical_guest_id as i64,
+            "alias_guest_id": alias_guest_id as i64,
+            "merged_at": now_us()
+        });
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(db_error("Insert guest alias"))?;
+        self.patch_row(&format!("guests/{}/{}", shop_id, alias_guest_id as i64), &json!({ "merged_into": canonical_guest_id as i64 })).await
+    }
+
+    /// Guest gốc mà guest này đã gộp vào (0 = không gộp)
+    #[tracing::instrument(name = "db.merged_into", skip_all, fields(shop_id = %shop_id))]
+    pub async fn merged_into(&self, shop_id: &str, guest_id: u64) -> Result<u64, ContractError> {
+        let url = format!("{}/guests/{}/{}?fields=merged_into", self.base_url, shop_id, guest_id as i64);
+        let body = self.get_json(&url).await?;
+        Ok(body["data"][0]["merged_into"].as_i64().unwrap_or(0) as u64)
+    }
+
+    /// Các guest đã gộp vào guest gốc
+    #[tracing::instrument(name = "db.guest_aliases", skip_all, fields(shop_id = %shop_id))]
+    pub async fn guest_aliases(&self, shop_id: &str, canonical_guest_id: u64) -> Result<Vec<u64>, ContractError> {
+        let url = format!("{}/guest_aliases/{}/{}?page-size=1000", self.base_url, shop_id, canonical_guest_id as i64);
+        let body = self.get_json(&url).await?;
+        Ok(body["data"].as_array()
+            .map(|rows| rows.iter().filter_map(|r| r["alias_guest_id"].as_i64()).map(|id| id as u64).collect())
+            .unwrap_or_default())
+    }
+
+    /// Xoá danh sách alias của guest gốc (GDPR erase)
+    #[tracing::instrument(name = "db.delete_guest_aliases", skip_all, fields(shop_id = %shop_id))]
+    pub async fn delete_guest_aliases(&self, shop_id: &str, canonical_guest_id: u64) -> Result<(), ContractError> {
+        self.delete_row(&format!("guest_aliases/{}/{}", shop_id, canonical_guest_id as i64)).await
+    }
+
+    #[tracing::instrument(name = "db.set_visitor_context", skip_all, fields(shop_id = %shop_id))]
+    pub async fn set_visitor_context(&self, shop_id: &str, guest_id: u64, context: &VisitorContext) -> Result<(), ContractError> {
+        let payload = json!({
+            "page_url": context.page_url,
+            "referrer": context.referrer,
+            "user_agent": context.user_agent,
+            "viewport_width": context.viewport_width,
+            "viewport_height": context.viewport_height,
+            "context_at": context.updated_at_us as i64
+        });
+        self.patch_row(&format!("guests/{}/{}", shop_id, guest_id as i64), &payload).await
+    }
+
+    #[tracing::instrument(name = "db.set_guest_geo", skip_all, fields(shop_id = %shop_id))]
+    pub async fn set_guest_geo(&self, shop_id: &str, guest_id: u64, geo: &GeoLocation) -> Result<(), ContractError> {
+        let payload = json!({ "country_code": geo.country_code, "country": geo.country, "city": geo.city });
+        self.patch_row(&format!("guests/{}/{}", shop_id, guest_id as i64), &payload).await
+    }
+
+    #[tracing::instrument(name = "db.set_guest_routing", skip_all, fields(shop_id = %shop_id))]
+    pub async fn set_guest_routing(&self, shop_id: &str, guest_id: u64, tags: &[String], assigned_to: &str) -> Result<(), ContractError> {
+        let payload = json!({ "tags": tags, "assigned_to": assigned_to });
+        self.patch_row(&format!("guests/{}/{}", shop_id, guest_id as i64), &payload).await
+    }
+
+    /// Khách bắt đầu chờ (since > 0) hoặc admin đã trả lời (since = 0, kèm thời gian phản hồi)
+    #[tracing::instrument(name = "db.set_awaiting", skip_all, fields(shop_id = %shop_id))]
+    pub async fn set_awaiting(&self, shop_id: &str, guest_id: u64, since: u64, first_response_ms: Option<u64>) -> Result<(), ContractError> {
+        let mut payload = serde_json::Map::new();
+        payload.insert("awaiting_since".into(), json!(since as i64));
+        if let Some(ms) = first_response_ms {
+            payload.insert("first_response_ms".into(), json!(ms as i64));
+        }
+        self.patch_row(&format!("guests/{}/{}", shop_id, guest_id as i64), &payload.into()).await
+    }
+
+    /// Bot chuyển cho nhân viên (true) / nhân viên đã trả lời (false)
+    #[tracing::instrument(name = "db.set_needs_human", skip_all, fields(shop_id = %shop_id))]
+    pub async fn set_needs_human(&self, shop_id: &str, guest_id: u64, needs_human: bool) -> Result<(), ContractError> {
+        let payload = json!({ "needs_human": needs_human });
+        self.patch_row(&format!("guests/{}/{}", shop_id, guest_id as i64), &payload).await
+    }
+
+    #[tracing::instrument(name = "db.set_guest_sentiment", skip_all, fields(shop_id = %shop_id))]
+    pub async fn set_guest_sentiment(&self, shop_id: &str, guest_id: u64, score: f32, at: u64) -> Result<(), ContractError> {
+        let payload = json!({ "sentiment": score, "sentiment_at": at as i64 });
+        self.patch_row(&format!("guests/{}/{}", shop_id, guest_id as i64), &payload).await
+    }
+
+    /// Tin cuối của hội thoại trong bảng conversation_feed (GET /feed) - ghi cùng lúc với set_guest_last_message
+    #[tracing::instrument(name = "db.set_feed_message", skip_all)]
+    pub async fn set_feed_message(&self, msg: &Message) -> Result<(), ContractError> {
+        let url = format!("{}/conversation_feed", self.base_url);
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&message_payload(msg))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(db_error("Update feed"))?;
+        Ok(())
+    }
+
+    /// Tin cuối của từng hội thoại, mới nhất trước; `before` = chỉ lấy tin có message_id nhỏ hơn (0 = từ đầu).
+    /// Partition theo shop, sắp xếp theo thời gian phải làm trong bộ nhớ (khoá clustering là guest_id)
+    #[tracing::instrument(name = "db.feed", skip_all, fields(shop_id = %shop_id))]
+    pub async fn feed(&self, shop_id: &str, limit: u32, before: u64) -> Result<(Vec<Message>, Option<u64>), ContractError> {
+        let limit = match limit {
+            0 => DEFAULT_PAGE_LIMIT,
+            n => n.min(MAX_PAGE_LIMIT),
+        } as usize;
+        let url = format!("{}/conversation_feed/{}?page-size=1000", self.base_url, shop_id);
+        let mut messages = Vec::new();
+        let mut page_state: Option<String> = None;
+
+        self.bounded("Load feed", async {
+            loop {
+                let body = self.get_json(&with_page_state(&url, page_state.as_deref())).await?;
+                for row in body["data"].as_array().into_iter().flatten() {
+                    let msg = message_from_row(row)?;
+                    if before == 0 || msg.message_id < before {
+                        messages.push(msg);
+                    }
+                }
+                page_state = body["pageState"].as_str().filter(|ps| !ps.is_empty()).map(String::from);
+                if page_state.is_none() {
+                    break;
+                }
+            }
+            Ok(())
+        }).await?;
+
+        messages.sort_by_key(|m| std::cmp::Reverse(m.message_id));
+        let next_before = (messages.len() > limit).then(|| messages[limit - 1].message_id);
+        messages.truncate(limit);
+        Ok((messages, next_before))
+    }
+
+    /// Tin chưa xoá có nội dung chứa `query`, cũ hơn `before_id` (0 = từ tin mới nhất), mới nhất trước.
+    /// Quét lùi từng bucket ngày tới khi đủ `want` tin khớp hoặc đã đọc quá `scan_limit` tin;
+    /// trả kèm id để tìm tiếp (None = đã quét hết)
+    #[tracing::instrument(name = "db.search_messages", skip_all, fields(shop_id = %shop_id))]
+    pub async fn search_messages(
+        &self,
+        shop_id: &str,
+        guest_id: u64,
+        query: &str,
+        before_id: u64,
+        want: usize,
+        scan_limit: usize,
+    ) -> Result<(Vec<Message>, Option<u64>), ContractError> {
+        let before = (before_id != 0).then_some(before_id);
+        let last_bucket = before.map_or(i32::MAX, day_bucket);
+        let mut found = Vec::new();
+        let mut next_before = None;
+
+        self.bounded("Search messages", async {
+            let buckets = self.message_buckets(shop_id, guest_id, i32::MIN, last_bucket).await?;
+            let mut scanned = 0;
+            for (i, &bucket) in buckets.iter().enumerate().rev() {
+                let mut messages = Vec::new();
+                self.fetch_bucket(shop_id, guest_id, bucket, (0, before), usize::MAX, &mut messages).await?;
+                scanned += messages.len();
+                let oldest = messages.first().map(|m| m.message_id);
+                found.extend(messages.into_iter().rev().filter(|m| {
+                    m.deleted_at_us == 0 && text_matches(&String::from_utf8_lossy(&m.content), query)
+                }));
+                if found.len() >= want {
+                    found.truncate(want);
+                    next_before = found.last().map(|m| m.message_id);
+                    break;
+                }
+                // Còn bucket cũ hơn nhưng hết lượt quét → lần sau tìm tiếp từ tin cũ nhất đã đọc
+                if scanned >= scan_limit && i > 0 {
+                    next_before = oldest;
+                    break;
+                }
+            }
+            Ok(())
+        }).await?;
+
+        Ok((found, next_before))
+    }
+
+    /// Tin mới nhất của guest (bucket ngày cuối cùng có tin)
+    #[tracing::instrument(name = "db.latest_message", skip_all, fields(shop_id = %shop_id))]
+    pub async fn latest_message(&self, shop_id: &str, guest_id: u64) -> Result<Option<Message>, ContractError> {
+        for bucket in self.message_buckets(shop_id, guest_id, i32::MIN, i32::MAX).await?.into_iter().rev() {
+            let mut messages = Vec::new();
+            self.fetch_bucket(shop_id, guest_id, bucket, (0, None), usize::MAX, &mut messages).await?;
+            if let Some(last) = messages.pop() {
+                return Ok(Some(last));
             }
         }
+        Ok(None)
+    }
 
-        Ok(guests)
+    /// Điền conversation_feed cho dữ liệu có từ trước - migration 8
+    #[tracing::instrument(name = "db.backfill_feed", skip_all)]
+    pub async fn backfill_feed(&self) -> Result<usize, ContractError> {
+        let mut filled = 0;
+        for shop_id in self.list_shop_ids().await? {
+            for guest in self.get_guests(&shop_id).await? {
+                if let Some(msg) = self.latest_message(&shop_id, guest.guest_id).await? {
+                    self.set_feed_message(&msg).await?;
+                    filled += 1;
+                }
+            }
+        }
+        Ok(filled)
     }
 
-    // ========== MESSAGE ==========
-    pub async fn insert_message(&self, msg: &Message) -> Result<(), ContractError> {
-        let url = format!("{}/messages", self.base_url);
-        
-        // Base64 mới (không deprecated)
-        let content_base64 = BASE64.encode(&msg.content);
-        
+    /// Tin gần nhất của hội thoại (sidebar admin) - ghi sau mỗi lần lưu tin, sửa/xoá tin cuối
+    #[tracing::instrument(name = "db.set_guest_last_message", skip_all)]
+    pub async fn set_guest_last_message(&self, msg: &Message) -> Result<(), ContractError> {
         let payload = json!({
-            "shop_id": msg.shop_id,
-            "guest_id": msg.guest_id as i64,
-            "message_id": msg.message_id as i64,
-            "sender_type": msg.sender_type,
-            "content": content_base64,
-            "timestamp_us": msg.timestamp_us as i64,
-            "content_crc": msg.content_crc as i32
+            "last_message_preview": ReplyPreview::from_message(msg).snippet,
+            "last_message_at": msg.timestamp_us as i64,
+            "last_sender": msg.sender_type
+        });
+        self.patch_row(&format!("guests/{}/{}", msg.shop_id, msg.guest_id as i64), &payload).await
+    }
+
+    #[tracing::instrument(name = "db.set_guest_summary", skip_all, fields(shop_id = %shop_id))]
+    pub async fn set_guest_summary(&self, shop_id: &str, guest_id: u64, summary: &ConversationSummary) -> Result<(), ContractError> {
+        let payload = json!({
+            "summary": summary.text,
+            "summary_tags": summary.suggested_tags,
+            "summary_at": summary.generated_at as i64
+        });
+        self.patch_row(&format!("guests/{}/{}", shop_id, guest_id as i64), &payload).await
+    }
+
+    #[tracing::instrument(name = "db.set_conversation_status", skip_all, fields(shop_id = %shop_id))]
+    pub async fn set_conversation_status(&self, shop_id: &str, guest_id: u64, status: ConversationStatus) -> Result<(), ContractError> {
+        let payload = json!({ "status": status.as_db_str(), "status_changed_at": now_us() });
+        self.patch_row(&format!("guests/{}/{}", shop_id, guest_id as i64), &payload).await
+    }
+
+    #[tracing::instrument(name = "db.insert_guest_event", skip_all, fields(shop_id = %shop_id))]
+    pub async fn insert_guest_event(&self, shop_id: &str, guest_id: u64, event: &TrackEvent) -> Result<(), ContractError> {
+        let url = format!("{}/guest_events", self.base_url);
+
+        let payload = json!({
+            "shop_id": shop_id,
+            "guest_id": guest_id as i64,
+            "event_id": self.ids.next_id().0 as i64,
+            "name": event.name,
+            "properties": event.properties,
+            "occurred_at": event.occurred_at_us as i64
         });
 
         self.client
@@ -146,53 +663,1836 @@ impl AstraRepo {
             .json(&payload)
             .send()
             .await
-            .map_err(|e| ContractError::DbError(format!("Insert message failed: {}", e)))?;
+            .map_err(db_error("Insert guest event"))?;
 
         Ok(())
     }
 
-    pub async fn fetch_messages(
+    #[tracing::instrument(name = "db.get_guests", skip_all, fields(shop_id = %shop_id))]
+    pub async fn get_guests(&self, shop_id: &str) -> Result<Vec<Guest>, ContractError> {
+        let rows = self.guest_rows(shop_id, None).await?;
+        Ok(rows.iter().map(guest_from_row).collect())
+    }
+
+    /// Một trang guest, hoạt động gần nhất trước. `active_since` (µs) > 0 → bỏ khách im lặng từ trước đó.
+    /// CQL không sắp được theo cột thường → đọc cột nhẹ của cả shop, sắp/cắt ở đây rồi mới lấy đủ dòng cho trang
+    #[tracing::instrument(name = "db.list_guests_page", skip_all, fields(shop_id = %shop_id))]
+    pub async fn list_guests_page(
         &self,
         shop_id: &str,
-        guest_id: u64,
-        after_id: u64,
         limit: u32,
-    ) -> Result<Vec<Message>, ContractError> {
+        cursor: Option<&str>,
+        active_since: u64,
+    ) -> Result<GuestPage, ContractError> {
+        let limit = match limit {
+            0 => DEFAULT_GUEST_PAGE,
+            n => n.min(MAX_GUEST_PAGE),
+        } as usize;
+        let after = cursor.and_then(parse_guest_cursor);
+
+        let mut ranked: Vec<(u64, u64)> = self.guest_rows(shop_id, Some("guest_id,last_seen,last_message_at,merged_into")).await?
+            .iter()
+            // Guest đã gộp → hội thoại hiện dưới guest gốc
+            .filter(|row| row["merged_into"].as_i64().unwrap_or(0) == 0)
+            .map(|row| {
+                let seen = row["last_seen"].as_i64().unwrap_or(0).max(row["last_message_at"].as_i64().unwrap_or(0));
+                (seen as u64, row["guest_id"].as_i64().unwrap_or(0) as u64)
+            })
+            .filter(|&(activity, _)| activity >= active_since)
+            .filter(|key| after.is_none_or(|after| *key < after))
+            .collect();
+        ranked.sort_unstable_by(|a, b| b.cmp(a));
+
+        let has_more = ranked.len() > limit;
+        ranked.truncate(limit);
+        let next_cursor = ranked.last().filter(|_| has_more).map(|(activity, id)| format!("{}:{}", activity, id));
+        if ranked.is_empty() {
+            return Ok(GuestPage { guests: Vec::new(), next_cursor });
+        }
+
+        let ids: Vec<i64> = ranked.iter().map(|(_, id)| *id as i64).collect();
         let url = format!(
-            "{}/messages?where={{\"shop_id\":{{\"$eq\":\"{}\"}},\"guest_id\":{{\"$eq\":{}}},\"message_id\":{{\"$gt\":{}}}}}&page-size={}",
-            self.base_url, shop_id, guest_id as i64, after_id as i64, limit
+            "{}/guests?where={}&page-size={}",
+            self.base_url,
+            json!({ "shop_id": { "$eq": shop_id }, "guest_id": { "$in": ids } }),
+            ids.len()
         );
-        
+        let body = self.get_json(&url).await?;
+        let mut by_id: HashMap<u64, Guest> = body["data"].as_array()
+            .map(|rows| rows.iter().map(guest_from_row).map(|g| (g.guest_id, g)).collect())
+            .unwrap_or_default();
+        let guests = ranked.iter().filter_map(|(_, id)| by_id.remove(id)).collect();
+
+        Ok(GuestPage { guests, next_cursor })
+    }
+
+    // Mọi dòng guests của shop (đi theo pageState); `fields` = chỉ lấy vài cột
+    async fn guest_rows(&self, shop_id: &str, fields: Option<&str>) -> Result<Vec<serde_json::Value>, ContractError> {
+        let mut rows = Vec::new();
+        let mut page_state: Option<String> = None;
+
+        self.bounded("List guests", async {
+            loop {
+                let mut url = format!(
+                    "{}/guests?where={{\"shop_id\":{{\"$eq\":\"{}\"}}}}&page-size=1000",
+                    self.base_url, shop_id
+                );
+                if let Some(fields) = fields {
+                    url.push_str(&format!("&fields={}", fields));
+                }
+                let body = self.get_json(&with_page_state(&url, page_state.as_deref())).await?;
+                if let Some(page) = body["data"].as_array() {
+                    rows.extend(page.iter().cloned());
+                }
+                page_state = body["pageState"].as_str().filter(|ps| !ps.is_empty()).map(String::from);
+                if page_state.is_none() {
+                    break;
+                }
+            }
+            Ok(())
+        }).await?;
+
+        Ok(rows)
+    }
+
+    /// Ghi đủ mọi cột của guest (restore từ backup) - ngược với guest_from_row
+    #[tracing::instrument(name = "db.restore_guest", skip_all)]
+    pub async fn restore_guest(&self, guest: &Guest) -> Result<(), ContractError> {
+        let url = format!("{}/guests", self.base_url);
+        let context = guest.context.clone().unwrap_or_default();
+        let geo = guest.geo.clone().unwrap_or_default();
+        let summary = guest.summary.clone().unwrap_or_default();
+
+        let payload = json!({
+            "shop_id": guest.shop_id,
+            "guest_id": guest.guest_id as i64,
+            "guest_name": guest.guest_name,
+            "created_at": guest.created_at as i64,
+            "last_seen": guest.last_seen as i64,
+            "last_ip": guest.last_ip,
+            "email": guest.email,
+            "status": guest.status().as_db_str(),
+            "status_changed_at": guest.status_changed_at as i64,
+            "page_url": context.page_url,
+            "referrer": context.referrer,
+            "user_agent": context.user_agent,
+            "viewport_width": context.viewport_width as i32,
+            "viewport_height": context.viewport_height as i32,
+            "context_at": context.updated_at_us as i64,
+            "country_code": geo.country_code,
+            "country": geo.country,
+            "city": geo.city,
+            "tags": guest.tags,
+            "assigned_to": guest.assigned_to,
+            "awaiting_since": guest.awaiting_since as i64,
+            "first_response_ms": guest.first_response_ms as i64,
+            "summary": summary.text,
+            "summary_tags": summary.suggested_tags,
+            "summary_at": summary.generated_at as i64,
+            "sentiment": guest.sentiment,
+            "sentiment_at": guest.sentiment_at as i64,
+            "last_message_preview": guest.last_message_preview,
+            "last_message_at": guest.last_message_at as i64,
+            "last_sender": guest.last_sender
+        });
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(db_error("Restore guest"))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.get_guest", skip_all, fields(shop_id = %shop_id))]
+    pub async fn get_guest(&self, shop_id: &str, guest_id: u64) -> Result<Option<Guest>, ContractError> {
+        let url = format!("{}/guests/{}/{}", self.base_url, shop_id, guest_id as i64);
+
         let resp = self.client
             .get(&url)
             .header("X-Cassandra-Token", &self.token)
             .send()
             .await
-            .map_err(|e| ContractError::DbError(format!("Fetch messages failed: {}", e)))?;
+            .map_err(db_error("Get guest"))?;
+
+        if resp.status().as_u16() == 404 {
+            return Ok(None);
+        }
 
         let body: serde_json::Value = resp.json().await
-            .map_err(|e| ContractError::DbError(format!("Parse failed: {}", e)))?;
+            .map_err(db_error("Parse"))?;
 
-        let mut messages = Vec::new();
-        if let Some(rows) = body["data"].as_array() {
-            for row in rows {
-                // Base64 decode mới
-                let content_b64 = row["content"].as_str().unwrap_or("");
-                let content_bytes = BASE64.decode(content_b64)
-                    .map_err(|e| ContractError::DbError(format!("Base64 decode failed: {}", e)))?;
-
-                messages.push(Message {
-                    shop_id: row["shop_id"].as_str().unwrap_or("").to_string(),
-                    guest_id: row["guest_id"].as_i64().unwrap_or(0) as u64,
-                    message_id: row["message_id"].as_i64().unwrap_or(0) as u64,
-                    sender_type: row["sender_type"].as_str().unwrap_or("").to_string(),
-                    content: Bytes::from(content_bytes),
-                    timestamp_us: row["timestamp_us"].as_i64().unwrap_or(0) as u64,
-                    content_crc: row["content_crc"].as_i64().unwrap_or(0) as u32,
-                });
+        Ok(body["data"].as_array()
+            .and_then(|rows| rows.first())
+            .map(guest_from_row))
+    }
+
+    /// Xoá guest + toàn bộ dữ liệu gắn với guest (GDPR erasure).
+    /// Trả về các tin nhắn đã xoá (để dọn file đính kèm).
+    #[tracing::instrument(name = "db.erase_guest", skip_all, fields(shop_id = %shop_id))]
+    pub async fn erase_guest(&self, shop_id: &str, guest_id: u64) -> Result<Vec<Message>, ContractError> {
+        let user_id = self.get_guest(shop_id, guest_id).await?.map(|g| g.user_id).unwrap_or_default();
+        let mut messages = self.fetch_all_messages(shop_id, guest_id).await?;
+        // Tin retention đã chuyển sang lưu trữ cũng là dữ liệu của khách (file đính kèm vẫn còn)
+        messages.extend(self.fetch_archived_messages(shop_id, guest_id).await?);
+
+        // Xoá mọi partition ngày của guest, rồi danh sách bucket (và bảng messages cũ trước khi chia bucket)
+        for bucket in self.message_buckets(shop_id, guest_id, i32::MIN, i32::MAX).await? {
+            self.delete_row(&format!("messages_by_day/{}/{}/{}", shop_id, guest_id as i64, bucket)).await?;
+        }
+        self.delete_row(&format!("message_buckets/{}/{}", shop_id, guest_id as i64)).await?;
+        self.known_buckets.lock().unwrap().retain(|(shop, guest, _)| !(shop == shop_id && *guest == guest_id));
+        self.delete_row(&format!("messages/{}/{}", shop_id, guest_id as i64)).await?;
+        self.delete_row(&format!("guests/{}/{}", shop_id, guest_id as i64)).await?;
+        self.delete_row(&format!("conversation_feed/{}/{}", shop_id, guest_id as i64)).await?;
+        self.delete_row(&format!("guest_events/{}/{}", shop_id, guest_id as i64)).await?;
+        self.delete_row(&format!("guest_notes/{}/{}", shop_id, guest_id as i64)).await?;
+        self.delete_row(&format!("message_edits/{}/{}", shop_id, guest_id as i64)).await?;
+        self.delete_row(&format!("messages_archive/{}/{}", shop_id, guest_id as i64)).await?;
+        // Tin bị lọc spam giữ nguyên nội dung
+        self.remove_flagged_for_guest(shop_id, guest_id).await?;
+        // Tài khoản website đã xác minh → guest
+        if !user_id.is_empty() {
+            self.delete_row(&format!("guest_identities/{}/{}/{}", shop_id, user_id, guest_id as i64)).await?;
+        }
+        for scheduled in self.list_scheduled(shop_id).await? {
+            if scheduled.guest_id == guest_id {
+                self.delete_scheduled(shop_id, scheduled.scheduled_id).await?;
             }
         }
+        self.delete_reminder(shop_id, guest_id).await?;
+        self.delete_flow_state(shop_id, guest_id).await?;
 
         Ok(messages)
     }
+
+    // ========== AGENT STATUS ==========
+    #[tracing::instrument(name = "db.set_agent_away", skip_all, fields(shop_id = %shop_id))]
+    pub async fn set_agent_away(&self, shop_id: &str, agent_id: &str, away: bool) -> Result<(), ContractError> {
+        let url = format!("{}/agent_status", self.base_url);
+
+        let payload = json!({
+            "shop_id": shop_id,
+            "agent_id": agent_id,
+            "away": away,
+            "updated_at": now_us()
+        });
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(db_error("Upsert agent status"))?;
+
+        Ok(())
+    }
+
+    /// Chưa có dòng nào → không vắng mặt
+    #[tracing::instrument(name = "db.get_agent_away", skip_all, fields(shop_id = %shop_id))]
+    pub async fn get_agent_away(&self, shop_id: &str, agent_id: &str) -> Result<bool, ContractError> {
+        let url = format!("{}/agent_status/{}/{}", self.base_url, shop_id, agent_id);
+        let body = self.get_json(&url).await?;
+        Ok(body["data"].as_array()
+            .and_then(|rows| rows.first())
+            .and_then(|row| row["away"].as_bool())
+            .unwrap_or(false))
+    }
+
+    // ========== ADMIN PREFERENCES ==========
+    /// Chưa lưu bao giờ → mặc định (bật hết)
+    #[tracing::instrument(name = "db.get_preferences", skip_all, fields(shop_id = %shop_id))]
+    pub async fn get_preferences(&self, shop_id: &str, agent_id: &str) -> Result<AdminPreferences, ContractError> {
+        let url = format!("{}/admin_preferences/{}/{}", self.base_url, shop_id, agent_id);
+        let body = self.get_json(&url).await?;
+        let b64 = body["data"][0]["preferences"].as_str().unwrap_or("");
+        let bytes = BASE64.decode(b64)
+            .map_err(|e| ContractError::DbError(format!("Base64 decode failed: {}", e)))?;
+        Ok(AdminPreferences::decode(&bytes[..])?)
+    }
+
+    #[tracing::instrument(name = "db.save_preferences", skip_all, fields(shop_id = %shop_id))]
+    pub async fn save_preferences(&self, shop_id: &str, agent_id: &str, prefs: &AdminPreferences) -> Result<(), ContractError> {
+        let url = format!("{}/admin_preferences", self.base_url);
+
+        let payload = json!({
+            "shop_id": shop_id,
+            "agent_id": agent_id,
+            "preferences": BASE64.encode(prefs.encode_to_vec()),
+            "updated_at": now_us()
+        });
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(db_error("Upsert admin preferences"))?;
+
+        Ok(())
+    }
+
+    // ========== SAVED VIEWS ==========
+    /// Chưa lưu bao giờ → không có tab nào
+    #[tracing::instrument(name = "db.get_views", skip_all, fields(shop_id = %shop_id))]
+    pub async fn get_views(&self, shop_id: &str, agent_id: &str) -> Result<SavedViewList, ContractError> {
+        let url = format!("{}/saved_views/{}/{}", self.base_url, shop_id, agent_id);
+        let body = self.get_json(&url).await?;
+        let b64 = body["data"][0]["views"].as_str().unwrap_or("");
+        let bytes = BASE64.decode(b64)
+            .map_err(|e| ContractError::DbError(format!("Base64 decode failed: {}", e)))?;
+        Ok(SavedViewList::decode(&bytes[..])?)
+    }
+
+    #[tracing::instrument(name = "db.save_views", skip_all, fields(shop_id = %shop_id))]
+    pub async fn save_views(&self, shop_id: &str, agent_id: &str, views: &SavedViewList) -> Result<(), ContractError> {
+        let url = format!("{}/saved_views", self.base_url);
+
+        let payload = json!({
+            "shop_id": shop_id,
+            "agent_id": agent_id,
+            "views": BASE64.encode(views.encode_to_vec()),
+            "updated_at": now_us()
+        });
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(db_error("Upsert saved views"))?;
+
+        Ok(())
+    }
+
+    // ========== NOTES ==========
+    #[tracing::instrument(name = "db.insert_note", skip_all, fields(shop_id = %shop_id))]
+    /// `transfer` có = ghi chú bàn giao khi chuyển hội thoại
+    pub async fn insert_note(&self, shop_id: &str, guest_id: u64, content: &str, transfer: Option<ConversationTransfer>) -> Result<GuestNote, ContractError> {
+        let url = format!("{}/guest_notes", self.base_url);
+        let now = now_us();
+        let note = GuestNote {
+            note_id: self.ids.next_id().0,
+            guest_id,
+            content: content.to_string(),
+            created_at: now,
+            updated_at: now,
+            deleted: false,
+            transfer,
+        };
+
+        let mut payload = json!({
+            "shop_id": shop_id,
+            "guest_id": guest_id as i64,
+            "note_id": note.note_id as i64,
+            "content": note.content,
+            "created_at": now as i64,
+            "updated_at": now as i64
+        });
+        if let Some(transfer) = &note.transfer {
+            payload["transfer"] = json!(BASE64.encode(transfer.encode_to_vec()));
+        }
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(db_error("Insert note"))?;
+
+        Ok(note)
+    }
+
+    #[tracing::instrument(name = "db.update_note", skip_all, fields(shop_id = %shop_id))]
+    pub async fn update_note(&self, shop_id: &str, guest_id: u64, note_id: u64, content: &str) -> Result<(), ContractError> {
+        let payload = json!({ "content": content, "updated_at": now_us() });
+        self.patch_row(&format!("guest_notes/{}/{}/{}", shop_id, guest_id as i64, note_id as i64), &payload).await
+    }
+
+    #[tracing::instrument(name = "db.delete_note", skip_all, fields(shop_id = %shop_id))]
+    pub async fn delete_note(&self, shop_id: &str, guest_id: u64, note_id: u64) -> Result<(), ContractError> {
+        self.delete_row(&format!("guest_notes/{}/{}/{}", shop_id, guest_id as i64, note_id as i64)).await
+    }
+
+    #[tracing::instrument(name = "db.list_notes", skip_all, fields(shop_id = %shop_id))]
+    pub async fn list_notes(&self, shop_id: &str, guest_id: u64) -> Result<Vec<GuestNote>, ContractError> {
+        let url = format!("{}/guest_notes/{}/{}?page-size=200", self.base_url, shop_id, guest_id as i64);
+        let body = self.get_json(&url).await?;
+
+        Ok(body["data"].as_array()
+            .map(|rows| rows.iter().map(|row| GuestNote {
+                note_id: row["note_id"].as_i64().unwrap_or(0) as u64,
+                guest_id,
+                content: row["content"].as_str().unwrap_or("").to_string(),
+                created_at: row["created_at"].as_i64().unwrap_or(0) as u64,
+                updated_at: row["updated_at"].as_i64().unwrap_or(0) as u64,
+                deleted: false,
+                transfer: row["transfer"].as_str()
+                    .and_then(|b64| BASE64.decode(b64).ok())
+                    .and_then(|bytes| ConversationTransfer::decode(&bytes[..]).ok()),
+            }).collect())
+            .unwrap_or_default())
+    }
+
+    // ========== SCHEDULED ==========
+    #[tracing::instrument(name = "db.insert_scheduled", skip_all, fields(shop_id = %shop_id))]
+    pub async fn insert_scheduled(&self, shop_id: &str, scheduled: &ScheduledMessage) -> Result<(), ContractError> {
+        let url = format!("{}/scheduled_messages", self.base_url);
+
+        let payload = json!({
+            "shop_id": shop_id,
+            "scheduled_id": scheduled.scheduled_id as i64,
+            "guest_id": scheduled.guest_id as i64,
+            "content": BASE64.encode(&scheduled.content),
+            "send_at": scheduled.send_at_us as i64,
+            "created_at": scheduled.created_at as i64,
+            "quick_replies": quick_replies_json(&scheduled.quick_replies)
+        });
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(db_error("Insert scheduled"))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.delete_scheduled", skip_all, fields(shop_id = %shop_id))]
+    pub async fn delete_scheduled(&self, shop_id: &str, scheduled_id: u64) -> Result<(), ContractError> {
+        self.delete_row(&format!("scheduled_messages/{}/{}", shop_id, scheduled_id as i64)).await
+    }
+
+    /// Mọi tin hẹn giờ còn chờ của shop (sớm nhất trước)
+    #[tracing::instrument(name = "db.list_scheduled", skip_all, fields(shop_id = %shop_id))]
+    pub async fn list_scheduled(&self, shop_id: &str) -> Result<Vec<ScheduledMessage>, ContractError> {
+        let url = format!("{}/scheduled_messages/{}?page-size=1000", self.base_url, shop_id);
+        let body = self.get_json(&url).await?;
+
+        let mut items: Vec<ScheduledMessage> = body["data"].as_array()
+            .map(|rows| rows.iter().map(|row| ScheduledMessage {
+                scheduled_id: row["scheduled_id"].as_i64().unwrap_or(0) as u64,
+                guest_id: row["guest_id"].as_i64().unwrap_or(0) as u64,
+                content: BASE64.decode(row["content"].as_str().unwrap_or("")).unwrap_or_default().into(),
+                send_at_us: row["send_at"].as_i64().unwrap_or(0) as u64,
+                created_at: row["created_at"].as_i64().unwrap_or(0) as u64,
+                quick_replies: quick_replies_from_row(row),
+                cancelled: false,
+            }).collect())
+            .unwrap_or_default();
+        items.sort_by_key(|s| s.send_at_us);
+        Ok(items)
+    }
+
+    // ========== REMINDERS ==========
+    /// Đặt / đặt lại lời nhắc của hội thoại (mỗi guest một dòng)
+    #[tracing::instrument(name = "db.upsert_reminder", skip_all, fields(shop_id = %shop_id))]
+    pub async fn upsert_reminder(&self, shop_id: &str, reminder: &Reminder) -> Result<(), ContractError> {
+        let url = format!("{}/reminders", self.base_url);
+
+        let payload = json!({
+            "shop_id": shop_id,
+            "guest_id": reminder.guest_id as i64,
+            "remind_at": reminder.remind_at_us as i64,
+            "agent_id": reminder.agent_id,
+            "created_at": reminder.created_at as i64
+        });
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(db_error("Upsert reminder"))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.get_reminder", skip_all, fields(shop_id = %shop_id))]
+    pub async fn get_reminder(&self, shop_id: &str, guest_id: u64) -> Result<Option<Reminder>, ContractError> {
+        let url = format!("{}/reminders/{}/{}", self.base_url, shop_id, guest_id as i64);
+        let body = self.get_json(&url).await?;
+        Ok(body["data"].as_array().and_then(|rows| rows.first()).map(|row| Reminder {
+            guest_id,
+            remind_at_us: row["remind_at"].as_i64().unwrap_or(0) as u64,
+            agent_id: row["agent_id"].as_str().unwrap_or("").to_string(),
+            created_at: row["created_at"].as_i64().unwrap_or(0) as u64,
+            ..Default::default()
+        }))
+    }
+
+    #[tracing::instrument(name = "db.delete_reminder", skip_all, fields(shop_id = %shop_id))]
+    pub async fn delete_reminder(&self, shop_id: &str, guest_id: u64) -> Result<(), ContractError> {
+        self.delete_row(&format!("reminders/{}/{}", shop_id, guest_id as i64)).await
+    }
+
+    /// Mọi hội thoại đang tạm ẩn của shop (tới hạn sớm nhất trước)
+    #[tracing::instrument(name = "db.list_reminders", skip_all, fields(shop_id = %shop_id))]
+    pub async fn list_reminders(&self, shop_id: &str) -> Result<Vec<Reminder>, ContractError> {
+        let url = format!("{}/reminders/{}?page-size=1000", self.base_url, shop_id);
+        let body = self.get_json(&url).await?;
+
+        let mut items: Vec<Reminder> = body["data"].as_array()
+            .map(|rows| rows.iter().map(|row| Reminder {
+                guest_id: row["guest_id"].as_i64().unwrap_or(0) as u64,
+                remind_at_us: row["remind_at"].as_i64().unwrap_or(0) as u64,
+                agent_id: row["agent_id"].as_str().unwrap_or("").to_string(),
+                created_at: row["created_at"].as_i64().unwrap_or(0) as u64,
+                ..Default::default()
+            }).collect())
+            .unwrap_or_default();
+        items.sort_by_key(|r| r.remind_at_us);
+        Ok(items)
+    }
+
+    // ========== BOT FLOWS ==========
+    #[tracing::instrument(name = "db.save_flow_state", skip_all, fields(shop_id = %shop_id))]
+    pub async fn save_flow_state(&self, shop_id: &str, guest_id: u64, state: &FlowState) -> Result<(), ContractError> {
+        let url = format!("{}/bot_flow_state", self.base_url);
+
+        let payload = json!({
+            "shop_id": shop_id,
+            "guest_id": guest_id as i64,
+            "state": BASE64.encode(state.encode_to_vec()),
+            "updated_at": state.updated_at as i64
+        });
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(db_error("Save flow state"))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.get_flow_state", skip_all, fields(shop_id = %shop_id))]
+    pub async fn get_flow_state(&self, shop_id: &str, guest_id: u64) -> Result<Option<FlowState>, ContractError> {
+        let url = format!("{}/bot_flow_state/{}/{}", self.base_url, shop_id, guest_id as i64);
+        let body = self.get_json(&url).await?;
+        let Some(row) = body["data"].as_array().and_then(|rows| rows.first()) else {
+            return Ok(None);
+        };
+        let bytes = BASE64.decode(row["state"].as_str().unwrap_or(""))
+            .map_err(|e| ContractError::DbError(format!("Base64 decode failed: {}", e)))?;
+        Ok(Some(FlowState::decode(&bytes[..])?))
+    }
+
+    #[tracing::instrument(name = "db.delete_flow_state", skip_all, fields(shop_id = %shop_id))]
+    pub async fn delete_flow_state(&self, shop_id: &str, guest_id: u64) -> Result<(), ContractError> {
+        self.delete_row(&format!("bot_flow_state/{}/{}", shop_id, guest_id as i64)).await
+    }
+
+    // ========== CAMPAIGNS ==========
+    #[tracing::instrument(name = "db.upsert_campaign", skip_all, fields(shop_id = %shop_id))]
+    pub async fn upsert_campaign(&self, shop_id: &str, campaign: &Campaign) -> Result<(), ContractError> {
+        let url = format!("{}/campaigns", self.base_url);
+
+        let payload = json!({
+            "shop_id": shop_id,
+            "campaign_id": campaign.campaign_id as i64,
+            "campaign": BASE64.encode(campaign.encode_to_vec()),
+            "updated_at": now_us()
+        });
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(db_error("Upsert campaign"))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.get_campaign", skip_all, fields(shop_id = %shop_id))]
+    pub async fn get_campaign(&self, shop_id: &str, campaign_id: u64) -> Result<Option<Campaign>, ContractError> {
+        let url = format!("{}/campaigns/{}/{}", self.base_url, shop_id, campaign_id as i64);
+        let body = self.get_json(&url).await?;
+        body["data"].as_array().and_then(|rows| rows.first()).map(campaign_from_row).transpose()
+    }
+
+    /// Mọi chiến dịch của shop (mới nhất trước)
+    #[tracing::instrument(name = "db.list_campaigns", skip_all, fields(shop_id = %shop_id))]
+    pub async fn list_campaigns(&self, shop_id: &str) -> Result<Vec<Campaign>, ContractError> {
+        let url = format!("{}/campaigns/{}?page-size=1000", self.base_url, shop_id);
+        let body = self.get_json(&url).await?;
+        let mut items = body["data"].as_array()
+            .map(|rows| rows.iter().map(campaign_from_row).collect::<Result<Vec<_>, _>>())
+            .transpose()?
+            .unwrap_or_default();
+        items.sort_by_key(|c| std::cmp::Reverse(c.campaign_id));
+        Ok(items)
+    }
+
+    /// guest_id của khách khớp segment (bỏ guest đã gộp), hoạt động gần nhất trước.
+    /// Như list_guests_page: đọc cột nhẹ của cả shop rồi lọc ở đây
+    #[tracing::instrument(name = "db.segment_guests", skip_all, fields(shop_id = %shop_id))]
+    pub async fn segment_guests(&self, shop_id: &str, segment: &CampaignSegment) -> Result<Vec<u64>, ContractError> {
+        let active_since = match segment.active_within_days {
+            0 => 0,
+            days => now_us().saturating_sub(days as u64 * 86_400 * 1_000_000),
+        };
+        let mut ranked: Vec<(u64, u64)> = self.guest_rows(shop_id, Some("guest_id,last_seen,last_message_at,merged_into,tags")).await?
+            .iter()
+            .map(guest_from_row)
+            .filter(|g| g.merged_into == 0)
+            .filter(|g| segment.tags.is_empty() || g.tags.iter().any(|t| segment.tags.iter().any(|want| t.eq_ignore_ascii_case(want))))
+            .map(|g| (g.last_seen.max(g.last_message_at), g.guest_id))
+            .filter(|&(activity, _)| activity >= active_since)
+            .collect();
+        ranked.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(ranked.into_iter().map(|(_, id)| id).collect())
+    }
+
+    // ========== NHÂN VIÊN / LỜI MỜI ==========
+    #[tracing::instrument(name = "db.insert_agent", skip_all, fields(shop_id = %shop_id))]
+    pub async fn insert_agent(&self, shop_id: &str, agent: &AgentAccount) -> Result<(), ContractError> {
+        let url = format!("{}/admins", self.base_url);
+
+        let payload = json!({
+            "shop_id": shop_id,
+            "agent_id": agent.agent_id,
+            "email": agent.email,
+            "name": agent.name,
+            "password_hash": agent.password_hash,
+            "session_hash": agent.session_hash,
+            "created_at": agent.created_at as i64
+        });
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(db_error("Insert agent"))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.get_agent", skip_all, fields(shop_id = %shop_id))]
+    pub async fn get_agent(&self, shop_id: &str, agent_id: &str) -> Result<Option<AgentAccount>, ContractError> {
+        let url = format!("{}/admins/{}/{}", self.base_url, shop_id, agent_id);
+        let body = self.get_json(&url).await?;
+        Ok(body["data"].as_array().and_then(|rows| rows.first()).map(agent_from_row))
+    }
+
+    #[tracing::instrument(name = "db.list_agents", skip_all, fields(shop_id = %shop_id))]
+    pub async fn list_agents(&self, shop_id: &str) -> Result<Vec<AgentAccount>, ContractError> {
+        let url = format!("{}/admins/{}?page-size=1000", self.base_url, shop_id);
+        let body = self.get_json(&url).await?;
+        Ok(body["data"].as_array()
+            .map(|rows| rows.iter().map(agent_from_row).collect())
+            .unwrap_or_default())
+    }
+
+    #[tracing::instrument(name = "db.set_agent_session", skip_all, fields(shop_id = %shop_id))]
+    pub async fn set_agent_session(&self, shop_id: &str, agent_id: &str, session_hash: &str) -> Result<(), ContractError> {
+        self.patch_row(&format!("admins/{}/{}", shop_id, agent_id), &json!({ "session_hash": session_hash })).await
+    }
+
+    #[tracing::instrument(name = "db.insert_invite", skip_all, fields(shop_id = %shop_id))]
+    pub async fn insert_invite(&self, shop_id: &str, invite: &AdminInvite) -> Result<(), ContractError> {
+        let url = format!("{}/admin_invites", self.base_url);
+
+        let payload = json!({
+            "shop_id": shop_id,
+            "invite_id": invite.invite_id,
+            "email": invite.email,
+            "created_at": now_us(),
+            "expires_at": invite.expires_at as i64,
+            "accepted_at": 0
+        });
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(db_error("Insert invite"))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.get_invite", skip_all, fields(shop_id = %shop_id))]
+    pub async fn get_invite(&self, shop_id: &str, invite_id: &str) -> Result<Option<AdminInvite>, ContractError> {
+        let url = format!("{}/admin_invites/{}/{}", self.base_url, shop_id, invite_id);
+        let body = self.get_json(&url).await?;
+        Ok(body["data"].as_array().and_then(|rows| rows.first()).map(|row| AdminInvite {
+            invite_id: row["invite_id"].as_str().unwrap_or("").to_string(),
+            email: row["email"].as_str().unwrap_or("").to_string(),
+            expires_at: row["expires_at"].as_i64().unwrap_or(0) as u64,
+            accepted_at: row["accepted_at"].as_i64().unwrap_or(0) as u64,
+        }))
+    }
+
+    #[tracing::instrument(name = "db.accept_invite", skip_all, fields(shop_id = %shop_id))]
+    pub async fn accept_invite(&self, shop_id: &str, invite_id: &str) -> Result<(), ContractError> {
+        self.patch_row(&format!("admin_invites/{}/{}", shop_id, invite_id), &json!({ "accepted_at": now_us() })).await
+    }
+
+    // ========== API KEYS ==========
+    #[tracing::instrument(name = "db.insert_api_key", skip_all, fields(shop_id = %shop_id))]
+    pub async fn insert_api_key(&self, shop_id: &str, key: &ApiKey, key_hash: &str) -> Result<(), ContractError> {
+        let url = format!("{}/api_keys", self.base_url);
+
+        let payload = json!({
+            "shop_id": shop_id,
+            "key_id": key.key_id,
+            "name": key.name,
+            "key_hash": key_hash,
+            "scopes": key.scopes,
+            "created_at": key.created_at as i64,
+            "rotated_at": key.rotated_at as i64,
+            "revoked_at": 0
+        });
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(db_error("Insert API key"))?;
+
+        Ok(())
+    }
+
+    /// Khoá + hash đã lưu
+    #[tracing::instrument(name = "db.get_api_key", skip_all, fields(shop_id = %shop_id))]
+    pub async fn get_api_key(&self, shop_id: &str, key_id: &str) -> Result<Option<(ApiKey, String)>, ContractError> {
+        let url = format!("{}/api_keys/{}/{}", self.base_url, shop_id, key_id);
+        let body = self.get_json(&url).await?;
+        Ok(body["data"].as_array()
+            .and_then(|rows| rows.first())
+            .map(|row| (api_key_from_row(row), row["key_hash"].as_str().unwrap_or("").to_string())))
+    }
+
+    #[tracing::instrument(name = "db.list_api_keys", skip_all, fields(shop_id = %shop_id))]
+    pub async fn list_api_keys(&self, shop_id: &str) -> Result<Vec<ApiKey>, ContractError> {
+        let url = format!("{}/api_keys/{}?page-size=1000", self.base_url, shop_id);
+        let body = self.get_json(&url).await?;
+        Ok(body["data"].as_array()
+            .map(|rows| rows.iter().map(api_key_from_row).collect())
+            .unwrap_or_default())
+    }
+
+    #[tracing::instrument(name = "db.rotate_api_key", skip_all, fields(shop_id = %shop_id))]
+    pub async fn rotate_api_key(&self, shop_id: &str, key_id: &str, key_hash: &str, rotated_at: u64) -> Result<(), ContractError> {
+        let payload = json!({ "key_hash": key_hash, "rotated_at": rotated_at as i64 });
+        self.patch_row(&format!("api_keys/{}/{}", shop_id, key_id), &payload).await
+    }
+
+    #[tracing::instrument(name = "db.revoke_api_key", skip_all, fields(shop_id = %shop_id))]
+    pub async fn revoke_api_key(&self, shop_id: &str, key_id: &str) -> Result<(), ContractError> {
+        self.patch_row(&format!("api_keys/{}/{}", shop_id, key_id), &json!({ "revoked_at": now_us() })).await
+    }
+
+    // ========== ANALYTICS ==========
+    #[tracing::instrument(name = "db.upsert_daily_stats", skip_all, fields(shop_id = %shop_id))]
+    pub async fn upsert_daily_stats(&self, shop_id: &str, stats: &DailyStats) -> Result<(), ContractError> {
+        let url = format!("{}/shop_daily_stats", self.base_url);
+
+        let payload = json!({
+            "shop_id": shop_id,
+            "day": stats.day,
+            "new_conversations": stats.new_conversations,
+            "guest_messages": stats.guest_messages,
+            "admin_messages": stats.admin_messages,
+            "median_first_response_ms": stats.median_first_response_ms as i64,
+            "hourly_messages": stats.hourly_messages,
+            "updated_at": now_us()
+        });
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(db_error("Upsert daily stats"))?;
+
+        Ok(())
+    }
+
+    /// Thống kê từ ngày `from_day` ("YYYY-MM-DD") tới nay, mới nhất trước
+    #[tracing::instrument(name = "db.get_daily_stats", skip_all, fields(shop_id = %shop_id))]
+    pub async fn get_daily_stats(&self, shop_id: &str, from_day: &str) -> Result<Vec<DailyStats>, ContractError> {
+        let url = format!(
+            "{}/shop_daily_stats?where={{\"shop_id\":{{\"$eq\":\"{}\"}},\"day\":{{\"$gte\":\"{}\"}}}}&page-size=400",
+            self.base_url, shop_id, from_day
+        );
+        let body = self.get_json(&url).await?;
+
+        let mut days = Vec::new();
+        if let Some(rows) = body["data"].as_array() {
+            for row in rows {
+                days.push(DailyStats {
+                    day: row["day"].as_str().unwrap_or("").to_string(),
+                    new_conversations: row["new_conversations"].as_u64().unwrap_or(0) as u32,
+                    guest_messages: row["guest_messages"].as_u64().unwrap_or(0) as u32,
+                    admin_messages: row["admin_messages"].as_u64().unwrap_or(0) as u32,
+                    median_first_response_ms: row["median_first_response_ms"].as_u64().unwrap_or(0),
+                    hourly_messages: row["hourly_messages"].as_array()
+                        .map(|h| h.iter().map(|v| v.as_u64().unwrap_or(0) as u32).collect())
+                        .unwrap_or_default(),
+                });
+            }
+        }
+
+        Ok(days)
+    }
+
+    // ========== BLOCKS ==========
+    #[tracing::instrument(name = "db.add_block", skip_all, fields(shop_id = %shop_id))]
+    pub async fn add_block(&self, shop_id: &str, target: &str, reason: &str) -> Result<(), ContractError> {
+        let url = format!("{}/blocks", self.base_url);
+
+        let payload = json!({
+            "shop_id": shop_id,
+            "target": target,
+            "reason": reason,
+            "created_at": now_us()
+        });
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(db_error("Insert block"))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.remove_block", skip_all, fields(shop_id = %shop_id))]
+    pub async fn remove_block(&self, shop_id: &str, target: &str) -> Result<(), ContractError> {
+        self.delete_row(&format!("blocks/{}/{}", shop_id, target)).await
+    }
+
+    #[tracing::instrument(name = "db.list_blocks", skip_all, fields(shop_id = %shop_id))]
+    pub async fn list_blocks(&self, shop_id: &str) -> Result<Vec<BlockEntry>, ContractError> {
+        let url = format!("{}/blocks/{}?page-size=1000", self.base_url, shop_id);
+        let body = self.get_json(&url).await?;
+
+        let mut blocks = Vec::new();
+        if let Some(rows) = body["data"].as_array() {
+            for row in rows {
+                blocks.push(BlockEntry {
+                    target: row["target"].as_str().unwrap_or("").to_string(),
+                    reason: row["reason"].as_str().unwrap_or("").to_string(),
+                    created_at: row["created_at"].as_i64().unwrap_or(0) as u64,
+                });
+            }
+        }
+
+        Ok(blocks)
+    }
+
+    // ========== FLAGGED ==========
+    #[tracing::instrument(name = "db.insert_flagged", skip_all)]
+    pub async fn insert_flagged(&self, msg: &Message, reason: &str) -> Result<(), ContractError> {
+        let url = format!("{}/flagged_messages", self.base_url);
+
+        let mut payload = message_payload(msg);
+        payload["reason"] = json!(reason);
+        payload["flagged_at"] = json!(now_us());
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(db_error("Insert flagged"))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.list_flagged", skip_all, fields(shop_id = %shop_id))]
+    pub async fn list_flagged(&self, shop_id: &str) -> Result<Vec<FlaggedMessage>, ContractError> {
+        let url = format!("{}/flagged_messages/{}?page-size=200", self.base_url, shop_id);
+        let body = self.get_json(&url).await?;
+
+        let mut items = Vec::new();
+        if let Some(rows) = body["data"].as_array() {
+            for row in rows {
+                items.push(FlaggedMessage {
+                    message: Some(message_from_row(row)?),
+                    reason: row["reason"].as_str().unwrap_or("").to_string(),
+                    flagged_at: row["flagged_at"].as_i64().unwrap_or(0) as u64,
+                });
+            }
+        }
+
+        Ok(items)
+    }
+
+    #[tracing::instrument(name = "db.remove_flagged", skip_all, fields(shop_id = %shop_id))]
+    pub async fn remove_flagged(&self, shop_id: &str, message_id: u64) -> Result<(), ContractError> {
+        self.delete_row(&format!("flagged_messages/{}/{}", shop_id, message_id as i64)).await
+    }
+
+    /// Xoá mọi tin bị đánh dấu của guest (partition theo shop → đi hết rồi lọc)
+    #[tracing::instrument(name = "db.remove_flagged_for_guest", skip_all, fields(shop_id = %shop_id))]
+    pub async fn remove_flagged_for_guest(&self, shop_id: &str, guest_id: u64) -> Result<(), ContractError> {
+        let url = format!("{}/flagged_messages/{}?page-size=500&fields=message_id,guest_id", self.base_url, shop_id);
+        let mut message_ids = Vec::new();
+        let mut page_state: Option<String> = None;
+        loop {
+            let body = self.get_json(&with_page_state(&url, page_state.as_deref())).await?;
+            message_ids.extend(body["data"].as_array().into_iter().flatten()
+                .filter(|row| row["guest_id"].as_i64() == Some(guest_id as i64))
+                .filter_map(|row| row["message_id"].as_i64()));
+            page_state = body["pageState"].as_str().filter(|ps| !ps.is_empty()).map(String::from);
+            if page_state.is_none() {
+                break;
+            }
+        }
+        for message_id in message_ids {
+            self.remove_flagged(shop_id, message_id as u64).await?;
+        }
+        Ok(())
+    }
+
+    /// Mọi dòng gắn với guest ngoài tin nhắn, nguyên các cột (GDPR export) - cùng các bảng erase_guest xoá
+    #[tracing::instrument(name = "db.guest_records", skip_all, fields(shop_id = %shop_id))]
+    pub async fn guest_records(&self, shop_id: &str, guest_id: u64) -> Result<serde_json::Value, ContractError> {
+        let gid = guest_id as i64;
+        let mut records = serde_json::Map::new();
+        for table in ["guests", "conversation_feed", "guest_events", "guest_notes", "message_edits", "reminders", "bot_flow_state"] {
+            records.insert(table.to_string(), json!(self.partition_rows(&format!("{}/{}/{}", table, shop_id, gid)).await?));
+        }
+        let user_id = records["guests"][0]["user_id"].as_str().unwrap_or("").to_string();
+        let identities = if user_id.is_empty() {
+            Vec::new()
+        } else {
+            self.partition_rows(&format!("guest_identities/{}/{}/{}", shop_id, user_id, gid)).await?
+        };
+        records.insert("guest_identities".to_string(), json!(identities));
+        // Bảng theo shop → lọc theo guest_id
+        for table in ["flagged_messages", "scheduled_messages"] {
+            let rows: Vec<_> = self.partition_rows(&format!("{}/{}", table, shop_id)).await?
+                .into_iter()
+                .filter(|row| row["guest_id"].as_i64() == Some(gid))
+                .collect();
+            records.insert(table.to_string(), json!(rows));
+        }
+        Ok(serde_json::Value::Object(records))
+    }
+
+    // Mọi dòng dưới một đường dẫn khoá (đi hết các trang)
+    async fn partition_rows(&self, path: &str) -> Result<Vec<serde_json::Value>, ContractError> {
+        let url = format!("{}/{}?page-size=500", self.base_url, path);
+        let mut rows = Vec::new();
+        let mut page_state: Option<String> = None;
+        loop {
+            let body = self.get_json(&with_page_state(&url, page_state.as_deref())).await?;
+            rows.extend(body["data"].as_array().into_iter().flatten().cloned());
+            page_state = body["pageState"].as_str().filter(|ps| !ps.is_empty()).map(String::from);
+            if page_state.is_none() {
+                break;
+            }
+        }
+        Ok(rows)
+    }
+
+    // ========== AUDIT ==========
+    #[tracing::instrument(name = "db.insert_audit", skip_all, fields(shop_id = %shop_id))]
+    pub async fn insert_audit(
+        &self,
+        shop_id: &str,
+        action: &str,
+        guest_id: u64,
+        actor: &str,
+        detail: &str,
+    ) -> Result<(), ContractError> {
+        let url = format!("{}/audit_log", self.base_url);
+
+        let payload = json!({
+            "shop_id": shop_id,
+            "event_id": self.ids.next_id().0 as i64,
+            "action": action,
+            "guest_id": guest_id as i64,
+            "actor": actor,
+            "detail": detail
+        });
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(db_error("Insert audit"))?;
+
+        Ok(())
+    }
+
+    // ========== SCHEMA ==========
+    #[tracing::instrument(name = "db.create_table", skip_all)]
+    pub async fn create_table(&self, definition: &serde_json::Value) -> Result<(), ContractError> {
+        let url = format!("{}/tables", self.schema_url);
+        let resp = self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(definition)
+            .send()
+            .await
+            .map_err(db_error("Create table"))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let detail = resp.text().await.unwrap_or_default();
+            return Err(ContractError::DbError(format!("Create table {} failed: {} {}", definition["name"], status, detail)));
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.column_exists", skip_all)]
+    pub async fn column_exists(&self, table: &str, column: &str) -> Result<bool, ContractError> {
+        let url = format!("{}/tables/{}/columns/{}", self.schema_url, table, column);
+        let resp = self.client
+            .get(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .send()
+            .await
+            .map_err(db_error("Request"))?;
+
+        match resp.status().as_u16() {
+            404 => Ok(false),
+            _ if resp.status().is_success() => Ok(true),
+            status => Err(ContractError::DbError(format!("Describe {}.{} failed: {}", table, column, status))),
+        }
+    }
+
+    #[tracing::instrument(name = "db.add_column", skip_all)]
+    pub async fn add_column(&self, table: &str, column: &str, cql_type: &str) -> Result<(), ContractError> {
+        let url = format!("{}/tables/{}/columns", self.schema_url, table);
+        let resp = self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&json!({ "name": column, "typeDefinition": cql_type, "static": false }))
+            .send()
+            .await
+            .map_err(db_error("Add column"))?;
+
+        if !resp.status().is_success() {
+            return Err(ContractError::DbError(format!("Add column {}.{} failed: {}", table, column, resp.status())));
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.applied_migrations", skip_all)]
+    pub async fn applied_migrations(&self) -> Result<Vec<u32>, ContractError> {
+        let mut versions = Vec::new();
+        let mut page_state: Option<String> = None;
+
+        self.bounded("Read migrations", async {
+            loop {
+                let url = format!("{}/schema_migrations/rows?fields=version&page-size=100", self.base_url);
+                let body = self.get_json(&with_page_state(&url, page_state.as_deref())).await?;
+                if let Some(rows) = body["data"].as_array() {
+                    versions.extend(rows.iter().filter_map(|r| r["version"].as_u64()).map(|v| v as u32));
+                }
+                match body["pageState"].as_str() {
+                    Some(ps) if !ps.is_empty() => page_state = Some(ps.to_string()),
+                    _ => break,
+                }
+            }
+            Ok(())
+        }).await?;
+
+        Ok(versions)
+    }
+
+    #[tracing::instrument(name = "db.record_migration", skip_all)]
+    pub async fn record_migration(&self, version: u32, name: &str) -> Result<(), ContractError> {
+        let url = format!("{}/schema_migrations", self.base_url);
+        let applied_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_micros() as i64;
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&json!({ "version": version, "name": name, "applied_at": applied_at }))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(db_error("Record migration"))?;
+
+        Ok(())
+    }
+
+    // ========== HELPERS ==========
+    // Thao tác nhiều request: quá op_timeout → huỷ, trả Timeout
+    async fn bounded<T>(&self, op: &str, fut: impl Future<Output = Result<T, ContractError>>) -> Result<T, ContractError> {
+        tokio::time::timeout(self.op_timeout, fut)
+            .await
+            .unwrap_or_else(|_| Err(ContractError::Timeout(op.to_string())))
+    }
+
+    async fn get_json(&self, url: &str) -> Result<serde_json::Value, ContractError> {
+        let resp = self.client
+            .get(url)
+            .header("X-Cassandra-Token", &self.token)
+            .send()
+            .await
+            .map_err(db_error("Request"))?;
+
+        resp.json().await
+            .map_err(db_error("Parse"))
+    }
+
+    async fn delete_row(&self, path: &str) -> Result<(), ContractError> {
+        let url = format!("{}/{}", self.base_url, path);
+
+        let resp = self.client
+            .delete(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .send()
+            .await
+            .map_err(db_error("Delete"))?;
+
+        if !resp.status().is_success() && resp.status().as_u16() != 404 {
+            return Err(ContractError::DbError(format!("Delete {} failed: {}", path, resp.status())));
+        }
+
+        Ok(())
+    }
+
+    async fn patch_row(&self, path: &str, payload: &serde_json::Value) -> Result<(), ContractError> {
+        let url = format!("{}/{}", self.base_url, path);
+
+        let resp = self.client
+            .patch(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(payload)
+            .send()
+            .await
+            .map_err(db_error("Update"))?;
+
+        if !resp.status().is_success() {
+            return Err(ContractError::DbError(format!("Update {} failed: {}", path, resp.status())));
+        }
+
+        Ok(())
+    }
+
+    // ========== MESSAGE ==========
+    async fn get_message_row(&self, shop_id: &str, guest_id: u64, message_id: u64) -> Result<Option<serde_json::Value>, ContractError> {
+        let url = format!("{}/{}", self.base_url, message_path(shop_id, guest_id, message_id));
+        let body = self.get_json(&url).await?;
+        Ok(body["data"].as_array().and_then(|rows| rows.first()).cloned())
+    }
+
+    #[tracing::instrument(name = "db.get_message", skip_all, fields(shop_id = %shop_id))]
+    pub async fn get_message(&self, shop_id: &str, guest_id: u64, message_id: u64) -> Result<Option<Message>, ContractError> {
+        self.get_message_row(shop_id, guest_id, message_id).await?
+            .map(|row| message_from_row(&row))
+            .transpose()
+    }
+
+    // Điền trích đoạn tin được trả lời (ưu tiên tin có sẵn trong batch)
+    #[tracing::instrument(name = "db.hydrate_replies", skip_all)]
+    pub async fn hydrate_replies(&self, messages: &mut [Message]) {
+        let mut previews: HashMap<u64, ReplyPreview> = messages.iter()
+            .map(|m| (m.message_id, ReplyPreview::from_message(m)))
+            .collect();
+
+        for msg in messages.iter_mut() {
+            let Some(reply_id) = msg.reply_to_message_id else { continue };
+            let preview = match previews.get(&reply_id) {
+                Some(preview) => preview.clone(),
+                None => match self.get_message(&msg.shop_id, msg.guest_id, reply_id).await {
+                    Ok(Some(quoted)) => {
+                        let preview = ReplyPreview::from_message(&quoted);
+                        previews.insert(reply_id, preview.clone());
+                        preview
+                    }
+                    Ok(None) => continue,
+                    Err(e) => {
+                        eprintln!("❌ Load quoted message {} failed: {:?}", reply_id, e);
+                        continue;
+                    }
+                },
+            };
+            msg.reply_preview = Some(preview);
+        }
+    }
+
+    // Sửa nội dung, giữ số lần sửa (edit_count)
+    #[tracing::instrument(name = "db.edit_message", skip_all, fields(shop_id = %shop_id))]
+    pub async fn edit_message(
+        &self,
+        shop_id: &str,
+        guest_id: u64,
+        message_id: u64,
+        content: &[u8],
+        content_crc: u32,
+        edited_at_us: u64,
+    ) -> Result<(), ContractError> {
+        let edit_count = self.get_message_row(shop_id, guest_id, message_id).await?
+            .and_then(|row| row["edit_count"].as_i64())
+            .unwrap_or(0);
+
+        let payload = json!({
+            "content": BASE64.encode(content),
+            "content_crc": content_crc as i32,
+            "edited_at": edited_at_us as i64,
+            "edit_count": edit_count + 1
+        });
+        self.patch_row(&message_path(shop_id, guest_id, message_id), &payload).await
+    }
+
+    /// Tombstone: giữ dòng + nội dung (admin còn xem được), đánh dấu thời điểm và người xoá
+    #[tracing::instrument(name = "db.soft_delete_message", skip_all, fields(shop_id = %shop_id))]
+    pub async fn soft_delete_message(&self, shop_id: &str, guest_id: u64, message_id: u64, deleted_at_us: u64, deleted_by: &str) -> Result<(), ContractError> {
+        let payload = json!({ "deleted_at": deleted_at_us as i64, "deleted_by": deleted_by });
+        self.patch_row(&message_path(shop_id, guest_id, message_id), &payload).await
+    }
+
+    #[tracing::instrument(name = "db.insert_message", skip_all)]
+    pub async fn insert_message(&self, msg: &Message) -> Result<(), ContractError> {
+        let url = format!("{}/messages_by_day", self.base_url);
+        let bucket = day_bucket(msg.message_id);
+        // Ghi bucket trước - đọc theo danh sách bucket nên không được sót
+        self.register_bucket(&msg.shop_id, msg.guest_id, bucket).await?;
+
+        let mut payload = message_payload(msg);
+        payload["day_bucket"] = json!(bucket);
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(db_error("Insert message"))?;
+
+        Ok(())
+    }
+
+    /// Tối đa `limit` tin sau `after_id` (0 = mặc định, quá MAX_PAGE_LIMIT thì cắt), đi qua các bucket ngày.
+    /// `page_state` lấy từ trang trước - phải gọi lại với cùng `after_id`
+    #[tracing::instrument(name = "db.fetch_messages", skip_all, fields(shop_id = %shop_id))]
+    pub async fn fetch_messages(
+        &self,
+        shop_id: &str,
+        guest_id: u64,
+        after_id: u64,
+        limit: u32,
+        page_state: Option<&str>,
+    ) -> Result<MessagePage, ContractError> {
+        let limit = match limit {
+            0 => DEFAULT_PAGE_LIMIT,
+            n => n.min(MAX_PAGE_LIMIT),
+        } as usize;
+        // Tiếp tục sau tin cuối của trang trước
+        let after_id = page_state
+            .and_then(|ps| ps.parse::<u64>().ok())
+            .map_or(after_id, |last| last.max(after_id));
+
+        // Lấy dư 1 tin để biết còn trang sau không
+        let mut messages = Vec::with_capacity(limit + 1);
+        self.bounded("Fetch messages", async {
+            for bucket in self.message_buckets(shop_id, guest_id, first_bucket_after(after_id), i32::MAX).await? {
+                self.fetch_bucket(shop_id, guest_id, bucket, (after_id, None), limit + 1, &mut messages).await?;
+                if messages.len() > limit {
+                    break;
+                }
+            }
+            Ok(())
+        }).await?;
+
+        let has_more = messages.len() > limit;
+        messages.truncate(limit);
+        let page_state = messages.last().filter(|_| has_more).map(|m| m.message_id.to_string());
+        Ok(MessagePage { messages, page_state })
+    }
+
+    /// Như fetch_messages nhưng gộp tin của nhiều guest (guest gốc + alias) theo message_id.
+    /// message_id toàn cục tăng dần → page_state (id cuối trang) dùng chung được cho mọi guest
+    #[tracing::instrument(name = "db.fetch_conversation", skip_all, fields(shop_id = %shop_id))]
+    pub async fn fetch_conversation(
+        &self,
+        shop_id: &str,
+        guest_ids: &[u64],
+        after_id: u64,
+        limit: u32,
+        page_state: Option<&str>,
+    ) -> Result<MessagePage, ContractError> {
+        if let [guest_id] = guest_ids {
+            return self.fetch_messages(shop_id, *guest_id, after_id, limit, page_state).await;
+        }
+        let pages = futures::future::try_join_all(
+            guest_ids.iter().map(|&guest_id| self.fetch_messages(shop_id, guest_id, after_id, limit, page_state)),
+        ).await?;
+
+        let limit = match limit {
+            0 => DEFAULT_PAGE_LIMIT,
+            n => n.min(MAX_PAGE_LIMIT),
+        } as usize;
+        // Guest nào còn trang sau thì chưa chắc đã đủ tin tới hết trang gộp
+        let any_more = pages.iter().any(|p| p.page_state.is_some());
+        let mut messages: Vec<Message> = pages.into_iter().flat_map(|p| p.messages).collect();
+        messages.sort_unstable_by_key(|m| m.message_id);
+        let has_more = messages.len() > limit || any_more;
+        messages.truncate(limit);
+        let page_state = messages.last().filter(|_| has_more).map(|m| m.message_id.to_string());
+        Ok(MessagePage { messages, page_state })
+    }
+
+    /// Tin nhắn cũ hơn `before_id` (dùng cho retention sweeper)
+    // Tin có after_id < message_id < before_id
+    #[tracing::instrument(name = "db.fetch_messages_between", skip_all, fields(shop_id = %shop_id))]
+    pub async fn fetch_messages_between(
+        &self,
+        shop_id: &str,
+        guest_id: u64,
+        after_id: u64,
+        before_id: u64,
+        limit: u32,
+    ) -> Result<Vec<Message>, ContractError> {
+        let limit = limit as usize;
+        let mut messages = Vec::with_capacity(limit);
+        self.bounded("Fetch messages", async {
+            for bucket in self.message_buckets(shop_id, guest_id, first_bucket_after(after_id), day_bucket(before_id)).await? {
+                self.fetch_bucket(shop_id, guest_id, bucket, (after_id, Some(before_id)), limit, &mut messages).await?;
+                if messages.len() >= limit {
+                    break;
+                }
+            }
+            Ok(())
+        }).await?;
+        Ok(messages)
+    }
+
+    // Đọc một partition ngày (theo pageState của Astra) tới khi `out` đủ `want` tin có after_id < id < before_id
+    async fn fetch_bucket(
+        &self,
+        shop_id: &str,
+        guest_id: u64,
+        bucket: i32,
+        (after_id, before_id): (u64, Option<u64>),
+        want: usize,
+        out: &mut Vec<Message>,
+    ) -> Result<(), ContractError> {
+        let before = before_id.map(|id| format!(",\"$lt\":{}", id as i64)).unwrap_or_default();
+        let mut page_state: Option<String> = None;
+        while out.len() < want {
+            let url = format!(
+                "{}/messages_by_day?where={{\"shop_id\":{{\"$eq\":\"{}\"}},\"guest_id\":{{\"$eq\":{}}},\"day_bucket\":{{\"$eq\":{}}},\"message_id\":{{\"$gt\":{}{}}}}}&page-size={}",
+                self.base_url, shop_id, guest_id as i64, bucket, after_id as i64, before, (want - out.len()).min(BUCKET_PAGE)
+            );
+            let body = self.get_json(&with_page_state(&url, page_state.as_deref())).await?;
+            for row in body["data"].as_array().into_iter().flatten() {
+                out.push(message_from_row(row)?);
+            }
+            page_state = body["pageState"].as_str().filter(|ps| !ps.is_empty()).map(String::from);
+            if page_state.is_none() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // ========== MESSAGE BUCKETS ==========
+    /// Các bucket ngày có tin của guest trong [from, to], tăng dần
+    async fn message_buckets(&self, shop_id: &str, guest_id: u64, from: i32, to: i32) -> Result<Vec<i32>, ContractError> {
+        let url = format!(
+            "{}/message_buckets?where={{\"shop_id\":{{\"$eq\":\"{}\"}},\"guest_id\":{{\"$eq\":{}}},\"day_bucket\":{{\"$gte\":{},\"$lte\":{}}}}}&page-size=1000",
+            self.base_url, shop_id, guest_id as i64, from, to
+        );
+        let mut buckets = Vec::new();
+        let mut page_state: Option<String> = None;
+        loop {
+            let body = self.get_json(&with_page_state(&url, page_state.as_deref())).await?;
+            buckets.extend(body["data"].as_array().into_iter().flatten().filter_map(|row| row["day_bucket"].as_i64()).map(|b| b as i32));
+            page_state = body["pageState"].as_str().filter(|ps| !ps.is_empty()).map(String::from);
+            if page_state.is_none() {
+                break;
+            }
+        }
+        Ok(buckets)
+    }
+
+    async fn register_bucket(&self, shop_id: &str, guest_id: u64, bucket: i32) -> Result<(), ContractError> {
+        let key = (shop_id.to_string(), guest_id, bucket);
+        if self.known_buckets.lock().unwrap().contains(&key) {
+            return Ok(());
+        }
+        let url = format!("{}/message_buckets", self.base_url);
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&json!({ "shop_id": shop_id, "guest_id": guest_id as i64, "day_bucket": bucket }))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(db_error("Register message bucket"))?;
+
+        let mut known = self.known_buckets.lock().unwrap();
+        if known.len() >= MAX_KNOWN_BUCKETS {
+            known.clear();
+        }
+        known.insert(key);
+        Ok(())
+    }
+
+    /// Chép bảng messages cũ (partition theo guest) sang messages_by_day - migration 7
+    #[tracing::instrument(name = "db.backfill_message_buckets", skip_all)]
+    pub async fn backfill_message_buckets(&self) -> Result<usize, ContractError> {
+        let mut copied = 0;
+        for shop_id in self.list_shop_ids().await? {
+            for guest in self.get_guests(&shop_id).await? {
+                let url = format!("{}/messages/{}/{}?page-size=500", self.base_url, shop_id, guest.guest_id as i64);
+                let mut page_state: Option<String> = None;
+                loop {
+                    let body = self.get_json(&with_page_state(&url, page_state.as_deref())).await?;
+                    for row in body["data"].as_array().into_iter().flatten() {
+                        self.insert_message(&message_from_row(row)?).await?;
+                        copied += 1;
+                    }
+                    page_state = body["pageState"].as_str().filter(|ps| !ps.is_empty()).map(String::from);
+                    if page_state.is_none() {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(copied)
+    }
+
+    /// Bỏ các bucket ngày cũ hơn `before_bucket` của guest (retention đã xoá hết tin trong đó)
+    #[tracing::instrument(name = "db.drop_buckets_before", skip_all, fields(shop_id = %shop_id))]
+    pub async fn drop_buckets_before(&self, shop_id: &str, guest_id: u64, before_bucket: i32) -> Result<(), ContractError> {
+        for bucket in self.message_buckets(shop_id, guest_id, i32::MIN, before_bucket - 1).await? {
+            self.delete_row(&format!("messages_by_day/{}/{}/{}", shop_id, guest_id as i64, bucket)).await?;
+            self.delete_row(&format!("message_buckets/{}/{}/{}", shop_id, guest_id as i64, bucket)).await?;
+            self.known_buckets.lock().unwrap().remove(&(shop_id.to_string(), guest_id, bucket));
+        }
+        Ok(())
+    }
+
+    /// Tin cuối (guests.last_message_* + conversation_feed) đã bị xoá → lấy lại từ tin còn lại, hết tin thì xoá trống
+    #[tracing::instrument(name = "db.refresh_last_message", skip_all, fields(shop_id = %shop_id))]
+    pub async fn refresh_last_message(&self, shop_id: &str, guest_id: u64) -> Result<(), ContractError> {
+        if let Some(last) = self.latest_message(shop_id, guest_id).await? {
+            self.set_guest_last_message(&last).await?;
+            return self.set_feed_message(&last).await;
+        }
+        let payload = json!({ "last_message_preview": "", "last_message_at": 0, "last_sender": "" });
+        self.patch_row(&format!("guests/{}/{}", shop_id, guest_id as i64), &payload).await?;
+        self.delete_row(&format!("conversation_feed/{}/{}", shop_id, guest_id as i64)).await
+    }
+
+    #[tracing::instrument(name = "db.delete_message", skip_all, fields(shop_id = %shop_id))]
+    pub async fn delete_message(&self, shop_id: &str, guest_id: u64, message_id: u64) -> Result<(), ContractError> {
+        self.delete_row(&message_path(shop_id, guest_id, message_id)).await?;
+        // Lịch sử sửa đi cùng tin
+        self.delete_row(&format!("message_edits/{}/{}/{}", shop_id, guest_id as i64, message_id as i64)).await
+    }
+
+    /// Lưu nội dung cũ trước khi sửa (`original` = tin trước lần sửa)
+    #[tracing::instrument(name = "db.insert_message_revision", skip_all)]
+    pub async fn insert_message_revision(&self, original: &Message, editor: &str, replaced_at_us: u64) -> Result<(), ContractError> {
+        let url = format!("{}/message_edits", self.base_url);
+        let payload = json!({
+            "shop_id": original.shop_id,
+            "guest_id": original.guest_id as i64,
+            "message_id": original.message_id as i64,
+            "replaced_at": replaced_at_us as i64,
+            "content": BASE64.encode(&original.content),
+            "content_crc": original.content_crc as i32,
+            "editor": editor
+        });
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(db_error("Insert revision"))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "db.message_history", skip_all, fields(shop_id = %shop_id))]
+    pub async fn message_history(&self, shop_id: &str, guest_id: u64, message_id: u64) -> Result<Vec<MessageRevision>, ContractError> {
+        let url = format!(
+            "{}/message_edits/{}/{}/{}?page-size=200",
+            self.base_url, shop_id, guest_id as i64, message_id as i64
+        );
+        let body = self.get_json(&url).await?;
+
+        let mut revisions = Vec::new();
+        for row in body["data"].as_array().into_iter().flatten() {
+            let content = BASE64.decode(row["content"].as_str().unwrap_or(""))
+                .map_err(|e| ContractError::DbError(format!("Base64 decode failed: {}", e)))?;
+            revisions.push(MessageRevision {
+                content: Bytes::from(content),
+                content_crc: row["content_crc"].as_i64().unwrap_or(0) as u32,
+                editor: row["editor"].as_str().unwrap_or("").to_string(),
+                replaced_at_us: row["replaced_at"].as_i64().unwrap_or(0) as u64,
+            });
+        }
+        Ok(revisions)
+    }
+
+    /// Chép tin nhắn sang bảng messages_archive (cùng schema với messages)
+    #[tracing::instrument(name = "db.archive_message", skip_all)]
+    pub async fn archive_message(&self, msg: &Message) -> Result<(), ContractError> {
+        let url = format!("{}/messages_archive", self.base_url);
+
+        self.client
+            .post(&url)
+            .header("X-Cassandra-Token", &self.token)
+            .header("Content-Type", "application/json")
+            .json(&message_payload(msg))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(db_error("Archive message"))?;
+
+        Ok(())
+    }
+
+    /// Tin của guest đã chuyển sang messages_archive
+    #[tracing::instrument(name = "db.fetch_archived_messages", skip_all, fields(shop_id = %shop_id))]
+    pub async fn fetch_archived_messages(&self, shop_id: &str, guest_id: u64) -> Result<Vec<Message>, ContractError> {
+        let url = format!("{}/messages_archive/{}/{}?page-size=500", self.base_url, shop_id, guest_id as i64);
+        let mut messages = Vec::new();
+        let mut page_state: Option<String> = None;
+        loop {
+            let body = self.get_json(&with_page_state(&url, page_state.as_deref())).await?;
+            for row in body["data"].as_array().into_iter().flatten() {
+                messages.push(message_from_row(row)?);
+            }
+            page_state = body["pageState"].as_str().filter(|ps| !ps.is_empty()).map(String::from);
+            if page_state.is_none() {
+                break;
+            }
+        }
+        Ok(messages)
+    }
+
+    /// Lấy toàn bộ lịch sử của guest
+    #[tracing::instrument(name = "db.fetch_all_messages", skip_all, fields(shop_id = %shop_id))]
+    pub async fn fetch_all_messages(&self, shop_id: &str, guest_id: u64) -> Result<Vec<Message>, ContractError> {
+        self.fetch_messages_since(shop_id, guest_id, 0, i64::MAX as u64).await
+    }
+
+    /// Lấy mọi tin nhắn sau `after_id`, đi theo message_id từng trang
+    #[tracing::instrument(name = "db.fetch_messages_since", skip_all, fields(shop_id = %shop_id))]
+    pub async fn fetch_messages_since(
+        &self,
+        shop_id: &str,
+        guest_id: u64,
+        after_id: u64,
+        before_id: u64,
+    ) -> Result<Vec<Message>, ContractError> {
+        const PAGE: u32 = 500;
+        let mut all = Vec::new();
+        let mut after_id = after_id;
+
+        self.bounded("Fetch message history", async {
+            loop {
+                let page = self.fetch_messages_between(shop_id, guest_id, after_id, before_id, PAGE).await?;
+                let done = page.len() < PAGE as usize;
+                if let Some(last) = page.last() {
+                    after_id = last.message_id;
+                }
+                all.extend(page);
+                if done {
+                    break;
+                }
+            }
+            Ok(())
+        }).await?;
+
+        Ok(all)
+    }
+}
+
+/// Bucket ngày (UTC, số ngày từ 1970) của tin - suy ra từ message_id nên tra theo id không cần đọc thêm
+pub fn day_bucket(message_id: u64) -> i32 {
+    (MessageId(message_id).timestamp() / MS_PER_DAY) as i32
+}
+
+// Bucket nhỏ nhất có thể chứa tin có id > after_id.
+// LEGACY_MAX - 1 là mốc "mọi Snowflake id" (không phải id thật) → bắt đầu từ Snowflake id đầu tiên
+fn first_bucket_after(after_id: u64) -> i32 {
+    if after_id >= MessageId::LEGACY_MAX - 1 {
+        day_bucket(after_id.max(MessageId::LEGACY_MAX))
+    } else {
+        day_bucket(after_id)
+    }
+}
+
+fn message_path(shop_id: &str, guest_id: u64, message_id: u64) -> String {
+    format!("messages_by_day/{}/{}/{}/{}", shop_id, guest_id as i64, day_bucket(message_id), message_id as i64)
+}
+
+// pageState của Astra là base64 (có + / =) → phải encode khi đưa vào URL
+fn with_page_state(url: &str, page_state: Option<&str>) -> String {
+    let Some(ps) = page_state else { return url.to_string() };
+    match reqwest::Url::parse(url) {
+        Ok(mut parsed) => {
+            parsed.query_pairs_mut().append_pair("page-state", ps);
+            parsed.to_string()
+        }
+        Err(_) => url.to_string(),
+    }
+}
+
+fn agent_from_row(row: &serde_json::Value) -> AgentAccount {
+    let text = |column: &str| row[column].as_str().unwrap_or("").to_string();
+    AgentAccount {
+        agent_id: text("agent_id"),
+        email: text("email"),
+        name: text("name"),
+        password_hash: text("password_hash"),
+        session_hash: text("session_hash"),
+        created_at: row["created_at"].as_i64().unwrap_or(0) as u64,
+    }
+}
+
+fn api_key_from_row(row: &serde_json::Value) -> ApiKey {
+    ApiKey {
+        key_id: row["key_id"].as_str().unwrap_or("").to_string(),
+        name: row["name"].as_str().unwrap_or("").to_string(),
+        scopes: row["scopes"].as_array()
+            .map(|scopes| scopes.iter().filter_map(|s| s.as_str().map(String::from)).collect())
+            .unwrap_or_default(),
+        created_at: row["created_at"].as_i64().unwrap_or(0) as u64,
+        rotated_at: row["rotated_at"].as_i64().unwrap_or(0) as u64,
+        revoked_at: row["revoked_at"].as_i64().unwrap_or(0) as u64,
+    }
+}
+
+// Con trỏ trang guest: "<hoạt động µs>:<guest_id>" của dòng cuối trang trước
+fn parse_guest_cursor(cursor: &str) -> Option<(u64, u64)> {
+    let (activity, guest_id) = cursor.split_once(':')?;
+    Some((activity.parse().ok()?, guest_id.parse().ok()?))
+}
+
+// Lỗi reqwest → Timeout khi quá hạn (để nơi gọi phân biệt), còn lại DbError như cũ
+fn db_error(context: &'static str) -> impl Fn(reqwest::Error) -> ContractError {
+    move |e| {
+        if e.is_timeout() {
+            ContractError::Timeout(context.to_string())
+        } else {
+            ContractError::DbError(format!("{} failed: {}", context, e))
+        }
+    }
+}
+
+/// Thời điểm hiện tại (micro giây từ epoch) - dùng chung cho mọi cột thời gian
+pub fn now_us() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_micros() as u64
+}
+
+fn message_payload(msg: &Message) -> serde_json::Value {
+    // Base64 mới (không deprecated)
+    json!({
+        "shop_id": msg.shop_id,
+        "guest_id": msg.guest_id as i64,
+        "message_id": msg.message_id as i64,
+        "sender_type": msg.sender_type,
+        "content": BASE64.encode(&msg.content),
+        "timestamp_us": msg.timestamp_us as i64,
+        "content_crc": msg.content_crc as i32,
+        "edited_at": msg.edited_at_us as i64,
+        "deleted_at": msg.deleted_at_us as i64,
+        "deleted_by": msg.deleted_by,
+        "reply_to": msg.reply_to_message_id.map(|id| id as i64),
+        "quick_replies": quick_replies_json(&msg.quick_replies),
+        "attachments": attachments_json(&msg.attachments)
+    })
+}
+
+// Lựa chọn nhanh lưu dạng JSON text (null khi không có)
+fn quick_replies_json(options: &[QuickReply]) -> Option<String> {
+    if options.is_empty() {
+        return None;
+    }
+    let list: Vec<_> = options.iter().map(|q| json!({ "label": q.label, "payload": q.payload })).collect();
+    Some(serde_json::Value::from(list).to_string())
+}
+
+fn quick_replies_from_row(row: &serde_json::Value) -> Vec<QuickReply> {
+    row["quick_replies"].as_str()
+        .and_then(|raw| serde_json::from_str::<Vec<serde_json::Value>>(raw).ok())
+        .map(|list| list.iter().map(|q| QuickReply {
+            label: q["label"].as_str().unwrap_or("").to_string(),
+            payload: q["payload"].as_str().unwrap_or("").to_string(),
+        }).collect())
+        .unwrap_or_default()
+}
+
+// File đính kèm lưu dạng JSON text (không lưu url - link có hạn, tạo lại mỗi lần trả)
+fn attachments_json(attachments: &[Attachment]) -> Option<String> {
+    if attachments.is_empty() {
+        return None;
+    }
+    let list: Vec<_> = attachments.iter().map(|a| json!({
+        "key": a.key,
+        "content_type": a.content_type,
+        "size": a.size,
+        "file_name": a.file_name,
+        "scan": a.scan.as_ref().map(|scan| json!({
+            "status": scan.status().as_db_str(),
+            "engine": scan.engine,
+            "scanned_at": scan.scanned_at_us,
+        })),
+    })).collect();
+    Some(serde_json::Value::from(list).to_string())
+}
+
+fn attachments_from_row(row: &serde_json::Value) -> Vec<Attachment> {
+    row["attachments"].as_str()
+        .and_then(|raw| serde_json::from_str::<Vec<serde_json::Value>>(raw).ok())
+        .map(|list| list.iter().map(|a| Attachment {
+            key: a["key"].as_str().unwrap_or("").to_string(),
+            content_type: a["content_type"].as_str().unwrap_or("").to_string(),
+            size: a["size"].as_u64().unwrap_or(0),
+            file_name: a["file_name"].as_str().unwrap_or("").to_string(),
+            scan: a["scan"].as_object().map(|scan| ScanResult {
+                status: ScanStatus::from_db_str(scan.get("status").and_then(|v| v.as_str()).unwrap_or("")) as i32,
+                engine: scan.get("engine").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                signature: String::new(),
+                scanned_at_us: scan.get("scanned_at").and_then(|v| v.as_u64()).unwrap_or(0),
+            }),
+            ..Default::default()
+        }).collect())
+        .unwrap_or_default()
+}
+
+fn guest_from_row(row: &serde_json::Value) -> Guest {
+    Guest {
+        shop_id: row["shop_id"].as_str().unwrap_or("").to_string(),
+        guest_id: row["guest_id"].as_i64().unwrap_or(0) as u64,
+        guest_name: row["guest_name"].as_str().unwrap_or("").to_string(),
+        created_at: row["created_at"].as_i64().unwrap_or(0) as u64,
+        last_seen: row["last_seen"].as_i64().unwrap_or(0) as u64,
+        last_ip: row["last_ip"].as_str().unwrap_or("").to_string(),
+        email: row["email"].as_str().unwrap_or("").to_string(),
+        status: ConversationStatus::from_db_str(row["status"].as_str().unwrap_or("")) as i32,
+        status_changed_at: row["status_changed_at"].as_i64().unwrap_or(0) as u64,
+        context: row["page_url"].as_str().filter(|url| !url.is_empty()).map(|url| VisitorContext {
+            page_url: url.to_string(),
+            referrer: row["referrer"].as_str().unwrap_or("").to_string(),
+            user_agent: row["user_agent"].as_str().unwrap_or("").to_string(),
+            viewport_width: row["viewport_width"].as_u64().unwrap_or(0) as u32,
+            viewport_height: row["viewport_height"].as_u64().unwrap_or(0) as u32,
+            updated_at_us: row["context_at"].as_i64().unwrap_or(0) as u64,
+        }),
+        geo: row["country_code"].as_str().filter(|code| !code.is_empty()).map(|code| GeoLocation {
+            country_code: code.to_string(),
+            country: row["country"].as_str().unwrap_or("").to_string(),
+            city: row["city"].as_str().unwrap_or("").to_string(),
+        }),
+        tags: row["tags"].as_array()
+            .map(|tags| tags.iter().filter_map(|t| t.as_str().map(String::from)).collect())
+            .unwrap_or_default(),
+        assigned_to: row["assigned_to"].as_str().unwrap_or("").to_string(),
+        awaiting_since: row["awaiting_since"].as_i64().unwrap_or(0) as u64,
+        first_response_ms: row["first_response_ms"].as_i64().unwrap_or(0) as u64,
+        summary: row["summary"].as_str().filter(|s| !s.is_empty()).map(|text| ConversationSummary {
+            text: text.to_string(),
+            suggested_tags: row["summary_tags"].as_array()
+                .map(|tags| tags.iter().filter_map(|t| t.as_str().map(String::from)).collect())
+                .unwrap_or_default(),
+            generated_at: row["summary_at"].as_i64().unwrap_or(0) as u64,
+        }),
+        sentiment: row["sentiment"].as_f64().unwrap_or(0.0) as f32,
+        sentiment_at: row["sentiment_at"].as_i64().unwrap_or(0) as u64,
+        last_message_preview: row["last_message_preview"].as_str().unwrap_or("").to_string(),
+        last_message_at: row["last_message_at"].as_i64().unwrap_or(0) as u64,
+        last_sender: row["last_sender"].as_str().unwrap_or("").to_string(),
+        user_id: row["user_id"].as_str().unwrap_or("").to_string(),
+        merged_into: row["merged_into"].as_i64().unwrap_or(0) as u64,
+        needs_human: row["needs_human"].as_bool().unwrap_or(false),
+    }
+}
+
+fn campaign_from_row(row: &serde_json::Value) -> Result<Campaign, ContractError> {
+    let bytes = BASE64.decode(row["campaign"].as_str().unwrap_or(""))
+        .map_err(|e| ContractError::DbError(format!("Base64 decode failed: {}", e)))?;
+    Ok(Campaign::decode(&bytes[..])?)
+}
+
+fn message_from_row(row: &serde_json::Value) -> Result<Message, ContractError> {
+    // Base64 decode mới
+    let content_b64 = row["content"].as_str().unwrap_or("");
+    let content_bytes = BASE64.decode(content_b64)
+        .map_err(|e| ContractError::DbError(format!("Base64 decode failed: {}", e)))?;
+
+    Ok(Message {
+        shop_id: row["shop_id"].as_str().unwrap_or("").to_string(),
+        guest_id: row["guest_id"].as_i64().unwrap_or(0) as u64,
+        message_id: row["message_id"].as_i64().unwrap_or(0) as u64,
+        sender_type: row["sender_type"].as_str().unwrap_or("").to_string(),
+        content: Bytes::from(content_bytes),
+        timestamp_us: row["timestamp_us"].as_i64().unwrap_or(0) as u64,
+        content_crc: row["content_crc"].as_i64().unwrap_or(0) as u32,
+        edited_at_us: row["edited_at"].as_i64().unwrap_or(0) as u64,
+        deleted_at_us: row["deleted_at"].as_i64().unwrap_or(0) as u64,
+        deleted_by: row["deleted_by"].as_str().unwrap_or("").to_string(),
+        reply_to_message_id: row["reply_to"].as_i64().map(|id| id as u64),
+        reply_preview: None,
+        quick_replies: quick_replies_from_row(row),
+        attachments: attachments_from_row(row),
+    })
 }
\ No newline at end of file
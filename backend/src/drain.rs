@@ -0,0 +1,96 @@
+// ============================================================================
+// XẢ KẾT NỐI KHI DEPLOY - POST /ops/drain (OPS_TOKEN) hoặc SIGTERM / Ctrl+C
+// 1. Ngừng nhận WS mới (503) + /healthz trả 503 → load balancer chuyển khách sang node mới
+// 2. Đóng mọi WS với CLOSE_SERVER_DRAINING → client kết nối lại ngay (khách nối lại phiên, không mất tin)
+// 3. Chờ kết nối đóng hết (tối đa DRAIN_TIMEOUT_SECS, mặc định 30) - tin đang xử lý được ghi xong
+// 4. Dừng HTTP, ghi nốt batch insert (main.rs) rồi thoát
+// ============================================================================
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+use crate::auth::OpsAuth;
+use crate::state::AppState;
+use crate::websocket::WebSocketState;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Cờ "đang xả" của node - kết nối WS theo dõi để tự đóng
+pub struct Drain {
+    tx: watch::Sender<bool>,
+}
+
+impl Drain {
+    pub fn new() -> Self {
+        Self { tx: watch::Sender::new(false) }
+    }
+
+    pub fn is_draining(&self) -> bool {
+        *self.tx.borrow()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.tx.subscribe()
+    }
+
+    /// Bắt đầu xả; false = đã đang xả
+    pub fn start(&self) -> bool {
+        self.tx.send_if_modified(|draining| !std::mem::replace(draining, true))
+    }
+
+    /// Chờ tới khi có lệnh xả (qua /ops/drain)
+    pub async fn requested(&self) {
+        let mut rx = self.subscribe();
+        let _ = rx.wait_for(|draining| *draining).await;
+    }
+}
+
+impl Default for Drain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Xả node: đóng mọi WS rồi chờ chúng ngắt hẳn (hoặc hết thời gian chờ)
+pub async fn run(state: &WebSocketState) {
+    state.drain.start();
+    let timeout_secs = std::env::var("DRAIN_TIMEOUT_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(30u64);
+    println!("🚰 Draining {} connections (up to {}s)...", state.open_connections(), timeout_secs);
+
+    let closed = tokio::time::timeout(Duration::from_secs(timeout_secs), async {
+        while state.open_connections() > 0 {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }).await;
+    match closed {
+        Ok(()) => println!("✅ All connections drained"),
+        Err(_) => println!("⚠️ Drain timed out with {} connections still open", state.open_connections()),
+    }
+}
+
+#[derive(Serialize)]
+pub struct DrainStatus {
+    pub draining: bool,
+    pub connections: usize,
+}
+
+// POST /ops/drain - trả ngay, xả chạy nền rồi node tự thoát
+pub async fn drain_handler(State(state): State<Arc<AppState>>, _ops: OpsAuth) -> impl IntoResponse {
+    if state.ws_state.drain.start() {
+        println!("🚰 Drain requested by operator");
+    }
+    let status = DrainStatus { draining: true, connections: state.ws_state.open_connections() };
+    (StatusCode::ACCEPTED, Json(status))
+}
+
+// GET /healthz - load balancer ngừng gửi request mới khi node đang xả
+pub async fn health_handler(State(state): State<Arc<WebSocketState>>) -> StatusCode {
+    if state.drain.is_draining() {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    }
+}
@@ -0,0 +1,242 @@
+// Sửa / xoá tin nhắn - dùng chung cho frame WS (guest) và endpoint REST (admin)
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use prost::Message as ProstMessage;
+use std::sync::Arc;
+
+use crate::auth::AdminAuth;
+use crate::contract::{ContractError, DeleteMessage, EditMessage, Guest, Message, MessageHistory, ReplyPreview, WsEnvelope};
+use crate::db::now_us;
+use crate::filter::Outcome;
+use crate::state::AppState;
+use crate::websocket::{publish_envelope, WebSocketState};
+
+/// Người thực hiện sửa/xoá
+pub enum Editor {
+    Guest(u64),
+    Admin,
+}
+
+impl Editor {
+    /// Ghi vào deleted_by
+    pub fn label(&self) -> &'static str {
+        match self {
+            Editor::Guest(_) => "guest",
+            Editor::Admin => "admin",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum EditError {
+    Invalid,
+    NotFound,
+    Forbidden,
+    Rejected(String),
+    Db(ContractError),
+}
+
+impl From<ContractError> for EditError {
+    fn from(e: ContractError) -> Self {
+        EditError::Db(e)
+    }
+}
+
+impl std::fmt::Display for EditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EditError::Invalid => write!(f, "invalid content"),
+            EditError::NotFound => write!(f, "message not found"),
+            EditError::Forbidden => write!(f, "not allowed"),
+            EditError::Rejected(reason) => write!(f, "rejected by filter ({})", reason),
+            EditError::Db(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl EditError {
+    fn status(&self) -> StatusCode {
+        match self {
+            EditError::Invalid => StatusCode::BAD_REQUEST,
+            EditError::NotFound => StatusCode::NOT_FOUND,
+            EditError::Forbidden => StatusCode::FORBIDDEN,
+            EditError::Rejected(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            EditError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+// Guest chỉ sửa/xoá tin của mình; admin sửa tin admin, xoá được mọi tin (kiểm duyệt)
+fn can_modify(editor: &Editor, msg: &Message, deleting: bool) -> bool {
+    match editor {
+        Editor::Guest(gid) => msg.sender_type == "guest" && msg.guest_id == *gid,
+        Editor::Admin => deleting || msg.sender_type == "admin",
+    }
+}
+
+async fn load_live(state: &WebSocketState, shop_id: &str, guest_id: u64, message_id: u64) -> Result<Message, EditError> {
+    match state.repo.get_message(shop_id, guest_id, message_id).await? {
+        Some(msg) if msg.deleted_at_us == 0 => Ok(msg),
+        _ => Err(EditError::NotFound),
+    }
+}
+
+pub async fn apply_edit(state: &WebSocketState, editor: Editor, mut edit: EditMessage) -> Result<EditMessage, EditError> {
+    if edit.content.is_empty() || edit.verify_content().is_err() {
+        return Err(EditError::Invalid);
+    }
+    let original = load_live(state, &edit.shop_id, edit.guest_id, edit.message_id).await?;
+    if !can_modify(&editor, &original, false) {
+        return Err(EditError::Forbidden);
+    }
+
+    // Nội dung mới của guest cũng phải qua bộ lọc spam
+    let mut edited = Message { content: edit.content.clone(), ..original.clone() };
+    let mut flags = Vec::new();
+    if let Editor::Guest(_) = editor {
+        let settings = state.settings.get(&edit.shop_id).await;
+        match state.filters.run(&settings, &mut edited) {
+            Outcome::Accept { flags: f } => flags = f,
+            Outcome::Reject(reason) => return Err(EditError::Rejected(reason)),
+        }
+    }
+    edit.content = edited.content.clone();
+    edit.content_crc = crc32c::crc32c(&edit.content);
+    edit.edited_at_us = now_us();
+    edited.content_crc = edit.content_crc;
+    edited.edited_at_us = edit.edited_at_us;
+
+    // Giữ bản cũ trước - không lưu được lịch sử thì không sửa
+    state.repo.insert_message_revision(&original, editor.label(), edit.edited_at_us).await?;
+    state.repo.edit_message(
+        &edit.shop_id, edit.guest_id, edit.message_id,
+        &edit.content, edit.content_crc, edit.edited_at_us,
+    ).await?;
+    println!("✏️ Edited message {} of guest {}", edit.message_id, edit.guest_id);
+
+    if !flags.is_empty() {
+        if let Err(e) = state.repo.insert_flagged(&edited, &flags.join("; ")).await {
+            eprintln!("❌ Flag insert failed: {:?}", e);
+        }
+    }
+
+    if let Err(e) = publish_envelope(state, &WsEnvelope::edit(edit.clone())).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+    refresh_last_message(state, &edited).await;
+    Ok(edit)
+}
+
+pub async fn apply_delete(state: &WebSocketState, editor: Editor, mut delete: DeleteMessage) -> Result<DeleteMessage, EditError> {
+    let original = load_live(state, &delete.shop_id, delete.guest_id, delete.message_id).await?;
+    if !can_modify(&editor, &original, true) {
+        return Err(EditError::Forbidden);
+    }
+
+    delete.deleted_at_us = now_us();
+    delete.deleted_by = editor.label().to_string();
+    state.repo.soft_delete_message(&delete.shop_id, delete.guest_id, delete.message_id, delete.deleted_at_us, &delete.deleted_by).await?;
+    println!("🗑️ Deleted message {} of guest {}", delete.message_id, delete.guest_id);
+
+    if let Err(e) = publish_envelope(state, &WsEnvelope::delete(delete.clone())).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+    refresh_last_message(state, &Message { deleted_at_us: delete.deleted_at_us, deleted_by: delete.deleted_by.clone(), ..original }).await;
+    Ok(delete)
+}
+
+// Tin vừa sửa/xoá là tin cuối của hội thoại → cập nhật trích đoạn trên guest, feed + báo admin
+async fn refresh_last_message(state: &WebSocketState, msg: &Message) {
+    let guest = match state.repo.get_guest(&msg.shop_id, msg.guest_id).await {
+        Ok(Some(guest)) if guest.last_message_at == msg.timestamp_us => guest,
+        Ok(_) => return,
+        Err(e) => {
+            eprintln!("❌ Load guest {} failed: {:?}", msg.guest_id, e);
+            return;
+        }
+    };
+    if let Err(e) = state.repo.set_feed_message(msg).await {
+        eprintln!("❌ Update feed for guest {} failed: {:?}", msg.guest_id, e);
+    }
+    if let Err(e) = state.repo.set_guest_last_message(msg).await {
+        eprintln!("❌ Update last message of guest {} failed: {:?}", msg.guest_id, e);
+        return;
+    }
+    let guest = Guest { last_message_preview: ReplyPreview::from_message(msg).snippet, ..guest };
+    if let Err(e) = publish_envelope(state, &WsEnvelope::guest(guest)).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+}
+
+// PATCH /messages/:guest_id/:message_id - Admin sửa tin (body: EditMessage)
+pub async fn edit_message_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path((guest_id, message_id)): Path<(u64, u64)>,
+    body: Bytes,
+) -> impl IntoResponse {
+    let Ok(mut edit) = EditMessage::decode(&body[..]) else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+    edit.shop_id = admin.shop_id;
+    edit.guest_id = guest_id;
+    edit.message_id = message_id;
+
+    match apply_edit(&state.ws_state, Editor::Admin, edit).await {
+        Ok(edit) => (StatusCode::OK, Bytes::from(edit.encode_to_vec())),
+        Err(e) => {
+            eprintln!("❌ Edit message {} failed: {}", message_id, e);
+            (e.status(), Bytes::new())
+        }
+    }
+}
+
+// GET /messages/:guest_id/:message_id/history - Các bản trước của tin đã sửa
+pub async fn history_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path((guest_id, message_id)): Path<(u64, u64)>,
+) -> impl IntoResponse {
+    match state.repo.message_history(&admin.shop_id, guest_id, message_id).await {
+        Ok(revisions) => {
+            let history = MessageHistory { message_id, revisions };
+            (StatusCode::OK, Bytes::from(history.encode_to_vec()))
+        }
+        Err(e) => {
+            eprintln!("❌ Load history of message {} failed: {:?}", message_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new())
+        }
+    }
+}
+
+// DELETE /messages/:guest_id/:message_id - Admin xoá (kiểm duyệt) tin
+pub async fn delete_message_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path((guest_id, message_id)): Path<(u64, u64)>,
+) -> impl IntoResponse {
+    let delete = DeleteMessage {
+        shop_id: admin.shop_id.clone(),
+        guest_id,
+        message_id,
+        ..Default::default()
+    };
+
+    match apply_delete(&state.ws_state, Editor::Admin, delete).await {
+        Ok(_) => {
+            let detail = format!("message {}", message_id);
+            if let Err(e) = state.repo.insert_audit(&admin.shop_id, "message.delete", guest_id, &admin.agent_id, &detail).await {
+                eprintln!("❌ Audit insert failed: {:?}", e);
+            }
+            StatusCode::NO_CONTENT
+        }
+        Err(e) => {
+            eprintln!("❌ Delete message {} failed: {}", message_id, e);
+            e.status()
+        }
+    }
+}
@@ -0,0 +1,47 @@
+// Tin cuối của mọi hội thoại trong shop (bảng conversation_feed) - một lần gọi cho màn "tất cả hội thoại"
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use prost::Message as ProstMessage;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::attachments;
+use crate::auth::AdminAuth;
+use crate::contract::{ContractError, FeedResponse};
+use crate::state::AppState;
+
+#[derive(Deserialize)]
+pub struct FeedQuery {
+    #[serde(default)]
+    pub limit: u32,
+    // message_id của tin cuối trang trước (next_before)
+    #[serde(default)]
+    pub before: u64,
+}
+
+// GET /feed?limit=&before=
+pub async fn feed_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Query(query): Query<FeedQuery>,
+) -> impl IntoResponse {
+    let (mut messages, next_before) = match state.repo.feed(&admin.shop_id, query.limit, query.before).await {
+        Ok(page) => page,
+        Err(e) => {
+            eprintln!("❌ Load feed for {} failed: {:?}", admin.shop_id, e);
+            let status = match e {
+                ContractError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            return (status, Bytes::new());
+        }
+    };
+    attachments::sign_urls(state.ws_state.blobs.as_ref(), &mut messages);
+
+    let resp = FeedResponse { messages, next_before: next_before.unwrap_or(0) };
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+}
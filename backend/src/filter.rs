@@ -0,0 +1,249 @@
+// Bộ lọc spam / từ cấm trên đường ingest - chuỗi filter có thể cắm thêm
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use prost::Message as ProstMessage;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::auth::AdminAuth;
+use crate::contract::{FilterAction, FlaggedListResponse, Message as ChatMessage, ShopSettings};
+use crate::request_id;
+use crate::state::AppState;
+
+// (shop_id, guest_id)
+type GuestKey = (String, u64);
+
+/// Kết quả của một filter
+pub enum Verdict {
+    Pass,
+    /// Thay nội dung tin (vd. che từ cấm) rồi chạy tiếp
+    Mask(String),
+    /// Vẫn gửi nhưng đưa vào danh sách cần duyệt
+    Flag(String),
+    /// Bỏ tin
+    Reject(String),
+}
+
+pub trait MessageFilter: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn check(&self, settings: &ShopSettings, msg: &ChatMessage) -> Verdict;
+}
+
+/// Kết quả cuối cùng của cả chuỗi
+pub enum Outcome {
+    Accept { flags: Vec<String> },
+    Reject(String),
+}
+
+pub struct FilterChain {
+    filters: Vec<Box<dyn MessageFilter>>,
+}
+
+impl Default for FilterChain {
+    fn default() -> Self {
+        Self::new(vec![
+            Box::new(WordListFilter),
+            Box::new(LinkThrottleFilter::default()),
+            Box::new(FloodFilter::default()),
+        ])
+    }
+}
+
+impl FilterChain {
+    pub fn new(filters: Vec<Box<dyn MessageFilter>>) -> Self {
+        Self { filters }
+    }
+
+    /// Chạy lần lượt các filter; Mask sửa `msg` tại chỗ (kèm CRC mới)
+    pub fn run(&self, settings: &ShopSettings, msg: &mut ChatMessage) -> Outcome {
+        let mut flags = Vec::new();
+        for filter in &self.filters {
+            match filter.check(settings, msg) {
+                Verdict::Pass => {}
+                Verdict::Mask(text) => {
+                    msg.content = Bytes::from(text);
+                    msg.content_crc = crc32c::crc32c(&msg.content);
+                }
+                Verdict::Flag(reason) => flags.push(format!("{}: {}", filter.name(), reason)),
+                Verdict::Reject(reason) => return Outcome::Reject(format!("{}: {}", filter.name(), reason)),
+            }
+        }
+        Outcome::Accept { flags }
+    }
+}
+
+// ============================================================================
+// WORD LIST - từ cấm theo shop
+// ============================================================================
+pub struct WordListFilter;
+
+impl MessageFilter for WordListFilter {
+    fn name(&self) -> &'static str {
+        "banned_words"
+    }
+
+    fn check(&self, settings: &ShopSettings, msg: &ChatMessage) -> Verdict {
+        if settings.banned_words.is_empty() {
+            return Verdict::Pass;
+        }
+        let text = String::from_utf8_lossy(&msg.content);
+        let (masked, hits) = mask_words(&text, &settings.banned_words);
+        if hits == 0 {
+            return Verdict::Pass;
+        }
+
+        match settings.banned_words_action() {
+            FilterAction::Mask => Verdict::Mask(masked),
+            FilterAction::Reject => Verdict::Reject(format!("{} banned word(s)", hits)),
+            FilterAction::Flag => Verdict::Flag(format!("{} banned word(s)", hits)),
+        }
+    }
+}
+
+/// Che các từ cấm (không phân biệt hoa thường) bằng '*', trả về số lần trúng
+fn mask_words(text: &str, words: &[String]) -> (String, usize) {
+    let mut chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+    let mut hits = 0;
+
+    for word in words {
+        let needle: Vec<char> = word.trim().chars().flat_map(|c| c.to_lowercase()).collect();
+        if needle.is_empty() || needle.len() > lower.len() {
+            continue;
+        }
+        let mut i = 0;
+        while i + needle.len() <= lower.len() {
+            if lower[i..i + needle.len()] == needle[..] {
+                chars[i..i + needle.len()].fill('*');
+                hits += 1;
+                i += needle.len();
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    (chars.into_iter().collect(), hits)
+}
+
+// ============================================================================
+// LINK THROTTLE - giới hạn số link mỗi phút cho từng guest
+// ============================================================================
+#[derive(Default)]
+pub struct LinkThrottleFilter {
+    // Thời điểm gửi các link gần đây của từng guest
+    recent: Mutex<HashMap<GuestKey, VecDeque<Instant>>>,
+}
+
+impl MessageFilter for LinkThrottleFilter {
+    fn name(&self) -> &'static str {
+        "link_throttle"
+    }
+
+    fn check(&self, settings: &ShopSettings, msg: &ChatMessage) -> Verdict {
+        if settings.max_links_per_minute == 0 {
+            return Verdict::Pass;
+        }
+        let links = count_links(&String::from_utf8_lossy(&msg.content));
+        if links == 0 {
+            return Verdict::Pass;
+        }
+
+        let now = Instant::now();
+        let mut recent = self.recent.lock().unwrap();
+        let window = recent.entry((msg.shop_id.clone(), msg.guest_id)).or_default();
+        while window.front().is_some_and(|t| now.duration_since(*t) > Duration::from_secs(60)) {
+            window.pop_front();
+        }
+
+        if window.len() + links > settings.max_links_per_minute as usize {
+            return Verdict::Reject(format!("more than {} links/minute", settings.max_links_per_minute));
+        }
+        window.extend(std::iter::repeat_n(now, links));
+        Verdict::Pass
+    }
+}
+
+fn count_links(text: &str) -> usize {
+    let lower = text.to_lowercase();
+    lower.matches("http://").count() + lower.matches("https://").count() + lower.matches("www.").count()
+}
+
+// ============================================================================
+// FLOOD - cùng một nội dung gửi lặp lại liên tục
+// ============================================================================
+struct FloodEntry {
+    crc: u32,
+    repeats: u32,
+    last_at: Instant,
+}
+
+#[derive(Default)]
+pub struct FloodFilter {
+    last: Mutex<HashMap<GuestKey, FloodEntry>>,
+}
+
+impl MessageFilter for FloodFilter {
+    fn name(&self) -> &'static str {
+        "flood"
+    }
+
+    fn check(&self, settings: &ShopSettings, msg: &ChatMessage) -> Verdict {
+        if settings.max_duplicate_messages == 0 {
+            return Verdict::Pass;
+        }
+
+        let crc = crc32c::crc32c(&msg.content);
+        let now = Instant::now();
+        let mut last = self.last.lock().unwrap();
+        let entry = last.entry((msg.shop_id.clone(), msg.guest_id))
+            .or_insert(FloodEntry { crc, repeats: 0, last_at: now });
+
+        if entry.crc == crc && now.duration_since(entry.last_at) < Duration::from_secs(60) {
+            entry.repeats += 1;
+        } else {
+            entry.crc = crc;
+            entry.repeats = 1;
+        }
+        entry.last_at = now;
+
+        if entry.repeats > settings.max_duplicate_messages {
+            Verdict::Reject(format!("duplicate message x{}", entry.repeats))
+        } else {
+            Verdict::Pass
+        }
+    }
+}
+
+// ============================================================================
+// REVIEW - admin duyệt tin bị đánh dấu
+// ============================================================================
+
+// GET /flagged - Tin đang chờ duyệt
+pub async fn list_flagged_handler(State(state): State<Arc<AppState>>, admin: AdminAuth) -> impl IntoResponse {
+    let resp = match state.repo.list_flagged(&admin.shop_id).await {
+        Ok(items) => FlaggedListResponse { success: true, items, ..Default::default() },
+        Err(e) => FlaggedListResponse { success: false, items: vec![], error: e.to_string(), request_id: request_id::current() },
+    };
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+}
+
+// DELETE /flagged/:message_id - Đã duyệt, bỏ khỏi danh sách
+pub async fn dismiss_flagged_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path(message_id): Path<u64>,
+) -> impl IntoResponse {
+    match state.repo.remove_flagged(&admin.shop_id, message_id).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(e) => {
+            eprintln!("❌ Dismiss flagged failed: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
@@ -0,0 +1,43 @@
+// Feature flag theo shop (cột shops.flags) - widget/admin nhận khi kết nối, đổi thì phát lại cho mọi kết nối
+use axum::{body::Bytes, extract::State, http::StatusCode, response::IntoResponse};
+use prost::Message as ProstMessage;
+use std::sync::Arc;
+
+use crate::auth::{AdminAuth, OwnerAuth};
+use crate::contract::{ShopFlags, WsEnvelope};
+use crate::state::AppState;
+use crate::websocket::publish_envelope;
+
+// GET /flags
+pub async fn get_flags_handler(State(state): State<Arc<AppState>>, admin: AdminAuth) -> impl IntoResponse {
+    match state.repo.get_flags(&admin.shop_id).await {
+        Ok(flags) => (StatusCode::OK, Bytes::from(flags.encode_to_vec())),
+        Err(e) => {
+            eprintln!("❌ Load flags for {} failed: {:?}", admin.shop_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new())
+        }
+    }
+}
+
+// PUT /flags - Thay toàn bộ flag của shop
+pub async fn put_flags_handler(State(state): State<Arc<AppState>>, OwnerAuth(admin): OwnerAuth, body: Bytes) -> impl IntoResponse {
+    let Ok(mut flags) = ShopFlags::decode(&body[..]) else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+    flags.voice_max_secs = flags.voice_max_secs.min(ShopFlags::MAX_VOICE_MAX_SECS);
+    if let Err(e) = state.repo.save_flags(&admin.shop_id, &flags).await {
+        eprintln!("❌ Save flags failed: {:?}", e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new());
+    }
+
+    let detail = flags.names().join(",");
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "flags.update", 0, &admin.agent_id, &detail).await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("🚩 Flags for shop {}: [{}]", admin.shop_id, detail);
+
+    if let Err(e) = publish_envelope(&state.ws_state, &WsEnvelope::flags(admin.shop_id.clone(), flags)).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+    (StatusCode::OK, Bytes::from(flags.encode_to_vec()))
+}
@@ -0,0 +1,285 @@
+// Kịch bản bot (cây quyết định): luật tự động start_flow → bot gửi từng bước, chờ khách chọn nút / nhập email.
+// Kịch bản kết thúc (hết bước / bước "handoff" / khách gõ "agent") → chuyển cho nhân viên (handoff.rs). Bước đang chờ lưu DB (bot_flow_state) - node nào nhận tin cũng chạy tiếp được
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::auth::{AdminAuth, OwnerAuth};
+use crate::contract::{BotFlow, BotFlows, FlowState, FlowStep, HandoffReason, Identify, Message as ChatMessage, QuickReply, ShopSettings};
+use crate::db::now_us;
+use crate::handoff;
+use crate::state::AppState;
+use crate::visitor;
+use crate::websocket::{send_bot_reply, WebSocketState};
+
+const MAX_FLOWS: usize = 20;
+const MAX_STEPS: usize = 50;
+const MAX_ID_CHARS: usize = 40;
+const MAX_TEXT_CHARS: usize = 1000;
+// Khách trả lời không khớp chừng này lần liên tiếp → chuyển cho nhân viên
+const MAX_MISSES: u32 = 2;
+// Khách bỏ dở lâu hơn → lần nhắn sau không còn trong kịch bản
+const FLOW_IDLE_US: u64 = 24 * 3600 * 1_000_000;
+
+const KIND_MESSAGE: &str = "message";
+const KIND_COLLECT_EMAIL: &str = "collect_email";
+const KIND_HANDOFF: &str = "handoff";
+
+/// Luật tự động chọn kịch bản → chạy từ bước đầu (hội thoại đang chạy kịch bản khác thì bỏ qua)
+pub async fn start(state: &WebSocketState, settings: &ShopSettings, shop_id: &str, guest_id: u64, flow_id: &str) {
+    let Some(flow) = settings.bot_flows.iter().find(|f| f.flow_id == flow_id) else {
+        println!("⚠️ Routing rule starts unknown flow {:?} for shop {}", flow_id, shop_id);
+        return;
+    };
+    let Some(first) = flow.steps.first() else { return };
+    println!("🤖 Guest {} starts flow {:?}", guest_id, flow_id);
+    enter(state, shop_id, guest_id, flow, &first.step_id).await;
+}
+
+/// Tin khách khi hội thoại đang chạy kịch bản → sang bước kế. `true` = tin đã được kịch bản xử lý
+pub async fn on_guest_message(state: &WebSocketState, settings: &ShopSettings, msg: &ChatMessage) -> bool {
+    if settings.bot_flows.is_empty() {
+        return false;
+    }
+    let Some(current) = load_state(state, &msg.shop_id, msg.guest_id).await else {
+        return false;
+    };
+    // Admin đã sửa / xoá kịch bản → dừng
+    let Some((flow, step)) = settings.bot_flows.iter()
+        .find(|f| f.flow_id == current.flow_id)
+        .and_then(|f| f.steps.iter().find(|s| s.step_id == current.step_id).map(|s| (f, s)))
+    else {
+        finish(state, &msg.shop_id, msg.guest_id).await;
+        return false;
+    };
+
+    if handoff::is_request(msg) {
+        end(state, &msg.shop_id, msg.guest_id, HandoffReason::GuestRequest, "").await;
+        return true;
+    }
+
+    let text = String::from_utf8_lossy(&msg.content);
+    let text = text.trim();
+    let next = match step.kind.as_str() {
+        KIND_COLLECT_EMAIL if visitor::is_valid_email(text) => {
+            let identify = Identify { email: text.to_string(), ..Default::default() };
+            visitor::handle_identify(state, &msg.shop_id, msg.guest_id, identify).await;
+            Some(step.next.clone())
+        }
+        KIND_COLLECT_EMAIL => None,
+        _ => step.options.iter()
+            .find(|o| o.label.to_lowercase() == text.to_lowercase())
+            .map(|o| o.next.clone()),
+    };
+
+    match next {
+        Some(next) if next.is_empty() => end(state, &msg.shop_id, msg.guest_id, HandoffReason::FlowEnded, "").await,
+        Some(next) => enter(state, &msg.shop_id, msg.guest_id, flow, &next).await,
+        // Không khớp: hỏi lại, quá MAX_MISSES lần → nhân viên tiếp
+        None if current.misses + 1 >= MAX_MISSES => {
+            println!("🤖 Guest {} left flow {:?} after {} unmatched replies", msg.guest_id, flow.flow_id, MAX_MISSES);
+            end(state, &msg.shop_id, msg.guest_id, HandoffReason::NoMatch, "").await;
+        }
+        None => {
+            send_step(state, &msg.shop_id, msg.guest_id, step).await;
+            let retry = FlowState { misses: current.misses + 1, updated_at: now_us(), ..current };
+            if let Err(e) = state.repo.save_flow_state(&msg.shop_id, msg.guest_id, &retry).await {
+                eprintln!("❌ Save flow state for guest {} failed: {:?}", msg.guest_id, e);
+            }
+        }
+    }
+    true
+}
+
+/// Nhân viên trả lời → người tiếp quản, dừng kịch bản
+pub async fn on_agent_message(state: &WebSocketState, settings: &ShopSettings, msg: &ChatMessage) {
+    if settings.bot_flows.is_empty() {
+        return;
+    }
+    if load_state(state, &msg.shop_id, msg.guest_id).await.is_some() {
+        println!("🤖 Agent took over guest {}, flow stopped", msg.guest_id);
+        finish(state, &msg.shop_id, msg.guest_id).await;
+    }
+}
+
+// Bước đang chờ của khách (bỏ dở quá FLOW_IDLE_US → coi như không có)
+async fn load_state(state: &WebSocketState, shop_id: &str, guest_id: u64) -> Option<FlowState> {
+    match state.repo.get_flow_state(shop_id, guest_id).await {
+        Ok(Some(current)) if current.updated_at + FLOW_IDLE_US > now_us() => Some(current),
+        Ok(_) => None,
+        Err(e) => {
+            eprintln!("❌ Load flow state for guest {} failed: {:?}", guest_id, e);
+            None
+        }
+    }
+}
+
+// Vào bước `step_id`: gửi tin, bước không chờ khách thì đi tiếp luôn
+async fn enter(state: &WebSocketState, shop_id: &str, guest_id: u64, flow: &BotFlow, step_id: &str) {
+    let mut step_id = step_id.to_string();
+    // Kịch bản vòng lặp không có bước chờ → dừng sau mỗi bước đi qua một lần
+    for _ in 0..flow.steps.len() {
+        let Some(step) = flow.steps.iter().find(|s| s.step_id == step_id) else { break };
+        send_step(state, shop_id, guest_id, step).await;
+
+        match step.kind.as_str() {
+            KIND_HANDOFF => {
+                end(state, shop_id, guest_id, HandoffReason::HandoffStep, &step.assign_to).await;
+                return;
+            }
+            KIND_MESSAGE if step.options.is_empty() && !step.next.is_empty() => {
+                step_id = step.next.clone();
+                continue;
+            }
+            KIND_MESSAGE if step.options.is_empty() => break,
+            // Chờ khách chọn nút / nhập email
+            _ => {
+                let waiting = FlowState { flow_id: flow.flow_id.clone(), step_id, misses: 0, updated_at: now_us() };
+                if let Err(e) = state.repo.save_flow_state(shop_id, guest_id, &waiting).await {
+                    eprintln!("❌ Save flow state for guest {} failed: {:?}", guest_id, e);
+                }
+                return;
+            }
+        }
+    }
+    end(state, shop_id, guest_id, HandoffReason::FlowEnded, "").await;
+}
+
+async fn send_step(state: &WebSocketState, shop_id: &str, guest_id: u64, step: &FlowStep) {
+    if step.message.is_empty() {
+        return;
+    }
+    let quick_replies = step.options.iter()
+        .map(|o| QuickReply { label: o.label.clone(), payload: o.label.clone() })
+        .collect();
+    send_bot_reply(state, shop_id, guest_id, &step.message, quick_replies).await;
+}
+
+async fn finish(state: &WebSocketState, shop_id: &str, guest_id: u64) {
+    if let Err(e) = state.repo.delete_flow_state(shop_id, guest_id).await {
+        eprintln!("❌ Delete flow state for guest {} failed: {:?}", guest_id, e);
+    }
+}
+
+// Kịch bản kết thúc → nhân viên tiếp (giao người phụ trách nếu bước có chỉ định)
+async fn end(state: &WebSocketState, shop_id: &str, guest_id: u64, reason: HandoffReason, assign_to: &str) {
+    finish(state, shop_id, guest_id).await;
+    handoff::hand_off(state, shop_id, guest_id, reason, assign_to).await;
+}
+
+// GET /flows - JSON dễ đọc để admin sửa
+pub async fn get_flows_handler(State(state): State<Arc<AppState>>, admin: AdminAuth) -> impl IntoResponse {
+    match state.repo.get_settings(&admin.shop_id).await {
+        Ok(settings) => json_response(&BotFlows { flows: settings.bot_flows }),
+        Err(e) => {
+            eprintln!("❌ Load settings for {} failed: {:?}", admin.shop_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, [(header::CONTENT_TYPE, "text/plain; charset=utf-8")], Bytes::new())
+        }
+    }
+}
+
+// PUT /flows - Thay toàn bộ kịch bản (JSON), trả lại bản đã chuẩn hoá; sai → 400 kèm lý do
+pub async fn put_flows_handler(State(state): State<Arc<AppState>>, OwnerAuth(admin): OwnerAuth, body: Bytes) -> impl IntoResponse {
+    let plain = [(header::CONTENT_TYPE, "text/plain; charset=utf-8")];
+    let flows = match serde_json::from_slice::<BotFlows>(&body).map_err(|e| e.to_string()).and_then(|req| validate(req.flows)) {
+        Ok(flows) => flows,
+        Err(reason) => return (StatusCode::BAD_REQUEST, plain, Bytes::from(reason)),
+    };
+
+    let mut settings = match state.repo.get_settings(&admin.shop_id).await {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("❌ Load settings for {} failed: {:?}", admin.shop_id, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, plain, Bytes::new());
+        }
+    };
+    settings.bot_flows = flows.clone();
+    if let Err(e) = state.repo.save_settings(&admin.shop_id, &settings).await {
+        eprintln!("❌ Save bot flows failed: {:?}", e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, plain, Bytes::new());
+    }
+    state.ws_state.settings.put(&admin.shop_id, settings);
+
+    let detail = format!("{} flows", flows.len());
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "flows.update", 0, &admin.agent_id, &detail).await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("🤖 Bot flows updated for shop {} ({} flows)", admin.shop_id, flows.len());
+
+    json_response(&BotFlows { flows })
+}
+
+fn json_response(flows: &BotFlows) -> (StatusCode, [(header::HeaderName, &'static str); 1], Bytes) {
+    match serde_json::to_vec_pretty(flows) {
+        Ok(json) => (StatusCode::OK, [(header::CONTENT_TYPE, "application/json")], Bytes::from(json)),
+        Err(e) => {
+            eprintln!("❌ Encode bot flows failed: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, [(header::CONTENT_TYPE, "text/plain; charset=utf-8")], Bytes::new())
+        }
+    }
+}
+
+// Cắt chuỗi, kind rỗng → "message"; id trùng / bước trỏ tới bước không có → lỗi
+fn validate(mut flows: Vec<BotFlow>) -> Result<Vec<BotFlow>, String> {
+    if flows.len() > MAX_FLOWS {
+        return Err(format!("at most {} flows", MAX_FLOWS));
+    }
+    let clip = |s: &mut String, max: usize| *s = s.trim().chars().take(max).collect();
+    let mut flow_ids = HashSet::new();
+    for flow in &mut flows {
+        clip(&mut flow.flow_id, MAX_ID_CHARS);
+        clip(&mut flow.name, MAX_TEXT_CHARS);
+        if flow.flow_id.is_empty() || !flow_ids.insert(flow.flow_id.clone()) {
+            return Err(format!("flow_id {:?} is empty or used twice", flow.flow_id));
+        }
+        if flow.steps.is_empty() || flow.steps.len() > MAX_STEPS {
+            return Err(format!("flow {:?} needs 1 to {} steps", flow.flow_id, MAX_STEPS));
+        }
+
+        let mut step_ids = HashSet::new();
+        for step in &mut flow.steps {
+            clip(&mut step.step_id, MAX_ID_CHARS);
+            clip(&mut step.kind, MAX_ID_CHARS);
+            clip(&mut step.message, MAX_TEXT_CHARS);
+            clip(&mut step.next, MAX_ID_CHARS);
+            clip(&mut step.assign_to, MAX_ID_CHARS);
+            if step.kind.is_empty() {
+                step.kind = KIND_MESSAGE.to_string();
+            }
+            if step.step_id.is_empty() || !step_ids.insert(step.step_id.clone()) {
+                return Err(format!("flow {:?}: step_id {:?} is empty or used twice", flow.flow_id, step.step_id));
+            }
+            if ![KIND_MESSAGE, KIND_COLLECT_EMAIL, KIND_HANDOFF].contains(&step.kind.as_str()) {
+                return Err(format!("step {:?}: unknown kind {:?}", step.step_id, step.kind));
+            }
+            if step.message.is_empty() && step.kind != KIND_HANDOFF {
+                return Err(format!("step {:?} has no message", step.step_id));
+            }
+            if step.kind != KIND_MESSAGE && !step.options.is_empty() {
+                return Err(format!("step {:?}: only \"message\" steps have options", step.step_id));
+            }
+            if step.options.len() > QuickReply::MAX_PER_MESSAGE {
+                return Err(format!("step {:?}: at most {} options", step.step_id, QuickReply::MAX_PER_MESSAGE));
+            }
+            for option in &mut step.options {
+                clip(&mut option.label, QuickReply::MAX_LABEL_CHARS);
+                clip(&mut option.next, MAX_ID_CHARS);
+                if option.label.is_empty() {
+                    return Err(format!("step {:?} has an option without label", step.step_id));
+                }
+            }
+        }
+
+        let targets = flow.steps.iter()
+            .flat_map(|s| std::iter::once(&s.next).chain(s.options.iter().map(|o| &o.next)));
+        if let Some(missing) = targets.filter(|t| !t.is_empty()).find(|t| !step_ids.contains(*t)) {
+            return Err(format!("flow {:?}: no step {:?}", flow.flow_id, missing));
+        }
+    }
+    Ok(flows)
+}
@@ -0,0 +1,172 @@
+// GDPR: quyền được xoá (erasure) + quyền truy cập dữ liệu (data access)
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::attachments;
+use crate::auth::{AdminAuth, OwnerAuth};
+use crate::contract::{ContractError, Guest, Message};
+use crate::db::AstraRepo;
+use crate::merge;
+use crate::state::AppState;
+
+// DELETE /guests/:id - Xoá guest + toàn bộ tin nhắn (cả các guest đã gộp cùng tài khoản website)
+pub async fn erase_guest_handler(
+    State(state): State<Arc<AppState>>,
+    OwnerAuth(admin): OwnerAuth,
+    Path(guest_id): Path<u64>,
+) -> impl IntoResponse {
+    let guests = match merge::conversation_guests(&state.ws_state, &admin.shop_id, guest_id).await {
+        Ok(guests) => guests,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    };
+    let mut erased = Vec::new();
+    for &id in &guests {
+        match state.repo.erase_guest(&admin.shop_id, id).await {
+            Ok(messages) => erased.extend(messages),
+            Err(e) => {
+                eprintln!("❌ Erase guest {} failed: {:?}", id, e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() })));
+            }
+        }
+    }
+    // guests[0] = guest gốc
+    if let Err(e) = state.repo.delete_guest_aliases(&admin.shop_id, guests[0]).await {
+        eprintln!("❌ Erase guest aliases failed: {:?}", e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() })));
+    }
+
+    // File đính kèm của khách cũng phải xoá
+    attachments::delete_files(state.ws_state.blobs.as_ref(), &erased).await;
+    state.ws_state.quotas.release_storage(&admin.shop_id, &erased).await;
+    let deleted = erased.len();
+
+    let detail = format!("deleted {} messages of guests {:?}", deleted, guests);
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "guest.erase", guest_id, &admin.agent_id, &detail).await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("🗑️ Erased guest {} of shop {} ({} messages, guests {:?})", guest_id, admin.shop_id, deleted, guests);
+
+    (StatusCode::OK, Json(json!({ "guest_id": guest_id, "guest_ids": guests, "deleted_messages": deleted })))
+}
+
+// GET /guests/:id/data - Xuất toàn bộ dữ liệu của guest (JSON): mọi bảng erase_guest xoá, của guest gốc và các alias
+pub async fn export_guest_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path(guest_id): Path<u64>,
+) -> impl IntoResponse {
+    let guest = match state.repo.get_guest(&admin.shop_id, guest_id).await {
+        Ok(Some(g)) => g,
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(json!({ "error": "Guest not found" }))),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    };
+
+    // Tin nằm dưới guest gốc và các alias đã gộp
+    let guests = match merge::conversation_guests(&state.ws_state, &admin.shop_id, guest_id).await {
+        Ok(guests) => guests,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    };
+    let export = match collect_export(&state.repo, &guest, &guests).await {
+        Ok(export) => export,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))),
+    };
+
+    let detail = format!("exported {} messages of guests {:?}", export.message_count(), guests);
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "guest.export", guest_id, &admin.agent_id, &detail).await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+
+    (StatusCode::OK, Json(export.to_json()))
+}
+
+/// Dữ liệu xuất cho khách - dùng chung cho API và turbochat-cli
+pub struct GuestExport<'a> {
+    guest: &'a Guest,
+    messages: Vec<Message>,
+    archived: Vec<Message>,
+    // guest_id → các bảng khác (nguyên dòng, xem AstraRepo::guest_records)
+    records: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Gom dữ liệu của `guests` (guest gốc trước)
+pub async fn collect_export<'a>(repo: &AstraRepo, guest: &'a Guest, guests: &[u64]) -> Result<GuestExport<'a>, ContractError> {
+    let mut export = GuestExport { guest, messages: Vec::new(), archived: Vec::new(), records: serde_json::Map::new() };
+    for &id in guests {
+        export.messages.extend(repo.fetch_all_messages(&guest.shop_id, id).await?);
+        export.archived.extend(repo.fetch_archived_messages(&guest.shop_id, id).await?);
+        export.records.insert(id.to_string(), repo.guest_records(&guest.shop_id, id).await?);
+    }
+    export.messages.sort_by_key(|m| m.message_id);
+    export.archived.sort_by_key(|m| m.message_id);
+    Ok(export)
+}
+
+impl GuestExport<'_> {
+    pub fn message_count(&self) -> usize {
+        self.messages.len() + self.archived.len()
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let guest = self.guest;
+        json!({
+            "shop_id": guest.shop_id,
+            "guest": {
+                "guest_id": guest.guest_id,
+                "guest_name": guest.guest_name,
+                "email": guest.email,
+                "user_id": guest.user_id,
+                "created_at": guest.created_at,
+                "last_seen": guest.last_seen,
+                "last_ip": guest.last_ip,
+                "geo": guest.geo.as_ref().map(|g| json!({
+                    "country_code": g.country_code,
+                    "country": g.country,
+                    "city": g.city,
+                })),
+                "context": guest.context.as_ref().map(|c| json!({
+                    "page_url": c.page_url,
+                    "referrer": c.referrer,
+                    "user_agent": c.user_agent,
+                    "viewport_width": c.viewport_width,
+                    "viewport_height": c.viewport_height,
+                    "updated_at_us": c.updated_at_us,
+                })),
+                "tags": guest.tags,
+                "status": guest.status().as_db_str(),
+                "assigned_to": guest.assigned_to,
+                "sentiment": guest.sentiment,
+                "summary": guest.summary.as_ref().map(|s| json!({
+                    "text": s.text,
+                    "suggested_tags": s.suggested_tags,
+                    "generated_at": s.generated_at,
+                })),
+                "last_message_preview": guest.last_message_preview,
+                "last_message_at": guest.last_message_at,
+            },
+            "messages": self.messages.iter().map(message_json).collect::<Vec<_>>(),
+            "archived_messages": self.archived.iter().map(message_json).collect::<Vec<_>>(),
+            "records": self.records,
+        })
+    }
+}
+
+fn message_json(m: &Message) -> serde_json::Value {
+    json!({
+        "message_id": m.message_id,
+        "guest_id": m.guest_id,
+        "sender_type": m.sender_type,
+        "content": String::from_utf8_lossy(&m.content),
+        "timestamp_us": m.timestamp_us,
+        "attachments": m.attachments.iter().map(|a| json!({
+            "file_name": a.file_name,
+            "content_type": a.content_type,
+            "size": a.size,
+        })).collect::<Vec<_>>(),
+    })
+}
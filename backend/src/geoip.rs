@@ -0,0 +1,130 @@
+// Geo-IP: tra quốc gia/thành phố của khách qua dịch vụ ngoài (GEOIP_URL), lưu lên guest
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::contract::{GeoLocation, Guest, WsEnvelope};
+use crate::websocket::{publish_envelope, WebSocketState};
+
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(3);
+// Giới hạn bộ nhớ đệm IP → vị trí
+const MAX_CACHED_IPS: usize = 10_000;
+
+/// GEOIP_URL dạng "http://ip-api.com/json/{ip}" - JSON trả về có countryCode/country/city
+/// (hoặc country_code/country_name/city). Không đặt → tắt.
+pub struct GeoIpResolver {
+    client: reqwest::Client,
+    url_template: Option<String>,
+    cache: Mutex<HashMap<String, Option<GeoLocation>>>,
+    // (shop_id, guest_id) đã có vị trí - không tra lại
+    done: Mutex<HashSet<(String, u64)>>,
+}
+
+impl GeoIpResolver {
+    pub fn from_env() -> Self {
+        let url_template = std::env::var("GEOIP_URL").ok().filter(|u| u.contains("{ip}"));
+        if url_template.is_none() {
+            println!("🌍 GEOIP_URL not set, geo-IP enrichment disabled");
+        }
+        Self {
+            client: reqwest::Client::builder().timeout(LOOKUP_TIMEOUT).build().unwrap_or_default(),
+            url_template,
+            cache: Mutex::new(HashMap::new()),
+            done: Mutex::new(HashSet::new()),
+        }
+    }
+
+    async fn resolve(&self, ip: &str) -> Option<GeoLocation> {
+        let template = self.url_template.as_ref()?;
+        if !is_public_ip(ip) {
+            return None;
+        }
+        if let Some(cached) = self.cache.lock().unwrap().get(ip) {
+            return cached.clone();
+        }
+
+        let url = template.replace("{ip}", ip);
+        let location = match self.client.get(&url).send().await {
+            Ok(resp) => resp.json::<serde_json::Value>().await.ok().and_then(|body| parse_location(&body)),
+            Err(e) => {
+                // Lỗi mạng → không cache, lần kết nối sau thử lại
+                eprintln!("❌ Geo-IP lookup for {} failed: {}", ip, e);
+                return None;
+            }
+        };
+
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= MAX_CACHED_IPS {
+            cache.clear();
+        }
+        cache.insert(ip.to_string(), location.clone());
+        location
+    }
+}
+
+/// Gắn vị trí cho khách lần đầu thấy (khách phải có bản ghi - tức đã nhắn tin)
+pub async fn enrich_guest(state: &WebSocketState, shop_id: &str, guest_id: u64, ip: &str) {
+    let key = (shop_id.to_string(), guest_id);
+    if state.geoip.url_template.is_none() || state.geoip.done.lock().unwrap().contains(&key) {
+        return;
+    }
+
+    let guest = match state.repo.get_guest(shop_id, guest_id).await {
+        Ok(Some(guest)) => guest,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("❌ Load guest {} failed: {:?}", guest_id, e);
+            return;
+        }
+    };
+    if guest.geo.is_some() {
+        state.geoip.done.lock().unwrap().insert(key);
+        return;
+    }
+
+    let Some(location) = state.geoip.resolve(ip).await else { return };
+    if let Err(e) = state.repo.set_guest_geo(shop_id, guest_id, &location).await {
+        eprintln!("❌ Save geo for guest {} failed: {:?}", guest_id, e);
+        return;
+    }
+    {
+        let mut done = state.geoip.done.lock().unwrap();
+        if done.len() >= MAX_CACHED_IPS {
+            done.clear();
+        }
+        done.insert(key);
+    }
+    println!("🌍 Guest {} located in {}", guest_id, location.label());
+
+    let guest = Guest { geo: Some(location), ..guest };
+    if let Err(e) = publish_envelope(state, &WsEnvelope::guest(guest)).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+}
+
+fn parse_location(body: &serde_json::Value) -> Option<GeoLocation> {
+    let field = |keys: &[&str]| keys.iter()
+        .find_map(|k| body[*k].as_str())
+        .unwrap_or("")
+        .trim()
+        .to_string();
+    let country_code = field(&["countryCode", "country_code"]).to_uppercase();
+    if country_code.len() != 2 {
+        return None;
+    }
+    Some(GeoLocation {
+        country_code,
+        country: field(&["country_name", "country"]),
+        city: field(&["city"]),
+    })
+}
+
+// IP nội bộ / loopback không tra được
+fn is_public_ip(ip: &str) -> bool {
+    match ip.parse::<IpAddr>() {
+        Ok(IpAddr::V4(v4)) => !(v4.is_private() || v4.is_loopback() || v4.is_link_local() || v4.is_unspecified()),
+        Ok(IpAddr::V6(v6)) => !(v6.is_loopback() || v6.is_unspecified() || (v6.segments()[0] & 0xfe00) == 0xfc00),
+        Err(_) => false,
+    }
+}
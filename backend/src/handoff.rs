@@ -0,0 +1,83 @@
+// Bot chuyển hội thoại cho nhân viên (kịch bản kết thúc / khách gõ "agent"):
+// guest.needs_human, dòng hệ thống trong hội thoại, frame Handoff để admin báo ưu tiên. Nhân viên trả lời → bỏ cờ
+use crate::contract::{Guest, Handoff, HandoffReason, Message as ChatMessage, WsEnvelope};
+use crate::db::now_us;
+use crate::websocket::{publish_envelope, send_system_message, WebSocketState};
+
+// Khách gõ đúng một trong các từ này (không phân biệt hoa thường) → gặp nhân viên
+const REQUEST_WORDS: [&str; 4] = ["agent", "human", "nhân viên", "gặp nhân viên"];
+
+/// Tin khách là yêu cầu gặp nhân viên
+pub fn is_request(msg: &ChatMessage) -> bool {
+    let text = String::from_utf8_lossy(&msg.content).trim().to_lowercase();
+    REQUEST_WORDS.contains(&text.as_str())
+}
+
+/// Chuyển cho nhân viên; `assign_to` rỗng = giữ người phụ trách. Đang chờ nhân viên rồi thì không báo lại
+pub async fn hand_off(state: &WebSocketState, shop_id: &str, guest_id: u64, reason: HandoffReason, assign_to: &str) {
+    let guest = match state.repo.get_guest(shop_id, guest_id).await {
+        Ok(Some(guest)) => guest,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("❌ Load guest {} for handoff failed: {:?}", guest_id, e);
+            return;
+        }
+    };
+    if guest.needs_human && (assign_to.is_empty() || assign_to == guest.assigned_to) {
+        return;
+    }
+
+    if let Err(e) = state.repo.set_needs_human(shop_id, guest_id, true).await {
+        eprintln!("❌ Flag guest {} for handoff failed: {:?}", guest_id, e);
+        return;
+    }
+    let assigned_to = if assign_to.is_empty() { guest.assigned_to.clone() } else { assign_to.to_string() };
+    if assigned_to != guest.assigned_to {
+        if let Err(e) = state.repo.set_guest_routing(shop_id, guest_id, &guest.tags, &assigned_to).await {
+            eprintln!("❌ Assign guest {} failed: {:?}", guest_id, e);
+        }
+    }
+    println!("🙋 Guest {} of shop {} handed off to {} ({:?})", guest_id, shop_id,
+        if assigned_to.is_empty() { "agents" } else { assigned_to.as_str() }, reason);
+
+    let first_time = !guest.needs_human;
+    let guest = Guest { needs_human: true, assigned_to: assigned_to.clone(), ..guest };
+    if let Err(e) = publish_envelope(state, &WsEnvelope::guest(guest)).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+    if !first_time {
+        return;
+    }
+    send_system_message(state, shop_id, guest_id, ChatMessage::SYSTEM_HANDOFF).await;
+    let handoff = Handoff { guest_id, reason: reason as i32, assigned_to, at_us: now_us() };
+    if let Err(e) = publish_envelope(state, &WsEnvelope::handoff(shop_id.to_string(), handoff)).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+}
+
+/// Khách gõ "agent" khi không chạy kịch bản → chuyển cho nhân viên (đang chờ rồi thì thôi)
+pub async fn on_guest_message(state: &WebSocketState, msg: &ChatMessage) {
+    if is_request(msg) {
+        hand_off(state, &msg.shop_id, msg.guest_id, HandoffReason::GuestRequest, "").await;
+    }
+}
+
+/// Nhân viên đã trả lời → hội thoại không còn chờ nhân viên
+pub async fn on_agent_message(state: &WebSocketState, msg: &ChatMessage) {
+    let guest = match state.repo.get_guest(&msg.shop_id, msg.guest_id).await {
+        Ok(Some(guest)) if guest.needs_human => guest,
+        Ok(_) => return,
+        Err(e) => {
+            eprintln!("❌ Load guest {} for handoff failed: {:?}", msg.guest_id, e);
+            return;
+        }
+    };
+    if let Err(e) = state.repo.set_needs_human(&msg.shop_id, msg.guest_id, false).await {
+        eprintln!("❌ Clear handoff of guest {} failed: {:?}", msg.guest_id, e);
+        return;
+    }
+    let guest = Guest { needs_human: false, ..guest };
+    if let Err(e) = publish_envelope(state, &WsEnvelope::guest(guest)).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+}
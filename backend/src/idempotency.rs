@@ -0,0 +1,190 @@
+// ============================================================================
+// IDEMPOTENCY-KEY CHO ENDPOINT GHI (POST/PUT/PATCH/DELETE)
+// Client / webhook gửi lại cùng key → trả đúng response lần đầu, handler không chạy lại (không chèn tin 2 lần).
+// Redis idem:<sha256(method, path, shop, credential, key)> giữ response IDEMPOTENCY_TTL_SECS. Cùng key nhưng body khác → 422,
+// lần đầu còn đang chạy → 409. Handler lỗi 5xx, sai xác thực (401/403) hay bị giới hạn (429) thì bỏ key
+// để lần thử lại được chạy thật. Key gắn với credential → request sai PIN không chiếm / đọc được key của admin thật
+// ============================================================================
+
+use axum::{
+    body::{to_bytes, Body, HttpBody},
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use crate::api_keys;
+use crate::attachments;
+use crate::redis_conn::RedisConn;
+use crate::state::AppState;
+
+const IDEMPOTENCY_TTL_SECS: u64 = 24 * 3600;
+// Giữ chỗ trong lúc handler chạy; tiến trình chết giữa chừng thì key tự nhả sau khoảng này
+const PENDING_TTL_SECS: u64 = 120;
+const MAX_KEY_LEN: usize = 255;
+// Response lớn hơn không lưu (vẫn trả bình thường, chỉ không chống trùng được)
+const MAX_STORED_RESPONSE_BYTES: usize = 1024 * 1024;
+
+/// Bản ghi trong Redis. status = 0: lần đầu đang chạy
+#[derive(Serialize, Deserialize)]
+struct Record {
+    fingerprint: String,
+    status: u16,
+    content_type: String,
+    body: String,  // base64
+}
+
+enum Claim {
+    New,
+    Existing(Record),
+}
+
+pub async fn middleware(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    if matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(request).await;
+    }
+    let Some(key) = request.headers().get("idempotency-key").map(|v| v.to_str().unwrap_or("").to_string()) else {
+        return next.run(request).await;
+    };
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        return (StatusCode::BAD_REQUEST, "Invalid Idempotency-Key").into_response();
+    }
+
+    let (parts, body) = request.into_parts();
+    let Ok(body) = to_bytes(body, attachments::max_upload_bytes()).await else {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    };
+    // Shop của admin (x-shop-id) hoặc của API key → key của các shop không đụng nhau
+    let header = |name| parts.headers.get(name).and_then(|v| v.to_str().ok());
+    let shop_id = header("x-shop-id")
+        .or_else(|| header("authorization").and_then(api_keys::bearer_shop))
+        .unwrap_or("");
+    let credential = header("x-admin-pin").or_else(|| header("authorization")).unwrap_or("");
+    let path = parts.uri.path_and_query().map(|p| p.as_str()).unwrap_or("");
+    let redis_key = storage_key(parts.method.as_str(), path, shop_id, credential, &key);
+    let fingerprint = hex::encode(Sha256::digest(&body));
+    let redis = &state.ws_state.redis;
+
+    match claim(redis, &redis_key, &fingerprint).await {
+        Ok(Claim::New) => {}
+        Ok(Claim::Existing(record)) if record.fingerprint != fingerprint => {
+            return (StatusCode::UNPROCESSABLE_ENTITY, "Idempotency-Key reused with a different request").into_response();
+        }
+        Ok(Claim::Existing(record)) if record.status == 0 => {
+            return (StatusCode::CONFLICT, "Request with this Idempotency-Key is still in progress").into_response();
+        }
+        Ok(Claim::Existing(record)) => {
+            println!("🔂 Replaying response for Idempotency-Key {} ({} {})", key, parts.method, path);
+            return replay(record);
+        }
+        Err(e) => {
+            eprintln!("❌ Idempotency check failed: {:?}", e);
+            return (StatusCode::SERVICE_UNAVAILABLE, "Idempotency store unavailable").into_response();
+        }
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if !is_storable(response.status()) {
+        release(redis, &redis_key).await;
+        return response;
+    }
+
+    // Response quá lớn / stream không rõ độ dài → trả thẳng, không lưu
+    let too_large = response.body().size_hint().upper().is_none_or(|n| n > MAX_STORED_RESPONSE_BYTES as u64);
+    if too_large {
+        println!("⚠️ Response for Idempotency-Key {} too large to store", key);
+        release(redis, &redis_key).await;
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let Ok(body) = to_bytes(body, MAX_STORED_RESPONSE_BYTES).await else {
+        release(redis, &redis_key).await;
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let record = Record {
+        fingerprint,
+        status: parts.status.as_u16(),
+        content_type: parts.headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or("").to_string(),
+        body: BASE64.encode(&body),
+    };
+    if let Err(e) = save(redis, &redis_key, &record).await {
+        eprintln!("❌ Store idempotent response failed: {:?}", e);
+    }
+    Response::from_parts(parts, Body::from(body))
+}
+
+// Lỗi tạm thời / lỗi xác thực → lần sau chạy lại thật, không phát lại lỗi
+fn is_storable(status: StatusCode) -> bool {
+    !status.is_server_error()
+        && !matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS)
+}
+
+fn storage_key(method: &str, path: &str, shop_id: &str, credential: &str, key: &str) -> String {
+    let mut hasher = Sha256::new();
+    for part in [method, path, shop_id, credential, key] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    format!("idem:{}", hex::encode(hasher.finalize()))
+}
+
+// Giữ chỗ key (SET NX); đã có thì trả bản ghi hiện tại
+async fn claim(redis: &RedisConn, redis_key: &str, fingerprint: &str) -> redis::RedisResult<Claim> {
+    let result = try_claim(redis, redis_key, fingerprint).await;
+    redis.checked(result).await
+}
+
+async fn try_claim(redis: &RedisConn, redis_key: &str, fingerprint: &str) -> redis::RedisResult<Claim> {
+    let mut conn = redis.get().await?;
+    let pending = Record { fingerprint: fingerprint.to_string(), status: 0, content_type: String::new(), body: String::new() };
+    let created: Option<String> = redis::cmd("SET")
+        .arg(redis_key)
+        .arg(serde_json::to_string(&pending).unwrap_or_default())
+        .arg("NX")
+        .arg("EX")
+        .arg(PENDING_TTL_SECS)
+        .query_async(&mut conn)
+        .await?;
+    if created.is_some() {
+        return Ok(Claim::New);
+    }
+    let stored: Option<String> = conn.get(redis_key).await?;
+    match stored.and_then(|s| serde_json::from_str(&s).ok()) {
+        Some(record) => Ok(Claim::Existing(record)),
+        // Vừa hết hạn giữa SET và GET → coi như lần đầu
+        None => Ok(Claim::New),
+    }
+}
+
+async fn save(redis: &RedisConn, redis_key: &str, record: &Record) -> redis::RedisResult<()> {
+    let result = async {
+        redis.get().await?.set_ex(redis_key, serde_json::to_string(record).unwrap_or_default(), IDEMPOTENCY_TTL_SECS).await
+    }.await;
+    redis.checked(result).await
+}
+
+async fn release(redis: &RedisConn, redis_key: &str) {
+    let result: redis::RedisResult<()> = async { redis.get().await?.del(redis_key).await }.await;
+    if let Err(e) = redis.checked(result).await {
+        eprintln!("❌ Release Idempotency-Key failed: {:?}", e);
+    }
+}
+
+fn replay(record: Record) -> Response {
+    let status = StatusCode::from_u16(record.status).unwrap_or(StatusCode::OK);
+    let body = BASE64.decode(&record.body).unwrap_or_default();
+    let mut response = (status, body).into_response();
+    if let Ok(content_type) = HeaderValue::from_str(&record.content_type) {
+        if !record.content_type.is_empty() {
+            response.headers_mut().insert(header::CONTENT_TYPE, content_type);
+        }
+    }
+    response.headers_mut().insert("idempotent-replayed", HeaderValue::from_static("true"));
+    response
+}
@@ -0,0 +1,109 @@
+// ============================================================================
+// XÁC MINH DANH TÍNH KHÁCH - gắn guest ẩn danh (localStorage) với tài khoản đăng nhập của website
+// Server website tính user_hash = hex(HMAC-SHA256(identity secret, user_id)) rồi in vào trang:
+//   <script src=".../widget.js?shop_id=X" data-user-id="42" data-user-email="a@b.com" data-user-hash="..."></script>
+//   hoặc TurboChat.identify({ user_id: "42", user_hash: "...", name, email })
+// Chữ ký đúng → guests.user_id + guest_identities (một tài khoản ↔ nhiều guest trên các thiết bị),
+// rồi gộp hội thoại vào guest gốc của tài khoản (merge.rs).
+// Không có chữ ký / sai → bỏ qua user_id (tên, email vẫn lưu như identify thường)
+// ============================================================================
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
+};
+use prost::Message as ProstMessage;
+use std::sync::Arc;
+use turbochat_shared::crypto;
+
+use crate::auth::OwnerAuth;
+use crate::blob;
+use crate::contract::{ContractError, IdentitySecretResponse};
+use crate::db::now_us;
+use crate::merge;
+use crate::request_id;
+use crate::state::AppState;
+use crate::websocket::WebSocketState;
+
+const MAX_USER_ID_CHARS: usize = 128;
+
+/// user_id dùng làm khoá trên path REST → chỉ cho ký tự an toàn
+pub fn is_valid_user_id(user_id: &str) -> bool {
+    !user_id.is_empty()
+        && user_id.len() <= MAX_USER_ID_CHARS
+        && user_id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@' | ':'))
+}
+
+/// Kiểm chữ ký user_hash; shop chưa tạo secret → false
+pub async fn verify(state: &WebSocketState, shop_id: &str, user_id: &str, user_hash: &str) -> Result<bool, ContractError> {
+    if !is_valid_user_id(user_id) || user_hash.is_empty() {
+        return Ok(false);
+    }
+    let Some((secret, _)) = state.repo.get_identity_secret(shop_id).await? else {
+        return Ok(false);
+    };
+    Ok(crypto::verify_hex(secret.as_bytes(), user_id.as_bytes(), user_hash.trim()))
+}
+
+/// Gắn guest với user_id đã xác minh rồi gộp vào guest gốc của user (idempotent - identify lại mỗi lần kết nối)
+pub async fn link(state: &WebSocketState, shop_id: &str, guest_id: u64, user_id: &str) -> Result<(), ContractError> {
+    // Guest đã thuộc tài khoản khác (máy dùng chung) → không trộn hội thoại của hai người
+    let current = state.repo.get_guest(shop_id, guest_id).await?.map(|g| g.user_id).unwrap_or_default();
+    if !current.is_empty() && current != user_id {
+        println!("⚠️ Guest {} already linked to another user, not linking {:?}", guest_id, user_id);
+        return Ok(());
+    }
+    if current.is_empty() {
+        state.repo.link_guest_identity(shop_id, user_id, guest_id).await?;
+        println!("🔗 Guest {} linked to user {:?} (shop {})", guest_id, user_id, shop_id);
+    }
+    merge::merge_identity(state, shop_id, guest_id, user_id).await
+}
+
+// ============================================================================
+// HANDLERS - chỉ chủ shop (PIN)
+// ============================================================================
+
+// GET /identity-secret - lần đầu gọi thì tạo
+pub async fn get_secret_handler(State(state): State<Arc<AppState>>, OwnerAuth(admin): OwnerAuth) -> impl IntoResponse {
+    match state.repo.get_identity_secret(&admin.shop_id).await {
+        Ok(Some((secret, rotated_at))) => secret_response(secret, rotated_at),
+        Ok(None) => issue(&state, &admin.shop_id).await,
+        Err(e) => {
+            eprintln!("❌ Load identity secret for {} failed: {:?}", admin.shop_id, e);
+            secret_error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string())
+        }
+    }
+}
+
+// POST /identity-secret/rotate - chữ ký cũ hết hiệu lực ngay (website phải dùng secret mới)
+pub async fn rotate_secret_handler(State(state): State<Arc<AppState>>, OwnerAuth(admin): OwnerAuth) -> impl IntoResponse {
+    let resp = issue(&state, &admin.shop_id).await;
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "identity.rotate_secret", 0, &admin.agent_id, "").await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    resp
+}
+
+async fn issue(state: &AppState, shop_id: &str) -> (StatusCode, Bytes) {
+    let secret = hex::encode(blob::random_key());
+    let rotated_at = now_us();
+    if let Err(e) = state.repo.set_identity_secret(shop_id, &secret, rotated_at).await {
+        eprintln!("❌ Save identity secret for {} failed: {:?}", shop_id, e);
+        return secret_error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string());
+    }
+    println!("🔑 New identity secret for shop {}", shop_id);
+    secret_response(secret, rotated_at)
+}
+
+fn secret_response(secret: String, rotated_at: u64) -> (StatusCode, Bytes) {
+    let resp = IdentitySecretResponse { success: true, secret, rotated_at, ..Default::default() };
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+}
+
+fn secret_error(status: StatusCode, error: &str) -> (StatusCode, Bytes) {
+    let resp = IdentitySecretResponse { success: false, error: error.to_string(), request_id: request_id::current(), ..Default::default() };
+    (status, Bytes::from(resp.encode_to_vec()))
+}
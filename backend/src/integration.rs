@@ -0,0 +1,175 @@
+// ============================================================================
+// API TÍCH HỢP /v1 - cho hệ thống của shop gọi bằng API key (api_keys.rs), không dùng PIN admin
+//   GET  /v1/guests?limit=&cursor=        guests:read    → GuestListResponse
+//   GET  /v1/guests/:id                   guests:read    → Guest
+//   GET  /v1/guests/:id/messages?after=&limit=&page_state=   messages:read → SyncResponse
+//   POST /v1/guests/:id/messages          messages:write (IntegrationMessageRequest) → 202 (503 khi đang bảo trì)
+// ============================================================================
+
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use prost::Message as ProstMessage;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::api_keys::{ApiKeyAuth, Scope};
+use crate::attachments;
+use crate::db::now_us;
+use crate::maintenance;
+use crate::contract::{ContractError, GuestListResponse, IntegrationMessageRequest, Message as ChatMessage, SyncResponse};
+use crate::request_id;
+use crate::state::AppState;
+use crate::websocket::{self, MAX_CONTENT_BYTES};
+
+#[derive(Deserialize)]
+pub struct GuestsQuery {
+    #[serde(default)]
+    pub limit: u32,
+    #[serde(default)]
+    pub cursor: String,
+}
+
+#[derive(Deserialize)]
+pub struct MessagesQuery {
+    #[serde(default)]
+    pub after: u64,
+    #[serde(default)]
+    pub limit: u32,
+    #[serde(default)]
+    pub page_state: String,
+}
+
+// GET /v1/guests
+pub async fn list_guests_handler(
+    State(state): State<Arc<AppState>>,
+    key: ApiKeyAuth,
+    Query(query): Query<GuestsQuery>,
+) -> Response {
+    if let Err(rejection) = key.require(Scope::GuestsRead) {
+        return rejection.into_response();
+    }
+    let cursor = Some(query.cursor.as_str()).filter(|c| !c.is_empty());
+    let resp = match state.repo.list_guests_page(&key.shop_id, query.limit, cursor, 0).await {
+        Ok(page) => GuestListResponse {
+            success: true,
+            guests: page.guests,
+            next_cursor: page.next_cursor.unwrap_or_default(),
+            ..Default::default()
+        },
+        Err(e) => {
+            eprintln!("❌ API list guests for {} failed: {:?}", key.shop_id, e);
+            let resp = GuestListResponse { success: false, error: e.to_string(), request_id: request_id::current(), ..Default::default() };
+            return (db_error_status(&e), Bytes::from(resp.encode_to_vec())).into_response();
+        }
+    };
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec())).into_response()
+}
+
+// GET /v1/guests/:id
+pub async fn get_guest_handler(
+    State(state): State<Arc<AppState>>,
+    key: ApiKeyAuth,
+    Path(guest_id): Path<u64>,
+) -> Response {
+    if let Err(rejection) = key.require(Scope::GuestsRead) {
+        return rejection.into_response();
+    }
+    match state.repo.get_guest(&key.shop_id, guest_id).await {
+        Ok(Some(guest)) => (StatusCode::OK, Bytes::from(guest.encode_to_vec())).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            eprintln!("❌ API get guest {} failed: {:?}", guest_id, e);
+            db_error_status(&e).into_response()
+        }
+    }
+}
+
+// GET /v1/guests/:id/messages - tin đã xoá chỉ còn tombstone như phía khách
+pub async fn list_messages_handler(
+    State(state): State<Arc<AppState>>,
+    key: ApiKeyAuth,
+    Path(guest_id): Path<u64>,
+    Query(query): Query<MessagesQuery>,
+) -> Response {
+    if let Err(rejection) = key.require(Scope::MessagesRead) {
+        return rejection.into_response();
+    }
+    let page = match state.repo.fetch_messages(&key.shop_id, guest_id, query.after, query.limit, Some(&query.page_state)).await {
+        Ok(page) => page,
+        Err(e) => {
+            eprintln!("❌ API messages for guest {} failed: {:?}", guest_id, e);
+            return db_error_status(&e).into_response();
+        }
+    };
+    let mut messages = page.messages;
+    messages.iter_mut().filter(|m| m.deleted_at_us > 0).for_each(|m| m.redact());
+    state.repo.hydrate_replies(&mut messages).await;
+    attachments::sign_urls(state.ws_state.blobs.as_ref(), &mut messages);
+
+    let mut resp = SyncResponse {
+        messages,
+        server_timestamp_us: now_us(),
+        has_more: page.page_state.is_some(),
+        page_state: page.page_state.unwrap_or_default(),
+        crc32: 0,
+    };
+    resp.finalize();
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec())).into_response()
+}
+
+// POST /v1/guests/:id/messages - gửi như tin admin (qua cùng pipeline lọc / lưu / publish)
+pub async fn send_message_handler(
+    State(state): State<Arc<AppState>>,
+    key: ApiKeyAuth,
+    Path(guest_id): Path<u64>,
+    body: Bytes,
+) -> Response {
+    if let Err(rejection) = key.require(Scope::MessagesWrite) {
+        return rejection.into_response();
+    }
+    if state.ws_state.maintenance.is_active() {
+        return maintenance::unavailable(&state.ws_state);
+    }
+    let Ok(req) = IntegrationMessageRequest::decode(&body[..]) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let content = req.content.trim();
+    if content.is_empty() || content.len() > MAX_CONTENT_BYTES {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+    match state.repo.get_guest(&key.shop_id, guest_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return db_error_status(&e).into_response(),
+    }
+    if let Err(exceeded) = state.ws_state.quotas.check_send(&key.shop_id, content.len()).await {
+        println!("📊 API message refused: shop {} at {}/{}", key.shop_id, exceeded.used, exceeded.limit);
+        return StatusCode::TOO_MANY_REQUESTS.into_response();
+    }
+
+    let msg = ChatMessage {
+        shop_id: key.shop_id.clone(),
+        guest_id,
+        sender_type: "admin".to_string(),
+        content_crc: crc32c::crc32c(content.as_bytes()),
+        content: content.as_bytes().to_vec().into(),
+        quick_replies: req.quick_replies,
+        ..Default::default()
+    };
+    websocket::ingest_message(&state.ws_state, msg, "").await;
+    if let Err(e) = state.repo.insert_audit(&key.shop_id, "message.api_send", guest_id, &key.actor(), "").await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    StatusCode::ACCEPTED.into_response()
+}
+
+fn db_error_status(e: &ContractError) -> StatusCode {
+    match e {
+        ContractError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
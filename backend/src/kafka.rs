@@ -0,0 +1,112 @@
+// ============================================================================
+// XUẤT SỰ KIỆN RA KAFKA - cho kho dữ liệu / CDP của shop, không phải đọc DB
+// Mỗi frame đã lưu (tin, sửa/xoá tin, khách đổi trạng thái, ghi chú) được gửi một lần từ node phát ra nó.
+// Payload: WsEnvelope protobuf (schema shared/proto/chat.proto), key = shop_id (cùng shop cùng partition,
+// giữ thứ tự), header event_type. KAFKA_BROKERS (vd. "k1:9092,k2:9092"), KAFKA_TOPIC (mặc định turbochat.events).
+// Chỉ có khi build với --features kafka
+// ============================================================================
+
+use crate::contract::{ws_envelope::Frame, WsEnvelope};
+
+/// Loại sự kiện của frame; None = frame không lưu DB (presence, trạng thái admin, ...) → không xuất
+pub fn event_type(envelope: &WsEnvelope) -> Option<&'static str> {
+    match envelope.frame.as_ref()? {
+        Frame::Message(_) => Some("message.created"),
+        Frame::Edit(_) => Some("message.edited"),
+        Frame::Delete(_) => Some("message.deleted"),
+        Frame::Guest(_) => Some("conversation.updated"),
+        Frame::Note(_) => Some("note.updated"),
+        Frame::Scheduled(_) => Some("scheduled.updated"),
+        _ => None,
+    }
+}
+
+pub struct EventExporter {
+    #[cfg(feature = "kafka")]
+    sink: Option<sink::KafkaSink>,
+}
+
+impl EventExporter {
+    pub fn from_env() -> Self {
+        let brokers = std::env::var("KAFKA_BROKERS").unwrap_or_default();
+        #[cfg(feature = "kafka")]
+        {
+            if brokers.is_empty() {
+                return Self { sink: None };
+            }
+            let topic = std::env::var("KAFKA_TOPIC").unwrap_or_else(|_| "turbochat.events".to_string());
+            let sink = sink::KafkaSink::new(&brokers, topic).unwrap_or_else(|e| panic!("❌ Kafka exporter misconfigured: {}", e));
+            println!("📤 Exporting events to Kafka topic {} ({})", sink.topic, brokers);
+            Self { sink: Some(sink) }
+        }
+        #[cfg(not(feature = "kafka"))]
+        {
+            if !brokers.is_empty() {
+                println!("⚠️ KAFKA_BROKERS set but backend built without the kafka feature, events not exported");
+            }
+            Self {}
+        }
+    }
+
+    /// Gửi nền (không chờ broker); hàng đợi đầy hoặc broker lỗi thì chỉ log
+    pub fn export(&self, envelope: &WsEnvelope) {
+        let Some(event_type) = event_type(envelope) else { return };
+        #[cfg(feature = "kafka")]
+        if let Some(sink) = &self.sink {
+            sink.send(event_type, envelope);
+        }
+        #[cfg(not(feature = "kafka"))]
+        let _ = event_type;
+    }
+}
+
+#[cfg(feature = "kafka")]
+mod sink {
+    use prost::Message as ProstMessage;
+    use rdkafka::config::ClientConfig;
+    use rdkafka::error::KafkaError;
+    use rdkafka::message::{Header, OwnedHeaders};
+    use rdkafka::producer::{FutureProducer, FutureRecord};
+
+    use crate::contract::WsEnvelope;
+
+    pub struct KafkaSink {
+        producer: FutureProducer,
+        pub topic: String,
+    }
+
+    impl KafkaSink {
+        pub fn new(brokers: &str, topic: String) -> Result<Self, KafkaError> {
+            let producer = ClientConfig::new()
+                .set("bootstrap.servers", brokers)
+                .set("client.id", "turbochat-backend")
+                // Broker tạm mất: librdkafka giữ và gửi lại trong khoảng này
+                .set("message.timeout.ms", "30000")
+                .set("compression.type", "lz4")
+                .create()?;
+            Ok(Self { producer, topic })
+        }
+
+        pub fn send(&self, event_type: &str, envelope: &WsEnvelope) {
+            let payload = envelope.encode_to_vec();
+            let headers = OwnedHeaders::new().insert(Header { key: "event_type", value: Some(event_type) });
+            let record = FutureRecord::to(&self.topic)
+                .key(&envelope.shop_id)
+                .payload(&payload)
+                .headers(headers);
+            match self.producer.send_result(record) {
+                Ok(delivery) => {
+                    let event_type = event_type.to_string();
+                    tokio::spawn(async move {
+                        match delivery.await {
+                            Ok(Ok(_)) => {}
+                            Ok(Err((e, _))) => eprintln!("❌ Kafka export of {} failed: {}", event_type, e),
+                            Err(_) => eprintln!("❌ Kafka export of {} cancelled", event_type),
+                        }
+                    });
+                }
+                Err((e, _)) => eprintln!("❌ Kafka export of {} dropped: {}", event_type, e),
+            }
+        }
+    }
+}
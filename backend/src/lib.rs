@@ -1,4 +1,64 @@
+pub mod activity;
+pub mod agents;
+pub mod analytics;
+pub mod api_keys;
+pub mod attachments;
+pub mod auth;
+pub mod backup;
+pub mod batch;
+pub mod blob;
+pub mod blocklist;
+pub mod bus;
+pub mod campaigns;
+pub mod cluster;
+pub mod config;
 pub mod contract;
+pub mod conversation;
 pub mod db;
+pub mod drain;
+pub mod edit;
+pub mod feed;
+pub mod filter;
+pub mod flags;
+pub mod flows;
+pub mod gdpr;
+pub mod geoip;
+pub mod handoff;
+pub mod idempotency;
+pub mod identity;
+pub mod integration;
+pub mod kafka;
+pub mod live;
+pub mod loader;
+pub mod lockout;
+pub mod maintenance;
+pub mod merge;
+pub mod migrations;
+pub mod notes;
+pub mod notify;
+pub mod preferences;
+pub mod presence;
+pub mod proactive;
+pub mod quota;
+pub mod redis_conn;
+pub mod reminders;
+pub mod request_id;
+pub mod retention;
+pub mod routing;
+pub mod scan;
+pub mod scheduled;
+pub mod search;
+pub mod seed;
+pub mod session;
+pub mod sentiment;
+pub mod settings;
+pub mod sla;
+pub mod state;
+pub mod static_files;
+pub mod summary;
+pub mod telemetry;
+pub mod thumbnail;
+pub mod views;
+pub mod visitor;
 pub mod websocket;
 // sync.rs đã được gộp vào main.rs
\ No newline at end of file
@@ -0,0 +1,49 @@
+// Khách đang ở trên website (đang mở kết nối widget) - mục "Đang online" của admin panel.
+// Nguồn: sổ kết nối của cụm (cluster.rs), trang đang xem lấy từ VisitorContext đã lưu trên guest
+use axum::{body::Bytes, extract::State, http::StatusCode, response::IntoResponse};
+use futures::future::join_all;
+use prost::Message as ProstMessage;
+use std::sync::Arc;
+
+use crate::auth::AdminAuth;
+use crate::contract::{Guest, LiveVisitor, LiveVisitorList};
+use crate::request_id;
+use crate::state::AppState;
+
+// Shop đông khách: chỉ trả những người vào trang lâu nhất
+const MAX_VISITORS: usize = 200;
+
+// GET /live - Khách đang kết nối, vào trang lâu nhất trước
+pub async fn live_visitors_handler(State(state): State<Arc<AppState>>, admin: AdminAuth) -> impl IntoResponse {
+    let connected = match state.ws_state.cluster.live_guests(&admin.shop_id).await {
+        Ok(connected) => connected,
+        Err(e) => {
+            eprintln!("❌ Load live visitors of {} failed: {:?}", admin.shop_id, e);
+            let resp = LiveVisitorList { success: false, visitors: vec![], error: e.to_string(), request_id: request_id::current() };
+            return (StatusCode::OK, Bytes::from(resp.encode_to_vec()));
+        }
+    };
+
+    // 0 (node bản cũ, không rõ lúc vào) xếp cuối
+    let mut connected: Vec<(u64, u64)> = connected.into_iter().collect();
+    connected.sort_by_key(|&(guest_id, since_us)| (since_us == 0, since_us, guest_id));
+    connected.truncate(MAX_VISITORS);
+
+    let repo = &state.repo;
+    let shop_id = admin.shop_id.as_str();
+    let visitors = join_all(connected.into_iter().map(|(guest_id, connected_at_us)| async move {
+        let guest = match repo.get_guest(shop_id, guest_id).await {
+            Ok(Some(guest)) => guest,
+            // Chưa lưu guest (vừa kết nối) → chỉ có id
+            Ok(None) => Guest { shop_id: shop_id.to_string(), guest_id, ..Default::default() },
+            Err(e) => {
+                eprintln!("❌ Load guest {} failed: {:?}", guest_id, e);
+                Guest { shop_id: shop_id.to_string(), guest_id, ..Default::default() }
+            }
+        };
+        LiveVisitor { guest: Some(guest), connected_at_us }
+    })).await;
+
+    let resp = LiveVisitorList { success: true, visitors, ..Default::default() };
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+}
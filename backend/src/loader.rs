@@ -0,0 +1,260 @@
+// ============================================================================
+// EMBED LOADER - Shop chỉ cần 1 thẻ script:
+//   <script src="https://chat.example.com/widget.js?shop_id=demo123" async></script>
+// Loader tạo #turbochat-root, gắn giao diện từ ShopSettings và vẽ sẵn nút launcher (JS + CSS thuần).
+// Bundle WASM của widget (tên file có hash của Trunk → cache lâu dài) chỉ tải khi khách bấm nút /
+// host gọi TurboChat.open(), hoặc ngay lúc rảnh nếu khách đã có hội thoại (để nhận tin mới) /
+// trang đang xem khớp luật chào chủ động (widget phải kết nối thì server mới biết khách ở trang nào).
+// Rê chuột / focus vào nút → tải trước file (chưa chạy, chưa mở WS)
+// ============================================================================
+use axum::{
+    extract::{ConnectInfo, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::config;
+use crate::state::AppState;
+
+// Loader chứa settings của shop → cache ngắn (asset: xem static_files)
+const LOADER_CACHE: &str = "public, max-age=300";
+
+/// Bundle widget do Trunk build (chat-widget/dist)
+pub struct WidgetAssets {
+    dir: PathBuf,
+    js: String,
+    wasm: String,
+    css: Option<String>,
+}
+
+impl WidgetAssets {
+    /// Tìm file đã hash trong WIDGET_DIST_DIR (mặc định ../chat-widget/dist)
+    pub fn from_env() -> Option<Self> {
+        let dir = PathBuf::from(std::env::var("WIDGET_DIST_DIR").unwrap_or_else(|_| "../chat-widget/dist".to_string()));
+        let files: Vec<String> = std::fs::read_dir(&dir)
+            .ok()?
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .collect();
+
+        let find = |pred: &dyn Fn(&str) -> bool| files.iter().find(|f| pred(f)).cloned();
+        let assets = Self {
+            js: find(&|f| f.starts_with("turbochat-widget") && f.ends_with(".js"))?,
+            wasm: find(&|f| f.ends_with("_bg.wasm"))?,
+            css: find(&|f| f.ends_with(".css")),
+            dir,
+        };
+        println!("📦 Widget bundle: {} + {}", assets.js, assets.wasm);
+        Some(assets)
+    }
+
+    pub fn dir(&self) -> &std::path::Path {
+        &self.dir
+    }
+}
+
+#[derive(Deserialize)]
+pub struct LoaderQuery {
+    pub shop_id: String,
+}
+
+// Gốc URL public của backend (PUBLIC_URL hoặc suy ra từ Host).
+// Loader được cache public → header chỉ dùng khi hợp lệ; X-Forwarded-Proto chỉ nhận từ proxy tin cậy
+fn public_base(headers: &HeaderMap, peer: &SocketAddr) -> String {
+    if let Ok(url) = std::env::var("PUBLIC_URL") {
+        return url.trim_end_matches('/').to_string();
+    }
+    let host = headers.get(header::HOST)
+        .and_then(|h| h.to_str().ok())
+        .filter(|h| is_valid_host(h))
+        .unwrap_or("localhost:8080");
+    let proto = Some(peer)
+        .filter(|peer| config::current().trusted_proxies.contains(peer.ip().to_canonical()))
+        .and_then(|_| headers.get("x-forwarded-proto"))
+        .and_then(|h| h.to_str().ok())
+        .filter(|p| matches!(*p, "http" | "https"))
+        .unwrap_or("http");
+    format!("{}://{}", proto, host)
+}
+
+// Tên máy[:cổng] (hoặc [IPv6]:cổng) - không có '/', '?', '@'... → không đổi được origin của URL
+fn is_valid_host(host: &str) -> bool {
+    !host.is_empty()
+        && host.len() <= 255
+        && host.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b':' | b'[' | b']'))
+}
+
+// GET /widget.js?shop_id=X
+pub async fn widget_js_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LoaderQuery>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(assets) = &state.widget_assets else {
+        return (StatusCode::SERVICE_UNAVAILABLE, "// TurboChat widget bundle not built").into_response();
+    };
+    if query.shop_id.is_empty() || query.shop_id.len() > 64 {
+        return (StatusCode::BAD_REQUEST, "// missing shop_id").into_response();
+    }
+
+    let settings = state.ws_state.settings.get(&query.shop_id).await;
+    let base = public_base(&headers, &peer);
+    let asset_url = |file: &str| format!("{}/widget/assets/{}", base, file);
+
+    // Mọi giá trị đi qua serde_json → không chèn được JS qua shop_id/settings
+    let config = json!({
+        "shopId": query.shop_id,
+        "api": base,
+        "js": asset_url(&assets.js),
+        "wasm": asset_url(&assets.wasm),
+        "css": assets.css.as_deref().map(asset_url),
+        "theme": {
+            "color": settings.widget_color,
+            "position": settings.widget_position,
+            "title": settings.widget_title,
+            "theme": settings.widget_theme,
+        },
+        // Chỉ phần URL của luật đang bật - lời chào do server gửi
+        "proactive": settings.proactive_triggers.iter()
+            .filter(|t| t.enabled)
+            .map(|t| t.page_url_contains.as_str())
+            .collect::<Vec<_>>(),
+    });
+
+    let script = LOADER_TEMPLATE.replace("__CONFIG__", &config.to_string());
+    (
+        [
+            (header::CONTENT_TYPE, "application/javascript; charset=utf-8"),
+            (header::CACHE_CONTROL, LOADER_CACHE),
+        ],
+        script,
+    ).into_response()
+}
+
+// data-* đặt trên chính thẻ script được ưu tiên hơn settings của shop; data-user-* xem identity.rs.
+// TurboChat.* gọi trước khi WASM tải xong được xếp vào TurboChat.q (open() kích hoạt tải).
+// Khoá turbochat_active_<shop> do widget ghi khi hội thoại đã có tin
+const LOADER_TEMPLATE: &str = r#"(function () {
+  if (window.__turbochatLoaded) return;
+  window.__turbochatLoaded = true;
+  var cfg = __CONFIG__;
+  var script = document.currentScript;
+  var state = "idle"; // idle → loading → ready
+  var root, stub;
+
+  var api = window.TurboChat = window.TurboChat || { q: [] };
+  ["open", "close", "identify", "trackEvent", "onUnreadChange"].forEach(function (m) {
+    if (!api[m]) api[m] = function () { api.q.push([m].concat([].slice.call(arguments))); };
+  });
+  var queueOpen = api.open;
+  api.open = function () {
+    queueOpen.apply(api, arguments);
+    load();
+  };
+
+  function option(key) {
+    return (script && script.getAttribute("data-" + key)) || cfg.theme[key];
+  }
+
+  function hasConversation() {
+    try { return !!localStorage.getItem("turbochat_active_" + cfg.shopId); } catch (e) { return false; }
+  }
+
+  function proactivePage() {
+    return (cfg.proactive || []).some(function (needle) { return location.href.indexOf(needle) !== -1; });
+  }
+
+  function prefetch() {
+    if (state !== "idle" || prefetch.done) return;
+    prefetch.done = true;
+    [["modulepreload", cfg.js], ["preload", cfg.wasm]].forEach(function (p) {
+      var link = document.createElement("link");
+      link.rel = p[0];
+      link.href = p[1];
+      if (p[0] === "preload") { link.as = "fetch"; link.crossOrigin = "anonymous"; }
+      document.head.appendChild(link);
+    });
+  }
+
+  function load() {
+    if (state !== "idle" || !root) return;
+    state = "loading";
+    if (stub) stub.setAttribute("aria-busy", "true");
+    import(cfg.js)
+      .then(function (m) { return m.default({ module_or_path: cfg.wasm }); })
+      .then(function () {
+        state = "ready";
+        if (stub) stub.remove();
+      }, function (e) {
+        state = "idle";
+        if (stub) stub.removeAttribute("aria-busy");
+        console.error("TurboChat: widget failed to load", e);
+      });
+  }
+
+  // Nút launcher tạm - cùng class với widget (CSS tải sẵn) nên không nhảy giao diện khi WASM thay thế
+  function renderStub() {
+    stub = document.createElement("div");
+    stub.className = "turbochat-widget" + (String(option("position")).toLowerCase() === "left" ? " left" : "");
+    if (option("color")) stub.style.setProperty("--tc-primary", option("color"));
+    var button = document.createElement("button");
+    button.className = "turbochat-launcher";
+    button.setAttribute("aria-label", option("title") || "Chat");
+    var icon = option("launcher-icon") || "\uD83D\uDCAC";
+    if (/^https?:\/\//.test(icon)) {
+      var img = document.createElement("img");
+      img.className = "turbochat-launcher-img";
+      img.src = icon;
+      img.alt = "Chat";
+      button.appendChild(img);
+    } else {
+      button.textContent = Array.from(icon).slice(0, 4).join("");
+    }
+    button.addEventListener("mouseenter", prefetch);
+    button.addEventListener("focus", prefetch);
+    button.addEventListener("click", function () { api.open(); });
+    stub.appendChild(button);
+    root.appendChild(stub);
+  }
+
+  function start() {
+    root = document.getElementById("turbochat-root");
+    if (!root) {
+      root = document.createElement("div");
+      root.id = "turbochat-root";
+      document.body.appendChild(root);
+    }
+    root.setAttribute("data-shop-id", cfg.shopId);
+    root.setAttribute("data-api", cfg.api);
+    ["color", "position", "title", "launcher-icon", "flash-title", "locale", "theme"].forEach(function (key) {
+      var value = option(key);
+      if (value && !root.hasAttribute("data-" + key)) root.setAttribute("data-" + key, value);
+    });
+    // Tài khoản website (data-user-hash do server website ký) - chỉ lấy từ thẻ script
+    ["user-id", "user-email", "user-name", "user-hash"].forEach(function (key) {
+      var value = script && script.getAttribute("data-" + key);
+      if (value && !root.hasAttribute("data-" + key)) root.setAttribute("data-" + key, value);
+    });
+
+    if (cfg.css) {
+      var link = document.createElement("link");
+      link.rel = "stylesheet";
+      link.href = cfg.css;
+      document.head.appendChild(link);
+    }
+    renderStub();
+
+    var wantsOpen = api.q.some(function (call) { return call[0] === "open"; });
+    if (wantsOpen) load();
+    else if (hasConversation() || proactivePage()) (window.requestIdleCallback || setTimeout)(load);
+  }
+
+  if (document.readyState === "loading") document.addEventListener("DOMContentLoaded", start);
+  else start();
+})();
+"#;
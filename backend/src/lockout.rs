@@ -0,0 +1,170 @@
+// ============================================================================
+// CHỐNG DÒ PIN - đếm lần nhập sai theo (shop, IP) trong Redis
+// FREE_ATTEMPTS lần sai đầu không bị chờ; sau đó mỗi lần sai phải đợi 2, 4, 8... giây trước khi thử tiếp.
+// Sai tới MAX_ATTEMPTS → khoá LOCKOUT_SECS. Lần thử được ghi (Lua, nguyên tử) trước khi kiểm tra PIN.
+// Bộ đếm tự xoá sau FAILURE_WINDOW_SECS kể từ lần sai cuối, đăng nhập đúng thì xoá ngay.
+// Redis lỗi → cho qua (không khoá nhầm cả shop)
+// ============================================================================
+
+use redis::AsyncCommands;
+use std::future::Future;
+
+use crate::agents;
+use crate::contract::ContractError;
+use crate::redis_conn::RedisConn;
+use crate::websocket::WebSocketState;
+
+const FREE_ATTEMPTS: u64 = 3;
+const MAX_ATTEMPTS: u64 = 10;
+const LOCKOUT_SECS: u64 = 15 * 60;
+const FAILURE_WINDOW_SECS: u64 = 3600;
+
+pub enum PinCheck<T = String> {
+    /// Đúng PIN - tên shop (đăng nhập nhân viên: kèm session token)
+    Valid(T),
+    Invalid { remaining_attempts: u32, retry_after_secs: u32 },
+    /// Đang bị khoá / chưa hết thời gian chờ - không kiểm tra PIN
+    Locked { retry_after_secs: u32 },
+}
+
+// Kiểm tra trên mọi request admin → giữ một kết nối dùng chung (redis_conn.rs)
+pub struct LoginGuard {
+    redis: RedisConn,
+    reserve: redis::Script,
+}
+
+enum Attempt {
+    Locked { retry_after_secs: u32 },
+    /// Lần thử thứ `failures` tính từ lần đúng gần nhất; `delay` = số giây phải chờ trước lần kế tiếp
+    Reserved { failures: u64, delay: u64 },
+}
+
+// KEYS[1] = bộ đếm, KEYS[2] = khoá → {0, ttl} khi đang khoá, không thì {số lần, số giây chờ}
+const RESERVE_SCRIPT: &str = r"
+local ttl = redis.call('TTL', KEYS[2])
+if ttl > 0 then return {0, ttl} end
+local failures = redis.call('INCR', KEYS[1])
+redis.call('EXPIRE', KEYS[1], ARGV[1])
+local delay = tonumber(ARGV[math.min(failures, #ARGV - 1) + 1])
+if delay > 0 then redis.call('SET', KEYS[2], failures, 'EX', delay) end
+return {failures, delay}
+";
+
+impl LoginGuard {
+    pub fn new(redis_url: &str) -> redis::RedisResult<Self> {
+        Ok(Self { redis: RedisConn::open(redis_url)?, reserve: redis::Script::new(RESERVE_SCRIPT) })
+    }
+
+    /// Giữ chỗ một lần thử trước khi kiểm tra (tính luôn là một lần sai, đúng thì reset xoá).
+    /// Đọc khoá + tăng bộ đếm + đặt thời gian chờ trong một script → các lần thử song song không lọt qua
+    async fn reserve(&self, shop_id: &str, ip: &str) -> Attempt {
+        // ARGV[1] = FAILURE_WINDOW_SECS, ARGV[n + 1] = delay_for(n)
+        let mut invocation = self.reserve.key(fail_key(shop_id, ip));
+        invocation.key(lock_key(shop_id, ip)).arg(FAILURE_WINDOW_SECS);
+        for failures in 1..=MAX_ATTEMPTS {
+            invocation.arg(delay_for(failures));
+        }
+        let result: redis::RedisResult<(u64, u64)> = async {
+            let mut conn = self.redis.get().await?;
+            invocation.invoke_async(&mut conn).await
+        }.await;
+        match self.redis.checked(result).await {
+            Ok((0, ttl)) => Attempt::Locked { retry_after_secs: ttl as u32 },
+            Ok((failures, delay)) => {
+                if failures >= MAX_ATTEMPTS {
+                    println!("🔒 Admin login locked for {}s: shop={}, ip={} ({} failures)", delay, shop_id, ip, failures);
+                }
+                Attempt::Reserved { failures, delay }
+            }
+            Err(e) => {
+                eprintln!("❌ Login lockout check failed: {:?}", e);
+                Attempt::Reserved { failures: 0, delay: 0 }
+            }
+        }
+    }
+
+    async fn reset(&self, shop_id: &str, ip: &str) {
+        let result: redis::RedisResult<()> = async {
+            self.redis.get().await?.del(&[fail_key(shop_id, ip), lock_key(shop_id, ip)]).await
+        }.await;
+        if let Err(e) = self.redis.checked(result).await {
+            eprintln!("❌ Reset login failures failed: {:?}", e);
+        }
+    }
+}
+
+/// Kiểm tra PIN admin / session nhân viên có tính lần sai - dùng ở mọi nơi nhận PIN (đăng nhập, header, WS)
+pub async fn check_pin(state: &WebSocketState, shop_id: &str, pin: &str, ip: &str) -> Result<PinCheck, ContractError> {
+    guarded(state, shop_id, ip, agents::verify_credential(&state.repo, shop_id, pin)).await
+}
+
+/// Đăng nhập nhân viên bằng email + mật khẩu - chung bộ đếm với PIN
+pub async fn check_login(state: &WebSocketState, shop_id: &str, email: &str, password: &str, ip: &str) -> Result<PinCheck<(String, String)>, ContractError> {
+    guarded(state, shop_id, ip, agents::login(&state.repo, shop_id, email, password)).await
+}
+
+// Đang bị khoá → không chạy `verify`; mỗi lần chạy `verify` đã được tính trước là một lần sai; đúng → xoá bộ đếm
+async fn guarded<T>(
+    state: &WebSocketState,
+    shop_id: &str,
+    ip: &str,
+    verify: impl Future<Output = Result<Option<T>, ContractError>>,
+) -> Result<PinCheck<T>, ContractError> {
+    let (failures, delay) = match state.logins.reserve(shop_id, ip).await {
+        Attempt::Locked { retry_after_secs } => return Ok(PinCheck::Locked { retry_after_secs }),
+        Attempt::Reserved { failures, delay } => (failures, delay),
+    };
+    match verify.await? {
+        Some(valid) => {
+            state.logins.reset(shop_id, ip).await;
+            Ok(PinCheck::Valid(valid))
+        }
+        _ => Ok(PinCheck::Invalid {
+            remaining_attempts: MAX_ATTEMPTS.saturating_sub(failures) as u32,
+            retry_after_secs: delay as u32,
+        }),
+    }
+}
+
+// Lần sai thứ n → số giây chờ
+fn delay_for(failures: u64) -> u64 {
+    if failures >= MAX_ATTEMPTS {
+        LOCKOUT_SECS
+    } else if failures >= FREE_ATTEMPTS {
+        (1u64 << (failures - FREE_ATTEMPTS + 1)).min(LOCKOUT_SECS)
+    } else {
+        0
+    }
+}
+
+fn fail_key(shop_id: &str, ip: &str) -> String {
+    format!("login:fail:{}:{}", shop_id, ip)
+}
+
+fn lock_key(shop_id: &str, ip: &str) -> String {
+    format!("login:lock:{}:{}", shop_id, ip)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn free_attempts_have_no_delay() {
+        for failures in 0..FREE_ATTEMPTS {
+            assert_eq!(delay_for(failures), 0);
+        }
+    }
+
+    #[test]
+    fn delay_doubles_then_locks() {
+        assert_eq!(delay_for(FREE_ATTEMPTS), 2);
+        assert_eq!(delay_for(FREE_ATTEMPTS + 1), 4);
+        assert_eq!(delay_for(FREE_ATTEMPTS + 2), 8);
+        for failures in FREE_ATTEMPTS..MAX_ATTEMPTS - 1 {
+            assert_eq!(delay_for(failures + 1), (delay_for(failures) * 2).min(LOCKOUT_SECS));
+        }
+        assert_eq!(delay_for(MAX_ATTEMPTS), LOCKOUT_SECS);
+        assert_eq!(delay_for(u64::MAX), LOCKOUT_SECS);
+    }
+}
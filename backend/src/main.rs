@@ -1,112 +1,454 @@
+mod activity;
+mod agents;
+mod analytics;
+mod api_keys;
+mod attachments;
+mod auth;
+mod backup;
+mod batch;
+mod blob;
+mod blocklist;
+mod bus;
+mod campaigns;
+mod cluster;
+mod config;
 mod contract;
+mod conversation;
 mod db;
+mod drain;
+mod edit;
+mod feed;
+mod filter;
+mod flags;
+mod flows;
+mod gdpr;
+mod geoip;
+mod handoff;
+mod idempotency;
+mod identity;
+mod integration;
+mod kafka;
+mod live;
+mod loader;
+mod lockout;
+mod maintenance;
+mod merge;
+mod migrations;
+mod notes;
+mod notify;
+mod preferences;
+mod presence;
+mod proactive;
+mod quota;
+mod redis_conn;
+mod reminders;
+mod request_id;
+mod retention;
+mod routing;
+mod scan;
+mod scheduled;
+mod search;
+mod seed;
+mod session;
+mod sentiment;
+mod settings;
+mod sla;
+mod state;
+mod static_files;
+mod summary;
+mod telemetry;
+mod thumbnail;
+mod views;
+mod visitor;
 mod websocket;
 
-use axum::{Router, routing::{get, post}, extract::State, body::Bytes, http::StatusCode, response::IntoResponse};
+use axum::{Router, routing::{get, post, put, patch, delete}, extract::{ConnectInfo, DefaultBodyLimit, State}, body::Bytes, http::{header, HeaderMap, HeaderName, StatusCode}, middleware, response::IntoResponse};
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tower_http::cors::{CorsLayer, Any};
+use tower_http::cors::{AllowOrigin, CorsLayer, Any};
 use prost::Message as ProstMessage;
 
+use auth::{client_ip, verify_body_pin};
 use contract::*;
+use lockout::PinCheck;
 use db::AstraRepo;
-
-struct AppState {
-    repo: Arc<AstraRepo>,
-    ws_state: Arc<websocket::WebSocketState>,
-}
+use state::AppState;
 
 #[tokio::main]
 async fn main() {
+    // --print-schema=cql|postgres: in DDL cho Scylla/Postgres rồi thoát (không cần DB)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(dialect) = args.iter().find_map(|a| a.strip_prefix("--print-schema=")) {
+        match dialect {
+            "cql" => print!("{}", migrations::render_cql()),
+            "postgres" => print!("{}", migrations::render_postgres()),
+            other => {
+                eprintln!("❌ Unknown schema dialect '{}' (expected cql or postgres)", other);
+                std::process::exit(2);
+            }
+        }
+        return;
+    }
+    let migrate_only = args.iter().any(|a| a == "--migrate-only");
+    let seed_demo = args.iter().any(|a| a == "--seed-demo");
+    // backup/restore: chạy xong thì thoát, không mở server
+    let backup_command = backup::Command::from_args(&args).unwrap_or_else(|e| {
+        eprintln!("❌ {}", e);
+        std::process::exit(2);
+    });
+    
     println!("🚀 TurboChat Backend Starting...");
     dotenvy::dotenv().ok();
+    // Cấu hình nạp lại nóng được (config.rs) - sai thì dừng ngay lúc khởi động
+    config::current();
     
     // Connect ScyllaDB
     let repo = Arc::new(AstraRepo::new().await
     .expect("❌ AstraDB connection failed"));
 println!("✅ AstraDB connected");
     
+    // Tạo/cập nhật schema (MIGRATE_ON_START=false để tắt khi chạy migrate riêng)
+    let migrate_on_start = std::env::var("MIGRATE_ON_START").map(|v| v != "false" && v != "0").unwrap_or(true);
+    if migrate_only || migrate_on_start {
+        migrations::run(&repo).await.expect("❌ Schema migration failed");
+    }
+    // Dữ liệu mẫu cho dev (shop demo123)
+    if seed_demo {
+        seed::seed_demo(&repo).await.expect("❌ Demo seed failed");
+    }
+    if migrate_only {
+        return;
+    }
+    if let Some(command) = backup_command {
+        if let Err(e) = backup::run(&repo, command).await {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    
+    // Tracing phân tán (OTEL_EXPORTER_OTLP_ENDPOINT)
+    let telemetry = telemetry::Telemetry::from_env();
+    
     // Setup WebSocket + Redis
     let redis_url = std::env::var("REDIS_URL").expect("❌ REDIS_URL not found in .env");
 let ws_state = Arc::new(websocket::WebSocketState::new(&redis_url, repo.clone()).await
         .expect("❌ Redis connection failed"));
     println!("✅ Redis connected");
     
-    // Redis subscriber task
+    // Nhận frame từ broker (Redis / NATS) → các kết nối trên node này
     let ws_clone = Arc::clone(&ws_state);
-    tokio::spawn(async move { websocket::redis_subscriber_task(ws_clone).await });
+    tokio::spawn(async move { websocket::bus_subscriber_task(ws_clone).await });
+    
+    // SIGHUP / file cấu hình đổi → nạp lại cấu hình
+    tokio::spawn(config::reload_task(ws_state.clone()));
+    
+    // Cluster: gia hạn NODE_ID + nhận frame định tuyến riêng cho node này
+    tokio::spawn(cluster::heartbeat_task(ws_state.clone()));
+    tokio::spawn(cluster::node_subscriber_task(ws_state.clone()));
+    
+    // Giờ làm việc → push trạng thái online/offline
+    tokio::spawn(presence::presence_task(ws_state.clone()));
+    
+    // Gửi tin hẹn giờ tới hạn
+    tokio::spawn(scheduled::scheduler_task(ws_state.clone()));
+    
+    // Chiến dịch tới giờ → gửi dần cho segment
+    tokio::spawn(campaigns::campaign_task(ws_state.clone()));
+    
+    // Hội thoại tạm ẩn tới giờ nhắc
+    tokio::spawn(reminders::reminder_task(ws_state.clone()));
+    
+    // Cảnh báo SLA phản hồi đầu
+    tokio::spawn(sla::sla_task(ws_state.clone()));
+    
+    // Gửi lại tin bị giữ do vượt hạn mức
+    tokio::spawn(quota::quota_task(ws_state.clone()));
+    
+    // Retention sweeper task
+    let retention_stats = Arc::new(retention::RetentionStatsStore::default());
+    tokio::spawn(retention::retention_sweeper_task(ws_state.clone(), retention_stats.clone()));
+    
+    // Analytics job
+    tokio::spawn(analytics::analytics_task(ws_state.clone()));
+    
+    // Bundle widget cho /widget.js (chưa build thì loader trả 503)
+    let widget_assets = loader::WidgetAssets::from_env();
+    if widget_assets.is_none() {
+        println!("⚠️ Widget bundle not found, /widget.js disabled (set WIDGET_DIST_DIR)");
+    }
+    
+    // /admin + bundle widget (tower-http ServeDir)
+    let static_routes = static_files::router(widget_assets.as_ref());
     
-    let state = Arc::new(AppState { repo, ws_state: ws_state.clone() });
+    let state = Arc::new(AppState {
+        repo,
+        ws_state: ws_state.clone(),
+        retention_stats,
+        widget_assets,
+        llm: summary::LlmClient::from_env(),
+        scanner: scan::from_env(),
+        invites: agents::InviteLinks::from_env(),
+        ops_token: auth::OpsToken::from_env(),
+    });
     
-    // CORS - cho phép mọi nguồn
+    // CORS - CORS_ORIGINS (trống = mọi nguồn), xét theo cấu hình hiện tại ở từng request
     let cors = CorsLayer::new()
-        .allow_origin(Any)
+        .allow_origin(AllowOrigin::predicate(|origin, _| config::current().allows_cors(origin)))
         .allow_methods(Any)
-        .allow_headers(Any);
+        .allow_headers(Any)
+        .expose_headers([HeaderName::from_static("idempotent-replayed"), HeaderName::from_static(request_id::HEADER)]);
     
     let app = Router::new()
         .route("/ws", get(websocket::ws_handler))
-        .with_state(ws_state)
+        .route("/healthz", get(drain::health_handler))
+        .with_state(ws_state.clone())
         .route("/auth", post(auth_handler))
+        .route("/admins", get(agents::list_agents_handler))
+        .route("/admins/invite", post(agents::invite_handler))
+        .route("/admins/accept", post(agents::accept_handler))
         .route("/guests", post(guests_handler))
+        .route("/feed", get(feed::feed_handler))
         .route("/sync", post(sync_handler))
+        .route("/settings", post(settings::settings_handler))
+        .route("/flags", get(flags::get_flags_handler).put(flags::put_flags_handler))
+        .route("/preferences", get(preferences::get_preferences_handler).put(preferences::put_preferences_handler))
+        .route("/views", get(views::get_views_handler).put(views::put_views_handler))
+        .route("/routing-rules", get(routing::get_rules_handler).put(routing::put_rules_handler))
+        .route("/triggers", get(proactive::get_triggers_handler).put(proactive::put_triggers_handler))
+        .route("/flows", get(flows::get_flows_handler).put(flows::put_flows_handler))
+        .route("/reports", get(analytics::reports_handler))
+        .route("/reports/export.csv", get(analytics::export_csv_handler))
+        .route("/usage", get(quota::usage_handler))
+        .route("/guests/:id", delete(gdpr::erase_guest_handler))
+        .route("/guests/:id/data", get(gdpr::export_guest_handler))
+        .route("/guests/:id/search", get(search::search_handler))
+        .route("/guests/:id/status", put(conversation::set_status_handler))
+        .route("/guests/:id/transfer", post(conversation::transfer_handler))
+        .route("/guests/:id/notes", get(notes::list_notes_handler).post(notes::create_note_handler))
+        .route("/guests/:id/notes/:note_id", patch(notes::update_note_handler).delete(notes::delete_note_handler))
+        .route("/guests/:id/scheduled", get(scheduled::list_scheduled_handler).post(scheduled::create_scheduled_handler))
+        .route("/guests/:id/scheduled/:scheduled_id", delete(scheduled::cancel_scheduled_handler))
+        .route("/guests/:id/reminder", put(reminders::set_reminder_handler).delete(reminders::cancel_reminder_handler))
+        .route("/reminders", get(reminders::list_reminders_handler))
+        .route("/conversations/bulk", post(conversation::bulk_handler))
+        .route("/conversations/:id/summary", post(summary::summarize_handler))
+        .route("/guests/:id/block", post(blocklist::block_guest_handler).delete(blocklist::unblock_guest_handler))
+        .route("/blocks", get(blocklist::list_blocks_handler))
+        .route("/live", get(live::live_visitors_handler))
+        .route("/campaigns", get(campaigns::list_campaigns_handler).post(campaigns::create_campaign_handler))
+        .route("/campaigns/preview", post(campaigns::preview_handler))
+        .route("/campaigns/:id", delete(campaigns::cancel_campaign_handler))
+        .route("/flagged", get(filter::list_flagged_handler))
+        .route("/flagged/:message_id", delete(filter::dismiss_flagged_handler))
+        .route("/messages/:guest_id/:message_id", patch(edit::edit_message_handler).delete(edit::delete_message_handler))
+        .route("/messages/:guest_id/:message_id/history", get(edit::history_handler))
+        .route("/attachments", post(attachments::upload_handler).layer(DefaultBodyLimit::max(attachments::max_upload_bytes())))
+        .route("/attachments/*key", get(attachments::download_handler))
+        .route("/widget.js", get(loader::widget_js_handler))
+        .route("/api-keys", get(api_keys::list_keys_handler).post(api_keys::create_key_handler))
+        .route("/api-keys/:key_id", delete(api_keys::revoke_key_handler))
+        .route("/api-keys/:key_id/rotate", post(api_keys::rotate_key_handler))
+        .route("/identity-secret", get(identity::get_secret_handler))
+        .route("/identity-secret/rotate", post(identity::rotate_secret_handler))
+        // Tích hợp server-to-server - xác thực bằng API key (Authorization: Bearer)
+        .route("/v1/guests", get(integration::list_guests_handler))
+        .route("/v1/guests/:id", get(integration::get_guest_handler))
+        .route("/v1/guests/:id/messages", get(integration::list_messages_handler).post(integration::send_message_handler))
+        // Vận hành (OPS_TOKEN)
+        .route("/ops/drain", post(drain::drain_handler))
+        .route("/ops/maintenance", get(maintenance::get_maintenance_handler).put(maintenance::put_maintenance_handler))
+        // Idempotency-Key: gửi lại request ghi → trả response cũ
+        .layer(middleware::from_fn_with_state(state.clone(), idempotency::middleware))
         .with_state(state)
-        .layer(cors);
+        .merge(static_routes)
+        .layer(cors)
+        // Ngoài cùng: mọi response (kể cả lỗi CORS / idempotency) đều có x-request-id
+        .layer(middleware::from_fn(request_id::middleware));
     
-    println!("🌐 Server: http://localhost:8080");
-    println!("📡 WebSocket: ws://localhost:8080/ws?shop_id=demo123");
+    // PORT (mặc định 8080)
+    let port = std::env::var("PORT").ok().and_then(|v| v.parse::<u16>().ok()).unwrap_or(8080);
+    println!("🌐 Server: http://localhost:{}", port);
+    println!("📡 WebSocket: ws://localhost:{}/ws?shop_id=demo123", port);
+    println!("🗂️ Admin: http://localhost:{}/admin/", port);
     
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await.unwrap();
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown_signal(ws_state.clone()))
+        .await
+        .unwrap();
+    
+    // Ghi nốt tin đang gom trước khi thoát
+    ws_state.inserts.shutdown().await;
+    if let Some(telemetry) = telemetry {
+        telemetry.shutdown();
+    }
+    println!("👋 TurboChat Backend stopped");
 }
 
-// POST /auth - Xác thực admin
-async fn auth_handler(State(state): State<Arc<AppState>>, body: Bytes) -> impl IntoResponse {
+// Ctrl+C, SIGTERM hoặc POST /ops/drain → xả kết nối WS rồi mới dừng HTTP (drain.rs)
+async fn shutdown_signal(ws_state: Arc<websocket::WebSocketState>) {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        if let Ok(mut sig) = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            sig.recv().await;
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+        _ = ws_state.drain.requested() => {},
+    }
+    println!("🛑 Shutdown signal received, draining...");
+    drain::run(&ws_state).await;
+}
+
+// POST /auth - Xác thực admin (sai nhiều lần → phải chờ / bị khoá, xem lockout.rs)
+async fn auth_handler(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
     let req = match AdminAuthRequest::decode(&body[..]) {
         Ok(r) => r,
-        Err(_) => return (StatusCode::BAD_REQUEST, Bytes::new()),
+        Err(_) => return (StatusCode::BAD_REQUEST, HeaderMap::new(), Bytes::new()),
     };
     
-    let resp = match state.repo.verify_admin(&req.shop_id, &req.admin_pin).await {
-        Ok(Some(name)) => AdminAuthResponse { success: true, shop_name: name, error: String::new() },
-        Ok(None) => AdminAuthResponse { success: false, shop_name: String::new(), error: "Invalid PIN".into() },
-        Err(e) => AdminAuthResponse { success: false, shop_name: String::new(), error: e.to_string() },
+    let ip = client_ip(&headers, &peer);
+    // Có email → nhân viên (mật khẩu), không → PIN chủ shop
+    let check = match req.email.trim() {
+        "" => lockout::check_pin(&state.ws_state, &req.shop_id, &req.admin_pin, &ip).await
+            .map(|check| match check {
+                PinCheck::Valid(name) => PinCheck::Valid((name, String::new())),
+                PinCheck::Invalid { remaining_attempts, retry_after_secs } => PinCheck::Invalid { remaining_attempts, retry_after_secs },
+                PinCheck::Locked { retry_after_secs } => PinCheck::Locked { retry_after_secs },
+            }),
+        email => lockout::check_login(&state.ws_state, &req.shop_id, email, &req.admin_pin, &ip).await,
+    };
+    let (status, resp) = match check {
+        Ok(PinCheck::Valid((name, session_token))) => (StatusCode::OK, AdminAuthResponse { success: true, shop_name: name, session_token, ..Default::default() }),
+        Ok(PinCheck::Invalid { remaining_attempts, retry_after_secs }) => (StatusCode::OK, AdminAuthResponse {
+            success: false,
+            error: "Invalid PIN".into(),
+            request_id: request_id::current(),
+            remaining_attempts,
+            retry_after_secs,
+            ..Default::default()
+        }),
+        Ok(PinCheck::Locked { retry_after_secs }) => (StatusCode::TOO_MANY_REQUESTS, AdminAuthResponse {
+            success: false,
+            error: "Too many failed attempts".into(),
+            request_id: request_id::current(),
+            retry_after_secs,
+            ..Default::default()
+        }),
+        Err(e) => (StatusCode::OK, AdminAuthResponse { success: false, error: e.to_string(), request_id: request_id::current(), ..Default::default() }),
     };
     
-    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+    let mut response_headers = HeaderMap::new();
+    if resp.retry_after_secs > 0 {
+        response_headers.insert(header::RETRY_AFTER, resp.retry_after_secs.into());
+    }
+    (status, response_headers, Bytes::from(resp.encode_to_vec()))
+}
+
+// DB quá hạn → 504 (client thử lại được), lỗi khác → 500
+fn db_error_status(e: &ContractError) -> StatusCode {
+    match e {
+        ContractError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
 }
 
 // POST /guests - Lấy danh sách guest
-async fn guests_handler(State(state): State<Arc<AppState>>, body: Bytes) -> impl IntoResponse {
+async fn guests_handler(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
     let req = match GuestListRequest::decode(&body[..]) {
         Ok(r) => r,
         Err(_) => return (StatusCode::BAD_REQUEST, Bytes::new()),
     };
     
     // Verify admin trước
-    if state.repo.verify_admin(&req.shop_id, &req.admin_pin).await.ok().flatten().is_none() {
-        let resp = GuestListResponse { success: false, guests: vec![], error: "Unauthorized".into() };
-        return (StatusCode::UNAUTHORIZED, Bytes::from(resp.encode_to_vec()));
+    if let Err(status) = verify_body_pin(&state, &req.shop_id, &req.admin_pin, &client_ip(&headers, &peer)).await {
+        let resp = GuestListResponse { success: false, error: "Unauthorized".into(), request_id: request_id::current(), ..Default::default() };
+        return (status, Bytes::from(resp.encode_to_vec()));
     }
     
-    let guests = state.repo.get_guests(&req.shop_id).await.unwrap_or_default();
-    let resp = GuestListResponse { success: true, guests, error: String::new() };
+    let active_since = match req.active_within_days {
+        0 => 0,
+        days => (chrono::Utc::now() - chrono::Duration::days(days as i64)).timestamp_micros().max(0) as u64,
+    };
+    let cursor = Some(req.cursor.as_str()).filter(|c| !c.is_empty());
+    let page = match state.repo.list_guests_page(&req.shop_id, req.limit, cursor, active_since).await {
+        Ok(page) => page,
+        Err(e) => {
+            eprintln!("❌ Load guests for {} failed: {:?}", req.shop_id, e);
+            let resp = GuestListResponse { success: false, error: e.to_string(), request_id: request_id::current(), ..Default::default() };
+            return (db_error_status(&e), Bytes::from(resp.encode_to_vec()));
+        }
+    };
+    let resp = GuestListResponse {
+        success: true,
+        guests: page.guests,
+        next_cursor: page.next_cursor.unwrap_or_default(),
+        ..Default::default()
+    };
     (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
 }
 
 // POST /sync - Lấy tin nhắn
-async fn sync_handler(State(state): State<Arc<AppState>>, body: Bytes) -> impl IntoResponse {
+async fn sync_handler(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
     let req = match SyncRequest::decode(&body[..]) {
         Ok(r) => r,
         Err(_) => return (StatusCode::BAD_REQUEST, Bytes::new()),
     };
     
-    let messages = state.repo.fetch_messages(&req.shop_id, req.guest_id, req.after_message_id, req.limit).await
-        .unwrap_or_default();
+    // Nội dung tin đã xoá chỉ trả cho admin
+    if req.include_deleted {
+        if let Err(status) = verify_body_pin(&state, &req.shop_id, &req.admin_pin, &client_ip(&headers, &peer)).await {
+            return (status, Bytes::new());
+        }
+    }
+    
+    // Khách đã gộp (cùng tài khoản website) → hội thoại gồm tin của guest gốc + mọi alias
+    let page = match async {
+        let guests = merge::conversation_guests(&state.ws_state, &req.shop_id, req.guest_id).await?;
+        state.repo.fetch_conversation(&req.shop_id, &guests, req.after_message_id, req.limit, Some(&req.page_state)).await
+    }.await {
+        Ok(page) => page,
+        Err(e) => {
+            eprintln!("❌ Sync for guest {} failed: {:?}", req.guest_id, e);
+            return (db_error_status(&e), Bytes::new());
+        }
+    };
+    let mut messages = page.messages;
+    if !req.include_deleted {
+        messages.iter_mut().filter(|m| m.deleted_at_us > 0).for_each(|m| m.redact());
+    }
+    state.repo.hydrate_replies(&mut messages).await;
+    attachments::sign_urls(state.ws_state.blobs.as_ref(), &mut messages);
     
     let mut resp = SyncResponse {
         messages,
         server_timestamp_us: std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_micros() as u64,
-        has_more: false,
+        has_more: page.page_state.is_some(),
+        page_state: page.page_state.unwrap_or_default(),
         crc32: 0,
     };
     resp.finalize();
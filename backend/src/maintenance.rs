@@ -0,0 +1,203 @@
+// ============================================================================
+// CHẾ ĐỘ BẢO TRÌ - vận hành bật/tắt qua PUT /ops/maintenance (OPS_TOKEN)
+// - Trạng thái lưu ở Redis (khoá ops:maintenance) → node khởi động sau / node lỡ pub/sub vẫn biết
+//   (heartbeat của cluster đọc lại định kỳ)
+// - Bật/tắt → phát ServerNotice qua kênh ops:notice tới mọi node → mọi kết nối (cả cụm, mọi shop) hiện/gỡ banner
+// - Đang bảo trì: tin mới (WS, /v1) bị từ chối, người gửi nhận ServerNotice kèm message_id để gửi lại sau
+// ============================================================================
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use prost::Message as ProstMessage;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+use crate::auth::OpsAuth;
+use crate::contract::{Message as ChatMessage, NoticeKind, ServerNotice, WsEnvelope, ws_envelope};
+use crate::db::now_us;
+use crate::redis_conn::RedisConn;
+use crate::state::AppState;
+use crate::websocket::{self, WebSocketState};
+
+pub const NOTICE_CHANNEL: &str = "ops:notice";
+const STATE_KEY: &str = "ops:maintenance";
+// Retry-After cho request bị từ chối khi chưa biết giờ xong
+const DEFAULT_RETRY_SECS: u64 = 60;
+const DEFAULT_TEXT: &str = "Hệ thống đang bảo trì, vui lòng thử lại sau ít phút.";
+const MAX_TEXT_CHARS: usize = 500;
+
+/// Trạng thái bảo trì của node (bản sao từ Redis)
+pub struct Maintenance {
+    redis: RedisConn,
+    current: Mutex<Option<ServerNotice>>,
+}
+
+impl Maintenance {
+    pub fn new(redis_url: &str) -> redis::RedisResult<Self> {
+        Ok(Self { redis: RedisConn::open(redis_url)?, current: Mutex::new(None) })
+    }
+
+    /// Banner đang bật (None = bình thường)
+    pub fn notice(&self) -> Option<ServerNotice> {
+        self.current.lock().unwrap().clone()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.current.lock().unwrap().is_some()
+    }
+
+    /// Số giây client nên chờ trước khi gửi lại
+    pub fn retry_after_secs(&self) -> u64 {
+        let until_us = self.current.lock().unwrap().as_ref().map_or(0, |n| n.until_us);
+        let now = now_us();
+        if until_us > now { (until_us - now).div_ceil(1_000_000) } else { DEFAULT_RETRY_SECS }
+    }
+
+    // Cập nhật bản sao; trả về true nếu trạng thái đổi
+    fn apply(&self, notice: Option<ServerNotice>) -> bool {
+        let mut current = self.current.lock().unwrap();
+        let changed = *current != notice;
+        *current = notice;
+        changed
+    }
+
+    /// Đọc lại từ Redis (khởi động / heartbeat); true = trạng thái đổi (đã lỡ frame phát qua pub/sub).
+    /// Lỗi Redis → giữ trạng thái cũ
+    pub async fn refresh(&self) -> bool {
+        let result: redis::RedisResult<Option<Vec<u8>>> = async { self.redis.get().await?.get(STATE_KEY).await }.await;
+        match self.redis.checked(result).await {
+            Ok(bytes) => {
+                let notice = bytes.and_then(|b| ServerNotice::decode(&b[..]).ok()).filter(|n| n.active);
+                let active = notice.is_some();
+                let changed = self.apply(notice);
+                if changed {
+                    println!("🛠️ Maintenance mode {} (from Redis)", if active { "on" } else { "off" });
+                }
+                changed
+            }
+            Err(e) => {
+                eprintln!("❌ Load maintenance state failed: {:?}", e);
+                false
+            }
+        }
+    }
+
+    /// Frame banner hiện tại cho mọi kết nối (không bảo trì = frame gỡ banner)
+    pub fn banner_frame(&self) -> WsEnvelope {
+        let notice = self.notice().unwrap_or(ServerNotice { kind: NoticeKind::Maintenance as i32, ..Default::default() });
+        WsEnvelope::server_notice(String::new(), 0, false, notice)
+    }
+
+    /// Frame từ kênh ops:notice (node nào bật/tắt cũng tới đây) → cập nhật bản sao
+    pub fn on_broadcast(&self, payload: &[u8]) {
+        let Ok(envelope) = WsEnvelope::decode(payload) else { return };
+        if let Some(ws_envelope::Frame::ServerNotice(notice)) = envelope.frame {
+            if notice.kind() == NoticeKind::Maintenance {
+                self.apply(Some(notice).filter(|n| n.active));
+            }
+        }
+    }
+
+    // Ghi Redis rồi phát cho cả cụm (kể cả node này, qua subscriber)
+    async fn publish(&self, notice: &ServerNotice) -> redis::RedisResult<()> {
+        let result = async {
+            let mut conn = self.redis.get().await?;
+            if notice.active {
+                let _: () = conn.set(STATE_KEY, notice.encode_to_vec()).await?;
+            } else {
+                let _: () = conn.del(STATE_KEY).await?;
+            }
+            let envelope = WsEnvelope::server_notice(String::new(), 0, false, notice.clone());
+            conn.publish(NOTICE_CHANNEL, envelope.encode_to_vec()).await
+        }.await;
+        self.redis.checked(result).await
+    }
+}
+
+/// Từ chối tin mới khi đang bảo trì: báo riêng người gửi (khách qua node giữ kết nối, admin qua broker)
+pub async fn reject_send(state: &WebSocketState, msg: &ChatMessage) {
+    let Some(mut notice) = state.maintenance.notice() else { return };
+    println!("🛠️ Message from {} refused during maintenance: shop={}, guest={}", msg.sender_type, msg.shop_id, msg.guest_id);
+    notice.rejected_message_id = msg.message_id;
+    let admin_only = msg.sender_type != "guest";
+    let envelope = WsEnvelope::server_notice(msg.shop_id.clone(), msg.guest_id, admin_only, notice);
+    let result = if admin_only { websocket::publish_envelope(state, &envelope).await } else { state.cluster.send_to_guest(&envelope).await.map_err(Into::into) };
+    if let Err(e) = result {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+}
+
+/// 503 + Retry-After cho endpoint HTTP gửi tin (/v1) khi đang bảo trì
+pub fn unavailable(state: &WebSocketState) -> Response {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::RETRY_AFTER, state.maintenance.retry_after_secs().into());
+    (StatusCode::SERVICE_UNAVAILABLE, headers, "Maintenance in progress").into_response()
+}
+
+// ============================================================================
+// ENDPOINT VẬN HÀNH
+//   GET /ops/maintenance              → MaintenanceStatus
+//   PUT /ops/maintenance  {"enabled": true, "message": "...", "until": <unix giây, 0 = chưa rõ>}
+// ============================================================================
+
+#[derive(Deserialize)]
+pub struct MaintenanceRequest {
+    pub enabled: bool,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub until: u64,
+}
+
+#[derive(Serialize)]
+pub struct MaintenanceStatus {
+    pub enabled: bool,
+    pub message: String,
+    pub until: u64,
+}
+
+impl MaintenanceStatus {
+    fn from_notice(notice: Option<ServerNotice>) -> Self {
+        match notice {
+            Some(n) => Self { enabled: true, message: n.text, until: n.until_us / 1_000_000 },
+            None => Self { enabled: false, message: String::new(), until: 0 },
+        }
+    }
+}
+
+// GET /ops/maintenance
+pub async fn get_maintenance_handler(State(state): State<Arc<AppState>>, _ops: OpsAuth) -> impl IntoResponse {
+    Json(MaintenanceStatus::from_notice(state.ws_state.maintenance.notice()))
+}
+
+// PUT /ops/maintenance
+pub async fn put_maintenance_handler(
+    State(state): State<Arc<AppState>>,
+    _ops: OpsAuth,
+    Json(req): Json<MaintenanceRequest>,
+) -> Response {
+    let text = match req.message.trim() {
+        "" => DEFAULT_TEXT.to_string(),
+        text => text.chars().take(MAX_TEXT_CHARS).collect(),
+    };
+    let notice = ServerNotice {
+        kind: NoticeKind::Maintenance as i32,
+        active: req.enabled,
+        text: if req.enabled { text } else { String::new() },
+        until_us: if req.enabled { req.until * 1_000_000 } else { 0 },
+        rejected_message_id: 0,
+    };
+    if let Err(e) = state.ws_state.maintenance.publish(&notice).await {
+        eprintln!("❌ Set maintenance mode failed: {:?}", e);
+        return StatusCode::SERVICE_UNAVAILABLE.into_response();
+    }
+    // Node này áp dụng ngay, không chờ vòng qua pub/sub
+    state.ws_state.maintenance.apply(Some(notice.clone()).filter(|n| n.active));
+    println!("🛠️ Maintenance mode {} by operator", if req.enabled { "ON" } else { "OFF" });
+    Json(MaintenanceStatus::from_notice(state.ws_state.maintenance.notice())).into_response()
+}
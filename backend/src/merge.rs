@@ -0,0 +1,69 @@
+// ============================================================================
+// GỘP GUEST ĐA THIẾT BỊ - cùng một tài khoản website (identity.rs) chat từ điện thoại và laptop
+// - Guest gốc = guest gắn với user_id sớm nhất; guest gắn sau được ghi alias (guest_aliases,
+//   guests.merged_into) - tin cũ giữ nguyên guest_id, /sync đọc gộp gốc + alias theo message_id
+// - Kết nối WS / /sync bằng guest_id alias → tự đổi sang guest gốc (widget cũ vẫn chạy đúng)
+// - Lúc gộp: báo GuestMerged cho kết nối của alias (widget lưu id gốc rồi nối lại) và admin
+// ============================================================================
+
+use crate::contract::{ContractError, GuestMerged, Message as ChatMessage, WsEnvelope};
+use crate::websocket::{publish_envelope, WebSocketState};
+
+// Chuỗi alias → gốc dài nhất đi theo (phòng dữ liệu vòng)
+const MAX_HOPS: usize = 4;
+
+/// Guest gốc của guest_id (chính nó nếu chưa gộp). Lỗi DB → coi như chưa gộp
+pub async fn canonical_guest(state: &WebSocketState, shop_id: &str, guest_id: u64) -> u64 {
+    let mut current = guest_id;
+    for _ in 0..MAX_HOPS {
+        match state.repo.merged_into(shop_id, current).await {
+            Ok(0) => break,
+            Ok(next) => current = next,
+            Err(e) => {
+                eprintln!("❌ Resolve merged guest {} failed: {:?}", current, e);
+                break;
+            }
+        }
+    }
+    current
+}
+
+/// Guest gốc + mọi alias - các guest có tin thuộc hội thoại
+pub async fn conversation_guests(state: &WebSocketState, shop_id: &str, guest_id: u64) -> Result<Vec<u64>, ContractError> {
+    let canonical = canonical_guest(state, shop_id, guest_id).await;
+    let mut guests = vec![canonical];
+    guests.extend(state.repo.guest_aliases(shop_id, canonical).await?.into_iter().filter(|&id| id != canonical));
+    Ok(guests)
+}
+
+/// Sau khi guest_id được xác minh là user_id: gộp vào guest gốc của user (nếu guest_id không phải gốc)
+pub async fn merge_identity(state: &WebSocketState, shop_id: &str, guest_id: u64, user_id: &str) -> Result<(), ContractError> {
+    let linked = state.repo.identity_guests(shop_id, user_id).await?;
+    let Some(&first) = linked.first() else { return Ok(()) };
+    let canonical = canonical_guest(state, shop_id, first).await;
+    if canonical == guest_id || canonical_guest(state, shop_id, guest_id).await == canonical {
+        return Ok(());
+    }
+
+    state.repo.merge_guest(shop_id, guest_id, canonical).await?;
+    // Tin gần nhất của alias mới hơn → sidebar admin hiện nó dưới guest gốc
+    let (alias_last, canonical_guest) = tokio::try_join!(
+        state.repo.latest_message(shop_id, guest_id),
+        state.repo.get_guest(shop_id, canonical),
+    )?;
+    if let Some(msg) = alias_last {
+        if canonical_guest.is_none_or(|g| msg.timestamp_us > g.last_message_at) {
+            state.repo.set_guest_last_message(&ChatMessage { guest_id: canonical, ..msg }).await?;
+        }
+    }
+    if let Err(e) = state.repo.insert_audit(shop_id, "guest.merge", guest_id, "identity", &canonical.to_string()).await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("🧬 Guest {} merged into {} (user {:?}, shop {})", guest_id, canonical, user_id, shop_id);
+
+    let merged = GuestMerged { from_guest_id: guest_id, into_guest_id: canonical, user_id: user_id.to_string() };
+    if let Err(e) = publish_envelope(state, &WsEnvelope::guest_merged(shop_id.to_string(), merged)).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+    Ok(())
+}
@@ -0,0 +1,569 @@
+// Schema có đánh version: chạy lúc khởi động (Astra REST schema API) hoặc xuất DDL cho Scylla (CQL) / Postgres
+use serde_json::json;
+
+use crate::contract::ContractError;
+use crate::db::AstraRepo;
+
+pub struct Table {
+    pub name: &'static str,
+    // (tên cột, kiểu CQL)
+    pub columns: &'static [(&'static str, &'static str)],
+    pub partition_key: &'static [&'static str],
+    // (cột, "ASC"/"DESC")
+    pub clustering_key: &'static [(&'static str, &'static str)],
+}
+
+pub enum Step {
+    CreateTable(Table),
+    AddColumn { table: &'static str, column: &'static str, cql_type: &'static str },
+    // Chép/biến đổi dữ liệu - chỉ chạy được qua repo (DDL xuất ra chỉ ghi chú)
+    Backfill(Backfill),
+}
+
+pub enum Backfill {
+    // messages → messages_by_day (+ message_buckets)
+    MessageDayBuckets,
+    // Tin cuối của mỗi guest → conversation_feed
+    ConversationFeed,
+}
+
+impl Backfill {
+    fn description(&self) -> &'static str {
+        match self {
+            Backfill::MessageDayBuckets => "copy messages into messages_by_day",
+            Backfill::ConversationFeed => "fill conversation_feed with each guest's latest message",
+        }
+    }
+}
+
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub steps: &'static [Step],
+}
+
+// Bảng ghi các version đã chạy - luôn tạo trước
+const SCHEMA_MIGRATIONS: Table = Table {
+    name: "schema_migrations",
+    columns: &[("version", "int"), ("name", "text"), ("applied_at", "bigint")],
+    partition_key: &["version"],
+    clustering_key: &[],
+};
+
+// Chỉ thêm migration mới ở cuối, không sửa migration đã phát hành
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial schema",
+        steps: &[
+            Step::CreateTable(Table {
+                name: "shops",
+                columns: &[("shop_id", "text"), ("shop_name", "text"), ("admin_pin", "text"), ("created_at", "bigint")],
+                partition_key: &["shop_id"],
+                clustering_key: &[],
+            }),
+            Step::CreateTable(Table {
+                name: "guests",
+                columns: &[
+                    ("shop_id", "text"), ("guest_id", "bigint"), ("guest_name", "text"), ("created_at", "bigint"),
+                    ("last_seen", "bigint"),
+                ],
+                partition_key: &["shop_id"],
+                clustering_key: &[("guest_id", "DESC")],
+            }),
+            Step::CreateTable(Table {
+                name: "messages",
+                columns: &[
+                    ("shop_id", "text"), ("guest_id", "bigint"), ("message_id", "bigint"), ("sender_type", "text"),
+                    ("content", "blob"), ("timestamp_us", "bigint"), ("content_crc", "int"),
+                ],
+                partition_key: &["shop_id", "guest_id"],
+                clustering_key: &[("message_id", "ASC")],
+            }),
+        ],
+    },
+    Migration {
+        version: 2,
+        name: "settings, moderation, analytics and profile data",
+        steps: &[
+            Step::AddColumn { table: "shops", column: "settings", cql_type: "blob" },
+            Step::AddColumn { table: "shops", column: "quota_monthly_messages", cql_type: "bigint" },
+            Step::AddColumn { table: "shops", column: "quota_connections", cql_type: "int" },
+            Step::AddColumn { table: "shops", column: "quota_storage_bytes", cql_type: "bigint" },
+            Step::AddColumn { table: "shops", column: "quota_over_limit", cql_type: "text" },
+            Step::AddColumn { table: "shops", column: "flags", cql_type: "set<text>" },
+            Step::AddColumn { table: "guests", column: "last_ip", cql_type: "text" },
+            Step::AddColumn { table: "guests", column: "email", cql_type: "text" },
+            Step::AddColumn { table: "guests", column: "status", cql_type: "text" },
+            Step::AddColumn { table: "guests", column: "status_changed_at", cql_type: "bigint" },
+            Step::AddColumn { table: "guests", column: "page_url", cql_type: "text" },
+            Step::AddColumn { table: "guests", column: "referrer", cql_type: "text" },
+            Step::AddColumn { table: "guests", column: "user_agent", cql_type: "text" },
+            Step::AddColumn { table: "guests", column: "viewport_width", cql_type: "int" },
+            Step::AddColumn { table: "guests", column: "viewport_height", cql_type: "int" },
+            Step::AddColumn { table: "guests", column: "context_at", cql_type: "bigint" },
+            Step::AddColumn { table: "guests", column: "country_code", cql_type: "text" },
+            Step::AddColumn { table: "guests", column: "country", cql_type: "text" },
+            Step::AddColumn { table: "guests", column: "city", cql_type: "text" },
+            Step::AddColumn { table: "guests", column: "tags", cql_type: "set<text>" },
+            Step::AddColumn { table: "guests", column: "assigned_to", cql_type: "text" },
+            Step::AddColumn { table: "guests", column: "awaiting_since", cql_type: "bigint" },
+            Step::AddColumn { table: "guests", column: "first_response_ms", cql_type: "bigint" },
+            Step::AddColumn { table: "guests", column: "summary", cql_type: "text" },
+            Step::AddColumn { table: "guests", column: "summary_tags", cql_type: "list<text>" },
+            Step::AddColumn { table: "guests", column: "summary_at", cql_type: "bigint" },
+            Step::AddColumn { table: "guests", column: "sentiment", cql_type: "float" },
+            Step::AddColumn { table: "guests", column: "sentiment_at", cql_type: "bigint" },
+            Step::AddColumn { table: "messages", column: "edited_at", cql_type: "bigint" },
+            Step::AddColumn { table: "messages", column: "edit_count", cql_type: "int" },
+            Step::AddColumn { table: "messages", column: "deleted_at", cql_type: "bigint" },
+            Step::AddColumn { table: "messages", column: "reply_to", cql_type: "bigint" },
+            Step::AddColumn { table: "messages", column: "quick_replies", cql_type: "text" },
+            Step::CreateTable(Table {
+                name: "messages_archive",
+                columns: &[
+                    ("shop_id", "text"), ("guest_id", "bigint"), ("message_id", "bigint"), ("sender_type", "text"),
+                    ("content", "blob"), ("timestamp_us", "bigint"), ("content_crc", "int"), ("edited_at", "bigint"),
+                    ("edit_count", "int"), ("deleted_at", "bigint"), ("reply_to", "bigint"), ("quick_replies", "text"),
+                ],
+                partition_key: &["shop_id", "guest_id"],
+                clustering_key: &[("message_id", "ASC")],
+            }),
+            Step::CreateTable(Table {
+                name: "shop_daily_stats",
+                columns: &[
+                    ("shop_id", "text"), ("day", "text"), ("new_conversations", "int"), ("guest_messages", "int"),
+                    ("admin_messages", "int"), ("median_first_response_ms", "bigint"),
+                    ("hourly_messages", "list<int>"), ("updated_at", "bigint"),
+                ],
+                partition_key: &["shop_id"],
+                clustering_key: &[("day", "DESC")],
+            }),
+            Step::CreateTable(Table {
+                name: "blocks",
+                columns: &[("shop_id", "text"), ("target", "text"), ("reason", "text"), ("created_at", "bigint")],
+                partition_key: &["shop_id"],
+                clustering_key: &[("target", "ASC")],
+            }),
+            Step::CreateTable(Table {
+                name: "flagged_messages",
+                columns: &[
+                    ("shop_id", "text"), ("message_id", "bigint"), ("guest_id", "bigint"), ("sender_type", "text"),
+                    ("content", "blob"), ("timestamp_us", "bigint"), ("content_crc", "int"), ("reason", "text"),
+                    ("flagged_at", "bigint"),
+                ],
+                partition_key: &["shop_id"],
+                clustering_key: &[("message_id", "DESC")],
+            }),
+            Step::CreateTable(Table {
+                name: "audit_log",
+                columns: &[
+                    ("shop_id", "text"), ("event_id", "bigint"), ("action", "text"), ("guest_id", "bigint"),
+                    ("actor", "text"), ("detail", "text"),
+                ],
+                partition_key: &["shop_id"],
+                clustering_key: &[("event_id", "DESC")],
+            }),
+            Step::CreateTable(Table {
+                name: "guest_events",
+                columns: &[
+                    ("shop_id", "text"), ("guest_id", "bigint"), ("event_id", "bigint"), ("name", "text"),
+                    ("properties", "map<text, text>"), ("occurred_at", "bigint"),
+                ],
+                partition_key: &["shop_id", "guest_id"],
+                clustering_key: &[("event_id", "DESC")],
+            }),
+            Step::CreateTable(Table {
+                name: "guest_notes",
+                columns: &[
+                    ("shop_id", "text"), ("guest_id", "bigint"), ("note_id", "bigint"), ("content", "text"),
+                    ("created_at", "bigint"), ("updated_at", "bigint"),
+                ],
+                partition_key: &["shop_id", "guest_id"],
+                clustering_key: &[("note_id", "DESC")],
+            }),
+            Step::CreateTable(Table {
+                name: "agent_status",
+                columns: &[("shop_id", "text"), ("agent_id", "text"), ("away", "boolean"), ("updated_at", "bigint")],
+                partition_key: &["shop_id"],
+                clustering_key: &[("agent_id", "ASC")],
+            }),
+            Step::CreateTable(Table {
+                name: "scheduled_messages",
+                columns: &[
+                    ("shop_id", "text"), ("scheduled_id", "bigint"), ("guest_id", "bigint"), ("content", "text"),
+                    ("send_at", "bigint"), ("created_at", "bigint"), ("quick_replies", "text"),
+                ],
+                partition_key: &["shop_id"],
+                clustering_key: &[("scheduled_id", "ASC")],
+            }),
+        ],
+    },
+    Migration {
+        version: 3,
+        name: "guest last message",
+        steps: &[
+            Step::AddColumn { table: "guests", column: "last_message_preview", cql_type: "text" },
+            Step::AddColumn { table: "guests", column: "last_message_at", cql_type: "bigint" },
+            Step::AddColumn { table: "guests", column: "last_sender", cql_type: "text" },
+        ],
+    },
+    Migration {
+        version: 4,
+        name: "message tombstones",
+        steps: &[
+            Step::AddColumn { table: "messages", column: "deleted_by", cql_type: "text" },
+            Step::AddColumn { table: "messages_archive", column: "deleted_by", cql_type: "text" },
+        ],
+    },
+    Migration {
+        version: 5,
+        name: "message edit history",
+        steps: &[
+            Step::CreateTable(Table {
+                name: "message_edits",
+                columns: &[
+                    ("shop_id", "text"), ("guest_id", "bigint"), ("message_id", "bigint"), ("replaced_at", "bigint"),
+                    ("content", "blob"), ("content_crc", "int"), ("editor", "text"),
+                ],
+                partition_key: &["shop_id", "guest_id"],
+                clustering_key: &[("message_id", "ASC"), ("replaced_at", "ASC")],
+            }),
+        ],
+    },
+    Migration {
+        version: 6,
+        name: "message attachments",
+        steps: &[
+            Step::AddColumn { table: "messages", column: "attachments", cql_type: "text" },
+            Step::AddColumn { table: "messages_archive", column: "attachments", cql_type: "text" },
+        ],
+    },
+    Migration {
+        version: 7,
+        name: "day-bucketed message partitions",
+        steps: &[
+            Step::CreateTable(Table {
+                name: "messages_by_day",
+                columns: &[
+                    ("shop_id", "text"), ("guest_id", "bigint"), ("day_bucket", "int"), ("message_id", "bigint"),
+                    ("sender_type", "text"), ("content", "blob"), ("timestamp_us", "bigint"), ("content_crc", "int"),
+                    ("edited_at", "bigint"), ("edit_count", "int"), ("deleted_at", "bigint"), ("deleted_by", "text"),
+                    ("reply_to", "bigint"), ("quick_replies", "text"), ("attachments", "text"),
+                ],
+                partition_key: &["shop_id", "guest_id", "day_bucket"],
+                clustering_key: &[("message_id", "ASC")],
+            }),
+            Step::CreateTable(Table {
+                name: "message_buckets",
+                columns: &[("shop_id", "text"), ("guest_id", "bigint"), ("day_bucket", "int")],
+                partition_key: &["shop_id", "guest_id"],
+                clustering_key: &[("day_bucket", "ASC")],
+            }),
+            Step::Backfill(Backfill::MessageDayBuckets),
+        ],
+    },
+    Migration {
+        version: 8,
+        name: "conversation feed",
+        steps: &[
+            Step::CreateTable(Table {
+                name: "conversation_feed",
+                columns: &[
+                    ("shop_id", "text"), ("guest_id", "bigint"), ("message_id", "bigint"), ("sender_type", "text"),
+                    ("content", "blob"), ("timestamp_us", "bigint"), ("content_crc", "int"), ("edited_at", "bigint"),
+                    ("deleted_at", "bigint"), ("deleted_by", "text"), ("reply_to", "bigint"), ("quick_replies", "text"),
+                    ("attachments", "text"),
+                ],
+                partition_key: &["shop_id"],
+                clustering_key: &[("guest_id", "DESC")],
+            }),
+            Step::Backfill(Backfill::ConversationFeed),
+        ],
+    },
+    Migration {
+        version: 9,
+        name: "api keys",
+        steps: &[
+            Step::CreateTable(Table {
+                name: "api_keys",
+                columns: &[
+                    ("shop_id", "text"), ("key_id", "text"), ("name", "text"), ("key_hash", "text"),
+                    ("scopes", "set<text>"), ("created_at", "bigint"), ("rotated_at", "bigint"), ("revoked_at", "bigint"),
+                ],
+                partition_key: &["shop_id"],
+                clustering_key: &[("key_id", "ASC")],
+            }),
+        ],
+    },
+    Migration {
+        version: 10,
+        name: "agent accounts and invites",
+        steps: &[
+            Step::CreateTable(Table {
+                name: "admins",
+                columns: &[
+                    ("shop_id", "text"), ("agent_id", "text"), ("email", "text"), ("name", "text"),
+                    ("password_hash", "text"), ("session_hash", "text"), ("created_at", "bigint"),
+                ],
+                partition_key: &["shop_id"],
+                clustering_key: &[("agent_id", "ASC")],
+            }),
+            Step::CreateTable(Table {
+                name: "admin_invites",
+                columns: &[
+                    ("shop_id", "text"), ("invite_id", "text"), ("email", "text"), ("created_at", "bigint"),
+                    ("expires_at", "bigint"), ("accepted_at", "bigint"),
+                ],
+                partition_key: &["shop_id"],
+                clustering_key: &[("invite_id", "ASC")],
+            }),
+        ],
+    },
+    Migration {
+        version: 11,
+        name: "guest identity linking",
+        steps: &[
+            Step::AddColumn { table: "shops", column: "identity_secret", cql_type: "text" },
+            Step::AddColumn { table: "shops", column: "identity_secret_at", cql_type: "bigint" },
+            Step::AddColumn { table: "guests", column: "user_id", cql_type: "text" },
+            Step::CreateTable(Table {
+                name: "guest_identities",
+                columns: &[("shop_id", "text"), ("user_id", "text"), ("guest_id", "bigint"), ("linked_at", "bigint")],
+                partition_key: &["shop_id"],
+                clustering_key: &[("user_id", "ASC"), ("guest_id", "ASC")],
+            }),
+        ],
+    },
+    Migration {
+        version: 12,
+        name: "guest merge aliases",
+        steps: &[
+            Step::AddColumn { table: "guests", column: "merged_into", cql_type: "bigint" },
+            Step::CreateTable(Table {
+                name: "guest_aliases",
+                columns: &[("shop_id", "text"), ("canonical_guest_id", "bigint"), ("alias_guest_id", "bigint"), ("merged_at", "bigint")],
+                partition_key: &["shop_id"],
+                clustering_key: &[("canonical_guest_id", "ASC"), ("alias_guest_id", "ASC")],
+            }),
+        ],
+    },
+    Migration {
+        version: 13,
+        name: "voice note limit",
+        steps: &[Step::AddColumn { table: "shops", column: "voice_max_secs", cql_type: "int" }],
+    },
+    Migration {
+        version: 14,
+        name: "admin notification preferences",
+        steps: &[Step::CreateTable(Table {
+            name: "admin_preferences",
+            columns: &[("shop_id", "text"), ("agent_id", "text"), ("preferences", "text"), ("updated_at", "bigint")],
+            partition_key: &["shop_id"],
+            clustering_key: &[("agent_id", "ASC")],
+        })],
+    },
+    Migration {
+        version: 15,
+        name: "conversation transfer notes",
+        steps: &[Step::AddColumn { table: "guest_notes", column: "transfer", cql_type: "text" }],
+    },
+    Migration {
+        version: 16,
+        name: "conversation reminders",
+        steps: &[Step::CreateTable(Table {
+            name: "reminders",
+            columns: &[("shop_id", "text"), ("guest_id", "bigint"), ("remind_at", "bigint"), ("agent_id", "text"), ("created_at", "bigint")],
+            partition_key: &["shop_id"],
+            clustering_key: &[("guest_id", "ASC")],
+        })],
+    },
+    Migration {
+        version: 17,
+        name: "saved inbox views",
+        steps: &[Step::CreateTable(Table {
+            name: "saved_views",
+            columns: &[("shop_id", "text"), ("agent_id", "text"), ("views", "text"), ("updated_at", "bigint")],
+            partition_key: &["shop_id"],
+            clustering_key: &[("agent_id", "ASC")],
+        })],
+    },
+    Migration {
+        version: 18,
+        name: "outbound campaigns",
+        steps: &[Step::CreateTable(Table {
+            name: "campaigns",
+            columns: &[("shop_id", "text"), ("campaign_id", "bigint"), ("campaign", "text"), ("updated_at", "bigint")],
+            partition_key: &["shop_id"],
+            clustering_key: &[("campaign_id", "DESC")],
+        })],
+    },
+    Migration {
+        version: 19,
+        name: "bot flow state",
+        steps: &[Step::CreateTable(Table {
+            name: "bot_flow_state",
+            columns: &[("shop_id", "text"), ("guest_id", "bigint"), ("state", "text"), ("updated_at", "bigint")],
+            partition_key: &["shop_id"],
+            clustering_key: &[("guest_id", "ASC")],
+        })],
+    },
+    Migration {
+        version: 20,
+        name: "bot handoff flag",
+        steps: &[Step::AddColumn { table: "guests", column: "needs_human", cql_type: "boolean" }],
+    },
+];
+
+// ========== ASTRA ==========
+
+/// Chạy các migration chưa có trong schema_migrations, theo thứ tự version
+pub async fn run(repo: &AstraRepo) -> Result<usize, ContractError> {
+    repo.create_table(&astra_table(&SCHEMA_MIGRATIONS)).await?;
+    let applied = repo.applied_migrations().await?;
+
+    let mut count = 0;
+    for migration in MIGRATIONS.iter().filter(|m| !applied.contains(&m.version)) {
+        println!("🗄️ Applying migration {} ({})", migration.version, migration.name);
+        for step in migration.steps {
+            match step {
+                Step::CreateTable(table) => repo.create_table(&astra_table(table)).await?,
+                // Cột đã có (node khác chạy trước / thêm tay) → bỏ qua
+                Step::AddColumn { table, column, cql_type } => {
+                    if !repo.column_exists(table, column).await? {
+                        repo.add_column(table, column, cql_type).await?;
+                    }
+                }
+                // Ghi đè được (upsert) - chạy lại sau khi hỏng giữa chừng vẫn đúng
+                Step::Backfill(backfill) => {
+                    let rows = match backfill {
+                        Backfill::MessageDayBuckets => repo.backfill_message_buckets().await?,
+                        Backfill::ConversationFeed => repo.backfill_feed().await?,
+                    };
+                    println!("🗄️ Backfill '{}': {} rows", backfill.description(), rows);
+                }
+            }
+        }
+        repo.record_migration(migration.version, migration.name).await?;
+        count += 1;
+    }
+
+    let latest = MIGRATIONS.last().map(|m| m.version).unwrap_or(0);
+    println!("🗄️ Schema at version {} ({} migrations applied)", latest, count);
+    Ok(count)
+}
+
+fn astra_table(table: &Table) -> serde_json::Value {
+    json!({
+        "name": table.name,
+        "ifNotExists": true,
+        "columnDefinitions": table.columns.iter()
+            .map(|(name, ty)| json!({ "name": name, "typeDefinition": ty, "static": false }))
+            .collect::<Vec<_>>(),
+        "primaryKey": {
+            "partitionKey": table.partition_key,
+            "clusteringKey": table.clustering_key.iter().map(|(c, _)| c).collect::<Vec<_>>(),
+        },
+        "tableOptions": {
+            "clusteringExpression": table.clustering_key.iter()
+                .map(|(column, order)| json!({ "column": column, "order": order }))
+                .collect::<Vec<_>>(),
+        }
+    })
+}
+
+// ========== DDL ==========
+
+/// Script CQL (Scylla/Cassandra, chạy bằng cqlsh -k <keyspace>) - chạy lại được
+pub fn render_cql() -> String {
+    let mut out = String::from("-- TurboChat schema (CQL) - sinh bởi `backend --print-schema=cql`\n\n");
+    out.push_str(&cql_create(&SCHEMA_MIGRATIONS));
+    for migration in MIGRATIONS {
+        out.push_str(&format!("\n-- Migration {}: {}\n", migration.version, migration.name));
+        for step in migration.steps {
+            match step {
+                Step::CreateTable(table) => out.push_str(&cql_create(table)),
+                Step::AddColumn { table, column, cql_type } => {
+                    out.push_str(&format!("ALTER TABLE {} ADD {} {};\n", table, column, cql_type));
+                }
+                Step::Backfill(backfill) => out.push_str(&backfill_note(backfill)),
+            }
+        }
+        out.push_str(&format!(
+            "INSERT INTO schema_migrations (version, name, applied_at) VALUES ({}, '{}', toUnixTimestamp(now()) * 1000) IF NOT EXISTS;\n",
+            migration.version, migration.name
+        ));
+    }
+    out
+}
+
+/// Script Postgres (psql) - cùng bảng/cột, kiểu collection đổi sang mảng/JSONB
+pub fn render_postgres() -> String {
+    let mut out = String::from("-- TurboChat schema (Postgres) - sinh bởi `backend --print-schema=postgres`\n\n");
+    out.push_str(&postgres_create(&SCHEMA_MIGRATIONS));
+    for migration in MIGRATIONS {
+        out.push_str(&format!("\n-- Migration {}: {}\n", migration.version, migration.name));
+        for step in migration.steps {
+            match step {
+                Step::CreateTable(table) => out.push_str(&postgres_create(table)),
+                Step::AddColumn { table, column, cql_type } => {
+                    out.push_str(&format!("ALTER TABLE {} ADD COLUMN IF NOT EXISTS {} {};\n", table, column, postgres_type(cql_type)));
+                }
+                Step::Backfill(backfill) => out.push_str(&backfill_note(backfill)),
+            }
+        }
+        out.push_str(&format!(
+            "INSERT INTO schema_migrations (version, name, applied_at) VALUES ({}, '{}', (extract(epoch FROM now()) * 1000000)::bigint) ON CONFLICT DO NOTHING;\n",
+            migration.version, migration.name
+        ));
+    }
+    out
+}
+
+fn backfill_note(backfill: &Backfill) -> String {
+    format!("-- Data: {} (run `backend --migrate-only` against the live database)\n", backfill.description())
+}
+
+fn cql_create(table: &Table) -> String {
+    let mut lines: Vec<String> = table.columns.iter().map(|(name, ty)| format!("    {} {}", name, ty)).collect();
+    let partition = format!("({})", table.partition_key.join(", "));
+    let key = std::iter::once(partition)
+        .chain(table.clustering_key.iter().map(|(c, _)| c.to_string()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    lines.push(format!("    PRIMARY KEY ({})", key));
+
+    let mut sql = format!("CREATE TABLE IF NOT EXISTS {} (\n{}\n)", table.name, lines.join(",\n"));
+    if !table.clustering_key.is_empty() {
+        let order = table.clustering_key.iter().map(|(c, o)| format!("{} {}", c, o)).collect::<Vec<_>>().join(", ");
+        sql.push_str(&format!(" WITH CLUSTERING ORDER BY ({})", order));
+    }
+    sql.push_str(";\n");
+    sql
+}
+
+fn postgres_create(table: &Table) -> String {
+    let mut lines: Vec<String> = table.columns.iter()
+        .map(|(name, ty)| format!("    {} {}", name, postgres_type(ty)))
+        .collect();
+    let key = table.partition_key.iter().copied()
+        .chain(table.clustering_key.iter().map(|(c, _)| *c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    lines.push(format!("    PRIMARY KEY ({})", key));
+    format!("CREATE TABLE IF NOT EXISTS {} (\n{}\n);\n", table.name, lines.join(",\n"))
+}
+
+fn postgres_type(cql_type: &str) -> &'static str {
+    match cql_type {
+        "text" => "TEXT",
+        "bigint" => "BIGINT",
+        "int" => "INTEGER",
+        "float" => "REAL",
+        "boolean" => "BOOLEAN",
+        "blob" => "BYTEA",
+        "set<text>" | "list<text>" => "TEXT[]",
+        "list<int>" => "INTEGER[]",
+        _ => "JSONB",
+    }
+}
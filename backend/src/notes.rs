@@ -0,0 +1,112 @@
+// Ghi chú nội bộ của admin về khách - REST + đồng bộ giữa các admin qua WS (admin_only)
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use prost::Message as ProstMessage;
+use std::sync::Arc;
+
+use crate::auth::AdminAuth;
+use crate::contract::{GuestNote, NoteListResponse, NoteRequest, WsEnvelope};
+use crate::db::now_us;
+use crate::request_id;
+use crate::state::AppState;
+use crate::websocket::publish_envelope;
+
+pub(crate) const MAX_NOTE_CHARS: usize = 2000;
+
+// GET /guests/:id/notes - Ghi chú của khách (mới nhất trước)
+pub async fn list_notes_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path(guest_id): Path<u64>,
+) -> impl IntoResponse {
+    let resp = match state.repo.list_notes(&admin.shop_id, guest_id).await {
+        Ok(notes) => NoteListResponse { success: true, notes, ..Default::default() },
+        Err(e) => NoteListResponse { success: false, notes: vec![], error: e.to_string(), request_id: request_id::current() },
+    };
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+}
+
+// POST /guests/:id/notes - Thêm ghi chú, trả lại ghi chú vừa tạo
+pub async fn create_note_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path(guest_id): Path<u64>,
+    body: Bytes,
+) -> impl IntoResponse {
+    let Some(content) = note_content(&body) else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+
+    let note = match state.repo.insert_note(&admin.shop_id, guest_id, &content, None).await {
+        Ok(note) => note,
+        Err(e) => {
+            eprintln!("❌ Insert note failed: {:?}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new());
+        }
+    };
+    println!("📝 Note {} added for guest {} of shop {}", note.note_id, guest_id, admin.shop_id);
+
+    broadcast(&state, &admin.shop_id, note.clone()).await;
+    (StatusCode::OK, Bytes::from(note.encode_to_vec()))
+}
+
+// PATCH /guests/:id/notes/:note_id - Sửa nội dung ghi chú
+pub async fn update_note_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path((guest_id, note_id)): Path<(u64, u64)>,
+    body: Bytes,
+) -> impl IntoResponse {
+    let Some(content) = note_content(&body) else {
+        return StatusCode::BAD_REQUEST;
+    };
+
+    if let Err(e) = state.repo.update_note(&admin.shop_id, guest_id, note_id, &content).await {
+        eprintln!("❌ Update note failed: {:?}", e);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    let note = GuestNote {
+        note_id,
+        guest_id,
+        content,
+        updated_at: now_us(),
+        ..Default::default()
+    };
+    broadcast(&state, &admin.shop_id, note).await;
+    StatusCode::NO_CONTENT
+}
+
+// DELETE /guests/:id/notes/:note_id
+pub async fn delete_note_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path((guest_id, note_id)): Path<(u64, u64)>,
+) -> impl IntoResponse {
+    if let Err(e) = state.repo.delete_note(&admin.shop_id, guest_id, note_id).await {
+        eprintln!("❌ Delete note failed: {:?}", e);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    let note = GuestNote { note_id, guest_id, deleted: true, ..Default::default() };
+    broadcast(&state, &admin.shop_id, note).await;
+    StatusCode::NO_CONTENT
+}
+
+// Nội dung hợp lệ: không rỗng, cắt bớt nếu quá dài
+fn note_content(body: &[u8]) -> Option<String> {
+    let req = NoteRequest::decode(body).ok()?;
+    let content: String = req.content.trim().chars().take(MAX_NOTE_CHARS).collect();
+    (!content.is_empty()).then_some(content)
+}
+
+// Envelope admin_only → kết nối guest không bao giờ nhận (WsEnvelope::is_visible_to)
+pub(crate) async fn broadcast(state: &AppState, shop_id: &str, note: GuestNote) {
+    if let Err(e) = publish_envelope(&state.ws_state, &WsEnvelope::note(shop_id.to_string(), note)).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+}
@@ -0,0 +1,92 @@
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::contract::{Guest, Message, ShopSettings};
+
+// Mỗi cuộc chat chỉ gửi 1 email trong khoảng này
+const NOTIFY_COOLDOWN: Duration = Duration::from_secs(30 * 60);
+
+/// Gửi email báo tin nhắn ngoài giờ qua webhook của dịch vụ email (EMAIL_WEBHOOK_URL)
+pub struct EmailNotifier {
+    client: reqwest::Client,
+    webhook_url: Option<String>,
+    last_sent: Mutex<HashMap<(String, u64), Instant>>,
+}
+
+impl EmailNotifier {
+    pub fn from_env() -> Self {
+        let webhook_url = std::env::var("EMAIL_WEBHOOK_URL").ok().filter(|u| !u.is_empty());
+        if webhook_url.is_none() {
+            println!("📧 EMAIL_WEBHOOK_URL not set, offline notifications are logged only");
+        }
+        Self {
+            client: reqwest::Client::new(),
+            webhook_url,
+            last_sent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn notify_offline_message(&self, settings: &ShopSettings, msg: &Message) {
+        if settings.notify_email.is_empty() {
+            return;
+        }
+
+        let key = (msg.shop_id.clone(), msg.guest_id);
+        {
+            let mut last_sent = self.last_sent.lock().unwrap();
+            if last_sent.get(&key).is_some_and(|at| at.elapsed() < NOTIFY_COOLDOWN) {
+                return;
+            }
+            last_sent.retain(|_, at| at.elapsed() < NOTIFY_COOLDOWN);
+            last_sent.insert(key, Instant::now());
+        }
+
+        let subject = format!("[TurboChat] Tin nhắn mới ngoài giờ từ {}", Guest::fallback_name(msg.guest_id));
+        let text = String::from_utf8_lossy(&msg.content).to_string();
+        self.send(&settings.notify_email, &msg.shop_id, msg.guest_id, &subject, &text).await;
+    }
+
+    /// Hội thoại quá hạn SLA phản hồi đầu (mỗi lượt chờ chỉ báo 1 lần - xem sla.rs)
+    pub async fn notify_sla_breach(&self, settings: &ShopSettings, shop_id: &str, guest: &Guest, waited_secs: u64) {
+        if settings.notify_email.is_empty() {
+            return;
+        }
+        let subject = format!("[TurboChat] {} đã chờ phản hồi {} phút", guest.display_name(), waited_secs / 60);
+        let text = format!("Khách {} đang chờ phản hồi quá hạn SLA ({} phút).", guest.display_name(), settings.sla_first_response_secs / 60);
+        self.send(&settings.notify_email, shop_id, guest.guest_id, &subject, &text).await;
+    }
+
+    /// Link mời nhân viên (không giới hạn tần suất - chủ shop chủ động gửi)
+    pub async fn send_invite(&self, to: &str, shop_id: &str, shop_name: &str, link: &str, valid_days: u64) {
+        let subject = format!("[TurboChat] Lời mời tham gia hỗ trợ khách hàng cho {}", shop_name);
+        let text = format!(
+            "Bạn được mời làm nhân viên chat của {}.\nMở link sau để đặt tên và mật khẩu (hết hạn sau {} ngày):\n{}",
+            shop_name, valid_days, link
+        );
+        self.send(to, shop_id, 0, &subject, &text).await;
+    }
+
+    async fn send(&self, to: &str, shop_id: &str, guest_id: u64, subject: &str, text: &str) {
+        let Some(url) = &self.webhook_url else {
+            println!("📧 Notification for {} (shop {}): {}", to, shop_id, subject);
+            return;
+        };
+
+        let body = json!({
+            "to": to,
+            "subject": subject,
+            "text": text,
+            "shop_id": shop_id,
+            "guest_id": guest_id,
+        });
+        match self.client.post(url).json(&body).send().await {
+            Ok(resp) if resp.status().is_success() => {
+                println!("📧 Notification sent to {}", to);
+            }
+            Ok(resp) => eprintln!("❌ Email webhook returned {}", resp.status()),
+            Err(e) => eprintln!("❌ Email webhook failed: {:?}", e),
+        }
+    }
+}
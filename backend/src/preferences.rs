@@ -0,0 +1,39 @@
+// Tuỳ chọn âm báo / thông báo theo từng admin - admin panel tự áp dụng khi nhận tin (server chỉ lưu)
+use axum::{body::Bytes, extract::State, http::StatusCode, response::IntoResponse};
+use prost::Message as ProstMessage;
+use std::sync::Arc;
+
+use crate::auth::AdminAuth;
+use crate::contract::AdminPreferences;
+use crate::state::AppState;
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+// GET /preferences - Tuỳ chọn của admin đang đăng nhập
+pub async fn get_preferences_handler(State(state): State<Arc<AppState>>, admin: AdminAuth) -> impl IntoResponse {
+    match state.repo.get_preferences(&admin.shop_id, &admin.agent_id).await {
+        Ok(prefs) => (StatusCode::OK, Bytes::from(AdminPreferences { agent_id: admin.agent_id, ..prefs }.encode_to_vec())),
+        Err(e) => {
+            eprintln!("❌ Load preferences for {}/{} failed: {:?}", admin.shop_id, admin.agent_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new())
+        }
+    }
+}
+
+// PUT /preferences - Thay toàn bộ tuỳ chọn
+pub async fn put_preferences_handler(State(state): State<Arc<AppState>>, admin: AdminAuth, body: Bytes) -> impl IntoResponse {
+    let Ok(prefs) = AdminPreferences::decode(&body[..]) else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+    if prefs.quiet_start_minute >= MINUTES_PER_DAY || prefs.quiet_end_minute >= MINUTES_PER_DAY {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    }
+    // agent_id lấy từ phiên đăng nhập, không tin giá trị client gửi
+    let prefs = AdminPreferences { agent_id: admin.agent_id.clone(), ..prefs };
+    if let Err(e) = state.repo.save_preferences(&admin.shop_id, &admin.agent_id, &prefs).await {
+        eprintln!("❌ Save preferences failed: {:?}", e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new());
+    }
+    println!("🔔 Preferences saved for {} of shop {}", admin.agent_id, admin.shop_id);
+    (StatusCode::OK, Bytes::from(prefs.encode_to_vec()))
+}
@@ -0,0 +1,114 @@
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Timelike, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::time::{interval, Duration};
+
+use crate::contract::{Presence, ShopSettings, WsEnvelope};
+use crate::websocket::{publish_envelope, WebSocketState};
+
+const PRESENCE_CHECK_SECS: u64 = 30;
+
+// ============================================================================
+// GIỜ LÀM VIỆC
+// ============================================================================
+
+// Giờ địa phương của shop (theo timezone_offset_minutes)
+fn local_time(settings: &ShopSettings, now: DateTime<Utc>) -> DateTime<Utc> {
+    now + ChronoDuration::minutes(settings.timezone_offset_minutes as i64)
+}
+
+/// Shop có đang trong giờ làm việc không (tắt giờ làm việc = luôn online)
+pub fn is_open(settings: &ShopSettings, now: DateTime<Utc>) -> bool {
+    if !settings.business_hours_enabled {
+        return true;
+    }
+    let local = local_time(settings, now);
+    let weekday = local.weekday().num_days_from_sunday();
+    let minute = local.hour() * 60 + local.minute();
+    settings.business_hours.iter().any(|h| {
+        h.weekday == weekday && h.open_minute <= minute && minute < h.close_minute
+    })
+}
+
+// Thời điểm mở cửa kế tiếp (trong 7 ngày tới), 0 = không có
+fn next_open_us(settings: &ShopSettings, now: DateTime<Utc>) -> u64 {
+    let local = local_time(settings, now);
+    let today_start = local - ChronoDuration::seconds(local.num_seconds_from_midnight() as i64);
+    let minute_now = local.hour() * 60 + local.minute();
+
+    for day in 0..8u32 {
+        let weekday = (local.weekday().num_days_from_sunday() + day) % 7;
+        let next = settings.business_hours.iter()
+            .filter(|h| h.weekday == weekday && h.open_minute < h.close_minute)
+            .filter(|h| day > 0 || h.open_minute > minute_now)
+            .map(|h| h.open_minute)
+            .min();
+        if let Some(open_minute) = next {
+            let open_local = today_start
+                + ChronoDuration::days(day as i64)
+                + ChronoDuration::minutes(open_minute as i64);
+            let open_utc = open_local - ChronoDuration::minutes(settings.timezone_offset_minutes as i64);
+            return open_utc.timestamp_micros().max(0) as u64;
+        }
+    }
+    0
+}
+
+/// Frame trạng thái hiện tại của shop (agents_away: mọi admin đang kết nối - mọi node - đều "Vắng mặt")
+pub fn presence_for(settings: &ShopSettings, now: DateTime<Utc>, agents_away: bool) -> Presence {
+    let online = is_open(settings, now);
+    Presence {
+        online,
+        offline_message: if online { String::new() } else { settings.offline_message.clone() },
+        next_open_us: if online { 0 } else { next_open_us(settings, now) },
+        away: online && agents_away,
+    }
+}
+
+/// Tính lại và phát Presence cho mọi kết nối của shop
+pub async fn publish_presence(state: &WebSocketState, shop_id: &str) {
+    let settings = state.settings.get(shop_id).await;
+    let presence = presence_for(&settings, Utc::now(), state.cluster.all_away(shop_id).await);
+    println!("🟢 Shop {} presence: online={}, away={}", shop_id, presence.online, presence.away);
+    if let Err(e) = publish_envelope(state, &WsEnvelope::presence(shop_id.to_string(), presence)).await {
+        eprintln!("❌ Presence publish failed for {}: {:?}", shop_id, e);
+    }
+}
+
+// ============================================================================
+// BACKGROUND TASK - push Presence khi shop đổi trạng thái
+// ============================================================================
+
+pub async fn presence_task(state: Arc<WebSocketState>) {
+    println!("🟢 Presence task started (every {}s)", PRESENCE_CHECK_SECS);
+    let mut last: HashMap<String, (bool, bool)> = HashMap::new();
+    let mut ticker = interval(Duration::from_secs(PRESENCE_CHECK_SECS));
+
+    loop {
+        ticker.tick().await;
+        // Chỉ theo dõi các shop đang có kết nối
+        let shops = state.connected_shops();
+        last.retain(|shop, _| shops.contains(shop));
+
+        for shop_id in shops {
+            let settings = state.settings.get(&shop_id).await;
+            let presence = presence_for(&settings, Utc::now(), state.cluster.all_away(&shop_id).await);
+            let current = (presence.online, presence.away);
+            if last.get(&shop_id) == Some(&current) {
+                continue;
+            }
+            let first_check = !last.contains_key(&shop_id);
+            last.insert(shop_id.clone(), current);
+            // Lần đầu thấy shop: client đã nhận trạng thái lúc kết nối
+            if first_check {
+                continue;
+            }
+
+            println!("🟢 Shop {} is now {}", shop_id, if presence.online { "online" } else { "offline" });
+            let envelope = WsEnvelope::presence(shop_id.clone(), presence);
+            if let Err(e) = publish_envelope(&state, &envelope).await {
+                eprintln!("❌ Presence publish failed for {}: {:?}", shop_id, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+// Chủ động bắt chuyện: khách ở yên một trang đủ lâu (theo frame VisitorContext) → bot gửi lời chào.
+// Widget nhận như tin bot thường (đang đóng → badge chưa đọc + bong bóng xem trước)
+use axum::{body::Bytes, extract::State, http::StatusCode, response::IntoResponse};
+use prost::Message as ProstMessage;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::auth::{AdminAuth, OwnerAuth};
+use crate::contract::{ProactiveTrigger, ProactiveTriggers, VisitorContext};
+use crate::state::AppState;
+use crate::websocket::{claim_once, send_bot_message, WebSocketState};
+
+const MAX_TRIGGERS: usize = 20;
+const MAX_TEXT_CHARS: usize = 500;
+const MAX_DELAY_SECS: u32 = 3600;
+// Mỗi luật chào cùng một khách tối đa 1 lần trong khoảng này
+const GREET_COOLDOWN_SECS: u64 = 24 * 3600;
+
+/// Khách vừa vào trang (context đã lưu) → hẹn giờ luật khớp đầu tiên
+pub async fn on_page_view(state: &Arc<WebSocketState>, shop_id: &str, guest_id: u64, context: &VisitorContext) {
+    let settings = state.settings.get(shop_id).await;
+    let Some((index, trigger)) = settings.proactive_triggers.iter().enumerate()
+        .find(|(_, t)| t.enabled && context.page_url.contains(t.page_url_contains.as_str()))
+    else {
+        return;
+    };
+
+    let state = Arc::clone(state);
+    let shop_id = shop_id.to_string();
+    let trigger = trigger.clone();
+    let seen_at = context.updated_at_us;
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(trigger.delay_secs as u64)).await;
+        greet(&state, &shop_id, guest_id, index, &trigger, seen_at).await;
+    });
+}
+
+// Tới giờ: khách vẫn ở trang đó (chưa chuyển trang, còn kết nối) → gửi lời chào
+async fn greet(state: &WebSocketState, shop_id: &str, guest_id: u64, index: usize, trigger: &ProactiveTrigger, seen_at: u64) {
+    let guest = match state.repo.get_guest(shop_id, guest_id).await {
+        Ok(Some(guest)) => guest,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("❌ Load guest {} for proactive greeting failed: {:?}", guest_id, e);
+            return;
+        }
+    };
+    if guest.context.as_ref().map(|c| c.updated_at_us) != Some(seen_at) {
+        return;
+    }
+    if trigger.skip_existing_conversations && guest.last_message_at > 0 {
+        return;
+    }
+    match state.cluster.live_guests(shop_id).await {
+        Ok(live) if live.contains_key(&guest_id) => {}
+        Ok(_) => return,
+        Err(e) => {
+            eprintln!("❌ Load live visitors of {} failed: {:?}", shop_id, e);
+            return;
+        }
+    }
+    // Khách mở nhiều tab (nhiều node cùng hẹn giờ) vẫn chỉ nhận một lần
+    if !claim_once(state, &format!("proactive:{}:{}:{}", shop_id, guest_id, index), GREET_COOLDOWN_SECS).await {
+        return;
+    }
+
+    println!("👋 Proactive greeting {:?} for guest {} of shop {}", trigger.name, guest_id, shop_id);
+    send_bot_message(state, shop_id, guest_id, &trigger.message).await;
+}
+
+// GET /triggers
+pub async fn get_triggers_handler(State(state): State<Arc<AppState>>, admin: AdminAuth) -> impl IntoResponse {
+    match state.repo.get_settings(&admin.shop_id).await {
+        Ok(settings) => {
+            let resp = ProactiveTriggers { triggers: settings.proactive_triggers };
+            (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+        }
+        Err(e) => {
+            eprintln!("❌ Load settings for {} failed: {:?}", admin.shop_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new())
+        }
+    }
+}
+
+// PUT /triggers - Thay toàn bộ danh sách, trả lại bản đã chuẩn hoá
+pub async fn put_triggers_handler(State(state): State<Arc<AppState>>, OwnerAuth(admin): OwnerAuth, body: Bytes) -> impl IntoResponse {
+    let Ok(req) = ProactiveTriggers::decode(&body[..]) else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+    if req.triggers.len() > MAX_TRIGGERS {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    }
+    let triggers: Vec<ProactiveTrigger> = req.triggers.into_iter().filter_map(normalize).collect();
+
+    let mut settings = match state.repo.get_settings(&admin.shop_id).await {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("❌ Load settings for {} failed: {:?}", admin.shop_id, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new());
+        }
+    };
+    settings.proactive_triggers = triggers.clone();
+    if let Err(e) = state.repo.save_settings(&admin.shop_id, &settings).await {
+        eprintln!("❌ Save proactive triggers failed: {:?}", e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new());
+    }
+    state.ws_state.settings.put(&admin.shop_id, settings);
+
+    let detail = format!("{} triggers", triggers.len());
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "proactive.update", 0, &admin.agent_id, &detail).await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("👋 Proactive triggers updated for shop {} ({} triggers)", admin.shop_id, triggers.len());
+
+    (StatusCode::OK, Bytes::from(ProactiveTriggers { triggers }.encode_to_vec()))
+}
+
+// Cắt chuỗi quá dài, giới hạn thời gian chờ; bỏ luật không có lời chào
+fn normalize(mut trigger: ProactiveTrigger) -> Option<ProactiveTrigger> {
+    let clean = |s: &mut String| *s = s.trim().chars().take(MAX_TEXT_CHARS).collect();
+    clean(&mut trigger.name);
+    clean(&mut trigger.page_url_contains);
+    clean(&mut trigger.message);
+    trigger.delay_secs = trigger.delay_secs.min(MAX_DELAY_SECS);
+    (!trigger.message.is_empty()).then_some(trigger)
+}
@@ -0,0 +1,324 @@
+// Hạn mức + đo lường theo shop: tin/tháng, kết nối khách đồng thời, dung lượng - đếm trên Redis (chung mọi node).
+// Kết nối khách đếm trên sổ kết nối của cụm (cluster.rs) → node chết không làm lệch
+use axum::{body::Bytes, extract::State, http::StatusCode, response::IntoResponse};
+use prost::Message as ProstMessage;
+use redis::AsyncCommands;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::auth::AdminAuth;
+use crate::cluster::Cluster;
+use crate::contract::{Message as ChatMessage, QuotaAction, QuotaExceeded, QuotaKind, ShopQuota, ShopUsage, WsEnvelope};
+use crate::db::AstraRepo;
+use crate::redis_conn::RedisConn;
+use crate::state::AppState;
+use crate::websocket::{self, publish_envelope, WebSocketState};
+
+const CACHE_TTL: Duration = Duration::from_secs(60);
+// Bộ đếm tháng giữ lâu hơn 1 tháng một chút rồi tự hết hạn
+const MONTH_COUNTER_TTL_SECS: i64 = 62 * 86_400;
+const QUEUED_SHOPS_KEY: &str = "quota:queued_shops";
+// Mỗi lượt xả hàng đợi tối đa bấy nhiêu tin / shop
+const MAX_DRAIN_PER_TICK: usize = 500;
+// Hàng đợi đầy → tin vượt hạn mức bị bỏ như chế độ reject (không để Redis phình vô hạn)
+const MAX_QUEUED_PER_SHOP: u64 = 10_000;
+
+fn month_key() -> String {
+    chrono::Utc::now().format("%Y-%m").to_string()
+}
+
+fn messages_key(shop_id: &str, month: &str) -> String {
+    format!("usage:{}:messages:{}", shop_id, month)
+}
+
+fn storage_key(shop_id: &str) -> String {
+    format!("usage:{}:storage", shop_id)
+}
+
+fn queue_key(shop_id: &str) -> String {
+    format!("quota:queue:{}", shop_id)
+}
+
+/// Mặc định cho mọi shop: QUOTA_MONTHLY_MESSAGES, QUOTA_CONNECTIONS, QUOTA_STORAGE_MB,
+/// QUOTA_OVER_LIMIT ("reject" / "queue"). Không đặt = không giới hạn. Từng shop ghi đè bằng cột quota_* trong bảng shops.
+pub struct QuotaMeter {
+    redis: RedisConn,
+    repo: Arc<AstraRepo>,
+    defaults: ShopQuota,
+    cache: RwLock<HashMap<String, (Instant, ShopQuota)>>,
+}
+
+impl QuotaMeter {
+    pub fn from_env(redis_url: &str, repo: Arc<AstraRepo>) -> redis::RedisResult<Self> {
+        let env_u64 = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok()).unwrap_or(0);
+        let over_limit = match std::env::var("QUOTA_OVER_LIMIT").as_deref() {
+            Ok("queue") => QuotaAction::Queue,
+            _ => QuotaAction::Reject,
+        };
+        let defaults = ShopQuota {
+            monthly_messages: env_u64("QUOTA_MONTHLY_MESSAGES"),
+            concurrent_connections: env_u64("QUOTA_CONNECTIONS") as u32,
+            storage_bytes: env_u64("QUOTA_STORAGE_MB") * 1024 * 1024,
+            over_limit: over_limit as i32,
+        };
+        Ok(Self {
+            redis: RedisConn::open(redis_url)?,
+            repo,
+            defaults,
+            cache: RwLock::new(HashMap::new()),
+        })
+    }
+
+    pub async fn quota(&self, shop_id: &str) -> ShopQuota {
+        if let Some((loaded_at, quota)) = self.cache.read().unwrap().get(shop_id) {
+            if loaded_at.elapsed() < CACHE_TTL {
+                return *quota;
+            }
+        }
+        match self.repo.get_quota(shop_id, &self.defaults).await {
+            Ok(quota) => {
+                self.cache.write().unwrap().insert(shop_id.to_string(), (Instant::now(), quota));
+                quota
+            }
+            Err(e) => {
+                eprintln!("❌ Load quota for {} failed: {:?}", shop_id, e);
+                self.defaults
+            }
+        }
+    }
+
+    pub async fn usage(&self, cluster: &Cluster, shop_id: &str) -> redis::RedisResult<ShopUsage> {
+        let (messages, storage_bytes, queued) = self.counters(shop_id).await?;
+        Ok(ShopUsage {
+            month: month_key(),
+            messages,
+            connections: cluster.guest_connections(shop_id).await? as u32,
+            storage_bytes,
+            quota: Some(self.quota(shop_id).await),
+            queued,
+        })
+    }
+
+    // (tin tháng này, dung lượng, số tin đang giữ trong hàng đợi)
+    async fn counters(&self, shop_id: &str) -> redis::RedisResult<(u64, u64, u64)> {
+        let result = async {
+            let mut conn = self.redis.get().await?;
+            let (messages, storage, queued): (Option<u64>, Option<i64>, u64) = redis::pipe()
+                .cmd("GET").arg(messages_key(shop_id, &month_key()))
+                .cmd("GET").arg(storage_key(shop_id))
+                .llen(queue_key(shop_id))
+                .query_async(&mut conn)
+                .await?;
+            Ok((messages.unwrap_or(0), storage.unwrap_or(0).max(0) as u64, queued))
+        }.await;
+        self.redis.checked(result).await
+    }
+
+    /// Tin mới có vượt hạn mức tháng / dung lượng không. Redis lỗi → cho qua
+    pub async fn check_send(&self, shop_id: &str, content_len: usize) -> Result<(), QuotaExceeded> {
+        let quota = self.quota(shop_id).await;
+        if quota.monthly_messages == 0 && quota.storage_bytes == 0 {
+            return Ok(());
+        }
+        let (messages, storage_bytes, _) = match self.counters(shop_id).await {
+            Ok(counters) => counters,
+            Err(e) => {
+                eprintln!("❌ Read usage for {} failed: {:?}", shop_id, e);
+                return Ok(());
+            }
+        };
+
+        if quota.monthly_messages > 0 && messages >= quota.monthly_messages {
+            return Err(exceeded(QuotaKind::MonthlyMessages, quota.monthly_messages, messages));
+        }
+        if quota.storage_bytes > 0 && storage_bytes + content_len as u64 > quota.storage_bytes {
+            return Err(exceeded(QuotaKind::Storage, quota.storage_bytes, storage_bytes));
+        }
+        Ok(())
+    }
+
+    /// Đếm tin đã lưu (mọi nguồn: khách, admin, bot, tin hẹn giờ).
+    /// Dung lượng là tổng nội dung + file đang lưu - dọn tin quá hạn / xoá khách trừ lại (release_storage)
+    pub async fn record_message(&self, shop_id: &str, content_len: usize) {
+        let key = messages_key(shop_id, &month_key());
+        let result: redis::RedisResult<()> = async {
+            let mut conn = self.redis.get().await?;
+            redis::pipe()
+                .incr(&key, 1).ignore()
+                .expire(&key, MONTH_COUNTER_TTL_SECS).ignore()
+                .incr(storage_key(shop_id), content_len as u64).ignore()
+                .query_async(&mut conn)
+                .await
+        }.await;
+        if let Err(e) = self.redis.checked(result).await {
+            eprintln!("❌ Record usage for {} failed: {:?}", shop_id, e);
+        }
+    }
+
+    /// Cộng dung lượng file đính kèm vừa lưu (attachments.rs)
+    pub async fn record_storage(&self, shop_id: &str, bytes: u64) {
+        let result: redis::RedisResult<()> = async {
+            self.redis.get().await?.incr(storage_key(shop_id), bytes).await
+        }.await;
+        if let Err(e) = self.redis.checked(result).await {
+            eprintln!("❌ Record storage for {} failed: {:?}", shop_id, e);
+        }
+    }
+
+    /// Trừ dung lượng của tin đã xoá hẳn (không xuống dưới 0)
+    pub async fn release_storage(&self, shop_id: &str, messages: &[ChatMessage]) {
+        let bytes = stored_bytes(messages);
+        if bytes == 0 {
+            return;
+        }
+        let result: redis::RedisResult<()> = async {
+            let mut conn = self.redis.get().await?;
+            redis::Script::new(RELEASE_SCRIPT).key(storage_key(shop_id)).arg(bytes).invoke_async(&mut conn).await
+        }.await;
+        if let Err(e) = self.redis.checked(result).await {
+            eprintln!("❌ Release storage for {} failed: {:?}", shop_id, e);
+        }
+    }
+
+    /// Còn chỗ cho thêm một kết nối khách không (kiểm tra trước khi upgrade WS)
+    pub async fn check_connection(&self, cluster: &Cluster, shop_id: &str) -> Result<(), QuotaExceeded> {
+        let quota = self.quota(shop_id).await;
+        if quota.concurrent_connections == 0 {
+            return Ok(());
+        }
+        let current = match cluster.guest_connections(shop_id).await {
+            Ok(current) => current,
+            Err(e) => {
+                eprintln!("❌ Read connections for {} failed: {:?}", shop_id, e);
+                return Ok(());
+            }
+        };
+        if current >= quota.concurrent_connections as u64 {
+            return Err(exceeded(QuotaKind::Connections, quota.concurrent_connections as u64, current));
+        }
+        Ok(())
+    }
+
+    /// Giữ tin trong hàng đợi của shop; đầy (MAX_QUEUED_PER_SHOP) → Ok(false)
+    async fn enqueue(&self, msg: &ChatMessage) -> redis::RedisResult<bool> {
+        let result = async {
+            let mut conn = self.redis.get().await?;
+            let queued: u64 = conn.llen(queue_key(&msg.shop_id)).await?;
+            if queued >= MAX_QUEUED_PER_SHOP {
+                return Ok(false);
+            }
+            redis::pipe()
+                .rpush(queue_key(&msg.shop_id), msg.encode_to_vec()).ignore()
+                .sadd(QUEUED_SHOPS_KEY, &msg.shop_id).ignore()
+                .query_async::<()>(&mut conn)
+                .await?;
+            Ok(true)
+        }.await;
+        self.redis.checked(result).await
+    }
+}
+
+// Số byte tính vào hạn mức dung lượng: nội dung + file đính kèm
+fn stored_bytes(messages: &[ChatMessage]) -> u64 {
+    messages.iter()
+        .map(|m| m.content.len() as u64 + m.attachments.iter().map(|a| a.size).sum::<u64>())
+        .sum()
+}
+
+// DECRBY nhưng không âm (bộ đếm cũ có thể thấp hơn dữ liệu thật)
+const RELEASE_SCRIPT: &str = r"
+local left = redis.call('DECRBY', KEYS[1], ARGV[1])
+if left < 0 then redis.call('SET', KEYS[1], 0) end
+return left
+";
+
+fn exceeded(kind: QuotaKind, limit: u64, used: u64) -> QuotaExceeded {
+    QuotaExceeded { kind: kind as i32, limit, used, ..Default::default() }
+}
+
+/// Tin vượt hạn mức: giữ lại hoặc bỏ tuỳ cấu hình, rồi báo người gửi
+pub async fn over_limit(state: &WebSocketState, msg: ChatMessage, mut exceeded: QuotaExceeded) {
+    let quota = state.quotas.quota(&msg.shop_id).await;
+    if quota.over_limit() == QuotaAction::Queue {
+        match state.quotas.enqueue(&msg).await {
+            Ok(queued) => exceeded.queued = queued,
+            Err(e) => eprintln!("❌ Queue over-quota message failed: {:?}", e),
+        }
+    }
+    println!("📊 Shop {} over {:?} quota ({}/{}), message from {} {}",
+        msg.shop_id, exceeded.kind(), exceeded.used, exceeded.limit, msg.sender_type,
+        if exceeded.queued { "queued" } else { "rejected" });
+
+    exceeded.message_id = msg.message_id;
+    let admin_only = msg.sender_type != "guest";
+    let envelope = WsEnvelope::quota_exceeded(msg.shop_id.clone(), msg.guest_id, admin_only, exceeded);
+    // Báo khách: chỉ gửi tới node đang giữ kết nối của khách
+    let result = if admin_only { publish_envelope(state, &envelope).await } else { state.cluster.send_to_guest(&envelope).await.map_err(Into::into) };
+    if let Err(e) = result {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+}
+
+// ============================================================================
+// XẢ HÀNG ĐỢI - tin bị giữ được gửi lại (đúng thứ tự) khi shop có hạn mức trở lại
+// ============================================================================
+pub async fn quota_task(state: Arc<WebSocketState>) {
+    let interval_secs = std::env::var("QUOTA_DRAIN_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60);
+    println!("📊 Quota queue drainer started (every {}s)", interval_secs);
+
+    loop {
+        let result = drain_all(&state, interval_secs).await;
+        if let Err(e) = state.quotas.redis.checked(result).await {
+            eprintln!("❌ Quota drain failed: {:?}", e);
+        }
+        tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+    }
+}
+
+async fn drain_all(state: &Arc<WebSocketState>, interval_secs: u64) -> redis::RedisResult<()> {
+    let mut conn = state.quotas.redis.get().await?;
+    let shops: Vec<String> = conn.smembers(QUEUED_SHOPS_KEY).await?;
+    for shop_id in shops {
+        // Một node xả mỗi shop trong một lượt
+        if !websocket::claim_once(state, &format!("quota:drain:{}", shop_id), interval_secs).await {
+            continue;
+        }
+        let key = queue_key(&shop_id);
+        let mut sent = 0;
+        while sent < MAX_DRAIN_PER_TICK {
+            let Some(raw): Option<Vec<u8>> = conn.lindex(&key, 0).await? else {
+                let _: () = conn.srem(QUEUED_SHOPS_KEY, &shop_id).await?;
+                break;
+            };
+            let Ok(msg) = ChatMessage::decode(&raw[..]) else {
+                let _: Option<Vec<u8>> = conn.lpop(&key, None).await?;
+                continue;
+            };
+            if state.quotas.check_send(&shop_id, msg.content.len()).await.is_err() {
+                break;
+            }
+            let _: Option<Vec<u8>> = conn.lpop(&key, None).await?;
+            websocket::ingest_message(state, msg, "").await;
+            sent += 1;
+        }
+        if sent > 0 {
+            println!("📊 Delivered {} queued messages for shop {}", sent, shop_id);
+        }
+    }
+    Ok(())
+}
+
+// GET /usage - Mức sử dụng tháng này + hạn mức của shop
+pub async fn usage_handler(State(state): State<Arc<AppState>>, admin: AdminAuth) -> impl IntoResponse {
+    match state.ws_state.quotas.usage(&state.ws_state.cluster, &admin.shop_id).await {
+        Ok(usage) => (StatusCode::OK, Bytes::from(usage.encode_to_vec())),
+        Err(e) => {
+            eprintln!("❌ Read usage for {} failed: {:?}", admin.shop_id, e);
+            (StatusCode::SERVICE_UNAVAILABLE, Bytes::new())
+        }
+    }
+}
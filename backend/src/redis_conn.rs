@@ -0,0 +1,40 @@
+// Một kết nối Redis (multiplexed) dùng chung cho mọi lệnh của một thành phần - mở lần đầu cần dùng.
+// Lỗi mạng / mất kết nối → bỏ kết nối cũ, lần sau nối lại
+use redis::aio::MultiplexedConnection;
+use tokio::sync::Mutex;
+
+pub struct RedisConn {
+    client: redis::Client,
+    conn: Mutex<Option<MultiplexedConnection>>,
+}
+
+impl RedisConn {
+    pub fn open(redis_url: &str) -> redis::RedisResult<Self> {
+        Ok(Self { client: redis::Client::open(redis_url)?, conn: Mutex::new(None) })
+    }
+
+    /// Kết nối riêng cho pub/sub (không dùng chung được)
+    pub fn client(&self) -> &redis::Client {
+        &self.client
+    }
+
+    pub async fn get(&self) -> redis::RedisResult<MultiplexedConnection> {
+        let mut cached = self.conn.lock().await;
+        if let Some(conn) = cached.as_ref() {
+            return Ok(conn.clone());
+        }
+        let conn = self.client.get_multiplexed_async_connection().await?;
+        *cached = Some(conn.clone());
+        Ok(conn)
+    }
+
+    /// Trả lại `result`; lỗi do kết nối thì bỏ kết nối đang giữ
+    pub async fn checked<T>(&self, result: redis::RedisResult<T>) -> redis::RedisResult<T> {
+        if let Err(e) = &result {
+            if e.is_io_error() || e.is_connection_dropped() || e.is_connection_refusal() || e.is_timeout() {
+                self.conn.lock().await.take();
+            }
+        }
+        result
+    }
+}
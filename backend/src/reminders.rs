@@ -0,0 +1,163 @@
+// Nhắc lại sau: admin tạm ẩn hội thoại ("nhắc tôi sau 2 giờ"), tới giờ scheduler đưa hội thoại trở lại + báo admin.
+// Khách nhắn trước giờ nhắc thì hội thoại trở lại ngay (như hội thoại đã đóng tự mở lại)
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use prost::Message as ProstMessage;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::auth::AdminAuth;
+use crate::contract::{ContractError, Message as ChatMessage, Reminder, ReminderListResponse, WsEnvelope};
+use crate::db::now_us;
+use crate::request_id;
+use crate::state::AppState;
+use crate::websocket::{self, publish_envelope, WebSocketState};
+
+// Tạm ẩn xa nhất 90 ngày
+const MAX_AHEAD_US: u64 = 90 * 86_400 * 1_000_000;
+// Khoá Redis giữ lời nhắc đang báo - nhiều node cùng quét không báo trùng
+const CLAIM_TTL_SECS: u64 = 3600;
+
+// GET /reminders - Mọi hội thoại đang tạm ẩn của shop (sidebar mục "Nhắc lại sau")
+pub async fn list_reminders_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+) -> impl IntoResponse {
+    let resp = match state.repo.list_reminders(&admin.shop_id).await {
+        Ok(items) => ReminderListResponse { success: true, items, ..Default::default() },
+        Err(e) => ReminderListResponse { success: false, items: vec![], error: e.to_string(), request_id: request_id::current() },
+    };
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+}
+
+// PUT /guests/:id/reminder - Tạm ẩn hội thoại tới remind_at_us (đặt lại thì ghi đè), trả lại lời nhắc đã lưu
+pub async fn set_reminder_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path(guest_id): Path<u64>,
+    body: Bytes,
+) -> impl IntoResponse {
+    let Ok(req) = Reminder::decode(&body[..]) else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+    let now = now_us();
+    if req.remind_at_us <= now || req.remind_at_us > now + MAX_AHEAD_US {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    }
+
+    let reminder = Reminder {
+        guest_id,
+        remind_at_us: req.remind_at_us,
+        agent_id: admin.agent_id.clone(),
+        created_at: now,
+        ..Default::default()
+    };
+    if let Err(e) = state.repo.upsert_reminder(&admin.shop_id, &reminder).await {
+        eprintln!("❌ Save reminder failed: {:?}", e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new());
+    }
+
+    let detail = format!("remind_at={}", reminder.remind_at_us);
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "conversation.snooze", guest_id, &admin.agent_id, &detail).await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("💤 Guest {} of shop {} snoozed until {}", guest_id, admin.shop_id, reminder.remind_at_us);
+
+    broadcast(&state.ws_state, &admin.shop_id, reminder.clone()).await;
+    (StatusCode::OK, Bytes::from(reminder.encode_to_vec()))
+}
+
+// DELETE /guests/:id/reminder - Bỏ tạm ẩn, hội thoại trở lại danh sách ngay
+pub async fn cancel_reminder_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path(guest_id): Path<u64>,
+) -> impl IntoResponse {
+    if let Err(e) = state.repo.delete_reminder(&admin.shop_id, guest_id).await {
+        eprintln!("❌ Cancel reminder failed: {:?}", e);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "conversation.unsnooze", guest_id, &admin.agent_id, "").await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+
+    let cancelled = Reminder { guest_id, cancelled: true, ..Default::default() };
+    broadcast(&state.ws_state, &admin.shop_id, cancelled).await;
+    StatusCode::NO_CONTENT
+}
+
+/// Tin khách mới → bỏ tạm ẩn hội thoại (nếu có)
+pub async fn on_guest_message(state: &WebSocketState, msg: &ChatMessage) {
+    match state.repo.get_reminder(&msg.shop_id, msg.guest_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("❌ Load reminder for guest {} failed: {:?}", msg.guest_id, e);
+            return;
+        }
+    }
+    if let Err(e) = state.repo.delete_reminder(&msg.shop_id, msg.guest_id).await {
+        eprintln!("❌ Remove reminder for guest {} failed: {:?}", msg.guest_id, e);
+        return;
+    }
+    println!("💤 Guest {} wrote back, snooze cleared", msg.guest_id);
+    broadcast(state, &msg.shop_id, Reminder { guest_id: msg.guest_id, cancelled: true, ..Default::default() }).await;
+}
+
+// ============================================================================
+// SCHEDULER - quét định kỳ, lời nhắc tới hạn → xoá + frame Reminder{due} cho admin
+// ============================================================================
+pub async fn reminder_task(state: Arc<WebSocketState>) {
+    let interval_secs = std::env::var("REMINDER_TICK_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30);
+    println!("💤 Reminder scheduler started (every {}s)", interval_secs);
+
+    loop {
+        if let Err(e) = fire_due(&state).await {
+            eprintln!("❌ Reminder tick failed: {:?}", e);
+        }
+        tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+    }
+}
+
+async fn fire_due(state: &Arc<WebSocketState>) -> Result<(), ContractError> {
+    let now = now_us();
+    for shop_id in state.repo.list_shop_ids().await? {
+        let due: Vec<Reminder> = match state.repo.list_reminders(&shop_id).await {
+            Ok(items) => items.into_iter().filter(|r| r.remind_at_us <= now).collect(),
+            Err(e) => {
+                eprintln!("❌ Load reminders for {} failed: {:?}", shop_id, e);
+                continue;
+            }
+        };
+
+        for reminder in due {
+            let key = format!("reminder:{}:{}:{}", shop_id, reminder.guest_id, reminder.remind_at_us);
+            if !websocket::claim_once(state, &key, CLAIM_TTL_SECS).await {
+                continue;
+            }
+            if let Err(e) = state.repo.delete_reminder(&shop_id, reminder.guest_id).await {
+                eprintln!("❌ Remove reminder for guest {} failed: {:?}", reminder.guest_id, e);
+                continue;
+            }
+            println!("⏰ Reminder due for guest {} of shop {}", reminder.guest_id, shop_id);
+
+            broadcast(state, &shop_id, Reminder { cancelled: true, due: true, ..reminder }).await;
+        }
+    }
+    Ok(())
+}
+
+// Envelope admin_only → guest không biết hội thoại đang tạm ẩn
+async fn broadcast(state: &WebSocketState, shop_id: &str, reminder: Reminder) {
+    if let Err(e) = publish_envelope(state, &WsEnvelope::reminder(shop_id.to_string(), reminder)).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+}
@@ -0,0 +1,76 @@
+// ============================================================================
+// REQUEST ID - lần theo một request / một frame WS qua log: nhận → lưu DB → publish → fan-out
+// HTTP: nhận x-request-id của client (hoặc tự sinh), trả lại ở header response và field request_id của proto lỗi.
+// WS: lấy từ WsEnvelope.request_id của frame client gửi lên.
+// Id gắn vào task hiện tại (task-local) → publish_envelope tự ghi vào frame đi qua broker
+// ============================================================================
+
+use axum::{
+    extract::{MatchedPath, Request},
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use std::future::Future;
+use tracing::{field, info_span, Instrument};
+
+use crate::telemetry;
+
+pub const HEADER: &str = "x-request-id";
+const MAX_LEN: usize = 64;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Id client gửi lên, chỉ nhận ký tự an toàn cho log / header (chữ, số, '-', '_', '.', ':')
+pub fn accept(candidate: &str) -> Option<String> {
+    let valid = !candidate.is_empty()
+        && candidate.len() <= MAX_LEN
+        && candidate.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'));
+    valid.then(|| candidate.to_string())
+}
+
+/// 16 ký tự hex ngẫu nhiên
+pub fn generate() -> String {
+    hex::encode(&crate::blob::random_key()[..8])
+}
+
+/// Id của request đang xử lý; rỗng khi ngoài request (việc nền)
+pub fn current() -> String {
+    REQUEST_ID.try_with(|id| id.clone()).unwrap_or_default()
+}
+
+/// Chạy `f` với request id `id` (xử lý một frame WS)
+pub async fn scope<F: Future>(id: String, f: F) -> F::Output {
+    REQUEST_ID.scope(id, f).await
+}
+
+pub async fn middleware(request: Request, next: Next) -> Response {
+    let id = request.headers().get(HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(accept)
+        .unwrap_or_else(generate);
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    // Tên span theo route (không theo path thật → không bùng số lượng tên)
+    let route = request.extensions().get::<MatchedPath>().map(|p| p.as_str().to_string()).unwrap_or_else(|| path.clone());
+    let span = info_span!("http.request",
+        otel.name = %format!("{} {}", method, route),
+        http.request.method = %method,
+        http.route = %route,
+        request_id = %id,
+        http.response.status_code = field::Empty);
+    let traceparent = request.headers().get("traceparent").and_then(|v| v.to_str().ok()).unwrap_or("");
+    telemetry::link_parent(&span, traceparent);
+
+    let mut response = scope(id.clone(), next.run(request)).instrument(span.clone()).await;
+    span.record("http.response.status_code", response.status().as_u16());
+    if response.status().is_server_error() {
+        eprintln!("❌ {} {} → {} [request {}]", method, path, response.status(), id);
+    }
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(HEADER, value);
+    }
+    response
+}
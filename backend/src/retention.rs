@@ -0,0 +1,156 @@
+// Retention sweeper - xoá/lưu trữ tin nhắn quá hạn theo cấu hình từng shop.
+// Xoá hẳn → xoá luôn file đính kèm; lưu trữ (retention_archive) → giữ file, tin trong messages_archive vẫn trỏ tới.
+// Cả hai cách đều dọn bản sao nội dung ngoài messages_by_day: tin bị lọc (flagged_messages), tin cuối của
+// hội thoại (guests.last_message_preview, conversation_feed) và bucket ngày đã hết tin
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::attachments;
+use crate::contract::{ContractError, MessageId, RetentionStats};
+use crate::db::day_bucket;
+use crate::websocket::{claim_once, WebSocketState};
+
+const BATCH: u32 = 200;
+
+/// Thống kê lần quét gần nhất của từng shop
+#[derive(Default)]
+pub struct RetentionStatsStore {
+    inner: Mutex<HashMap<String, RetentionStats>>,
+}
+
+impl RetentionStatsStore {
+    pub fn get(&self, shop_id: &str) -> Option<RetentionStats> {
+        self.inner.lock().unwrap().get(shop_id).cloned()
+    }
+
+    fn set(&self, shop_id: &str, stats: RetentionStats) {
+        self.inner.lock().unwrap().insert(shop_id.to_string(), stats);
+    }
+}
+
+/// Mỗi lượt chỉ một node quét (các node cùng xoá một tin thì đua nhau) - thống kê nằm ở node đã quét
+pub async fn retention_sweeper_task(state: Arc<WebSocketState>, stats: Arc<RetentionStatsStore>) {
+    let interval_secs = std::env::var("RETENTION_SWEEP_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3600);
+    println!("🧹 Retention sweeper started (every {}s)", interval_secs);
+
+    loop {
+        if claim_once(&state, "retention:sweep", interval_secs).await {
+            if let Err(e) = sweep_all(&state, &stats).await {
+                eprintln!("❌ Retention sweep failed: {:?}", e);
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+    }
+}
+
+async fn sweep_all(state: &WebSocketState, stats: &RetentionStatsStore) -> Result<(), ContractError> {
+    for shop_id in state.repo.list_shop_ids().await? {
+        let settings = match state.repo.get_settings(&shop_id).await {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("❌ Load settings for {} failed: {:?}", shop_id, e);
+                continue;
+            }
+        };
+        if settings.retention_days == 0 {
+            continue;
+        }
+
+        // Một shop lỗi không chặn các shop sau
+        let shop_stats = match sweep_shop(state, &shop_id, settings.retention_days, settings.retention_archive).await {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("❌ Retention sweep for {} failed: {:?}", shop_id, e);
+                continue;
+            }
+        };
+        if shop_stats.messages_deleted > 0 || shop_stats.messages_archived > 0 {
+            println!("🧹 Shop {}: purged {} messages ({} archived)",
+                shop_id, shop_stats.messages_deleted, shop_stats.messages_archived);
+        }
+        stats.set(&shop_id, shop_stats);
+    }
+    Ok(())
+}
+
+async fn sweep_shop(
+    state: &WebSocketState,
+    shop_id: &str,
+    retention_days: u32,
+    archive: bool,
+) -> Result<RetentionStats, ContractError> {
+    let now_us = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_micros() as u64;
+    let cutoff_us = now_us.saturating_sub(retention_days as u64 * 86_400 * 1_000_000);
+
+    // message_id tăng theo thời gian → dùng làm mốc cắt.
+    // Id cũ (µs) và Snowflake id nằm ở 2 khoảng riêng → cắt từng khoảng
+    let ranges = [
+        (0, cutoff_us.min(MessageId::LEGACY_MAX)),
+        (MessageId::LEGACY_MAX - 1, MessageId::lower_bound(cutoff_us / 1000).0),
+    ];
+
+    let mut stats = RetentionStats { last_run_us: now_us, ..Default::default() };
+
+    let cutoff_bucket = day_bucket(MessageId::lower_bound(cutoff_us / 1000).0);
+
+    for guest in state.repo.get_guests(shop_id).await? {
+        let deleted_before = stats.messages_deleted;
+        for (after_id, before_id) in ranges {
+            sweep_range(state, archive, shop_id, guest.guest_id, after_id, before_id, &mut stats).await?;
+        }
+        if stats.messages_deleted == deleted_before {
+            continue;
+        }
+        state.repo.drop_buckets_before(shop_id, guest.guest_id, cutoff_bucket).await?;
+        // Tin cuối đã quá hạn → sidebar / feed không được giữ nội dung của nó
+        if guest.last_message_at < cutoff_us {
+            state.repo.refresh_last_message(shop_id, guest.guest_id).await?;
+        }
+    }
+
+    Ok(stats)
+}
+
+// `archive` = chép sang messages_archive trước khi xoá (giữ file, vẫn tính dung lượng);
+// xoá hẳn → xoá cả file và trừ dung lượng của shop
+async fn sweep_range(
+    state: &WebSocketState,
+    archive: bool,
+    shop_id: &str,
+    guest_id: u64,
+    after_id: u64,
+    before_id: u64,
+    stats: &mut RetentionStats,
+) -> Result<(), ContractError> {
+    if before_id <= after_id + 1 {
+        return Ok(());
+    }
+    let repo = &state.repo;
+    loop {
+        let old = repo.fetch_messages_between(shop_id, guest_id, after_id, before_id, BATCH).await?;
+        if old.is_empty() {
+            break;
+        }
+        for msg in &old {
+            if archive {
+                repo.archive_message(msg).await?;
+                stats.messages_archived += 1;
+            }
+            repo.delete_message(shop_id, msg.guest_id, msg.message_id).await?;
+            repo.remove_flagged(shop_id, msg.message_id).await?;
+            stats.messages_deleted += 1;
+        }
+        if !archive {
+            attachments::delete_files(state.blobs.as_ref(), &old).await;
+            state.quotas.release_storage(shop_id, &old).await;
+        }
+        if old.len() < BATCH as usize {
+            break;
+        }
+    }
+    Ok(())
+}
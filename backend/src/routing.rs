@@ -0,0 +1,215 @@
+// Luật tự động cho tin khách: URL trang / nội dung / giờ làm việc / hội thoại mới → gắn tag, bot trả lời,
+// giao người phụ trách, chạy kịch bản bot
+use axum::{body::Bytes, extract::State, http::StatusCode, response::IntoResponse};
+use prost::Message as ProstMessage;
+use std::sync::Arc;
+
+use crate::auth::{AdminAuth, OwnerAuth};
+use crate::contract::{rule_action, rule_condition, ConversationStatus, Guest, Message as ChatMessage, RoutingRule, RoutingRules, ShopSettings, WsEnvelope};
+use crate::flows;
+use crate::presence;
+use crate::state::AppState;
+use crate::websocket::{claim_once, publish_envelope, send_bot_message, WebSocketState};
+
+const MAX_RULES: usize = 50;
+const MAX_RULE_TEXT_CHARS: usize = 500;
+// Bot trả lời mỗi khách tối đa 1 lần trong khoảng này
+const AUTO_REPLY_COOLDOWN_SECS: u64 = 30 * 60;
+
+/// Kết quả xét luật cho một tin
+#[derive(Default)]
+pub struct RuleOutcome {
+    pub tags: Vec<String>,
+    pub auto_replies: Vec<String>,
+    pub assign_to: Option<String>,
+    pub start_flow: Option<String>,
+}
+
+/// Dữ liệu luật được xét trên
+pub struct RuleInput<'a> {
+    pub page_url: &'a str,
+    pub text: &'a str,
+    pub outside_hours: bool,
+    pub new_conversation: bool,
+}
+
+/// Xét luật theo thứ tự; luật sau ghi đè người phụ trách của luật trước
+pub fn evaluate(rules: &[RoutingRule], input: &RuleInput) -> RuleOutcome {
+    let text = input.text.to_lowercase();
+    let mut outcome = RuleOutcome::default();
+
+    for rule in rules.iter().filter(|r| r.enabled) {
+        let matched = rule.conditions.iter().all(|c| match &c.kind {
+            Some(rule_condition::Kind::PageUrlContains(needle)) => input.page_url.contains(needle.as_str()),
+            Some(rule_condition::Kind::MessageContains(needle)) => text.contains(&needle.to_lowercase()),
+            Some(rule_condition::Kind::OutsideBusinessHours(outside)) => *outside == input.outside_hours,
+            Some(rule_condition::Kind::NewConversation(new)) => *new == input.new_conversation,
+            None => true,
+        });
+        if !matched {
+            continue;
+        }
+
+        for action in &rule.actions {
+            match &action.kind {
+                Some(rule_action::Kind::AddTag(tag)) if !outcome.tags.contains(tag) => outcome.tags.push(tag.clone()),
+                Some(rule_action::Kind::AutoReply(reply)) => outcome.auto_replies.push(reply.clone()),
+                Some(rule_action::Kind::AssignTo(agent)) => outcome.assign_to = Some(agent.clone()),
+                Some(rule_action::Kind::StartFlow(flow_id)) => outcome.start_flow = Some(flow_id.clone()),
+                _ => {}
+            }
+        }
+        if rule.stop {
+            break;
+        }
+    }
+    outcome
+}
+
+/// Luật có điều kiện "hội thoại mới" → xét guest trước khi lưu tin này (khách chưa từng nhắn / hội thoại đã đóng)
+pub async fn is_new_conversation(state: &WebSocketState, settings: &ShopSettings, shop_id: &str, guest_id: u64) -> bool {
+    let used = settings.routing_rules.iter()
+        .filter(|r| r.enabled)
+        .flat_map(|r| &r.conditions)
+        .any(|c| matches!(c.kind, Some(rule_condition::Kind::NewConversation(_))));
+    if !used {
+        return false;
+    }
+    match state.repo.get_guest(shop_id, guest_id).await {
+        Ok(Some(guest)) => guest.last_message_at == 0 || guest.status() != ConversationStatus::Open,
+        Ok(None) => true,
+        Err(e) => {
+            eprintln!("❌ Load guest {} for routing failed: {:?}", guest_id, e);
+            false
+        }
+    }
+}
+
+/// Chạy sau khi tin khách đã lưu + publish. Hội thoại đang chạy kịch bản bot (`in_flow`) → không bot trả lời / đổi kịch bản
+pub async fn apply_rules(state: &WebSocketState, settings: &ShopSettings, msg: &ChatMessage, new_conversation: bool, in_flow: bool) {
+    if !settings.routing_rules.iter().any(|r| r.enabled) {
+        return;
+    }
+
+    let guest = match state.repo.get_guest(&msg.shop_id, msg.guest_id).await {
+        Ok(Some(guest)) => guest,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("❌ Load guest {} for routing failed: {:?}", msg.guest_id, e);
+            return;
+        }
+    };
+    let text = String::from_utf8_lossy(&msg.content);
+    let input = RuleInput {
+        page_url: guest.context.as_ref().map_or("", |c| c.page_url.as_str()),
+        text: &text,
+        outside_hours: !presence::is_open(settings, chrono::Utc::now()),
+        new_conversation,
+    };
+    let outcome = evaluate(&settings.routing_rules, &input);
+
+    // Tag + người phụ trách → cập nhật guest và báo admin
+    let mut tags = guest.tags.clone();
+    for tag in &outcome.tags {
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+    let assigned_to = outcome.assign_to.clone().unwrap_or_else(|| guest.assigned_to.clone());
+    if tags != guest.tags || assigned_to != guest.assigned_to {
+        if let Err(e) = state.repo.set_guest_routing(&msg.shop_id, msg.guest_id, &tags, &assigned_to).await {
+            eprintln!("❌ Save routing for guest {} failed: {:?}", msg.guest_id, e);
+        } else {
+            println!("🧭 Guest {} routed: tags={:?}, assigned_to={:?}", msg.guest_id, tags, assigned_to);
+            let guest = Guest { tags, assigned_to, ..guest };
+            if let Err(e) = publish_envelope(state, &WsEnvelope::guest(guest)).await {
+                eprintln!("❌ Redis publish failed: {:?}", e);
+            }
+        }
+    }
+
+    // Chống bot trả lời dồn dập cho cùng một khách - khoá trên Redis, khách mở nhiều tab ở nhiều node vẫn chỉ nhận một lần
+    if in_flow {
+        return;
+    }
+    if !outcome.auto_replies.is_empty()
+        && claim_once(state, &format!("autoreply:{}:{}", msg.shop_id, msg.guest_id), AUTO_REPLY_COOLDOWN_SECS).await
+    {
+        for reply in &outcome.auto_replies {
+            send_bot_message(state, &msg.shop_id, msg.guest_id, reply).await;
+        }
+    }
+    if let Some(flow_id) = &outcome.start_flow {
+        flows::start(state, settings, &msg.shop_id, msg.guest_id, flow_id).await;
+    }
+}
+
+// GET /routing-rules
+pub async fn get_rules_handler(State(state): State<Arc<AppState>>, admin: AdminAuth) -> impl IntoResponse {
+    match state.repo.get_settings(&admin.shop_id).await {
+        Ok(settings) => {
+            let resp = RoutingRules { rules: settings.routing_rules };
+            (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+        }
+        Err(e) => {
+            eprintln!("❌ Load settings for {} failed: {:?}", admin.shop_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new())
+        }
+    }
+}
+
+// PUT /routing-rules - Thay toàn bộ danh sách luật, trả lại bản đã chuẩn hoá
+pub async fn put_rules_handler(State(state): State<Arc<AppState>>, OwnerAuth(admin): OwnerAuth, body: Bytes) -> impl IntoResponse {
+    let Ok(req) = RoutingRules::decode(&body[..]) else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+    if req.rules.len() > MAX_RULES {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    }
+    let rules: Vec<RoutingRule> = req.rules.into_iter().map(normalize).collect();
+
+    let mut settings = match state.repo.get_settings(&admin.shop_id).await {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("❌ Load settings for {} failed: {:?}", admin.shop_id, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new());
+        }
+    };
+    settings.routing_rules = rules.clone();
+    if let Err(e) = state.repo.save_settings(&admin.shop_id, &settings).await {
+        eprintln!("❌ Save routing rules failed: {:?}", e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new());
+    }
+    state.ws_state.settings.put(&admin.shop_id, settings);
+
+    let detail = format!("{} rules", rules.len());
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "routing.update", 0, &admin.agent_id, &detail).await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("🧭 Routing rules updated for shop {} ({} rules)", admin.shop_id, rules.len());
+
+    (StatusCode::OK, Bytes::from(RoutingRules { rules }.encode_to_vec()))
+}
+
+// Cắt chuỗi quá dài, bỏ điều kiện/hành động rỗng
+fn normalize(mut rule: RoutingRule) -> RoutingRule {
+    let clean = |s: &mut String| *s = s.trim().chars().take(MAX_RULE_TEXT_CHARS).collect();
+    clean(&mut rule.name);
+    rule.conditions.retain_mut(|c| match &mut c.kind {
+        Some(rule_condition::Kind::PageUrlContains(s)) | Some(rule_condition::Kind::MessageContains(s)) => {
+            clean(s);
+            !s.is_empty()
+        }
+        Some(rule_condition::Kind::OutsideBusinessHours(_)) | Some(rule_condition::Kind::NewConversation(_)) => true,
+        None => false,
+    });
+    rule.actions.retain_mut(|a| match &mut a.kind {
+        Some(rule_action::Kind::AddTag(s)) | Some(rule_action::Kind::AutoReply(s)) | Some(rule_action::Kind::AssignTo(s))
+        | Some(rule_action::Kind::StartFlow(s)) => {
+            clean(s);
+            !s.is_empty()
+        }
+        None => false,
+    });
+    rule
+}
@@ -0,0 +1,113 @@
+// Quét virus file đính kèm lúc upload - clamd (INSTREAM qua TCP hoặc unix socket), thêm engine khác qua trait Scanner
+use futures::future::BoxFuture;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const SCAN_TIMEOUT: Duration = Duration::from_secs(30);
+// clamd mặc định StreamMaxLength 25MB; gửi từng khúc nhỏ
+const CHUNK_SIZE: usize = 64 * 1024;
+
+pub enum Verdict {
+    Clean,
+    /// Tên mẫu phát hiện, vd. "Eicar-Test-Signature"
+    Infected(String),
+}
+
+/// Lỗi của bước quét - upload bị từ chối trong cả hai trường hợp
+#[derive(Debug)]
+pub enum ScanError {
+    Infected(String),
+    /// Không quét được (engine lỗi / mất kết nối) → không cho qua
+    Unavailable(String),
+}
+
+pub trait Scanner: Send + Sync {
+    /// Tên engine ghi vào ScanResult
+    fn engine(&self) -> &'static str;
+    fn scan<'a>(&'a self, data: &'a [u8]) -> BoxFuture<'a, Result<Verdict, String>>;
+}
+
+/// AV_SCANNER=clamd bật quét: CLAMD_SOCKET (unix socket) hoặc CLAMD_ADDR (host:port, mặc định 127.0.0.1:3310).
+/// Không đặt → không quét (ScanStatus::Skipped).
+pub fn from_env() -> Option<Box<dyn Scanner>> {
+    match std::env::var("AV_SCANNER").unwrap_or_default().as_str() {
+        "clamd" => {
+            let scanner = ClamdScanner::from_env();
+            println!("🦠 Attachment scanning via clamd at {}", scanner.target);
+            Some(Box::new(scanner))
+        }
+        "" | "none" => {
+            println!("🦠 AV_SCANNER not set, attachments are not scanned");
+            None
+        }
+        other => panic!("❌ Unknown AV_SCANNER '{}' (expected clamd or none)", other),
+    }
+}
+
+pub struct ClamdScanner {
+    // "unix:/path" hoặc "host:port"
+    target: String,
+}
+
+impl ClamdScanner {
+    pub fn from_env() -> Self {
+        let target = match std::env::var("CLAMD_SOCKET") {
+            Ok(path) if !path.is_empty() => format!("unix:{}", path),
+            _ => std::env::var("CLAMD_ADDR").unwrap_or_else(|_| "127.0.0.1:3310".to_string()),
+        };
+        Self { target }
+    }
+
+    async fn scan_inner(&self, data: &[u8]) -> Result<Verdict, String> {
+        #[cfg(unix)]
+        if let Some(path) = self.target.strip_prefix("unix:") {
+            let stream = tokio::net::UnixStream::connect(path).await.map_err(|e| format!("connect failed: {}", e))?;
+            return instream(stream, data).await;
+        }
+        let stream = tokio::net::TcpStream::connect(&self.target).await.map_err(|e| format!("connect failed: {}", e))?;
+        instream(stream, data).await
+    }
+}
+
+impl Scanner for ClamdScanner {
+    fn engine(&self) -> &'static str {
+        "clamd"
+    }
+
+    fn scan<'a>(&'a self, data: &'a [u8]) -> BoxFuture<'a, Result<Verdict, String>> {
+        Box::pin(async move {
+            tokio::time::timeout(SCAN_TIMEOUT, self.scan_inner(data))
+                .await
+                .unwrap_or_else(|_| Err(format!("no answer within {}s", SCAN_TIMEOUT.as_secs())))
+        })
+    }
+}
+
+// Giao thức INSTREAM: "zINSTREAM\0", các khúc <độ dài u32 big-endian><dữ liệu>, kết thúc bằng khúc độ dài 0
+async fn instream<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, data: &[u8]) -> Result<Verdict, String> {
+    let io = |e: std::io::Error| format!("clamd I/O failed: {}", e);
+    stream.write_all(b"zINSTREAM\0").await.map_err(io)?;
+    for chunk in data.chunks(CHUNK_SIZE) {
+        stream.write_all(&(chunk.len() as u32).to_be_bytes()).await.map_err(io)?;
+        stream.write_all(chunk).await.map_err(io)?;
+    }
+    stream.write_all(&0u32.to_be_bytes()).await.map_err(io)?;
+    stream.flush().await.map_err(io)?;
+
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply).await.map_err(io)?;
+    parse_reply(&String::from_utf8_lossy(&reply))
+}
+
+// "stream: OK" / "stream: Eicar-Test-Signature FOUND" / "INSTREAM size limit exceeded. ERROR"
+fn parse_reply(reply: &str) -> Result<Verdict, String> {
+    let reply = reply.trim_end_matches(['\0', '\n']).trim();
+    let body = reply.strip_prefix("stream:").map(str::trim).unwrap_or(reply);
+    if body == "OK" {
+        return Ok(Verdict::Clean);
+    }
+    if let Some(signature) = body.strip_suffix("FOUND") {
+        return Ok(Verdict::Infected(signature.trim().to_string()));
+    }
+    Err(format!("clamd replied {:?}", reply))
+}
@@ -0,0 +1,172 @@
+// Tin hẹn giờ: admin lên lịch ("gửi 9:00 sáng mai"), scheduler gửi đúng giờ qua pipeline thường
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use prost::Message as ProstMessage;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::auth::AdminAuth;
+use crate::contract::{ContractError, Message as ChatMessage, QuickReply, ScheduledListResponse, ScheduledMessage, WsEnvelope};
+use crate::db::now_us;
+use crate::request_id;
+use crate::state::AppState;
+use crate::websocket::{self, publish_envelope, WebSocketState};
+
+// Hẹn xa nhất 1 năm
+const MAX_AHEAD_US: u64 = 365 * 86_400 * 1_000_000;
+// Khoá Redis giữ tin đang gửi - nhiều node cùng quét không gửi trùng
+const CLAIM_TTL_SECS: u64 = 3600;
+
+// GET /guests/:id/scheduled - Tin hẹn giờ còn chờ của khách (sớm nhất trước)
+pub async fn list_scheduled_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path(guest_id): Path<u64>,
+) -> impl IntoResponse {
+    let resp = match state.repo.list_scheduled(&admin.shop_id).await {
+        Ok(items) => ScheduledListResponse {
+            success: true,
+            items: items.into_iter().filter(|s| s.guest_id == guest_id).collect(),
+            ..Default::default()
+        },
+        Err(e) => ScheduledListResponse { success: false, items: vec![], error: e.to_string(), request_id: request_id::current() },
+    };
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+}
+
+// POST /guests/:id/scheduled - Hẹn giờ một tin, trả lại tin đã lưu
+pub async fn create_scheduled_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path(guest_id): Path<u64>,
+    body: Bytes,
+) -> impl IntoResponse {
+    let Ok(req) = ScheduledMessage::decode(&body[..]) else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+    let now = now_us();
+    if req.send_at_us <= now || req.send_at_us > now + MAX_AHEAD_US {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    }
+    if String::from_utf8_lossy(&req.content).trim().is_empty() {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    }
+
+    let scheduled = ScheduledMessage {
+        scheduled_id: state.repo.ids.next_id().0,
+        guest_id,
+        content: req.content,
+        send_at_us: req.send_at_us,
+        created_at: now,
+        quick_replies: QuickReply::sanitize(req.quick_replies),
+        cancelled: false,
+    };
+    if let Err(e) = state.repo.insert_scheduled(&admin.shop_id, &scheduled).await {
+        eprintln!("❌ Insert scheduled message failed: {:?}", e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new());
+    }
+
+    let detail = format!("send_at={}", scheduled.send_at_us);
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "message.schedule", guest_id, &admin.agent_id, &detail).await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("⏰ Message {} scheduled for guest {} of shop {}", scheduled.scheduled_id, guest_id, admin.shop_id);
+
+    broadcast(&state.ws_state, &admin.shop_id, scheduled.clone()).await;
+    (StatusCode::OK, Bytes::from(scheduled.encode_to_vec()))
+}
+
+// DELETE /guests/:id/scheduled/:scheduled_id - Huỷ tin hẹn giờ
+pub async fn cancel_scheduled_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path((guest_id, scheduled_id)): Path<(u64, u64)>,
+) -> impl IntoResponse {
+    if let Err(e) = state.repo.delete_scheduled(&admin.shop_id, scheduled_id).await {
+        eprintln!("❌ Cancel scheduled message failed: {:?}", e);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "message.unschedule", guest_id, &admin.agent_id, "").await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+
+    let cancelled = ScheduledMessage { scheduled_id, guest_id, cancelled: true, ..Default::default() };
+    broadcast(&state.ws_state, &admin.shop_id, cancelled).await;
+    StatusCode::NO_CONTENT
+}
+
+// ============================================================================
+// SCHEDULER - quét định kỳ, tin tới hạn đi qua ingest như tin admin gửi tay
+// ============================================================================
+pub async fn scheduler_task(state: Arc<WebSocketState>) {
+    let interval_secs = std::env::var("SCHEDULER_TICK_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(15);
+    println!("⏰ Message scheduler started (every {}s)", interval_secs);
+
+    loop {
+        if let Err(e) = send_due(&state).await {
+            eprintln!("❌ Scheduler tick failed: {:?}", e);
+        }
+        tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+    }
+}
+
+async fn send_due(state: &Arc<WebSocketState>) -> Result<(), ContractError> {
+    let now = now_us();
+    for shop_id in state.repo.list_shop_ids().await? {
+        let due: Vec<ScheduledMessage> = match state.repo.list_scheduled(&shop_id).await {
+            Ok(items) => items.into_iter().filter(|s| s.send_at_us <= now).collect(),
+            Err(e) => {
+                eprintln!("❌ Load scheduled messages for {} failed: {:?}", shop_id, e);
+                continue;
+            }
+        };
+
+        for scheduled in due {
+            let key = format!("scheduled:{}:{}", shop_id, scheduled.scheduled_id);
+            if !websocket::claim_once(state, &key, CLAIM_TTL_SECS).await {
+                continue;
+            }
+            // Xoá trước khi gửi: lỗi giữa chừng thì mất tin chứ không gửi lặp
+            if let Err(e) = state.repo.delete_scheduled(&shop_id, scheduled.scheduled_id).await {
+                eprintln!("❌ Remove scheduled message {} failed: {:?}", scheduled.scheduled_id, e);
+                continue;
+            }
+            println!("📤 Sending scheduled message {} to guest {}", scheduled.scheduled_id, scheduled.guest_id);
+
+            let msg = ChatMessage {
+                shop_id: shop_id.clone(),
+                guest_id: scheduled.guest_id,
+                sender_type: "admin".to_string(),
+                content_crc: crc32c::crc32c(&scheduled.content),
+                content: scheduled.content.clone(),
+                quick_replies: scheduled.quick_replies.clone(),
+                ..Default::default()
+            };
+            websocket::ingest_message(state, msg, "").await;
+
+            let sent = ScheduledMessage {
+                scheduled_id: scheduled.scheduled_id,
+                guest_id: scheduled.guest_id,
+                cancelled: true,
+                ..Default::default()
+            };
+            broadcast(state, &shop_id, sent).await;
+        }
+    }
+    Ok(())
+}
+
+// Envelope admin_only → guest không thấy tin trước giờ gửi
+async fn broadcast(state: &WebSocketState, shop_id: &str, scheduled: ScheduledMessage) {
+    if let Err(e) = publish_envelope(state, &WsEnvelope::scheduled(shop_id.to_string(), scheduled)).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+}
@@ -0,0 +1,79 @@
+// Tìm tin trong một hội thoại (admin) - phần lịch sử admin chưa tải về; phần đã tải thì admin panel tự lọc
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use prost::Message as ProstMessage;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::attachments;
+use crate::auth::AdminAuth;
+use crate::contract::{ContractError, Message, MessageSearchResponse};
+use crate::merge;
+use crate::state::AppState;
+
+const MAX_QUERY_CHARS: usize = 100;
+// Tin khớp tối đa mỗi lần gọi
+const MAX_RESULTS: usize = 50;
+// Số tin đọc tối đa mỗi guest mỗi lần gọi (hội thoại dài → client gọi tiếp với ?before=)
+const SCAN_LIMIT: usize = 5_000;
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    #[serde(default)]
+    pub q: String,
+    // next_before của lần tìm trước, 0 = từ tin mới nhất
+    #[serde(default)]
+    pub before: u64,
+}
+
+// GET /guests/:id/search?q=&before=
+pub async fn search_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path(guest_id): Path<u64>,
+    Query(query): Query<SearchQuery>,
+) -> impl IntoResponse {
+    let q = query.q.trim();
+    if q.is_empty() || q.chars().count() > MAX_QUERY_CHARS {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    }
+
+    let (mut messages, next_before) = match search(&state, &admin.shop_id, guest_id, q, query.before).await {
+        Ok(found) => found,
+        Err(e) => {
+            eprintln!("❌ Search in guest {} failed: {:?}", guest_id, e);
+            let status = match e {
+                ContractError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            return (status, Bytes::new());
+        }
+    };
+    state.repo.hydrate_replies(&mut messages).await;
+    attachments::sign_urls(state.ws_state.blobs.as_ref(), &mut messages);
+
+    let resp = MessageSearchResponse { messages, next_before: next_before.unwrap_or(0) };
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+}
+
+// Khách đã gộp → tìm trong guest gốc + mọi alias, ghép lại theo message_id
+async fn search(state: &AppState, shop_id: &str, guest_id: u64, q: &str, before: u64) -> Result<(Vec<Message>, Option<u64>), ContractError> {
+    let guests = merge::conversation_guests(&state.ws_state, shop_id, guest_id).await?;
+    let results = futures::future::try_join_all(
+        guests.iter().map(|&gid| state.repo.search_messages(shop_id, gid, q, before, MAX_RESULTS, SCAN_LIMIT)),
+    ).await?;
+
+    // Guest nào chưa quét hết thì phải tìm tiếp từ chỗ gần hiện tại nhất (có thể trả trùng - client bỏ theo id)
+    let mut next_before = results.iter().filter_map(|(_, next)| *next).max();
+    let mut messages: Vec<_> = results.into_iter().flat_map(|(found, _)| found).collect();
+    messages.sort_by_key(|m| std::cmp::Reverse(m.message_id));
+    if messages.len() > MAX_RESULTS {
+        messages.truncate(MAX_RESULTS);
+        next_before = next_before.max(messages.last().map(|m| m.message_id));
+    }
+    Ok((messages, next_before))
+}
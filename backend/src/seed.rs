@@ -0,0 +1,196 @@
+// Dữ liệu mẫu cho dev/chụp màn hình: `backend --seed-demo` tạo shop demo123 (PIN 123456),
+// vài khách và lịch sử hội thoại. Shop đã có khách thì bỏ qua.
+use crate::contract::{ContractError, ConversationStatus, GeoLocation, Message, MessageId, ShopSettings, VisitorContext};
+use crate::db::AstraRepo;
+
+pub const DEMO_SHOP_ID: &str = "demo123";
+const DEMO_SHOP_NAME: &str = "Shop Demo";
+const DEMO_PIN: &str = "123456";
+// Khoảng cách giữa hai tin trong một hội thoại mẫu
+const MESSAGE_GAP_MS: u64 = 90_000;
+
+struct DemoGuest {
+    guest_id: u64,
+    name: &'static str,
+    email: &'static str,
+    ip: &'static str,
+    city: &'static str,
+    page_url: &'static str,
+    tags: &'static [&'static str],
+    assigned_to: &'static str,
+    status: ConversationStatus,
+    // Hội thoại bắt đầu cách đây bao lâu
+    hours_ago: u64,
+    // (sender_type, nội dung)
+    script: &'static [(&'static str, &'static str)],
+}
+
+const GUESTS: &[DemoGuest] = &[
+    DemoGuest {
+        guest_id: 900_001,
+        name: "Nguyễn Văn An",
+        email: "an.nguyen@example.com",
+        ip: "203.113.0.10",
+        city: "Hà Nội",
+        page_url: "https://shop.example.com/ao-thun-basic",
+        tags: &["size"],
+        assigned_to: "owner",
+        status: ConversationStatus::Open,
+        hours_ago: 1,
+        script: &[
+            ("guest", "Chào shop, áo thun basic còn size L màu trắng không ạ?"),
+            ("admin", "Chào anh An, size L màu trắng bên em còn 3 chiếc ạ."),
+            ("guest", "Mình cao 1m75, nặng 70kg thì mặc L có vừa không?"),
+            ("admin", "Dạ với số đo đó anh mặc L là vừa, thích rộng thì lấy XL ạ."),
+            ("guest", "Ok shop, mình đặt 2 cái L nhé. Ship về Cầu Giấy mất mấy ngày?"),
+        ],
+    },
+    DemoGuest {
+        guest_id: 900_002,
+        name: "Trần Thị Bình",
+        email: "binh.tran@example.com",
+        ip: "113.161.0.22",
+        city: "TP. Hồ Chí Minh",
+        page_url: "https://shop.example.com/don-hang/DH10293",
+        tags: &["giao-hang"],
+        assigned_to: "",
+        status: ConversationStatus::Closed,
+        hours_ago: 26,
+        script: &[
+            ("guest", "Đơn DH10293 của mình đặt 3 hôm rồi mà chưa thấy giao ạ"),
+            ("admin", "Chị Bình chờ em kiểm tra chút nhé."),
+            ("admin", "Đơn của chị đang ở kho Thủ Đức, chiều nay shipper sẽ giao ạ."),
+            ("guest", "Cảm ơn shop nhiều!"),
+            ("admin", "Dạ không có gì ạ, chị nhận hàng vui vẻ nhé 🎉"),
+        ],
+    },
+    DemoGuest {
+        guest_id: 900_003,
+        name: "Lê Minh Châu",
+        email: "",
+        ip: "14.161.0.35",
+        city: "Đà Nẵng",
+        page_url: "https://shop.example.com/chinh-sach-doi-tra",
+        tags: &["doi-tra"],
+        assigned_to: "owner",
+        status: ConversationStatus::Open,
+        hours_ago: 5,
+        script: &[
+            ("guest", "Shop ơi mình muốn đổi quần jean sang size khác được không?"),
+            ("bot", "Shop hỗ trợ đổi size trong 7 ngày kể từ khi nhận hàng, sản phẩm còn nguyên tem mác."),
+            ("guest", "Mình nhận hôm qua, tem vẫn còn. Gửi lại về đâu ạ?"),
+            ("admin", "Bạn gửi về 12 Nguyễn Văn Linh, Đà Nẵng, ghi chú \"đổi size\" giúp shop nhé."),
+            ("guest", "Phí ship đổi hàng ai chịu vậy shop?"),
+        ],
+    },
+    DemoGuest {
+        guest_id: 900_004,
+        name: "",
+        email: "",
+        ip: "171.244.0.48",
+        city: "Cần Thơ",
+        page_url: "https://shop.example.com/",
+        tags: &[],
+        assigned_to: "",
+        status: ConversationStatus::Archived,
+        hours_ago: 72,
+        script: &[
+            ("guest", "Shop có bán sỉ không ạ?"),
+            ("admin", "Dạ có, đơn từ 50 sản phẩm bên em chiết khấu 15% ạ."),
+        ],
+    },
+    DemoGuest {
+        guest_id: 900_005,
+        name: "Phạm Quốc Dũng",
+        email: "dung.pham@example.com",
+        ip: "27.72.0.57",
+        city: "Hải Phòng",
+        page_url: "https://shop.example.com/khuyen-mai",
+        tags: &["khuyen-mai"],
+        assigned_to: "",
+        status: ConversationStatus::Open,
+        hours_ago: 0,
+        script: &[
+            ("guest", "Mã giảm giá SALE50 nhập báo hết hạn là sao vậy shop?"),
+        ],
+    },
+];
+
+/// Tạo shop demo + khách + hội thoại qua AstraRepo. Gọi lại nhiều lần không nhân đôi dữ liệu
+pub async fn seed_demo(repo: &AstraRepo) -> Result<(), ContractError> {
+    if !repo.get_guests(DEMO_SHOP_ID).await?.is_empty() {
+        println!("🌱 Demo shop {} already has guests, skipping seed", DEMO_SHOP_ID);
+        return Ok(());
+    }
+
+    repo.create_shop(DEMO_SHOP_ID, DEMO_SHOP_NAME, DEMO_PIN).await?;
+    repo.save_settings(DEMO_SHOP_ID, &ShopSettings {
+        widget_title: "Shop Demo - Hỗ trợ khách hàng".to_string(),
+        widget_color: "#3390EC".to_string(),
+        offline_message: "Shop đang ngoài giờ làm việc, để lại lời nhắn shop sẽ trả lời sớm nhé!".to_string(),
+        ..Default::default()
+    }).await?;
+
+    let now_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+    let mut messages = 0;
+    for guest in GUESTS {
+        seed_guest(repo, guest, now_ms).await?;
+        messages += guest.script.len();
+    }
+
+    println!("🌱 Seeded demo shop {} (PIN {}): {} guests, {} messages", DEMO_SHOP_ID, DEMO_PIN, GUESTS.len(), messages);
+    Ok(())
+}
+
+async fn seed_guest(repo: &AstraRepo, guest: &DemoGuest, now_ms: u64) -> Result<(), ContractError> {
+    let shop_id = DEMO_SHOP_ID;
+    let start_ms = now_ms.saturating_sub(guest.hours_ago * 3_600_000 + guest.script.len() as u64 * MESSAGE_GAP_MS);
+
+    repo.upsert_guest(shop_id, guest.guest_id, guest.ip).await?;
+    repo.identify_guest(shop_id, guest.guest_id, guest.name, guest.email).await?;
+    repo.set_visitor_context(shop_id, guest.guest_id, &VisitorContext {
+        page_url: guest.page_url.to_string(),
+        referrer: "https://www.google.com/".to_string(),
+        user_agent: "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 Mobile/15E148".to_string(),
+        viewport_width: 390,
+        viewport_height: 844,
+        updated_at_us: start_ms * 1000,
+    }).await?;
+    repo.set_guest_geo(shop_id, guest.guest_id, &GeoLocation {
+        country_code: "VN".to_string(),
+        country: "Việt Nam".to_string(),
+        city: guest.city.to_string(),
+    }).await?;
+    let tags: Vec<String> = guest.tags.iter().map(|t| t.to_string()).collect();
+    repo.set_guest_routing(shop_id, guest.guest_id, &tags, guest.assigned_to).await?;
+
+    let mut last = None;
+    for (i, (sender, text)) in guest.script.iter().enumerate() {
+        let sent_ms = start_ms + i as u64 * MESSAGE_GAP_MS;
+        let msg = Message {
+            shop_id: shop_id.to_string(),
+            guest_id: guest.guest_id,
+            message_id: MessageId::from_parts(sent_ms, repo.ids.node_id(), 0).0,
+            sender_type: sender.to_string(),
+            content_crc: crc32c::crc32c(text.as_bytes()),
+            content: text.as_bytes().to_vec().into(),
+            timestamp_us: sent_ms * 1000,
+            ..Default::default()
+        };
+        repo.insert_message(&msg).await?;
+        last = Some(msg);
+    }
+
+    if let Some(last) = last {
+        repo.set_guest_last_message(&last).await?;
+        repo.set_feed_message(&last).await?;
+        // Khách nhắn cuối mà chưa ai trả lời → hiện trong hàng chờ SLA
+        if last.sender_type == "guest" && guest.status == ConversationStatus::Open {
+            repo.set_awaiting(shop_id, guest.guest_id, last.timestamp_us, None).await?;
+        }
+    }
+    if guest.status != ConversationStatus::Open {
+        repo.set_conversation_status(shop_id, guest.guest_id, guest.status).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,126 @@
+// Chấm cảm xúc tin khách bằng từ điển (tiếng Việt + tiếng Anh + emoji) - lưu điểm trung bình trượt lên guest
+use crate::contract::{Guest, Message as ChatMessage, Mood, WsEnvelope};
+use crate::websocket::{publish_envelope, WebSocketState};
+
+// Trọng số tin mới trong trung bình trượt của hội thoại
+const SMOOTHING: f32 = 0.4;
+// Chuẩn hoá tổng điểm về -1..1 (kiểu VADER)
+const NORMALIZE_ALPHA: f32 = 15.0;
+const INTENSIFIER_BOOST: f32 = 1.5;
+
+// Cụm 2 từ được xét trước từ đơn
+const LEXICON: &[(&str, f32)] = &[
+    // Tích cực
+    ("cảm ơn", 2.0), ("cám ơn", 2.0), ("cam on", 2.0), ("tuyệt vời", 3.0), ("tuyệt", 3.0),
+    ("xuất sắc", 3.0), ("hài lòng", 2.0), ("tốt", 2.0), ("thích", 2.0), ("yêu", 2.0),
+    ("vui", 2.0), ("đẹp", 2.0), ("ưng", 2.0), ("nhiệt tình", 2.0), ("nhanh", 1.0),
+    ("ok", 1.0), ("oke", 1.0), ("chuẩn", 1.0),
+    ("thanks", 2.0), ("thank", 2.0), ("great", 3.0), ("good", 2.0), ("love", 3.0),
+    ("awesome", 3.0), ("perfect", 3.0), ("happy", 2.0), ("nice", 2.0),
+    // Tiêu cực
+    ("thất vọng", -3.0), ("lừa đảo", -3.0), ("bực mình", -3.0), ("bực bội", -3.0), ("tệ", -3.0),
+    ("kém", -2.0), ("chán", -2.0), ("bực", -2.0), ("tức", -2.0), ("điên", -2.0),
+    ("lừa", -2.0), ("hỏng", -2.0), ("chậm", -2.0), ("vô lý", -2.0), ("khiếu nại", -2.0),
+    ("lỗi", -1.0), ("lâu", -1.0), ("sai", -1.0), ("đắt", -1.0), ("hoàn tiền", -1.0),
+    ("trả hàng", -1.0),
+    ("bad", -2.0), ("terrible", -3.0), ("awful", -3.0), ("angry", -3.0), ("worst", -3.0),
+    ("scam", -3.0), ("hate", -3.0), ("disappointed", -3.0), ("broken", -2.0), ("slow", -2.0),
+    ("refund", -1.0), ("complaint", -2.0),
+];
+
+const NEGATIONS: &[&str] = &["không", "ko", "k", "chẳng", "chả", "chưa", "not", "no", "never", "dont", "don't"];
+// "rất tốt" / "tệ quá"
+const INTENSIFIERS_BEFORE: &[&str] = &["rất", "quá", "cực", "very", "so", "really"];
+const INTENSIFIERS_AFTER: &[&str] = &["quá", "lắm", "thật", "vãi"];
+
+const EMOJIS: &[(char, f32)] = &[
+    ('😡', -3.0), ('😠', -3.0), ('🤬', -3.0), ('😤', -2.0), ('😞', -2.0), ('😢', -2.0),
+    ('😭', -2.0), ('👎', -2.0),
+    ('🙂', 1.0), ('😊', 2.0), ('😄', 2.0), ('😍', 3.0), ('🥰', 3.0), ('❤', 2.0), ('👍', 2.0),
+];
+
+/// Điểm cảm xúc của một tin, -1 (rất tiêu cực) .. 1 (rất tích cực)
+pub fn score_text(text: &str) -> f32 {
+    let lower = text.to_lowercase();
+    let tokens: Vec<&str> = lower
+        .split(|c: char| !(c.is_alphanumeric() || c == '\''))
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    let mut total = 0.0;
+    let mut i = 0;
+    while i < tokens.len() {
+        let bigram = tokens.get(i + 1).map(|next| format!("{} {}", tokens[i], next));
+        let (weight, len) = match bigram.as_deref().and_then(lookup) {
+            Some(w) => (w, 2),
+            None => match lookup(tokens[i]) {
+                Some(w) => (w, 1),
+                None => {
+                    i += 1;
+                    continue;
+                }
+            },
+        };
+
+        let before = |n: usize| i.checked_sub(n).map(|j| tokens[j]);
+        let mut weight = weight;
+        if before(1).is_some_and(|t| INTENSIFIERS_BEFORE.contains(&t)) {
+            weight *= INTENSIFIER_BOOST;
+        }
+        if tokens.get(i + len).is_some_and(|t| INTENSIFIERS_AFTER.contains(t)) {
+            weight *= INTENSIFIER_BOOST;
+        }
+        // "không tốt", "không rất tốt"
+        if before(1).into_iter().chain(before(2)).any(|t| NEGATIONS.contains(&t)) {
+            weight = -weight * 0.75;
+        }
+        total += weight;
+        i += len;
+    }
+
+    total += lower
+        .chars()
+        .filter_map(|c| EMOJIS.iter().find(|(e, _)| *e == c).map(|(_, w)| *w))
+        .sum::<f32>();
+
+    total / (total * total + NORMALIZE_ALPHA).sqrt()
+}
+
+fn lookup(word: &str) -> Option<f32> {
+    LEXICON.iter().find(|(w, _)| *w == word).map(|(_, weight)| *weight)
+}
+
+/// Cập nhật điểm hội thoại sau mỗi tin khách; chỉ báo admin khi mức cảm xúc đổi
+pub async fn on_guest_message(state: &WebSocketState, msg: &ChatMessage) {
+    let text = String::from_utf8_lossy(&msg.content);
+    let score = score_text(&text);
+
+    let guest = match state.repo.get_guest(&msg.shop_id, msg.guest_id).await {
+        Ok(Some(guest)) => guest,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("❌ Load guest {} for sentiment failed: {:?}", msg.guest_id, e);
+            return;
+        }
+    };
+    let previous = guest.mood();
+    let sentiment = if previous.is_none() {
+        score
+    } else {
+        guest.sentiment * (1.0 - SMOOTHING) + score * SMOOTHING
+    };
+    if let Err(e) = state.repo.set_guest_sentiment(&msg.shop_id, msg.guest_id, sentiment, msg.timestamp_us).await {
+        eprintln!("❌ Save sentiment for guest {} failed: {:?}", msg.guest_id, e);
+        return;
+    }
+
+    let mood = Mood::from_score(sentiment);
+    if previous == Some(mood) {
+        return;
+    }
+    println!("{} Guest {} sentiment now {:.2}", mood.emoji(), msg.guest_id, sentiment);
+    let guest = Guest { sentiment, sentiment_at: msg.timestamp_us, ..guest };
+    if let Err(e) = publish_envelope(state, &WsEnvelope::guest(guest)).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+}
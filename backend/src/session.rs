@@ -0,0 +1,104 @@
+// ============================================================================
+// PHIÊN KẾT NỐI KHÁCH - token nối lại + phát bù tin bị lỡ
+// Token lưu Redis (session:<token> → "<shop_id>:<guest_id>", hết hạn sau SESSION_TTL_SECS không kết nối),
+// dùng được ở mọi node. Nối lại hợp lệ → tin sau last_id lấy từ DB, gửi trước khi chuyển sang tin trực tiếp
+// ============================================================================
+
+use redis::AsyncCommands;
+
+use crate::attachments;
+use crate::contract::{Message as ChatMessage, Session};
+use crate::merge;
+use crate::websocket::WebSocketState;
+
+const SESSION_TTL_SECS: u64 = 24 * 3600;
+// Lỡ nhiều hơn bấy nhiêu tin → không phát bù, client tự /sync lại từ đầu
+const MAX_REPLAY_MESSAGES: u32 = 500;
+
+fn session_key(token: &str) -> String {
+    format!("session:{}", token)
+}
+
+fn owner(shop_id: &str, guest_id: u64) -> String {
+    format!("{}:{}", shop_id, guest_id)
+}
+
+/// Kết quả mở phiên: frame Session gửi cho client + các tin cần phát bù trước đó
+pub struct Opened {
+    pub session: Session,
+    pub replay: Vec<ChatMessage>,
+}
+
+/// Mở phiên cho kết nối khách. `resume` + `last_id` từ query của lần nối lại.
+/// Token hỏng / hết hạn / của khách khác → phiên mới, không phát bù
+pub async fn open(state: &WebSocketState, shop_id: &str, guest_id: u64, resume: Option<&str>, last_id: u64) -> Opened {
+    if let Some(token) = resume.filter(|t| is_valid_token(t)) {
+        match load(state, token).await {
+            Ok(Some(stored)) if stored == owner(shop_id, guest_id) => {
+                refresh(state, token, shop_id, guest_id).await;
+                match missed_messages(state, shop_id, guest_id, last_id).await {
+                    Some(replay) => {
+                        println!("🔁 Guest {} resumed session, replaying {} messages", guest_id, replay.len());
+                        let session = Session { token: token.to_string(), resumed: true, replayed: replay.len() as u32 };
+                        return Opened { session, replay };
+                    }
+                    None => println!("🔁 Guest {} missed too many messages, full resync", guest_id),
+                }
+                let session = Session { token: token.to_string(), resumed: false, replayed: 0 };
+                return Opened { session, replay: Vec::new() };
+            }
+            Ok(_) => println!("🔁 Guest {} sent unknown or expired session token", guest_id),
+            Err(e) => eprintln!("❌ Load session failed: {:?}", e),
+        }
+    }
+
+    let token = hex_token();
+    refresh(state, &token, shop_id, guest_id).await;
+    Opened { session: Session { token, resumed: false, replayed: 0 }, replay: Vec::new() }
+}
+
+async fn load(state: &WebSocketState, token: &str) -> redis::RedisResult<Option<String>> {
+    let result = async { state.redis.get().await?.get(session_key(token)).await }.await;
+    state.redis.checked(result).await
+}
+
+// Ghi / gia hạn token
+async fn refresh(state: &WebSocketState, token: &str, shop_id: &str, guest_id: u64) {
+    let result: redis::RedisResult<()> = async {
+        state.redis.get().await?.set_ex(session_key(token), owner(shop_id, guest_id), SESSION_TTL_SECS).await
+    }.await;
+    if let Err(e) = state.redis.checked(result).await {
+        eprintln!("❌ Save session for guest {} failed: {:?}", guest_id, e);
+    }
+}
+
+// Tin sau last_id (đã che tin xoá, kèm trích dẫn + link file như /sync). None = quá nhiều hoặc lỗi DB
+async fn missed_messages(state: &WebSocketState, shop_id: &str, guest_id: u64, last_id: u64) -> Option<Vec<ChatMessage>> {
+    // Khách đã gộp → cả tin lưu dưới các alias (như /sync)
+    let page = match async {
+        let guests = merge::conversation_guests(state, shop_id, guest_id).await?;
+        state.repo.fetch_conversation(shop_id, &guests, last_id, MAX_REPLAY_MESSAGES, None).await
+    }.await {
+        Ok(page) => page,
+        Err(e) => {
+            eprintln!("❌ Replay for guest {} failed: {:?}", guest_id, e);
+            return None;
+        }
+    };
+    if page.page_state.is_some() {
+        return None;
+    }
+    let mut messages = page.messages;
+    messages.iter_mut().filter(|m| m.deleted_at_us > 0).for_each(|m| m.redact());
+    state.repo.hydrate_replies(&mut messages).await;
+    attachments::sign_urls(state.blobs.as_ref(), &mut messages);
+    Some(messages)
+}
+
+fn hex_token() -> String {
+    hex::encode(crate::blob::random_key())
+}
+
+fn is_valid_token(token: &str) -> bool {
+    token.len() == 64 && token.bytes().all(|b| b.is_ascii_hexdigit())
+}
@@ -0,0 +1,103 @@
+use axum::{
+    body::Bytes,
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use prost::Message as ProstMessage;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::agents;
+use crate::auth::{client_ip, verify_body_pin};
+use crate::contract::{SettingsRequest, SettingsResponse, ShopSettings};
+use crate::db::AstraRepo;
+use crate::request_id;
+use crate::state::AppState;
+
+const CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Cache ShopSettings cho đường ingest (tránh gọi DB mỗi tin nhắn)
+pub struct ShopSettingsCache {
+    repo: Arc<AstraRepo>,
+    cache: RwLock<HashMap<String, (Instant, ShopSettings)>>,
+}
+
+impl ShopSettingsCache {
+    pub fn new(repo: Arc<AstraRepo>) -> Self {
+        Self { repo, cache: RwLock::new(HashMap::new()) }
+    }
+
+    pub async fn get(&self, shop_id: &str) -> ShopSettings {
+        if let Some((loaded_at, settings)) = self.cache.read().unwrap().get(shop_id) {
+            if loaded_at.elapsed() < CACHE_TTL {
+                return settings.clone();
+            }
+        }
+
+        match self.repo.get_settings(shop_id).await {
+            Ok(settings) => {
+                self.put(shop_id, settings.clone());
+                settings
+            }
+            Err(e) => {
+                eprintln!("❌ Load settings for {} failed: {:?}", shop_id, e);
+                self.cache.read().unwrap()
+                    .get(shop_id)
+                    .map(|(_, s)| s.clone())
+                    .unwrap_or_default()
+            }
+        }
+    }
+
+    pub fn put(&self, shop_id: &str, settings: ShopSettings) {
+        self.cache.write().unwrap().insert(shop_id.to_string(), (Instant::now(), settings));
+    }
+}
+
+// POST /settings - Đọc (và cập nhật nếu có gửi settings) cấu hình shop
+pub async fn settings_handler(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let req = match SettingsRequest::decode(&body[..]) {
+        Ok(r) => r,
+        Err(_) => return (StatusCode::BAD_REQUEST, Bytes::new()),
+    };
+
+    if let Err(status) = verify_body_pin(&state, &req.shop_id, &req.admin_pin, &client_ip(&headers, &peer)).await {
+        let resp = SettingsResponse { success: false, error: "Unauthorized".into(), request_id: request_id::current(), ..Default::default() };
+        return (status, Bytes::from(resp.encode_to_vec()));
+    }
+
+    if let Some(new_settings) = &req.settings {
+        // Nhân viên được mời chỉ xem, không ghi cài đặt shop
+        if agents::is_session(&req.admin_pin) {
+            let resp = SettingsResponse { success: false, error: "Only the shop owner can change settings".into(), request_id: request_id::current(), ..Default::default() };
+            return (StatusCode::FORBIDDEN, Bytes::from(resp.encode_to_vec()));
+        }
+        if let Err(e) = state.repo.save_settings(&req.shop_id, new_settings).await {
+            let resp = SettingsResponse { success: false, error: e.to_string(), request_id: request_id::current(), ..Default::default() };
+            return (StatusCode::OK, Bytes::from(resp.encode_to_vec()));
+        }
+        state.ws_state.settings.put(&req.shop_id, new_settings.clone());
+        println!("⚙️ Settings updated for shop {}", req.shop_id);
+    }
+
+    let resp = match state.repo.get_settings(&req.shop_id).await {
+        Ok(settings) => SettingsResponse {
+            success: true,
+            settings: Some(settings),
+            retention: state.retention_stats.get(&req.shop_id),
+            flags: state.repo.get_flags(&req.shop_id).await.ok(),
+            ..Default::default()
+        },
+        Err(e) => SettingsResponse { success: false, error: e.to_string(), request_id: request_id::current(), ..Default::default() },
+    };
+
+    (StatusCode::OK, Bytes::from(resp.encode_to_vec()))
+}
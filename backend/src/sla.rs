@@ -0,0 +1,107 @@
+// SLA phản hồi đầu: đánh dấu lượt chờ trên guest, quét định kỳ → cảnh báo admin (+ email)
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::contract::{ConversationStatus, Guest, Message as ChatMessage, SlaAlert, SlaLevel, WsEnvelope};
+use crate::db::now_us;
+use crate::websocket::{claim_once, publish_envelope, WebSocketState};
+
+// Mỗi lượt chờ chỉ cảnh báo 1 lần cho mỗi mức (khoá Redis giữ 1 ngày)
+const ALERT_CLAIM_TTL_SECS: u64 = 86_400;
+
+/// Cập nhật lượt chờ khi có tin mới: khách nhắn → bắt đầu chờ, admin trả lời → ghi thời gian phản hồi
+pub async fn on_message(state: &WebSocketState, msg: &ChatMessage) {
+    let is_guest = match msg.sender_type.as_str() {
+        "guest" => true,
+        "admin" => false,
+        _ => return,
+    };
+    let guest = match state.repo.get_guest(&msg.shop_id, msg.guest_id).await {
+        Ok(Some(guest)) => guest,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("❌ Load guest {} for SLA failed: {:?}", msg.guest_id, e);
+            return;
+        }
+    };
+
+    let (awaiting_since, first_response_ms) = match (is_guest, guest.awaiting_since) {
+        (true, 0) => (msg.timestamp_us, None),
+        (false, since) if since > 0 => (0, Some(msg.timestamp_us.saturating_sub(since) / 1000)),
+        _ => return,
+    };
+    if let Err(e) = state.repo.set_awaiting(&msg.shop_id, msg.guest_id, awaiting_since, first_response_ms).await {
+        eprintln!("❌ Save SLA state for guest {} failed: {:?}", msg.guest_id, e);
+        return;
+    }
+    if let Some(ms) = first_response_ms {
+        println!("⏱️ Guest {} answered after {}s", msg.guest_id, ms / 1000);
+    }
+
+    let guest = Guest {
+        awaiting_since,
+        first_response_ms: first_response_ms.unwrap_or(guest.first_response_ms),
+        ..guest
+    };
+    if let Err(e) = publish_envelope(state, &WsEnvelope::guest(guest)).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+}
+
+pub async fn sla_task(state: Arc<WebSocketState>) {
+    let interval_secs = std::env::var("SLA_CHECK_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30);
+    println!("⏱️ SLA monitor started (every {}s)", interval_secs);
+
+    loop {
+        check_all(&state).await;
+        tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+    }
+}
+
+// Chỉ quét shop đang có kết nối tới node này
+async fn check_all(state: &WebSocketState) {
+    let now = now_us();
+    for shop_id in state.connected_shops() {
+        let settings = state.settings.get(&shop_id).await;
+        if settings.sla_first_response_secs == 0 {
+            continue;
+        }
+        let guests = match state.repo.get_guests(&shop_id).await {
+            Ok(guests) => guests,
+            Err(e) => {
+                eprintln!("❌ Load guests for SLA of {} failed: {:?}", shop_id, e);
+                continue;
+            }
+        };
+
+        for guest in guests.iter().filter(|g| g.awaiting_since > 0 && g.status() == ConversationStatus::Open) {
+            let breached = match settings.sla_level(guest.awaiting_since, now) {
+                SlaLevel::Ok => continue,
+                SlaLevel::Warning => false,
+                SlaLevel::Breached => true,
+            };
+            let key = format!("sla:{}:{}:{}:{}", shop_id, guest.guest_id, guest.awaiting_since, if breached { "breach" } else { "warn" });
+            if !claim_once(state, &key, ALERT_CLAIM_TTL_SECS).await {
+                continue;
+            }
+
+            println!("⏱️ Guest {} of shop {} {} SLA", guest.guest_id, shop_id, if breached { "breached" } else { "is about to breach" });
+            let alert = SlaAlert {
+                guest_id: guest.guest_id,
+                awaiting_since: guest.awaiting_since,
+                deadline_us: settings.sla_deadline(guest.awaiting_since),
+                breached,
+            };
+            if let Err(e) = publish_envelope(state, &WsEnvelope::sla_alert(shop_id.clone(), alert)).await {
+                eprintln!("❌ Redis publish failed: {:?}", e);
+            }
+            if breached && settings.sla_email_alerts {
+                let waited_secs = now.saturating_sub(guest.awaiting_since) / 1_000_000;
+                state.notifier.notify_sla_breach(&settings, &shop_id, guest, waited_secs).await;
+            }
+        }
+    }
+}
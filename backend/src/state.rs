@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use crate::agents::InviteLinks;
+use crate::auth::OpsToken;
+use crate::db::AstraRepo;
+use crate::loader::WidgetAssets;
+use crate::retention::RetentionStatsStore;
+use crate::scan::Scanner;
+use crate::summary::LlmClient;
+use crate::websocket::WebSocketState;
+
+// State dùng chung cho các HTTP handler
+pub struct AppState {
+    pub repo: Arc<AstraRepo>,
+    pub ws_state: Arc<WebSocketState>,
+    pub retention_stats: Arc<RetentionStatsStore>,
+    pub widget_assets: Option<WidgetAssets>,
+    pub llm: LlmClient,
+    // Quét virus file upload (None = tắt)
+    pub scanner: Option<Box<dyn Scanner>>,
+    // Ký link mời nhân viên
+    pub invites: InviteLinks,
+    // Endpoint /ops (bảo trì...)
+    pub ops_token: OpsToken,
+}
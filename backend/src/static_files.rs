@@ -0,0 +1,61 @@
+// ============================================================================
+// STATIC FILES - 1 binary + thư mục asset là đủ cho deploy nhỏ (không cần nginx)
+//   /admin/*          → admin-panel/dist (SPA: đường dẫn lạ trả index.html)
+//   /widget/assets/*  → chat-widget/dist (bundle cho /widget.js)
+// Admin phải build với: trunk build --release --public-url /admin/
+// ============================================================================
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue},
+    middleware::{self, Next},
+    response::Response,
+    Router,
+};
+use std::path::PathBuf;
+use tower_http::services::{ServeDir, ServeFile};
+
+use crate::loader::WidgetAssets;
+
+// File có hash của Trunk → cache vĩnh viễn; còn lại (index.html) luôn kiểm tra lại
+const ASSET_CACHE: &str = "public, max-age=31536000, immutable";
+const REVALIDATE: &str = "no-cache";
+
+pub fn router(widget_assets: Option<&WidgetAssets>) -> Router {
+    let mut router = Router::new();
+
+    let admin_dir = PathBuf::from(std::env::var("ADMIN_DIST_DIR").unwrap_or_else(|_| "../admin-panel/dist".to_string()));
+    if admin_dir.join("index.html").exists() {
+        let spa = ServeDir::new(&admin_dir).fallback(ServeFile::new(admin_dir.join("index.html")));
+        router = router.nest_service("/admin", spa);
+        println!("🗂️ Serving admin panel from {}", admin_dir.display());
+    } else {
+        println!("⚠️ Admin build not found at {}, /admin disabled (set ADMIN_DIST_DIR)", admin_dir.display());
+    }
+
+    if let Some(assets) = widget_assets {
+        router = router.nest_service("/widget/assets", ServeDir::new(assets.dir()));
+    }
+
+    router.layer(middleware::from_fn(cache_control))
+}
+
+async fn cache_control(req: Request, next: Next) -> Response {
+    let hashed = is_hashed_asset(req.uri().path());
+    let mut resp = next.run(req).await;
+    if resp.status().is_success() {
+        let value = if hashed { ASSET_CACHE } else { REVALIDATE };
+        resp.headers_mut().entry(header::CACHE_CONTROL).or_insert(HeaderValue::from_static(value));
+    }
+    resp
+}
+
+// "admin-panel-4f0c2ed64a2a5a8e.js", "turbochat-widget-4f0c2ed64a2a5a8e_bg.wasm"
+fn is_hashed_asset(path: &str) -> bool {
+    let file = path.rsplit('/').next().unwrap_or("");
+    let Some((stem, _ext)) = file.rsplit_once('.') else { return false };
+    let stem = stem.strip_suffix("_bg").unwrap_or(stem);
+    match stem.rsplit_once('-') {
+        Some((_, hash)) => hash.len() >= 16 && hash.chars().all(|c| c.is_ascii_hexdigit()),
+        None => false,
+    }
+}
@@ -0,0 +1,166 @@
+// Tóm tắt hội thoại bằng LLM (API kiểu OpenAI chat completions) - lưu lên guest cho admin đọc nhanh
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use prost::Message as ProstMessage;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::auth::AdminAuth;
+use crate::contract::{ConversationSummary, Guest, Message, WsEnvelope};
+use crate::db::now_us;
+use crate::state::AppState;
+use crate::websocket::publish_envelope;
+
+// Chỉ gửi phần cuối hội thoại cho LLM
+const MAX_TRANSCRIPT_MESSAGES: usize = 200;
+const MAX_TRANSCRIPT_CHARS: usize = 12_000;
+const MAX_SUGGESTED_TAGS: usize = 5;
+const LLM_TIMEOUT: Duration = Duration::from_secs(30);
+
+const SYSTEM_PROMPT: &str = "Bạn là trợ lý chăm sóc khách hàng. Tóm tắt hội thoại giữa khách và shop trong tối đa 3 câu \
+tiếng Việt (khách cần gì, đã xử lý tới đâu, còn việc gì phải làm) và gợi ý tối đa 5 tag ngắn. \
+Chỉ trả lời JSON dạng {\"summary\": \"...\", \"tags\": [\"...\"]}.";
+
+/// LLM_API_URL (vd. https://api.openai.com/v1/chat/completions), LLM_API_KEY, LLM_MODEL.
+/// Không đặt LLM_API_URL → endpoint trả 503.
+pub struct LlmClient {
+    client: reqwest::Client,
+    api_url: Option<String>,
+    api_key: String,
+    model: String,
+}
+
+impl LlmClient {
+    pub fn from_env() -> Self {
+        let api_url = std::env::var("LLM_API_URL").ok().filter(|u| !u.is_empty());
+        if api_url.is_none() {
+            println!("✨ LLM_API_URL not set, conversation summaries disabled");
+        }
+        Self {
+            client: reqwest::Client::builder().timeout(LLM_TIMEOUT).build().unwrap_or_default(),
+            api_url,
+            api_key: std::env::var("LLM_API_KEY").unwrap_or_default(),
+            model: std::env::var("LLM_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.api_url.is_some()
+    }
+
+    async fn summarize(&self, transcript: &str) -> Result<(String, Vec<String>), String> {
+        let url = self.api_url.as_ref().ok_or("LLM disabled")?;
+        let body = json!({
+            "model": self.model,
+            "temperature": 0.2,
+            "response_format": { "type": "json_object" },
+            "messages": [
+                { "role": "system", "content": SYSTEM_PROMPT },
+                { "role": "user", "content": transcript }
+            ]
+        });
+
+        let resp = self.client.post(url)
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("request failed: {}", e))?;
+        if !resp.status().is_success() {
+            return Err(format!("LLM returned {}", resp.status()));
+        }
+        let reply: serde_json::Value = resp.json().await.map_err(|e| format!("parse failed: {}", e))?;
+        let content = reply["choices"][0]["message"]["content"].as_str().ok_or("empty completion")?;
+        let parsed: serde_json::Value = serde_json::from_str(content).map_err(|e| format!("bad JSON: {}", e))?;
+
+        let summary = parsed["summary"].as_str().unwrap_or("").trim().to_string();
+        if summary.is_empty() {
+            return Err("empty summary".into());
+        }
+        let tags = parsed["tags"].as_array()
+            .map(|tags| tags.iter()
+                .filter_map(|t| t.as_str())
+                .map(|t| t.trim().to_lowercase())
+                .filter(|t| !t.is_empty())
+                .take(MAX_SUGGESTED_TAGS)
+                .collect())
+            .unwrap_or_default();
+        Ok((summary, tags))
+    }
+}
+
+// POST /conversations/:id/summary - Tóm tắt lại hội thoại, trả về bản tóm tắt mới
+pub async fn summarize_handler(
+    State(state): State<Arc<AppState>>,
+    admin: AdminAuth,
+    Path(guest_id): Path<u64>,
+) -> impl IntoResponse {
+    if !state.llm.is_enabled() {
+        return (StatusCode::SERVICE_UNAVAILABLE, Bytes::new());
+    }
+
+    let guest = match state.repo.get_guest(&admin.shop_id, guest_id).await {
+        Ok(Some(guest)) => guest,
+        Ok(None) => return (StatusCode::NOT_FOUND, Bytes::new()),
+        Err(e) => {
+            eprintln!("❌ Load guest {} failed: {:?}", guest_id, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new());
+        }
+    };
+    let messages = match state.repo.fetch_all_messages(&admin.shop_id, guest_id).await {
+        Ok(messages) => messages,
+        Err(e) => {
+            eprintln!("❌ Load transcript for guest {} failed: {:?}", guest_id, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new());
+        }
+    };
+    let transcript = transcript(&messages);
+    if transcript.is_empty() {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    }
+
+    let (text, suggested_tags) = match state.llm.summarize(&transcript).await {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("❌ Summarize guest {} failed: {}", guest_id, e);
+            return (StatusCode::BAD_GATEWAY, Bytes::new());
+        }
+    };
+    let summary = ConversationSummary { text, suggested_tags, generated_at: now_us() };
+    if let Err(e) = state.repo.set_guest_summary(&admin.shop_id, guest_id, &summary).await {
+        eprintln!("❌ Save summary for guest {} failed: {:?}", guest_id, e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new());
+    }
+    if let Err(e) = state.repo.insert_audit(&admin.shop_id, "conversation.summarize", guest_id, &admin.agent_id, "").await {
+        eprintln!("❌ Audit insert failed: {:?}", e);
+    }
+    println!("✨ Summarized conversation of guest {} ({} messages)", guest_id, messages.len());
+
+    let guest = Guest { summary: Some(summary.clone()), ..guest };
+    if let Err(e) = publish_envelope(&state.ws_state, &WsEnvelope::guest(guest)).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+    (StatusCode::OK, Bytes::from(summary.encode_to_vec()))
+}
+
+// "Khách: ..." / "Shop: ..." - lấy phần cuối, bỏ tin đã xoá
+fn transcript(messages: &[Message]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut chars = 0;
+    for msg in messages.iter().rev().filter(|m| m.deleted_at_us == 0).take(MAX_TRANSCRIPT_MESSAGES) {
+        let speaker = if msg.sender_type == "guest" { "Khách" } else { "Shop" };
+        let line = format!("{}: {}", speaker, String::from_utf8_lossy(&msg.content));
+        chars += line.chars().count();
+        if chars > MAX_TRANSCRIPT_CHARS {
+            break;
+        }
+        lines.push(line);
+    }
+    lines.reverse();
+    lines.join("\n")
+}
@@ -0,0 +1,109 @@
+// ============================================================================
+// TRACING PHÂN TÁN (OpenTelemetry) - xem độ trễ giao tin nằm ở đâu trong Grafana Tempo / Jaeger
+// Span: http.request, ws.connection, ws.frame, bus.publish → bus.deliver (node nhận, nối trace qua
+// WsEnvelope.traceparent), cluster.*, db.* (mỗi lời gọi repo).
+// Bật khi có OTEL_EXPORTER_OTLP_ENDPOINT (OTLP/HTTP, vd. http://tempo:4318). OTEL_SERVICE_NAME (mặc định
+// turbochat-backend), OTEL_TRACES_SAMPLER_ARG = tỉ lệ lấy mẫu trace gốc 0.0-1.0 (mặc định 1.0; trace có
+// cha thì theo quyết định của cha). Không bật → span là no-op
+// ============================================================================
+
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::{global, Context};
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use prost::Message as ProstMessage;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{info_span, Level, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+use crate::contract::WsEnvelope;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub struct Telemetry {
+    provider: SdkTracerProvider,
+}
+
+impl Telemetry {
+    /// None = chưa cấu hình endpoint; cấu hình sai thì dừng luôn
+    pub fn from_env() -> Option<Self> {
+        if std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").unwrap_or_default().is_empty() {
+            return None;
+        }
+        let service = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| "turbochat-backend".to_string());
+        let ratio = std::env::var("OTEL_TRACES_SAMPLER_ARG").ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .unwrap_or(1.0)
+            .clamp(0.0, 1.0);
+
+        // Endpoint / header đọc từ biến OTEL_EXPORTER_OTLP_* chuẩn
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .build()
+            .unwrap_or_else(|e| panic!("❌ OTLP exporter misconfigured: {}", e));
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(ratio))))
+            .with_resource(Resource::builder().with_service_name(service.clone()).build())
+            .build();
+
+        // Chỉ span của backend (bỏ span nội bộ của hyper/reqwest/async-nats)
+        let layer = tracing_opentelemetry::layer()
+            .with_tracer(provider.tracer("turbochat-backend"))
+            .with_filter(Targets::new().with_target("backend", Level::INFO));
+        if let Err(e) = tracing_subscriber::registry().with(layer).try_init() {
+            eprintln!("❌ Tracing subscriber already set: {}", e);
+            return None;
+        }
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        ENABLED.store(true, Ordering::Relaxed);
+        println!("🔭 Exporting traces as {} (sample ratio {})", service, ratio);
+        Some(Self { provider })
+    }
+
+    /// Đẩy nốt span còn trong hàng đợi trước khi thoát
+    pub fn shutdown(self) {
+        if let Err(e) = self.provider.shutdown() {
+            eprintln!("❌ Trace exporter shutdown failed: {}", e);
+        }
+    }
+}
+
+/// traceparent (W3C) của span hiện tại - ghi vào frame publish lên broker. Rỗng khi tắt tracing
+pub fn current_traceparent() -> String {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return String::new();
+    }
+    let mut carrier = HashMap::new();
+    global::get_text_map_propagator(|propagator| propagator.inject_context(&Span::current().context(), &mut carrier));
+    carrier.remove("traceparent").unwrap_or_default()
+}
+
+/// Nối `span` vào trace của bên gửi (header traceparent / WsEnvelope.traceparent)
+pub fn link_parent(span: &Span, traceparent: &str) {
+    if traceparent.is_empty() || !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let carrier = HashMap::from([("traceparent".to_string(), traceparent.to_string())]);
+    let parent: Context = global::get_text_map_propagator(|propagator| propagator.extract(&carrier));
+    let _ = span.set_parent(parent);
+}
+
+/// Span cho frame node nhận từ broker (con của bus.publish ở node gửi)
+pub fn deliver_span(payload: &[u8], receivers: usize) -> Span {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return Span::none();
+    }
+    let Ok(envelope) = WsEnvelope::decode(payload) else { return Span::none() };
+    let span = info_span!(parent: None, "bus.deliver",
+        shop_id = %envelope.shop_id, request_id = %envelope.request_id, receivers);
+    link_parent(&span, &envelope.traceparent);
+    span
+}
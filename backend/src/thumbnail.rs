@@ -0,0 +1,83 @@
+// Ảnh thu nhỏ cho file đính kèm dạng ảnh - tạo nền sau khi upload, lưu cạnh file gốc trong blob store
+use bytes::Bytes;
+use image::{codecs::jpeg::JpegEncoder, DynamicImage, ImageReader, Limits, Rgb, RgbImage};
+use std::io::Cursor;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+use crate::blob::BlobStore;
+
+// Cạnh dài nhất của ảnh thu nhỏ (px)
+const MAX_EDGE: u32 = 320;
+const JPEG_QUALITY: u8 = 80;
+// Chặn ảnh "bom giải nén"
+const MAX_SOURCE_EDGE: u32 = 12_000;
+const MAX_DECODE_BYTES: u64 = 256 * 1024 * 1024;
+
+// Resize tốn CPU - giới hạn số ảnh xử lý cùng lúc
+static SLOTS: Semaphore = Semaphore::const_new(2);
+
+/// Key ảnh thu nhỏ: "<shop>/<guest>/<id>.png" → "<shop>/<guest>/<id>.thumb.jpg"
+pub fn thumbnail_key(key: &str) -> String {
+    let stem = key.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(key);
+    format!("{}.thumb.jpg", stem)
+}
+
+/// Tạo ảnh thu nhỏ chạy nền - lỗi chỉ log (client dùng ảnh gốc khi không tải được ảnh thu nhỏ)
+pub fn spawn(store: Arc<dyn BlobStore>, key: String, data: Bytes) {
+    tokio::spawn(async move {
+        let Ok(_permit) = SLOTS.acquire().await else { return };
+        let thumb = match tokio::task::spawn_blocking(move || render(&data)).await {
+            Ok(Ok(thumb)) => thumb,
+            Ok(Err(e)) => {
+                eprintln!("❌ Thumbnail for {} failed: {}", key, e);
+                return;
+            }
+            Err(e) => {
+                eprintln!("❌ Thumbnail task for {} panicked: {}", key, e);
+                return;
+            }
+        };
+        let thumb_key = thumbnail_key(&key);
+        let size = thumb.len();
+        match store.put(&thumb_key, "image/jpeg", Bytes::from(thumb)).await {
+            Ok(()) => println!("🖼️ Thumbnail {} ({} bytes)", thumb_key, size),
+            Err(e) => eprintln!("❌ Store thumbnail {} failed: {}", thumb_key, e),
+        }
+    });
+}
+
+// Giải mã → thu nhỏ giữ tỉ lệ → JPEG (nền trắng thay cho vùng trong suốt)
+fn render(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_SOURCE_EDGE);
+    limits.max_image_height = Some(MAX_SOURCE_EDGE);
+    limits.max_alloc = Some(MAX_DECODE_BYTES);
+
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| format!("read failed: {}", e))?;
+    reader.limits(limits);
+    let image = reader.decode().map_err(|e| format!("decode failed: {}", e))?;
+
+    let thumb = image.thumbnail(MAX_EDGE, MAX_EDGE);
+    let rgb = flatten(&thumb);
+
+    let mut out = Vec::new();
+    JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY)
+        .encode_image(&rgb)
+        .map_err(|e| format!("encode failed: {}", e))?;
+    Ok(out)
+}
+
+fn flatten(image: &DynamicImage) -> RgbImage {
+    if !image.color().has_alpha() {
+        return image.to_rgb8();
+    }
+    let rgba = image.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend = |c: u8| ((c as u16 * a as u16 + 255 * (255 - a as u16)) / 255) as u8;
+        Rgb([blend(r), blend(g), blend(b)])
+    })
+}
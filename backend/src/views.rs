@@ -0,0 +1,49 @@
+// Bộ lọc hội thoại đặt tên ("sales chưa giao") theo từng admin - admin panel tự lọc + đếm (server chỉ lưu)
+use axum::{body::Bytes, extract::State, http::StatusCode, response::IntoResponse};
+use prost::Message as ProstMessage;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::auth::AdminAuth;
+use crate::contract::SavedViewList;
+use crate::state::AppState;
+
+const MAX_VIEWS: usize = 20;
+const MAX_NAME_CHARS: usize = 40;
+
+// GET /views - Các tab của admin đang đăng nhập
+pub async fn get_views_handler(State(state): State<Arc<AppState>>, admin: AdminAuth) -> impl IntoResponse {
+    match state.repo.get_views(&admin.shop_id, &admin.agent_id).await {
+        Ok(views) => (StatusCode::OK, Bytes::from(views.encode_to_vec())),
+        Err(e) => {
+            eprintln!("❌ Load views for {}/{} failed: {:?}", admin.shop_id, admin.agent_id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new())
+        }
+    }
+}
+
+// PUT /views - Thay toàn bộ danh sách (thêm / sửa / xoá / đổi thứ tự)
+pub async fn put_views_handler(State(state): State<Arc<AppState>>, admin: AdminAuth, body: Bytes) -> impl IntoResponse {
+    let Ok(mut list) = SavedViewList::decode(&body[..]) else {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    };
+    for view in &mut list.views {
+        view.name = view.name.trim().to_string();
+        view.tag = view.tag.trim().to_string();
+    }
+    let mut ids = HashSet::new();
+    let valid = list.views.len() <= MAX_VIEWS && list.views.iter().all(|v| {
+        !v.view_id.is_empty() && ids.insert(v.view_id.clone())
+            && !v.name.is_empty() && v.name.chars().count() <= MAX_NAME_CHARS
+    });
+    if !valid {
+        return (StatusCode::BAD_REQUEST, Bytes::new());
+    }
+
+    if let Err(e) = state.repo.save_views(&admin.shop_id, &admin.agent_id, &list).await {
+        eprintln!("❌ Save views failed: {:?}", e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, Bytes::new());
+    }
+    println!("🗂️ {} views saved for {} of shop {}", list.views.len(), admin.agent_id, admin.shop_id);
+    (StatusCode::OK, Bytes::from(list.encode_to_vec()))
+}
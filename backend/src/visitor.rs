@@ -0,0 +1,123 @@
+// Thông tin khách do website chủ cung cấp qua window.TurboChat (identify / trackEvent)
+use std::sync::Arc;
+
+use crate::contract::{Identify, TrackEvent, VisitorContext, WsEnvelope};
+use crate::db::now_us;
+use crate::identity;
+use crate::proactive;
+use crate::websocket::{publish_envelope, WebSocketState};
+
+const MAX_NAME_CHARS: usize = 80;
+const MAX_EMAIL_CHARS: usize = 254;
+const MAX_EVENT_NAME_CHARS: usize = 64;
+const MAX_PROPERTIES: usize = 20;
+const MAX_PROPERTY_CHARS: usize = 256;
+const MAX_URL_CHARS: usize = 2048;
+const MAX_USER_AGENT_CHARS: usize = 512;
+const MAX_VIEWPORT_PX: u32 = 16_384;
+
+pub async fn handle_identify(state: &WebSocketState, shop_id: &str, guest_id: u64, identify: Identify) {
+    let name = truncate(identify.name.trim(), MAX_NAME_CHARS);
+    let email = identify.email.trim();
+    // Email sai định dạng → bỏ qua email, vẫn lưu tên
+    let email = if is_valid_email(email) { email } else { "" };
+    // user_id chỉ được tin khi có chữ ký đúng của website
+    let user_id = identify.user_id.trim();
+    let verified = !user_id.is_empty() && match identity::verify(state, shop_id, user_id, &identify.user_hash).await {
+        Ok(true) => true,
+        Ok(false) => {
+            println!("⚠️ Guest {} sent unverified user_id {:?}, ignoring", guest_id, user_id);
+            false
+        }
+        Err(e) => {
+            eprintln!("❌ Verify identity for guest {} failed: {:?}", guest_id, e);
+            false
+        }
+    };
+    if name.is_empty() && email.is_empty() && !verified {
+        return;
+    }
+
+    if !name.is_empty() || !email.is_empty() {
+        if let Err(e) = state.repo.identify_guest(shop_id, guest_id, &name, email).await {
+            eprintln!("❌ Identify guest {} failed: {:?}", guest_id, e);
+            return;
+        }
+        println!("🪪 Guest {} identified as {:?}", guest_id, name);
+    }
+    if verified {
+        if let Err(e) = identity::link(state, shop_id, guest_id, user_id).await {
+            eprintln!("❌ Link guest {} to user failed: {:?}", guest_id, e);
+        }
+    }
+
+    // Báo admin đang mở dashboard để đổi tên trên sidebar/header ngay
+    publish_guest(state, shop_id, guest_id).await;
+}
+
+// Trang khách đang xem - chỉ giữ bản mới nhất trên guest; luật chào chủ động xét theo trang mới
+pub async fn handle_visitor_context(state: &Arc<WebSocketState>, shop_id: &str, guest_id: u64, context: VisitorContext) {
+    let context = VisitorContext {
+        page_url: truncate(context.page_url.trim(), MAX_URL_CHARS),
+        referrer: truncate(context.referrer.trim(), MAX_URL_CHARS),
+        user_agent: truncate(context.user_agent.trim(), MAX_USER_AGENT_CHARS),
+        viewport_width: context.viewport_width.min(MAX_VIEWPORT_PX),
+        viewport_height: context.viewport_height.min(MAX_VIEWPORT_PX),
+        updated_at_us: now_us(),
+    };
+    if !context.page_url.starts_with("http://") && !context.page_url.starts_with("https://") {
+        return;
+    }
+
+    if let Err(e) = state.repo.set_visitor_context(shop_id, guest_id, &context).await {
+        eprintln!("❌ Save visitor context for guest {} failed: {:?}", guest_id, e);
+        return;
+    }
+    publish_guest(state, shop_id, guest_id).await;
+    proactive::on_page_view(state, shop_id, guest_id, &context).await;
+}
+
+async fn publish_guest(state: &WebSocketState, shop_id: &str, guest_id: u64) {
+    match state.repo.get_guest(shop_id, guest_id).await {
+        Ok(Some(guest)) => {
+            if let Err(e) = publish_envelope(state, &WsEnvelope::guest(guest)).await {
+                eprintln!("❌ Redis publish failed: {:?}", e);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("❌ Load guest {} failed: {:?}", guest_id, e),
+    }
+}
+
+pub async fn handle_track_event(state: &WebSocketState, shop_id: &str, guest_id: u64, mut event: TrackEvent) {
+    event.name = truncate(event.name.trim(), MAX_EVENT_NAME_CHARS);
+    if event.name.is_empty() {
+        return;
+    }
+    if event.properties.len() > MAX_PROPERTIES {
+        println!("⚠️ Guest {} event {:?} has {} properties, dropping", guest_id, event.name, event.properties.len());
+        return;
+    }
+    for value in event.properties.values_mut() {
+        *value = truncate(value, MAX_PROPERTY_CHARS);
+    }
+
+    if let Err(e) = state.repo.insert_guest_event(shop_id, guest_id, &event).await {
+        eprintln!("❌ Track event failed: {:?}", e);
+    }
+}
+
+fn truncate(s: &str, max_chars: usize) -> String {
+    s.chars().take(max_chars).collect()
+}
+
+pub(crate) fn is_valid_email(email: &str) -> bool {
+    email.len() <= MAX_EMAIL_CHARS && is_plausible_email(email)
+}
+
+fn is_plausible_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => !local.is_empty() && domain.contains('.') && !email.contains(char::is_whitespace),
+        None => false,
+    }
+}
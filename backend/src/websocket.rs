@@ -1,69 +1,402 @@
 use axum::{
-    extract::{ws::{Message as WsMessage, WebSocket, WebSocketUpgrade}, State, Query},
-    response::IntoResponse,
+    extract::{ws::{CloseFrame, Message as WsMessage, WebSocket, WebSocketUpgrade}, ConnectInfo, State, Query},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
 };
 use futures::{sink::SinkExt, stream::StreamExt};
-use redis::AsyncCommands;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::sync::broadcast;
+use tracing::{info_span, Instrument};
 use prost::Message as ProstMessage;
 use serde::Deserialize;
 
-use crate::contract::Message as ChatMessage;
+use crate::activity;
+use crate::attachments;
+use crate::auth::{client_ip, request_origin};
+use crate::batch::InsertBatcher;
+use crate::blob::{self, BlobStore};
+use crate::blocklist::BlockList;
+use crate::bus::{self, MessageBus};
+use crate::campaigns;
+use crate::cluster::{Cluster, Peer, Registration};
+use crate::drain::Drain;
+use crate::config;
+use crate::contract::{negotiate, supported_range, ws_envelope, AgentStatus, Attachment, ContractError, Message as ChatMessage, QuickReply, ReplyPreview, WsEnvelope, CLOSE_SERVER_DRAINING, CLOSE_TOO_MANY_CONNECTIONS, CLOSE_UNSUPPORTED_PROTOCOL, PROTOCOL_VERSION};
 use crate::db::AstraRepo;
+use crate::edit::{self, Editor};
+use crate::filter::{FilterChain, Outcome};
+use crate::flows;
+use crate::geoip::{self, GeoIpResolver};
+use crate::handoff;
+use crate::kafka::EventExporter;
+use crate::lockout::{self, LoginGuard, PinCheck};
+use crate::maintenance::{self, Maintenance};
+use crate::merge;
+use crate::notify::EmailNotifier;
+use crate::presence;
+use crate::quota::{self, QuotaMeter};
+use crate::redis_conn::RedisConn;
+use crate::reminders;
+use crate::request_id;
+use crate::routing;
+use crate::session;
+use crate::telemetry;
+use crate::settings::ShopSettingsCache;
+use crate::sentiment;
+use crate::sla;
+use crate::visitor;
+
+// Close code khi guest bị chặn
+const CLOSE_BLOCKED: u16 = 4003;
+// Xả node: chờ client đóng (và frame đang xử lý ghi xong) tối đa chừng này sau close frame
+const DRAIN_GRACE: Duration = Duration::from_secs(5);
+// Nội dung một tin (file đi qua /attachments) và cả frame WS client gửi lên
+pub const MAX_CONTENT_BYTES: usize = 16 * 1024;
+const MAX_FRAME_BYTES: usize = 64 * 1024;
 
 #[derive(Deserialize)]
 pub struct WsQuery {
     pub shop_id: String,
     pub guest_id: Option<u64>,  // None = admin, Some = guest
+    pub admin_pin: Option<String>,  // Bắt buộc khi guest_id = None
+    pub agent_id: Option<String>,   // Admin: id ổn định của trình duyệt (trạng thái vắng mặt)
+    pub resume: Option<String>,     // Guest nối lại: token từ frame Session trước
+    pub last_id: Option<u64>,       // Guest nối lại: message_id cuối đã nhận
+    pub v: Option<u32>,             // Phiên bản protocol của client (không có = 1)
 }
 
 pub struct WebSocketState {
-    pub redis_url: String,
+    // Kết nối Redis dùng chung (phiên, idempotency, chiến dịch, claim_once)
+    pub redis: RedisConn,
+    // Phát frame giữa các node (Redis / NATS)
+    pub bus: Arc<dyn MessageBus>,
     pub tx: broadcast::Sender<Vec<u8>>,
     pub repo: Arc<AstraRepo>,
+    pub blocks: BlockList,
+    pub settings: ShopSettingsCache,
+    pub filters: FilterChain,
+    // (shop_id, guest_id) cần ngắt kết nối ngay trên node này (cluster::kick_guest định tuyến tới đây)
+    pub kick_tx: broadcast::Sender<(String, u64)>,
+    pub notifier: EmailNotifier,
+    // Xuất sự kiện ra Kafka (tắt nếu không đặt KAFKA_BROKERS)
+    pub exporter: EventExporter,
+    // Node id + sổ kết nối chung các node
+    pub cluster: Cluster,
+    pub geoip: GeoIpResolver,
+    pub quotas: QuotaMeter,
+    // Gom insert tin nhắn (batch.rs)
+    pub inserts: InsertBatcher,
+    // Kho file đính kèm (local / S3)
+    pub blobs: Arc<dyn BlobStore>,
+    // Đếm lần sai PIN admin (chống dò)
+    pub logins: LoginGuard,
+    // Chế độ bảo trì (bản sao trạng thái chung trên Redis)
+    pub maintenance: Maintenance,
+    // Node đang xả kết nối để deploy
+    pub drain: Drain,
+    // Số kết nối đang mở theo shop
+    connections: Mutex<HashMap<String, usize>>,
 }
 
 impl WebSocketState {
     pub async fn new(redis_url: &str, repo: Arc<AstraRepo>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let (tx, _) = broadcast::channel(1000);
-        Ok(Self { redis_url: redis_url.to_string(), tx, repo })
+        let (kick_tx, _) = broadcast::channel(100);
+        let blocks = BlockList::new(repo.clone());
+        let settings = ShopSettingsCache::new(repo.clone());
+        let quotas = QuotaMeter::from_env(redis_url, repo.clone())?;
+        let inserts = InsertBatcher::from_env(repo.clone());
+        let cluster = Cluster::join(redis_url, repo.ids.node_id()).await?;
+        let bus = bus::from_env(redis_url, Some(format!("node-{}", cluster.node_id))).await;
+        let maintenance = Maintenance::new(redis_url)?;
+        maintenance.refresh().await;
+        Ok(Self {
+            redis: RedisConn::open(redis_url)?,
+            bus,
+            tx,
+            repo,
+            blocks,
+            settings,
+            filters: FilterChain::default(),
+            kick_tx,
+            notifier: EmailNotifier::from_env(),
+            exporter: EventExporter::from_env(),
+            cluster,
+            geoip: GeoIpResolver::from_env(),
+            quotas,
+            inserts,
+            blobs: blob::from_env(),
+            logins: LoginGuard::new(redis_url)?,
+            maintenance,
+            drain: Drain::new(),
+            connections: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Các shop đang có ít nhất 1 kết nối tới node này
+    pub fn connected_shops(&self) -> Vec<String> {
+        self.connections.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Số kết nối WS đang mở trên node này
+    pub fn open_connections(&self) -> usize {
+        self.connections.lock().unwrap().values().sum()
+    }
+
+    fn connect(&self, shop_id: &str) {
+        *self.connections.lock().unwrap().entry(shop_id.to_string()).or_insert(0) += 1;
+    }
+
+    fn disconnect(&self, shop_id: &str) {
+        let mut connections = self.connections.lock().unwrap();
+        if let Some(count) = connections.get_mut(shop_id) {
+            *count -= 1;
+            if *count == 0 {
+                connections.remove(shop_id);
+            }
+        }
     }
 }
 
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
-    Query(query): Query<WsQuery>,
+    Query(mut query): Query<WsQuery>,
     State(state): State<Arc<WebSocketState>>,
-) -> impl IntoResponse {
-    println!("🔌 WebSocket upgrade request: shop={}, guest={:?}", query.shop_id, query.guest_id);
-    ws.on_upgrade(move |socket| handle_socket(socket, state, query))
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Response {
+    let ip = client_ip(&headers, &peer);
+    println!("🔌 WebSocket upgrade request: shop={}, guest={:?}, ip={}", query.shop_id, query.guest_id, ip);
+
+    // Node đang xả để deploy → client thử lại, load balancer đưa sang node khác
+    if state.drain.is_draining() {
+        return StatusCode::SERVICE_UNAVAILABLE.into_response();
+    }
+
+    // Phiên bản không hỗ trợ → vẫn nâng cấp để gửi close code (trình duyệt không đọc được HTTP status của WS)
+    let Some(version) = negotiate(query.v) else {
+        println!("🚫 Rejected protocol v{:?}: shop={}, supported {}", query.v, query.shop_id, supported_range());
+        return ws.on_upgrade(|mut socket| async move {
+            let _ = socket.send(WsMessage::Close(Some(CloseFrame {
+                code: CLOSE_UNSUPPORTED_PROTOCOL,
+                reason: supported_range().into(),
+            }))).await;
+        });
+    };
+
+    // Widget nhúng trên website lạ → từ chối. Không có Origin (client ngoài trình duyệt) thì cho qua
+    if let Some(origin) = request_origin(&headers) {
+        if !config::current().admin_origins.contains(&origin) && !state.settings.get(&query.shop_id).await.allows_origin(&origin) {
+            println!("🚫 Rejected WebSocket from disallowed origin: shop={}, origin={}, ip={}", query.shop_id, origin, ip);
+            return StatusCode::FORBIDDEN.into_response();
+        }
+    }
+
+    if query.guest_id.is_some() && state.blocks.is_blocked(&query.shop_id, query.guest_id, &ip).await {
+        println!("🚫 Rejected blocked guest: shop={}, guest={:?}, ip={}", query.shop_id, query.guest_id, ip);
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    // Guest đã gộp vào guest gốc (cùng tài khoản website) → kết nối như guest gốc
+    if let Some(gid) = query.guest_id {
+        let canonical = merge::canonical_guest(&state, &query.shop_id, gid).await;
+        if canonical != gid {
+            println!("🧬 Guest {} connecting as merged guest {}", gid, canonical);
+            query.guest_id = Some(canonical);
+        }
+    }
+
+    // Shop hết hạn mức kết nối khách → từ chối (widget tự thử lại sau)
+    if query.guest_id.is_some() {
+        if let Err(exceeded) = state.quotas.check_connection(&state.cluster, &query.shop_id).await {
+            println!("📊 Rejected guest connection: shop {} at {}/{} connections", query.shop_id, exceeded.used, exceeded.limit);
+            return StatusCode::TOO_MANY_REQUESTS.into_response();
+        }
+    }
+
+    // Kết nối admin nhận mọi frame của shop (kể cả ghi chú nội bộ) → phải đúng PIN
+    if query.guest_id.is_none() {
+        let pin = query.admin_pin.as_deref().unwrap_or("");
+        match lockout::check_pin(&state, &query.shop_id, pin, &ip).await {
+            Ok(PinCheck::Valid(_)) => {}
+            Ok(PinCheck::Invalid { .. }) => {
+                println!("🚫 Rejected admin WebSocket with bad PIN: shop={}, ip={}", query.shop_id, ip);
+                return StatusCode::UNAUTHORIZED.into_response();
+            }
+            Ok(PinCheck::Locked { retry_after_secs }) => {
+                println!("🔒 Rejected admin WebSocket while locked out ({}s left): shop={}, ip={}", retry_after_secs, query.shop_id, ip);
+                return StatusCode::TOO_MANY_REQUESTS.into_response();
+            }
+            Err(e) => {
+                eprintln!("❌ Admin auth failed: {:?}", e);
+                return StatusCode::SERVICE_UNAVAILABLE.into_response();
+            }
+        }
+    }
+
+    ws.max_message_size(MAX_FRAME_BYTES)
+        .max_frame_size(MAX_FRAME_BYTES)
+        .on_upgrade(move |socket| {
+            // Cả vòng đời kết nối (mở → đóng); frame xử lý trong span ws.frame riêng
+            let span = info_span!(parent: None, "ws.connection", shop_id = %query.shop_id, guest_id = ?query.guest_id, protocol = version);
+            handle_socket(socket, state, query, version, ip).instrument(span)
+        })
 }
 
-async fn handle_socket(socket: WebSocket, state: Arc<WebSocketState>, query: WsQuery) {
+async fn handle_socket(socket: WebSocket, state: Arc<WebSocketState>, query: WsQuery, version: u32, ip: String) {
     let (mut sender, mut receiver) = socket.split();
     let shop_id = query.shop_id.clone();
     let guest_id = query.guest_id;
     
-    println!("✅ WebSocket connected: shop={}, guest={:?}", shop_id, guest_id);
+    // Admin: khôi phục trạng thái vắng mặt đã lưu
+    let agent_id = if guest_id.is_none() { query.agent_id.filter(|id| is_valid_agent_id(id)) } else { None };
+    let mut away = false;
+    if let Some(agent) = &agent_id {
+        away = state.repo.get_agent_away(&shop_id, agent).await.unwrap_or_else(|e| {
+            eprintln!("❌ Load agent status failed: {:?}", e);
+            false
+        });
+    }
+    
+    // Ghi vào sổ kết nối của cụm (frame riêng cho khách được định tuyến về node này).
+    // Quá số kết nối cho phép theo IP khách → đóng luôn (theo shop: hạn mức, kiểm tra trước upgrade)
+    let peer = match guest_id {
+        Some(gid) => Peer::Guest { guest_id: gid, since_us: chrono::Utc::now().timestamp_micros() as u64 },
+        None => Peer::Admin { agent_id: agent_id.clone(), away },
+    };
+    let was_away = if agent_id.is_some() { state.cluster.all_away(&shop_id).await } else { false };
+    let registration = match state.cluster.register(&shop_id, &peer, guest_id.map(|_| ip.as_str())).await {
+        Ok(registration) => Arc::new(registration),
+        Err(limit) => {
+            println!("🚧 Rejected WebSocket over {:?} limit: shop={}, guest={:?}, ip={}", limit, shop_id, guest_id, ip);
+            let _ = sender.send(WsMessage::Close(Some(CloseFrame {
+                code: CLOSE_TOO_MANY_CONNECTIONS,
+                reason: limit.reason().into(),
+            }))).await;
+            return;
+        }
+    };
+    if agent_id.is_some() {
+        publish_presence_if_changed(&state, &shop_id, was_away).await;
+    }
+    
+    println!("✅ WebSocket connected: shop={}, guest={:?}, protocol v{}", shop_id, guest_id, version);
+    state.connect(&shop_id);
+    if let Some(gid) = guest_id {
+        // Tin chiến dịch gửi lúc khách vắng → giờ mới tới widget
+        let (state, shop_id) = (state.clone(), shop_id.clone());
+        tokio::spawn(async move { campaigns::on_guest_connected(&state, &shop_id, gid).await });
+    }
     
     // Subscribe Redis channel cho shop này
     let mut rx = state.tx.subscribe();
+    let mut kick_rx = state.kick_tx.subscribe();
+    let mut draining = state.drain.subscribe();
     
-    // Task gửi tin từ Redis → Client
+    if let Some(agent) = &agent_id {
+        let status = WsEnvelope::agent_status(shop_id.clone(), AgentStatus { agent_id: agent.clone(), away });
+        let _ = sender.send(WsMessage::Binary(status.encode_to_vec())).await;
+    }
+    
+    // Khách cũ chưa có vị trí → tra Geo-IP (chạy nền)
+    if let Some(gid) = guest_id {
+        let (state, shop_id, ip) = (state.clone(), shop_id.clone(), ip.clone());
+        tokio::spawn(async move { geoip::enrich_guest(&state, &shop_id, gid, &ip).await });
+    }
+    
+    // Gửi trạng thái online/offline hiện tại ngay khi kết nối
+    let settings = state.settings.get(&shop_id).await;
+    let presence = presence::presence_for(&settings, chrono::Utc::now(), state.cluster.all_away(&shop_id).await);
+    let mut hello = WsEnvelope::presence(shop_id.clone(), presence);
+    hello.protocol_version = PROTOCOL_VERSION;
+    let _ = sender.send(WsMessage::Binary(hello.encode_to_vec())).await;
+    
+    // Feature flag của shop → widget/admin bật tắt tính năng tương ứng
+    match state.repo.get_flags(&shop_id).await {
+        Ok(flags) => {
+            let frame = WsEnvelope::flags(shop_id.clone(), flags);
+            let _ = sender.send(WsMessage::Binary(frame.encode_to_vec())).await;
+        }
+        Err(e) => eprintln!("❌ Load flags for {} failed: {:?}", shop_id, e),
+    }
+    
+    // Đang bảo trì → hiện banner ngay (client cũ không hiểu frame này thì bỏ qua)
+    if state.maintenance.is_active() && version >= 3 {
+        let _ = sender.send(WsMessage::Binary(state.maintenance.banner_frame().encode_to_vec())).await;
+    }
+    
+    // Khách (protocol v2+): mở phiên, nối lại thì phát bù tin bị lỡ trước. rx đã subscribe từ trước khi
+    // đọc DB nên tin mới trong lúc đó vẫn nằm chờ trong rx - không hở khoảng nào
+    let mut replayed_up_to = 0;
+    if let Some(gid) = guest_id.filter(|_| version >= 2) {
+        let opened = session::open(&state, &shop_id, gid, query.resume.as_deref(), query.last_id.unwrap_or(0)).await;
+        for msg in opened.replay {
+            replayed_up_to = replayed_up_to.max(msg.message_id);
+            let _ = sender.send(WsMessage::Binary(WsEnvelope::message(msg).encode_to_vec())).await;
+        }
+        let frame = WsEnvelope::session(shop_id.clone(), gid, opened.session);
+        let _ = sender.send(WsMessage::Binary(frame.encode_to_vec())).await;
+    }
+    
+    // Admin: người đứng sau kết nối (cho "đang xem / đang gõ" ở các admin khác)
+    let viewer = match &agent_id {
+        Some(device) => Some(activity::viewer(&state, &shop_id, query.admin_pin.as_deref().unwrap_or(""), device).await),
+        None => None,
+    };
+
+    // Frame trả lời riêng kết nối này (pong) - không qua broker
+    let (reply_tx, mut reply_rx) = tokio::sync::mpsc::channel::<WsEnvelope>(8);
+
+    // Task gửi tin từ broker → Client. Kết thúc: true = do xả node
     let shop_filter = shop_id.clone();
     let mut send_task = tokio::spawn(async move {
-        while let Ok(bytes) = rx.recv().await {
-            if let Ok(msg) = ChatMessage::decode(&bytes[..]) {
-                if msg.shop_id == shop_filter {
-                    // Guest chỉ nhận tin của mình, Admin nhận tất cả
-                    if guest_id.is_none() || guest_id == Some(msg.guest_id) {
-                        println!("📤 Forwarding to client: {} bytes", bytes.len());
-                        if sender.send(WsMessage::Binary(bytes)).await.is_err() {
-                            break;
+        loop {
+            tokio::select! {
+                recv = rx.recv() => {
+                    let Ok(bytes) = recv else { break false };
+                    if let Ok(envelope) = WsEnvelope::decode(&bytes[..]) {
+                        // Guest chỉ nhận frame của mình, Admin nhận tất cả
+                        // Tin vừa phát bù cũng có thể đang chờ trong rx → bỏ bản trùng
+                        let replayed = matches!(&envelope.frame, Some(ws_envelope::Frame::Message(m)) if m.message_id <= replayed_up_to);
+                        // Frame mới hơn phiên bản client → bỏ (client cũ không hiểu)
+                        if envelope.is_visible_to(&shop_filter, guest_id) && envelope.supported_by(version) && !replayed {
+                            if config::debug_logs() {
+                                println!("📤 Forwarding to client: {} bytes [request {}]", bytes.len(), envelope.request_id);
+                            }
+                            if sender.send(WsMessage::Binary(bytes)).await.is_err() {
+                                break false;
+                            }
+                        }
+                    }
+                }
+                Some(reply) = reply_rx.recv() => {
+                    if sender.send(WsMessage::Binary(reply.encode_to_vec())).await.is_err() {
+                        break false;
+                    }
+                }
+                kick = kick_rx.recv() => {
+                    if let Ok((kick_shop, kick_guest)) = kick {
+                        if kick_shop == shop_filter && guest_id == Some(kick_guest) {
+                            println!("🚫 Disconnecting blocked guest {}", kick_guest);
+                            let _ = sender.send(WsMessage::Close(Some(CloseFrame {
+                                code: CLOSE_BLOCKED,
+                                reason: "blocked".into(),
+                            }))).await;
+                            break false;
                         }
                     }
                 }
+                // Node sắp tắt để deploy → client kết nối lại (sang node khác)
+                _ = async { drop(draining.wait_for(|draining| *draining).await) } => {
+                    let _ = sender.send(WsMessage::Close(Some(CloseFrame {
+                        code: CLOSE_SERVER_DRAINING,
+                        reason: "draining".into(),
+                    }))).await;
+                    break true;
+                }
             }
         }
     });
@@ -71,101 +404,406 @@ async fn handle_socket(socket: WebSocket, state: Arc<WebSocketState>, query: WsQ
     // Task nhận tin từ Client → Redis
     let state_clone = Arc::clone(&state);
     let shop_id_clone = shop_id.clone();
+    let agent_clone = agent_id.clone();
+    let registration_clone = registration.clone();
+    let viewer_clone = viewer.clone();
     let mut recv_task = tokio::spawn(async move {
         println!("👂 Listening for messages from client...");
         while let Some(Ok(msg)) = receiver.next().await {
             if let WsMessage::Binary(data) = msg {
-                println!("📩 Received WebSocket message: {:?}", "Binary");
-                println!("📦 Binary data: {} bytes", data.len());
+                if config::debug_logs() {
+                    println!("📩 Received WebSocket message: {:?}", "Binary");
+                    println!("📦 Binary data: {} bytes", data.len());
+                }
                 
-                if let Ok(mut chat_msg) = ChatMessage::decode(&data[..]) {
-                    chat_msg.shop_id = shop_id_clone.clone();
-                    println!("💬 Message decoded: shop={}, guest={}, sender={}, content={:?}",
-                        chat_msg.shop_id, chat_msg.guest_id, chat_msg.sender_type,
-                        String::from_utf8_lossy(&chat_msg.content));
-                    
-                    // Tạo/cập nhật guest nếu là guest
-                    if chat_msg.sender_type == "guest" {
-                        let name = format!("Guest #{}", chat_msg.guest_id % 10000);
-                        let _ = state_clone.repo.upsert_guest(&chat_msg.shop_id, chat_msg.guest_id, &name).await;
-                    }
-                    
-                    // Lưu DB
-                    if let Err(e) = state_clone.repo.insert_message(&chat_msg).await {
-                        eprintln!("❌ DB insert failed: {:?}", e);
-                        continue;
-                    }
-                    println!("✅ Message saved to DB");
-                    
-                    // Publish Redis
-                    if let Err(e) = publish_to_redis(&state_clone, &chat_msg).await {
-                        eprintln!("❌ Redis publish failed: {:?}", e);
+                let Some((frame, request_id)) = decode_client_frame(&data, &shop_id_clone, guest_id) else { continue };
+                let request_id = request_id.unwrap_or_else(request_id::generate);
+                let span = info_span!(parent: None, "ws.frame", shop_id = %shop_id_clone, request_id = %request_id);
+                // Mọi log / publish trong lúc xử lý frame mang id này
+                request_id::scope(request_id, async {
+                    match frame {
+                        ws_envelope::Frame::Message(chat_msg) => {
+                            if state_clone.maintenance.is_active() {
+                                maintenance::reject_send(&state_clone, &chat_msg).await;
+                                return;
+                            }
+                            match state_clone.quotas.check_send(&shop_id_clone, chat_msg.content.len()).await {
+                                Ok(()) => ingest_message(&state_clone, chat_msg, &ip).await,
+                                Err(exceeded) => quota::over_limit(&state_clone, chat_msg, exceeded).await,
+                            }
+                        }
+                        // Sửa/xoá qua WS chỉ dành cho guest (admin dùng endpoint REST có xác thực)
+                        ws_envelope::Frame::Edit(edit) => {
+                            let Some(gid) = guest_id else { return };
+                            if let Err(e) = edit::apply_edit(&state_clone, Editor::Guest(gid), edit).await {
+                                println!("⚠️ Guest {} edit refused: {}", gid, e);
+                            }
+                        }
+                        ws_envelope::Frame::Delete(delete) => {
+                            let Some(gid) = guest_id else { return };
+                            if let Err(e) = edit::apply_delete(&state_clone, Editor::Guest(gid), delete).await {
+                                println!("⚠️ Guest {} delete refused: {}", gid, e);
+                            }
+                        }
+                        ws_envelope::Frame::Identify(identify) => {
+                            let Some(gid) = guest_id else { return };
+                            visitor::handle_identify(&state_clone, &shop_id_clone, gid, identify).await;
+                        }
+                        ws_envelope::Frame::TrackEvent(event) => {
+                            let Some(gid) = guest_id else { return };
+                            visitor::handle_track_event(&state_clone, &shop_id_clone, gid, event).await;
+                        }
+                        ws_envelope::Frame::VisitorContext(context) => {
+                            let Some(gid) = guest_id else { return };
+                            visitor::handle_visitor_context(&state_clone, &shop_id_clone, gid, context).await;
+                        }
+                        ws_envelope::Frame::ReadReceipt(receipt) => {
+                            let Some(gid) = guest_id else { return };
+                            campaigns::on_read(&state_clone, &shop_id_clone, gid, receipt).await;
+                        }
+                        // Client kiểm tra kết nối còn sống (tab hiện lại) - đầy hàng đợi thì bỏ, client tự nối lại
+                        ws_envelope::Frame::Ping(ping) => {
+                            let _ = reply_tx.try_send(WsEnvelope::pong(ping));
+                        }
+                        // Admin bật/tắt vắng mặt - chỉ cho chính agent của kết nối này
+                        ws_envelope::Frame::AgentStatus(status) => {
+                            let Some(agent) = &agent_clone else { return };
+                            set_agent_away(&state_clone, &registration_clone, &shop_id_clone, agent, status.away).await;
+                        }
+                        ws_envelope::Frame::AgentActivity(activity) => {
+                            let Some(viewer) = &viewer_clone else { return };
+                            activity::relay(&state_clone, &shop_id_clone, viewer, activity).await;
+                        }
+                        _ => {}
                     }
-                }
+                }.instrument(span)).await;
             }
         }
     });
 
     tokio::select! {
-        _ = (&mut send_task) => recv_task.abort(),
+        drained = (&mut send_task) => {
+            // Đang xả: để frame đang xử lý ghi xong (client đóng theo close frame → recv_task tự kết thúc)
+            let finished = matches!(drained, Ok(true)) && tokio::time::timeout(DRAIN_GRACE, &mut recv_task).await.is_ok();
+            if !finished {
+                recv_task.abort();
+            }
+        }
         _ = (&mut recv_task) => send_task.abort(),
     }
     
+    state.disconnect(&shop_id);
+    let was_away = if agent_id.is_some() { state.cluster.all_away(&shop_id).await } else { false };
+    state.cluster.unregister(&registration).await;
+    if agent_id.is_some() {
+        publish_presence_if_changed(&state, &shop_id, was_away).await;
+    }
+    if let Some(viewer) = &viewer {
+        activity::left(&state, &shop_id, viewer).await;
+    }
     println!("🔌 WebSocket disconnected: shop={}", shop_id);
 }
 
-async fn publish_to_redis(state: &Arc<WebSocketState>, msg: &ChatMessage) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let client = redis::Client::open(state.redis_url.as_str())?;
-    let mut conn = client.get_multiplexed_async_connection().await?;
-    let channel = format!("chat:{}", msg.shop_id);
-    let payload = msg.encode_to_vec();
-    println!("📡 Publishing to channel: {}", channel);
-    conn.publish::<_, _, ()>(&channel, &payload).await?;
-    println!("✅ Published to Redis");
-    Ok(())
+/// Giải mã + chuẩn hoá frame client gửi lên theo kết nối (guest_id = None là admin).
+/// Kèm request id client gắn vào frame (nếu hợp lệ).
+/// None = frame hỏng, quá lớn, sai CRC hoặc không dành cho loại kết nối này → bỏ qua.
+/// Không được panic với bất kỳ input nào (fuzz/fuzz_targets/ws_ingest.rs)
+pub fn decode_client_frame(data: &[u8], shop_id: &str, guest_id: Option<u64>) -> Option<(ws_envelope::Frame, Option<String>)> {
+    if data.len() > MAX_FRAME_BYTES {
+        return None;
+    }
+    let envelope = WsEnvelope::decode(data).ok()?;
+    let request_id = request_id::accept(&envelope.request_id);
+    normalize_client_frame(envelope.frame?, shop_id, guest_id).map(|frame| (frame, request_id))
 }
 
-pub async fn redis_subscriber_task(state: Arc<WebSocketState>) {
-    println!("🔔 Redis subscriber starting...");
-    
-    loop {
-        match run_redis_subscriber(&state).await {
-            Ok(_) => {
-                println!("⚠️ Redis stream ended, reconnecting...");
+fn normalize_client_frame(frame: ws_envelope::Frame, shop_id: &str, guest_id: Option<u64>) -> Option<ws_envelope::Frame> {
+    match (frame, guest_id) {
+        (ws_envelope::Frame::Message(mut msg), _) => {
+            if msg.content.len() > MAX_CONTENT_BYTES || msg.verify_content().is_err() {
+                return None;
             }
-            Err(e) => {
-                eprintln!("❌ Redis pubsub error: {:?}", e);
+            msg.shop_id = shop_id.to_string();
+            match guest_id {
+                // Kết nối guest chỉ được gửi tin của chính mình
+                Some(gid) => {
+                    msg.guest_id = gid;
+                    msg.sender_type = "guest".to_string();
+                }
+                None if msg.guest_id == 0 => return None,
+                None => msg.sender_type = "admin".to_string(),
             }
+            // Các trường do server cấp
+            msg.message_id = 0;
+            msg.timestamp_us = 0;
+            msg.edited_at_us = 0;
+            msg.deleted_at_us = 0;
+            msg.deleted_by.clear();
+            msg.reply_preview = None;
+            Some(ws_envelope::Frame::Message(msg))
         }
-        // Đợi 2 giây trước khi reconnect
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+        (ws_envelope::Frame::Edit(mut edit), Some(gid)) => {
+            if edit.content.len() > MAX_CONTENT_BYTES || edit.verify_content().is_err() {
+                return None;
+            }
+            edit.shop_id = shop_id.to_string();
+            edit.guest_id = gid;
+            Some(ws_envelope::Frame::Edit(edit))
+        }
+        (ws_envelope::Frame::Delete(mut delete), Some(gid)) => {
+            delete.shop_id = shop_id.to_string();
+            delete.guest_id = gid;
+            Some(ws_envelope::Frame::Delete(delete))
+        }
+        (frame @ (ws_envelope::Frame::Identify(_) | ws_envelope::Frame::TrackEvent(_) | ws_envelope::Frame::VisitorContext(_) | ws_envelope::Frame::ReadReceipt(_)), Some(_)) => Some(frame),
+        (frame @ (ws_envelope::Frame::AgentStatus(_) | ws_envelope::Frame::AgentActivity(_)), None) => Some(frame),
+        (frame @ ws_envelope::Frame::Ping(_), _) => Some(frame),
+        _ => None,
+    }
+}
+
+// agent_id nằm trong URL của Astra REST → chỉ cho ký tự an toàn
+fn is_valid_agent_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 64 && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+async fn set_agent_away(state: &WebSocketState, registration: &Registration, shop_id: &str, agent_id: &str, away: bool) {
+    if let Err(e) = state.repo.set_agent_away(shop_id, agent_id, away).await {
+        eprintln!("❌ Save agent status failed: {:?}", e);
+    }
+    println!("🙋 Agent {} of shop {} is now {}", agent_id, shop_id, if away { "away" } else { "available" });
+
+    // Các tab khác của cùng admin cập nhật nút bật/tắt
+    let status = WsEnvelope::agent_status(shop_id.to_string(), AgentStatus { agent_id: agent_id.to_string(), away });
+    if let Err(e) = publish_envelope(state, &status).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
     }
+    let was_away = state.cluster.all_away(shop_id).await;
+    state.cluster.update(registration, &Peer::Admin { agent_id: Some(agent_id.to_string()), away }).await;
+    publish_presence_if_changed(state, shop_id, was_away).await;
 }
 
-async fn run_redis_subscriber(state: &Arc<WebSocketState>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let client = redis::Client::open(state.redis_url.as_str())?;
+// Sổ kết nối vừa đổi (admin vào/ra/bật vắng mặt) → phát Presence nếu trạng thái vắng mặt của shop đổi
+async fn publish_presence_if_changed(state: &WebSocketState, shop_id: &str, was_away: bool) {
+    if state.cluster.all_away(shop_id).await != was_away {
+        presence::publish_presence(state, shop_id).await;
+    }
+}
+
+// Pipeline xử lý tin từ client: chặn → lọc → lưu DB → publish Redis
+pub(crate) async fn ingest_message(state: &Arc<WebSocketState>, mut chat_msg: ChatMessage, ip: &str) {
+    println!("💬 Message decoded: shop={}, guest={}, sender={}, content={:?} [request {}]",
+        chat_msg.shop_id, chat_msg.guest_id, chat_msg.sender_type,
+        String::from_utf8_lossy(&chat_msg.content), request_id::current());
+    let is_guest = chat_msg.sender_type == "guest";
+    
+    // Guest/IP bị chặn → bỏ tin
+    if is_guest && state.blocks.is_blocked(&chat_msg.shop_id, Some(chat_msg.guest_id), ip).await {
+        println!("🚫 Dropped message from blocked guest {}", chat_msg.guest_id);
+        return;
+    }
     
-    // Dùng get_async_pubsub() cho redis 0.27+
-    let mut pubsub = client.get_async_pubsub().await?;
+    // Lọc spam / từ cấm
+    let mut flags = Vec::new();
+    let settings = state.settings.get(&chat_msg.shop_id).await;
+    if is_guest {
+        match state.filters.run(&settings, &mut chat_msg) {
+            Outcome::Accept { flags: f } => flags = f,
+            Outcome::Reject(reason) => {
+                println!("🛑 Rejected message from guest {}: {}", chat_msg.guest_id, reason);
+                return;
+            }
+        }
+    }
     
-    // Subscribe pattern chat:*
-    pubsub.psubscribe("chat:*").await?;
-    println!("✅ Redis subscribed to chat:*");
+    // Lựa chọn nhanh chỉ dành cho tin admin/bot
+    chat_msg.quick_replies = if is_guest { Vec::new() } else { QuickReply::sanitize(std::mem::take(&mut chat_msg.quick_replies)) };
     
-    // Lấy stream từ pubsub
-    let mut stream = pubsub.on_message();
+    // File đính kèm phải đã upload vào đúng cuộc chat này
+    chat_msg.attachments = Attachment::sanitize(std::mem::take(&mut chat_msg.attachments), &chat_msg.shop_id, chat_msg.guest_id);
     
-    while let Some(msg) = stream.next().await {
-        let channel: String = msg.get_channel()?;
-        let payload: Vec<u8> = msg.get_payload()?;
-        
-        println!("📬 Redis received on {}: {} bytes", channel, payload.len());
-        
-        let receiver_count = state.tx.receiver_count();
-        println!("📢 Broadcast to {} receivers", receiver_count);
-        
-        let _ = state.tx.send(payload);
+    // Chỉ được trả lời tin trong cùng cuộc chat
+    chat_msg.reply_preview = None;
+    if let Some(reply_id) = chat_msg.reply_to_message_id {
+        match state.repo.get_message(&chat_msg.shop_id, chat_msg.guest_id, reply_id).await {
+            Ok(Some(quoted)) => chat_msg.reply_preview = Some(ReplyPreview::from_message(&quoted)),
+            _ => chat_msg.reply_to_message_id = None,
+        }
+    }
+    
+    // Server cấp message_id (Snowflake) - id phía client chỉ để hiển thị tạm
+    let id = state.repo.ids.next_id();
+    chat_msg.message_id = id.0;
+    chat_msg.timestamp_us = id.timestamp() * 1000;
+    
+    // Luật "hội thoại mới" cần trạng thái guest trước tin này
+    let new_conversation = is_guest && routing::is_new_conversation(state, &settings, &chat_msg.shop_id, chat_msg.guest_id).await;
+    
+    // Tạo/cập nhật guest nếu là guest
+    if is_guest {
+        let _ = state.repo.upsert_guest(&chat_msg.shop_id, chat_msg.guest_id, ip).await;
+        // Khách mới (tin đầu tiên) → tra Geo-IP (chạy nền, mỗi khách chỉ một lần)
+        let (state, shop_id, guest_id, ip) = (state.clone(), chat_msg.shop_id.clone(), chat_msg.guest_id, ip.to_string());
+        tokio::spawn(async move { geoip::enrich_guest(&state, &shop_id, guest_id, &ip).await });
+    }
+    
+    // Lưu DB
+    // Lỗi/quá hạn chỉ bỏ tin này - vòng nhận WS vẫn chạy tiếp
+    match state.inserts.insert(&chat_msg).await {
+        Ok(()) => println!("✅ Message {} saved to DB [request {}]", chat_msg.message_id, request_id::current()),
+        Err(ContractError::Timeout(op)) => {
+            eprintln!("⏱️ DB insert of message {} timed out ({}), dropped", chat_msg.message_id, op);
+            return;
+        }
+        Err(e) => {
+            eprintln!("❌ DB insert failed: {:?}", e);
+            return;
+        }
+    }
+    state.quotas.record_message(&chat_msg.shop_id, chat_msg.content.len()).await;
+    
+    if !flags.is_empty() {
+        println!("🚩 Flagged message {}: {}", chat_msg.message_id, flags.join(", "));
+        if let Err(e) = state.repo.insert_flagged(&chat_msg, &flags.join("; ")).await {
+            eprintln!("❌ Flag insert failed: {:?}", e);
+        }
+    }
+    
+    // Ngoài giờ làm việc → báo email cho shop
+    if is_guest && !presence::is_open(&settings, chrono::Utc::now()) {
+        state.notifier.notify_offline_message(&settings, &chat_msg).await;
+    }
+    
+    // Publish Redis (kèm link tải file)
+    attachments::sign_urls(state.blobs.as_ref(), std::slice::from_mut(&mut chat_msg));
+    if let Err(e) = publish_envelope(state, &WsEnvelope::message(chat_msg.clone())).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
     }
     
+    // Các hook sau (SLA, cảm xúc, kịch bản bot, luật tự động) mỗi cái đọc/ghi DB vài lượt
+    // → chạy nền (giữ request id + span), vòng nhận WS của kết nối không phải chờ
+    let state = state.clone();
+    let id = request_id::current();
+    tokio::spawn(request_id::scope(id, async move {
+        // Lượt chờ phản hồi (SLA)
+        sla::on_message(&state, &chat_msg).await;
+        
+        // Cảm xúc hội thoại + kịch bản bot + luật tự động (tag / bot trả lời / giao người phụ trách)
+        if is_guest {
+            sentiment::on_guest_message(&state, &chat_msg).await;
+            reminders::on_guest_message(&state, &chat_msg).await;
+            let in_flow = flows::on_guest_message(&state, &settings, &chat_msg).await;
+            if !in_flow {
+                handoff::on_guest_message(&state, &chat_msg).await;
+            }
+            routing::apply_rules(&state, &settings, &chat_msg, new_conversation, in_flow).await;
+        } else if chat_msg.sender_type == "admin" {
+            flows::on_agent_message(&state, &settings, &chat_msg).await;
+            handoff::on_agent_message(&state, &chat_msg).await;
+        }
+    }).in_current_span());
+}
+
+/// Tin của bot (luật tự động) - không qua chặn/lọc, lưu + publish như tin thường
+pub(crate) async fn send_bot_message(state: &WebSocketState, shop_id: &str, guest_id: u64, text: &str) {
+    send_bot_reply(state, shop_id, guest_id, text, Vec::new()).await;
+}
+
+/// Tin bot kèm nút trả lời nhanh (kịch bản bot)
+pub(crate) async fn send_bot_reply(state: &WebSocketState, shop_id: &str, guest_id: u64, text: &str, quick_replies: Vec<QuickReply>) {
+    send_server_message(state, shop_id, guest_id, "bot", text, quick_replies).await;
+}
+
+/// Dòng hệ thống trong hội thoại (content = mã sự kiện, vd. ChatMessage::SYSTEM_HANDOFF)
+pub(crate) async fn send_system_message(state: &WebSocketState, shop_id: &str, guest_id: u64, code: &str) {
+    send_server_message(state, shop_id, guest_id, "system", code, Vec::new()).await;
+}
+
+async fn send_server_message(state: &WebSocketState, shop_id: &str, guest_id: u64, sender_type: &str, text: &str, quick_replies: Vec<QuickReply>) {
+    let id = state.repo.ids.next_id();
+    let msg = ChatMessage {
+        shop_id: shop_id.to_string(),
+        guest_id,
+        message_id: id.0,
+        sender_type: sender_type.to_string(),
+        content_crc: crc32c::crc32c(text.as_bytes()),
+        content: text.as_bytes().to_vec().into(),
+        timestamp_us: id.timestamp() * 1000,
+        quick_replies: QuickReply::sanitize(quick_replies),
+        ..Default::default()
+    };
+    if let Err(e) = state.inserts.insert(&msg).await {
+        eprintln!("❌ DB insert failed: {:?}", e);
+        return;
+    }
+    println!("🤖 {} message sent to guest {}", sender_type, guest_id);
+    if let Err(e) = publish_envelope(state, &WsEnvelope::message(msg)).await {
+        eprintln!("❌ Redis publish failed: {:?}", e);
+    }
+}
+
+/// SET NX trên Redis - chỉ một node giành được `key` (việc nền chạy trên mọi node không làm trùng)
+pub(crate) async fn claim_once(state: &WebSocketState, key: &str, ttl_secs: u64) -> bool {
+    let result: Result<Option<String>, redis::RedisError> = async {
+        let mut conn = state.redis.get().await?;
+        redis::cmd("SET").arg(key).arg(1).arg("NX").arg("EX").arg(ttl_secs)
+            .query_async(&mut conn)
+            .await
+    }.await;
+
+    match state.redis.checked(result).await {
+        Ok(reply) => reply.is_some(),
+        Err(e) => {
+            eprintln!("❌ Redis claim {} failed: {:?}", key, e);
+            false
+        }
+    }
+}
+
+#[tracing::instrument(name = "bus.publish", skip_all, fields(shop_id = %envelope.shop_id))]
+pub async fn publish_envelope(state: &WebSocketState, envelope: &WsEnvelope) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Đang trong một request → gắn id + trace vào frame để node nhận (và Kafka) nối tiếp
+    let request_id = request_id::current();
+    let traceparent = telemetry::current_traceparent();
+    let stamped;
+    let envelope = if (envelope.request_id.is_empty() && !request_id.is_empty()) || !traceparent.is_empty() {
+        let mut copy = envelope.clone();
+        if copy.request_id.is_empty() {
+            copy.request_id = request_id;
+        }
+        copy.traceparent = traceparent;
+        stamped = copy;
+        &stamped
+    } else {
+        envelope
+    };
+    println!("📡 Publishing frame for shop {} [request {}]", envelope.shop_id, envelope.request_id);
+    state.exporter.export(envelope);
+    state.bus.publish(&envelope.shop_id, envelope.encode_to_vec()).await?;
+    println!("✅ Published to message bus");
     Ok(())
-}
\ No newline at end of file
+}
+
+pub async fn bus_subscriber_task(state: Arc<WebSocketState>) {
+    println!("🔔 Message bus subscriber starting ({:?})...", state.bus.delivery());
+    
+    loop {
+        match state.bus.subscribe(None).await {
+            Ok(mut frames) => {
+                println!("✅ Subscribed to every shop");
+                while let Some(payload) = frames.next().await {
+                    let receivers = state.tx.receiver_count();
+                    let _span = telemetry::deliver_span(&payload, receivers).entered();
+                    if config::debug_logs() {
+                        println!("📬 Bus received {} bytes, broadcast to {} receivers", payload.len(), receivers);
+                    }
+                    let _ = state.tx.send(payload);
+                }
+                println!("⚠️ Message bus stream ended, reconnecting...");
+            }
+            Err(e) => eprintln!("❌ Message bus subscribe error: {}", e),
+        }
+        // Đợi 2 giây trước khi reconnect
+        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+    }
+}
@@ -0,0 +1,341 @@
+// Integration test: Redis + Stargate (Cassandra + REST API giống Astra) trong container, chạy đúng binary backend
+// trên cổng ngẫu nhiên rồi đi hết luồng: auth, guest gửi tin, admin nhận, sync theo trang, reconnect + replay.
+//
+// Chạy cùng cargo test. Máy không có Docker → bỏ qua kèm cảnh báo; CI đặt TURBOCHAT_REQUIRE_DOCKER=1
+// để thiếu Docker thành lỗi thay vì bỏ qua.
+use backend::contract::{
+    ws_envelope::Frame, AdminAuthRequest, AdminAuthResponse, Message, SyncRequest, SyncResponse, WsEnvelope,
+};
+use futures::{SinkExt, StreamExt};
+use prost::Message as ProstMessage;
+use serde_json::json;
+use std::process::Stdio;
+use std::time::Duration;
+use testcontainers::core::{IntoContainerPort, WaitFor};
+use testcontainers::runners::AsyncRunner;
+use testcontainers::{ContainerAsync, GenericImage, ImageExt};
+use tokio::net::TcpStream;
+use tokio::process::{Child, Command};
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+const SHOP_ID: &str = "demo123";
+const PIN: &str = "123456";
+const KEYSPACE: &str = "turbochat_test";
+const STARGATE_IMAGE: (&str, &str) = ("stargateio/stargate-4_0", "v1.0.85");
+// Stargate khởi động Cassandra bên trong - chậm
+const STARGATE_STARTUP: Duration = Duration::from_secs(300);
+const BACKEND_STARTUP: Duration = Duration::from_secs(120);
+const FRAME_TIMEOUT: Duration = Duration::from_secs(10);
+const DOCKER_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Ws = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+struct Harness {
+    _redis: ContainerAsync<GenericImage>,
+    _stargate: ContainerAsync<GenericImage>,
+    _backend: Child,
+    _blob_dir: std::path::PathBuf,
+    http: reqwest::Client,
+    base: String,
+    ws_base: String,
+}
+
+impl Harness {
+    async fn start() -> Self {
+        let redis = GenericImage::new("redis", "7-alpine")
+            .with_exposed_port(6379.tcp())
+            .with_wait_for(WaitFor::message_on_stdout("Ready to accept connections"))
+            .start()
+            .await
+            .expect("start redis");
+        let redis_url = format!(
+            "redis://{}:{}",
+            redis.get_host().await.unwrap(),
+            redis.get_host_port_ipv4(6379).await.unwrap()
+        );
+
+        let stargate = GenericImage::new(STARGATE_IMAGE.0, STARGATE_IMAGE.1)
+            .with_exposed_port(8081.tcp())
+            .with_exposed_port(8082.tcp())
+            .with_wait_for(WaitFor::message_on_stdout("Finished starting bundles"))
+            .with_env_var("CLUSTER_NAME", "turbochat")
+            .with_env_var("CLUSTER_VERSION", "4.0")
+            .with_env_var("DEVELOPER_MODE", "true")
+            .with_startup_timeout(STARGATE_STARTUP)
+            .start()
+            .await
+            .expect("start stargate");
+        let host = stargate.get_host().await.unwrap();
+        let auth_url = format!("http://{}:{}", host, stargate.get_host_port_ipv4(8081).await.unwrap());
+        let rest_url = format!("http://{}:{}", host, stargate.get_host_port_ipv4(8082).await.unwrap());
+
+        let http = reqwest::Client::new();
+        let token = stargate_token(&http, &auth_url).await;
+        http.post(format!("{}/v2/schemas/keyspaces", rest_url))
+            .header("X-Cassandra-Token", &token)
+            .json(&json!({ "name": KEYSPACE, "replicas": 1 }))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .expect("create keyspace");
+
+        let port = free_port();
+        let blob_dir = std::env::temp_dir().join(format!("turbochat-it-{}", port));
+        // Migrate lúc khởi động + --seed-demo tạo shop demo123 / PIN 123456
+        let backend = Command::new(env!("CARGO_BIN_EXE_backend"))
+            .arg("--seed-demo")
+            .current_dir(std::env::temp_dir())
+            .env("ASTRA_REST_URL", &rest_url)
+            .env("ASTRA_DB_KEYSPACE", KEYSPACE)
+            .env("ASTRA_DB_TOKEN", &token)
+            .env("REDIS_URL", &redis_url)
+            .env("PORT", port.to_string())
+            .env("PUBLIC_URL", format!("http://127.0.0.1:{}", port))
+            .env("BLOB_DIR", &blob_dir)
+            .stdout(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .expect("spawn backend");
+
+        let harness = Self {
+            _redis: redis,
+            _stargate: stargate,
+            _backend: backend,
+            _blob_dir: blob_dir,
+            http,
+            base: format!("http://127.0.0.1:{}", port),
+            ws_base: format!("ws://127.0.0.1:{}/ws", port),
+        };
+        harness.wait_ready().await;
+        harness
+    }
+
+    // Server trả lời bất kỳ (kể cả 503 khi chưa build widget) = đã lắng nghe
+    async fn wait_ready(&self) {
+        let deadline = tokio::time::Instant::now() + BACKEND_STARTUP;
+        while tokio::time::Instant::now() < deadline {
+            if self.http.get(format!("{}/widget.js", self.base)).send().await.is_ok() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        panic!("backend did not start within {:?}", BACKEND_STARTUP);
+    }
+
+    async fn post_proto<Req: ProstMessage, Resp: ProstMessage + Default>(&self, path: &str, req: &Req) -> (u16, Resp) {
+        let resp = self.http.post(format!("{}{}", self.base, path))
+            .body(req.encode_to_vec())
+            .send()
+            .await
+            .expect("http request");
+        let status = resp.status().as_u16();
+        let body = resp.bytes().await.expect("http body");
+        (status, Resp::decode(&body[..]).expect("decode response"))
+    }
+
+    async fn guest_ws(&self, guest_id: u64) -> Ws {
+        let url = format!("{}?shop_id={}&guest_id={}", self.ws_base, SHOP_ID, guest_id);
+        tokio_tungstenite::connect_async(url).await.expect("guest ws").0
+    }
+
+    async fn admin_ws(&self) -> Ws {
+        let url = format!("{}?shop_id={}&admin_pin={}", self.ws_base, SHOP_ID, PIN);
+        tokio_tungstenite::connect_async(url).await.expect("admin ws").0
+    }
+
+    async fn sync(&self, guest_id: u64, after_message_id: u64, limit: u32, page_state: &str) -> SyncResponse {
+        let req = SyncRequest {
+            shop_id: SHOP_ID.to_string(),
+            guest_id,
+            after_message_id,
+            limit,
+            page_state: page_state.to_string(),
+            ..Default::default()
+        };
+        let (status, resp): (u16, SyncResponse) = self.post_proto("/sync", &req).await;
+        assert_eq!(status, 200);
+        resp.verify_crc().expect("sync crc");
+        resp
+    }
+}
+
+async fn stargate_token(http: &reqwest::Client, auth_url: &str) -> String {
+    // REST/auth có thể chưa sẵn sàng ngay cả khi log đã báo xong
+    for _ in 0..60 {
+        let resp = http.post(format!("{}/v1/auth", auth_url))
+            .json(&json!({ "username": "cassandra", "password": "cassandra" }))
+            .send()
+            .await;
+        if let Ok(resp) = resp {
+            if let Ok(body) = resp.json::<serde_json::Value>().await {
+                if let Some(token) = body["authToken"].as_str() {
+                    return token.to_string();
+                }
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    panic!("stargate auth did not come up");
+}
+
+// Docker daemon trả lời `docker info` (testcontainers cần cùng daemon đó)
+async fn docker_available() -> bool {
+    let probe = Command::new("docker").arg("info").stdout(Stdio::null()).stderr(Stdio::null()).status();
+    matches!(tokio::time::timeout(DOCKER_PROBE_TIMEOUT, probe).await, Ok(Ok(status)) if status.success())
+}
+
+fn free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+async fn send_message(ws: &mut Ws, guest_id: u64, sender_type: &str, text: &str) {
+    let msg = Message {
+        shop_id: SHOP_ID.to_string(),
+        guest_id,
+        sender_type: sender_type.to_string(),
+        content_crc: crc32c::crc32c(text.as_bytes()),
+        content: text.as_bytes().to_vec().into(),
+        ..Default::default()
+    };
+    ws.send(WsMessage::Binary(WsEnvelope::message(msg).encode_to_vec())).await.expect("ws send");
+}
+
+// Tin kế tiếp có nội dung `text` (bỏ qua presence/flags/... và tin khác)
+async fn expect_message(ws: &mut Ws, text: &str) -> Message {
+    let wait = async {
+        while let Some(frame) = ws.next().await {
+            let WsMessage::Binary(data) = frame.expect("ws frame") else { continue };
+            let envelope = WsEnvelope::decode(&data[..]).expect("decode envelope");
+            if let Some(Frame::Message(msg)) = envelope.frame {
+                if msg.content[..] == *text.as_bytes() {
+                    return msg;
+                }
+            }
+        }
+        panic!("ws closed while waiting for {:?}", text);
+    };
+    tokio::time::timeout(FRAME_TIMEOUT, wait).await.unwrap_or_else(|_| panic!("no message {:?} within {:?}", text, FRAME_TIMEOUT))
+}
+
+// Không có tin nào trong `window`
+async fn expect_silence(ws: &mut Ws, window: Duration) {
+    let wait = async {
+        while let Some(Ok(WsMessage::Binary(data))) = ws.next().await {
+            if let Ok(WsEnvelope { frame: Some(Frame::Message(msg)), .. }) = WsEnvelope::decode(&data[..]) {
+                return Some(msg);
+            }
+        }
+        None
+    };
+    if let Ok(Some(msg)) = tokio::time::timeout(window, wait).await {
+        panic!("unexpected live message {:?}", String::from_utf8_lossy(&msg.content));
+    }
+}
+
+#[tokio::test]
+async fn chat_flow_end_to_end() {
+    if !docker_available().await {
+        assert!(std::env::var_os("TURBOCHAT_REQUIRE_DOCKER").is_none(), "TURBOCHAT_REQUIRE_DOCKER is set but Docker is not reachable");
+        eprintln!("⚠️ Docker not reachable, skipping chat_flow_end_to_end (needs Redis + Stargate containers)");
+        return;
+    }
+    let h = Harness::start().await;
+    auth(&h).await;
+    let guest_id = 4_200_000_000 + free_port() as u64;
+    let first_id = guest_to_admin(&h, guest_id).await;
+    sync_pagination(&h, guest_id, first_id).await;
+    reconnect_replay(&h, guest_id).await;
+}
+
+async fn auth(h: &Harness) {
+    let ok = AdminAuthRequest { shop_id: SHOP_ID.to_string(), admin_pin: PIN.to_string(), ..Default::default() };
+    let (status, resp): (u16, AdminAuthResponse) = h.post_proto("/auth", &ok).await;
+    assert_eq!(status, 200);
+    assert!(resp.success, "demo PIN rejected: {}", resp.error);
+    assert_eq!(resp.shop_name, "Shop Demo");
+
+    let bad = AdminAuthRequest { shop_id: SHOP_ID.to_string(), admin_pin: "000000".to_string(), ..Default::default() };
+    let (_, resp): (u16, AdminAuthResponse) = h.post_proto("/auth", &bad).await;
+    assert!(!resp.success);
+
+    // WS admin sai PIN bị từ chối ngay lúc upgrade
+    let url = format!("{}?shop_id={}&admin_pin=000000", h.ws_base, SHOP_ID);
+    assert!(tokio_tungstenite::connect_async(url).await.is_err());
+}
+
+// Guest gửi → guest nhận echo có id do server cấp, admin nhận cùng tin
+async fn guest_to_admin(h: &Harness, guest_id: u64) -> u64 {
+    let mut admin = h.admin_ws().await;
+    let mut guest = h.guest_ws(guest_id).await;
+
+    send_message(&mut guest, guest_id, "guest", "xin chào shop").await;
+    let echo = expect_message(&mut guest, "xin chào shop").await;
+    assert_ne!(echo.message_id, 0);
+    assert_eq!(echo.sender_type, "guest");
+    assert_eq!(echo.content_crc, crc32c::crc32c(&echo.content));
+
+    let received = expect_message(&mut admin, "xin chào shop").await;
+    assert_eq!(received.message_id, echo.message_id);
+    assert_eq!(received.guest_id, guest_id);
+
+    // Admin trả lời → guest nhận
+    send_message(&mut admin, guest_id, "admin", "shop chào bạn").await;
+    let reply = expect_message(&mut guest, "shop chào bạn").await;
+    assert_eq!(reply.sender_type, "admin");
+    assert!(reply.message_id > echo.message_id);
+
+    echo.message_id
+}
+
+// 2 tin có sẵn + 5 tin mới, đọc từng trang 3 tin → đủ 7 tin, đúng thứ tự, không trùng
+async fn sync_pagination(h: &Harness, guest_id: u64, first_id: u64) {
+    let mut guest = h.guest_ws(guest_id).await;
+    for i in 0..5 {
+        let text = format!("tin số {}", i);
+        send_message(&mut guest, guest_id, "guest", &text).await;
+        expect_message(&mut guest, &text).await;
+    }
+
+    let mut messages = Vec::new();
+    let mut page_state = String::new();
+    let mut pages = 0;
+    loop {
+        let page = h.sync(guest_id, 0, 3, &page_state).await;
+        assert!(page.messages.len() <= 3);
+        messages.extend(page.messages);
+        pages += 1;
+        if !page.has_more {
+            break;
+        }
+        assert!(!page.page_state.is_empty());
+        page_state = page.page_state;
+        assert!(pages < 10, "pagination does not terminate");
+    }
+    assert_eq!(pages, 3);
+    assert_eq!(messages.len(), 7);
+    assert_eq!(messages[0].message_id, first_id);
+    assert!(messages.windows(2).all(|w| w[0].message_id < w[1].message_id));
+    assert_eq!(&messages[6].content[..], "tin số 4".as_bytes());
+}
+
+// Guest rớt mạng, admin nhắn trong lúc đó → kết nối lại không nhận live, sync sau tin cuối đã thấy thì có
+async fn reconnect_replay(h: &Harness, guest_id: u64) {
+    let last_seen = h.sync(guest_id, 0, 500, "").await.messages.last().map(|m| m.message_id).expect("history");
+
+    let guest = h.guest_ws(guest_id).await;
+    drop(guest);
+
+    let mut admin = h.admin_ws().await;
+    send_message(&mut admin, guest_id, "admin", "bạn còn đó không?").await;
+    let sent = expect_message(&mut admin, "bạn còn đó không?").await;
+
+    let mut guest = h.guest_ws(guest_id).await;
+    expect_silence(&mut guest, Duration::from_secs(1)).await;
+
+    let replay = h.sync(guest_id, last_seen, 0, "").await;
+    assert_eq!(replay.messages.len(), 1);
+    assert_eq!(replay.messages[0].message_id, sent.message_id);
+    assert_eq!(replay.messages[0].sender_type, "admin");
+    assert!(!replay.has_more);
+}
@@ -0,0 +1,94 @@
+// ============================================================================
+// window.TurboChat - API cho website chủ điều khiển widget
+//   TurboChat.open() / close()
+//   TurboChat.identify({ name, email, user_id, user_hash })  (user_hash: HMAC do server website ký)
+//   TurboChat.trackEvent(name, { key: value })
+//   TurboChat.onUnreadChange(count => ...)
+// Lệnh gọi trước khi WASM tải xong được xếp vào TurboChat.q = [[method, ...args]]
+// ============================================================================
+use std::collections::HashMap;
+use turbochat_shared::Identify;
+use wasm_bindgen::prelude::*;
+
+pub struct Api {
+    pub open: Box<dyn Fn()>,
+    pub close: Box<dyn Fn()>,
+    pub identify: Box<dyn Fn(Identify)>,
+    pub track_event: Box<dyn Fn(String, HashMap<String, String>)>,
+    pub on_unread_change: Box<dyn Fn(js_sys::Function)>,
+}
+
+pub fn install(api: Api) {
+    let window = web_sys::window().unwrap();
+    let global = js_sys::Reflect::get(&window, &"TurboChat".into()).unwrap_or(JsValue::UNDEFINED);
+    let queued = js_sys::Reflect::get(&global, &"q".into())
+        .ok()
+        .and_then(|q| q.dyn_into::<js_sys::Array>().ok());
+
+    let obj = js_sys::Object::new();
+    let Api { open, close, identify, track_event, on_unread_change } = api;
+
+    set_method(&obj, "open", Closure::<dyn Fn()>::new(open).into_js_value());
+    set_method(&obj, "close", Closure::<dyn Fn()>::new(close).into_js_value());
+    set_method(&obj, "identify", Closure::<dyn Fn(JsValue)>::new(move |user: JsValue| {
+        identify(Identify {
+            name: string_prop(&user, "name"),
+            email: string_prop(&user, "email"),
+            user_id: string_prop(&user, "user_id"),
+            user_hash: string_prop(&user, "user_hash"),
+        });
+    }).into_js_value());
+    set_method(&obj, "trackEvent", Closure::<dyn Fn(String, JsValue)>::new(move |name: String, props: JsValue| {
+        track_event(name, properties(&props));
+    }).into_js_value());
+    set_method(&obj, "onUnreadChange", Closure::<dyn Fn(JsValue)>::new(move |cb: JsValue| {
+        if let Ok(cb) = cb.dyn_into::<js_sys::Function>() {
+            on_unread_change(cb);
+        }
+    }).into_js_value());
+
+    let _ = js_sys::Reflect::set(&window, &"TurboChat".into(), &obj);
+
+    // Chạy lại các lệnh đã xếp hàng
+    if let Some(queued) = queued {
+        for call in queued.iter() {
+            let Ok(call) = call.dyn_into::<js_sys::Array>() else { continue };
+            let method = js_sys::Reflect::get(&obj, &call.get(0)).unwrap_or(JsValue::UNDEFINED);
+            if let Ok(method) = method.dyn_into::<js_sys::Function>() {
+                let _ = method.apply(&obj, &call.slice(1, call.length()));
+            }
+        }
+    }
+    leptos::logging::log!("🔌 window.TurboChat ready");
+}
+
+fn set_method(obj: &js_sys::Object, name: &str, f: JsValue) {
+    let _ = js_sys::Reflect::set(obj, &name.into(), &f);
+}
+
+fn string_prop(obj: &JsValue, key: &str) -> String {
+    js_sys::Reflect::get(obj, &key.into())
+        .ok()
+        .and_then(|v| v.as_string())
+        .unwrap_or_default()
+}
+
+// { key: value } → các cặp chuỗi (số/bool được chuyển sang chuỗi)
+fn properties(props: &JsValue) -> HashMap<String, String> {
+    if !props.is_object() {
+        return HashMap::new();
+    }
+    js_sys::Object::entries(props.unchecked_ref())
+        .iter()
+        .filter_map(|entry| {
+            let entry: js_sys::Array = entry.dyn_into().ok()?;
+            let key = entry.get(0).as_string()?;
+            let value = entry.get(1);
+            let value = value.as_string().or_else(|| {
+                value.as_f64().map(|n| n.to_string())
+                    .or_else(|| value.as_bool().map(|b| b.to_string()))
+            })?;
+            Some((key, value))
+        })
+        .collect()
+}
@@ -0,0 +1,117 @@
+// ============================================================================
+// TRẠNG THÁI KẾT NỐI - hiện cho khách: đang kết nối / đã kết nối / nối lại sau Ns / mất mạng
+// Tab leader giữ WS nên tự tính trạng thái rồi phát cho tab khác (TabSync, dạng chuỗi encode()).
+// Chưa kết nối: nút Gửi tắt, Enter xếp tin vào outbox - gửi khi kết nối lại
+// ============================================================================
+
+use crate::i18n::T;
+
+/// Vì sao WS đóng (chọn câu hiển thị)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CloseReason {
+    Lost,
+    /// Máy chủ đang deploy (CLOSE_SERVER_DRAINING)
+    Draining,
+    /// Quá số kết nối cho phép (CLOSE_TOO_MANY_CONNECTIONS)
+    Busy,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConnState {
+    /// Lần kết nối đầu
+    Connecting,
+    Connected,
+    /// Hẹn nối lại lúc retry_at (ms, Date.now()); qua giờ hẹn = đang nối lại
+    Reconnecting { retry_at: f64, reason: CloseReason },
+    /// Trình duyệt báo mất mạng - nối lại khi có sự kiện online
+    Offline,
+    /// Guest bị shop chặn (close 4003)
+    Blocked,
+    /// Server không còn hỗ trợ bản widget này
+    Unsupported,
+}
+
+impl ConnState {
+    pub fn is_connected(&self) -> bool {
+        *self == ConnState::Connected
+    }
+
+    /// Có thể gửi lại sau (đưa tin vào outbox); false = không bao giờ gửi được
+    pub fn can_queue(&self) -> bool {
+        !matches!(self, ConnState::Blocked | ConnState::Unsupported)
+    }
+
+    /// Hiện nút "Thử lại ngay"
+    pub fn can_retry(&self) -> bool {
+        matches!(self, ConnState::Reconnecting { .. } | ConnState::Offline)
+    }
+
+    /// Dòng trạng thái; now_ms = Date.now() (đếm ngược tới lần nối lại)
+    pub fn label(&self, now_ms: f64) -> String {
+        match self {
+            ConnState::Connecting => T::Connecting.text().to_string(),
+            ConnState::Connected => T::Connected.text().to_string(),
+            ConnState::Reconnecting { retry_at, reason } => {
+                let secs = ((retry_at - now_ms) / 1000.0).ceil();
+                let prefix = match reason {
+                    CloseReason::Lost => T::ConnectionLost,
+                    CloseReason::Draining => T::ServerUpdating,
+                    CloseReason::Busy => T::ServerBusy,
+                }.text();
+                if secs >= 1.0 {
+                    T::ReconnectIn.fill(&[&prefix, &secs])
+                } else {
+                    T::Reconnecting.fill(&[&prefix])
+                }
+            }
+            ConnState::Offline => T::BrowserOffline.text().to_string(),
+            ConnState::Blocked => T::Blocked.text().to_string(),
+            ConnState::Unsupported => T::Unsupported.text().to_string(),
+        }
+    }
+
+    /// Dạng chuỗi gửi qua BroadcastChannel
+    pub fn encode(&self) -> String {
+        match self {
+            ConnState::Connecting => "connecting".to_string(),
+            ConnState::Connected => "connected".to_string(),
+            ConnState::Reconnecting { retry_at, reason } => {
+                let reason = match reason {
+                    CloseReason::Lost => "lost",
+                    CloseReason::Draining => "draining",
+                    CloseReason::Busy => "busy",
+                };
+                format!("reconnecting:{}:{}", reason, retry_at)
+            }
+            ConnState::Offline => "offline".to_string(),
+            ConnState::Blocked => "blocked".to_string(),
+            ConnState::Unsupported => "unsupported".to_string(),
+        }
+    }
+
+    pub fn decode(value: &str) -> Option<Self> {
+        let state = match value {
+            "connecting" => ConnState::Connecting,
+            "connected" => ConnState::Connected,
+            "offline" => ConnState::Offline,
+            "blocked" => ConnState::Blocked,
+            "unsupported" => ConnState::Unsupported,
+            other => {
+                let mut parts = other.strip_prefix("reconnecting:")?.splitn(2, ':');
+                let reason = match parts.next()? {
+                    "lost" => CloseReason::Lost,
+                    "draining" => CloseReason::Draining,
+                    "busy" => CloseReason::Busy,
+                    _ => return None,
+                };
+                ConnState::Reconnecting { retry_at: parts.next()?.parse().ok()?, reason }
+            }
+        };
+        Some(state)
+    }
+}
+
+/// Trình duyệt đang có mạng (không rõ → coi như có)
+pub fn browser_online() -> bool {
+    web_sys::window().is_none_or(|w| w.navigator().on_line())
+}
@@ -0,0 +1,105 @@
+// ============================================================================
+// CHUỖI GIAO DIỆN WIDGET - vi (gốc) + en. Locale: data-locale trên thẻ script
+// → ngôn ngữ trình duyệt (navigator.languages) → tiếng Việt
+// ============================================================================
+
+use turbochat_shared::{string_table, Locale};
+
+/// Locale theo data-locale của trang, nếu không có thì theo trình duyệt
+pub fn detect(root: &web_sys::Element) -> Locale {
+    let mut preferred: Vec<String> = root.get_attribute("data-locale").into_iter().collect();
+    if let Some(navigator) = web_sys::window().map(|w| w.navigator()) {
+        preferred.extend(navigator.languages().iter().filter_map(|l| l.as_string()));
+        preferred.extend(navigator.language());
+    }
+    Locale::negotiate(preferred.iter().map(String::as_str))
+}
+
+string_table! {
+    pub enum T {
+        // Khung chat
+        DefaultTitle { vi: "Chat với chúng tôi", en: "Chat with us" }
+        OpenChat { vi: "Mở cửa sổ chat", en: "Open chat" }
+        OpenChatUnread { vi: "Mở cửa sổ chat, {0} tin nhắn mới", en: "Open chat, {0} new messages" }
+        CloseChat { vi: "Đóng cửa sổ chat", en: "Close chat" }
+        DismissPreview { vi: "Ẩn tin xem trước", en: "Dismiss preview" }
+        LeaveMessage { vi: "Để lại lời nhắn", en: "Leave a message" }
+        AgentsAway { vi: "Nhân viên đang vắng mặt", en: "Our team is away" }
+        Unmute { vi: "Bật âm báo", en: "Turn sound on" }
+        Mute { vi: "Tắt âm báo", en: "Turn sound off" }
+        DarkMode { vi: "Giao diện tối", en: "Dark mode" }
+        LightMode { vi: "Giao diện sáng", en: "Light mode" }
+        RetryNow { vi: "Thử lại ngay", en: "Retry now" }
+        AwayNotice {
+            vi: "Nhân viên đang tạm vắng. Bạn cứ nhắn, chúng tôi sẽ trả lời ngay khi quay lại.",
+            en: "Our team is away right now. Leave a message and we'll reply as soon as we're back.",
+        }
+        OfflineNotice {
+            vi: "Chúng tôi hiện không trực tuyến. Hãy để lại lời nhắn, chúng tôi sẽ phản hồi sớm nhất có thể.",
+            en: "We're offline right now. Leave a message and we'll get back to you as soon as possible.",
+        }
+        ReopensAt { vi: " (Mở cửa lại: {0})", en: " (Back at: {0})" }
+        NewMessagesTitle { vi: "({0}) Tin nhắn mới", en: "({0}) New messages" }
+
+        // Tin nhắn
+        Messages { vi: "Tin nhắn", en: "Messages" }
+        SenderYou { vi: "Bạn", en: "You" }
+        SenderShop { vi: "Shop", en: "Shop" }
+        MessageFrom { vi: "{0} lúc {1}", en: "{0} at {1}" }
+        Deleted { vi: "🚫 Tin nhắn đã bị xoá", en: "🚫 This message was deleted" }
+        QuoteDeleted { vi: "Tin nhắn đã bị xoá", en: "Message deleted" }
+        ViewQuoted { vi: "Xem tin được trả lời", en: "Show the quoted message" }
+        Edited { vi: " (đã sửa)", en: " (edited)" }
+        Reply { vi: "Trả lời", en: "Reply" }
+        Edit { vi: "Sửa", en: "Edit" }
+        Delete { vi: "Xoá", en: "Delete" }
+        EditPrompt { vi: "Sửa tin nhắn", en: "Edit message" }
+        DeleteConfirm { vi: "Xoá tin nhắn này?", en: "Delete this message?" }
+        QuickReplies { vi: "Lựa chọn nhanh", en: "Quick replies" }
+        ReplyingTo { vi: "↩️ Trả lời ", en: "↩️ Replying to " }
+        CancelReply { vi: "Huỷ trả lời", en: "Cancel reply" }
+        HandoffNotice { vi: "Đã chuyển bạn tới nhân viên hỗ trợ, vui lòng chờ trong giây lát", en: "You've been connected to our team, someone will be with you shortly" }
+
+        // Trạng thái tin gửi
+        OutboxNotice { vi: "⏳ Sẽ gửi khi có kết nối", en: "⏳ Will send when connected" }
+        QuotaQueued { vi: "⏳ Đang chờ gửi", en: "⏳ Waiting to send" }
+        QuotaRejected { vi: "⚠️ Chưa gửi được, vui lòng thử lại sau", en: "⚠️ Not sent, please try again later" }
+        MaintenanceRejected { vi: "⚠️ Hệ thống đang bảo trì, vui lòng gửi lại sau", en: "⚠️ Under maintenance, please send again later" }
+
+        // Ô nhập
+        InputLabel { vi: "Nhập tin nhắn", en: "Type a message" }
+        InputPlaceholder { vi: "Nhập tin nhắn...", en: "Type a message..." }
+        InputPlaceholderOffline { vi: "Để lại lời nhắn của bạn...", en: "Leave your message..." }
+        Send { vi: "Gửi", en: "Send" }
+        SendQueued { vi: "Chưa kết nối - nhấn Enter để xếp tin chờ gửi", en: "Not connected - press Enter to queue your message" }
+
+        // Kết nối (connection.rs)
+        Connecting { vi: "🔴 Đang kết nối...", en: "🔴 Connecting..." }
+        Connected { vi: "🟢 Đã kết nối", en: "🟢 Connected" }
+        ConnectionLost { vi: "🟡 Mất kết nối", en: "🟡 Connection lost" }
+        ServerUpdating { vi: "🟡 Máy chủ đang cập nhật", en: "🟡 Server is updating" }
+        ServerBusy { vi: "🟡 Máy chủ đang bận", en: "🟡 Server is busy" }
+        ReconnectIn { vi: "{0}, kết nối lại sau {1}s", en: "{0}, reconnecting in {1}s" }
+        Reconnecting { vi: "{0}, đang kết nối lại…", en: "{0}, reconnecting…" }
+        BrowserOffline { vi: "🔴 Không có mạng - tin nhắn sẽ được gửi khi có mạng trở lại", en: "🔴 No network - messages will be sent when you're back online" }
+        Blocked { vi: "🚫 Bạn không thể gửi tin nhắn", en: "🚫 You can't send messages" }
+        Unsupported { vi: "⚠️ Vui lòng tải lại trang để tiếp tục chat", en: "⚠️ Please reload the page to keep chatting" }
+
+        // Tin nhắn thoại (voice.rs)
+        RecordVoice { vi: "Ghi âm tin nhắn thoại", en: "Record a voice message" }
+        CancelRecording { vi: "Huỷ ghi âm", en: "Cancel recording" }
+        SendVoice { vi: "Gửi tin nhắn thoại", en: "Send voice message" }
+        VoiceSending { vi: "⏫ Đang gửi tin nhắn thoại...", en: "⏫ Sending voice message..." }
+        VoiceEmpty { vi: "⚠️ Không ghi được âm thanh", en: "⚠️ Nothing was recorded" }
+        VoiceUnsupported { vi: "Trình duyệt không hỗ trợ ghi âm", en: "Your browser can't record audio" }
+        MicUnavailable { vi: "Không mở được micro", en: "Couldn't open the microphone" }
+        MicDenied { vi: "Bạn chưa cho phép dùng micro", en: "Microphone access was not allowed" }
+        RecordFailed { vi: "Không ghi âm được", en: "Recording failed" }
+        RecordingUnreadable { vi: "Không đọc được bản ghi", en: "Couldn't read the recording" }
+        ServerUnreachable { vi: "Mất kết nối tới máy chủ", en: "Can't reach the server" }
+        InvalidResponse { vi: "Phản hồi không hợp lệ", en: "Invalid server response" }
+        VoiceForbidden { vi: "Không gửi được tin nhắn thoại", en: "Voice messages can't be sent" }
+        VoiceTooLong { vi: "Tin nhắn thoại quá dài", en: "Voice message is too long" }
+        VoiceFailed { vi: "Gửi tin nhắn thoại thất bại (HTTP {0})", en: "Sending the voice message failed (HTTP {0})" }
+    }
+}
@@ -1,3 +1,10 @@
+mod api;
+mod connection;
+mod i18n;
+mod notify;
+mod tabs;
+mod theme;
+mod voice;
 mod widget;
 
 use wasm_bindgen::prelude::*;
@@ -31,12 +38,24 @@ fn try_mount() -> bool {
     let shop_id = root.get_attribute("data-shop-id")
         .unwrap_or_else(|| "demo123".to_string());
     
+    // Địa chỉ backend (widget.js tự gắn data-api)
+    let api_base = root.get_attribute("data-api")
+        .map(|url| url.trim_end_matches('/').to_string())
+        .unwrap_or_else(|| "http://localhost:8080".to_string());
+    
+    // Ngôn ngữ giao diện (data-locale / trình duyệt) - chọn trước khi dựng theme (tiêu đề mặc định)
+    turbochat_shared::i18n::set_locale(i18n::detect(&root));
+    // Màu, vị trí, icon, tiêu đề từ data-*
+    let theme = theme::Theme::from_element(&root);
+    // Tài khoản đăng nhập của website (nếu trang có data-user-*)
+    let identity = widget::identity_from_element(&root);
+    
     leptos::logging::log!("✅ Found root, shop_id: {}", shop_id);
     
     // Mount widget với shop_id
     leptos::mount::mount_to(
         root.unchecked_into(),
-        move || widget::Widget(widget::WidgetProps { shop_id: shop_id.clone() })
+        move || widget::Widget(widget::WidgetProps { shop_id: shop_id.clone(), api_base: api_base.clone(), theme: theme.clone(), identity: identity.clone() })
     ).forget();
     
     leptos::logging::log!("✅ Widget mounted!");
@@ -0,0 +1,96 @@
+// ============================================================================
+// THÔNG BÁO - Âm báo (Web Audio) + Notification của trình duyệt khi shop trả lời
+// Trình duyệt chặn autoplay → AudioContext chỉ được tạo/resume sau thao tác
+// của khách (unlock_audio gọi trong on:click)
+// ============================================================================
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+use web_sys::{AudioContext, AudioContextState, Notification, NotificationOptions, NotificationPermission, OscillatorType};
+
+thread_local! {
+    static AUDIO: RefCell<Option<AudioContext>> = const { RefCell::new(None) };
+}
+
+/// Gọi trong handler click để lần sau được phép phát âm thanh
+pub fn unlock_audio() {
+    AUDIO.with(|audio| {
+        let mut audio = audio.borrow_mut();
+        if audio.is_none() {
+            *audio = AudioContext::new().ok();
+        }
+        if let Some(ctx) = audio.as_ref() {
+            if ctx.state() == AudioContextState::Suspended {
+                let _ = ctx.resume();
+            }
+        }
+    });
+}
+
+/// Tiếng "ping" ngắn - bỏ qua nếu trình duyệt chưa cho phát
+pub fn play_ping() {
+    AUDIO.with(|audio| {
+        let audio = audio.borrow();
+        let Some(ctx) = audio.as_ref().filter(|ctx| ctx.state() == AudioContextState::Running) else { return };
+        let (Ok(osc), Ok(gain)) = (ctx.create_oscillator(), ctx.create_gain()) else { return };
+        let now = ctx.current_time();
+        osc.set_type(OscillatorType::Sine);
+        osc.frequency().set_value(880.0);
+        let _ = gain.gain().set_value_at_time(0.15, now);
+        let _ = gain.gain().exponential_ramp_to_value_at_time(0.001, now + 0.3);
+        let _ = osc.connect_with_audio_node(&gain);
+        let _ = gain.connect_with_audio_node(&ctx.destination());
+        let _ = osc.start();
+        let _ = osc.stop_with_when(now + 0.3);
+    });
+}
+
+fn notifications_supported() -> bool {
+    let window = web_sys::window().unwrap();
+    js_sys::Reflect::has(&window, &"Notification".into()).unwrap_or(false)
+}
+
+/// Xin quyền Notification (chỉ hỏi khi chưa chọn)
+pub fn request_permission() {
+    if notifications_supported() && Notification::permission() == NotificationPermission::Default {
+        let _ = Notification::request_permission();
+    }
+}
+
+/// Hiện Notification nếu đã được cấp quyền; click → focus tab và gọi on_click
+pub fn show_notification(title: &str, body: &str, on_click: impl Fn() + 'static) {
+    if !notifications_supported() || Notification::permission() != NotificationPermission::Granted {
+        return;
+    }
+    let options = NotificationOptions::new();
+    options.set_body(body);
+    options.set_tag("turbochat");
+    let Ok(notification) = Notification::new_with_options(title, &options) else { return };
+
+    let notification_clone = notification.clone();
+    let onclick = Closure::wrap(Box::new(move || {
+        let _ = web_sys::window().unwrap().focus();
+        on_click();
+        notification_clone.close();
+    }) as Box<dyn FnMut()>);
+    notification.set_onclick(Some(onclick.as_ref().unchecked_ref()));
+    onclick.forget();
+}
+
+// Tắt tiếng theo từng guest (localStorage)
+fn mute_key(shop_id: &str, guest_id: u64) -> String {
+    format!("turbochat_muted_{}_{}", shop_id, guest_id)
+}
+
+pub fn is_muted(shop_id: &str, guest_id: u64) -> bool {
+    web_sys::window()
+        .and_then(|w| w.local_storage().ok().flatten())
+        .and_then(|s| s.get_item(&mute_key(shop_id, guest_id)).ok().flatten())
+        .is_some_and(|v| v == "1")
+}
+
+pub fn set_muted(shop_id: &str, guest_id: u64, muted: bool) {
+    if let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) {
+        let key = mute_key(shop_id, guest_id);
+        let _ = if muted { storage.set_item(&key, "1") } else { storage.remove_item(&key) };
+    }
+}
@@ -0,0 +1,212 @@
+// ============================================================================
+// MULTI-TAB - Nhiều tab cùng shop chỉ giữ 1 WebSocket
+// Tab leader giữ kết nối và phát lại mọi frame qua BroadcastChannel;
+// tab follower gửi tin qua leader. Bầu leader bằng heartbeat:
+// không thấy heartbeat quá LEADER_TIMEOUT_MS → tự nhận leader,
+// 2 leader gặp nhau → tab có id nhỏ hơn giữ quyền.
+// ============================================================================
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use web_sys::{BroadcastChannel, MessageEvent};
+
+const HEARTBEAT_MS: i32 = 1000;
+const LEADER_TIMEOUT_MS: f64 = 3000.0;
+// Tab mới mở đợi chừng này để nghe leader hiện tại
+const STARTUP_WAIT_MS: i32 = 400;
+
+// Tag byte đầu của mỗi gói trên channel
+const TAG_HEARTBEAT: u8 = 1;
+const TAG_FRAME: u8 = 2;
+const TAG_OUTBOUND: u8 = 3;
+const TAG_RESIGN: u8 = 4;
+const TAG_STATUS: u8 = 5;
+const TAG_PROBE: u8 = 6;
+const TAG_RETRY: u8 = 7;
+
+pub enum TabEvent {
+    BecameLeader,
+    LostLeadership,
+    /// Frame WS do leader phát lại (follower xử lý như nhận từ WS)
+    Frame(Vec<u8>),
+    /// Frame follower nhờ leader gửi lên server
+    Outbound(Vec<u8>),
+    /// Trạng thái kết nối của leader
+    Status(String),
+    /// Có tab mới mở (leader gửi lại trạng thái hiện tại)
+    FollowerJoined,
+    /// Khách bấm "Thử lại ngay" ở tab follower
+    RetryRequested,
+}
+
+struct TabState {
+    is_leader: bool,
+    last_leader_seen: f64,
+}
+
+pub struct TabSync {
+    channel: BroadcastChannel,
+    tab_id: u64,
+    state: RefCell<TabState>,
+    on_event: Box<dyn Fn(TabEvent)>,
+}
+
+impl TabSync {
+    /// None nếu trình duyệt không hỗ trợ BroadcastChannel (tab tự kết nối)
+    pub fn start(name: &str, on_event: impl Fn(TabEvent) + 'static) -> Option<Rc<Self>> {
+        let channel = BroadcastChannel::new(name).ok()?;
+        let tabs = Rc::new(Self {
+            channel,
+            tab_id: (js_sys::Math::random() * u32::MAX as f64) as u64,
+            state: RefCell::new(TabState { is_leader: false, last_leader_seen: 0.0 }),
+            on_event: Box::new(on_event),
+        });
+
+        // Nhận gói từ các tab khác
+        {
+            let weak = Rc::downgrade(&tabs);
+            let on_message = Closure::wrap(Box::new(move |event: MessageEvent| {
+                let Some(tabs) = weak.upgrade() else { return };
+                if let Ok(arr) = event.data().dyn_into::<js_sys::Uint8Array>() {
+                    tabs.handle_packet(arr.to_vec());
+                }
+            }) as Box<dyn FnMut(MessageEvent)>);
+            tabs.channel.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+            on_message.forget();
+        }
+
+        let window = web_sys::window().unwrap();
+
+        // Heartbeat / kiểm tra leader định kỳ
+        {
+            let weak = Rc::downgrade(&tabs);
+            let tick = Closure::wrap(Box::new(move || {
+                if let Some(tabs) = weak.upgrade() {
+                    tabs.tick();
+                }
+            }) as Box<dyn FnMut()>);
+            let _ = window.set_interval_with_callback_and_timeout_and_arguments_0(
+                tick.as_ref().unchecked_ref(), HEARTBEAT_MS);
+            tick.forget();
+        }
+
+        // Mở tab: hỏi leader, không ai trả lời thì tự nhận
+        tabs.post(TAG_PROBE, &[]);
+        {
+            let weak = Rc::downgrade(&tabs);
+            let check = Closure::once(Box::new(move || {
+                if let Some(tabs) = weak.upgrade() {
+                    if tabs.state.borrow().last_leader_seen == 0.0 {
+                        tabs.become_leader();
+                    }
+                }
+            }) as Box<dyn FnOnce()>);
+            let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(
+                check.as_ref().unchecked_ref(), STARTUP_WAIT_MS);
+            check.forget();
+        }
+
+        // Đóng tab leader → nhường ngay cho tab khác
+        {
+            let weak = Rc::downgrade(&tabs);
+            let on_hide = Closure::wrap(Box::new(move || {
+                if let Some(tabs) = weak.upgrade() {
+                    if tabs.is_leader() {
+                        tabs.post(TAG_RESIGN, &tabs.tab_id.to_le_bytes());
+                    }
+                }
+            }) as Box<dyn FnMut()>);
+            let _ = window.add_event_listener_with_callback("pagehide", on_hide.as_ref().unchecked_ref());
+            on_hide.forget();
+        }
+
+        Some(tabs)
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.state.borrow().is_leader
+    }
+
+    /// Leader phát lại frame vừa nhận từ WS
+    pub fn broadcast_frame(&self, bytes: &[u8]) {
+        self.post(TAG_FRAME, bytes);
+    }
+
+    /// Follower nhờ leader gửi frame lên server
+    pub fn send_outbound(&self, bytes: &[u8]) {
+        self.post(TAG_OUTBOUND, bytes);
+    }
+
+    pub fn broadcast_status(&self, status: &str) {
+        self.post(TAG_STATUS, status.as_bytes());
+    }
+
+    /// Follower nhờ leader nối lại ngay
+    pub fn request_retry(&self) {
+        self.post(TAG_RETRY, &[]);
+    }
+
+    fn post(&self, tag: u8, payload: &[u8]) {
+        let mut packet = Vec::with_capacity(payload.len() + 1);
+        packet.push(tag);
+        packet.extend_from_slice(payload);
+        let _ = self.channel.post_message(&js_sys::Uint8Array::from(&packet[..]));
+    }
+
+    fn tick(&self) {
+        if self.is_leader() {
+            self.post(TAG_HEARTBEAT, &self.tab_id.to_le_bytes());
+            return;
+        }
+        let last_seen = self.state.borrow().last_leader_seen;
+        if last_seen > 0.0 && js_sys::Date::now() - last_seen > LEADER_TIMEOUT_MS {
+            self.become_leader();
+        }
+    }
+
+    fn become_leader(&self) {
+        if self.is_leader() {
+            return;
+        }
+        self.state.borrow_mut().is_leader = true;
+        self.post(TAG_HEARTBEAT, &self.tab_id.to_le_bytes());
+        (self.on_event)(TabEvent::BecameLeader);
+    }
+
+    fn handle_packet(&self, packet: Vec<u8>) {
+        let Some((&tag, payload)) = packet.split_first() else { return };
+        let sender_id = || payload.get(..8).map(|b| u64::from_le_bytes(b.try_into().unwrap()));
+
+        match tag {
+            TAG_HEARTBEAT => {
+                let Some(other) = sender_id() else { return };
+                if self.is_leader() {
+                    // 2 leader: id nhỏ hơn giữ quyền
+                    if other < self.tab_id {
+                        self.state.borrow_mut().is_leader = false;
+                        (self.on_event)(TabEvent::LostLeadership);
+                    } else {
+                        return;
+                    }
+                }
+                self.state.borrow_mut().last_leader_seen = js_sys::Date::now();
+            }
+            TAG_PROBE if self.is_leader() => {
+                self.post(TAG_HEARTBEAT, &self.tab_id.to_le_bytes());
+                (self.on_event)(TabEvent::FollowerJoined);
+            }
+            TAG_RESIGN if !self.is_leader() => {
+                // Chênh ngẫu nhiên để các tab không cùng nhận leader
+                let delay = js_sys::Math::random() * 200.0;
+                self.state.borrow_mut().last_leader_seen = js_sys::Date::now() - LEADER_TIMEOUT_MS + delay;
+            }
+            TAG_FRAME if !self.is_leader() => (self.on_event)(TabEvent::Frame(payload.to_vec())),
+            TAG_OUTBOUND if self.is_leader() => (self.on_event)(TabEvent::Outbound(payload.to_vec())),
+            TAG_RETRY if self.is_leader() => (self.on_event)(TabEvent::RetryRequested),
+            TAG_STATUS if !self.is_leader() => {
+                (self.on_event)(TabEvent::Status(String::from_utf8_lossy(payload).to_string()))
+            }
+            _ => {}
+        }
+    }
+}
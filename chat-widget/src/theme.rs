@@ -0,0 +1,113 @@
+// ============================================================================
+// THEME - Tuỳ biến widget qua data-* trên #turbochat-root
+//   data-color="#FF6600"  data-position="left"
+//   data-launcher-icon="🛒" (hoặc URL ảnh https://...)  data-title="Hỗ trợ"
+//   data-flash-title="false" (tắt nhấp nháy tiêu đề tab khi có tin mới)
+//   data-theme="auto" | "light" | "dark" (mặc định theo hệ điều hành; khách đổi bằng nút 🌙 thì nhớ lựa chọn của khách)
+//   data-locale="en" (ngôn ngữ giao diện; mặc định theo trình duyệt, rồi tiếng Việt)
+// Locale viết từ phải sang trái (ar, he): widget đặt dir="rtl", CSS dùng thuộc tính logic nên tự lật;
+// không có data-position thì launcher sang góc trái
+// ============================================================================
+use turbochat_shared::{is_safe_url, ColorScheme, Direction};
+
+use crate::i18n::T;
+
+pub const DEFAULT_COLOR: &str = "#3390EC";
+pub const DEFAULT_ICON: &str = "💬";
+const MAX_TITLE_CHARS: usize = 60;
+const MAX_ICON_CHARS: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Position {
+    Left,
+    #[default]
+    Right,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum LauncherIcon {
+    Text(String),
+    Image(String),
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Theme {
+    pub color: String,
+    pub position: Position,
+    pub launcher_icon: LauncherIcon,
+    pub title: String,
+    pub flash_title: bool,
+    pub direction: Direction,
+    pub color_scheme: ColorScheme,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let direction = turbochat_shared::i18n::locale().direction();
+        Self {
+            color: DEFAULT_COLOR.to_string(),
+            position: if direction.is_rtl() { Position::Left } else { Position::Right },
+            launcher_icon: LauncherIcon::Text(DEFAULT_ICON.to_string()),
+            title: T::DefaultTitle.text().to_string(),
+            flash_title: true,
+            direction,
+            color_scheme: ColorScheme::Auto,
+        }
+    }
+}
+
+impl Theme {
+    pub fn from_element(root: &web_sys::Element) -> Self {
+        let attr = |name: &str| root.get_attribute(name).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let mut theme = Self::default();
+
+        if let Some(color) = attr("data-color") {
+            if is_safe_color(&color) {
+                theme.color = color;
+            } else {
+                leptos::logging::log!("⚠️ Ignoring invalid data-color: {}", color);
+            }
+        }
+        if let Some(position) = attr("data-position") {
+            theme.position = if position.eq_ignore_ascii_case("left") { Position::Left } else { Position::Right };
+        }
+        if let Some(icon) = attr("data-launcher-icon") {
+            theme.launcher_icon = if icon.starts_with("http") && is_safe_url(&icon) {
+                LauncherIcon::Image(icon)
+            } else {
+                LauncherIcon::Text(icon.chars().take(MAX_ICON_CHARS).collect())
+            };
+        }
+        if let Some(title) = attr("data-title") {
+            theme.title = title.chars().take(MAX_TITLE_CHARS).collect();
+        }
+        if let Some(scheme) = attr("data-theme") {
+            theme.color_scheme = ColorScheme::parse(&scheme).unwrap_or_default();
+        }
+        if let Some(flash) = attr("data-flash-title") {
+            theme.flash_title = !matches!(flash.to_ascii_lowercase().as_str(), "false" | "0" | "off");
+        }
+        theme
+    }
+
+    /// Biến CSS gắn lên .turbochat-widget
+    pub fn style(&self) -> String {
+        format!("--tc-primary: {};", self.color)
+    }
+
+    pub fn class(&self) -> &'static str {
+        match self.position {
+            Position::Left => "turbochat-widget left",
+            Position::Right => "turbochat-widget",
+        }
+    }
+}
+
+// #rgb / #rrggbb / #rrggbbaa, rgb()/hsl() hoặc tên màu - không cho ; { } để tránh chèn CSS
+fn is_safe_color(color: &str) -> bool {
+    if let Some(hex) = color.strip_prefix('#') {
+        return matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    }
+    color.len() <= 40
+        && color.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '(' | ')' | ',' | '.' | '%' | ' '))
+}
@@ -0,0 +1,135 @@
+// ============================================================================
+// TIN NHẮN THOẠI - ghi âm bằng MediaRecorder, tải lên POST /attachments rồi gửi tin kèm Attachment
+// Chỉ bật khi shop có flag voice_notes. Ghi dạng webm/ogg (server nhận diện được là audio);
+// trình duyệt không ghi được hai dạng này → ẩn nút micro
+// ============================================================================
+
+use gloo_net::http::Request;
+use prost::Message as ProstMessage;
+use std::cell::RefCell;
+use std::rc::Rc;
+use turbochat_shared::Attachment;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Blob, BlobEvent, BlobPropertyBag, MediaRecorder, MediaRecorderOptions, MediaStream, MediaStreamConstraints, MediaStreamTrack, RecordingState};
+
+use crate::i18n::T;
+
+const MIME_TYPES: [&str; 2] = ["audio/webm;codecs=opus", "audio/ogg;codecs=opus"];
+
+fn mime_type() -> Option<&'static str> {
+    // Trình duyệt cũ không có MediaRecorder → gọi is_type_supported sẽ lỗi
+    let window = web_sys::window()?;
+    if !js_sys::Reflect::has(&window, &JsValue::from_str("MediaRecorder")).unwrap_or(false) {
+        return None;
+    }
+    MIME_TYPES.into_iter().find(|t| MediaRecorder::is_type_supported(t))
+}
+
+/// Trình duyệt ghi âm được
+pub fn supported() -> bool {
+    mime_type().is_some()
+}
+
+pub struct Recorder {
+    recorder: MediaRecorder,
+    stream: MediaStream,
+    chunks: Rc<RefCell<js_sys::Array>>,
+}
+
+impl Recorder {
+    /// Xin quyền micro rồi bắt đầu ghi; Err = câu báo lỗi cho khách
+    pub async fn start() -> Result<Recorder, String> {
+        let mime = mime_type().ok_or(T::VoiceUnsupported.text())?;
+        let devices = web_sys::window()
+            .and_then(|w| w.navigator().media_devices().ok())
+            .ok_or(T::VoiceUnsupported.text())?;
+        let constraints = MediaStreamConstraints::new();
+        constraints.set_audio_bool(true);
+        let request = devices.get_user_media_with_constraints(&constraints).map_err(|_| T::MicUnavailable.text())?;
+        let stream: MediaStream = JsFuture::from(request).await
+            .map_err(|_| T::MicDenied.text())?
+            .unchecked_into();
+
+        let options = MediaRecorderOptions::new();
+        options.set_mime_type(mime);
+        let recorder = match MediaRecorder::new_with_media_stream_and_media_recorder_options(&stream, &options) {
+            Ok(recorder) => recorder,
+            Err(_) => {
+                stop_tracks(&stream);
+                return Err(T::RecordFailed.text().to_string());
+            }
+        };
+        let chunks = Rc::new(RefCell::new(js_sys::Array::new()));
+        let sink = chunks.clone();
+        let on_data = Closure::wrap(Box::new(move |event: BlobEvent| {
+            if let Some(blob) = event.data().filter(|b| b.size() > 0.0) {
+                sink.borrow().push(&blob);
+            }
+        }) as Box<dyn FnMut(BlobEvent)>);
+        recorder.set_ondataavailable(Some(on_data.as_ref().unchecked_ref()));
+        on_data.forget();
+        if recorder.start().is_err() {
+            stop_tracks(&stream);
+            return Err(T::RecordFailed.text().to_string());
+        }
+        Ok(Recorder { recorder, stream, chunks })
+    }
+
+    /// Dừng ghi, trả file âm thanh (None = không ghi được gì)
+    pub async fn finish(&self) -> Option<Blob> {
+        if self.recorder.state() != RecordingState::Inactive {
+            // Phần dữ liệu cuối đến trước sự kiện stop
+            let recorder = self.recorder.clone();
+            let stopped = js_sys::Promise::new(&mut |resolve, _| recorder.set_onstop(Some(&resolve)));
+            let _ = self.recorder.stop();
+            let _ = JsFuture::from(stopped).await;
+        }
+        stop_tracks(&self.stream);
+        let options = BlobPropertyBag::new();
+        options.set_type(&self.recorder.mime_type());
+        Blob::new_with_blob_sequence_and_options(&self.chunks.borrow(), &options).ok().filter(|b| b.size() > 0.0)
+    }
+
+    /// Bỏ bản ghi, tắt micro
+    pub fn cancel(&self) {
+        self.recorder.set_ondataavailable(None);
+        let _ = self.recorder.stop();
+        stop_tracks(&self.stream);
+    }
+}
+
+// Tắt hẳn micro (trình duyệt thôi hiện biểu tượng đang ghi)
+fn stop_tracks(stream: &MediaStream) {
+    for track in stream.get_tracks().iter() {
+        track.unchecked_into::<MediaStreamTrack>().stop();
+    }
+}
+
+/// Tải bản ghi lên cuộc chat; Err = câu báo lỗi cho khách
+pub async fn upload(api_base: &str, shop_id: &str, guest_id: u64, blob: &Blob) -> Result<Attachment, String> {
+    let buffer = JsFuture::from(blob.array_buffer()).await.map_err(|_| T::RecordingUnreadable.text().to_string())?;
+    let bytes = js_sys::Uint8Array::new(&buffer).to_vec();
+    let ext = if blob.type_().starts_with("audio/ogg") { "ogg" } else { "webm" };
+    let url = format!(
+        "{}/attachments?shop_id={}&guest_id={}&name=voice-note.{}",
+        api_base,
+        js_sys::encode_uri_component(shop_id),
+        guest_id,
+        ext,
+    );
+    let resp = Request::post(&url)
+        .header("Content-Type", "application/octet-stream")
+        .body(bytes)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(|_| T::ServerUnreachable.text().to_string())?;
+    let body = resp.binary().await.unwrap_or_default();
+    match resp.status() {
+        200 => Attachment::decode(&body[..]).map_err(|_| T::InvalidResponse.text().to_string()),
+        403 => Err(T::VoiceForbidden.text().to_string()),
+        413 => Err(T::VoiceTooLong.text().to_string()),
+        status => Err(T::VoiceFailed.fill(&[&status])),
+    }
+}
@@ -1,99 +1,489 @@
+use leptos::html::{Button, Input};
 use leptos::prelude::*;
-use turbochat_shared::{Message as ChatMessage, SyncRequest, SyncResponse};
+use turbochat_shared::{format_clock, Attachment, format_datetime, format_day_label, is_new_day, now_us, ws_envelope, DeleteMessage, EditMessage, Identify, TrackEvent, VisitorContext, Message as ChatMessage, MessageId, MessageIdGenerator, Ping, Presence, QuickReply, ReadReceipt, ReplyPreview, render_markdown, ServerNotice, ShopFlags, EmojiPicker, ThemeMode, SyncRequest, SyncResponse, WsEnvelope, CLOSE_SERVER_DRAINING, CLOSE_TOO_MANY_CONNECTIONS, CLOSE_UNSUPPORTED_PROTOCOL, PROTOCOL_VERSION};
 use prost::Message as ProstMessage;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::spawn_local;
 use web_sys::{WebSocket, MessageEvent};
 use gloo_net::http::Request;
+use std::rc::Rc;
+use crate::api::{self, Api};
+use crate::connection::{self, CloseReason, ConnState};
+use crate::i18n::T;
+use crate::notify;
+use crate::tabs::{TabEvent, TabSync};
+use crate::theme::{LauncherIcon, Theme};
+use crate::voice::{self, Recorder};
 
 #[derive(Clone)]
 struct SendWs(WebSocket);
 unsafe impl Send for SendWs {}
 unsafe impl Sync for SendWs {}
 
+#[derive(Clone)]
+struct SendTabs(Rc<TabSync>);
+unsafe impl Send for SendTabs {}
+unsafe impl Sync for SendTabs {}
+
+#[derive(Clone)]
+struct SendFn(js_sys::Function);
+unsafe impl Send for SendFn {}
+unsafe impl Sync for SendFn {}
+
+#[derive(Clone)]
+struct SendRecorder(Rc<Recorder>);
+unsafe impl Send for SendRecorder {}
+unsafe impl Sync for SendRecorder {}
+
+#[derive(Clone, PartialEq)]
+struct WidgetMessage {
+    id: u64,
+    sender: String,
+    text: String,
+    timestamp_us: u64,
+    edited: bool,
+    deleted: bool,
+    pending: bool,   // Tin vừa gửi, chưa nhận id thật từ server
+    notice: Option<&'static str>,  // Shop vượt hạn mức / hệ thống bảo trì: tin bị giữ lại / không gửi được
+    reply: Option<ReplyPreview>,
+    quick_replies: Vec<QuickReply>,
+    attachments: Vec<Attachment>,
+}
+
+impl WidgetMessage {
+    fn from_proto(msg: &ChatMessage) -> Self {
+        Self {
+            id: msg.message_id,
+            sender: msg.sender_type.clone(),
+            text: message_text(msg),
+            timestamp_us: msg.timestamp_us,
+            edited: msg.edited_at_us > 0,
+            deleted: msg.deleted_at_us > 0,
+            pending: false,
+            notice: None,
+            reply: msg.reply_preview.clone(),
+            quick_replies: msg.quick_replies.clone(),
+            attachments: msg.attachments.clone(),
+        }
+    }
+
+    fn is_system(&self) -> bool {
+        self.sender == "system"
+    }
+
+    fn preview(&self) -> ReplyPreview {
+        ReplyPreview {
+            message_id: self.id,
+            sender_type: self.sender.clone(),
+            snippet: self.text.chars().take(ReplyPreview::SNIPPET_CHARS).collect(),
+            deleted: self.deleted,
+        }
+    }
+}
+
+// Dòng hệ thống server gửi mã (Message::SYSTEM_*) → dịch theo ngôn ngữ widget
+fn message_text(msg: &ChatMessage) -> String {
+    match (msg.is_system(), msg.content.as_ref()) {
+        (true, code) if code == ChatMessage::SYSTEM_HANDOFF.as_bytes() => T::HandoffNotice.text().to_string(),
+        _ => String::from_utf8_lossy(&msg.content).to_string(),
+    }
+}
+
+// Cuộn tới tin được trích dẫn
+fn scroll_to_message(id: u64) {
+    let document = web_sys::window().unwrap().document().unwrap();
+    if let Some(el) = document.get_element_by_id(&format!("tc-msg-{}", id)) {
+        el.scroll_into_view();
+    }
+}
+
+fn guest_key(shop_id: &str) -> String {
+    format!("turbochat_guest_{}", shop_id)
+}
+
+fn store_guest_id(shop_id: &str, guest_id: u64) {
+    if let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) {
+        let _ = storage.set_item(&guest_key(shop_id), &guest_id.to_string());
+    }
+}
+
+// Thông tin khách từ TurboChat.identify() - gửi lại mỗi lần kết nối
+fn identity_key(shop_id: &str) -> String {
+    format!("turbochat_identity_{}", shop_id)
+}
+
+fn stored_identity(shop_id: &str) -> Option<Identify> {
+    let storage = web_sys::window()?.local_storage().ok()??;
+    let raw = storage.get_item(&identity_key(shop_id)).ok()??;
+    let value: serde_json::Value = serde_json::from_str(&raw).ok()?;
+    Some(Identify {
+        name: value["name"].as_str().unwrap_or("").to_string(),
+        email: value["email"].as_str().unwrap_or("").to_string(),
+        user_id: value["user_id"].as_str().unwrap_or("").to_string(),
+        user_hash: value["user_hash"].as_str().unwrap_or("").to_string(),
+    })
+}
+
+fn store_identity(shop_id: &str, identity: &Identify) {
+    if let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) {
+        let raw = serde_json::json!({
+            "name": identity.name,
+            "email": identity.email,
+            "user_id": identity.user_id,
+            "user_hash": identity.user_hash,
+        }).to_string();
+        let _ = storage.set_item(&identity_key(shop_id), &raw);
+    }
+}
+
+/// data-user-id / data-user-email / data-user-name / data-user-hash trên #turbochat-root (loader chép từ thẻ script)
+pub fn identity_from_element(root: &web_sys::Element) -> Option<Identify> {
+    let attr = |name: &str| root.get_attribute(name).map(|v| v.trim().to_string()).unwrap_or_default();
+    let identity = Identify {
+        name: attr("data-user-name"),
+        email: attr("data-user-email"),
+        user_id: attr("data-user-id"),
+        user_hash: attr("data-user-hash"),
+    };
+    (!identity.user_id.is_empty() || !identity.email.is_empty() || !identity.name.is_empty()).then_some(identity)
+}
+
+// SPA chuyển trang bằng pushState không phát sự kiện → so URL định kỳ
+const NAV_POLL_MS: i32 = 1_000;
+// Tab hiện lại: không nhận pong trong khoảng này → coi kết nối đã chết, nối lại
+const PING_TIMEOUT_MS: i32 = 5_000;
+
+// Trang khách đang xem - gửi khi kết nối và khi URL đổi
+fn visitor_context() -> Option<VisitorContext> {
+    let window = web_sys::window()?;
+    let dimension = |v: Result<JsValue, JsValue>| v.ok().and_then(|v| v.as_f64()).unwrap_or(0.0) as u32;
+    Some(VisitorContext {
+        page_url: window.location().href().ok()?,
+        referrer: window.document().map(|d| d.referrer()).unwrap_or_default(),
+        user_agent: window.navigator().user_agent().unwrap_or_default(),
+        viewport_width: dimension(window.inner_width()),
+        viewport_height: dimension(window.inner_height()),
+        updated_at_us: 0,
+    })
+}
+
+// "1:05"
+fn format_duration(secs: u32) -> String {
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+// File đính kèm trong bong bóng tin: ảnh, tin thoại (trình phát), file khác là link tải
+fn attachments_view(attachments: Vec<Attachment>) -> impl IntoView {
+    (!attachments.is_empty()).then(|| view! {
+        <div class="turbochat-attachments">
+            {attachments.into_iter().map(|a| {
+                if a.is_audio() {
+                    view! { <audio class="turbochat-audio" controls preload="metadata" src=a.url></audio> }.into_any()
+                } else if a.is_image() {
+                    let src = if a.thumbnail_url.is_empty() { a.url.clone() } else { a.thumbnail_url.clone() };
+                    view! {
+                        <a href=a.url target="_blank" rel="noopener"><img class="turbochat-image" src=src alt=a.file_name/></a>
+                    }.into_any()
+                } else {
+                    view! { <a class="turbochat-file" href=a.url target="_blank" rel="noopener">{format!("📎 {}", a.file_name)}</a> }.into_any()
+                }
+            }).collect_view()}
+        </div>
+    })
+}
+
+fn sender_label(sender_type: &str) -> &'static str {
+    if sender_type == "guest" { T::SenderYou.text() } else { T::SenderShop.text() }
+}
+
+// Backoff nối lại: 1s, 2s, 4s... tối đa 30s, cộng ngẫu nhiên tới 1s để các khách không nối lại cùng lúc
+fn reconnect_delay_ms(attempt: u32) -> i32 {
+    let base = 1000 * 2i32.pow(attempt.saturating_sub(1).min(5));
+    base.min(30_000) + (js_sys::Math::random() * 1000.0) as i32
+}
+
+// Id lần theo frame qua log server (x-request-id)
+fn new_request_id() -> String {
+    format!("{:016x}", (js_sys::Math::random() * u64::MAX as f64) as u64)
+}
+
+// Gửi frame đã encode qua WS nếu đang kết nối
+fn send_bytes(ws: &WebSocket, bytes: &[u8]) -> bool {
+    if ws.ready_state() != WebSocket::OPEN {
+        return false;
+    }
+    let arr = js_sys::Uint8Array::from(bytes);
+    ws.send_with_array_buffer(&arr.buffer()).is_ok()
+}
+
 #[component]
-pub fn Widget(shop_id: String) -> impl IntoView {
+pub fn Widget(shop_id: String, api_base: String, theme: Theme, identity: Option<Identify>) -> impl IntoView {
     let (is_open, set_is_open) = signal(false);
-    let (messages, set_messages) = signal(Vec::<(String, String, u64)>::new()); // (sender, text, id)
+    let (messages, set_messages) = signal(Vec::<WidgetMessage>::new());
     let (input, set_input) = signal(String::new());
     let (send_trigger, set_send_trigger) = signal(0u64);
-    let (connection_status, set_connection_status) = signal("🔴 Đang kết nối...".to_string());
+    let input_ref = NodeRef::<Input>::new();
+    let launcher_ref = NodeRef::<Button>::new();
+    let (conn_state, set_conn_state) = signal(ConnState::Connecting);
+    // Đồng hồ cho dòng "kết nối lại sau Ns" (chỉ chạy khi đang chờ nối lại)
+    let (clock, set_clock) = signal(js_sys::Date::now());
+    // Tin gửi lúc chưa kết nối - gửi khi kết nối lại
+    let outbox = StoredValue::new(Vec::<ChatMessage>::new());
+    // Tin nhắn thoại: bản ghi đang chạy + lúc bắt đầu (Date.now()), lỗi / trạng thái tải lên
+    let recorder = StoredValue::new(None::<SendRecorder>);
+    let (recording_since, set_recording_since) = signal(None::<f64>);
+    let (voice_status, set_voice_status) = signal(None::<String>);
     let ws_ref = StoredValue::new(None::<SendWs>);
+    // Token phiên server cấp - gửi lại khi nối lại để nhận bù tin bị lỡ
+    let session_token = StoredValue::new(None::<String>);
+    let (reconnect_tick, set_reconnect_tick) = signal(0u32);
+    let reconnect_attempt = StoredValue::new(0u32);
+    // Hẹn giờ nối lại đang chờ (tab hiện lại thì nối ngay, không chờ backoff)
+    let retry_timer = StoredValue::new(None::<i32>);
+    // Nonce của ping đang chờ pong
+    let pending_ping = StoredValue::new(None::<u64>);
+    let tabs = StoredValue::new(None::<SendTabs>);
+    // Sinh id phía client - node ngẫu nhiên cho mỗi tab
+    let ids = StoredValue::new(MessageIdGenerator::new((js_sys::Math::random() * 1024.0) as u16));
+    // Trạng thái shop (giờ làm việc) - None = chưa nhận được
+    let (presence, set_presence) = signal(None::<Presence>);
+    // Banner bảo trì hệ thống (ServerNotice)
+    let (server_notice, set_server_notice) = signal(None::<ServerNotice>);
+    // Feature flag của shop (server gửi khi kết nối) - component con lấy qua use_context
+    let shop_flags = RwSignal::new(ShopFlags::default());
+    provide_context(shop_flags);
+    // Tin đang được trả lời
+    let (replying_to, set_replying_to) = signal(None::<ReplyPreview>);
+    // Tin của shop đến khi popup đang đóng
+    let (unread, set_unread) = signal(0u32);
+    // Bong bóng xem trước tin mới nhất bên nút mở (shop chủ động bắt chuyện / trả lời khi đang đóng)
+    let (teaser, set_teaser) = signal(None::<String>);
+    let unread_listeners = StoredValue::new(Vec::<SendFn>::new());
+    let title = StoredValue::new(theme.title.clone());
+    let is_offline = move || presence.with(|p| p.as_ref().is_some_and(|p| !p.online));
+    // Trong giờ nhưng mọi nhân viên đang vắng mặt
+    let is_away = move || presence.with(|p| p.as_ref().is_some_and(|p| p.away));
     
+    // Thuộc tính trên trang thắng bản lưu từ lần trước. Đổi sang tài khoản website khác (máy dùng chung)
+    // → bắt đầu guest mới, không hiện hội thoại của người trước
+    if let Some(identity) = &identity {
+        let previous_user = stored_identity(&shop_id).map(|i| i.user_id).unwrap_or_default();
+        if !previous_user.is_empty() && !identity.user_id.is_empty() && previous_user != identity.user_id {
+            if let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) {
+                let _ = storage.remove_item(&guest_key(&shop_id));
+            }
+        }
+        store_identity(&shop_id, identity);
+    }
+
     // Guest ID - lưu localStorage
     let shop_id_storage = shop_id.clone();
     let guest_id = StoredValue::new({
         let storage = web_sys::window().unwrap().local_storage().unwrap().unwrap();
-        let key = format!("turbochat_guest_{}", shop_id_storage);
-        if let Some(id) = storage.get_item(&key).ok().flatten() {
-            id.parse::<u64>().unwrap_or_else(|_| {
-                let new_id = js_sys::Date::now() as u64;
-                let _ = storage.set_item(&key, &new_id.to_string());
+        match storage.get_item(&guest_key(&shop_id_storage)).ok().flatten().and_then(|id| id.parse::<u64>().ok()) {
+            Some(id) => id,
+            None => {
+                let new_id = ids.with_value(|g| g.next_id()).0;
+                store_guest_id(&shop_id_storage, new_id);
                 new_id
-            })
-        } else {
-            let new_id = js_sys::Date::now() as u64;
-            let _ = storage.set_item(&key, &new_id.to_string());
-            new_id
+            }
         }
     });
 
     let guest_id_val = guest_id.get_value();
+    // Tắt âm báo / thông báo (theo guest)
+    let (muted, set_muted) = signal(notify::is_muted(&shop_id, guest_id_val));
+    // Sáng/tối: lựa chọn của khách > cài đặt shop (data-theme) > hệ điều hành
+    let color_mode = ThemeMode::new("turbochat_color_scheme", theme.color_scheme);
 
     // ============================================================
     // THÊM MỚI: Load tin nhắn cũ từ Database khi mở widget
     // ============================================================
-    let shop_id_sync = shop_id.clone();
-    Effect::new(move |_| {
-        let shop = shop_id_sync.clone();
+    let shop_id_sync = StoredValue::new(shop_id.clone());
+    let sync_url = StoredValue::new(format!("{}/sync", api_base));
+    // Cũng gọi lại khi nối lại mà server không phát bù được (token hết hạn / lỡ quá nhiều tin).
+    // after ≠ 0 → chỉ tải tin mới hơn (tab hiện lại)
+    let load_history = move |after: u64| {
+        let shop = shop_id_sync.get_value();
+        let sync_url = sync_url.get_value();
         let gid = guest_id_val;
         spawn_local(async move {
-            let req = SyncRequest {
-                shop_id: shop,
-                guest_id: gid,
-                after_message_id: 0,
-                limit: 50,
-            };
-            
-            match Request::post("http://localhost:8080/sync")
-                .header("Content-Type", "application/octet-stream")
-                .body(req.encode_to_vec())
-                .unwrap()
-                .send()
-                .await 
-            {
-                Ok(resp) => {
-                    if let Ok(bytes) = resp.binary().await {
-                        if let Ok(sync_resp) = SyncResponse::decode(&bytes[..]) {
-                            leptos::logging::log!("📥 Loaded {} old messages", sync_resp.messages.len());
-                            for msg in sync_resp.messages {
-                                let id = msg.message_id;
-                                let sender = msg.sender_type.clone();
-                                let text = String::from_utf8_lossy(&msg.content).to_string();
-                                set_messages.update(|m| {
-                                    if !m.iter().any(|(_, _, mid)| *mid == id) {
-                                        m.push((sender, text, id));
-                                    }
-                                });
-                            }
-                            // Sort theo message_id
-                            set_messages.update(|m| m.sort_by_key(|(_, _, id)| *id));
-                        }
+            // Hội thoại dài → đi tiếp theo page_state tới hết
+            let mut page_state = String::new();
+            loop {
+                let req = SyncRequest {
+                    shop_id: shop.clone(),
+                    guest_id: gid,
+                    after_message_id: after,
+                    limit: 200,
+                    page_state: std::mem::take(&mut page_state),
+                    ..Default::default()
+                };
+                
+                let sync_resp = match Request::post(&sync_url)
+                    .header("Content-Type", "application/octet-stream")
+                    .body(req.encode_to_vec())
+                    .unwrap()
+                    .send()
+                    .await 
+                {
+                    Ok(resp) => match resp.binary().await.ok().and_then(|b| SyncResponse::decode(&b[..]).ok()) {
+                        Some(sync_resp) => sync_resp,
+                        None => break,
+                    },
+                    Err(e) => {
+                        leptos::logging::log!("❌ Sync error: {:?}", e);
+                        break;
                     }
+                };
+                leptos::logging::log!("📥 Loaded {} old messages", sync_resp.messages.len());
+                for msg in sync_resp.messages {
+                    let wm = WidgetMessage::from_proto(&msg);
+                    set_messages.update(|m| {
+                        if !m.iter().any(|x| x.id == wm.id) {
+                            m.push(wm);
+                        }
+                    });
                 }
-                Err(e) => {
-                    leptos::logging::log!("❌ Sync error: {:?}", e);
+                // Sort theo message_id
+                set_messages.update(|m| m.sort_by_key(|x| x.id));
+                if !sync_resp.has_more || sync_resp.page_state.is_empty() {
+                    break;
                 }
+                page_state = sync_resp.page_state;
             }
         });
-    });
+    };
+    Effect::new(move |_| load_history(0));
 
     // ============================================================
-    // WebSocket connection
+    // Xử lý frame (từ WS của tab này hoặc do tab leader phát lại)
     // ============================================================
-    let shop_id_ws = shop_id.clone();
-    Effect::new(move |_| {
-        let url = format!("ws://localhost:8080/ws?shop_id={}&guest_id={}", shop_id_ws, guest_id_val);
+    let my_guest_id = guest_id_val;
+    // Guest gốc khi đã gộp đa thiết bị (server cho kết nối chạy dưới id này) - tin của mình mang id đó
+    let conversation_guest = StoredValue::new(guest_id_val);
+    let handle_frame = move |bytes: &[u8]| {
+        let Ok(envelope) = WsEnvelope::decode(bytes) else { return };
+        match envelope.frame {
+            Some(ws_envelope::Frame::Presence(p)) => set_presence.set(Some(p)),
+            Some(ws_envelope::Frame::Flags(flags)) => shop_flags.set(flags),
+            Some(ws_envelope::Frame::Session(session)) => {
+                if envelope.guest_id != 0 && envelope.guest_id != conversation_guest.get_value() {
+                    conversation_guest.set_value(envelope.guest_id);
+                    store_guest_id(&shop_id_sync.get_value(), envelope.guest_id);
+                }
+                // Đã có token mà server không nối lại được → có thể thiếu tin, tải lại lịch sử
+                if !session.resumed && session_token.get_value().is_some() {
+                    load_history(0);
+                }
+                session_token.set_value(Some(session.token));
+            }
+            Some(ws_envelope::Frame::Message(msg)) => {
+                let wm = WidgetMessage::from_proto(&msg);
+                let own = msg.sender_type == "guest" && (msg.guest_id == my_guest_id || msg.guest_id == conversation_guest.get_value());
+                // Dòng hệ thống (bot chuyển cho nhân viên) không tính chưa đọc / không báo
+                if msg.sender_type != "guest" && !msg.is_system() {
+                    let hidden = web_sys::window().unwrap().document().unwrap().hidden();
+                    if !is_open.get_untracked() {
+                        set_unread.update(|n| *n += 1);
+                        if !wm.text.is_empty() {
+                            set_teaser.set(Some(wm.text.clone()));
+                        }
+                    }
+                    // Chỉ tab leader báo (nhiều tab không kêu nhiều lần)
+                    let is_leader = tabs.get_value().is_none_or(|t| t.0.is_leader());
+                    if (!is_open.get_untracked() || hidden) && is_leader && !muted.get_untracked() {
+                        notify::play_ping();
+                        notify::show_notification(&title.get_value(), &wm.text, move || set_is_open.set(true));
+                    }
+                }
+                set_messages.update(|m| {
+                    if m.iter().any(|x| x.id == wm.id) {
+                        return;
+                    }
+                    // Tin của chính mình (đã thêm optimistic) → nhận id thật từ server
+                    if own {
+                        let same_files = |x: &WidgetMessage| x.attachments.iter().map(|a| &a.key).eq(wm.attachments.iter().map(|a| &a.key));
+                        if let Some(local) = m.iter_mut().find(|x| x.pending && x.text == wm.text && same_files(x)) {
+                            local.id = wm.id;
+                            local.timestamp_us = wm.timestamp_us;
+                            local.pending = false;
+                            local.notice = None;
+                            return;
+                        }
+                    }
+                    m.push(wm);
+                });
+            }
+            Some(ws_envelope::Frame::QuotaExceeded(exceeded)) => {
+                let notice = if exceeded.queued { T::QuotaQueued.text() } else { T::QuotaRejected.text() };
+                set_messages.update(|m| {
+                    if let Some(x) = m.iter_mut().find(|x| x.pending && x.id == exceeded.message_id) {
+                        x.notice = Some(notice);
+                    }
+                });
+            }
+            Some(ws_envelope::Frame::ServerNotice(notice)) => {
+                if notice.rejected_message_id != 0 {
+                    set_messages.update(|m| {
+                        if let Some(x) = m.iter_mut().find(|x| x.pending && x.id == notice.rejected_message_id) {
+                            x.notice = Some(T::MaintenanceRejected.text());
+                        }
+                    });
+                }
+                set_server_notice.set(Some(notice).filter(|n| n.active));
+            }
+            // Vừa được gộp vào guest gốc (đăng nhập cùng tài khoản trên thiết bị khác) → nối lại để nhận hội thoại chung
+            Some(ws_envelope::Frame::GuestMerged(merged)) if merged.from_guest_id == conversation_guest.get_value() => {
+                conversation_guest.set_value(merged.into_guest_id);
+                store_guest_id(&shop_id_sync.get_value(), merged.into_guest_id);
+                if let Some(ws) = ws_ref.get_value() {
+                    let _ = ws.0.close();
+                }
+            }
+            Some(ws_envelope::Frame::Pong(pong)) if pending_ping.get_value() == Some(pong.nonce) => pending_ping.set_value(None),
+            Some(ws_envelope::Frame::Edit(edit)) => {
+                let text = String::from_utf8_lossy(&edit.content).to_string();
+                set_messages.update(|m| {
+                    if let Some(x) = m.iter_mut().find(|x| x.id == edit.message_id) {
+                        x.text = text;
+                        x.edited = true;
+                    }
+                });
+            }
+            Some(ws_envelope::Frame::Delete(delete)) => {
+                set_messages.update(|m| {
+                    if let Some(x) = m.iter_mut().find(|x| x.id == delete.message_id) {
+                        x.text.clear();
+                        x.deleted = true;
+                    }
+                });
+            }
+            _ => {}
+        }
+    };
+
+    // Trạng thái kết nối - leader báo cho các tab khác
+    let set_status = move |state: ConnState| {
+        set_conn_state.set(state);
+        if let Some(tabs) = tabs.get_value() {
+            tabs.0.broadcast_status(&state.encode());
+        }
+    };
+
+    // ============================================================
+    // WebSocket connection (chỉ tab leader)
+    // ============================================================
+    let shop_id_ws = StoredValue::new(shop_id.clone());
+    // http(s)://host → ws(s)://host
+    let ws_base = StoredValue::new(api_base.replacen("http", "ws", 1));
+    let connect = move || {
+        let mut url = format!("{}/ws?shop_id={}&guest_id={}&v={}", ws_base.get_value(), shop_id_ws.get_value(), guest_id_val, PROTOCOL_VERSION);
+        // Nối lại → server phát bù các tin sau tin cuối đã nhận
+        if let Some(token) = session_token.get_value() {
+            let last_id = messages.with_untracked(|m| m.iter().filter(|x| !x.pending).map(|x| x.id).max().unwrap_or(0));
+            url.push_str(&format!("&resume={}&last_id={}", token, last_id));
+        }
         let ws = match WebSocket::new(&url) {
             Ok(w) => w,
             Err(_) => return,
@@ -102,14 +492,23 @@ pub fn Widget(shop_id: String) -> impl IntoView {
         // On open
         {
             let on_open = Closure::wrap(Box::new(move |_: JsValue| {
-                set_connection_status.set("🟢 Đã kết nối".to_string());
+                set_status(ConnState::Connected);
+                reconnect_attempt.set_value(0);
+                let Some(ws) = ws_ref.get_value() else { return };
+                if let Some(identity) = stored_identity(&shop_id_ws.get_value()) {
+                    let envelope = WsEnvelope::from_client(ws_envelope::Frame::Identify(identity));
+                    send_bytes(&ws.0, &envelope.encode_to_vec());
+                }
+                if let Some(context) = visitor_context() {
+                    let envelope = WsEnvelope::from_client(ws_envelope::Frame::VisitorContext(context));
+                    send_bytes(&ws.0, &envelope.encode_to_vec());
+                }
             }) as Box<dyn FnMut(JsValue)>);
             ws.set_onopen(Some(on_open.as_ref().unchecked_ref()));
             on_open.forget();
         }
         
-        // On message - SỬA: Bỏ qua tin do chính mình gửi
-        let my_guest_id = guest_id_val;
+        // On message
         let on_message = Closure::wrap(Box::new(move |event: MessageEvent| {
             if let Ok(blob) = event.data().dyn_into::<web_sys::Blob>() {
                 let fr = web_sys::FileReader::new().unwrap();
@@ -118,21 +517,10 @@ pub fn Widget(shop_id: String) -> impl IntoView {
                 let onload = Closure::wrap(Box::new(move |_: web_sys::ProgressEvent| {
                     if let Ok(ab) = fr_clone.result().unwrap().dyn_into::<js_sys::ArrayBuffer>() {
                         let bytes = js_sys::Uint8Array::new(&ab).to_vec();
-                        if let Ok(msg) = ChatMessage::decode(&bytes[..]) {
-                            // ⚠️ QUAN TRỌNG: Bỏ qua tin do chính mình gửi (đã thêm optimistic)
-                            if msg.sender_type == "guest" && msg.guest_id == my_guest_id {
-                                return;
-                            }
-                            
-                            let id = msg.message_id;
-                            let sender = msg.sender_type.clone();
-                            let text = String::from_utf8_lossy(&msg.content).to_string();
-                            set_messages.update(|m| {
-                                if !m.iter().any(|(_, _, mid)| *mid == id) {
-                                    m.push((sender, text, id));
-                                }
-                            });
+                        if let Some(tabs) = tabs.get_value() {
+                            tabs.0.broadcast_frame(&bytes);
                         }
+                        handle_frame(&bytes);
                     }
                 }) as Box<dyn FnMut(web_sys::ProgressEvent)>);
                 
@@ -147,95 +535,742 @@ pub fn Widget(shop_id: String) -> impl IntoView {
         
         // On close
         {
-            let on_close = Closure::wrap(Box::new(move |_: JsValue| {
-                set_connection_status.set("🔴 Mất kết nối".to_string());
-            }) as Box<dyn FnMut(JsValue)>);
+            let on_close = Closure::wrap(Box::new(move |event: web_sys::CloseEvent| {
+                // 4003 = guest bị shop chặn
+                if event.code() == 4003 {
+                    set_status(ConnState::Blocked);
+                    return;
+                }
+                // Server không còn hỗ trợ bản widget này (trang đang giữ bản cũ trong cache)
+                if event.code() == CLOSE_UNSUPPORTED_PROTOCOL {
+                    set_status(ConnState::Unsupported);
+                    return;
+                }
+                // Mất mạng → chờ sự kiện online thay vì thử theo backoff
+                if !connection::browser_online() {
+                    set_status(ConnState::Offline);
+                    return;
+                }
+                // Máy chủ đang deploy → nối lại ngay (sang máy chủ khác), không tính là lỗi
+                if event.code() == CLOSE_SERVER_DRAINING {
+                    reconnect_attempt.set_value(0);
+                }
+                let attempt = reconnect_attempt.get_value() + 1;
+                reconnect_attempt.set_value(attempt);
+                // Quá nhiều kết nối cùng IP / shop → vẫn thử lại theo backoff, chỗ có thể trống ra
+                let reason = match event.code() {
+                    CLOSE_SERVER_DRAINING => CloseReason::Draining,
+                    CLOSE_TOO_MANY_CONNECTIONS => CloseReason::Busy,
+                    _ => CloseReason::Lost,
+                };
+                let delay = reconnect_delay_ms(attempt);
+                set_status(ConnState::Reconnecting { retry_at: js_sys::Date::now() + delay as f64, reason });
+                let retry = Closure::once(Box::new(move || {
+                    retry_timer.set_value(None);
+                    set_reconnect_tick.update(|t| *t += 1);
+                }) as Box<dyn FnOnce()>);
+                let handle = web_sys::window().unwrap().set_timeout_with_callback_and_timeout_and_arguments_0(
+                    retry.as_ref().unchecked_ref(), delay);
+                retry.forget();
+                retry_timer.set_value(handle.ok());
+            }) as Box<dyn FnMut(web_sys::CloseEvent)>);
             ws.set_onclose(Some(on_close.as_ref().unchecked_ref()));
             on_close.forget();
         }
         
         ws_ref.set_value(Some(SendWs(ws)));
+    };
+
+    // Hẹn giờ nối lại chạy xong; tab đã mất quyền leader trong lúc chờ thì thôi
+    Effect::new(move |prev: Option<()>| {
+        reconnect_tick.track();
+        if prev.is_some() && tabs.get_value().is_none_or(|t| t.0.is_leader()) {
+            connect();
+        }
+    });
+
+    // Nối lại ngay (nút "Thử lại ngay", có mạng trở lại, tab hiện lại) - bỏ hẹn giờ backoff đang chờ
+    let retry_now = move || {
+        if tabs.get_value().is_some_and(|t| !t.0.is_leader()) || !conn_state.get_untracked().can_retry() {
+            return;
+        }
+        if let Some(handle) = retry_timer.get_value() {
+            web_sys::window().unwrap().clear_timeout_with_handle(handle);
+            retry_timer.set_value(None);
+        }
+        reconnect_attempt.set_value(0);
+        set_status(ConnState::Connecting);
+        connect();
+    };
+    // Follower không giữ WS → nhờ leader
+    let retry = move || match tabs.get_value() {
+        Some(tabs) if !tabs.0.is_leader() => tabs.0.request_retry(),
+        _ => retry_now(),
+    };
+    {
+        let window = web_sys::window().unwrap();
+        let on_online = Closure::wrap(Box::new(retry_now) as Box<dyn FnMut()>);
+        let _ = window.add_event_listener_with_callback("online", on_online.as_ref().unchecked_ref());
+        on_online.forget();
+        // Đếm ngược "kết nối lại sau Ns"
+        let tick = Closure::wrap(Box::new(move || {
+            if matches!(conn_state.get_untracked(), ConnState::Reconnecting { .. }) || recording_since.get_untracked().is_some() {
+                set_clock.set(js_sys::Date::now());
+            }
+        }) as Box<dyn FnMut()>);
+        let _ = window.set_interval_with_callback_and_timeout_and_arguments_0(tick.as_ref().unchecked_ref(), 1000);
+        tick.forget();
+    }
+
+    // Mất quyền leader → đóng WS, nhận frame qua tab leader mới
+    let disconnect = move || {
+        if let Some(ws) = ws_ref.get_value() {
+            ws.0.set_onclose(None);
+            let _ = ws.0.close();
+        }
+        ws_ref.set_value(None);
+    };
+
+    // ============================================================
+    // Tab hiện lại / trang khôi phục từ bfcache: trình duyệt có thể đã treo tab lâu (máy ngủ, đổi mạng)
+    // → tải bù tin sau tin cuối đã có, leader ping kiểm tra WS còn sống
+    // ============================================================
+    let check_connection = move || {
+        if tabs.get_value().is_some_and(|t| !t.0.is_leader()) {
+            return;
+        }
+        // Đang chờ backoff / mất mạng → nối ngay
+        if conn_state.get_untracked().can_retry() {
+            retry_now();
+            return;
+        }
+        let Some(ws) = ws_ref.get_value() else { return };
+        if ws.0.ready_state() != WebSocket::OPEN || pending_ping.get_value().is_some() {
+            return;
+        }
+        let nonce = (js_sys::Math::random() * u64::MAX as f64) as u64;
+        let envelope = WsEnvelope::from_client(ws_envelope::Frame::Ping(Ping { nonce }));
+        if !send_bytes(&ws.0, &envelope.encode_to_vec()) {
+            return;
+        }
+        pending_ping.set_value(Some(nonce));
+        let check = Closure::once(Box::new(move || {
+            if pending_ping.get_value() != Some(nonce) {
+                return;
+            }
+            pending_ping.set_value(None);
+            leptos::logging::log!("⚠️ No pong from server, reconnecting");
+            disconnect();
+            reconnect_attempt.set_value(0);
+            set_status(ConnState::Reconnecting { retry_at: js_sys::Date::now(), reason: CloseReason::Lost });
+            connect();
+        }) as Box<dyn FnOnce()>);
+        let _ = web_sys::window().unwrap().set_timeout_with_callback_and_timeout_and_arguments_0(check.as_ref().unchecked_ref(), PING_TIMEOUT_MS);
+        check.forget();
+    };
+    let catch_up = move || {
+        let after = messages.with_untracked(|m| m.iter().filter(|x| !x.pending).map(|x| x.id).max().unwrap_or(0));
+        load_history(after);
+        check_connection();
+    };
+    {
+        let window = web_sys::window().unwrap();
+        let on_visible = Closure::wrap(Box::new(move || {
+            if !web_sys::window().unwrap().document().unwrap().hidden() {
+                catch_up();
+            }
+        }) as Box<dyn FnMut()>);
+        let _ = window.document().unwrap().add_event_listener_with_callback("visibilitychange", on_visible.as_ref().unchecked_ref());
+        on_visible.forget();
+        // Lần tải đầu cũng phát pageshow - chỉ xử lý khi khôi phục từ bfcache
+        let on_pageshow = Closure::wrap(Box::new(move |event: web_sys::PageTransitionEvent| {
+            if event.persisted() {
+                catch_up();
+            }
+        }) as Box<dyn FnMut(web_sys::PageTransitionEvent)>);
+        let _ = window.add_event_listener_with_callback("pageshow", on_pageshow.as_ref().unchecked_ref());
+        on_pageshow.forget();
+    }
+
+    // ============================================================
+    // Multi-tab: bầu 1 tab leader giữ WS cho cả shop/guest
+    // ============================================================
+    let on_tab_event = move |event: TabEvent| match event {
+        TabEvent::BecameLeader => {
+            leptos::logging::log!("👑 Tab became leader");
+            connect();
+        }
+        TabEvent::LostLeadership => disconnect(),
+        TabEvent::Frame(bytes) => handle_frame(&bytes),
+        TabEvent::Outbound(bytes) => {
+            if let Some(ws) = ws_ref.get_value() {
+                send_bytes(&ws.0, &bytes);
+            }
+        }
+        TabEvent::Status(status) => {
+            if let Some(state) = ConnState::decode(&status) {
+                set_conn_state.set(state);
+            }
+        }
+        TabEvent::FollowerJoined => set_status(conn_state.get_untracked()),
+        TabEvent::RetryRequested => retry_now(),
+    };
+    Effect::new(move |_| {
+        let channel = format!("turbochat_{}_{}", shop_id_ws.get_value(), guest_id_val);
+        match TabSync::start(&channel, on_tab_event) {
+            Some(sync) => tabs.set_value(Some(SendTabs(sync))),
+            // Không có BroadcastChannel → mỗi tab tự kết nối
+            None => connect(),
+        }
+    });
+
+    // Gửi frame: leader gửi thẳng qua WS, follower nhờ leader gửi
+    let send_frame = move |envelope: WsEnvelope| -> bool {
+        let envelope = envelope.with_request_id(new_request_id());
+        if matches!(envelope.frame, Some(ws_envelope::Frame::Message(_))) {
+            leptos::logging::log!("📤 Sending message [request {}]", envelope.request_id);
+        }
+        let bytes = envelope.encode_to_vec();
+        match tabs.get_value() {
+            Some(tabs) if !tabs.0.is_leader() => {
+                tabs.0.send_outbound(&bytes);
+                true
+            }
+            _ => ws_ref.get_value().is_some_and(|ws| send_bytes(&ws.0, &bytes)),
+        }
+    };
+
+    // Chuyển trang (kể cả SPA) → báo trang mới; tab phụ gửi qua tab leader
+    {
+        let mut last_url = visitor_context().map(|c| c.page_url).unwrap_or_default();
+        let watch = Closure::wrap(Box::new(move || {
+            let Some(context) = visitor_context() else { return };
+            if context.page_url == last_url { return; }
+            last_url = context.page_url.clone();
+            send_frame(WsEnvelope::from_client(ws_envelope::Frame::VisitorContext(context)));
+        }) as Box<dyn FnMut()>);
+        let _ = web_sys::window().unwrap()
+            .set_interval_with_callback_and_timeout_and_arguments_0(watch.as_ref().unchecked_ref(), NAV_POLL_MS);
+        watch.forget();
+    }
+    if tabs.get_value().is_some_and(|t| !t.0.is_leader()) {
+        if let Some(context) = visitor_context() {
+            send_frame(WsEnvelope::from_client(ws_envelope::Frame::VisitorContext(context)));
+        }
+    }
+
+    // ============================================================
+    // window.TurboChat
+    // ============================================================
+    Effect::new(move |_| {
+        if is_open.get() {
+            set_unread.set(0);
+            set_teaser.set(None);
+        }
+    });
+    // Popup đang mở → báo server khách đã xem tới tin mới nhất của shop (bộ đếm "đã xem" của chiến dịch)
+    let read_up_to = StoredValue::new(0u64);
+    Effect::new(move |_| {
+        if !is_open.get() { return; }
+        let latest = messages.with(|m| m.iter().filter(|x| x.sender != "guest" && !x.pending).map(|x| x.id).max()).unwrap_or(0);
+        if latest > read_up_to.get_value()
+            && send_frame(WsEnvelope::from_client(ws_envelope::Frame::ReadReceipt(ReadReceipt { up_to_message_id: latest })))
+        {
+            read_up_to.set_value(latest);
+        }
+    });
+    // Đã có hội thoại → lần sau loader tải widget ngay (không chờ bấm) để khách nhận được tin trả lời
+    Effect::new(move |_| {
+        if messages.with(|m| !m.is_empty()) {
+            if let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) {
+                let _ = storage.set_item(&format!("turbochat_active_{}", shop_id_ws.get_value()), "1");
+            }
+        }
+    });
+    // Nhấp nháy tiêu đề tab "(N) Tin nhắn mới" cho tới khi mở popup
+    let flash_title = theme.flash_title;
+    let original_title = StoredValue::new(String::new());
+    let flash_timer = StoredValue::new(None::<i32>);
+    Effect::new(move |_| {
+        let count = unread.get();
+        let window = web_sys::window().unwrap();
+        let document = window.document().unwrap();
+        if count == 0 || !flash_title {
+            if let Some(handle) = flash_timer.get_value() {
+                window.clear_interval_with_handle(handle);
+                flash_timer.set_value(None);
+                document.set_title(&original_title.get_value());
+            }
+            return;
+        }
+        if flash_timer.get_value().is_some() {
+            return;
+        }
+        original_title.set_value(document.title());
+        let mut showing_count = false;
+        let tick = Closure::wrap(Box::new(move || {
+            let document = web_sys::window().unwrap().document().unwrap();
+            showing_count = !showing_count;
+            if showing_count {
+                document.set_title(&T::NewMessagesTitle.fill(&[&unread.get_untracked()]));
+            } else {
+                document.set_title(&original_title.get_value());
+            }
+        }) as Box<dyn FnMut()>);
+        let handle = window
+            .set_interval_with_callback_and_timeout_and_arguments_0(tick.as_ref().unchecked_ref(), 1000)
+            .ok();
+        tick.forget();
+        flash_timer.set_value(handle);
+    });
+    Effect::new(move |_| {
+        let count = JsValue::from(unread.get());
+        unread_listeners.with_value(|listeners| {
+            for cb in listeners {
+                let _ = cb.0.call1(&JsValue::NULL, &count);
+            }
+        });
+    });
+    api::install(Api {
+        open: Box::new(move || set_is_open.set(true)),
+        close: Box::new(move || set_is_open.set(false)),
+        identify: Box::new(move |identity| {
+            store_identity(&shop_id_ws.get_value(), &identity);
+            // Chưa kết nối thì gửi khi kết nối (on_open)
+            send_frame(WsEnvelope::from_client(ws_envelope::Frame::Identify(identity)));
+        }),
+        track_event: Box::new(move |name, properties| {
+            let event = TrackEvent {
+                name,
+                properties,
+                occurred_at_us: js_sys::Date::now() as u64 * 1000,
+            };
+            if !send_frame(WsEnvelope::from_client(ws_envelope::Frame::TrackEvent(event))) {
+                leptos::logging::log!("⚠️ trackEvent dropped: not connected");
+            }
+        }),
+        on_unread_change: Box::new(move |cb| {
+            let _ = cb.call1(&JsValue::NULL, &JsValue::from(unread.get_untracked()));
+            unread_listeners.update_value(|listeners| listeners.push(SendFn(cb)));
+        }),
     });
 
     // ============================================================
     // Effect xử lý gửi tin nhắn
     // ============================================================
-    let shop_id_send = shop_id.clone();
+    let shop_id_send = StoredValue::new(shop_id.clone());
+    // Gửi một tin của khách (ô nhập, nút lựa chọn nhanh, tin thoại) - chưa kết nối thì xếp vào outbox.
+    // false nếu không gửi được (bị chặn / widget quá cũ)
+    let send_message = move |text: String, attachments: Vec<Attachment>| -> bool {
+        if text.trim().is_empty() && attachments.is_empty() { return false; }
+
+        let id: MessageId = ids.with_value(|g| g.next_id());
+        let ts = id.timestamp() * 1000;
+        let content = text.as_bytes();
+        
+        let reply = replying_to.get_untracked();
+        let msg = ChatMessage {
+            shop_id: shop_id_send.get_value(),
+            guest_id: guest_id.get_value(),
+            message_id: id.0,
+            sender_type: "guest".to_string(),
+            content: content.to_vec().into(),
+            timestamp_us: ts,
+            content_crc: crc32c::crc32c(content),
+            reply_to_message_id: reply.as_ref().map(|r| r.message_id),
+            attachments: attachments.clone(),
+            ..Default::default()
+        };
+        
+        let state = conn_state.get_untracked();
+        let queued = !state.is_connected() || !send_frame(WsEnvelope::message(msg.clone()));
+        if queued {
+            if !state.can_queue() {
+                return false;
+            }
+            outbox.update_value(|q| q.push(msg));
+        }
+        
+        // ✅ THÊM MỚI: Optimistic update - hiển thị ngay khi gửi
+        set_messages.update(|m| {
+            m.push(WidgetMessage {
+                id: id.0,
+                sender: "guest".to_string(),
+                text: text.clone(),
+                timestamp_us: ts,
+                edited: false,
+                deleted: false,
+                pending: true,
+                notice: queued.then(|| T::OutboxNotice.text()),
+                reply,
+                quick_replies: Vec::new(),
+                attachments,
+            });
+        });
+        set_replying_to.set(None);
+        true
+    };
+    let send_text = move |text: String| send_message(text, Vec::new());
+    // Kết nối lại → gửi các tin trong outbox theo thứ tự
+    Effect::new(move |_| {
+        if !conn_state.get().is_connected() {
+            return;
+        }
+        let queued = outbox.get_value();
+        for (i, msg) in queued.iter().enumerate() {
+            // Mất kết nối giữa chừng → giữ phần còn lại cho lần kết nối sau
+            if !send_frame(WsEnvelope::message(msg.clone())) {
+                outbox.set_value(queued[i..].to_vec());
+                return;
+            }
+            set_messages.update(|m| {
+                if let Some(x) = m.iter_mut().find(|x| x.pending && x.id == msg.message_id) {
+                    x.notice = None;
+                }
+            });
+        }
+        outbox.set_value(Vec::new());
+    });
     Effect::new(move |_| {
         let trigger = send_trigger.get();
         if trigger == 0 { return; }
-        
-        let text = input.get_untracked();
-        if text.trim().is_empty() { return; }
+        if send_text(input.get_untracked()) {
+            set_input.set(String::new());
+        }
+    });
 
-        if let Some(ws) = ws_ref.get_value() {
-            if ws.0.ready_state() == WebSocket::OPEN {
-                let ts = js_sys::Date::now() as u64 * 1000;
-                let content = text.as_bytes();
-                
-                let msg = ChatMessage {
-                    shop_id: shop_id_send.clone(),
-                    guest_id: guest_id.get_value(),
-                    message_id: ts,
-                    sender_type: "guest".to_string(),
-                    content: content.to_vec().into(),
-                    timestamp_us: ts,
-                    content_crc: crc32c::crc32c(content),
-                };
-                
-                // ✅ THÊM MỚI: Optimistic update - hiển thị ngay khi gửi
-                let text_clone = text.clone();
-                set_messages.update(|m| {
-                    m.push(("guest".to_string(), text_clone, ts));
-                });
-                
-                let bytes = msg.encode_to_vec();
-                let arr = js_sys::Uint8Array::from(&bytes[..]);
-                let _ = ws.0.send_with_array_buffer(&arr.buffer());
-                set_input.set(String::new());
+    // ============================================================
+    // Tin nhắn thoại - ghi tối đa voice_limit_secs() giây rồi tự dừng và gửi
+    // ============================================================
+    let api_base_voice = StoredValue::new(api_base.clone());
+    let stop_recording = move |send: bool| {
+        let Some(rec) = recorder.get_value() else { return };
+        recorder.set_value(None);
+        set_recording_since.set(None);
+        if !send {
+            rec.0.cancel();
+            return;
+        }
+        set_voice_status.set(Some(T::VoiceSending.text().to_string()));
+        spawn_local(async move {
+            let Some(blob) = rec.0.finish().await else {
+                set_voice_status.set(Some(T::VoiceEmpty.text().to_string()));
+                return;
+            };
+            match voice::upload(&api_base_voice.get_value(), &shop_id_send.get_value(), guest_id.get_value(), &blob).await {
+                Ok(attachment) => {
+                    set_voice_status.set(None);
+                    send_message(String::new(), vec![attachment]);
+                }
+                Err(e) => set_voice_status.set(Some(format!("⚠️ {}", e))),
             }
+        });
+    };
+    let start_recording = move || {
+        if recorder.get_value().is_some() {
+            return;
         }
-    });
+        set_voice_status.set(None);
+        spawn_local(async move {
+            let rec = match Recorder::start().await {
+                Ok(rec) => rec,
+                Err(e) => {
+                    set_voice_status.set(Some(format!("⚠️ {}", e)));
+                    return;
+                }
+            };
+            let started_at = js_sys::Date::now();
+            recorder.set_value(Some(SendRecorder(Rc::new(rec))));
+            set_recording_since.set(Some(started_at));
+            set_clock.set(started_at);
+            // Hết giờ → tự dừng và gửi (bản ghi đã dừng / bản ghi mới thì thôi)
+            let limit_ms = shop_flags.with_untracked(|f| f.voice_limit_secs()) as i32 * 1000;
+            let auto_stop = Closure::once(Box::new(move || {
+                if recording_since.get_untracked() == Some(started_at) {
+                    stop_recording(true);
+                }
+            }) as Box<dyn FnOnce()>);
+            let _ = web_sys::window().unwrap().set_timeout_with_callback_and_timeout_and_arguments_0(auto_stop.as_ref().unchecked_ref(), limit_ms);
+            auto_stop.forget();
+        });
+    };
+    let recording_label = move || {
+        let since = recording_since.get().unwrap_or(0.0);
+        let elapsed = ((clock.get() - since).max(0.0) / 1000.0) as u32;
+        let limit = shop_flags.with(|f| f.voice_limit_secs());
+        format!("🔴 {} / {}", format_duration(elapsed.min(limit)), format_duration(limit))
+    };
+
+    // Sửa / xoá tin của chính mình
+    let edit_message = move |id: u64, old_text: String| {
+        let window = web_sys::window().unwrap();
+        let Ok(Some(text)) = window.prompt_with_message_and_default(T::EditPrompt.text(), &old_text) else { return };
+        if text.trim().is_empty() || text == old_text { return; }
+        let edit = EditMessage::new(shop_id_ws.get_value(), guest_id.get_value(), id, text.into_bytes().into());
+        send_frame(WsEnvelope::edit(edit));
+    };
+    let delete_message = move |id: u64| {
+        let window = web_sys::window().unwrap();
+        if !window.confirm_with_message(T::DeleteConfirm.text()).unwrap_or(false) { return; }
+        let delete = DeleteMessage {
+            shop_id: shop_id_ws.get_value(),
+            guest_id: guest_id.get_value(),
+            message_id: id,
+            ..Default::default()
+        };
+        send_frame(WsEnvelope::delete(delete));
+    };
 
     // ============================================================
     // UI - Giữ nguyên như gốc
     // ============================================================
+    let toggle_mute = move |_| {
+        let now_muted = !muted.get_untracked();
+        set_muted.set(now_muted);
+        notify::set_muted(&shop_id_ws.get_value(), guest_id_val, now_muted);
+        if !now_muted {
+            notify::unlock_audio();
+            notify::request_permission();
+        }
+    };
+    // Mở popup → con trỏ vào ô nhập; đóng → trả focus về nút mở (người dùng bàn phím / trình đọc màn hình)
+    Effect::new(move |prev: Option<bool>| {
+        let open = is_open.get();
+        if prev.is_some_and(|was| was != open) {
+            let focus = Closure::once(Box::new(move || {
+                if open {
+                    if let Some(el) = input_ref.get_untracked() {
+                        let _ = el.focus();
+                    }
+                } else if let Some(el) = launcher_ref.get_untracked() {
+                    let _ = el.focus();
+                }
+            }) as Box<dyn FnOnce()>);
+            // Chờ Show dựng xong popup
+            let _ = web_sys::window().unwrap().request_animation_frame(focus.as_ref().unchecked_ref());
+            focus.forget();
+        }
+        open
+    });
+    let on_popup_keydown = move |e: web_sys::KeyboardEvent| {
+        // Esc đóng popup (bảng emoji đang mở thì nó tự xử lý trước)
+        if e.key() == "Escape" && !e.default_prevented() {
+            if recording_since.get_untracked().is_some() {
+                stop_recording(false);
+            } else {
+                set_is_open.set(false);
+            }
+        }
+    };
     view! {
-        <div class="turbochat-widget">
-            <button class="turbochat-launcher" on:click=move |_| set_is_open.update(|o| *o = !*o)>
-                "💬"
+        <div class=theme.class() style=theme.style() lang=turbochat_shared::i18n::locale().code() dir=theme.direction.attr() data-theme=move || color_mode.attr()>
+            <button
+                class="turbochat-launcher"
+                node_ref=launcher_ref
+                aria-label=move || match unread.get() {
+                    0 if is_open.get() => T::CloseChat.text().to_string(),
+                    0 => T::OpenChat.text().to_string(),
+                    n => T::OpenChatUnread.fill(&[&n]),
+                }
+                aria-expanded=move || is_open.get().to_string()
+                aria-controls="turbochat-popup"
+                on:click=move |_| {
+                notify::unlock_audio();
+                if !muted.get_untracked() {
+                    notify::request_permission();
+                }
+                set_is_open.update(|o| *o = !*o);
+            }>
+                {match theme.launcher_icon.clone() {
+                    LauncherIcon::Text(icon) => icon.into_any(),
+                    LauncherIcon::Image(src) => view! { <img class="turbochat-launcher-img" src=src alt="Chat"/> }.into_any(),
+                }}
+                {move || (unread.get() > 0).then(|| view! {
+                    <span class="turbochat-badge" aria-hidden="true">{move || badge_label(unread.get())}</span>
+                })}
             </button>
+            {move || teaser.get().filter(|_| !is_open.get()).map(|text| view! {
+                <div class="turbochat-teaser" role="status">
+                    <button class="turbochat-teaser-text" on:click=move |_| set_is_open.set(true)>
+                        {teaser_text(&text)}
+                    </button>
+                    <button class="turbochat-teaser-close" aria-label=T::DismissPreview.text() on:click=move |_| set_teaser.set(None)>"✕"</button>
+                </div>
+            })}
             
             <Show when=move || is_open.get()>
-                <div class="turbochat-popup">
+                <div
+                    class="turbochat-popup"
+                    id="turbochat-popup"
+                    role="dialog"
+                    aria-label=move || title.get_value()
+                    on:keydown=on_popup_keydown
+                >
                     <div class="turbochat-header">
-                        <span>"Chat với chúng tôi"</span>
-                        <button on:click=move |_| set_is_open.set(false)>"✕"</button>
+                        <span>{move || if is_offline() {
+                            T::LeaveMessage.text().to_string()
+                        } else if is_away() {
+                            T::AgentsAway.text().to_string()
+                        } else {
+                            title.get_value()
+                        }}</span>
+                        <span>
+                            <button
+                                title=move || if color_mode.is_dark() { T::LightMode.text() } else { T::DarkMode.text() }
+                                aria-label=move || if color_mode.is_dark() { T::LightMode.text() } else { T::DarkMode.text() }
+                                on:click=move |_| color_mode.toggle()
+                            >
+                                {move || if color_mode.is_dark() { "☀️" } else { "🌙" }}
+                            </button>
+                            <button
+                                title=move || if muted.get() { T::Unmute.text() } else { T::Mute.text() }
+                                aria-label=move || if muted.get() { T::Unmute.text() } else { T::Mute.text() }
+                                aria-pressed=move || muted.get().to_string()
+                                on:click=toggle_mute
+                            >
+                                {move || if muted.get() { "🔕" } else { "🔔" }}
+                            </button>
+                            <button aria-label=T::CloseChat.text() on:click=move |_| set_is_open.set(false)>"✕"</button>
+                        </span>
                     </div>
                     
-                    <div style="padding: 4px 16px; font-size: 12px; color: #666;">
-                        {move || connection_status.get()}
+                    <div class="turbochat-status" role="status">
+                        <span>{move || {
+                            clock.track();
+                            conn_state.get().label(js_sys::Date::now())
+                        }}</span>
+                        {move || conn_state.get().can_retry().then(|| view! {
+                            <button on:click=move |_| retry()>{T::RetryNow.text()}</button>
+                        })}
                     </div>
                     
-                    <div class="turbochat-messages">
+                    {move || server_notice.get().map(|notice| view! {
+                        <div class="turbochat-offline turbochat-maintenance" role="alert">{notice.banner_text()}</div>
+                    })}
+                    <Show when=is_offline>
+                        <div class="turbochat-offline">
+                            {move || offline_notice(presence.get())}
+                        </div>
+                    </Show>
+                    <Show when=is_away>
+                        <div class="turbochat-offline">
+                            {T::AwayNotice.text()}
+                        </div>
+                    </Show>
+                    
+                    // Tin mới được trình đọc màn hình đọc lên (role=log ~ aria-live=polite)
+                    <div class="turbochat-messages" role="log" aria-live="polite" aria-label=T::Messages.text() tabindex="0">
                         <For 
-                            each=move || messages.get() 
-                            key=|(_, _, id)| *id 
-                            children=move |(sender, text, _)| {
-                                let class = if sender == "guest" { 
+                            each=move || with_day_breaks(messages.get())
+                            key=|(new_day, m)| (m.id, m.text.clone(), m.deleted, m.pending, m.notice, *new_day)
+                            children=move |(new_day, m)| {
+                                let ts = m.timestamp_us;
+                                let separator = new_day.then(|| view! {
+                                    <div class="turbochat-day"><span>{format_day_label(ts, now_us())}</span></div>
+                                });
+                                if m.is_system() {
+                                    return view! {
+                                        {separator}
+                                        <div class="turbochat-system" role="status">{m.text}</div>
+                                    }.into_any();
+                                }
+                                let class = if m.sender == "guest" { 
                                     "turbochat-message sent" 
                                 } else { 
                                     "turbochat-message received" 
                                 };
-                                view! { <div class=class>{text}</div> }
+                                if m.deleted {
+                                    return view! {
+                                        {separator}
+                                        <div class=format!("{} deleted", class) id=format!("tc-msg-{}", m.id)>{T::Deleted.text()}</div>
+                                    }.into_any();
+                                }
+                                let own = m.sender == "guest";
+                                let (id, text) = (m.id, m.text.clone());
+                                let preview = m.preview();
+                                let quote = m.reply.clone().map(|r| {
+                                    let quoted_id = r.message_id;
+                                    view! {
+                                        <div
+                                            class="turbochat-quote"
+                                            role="button"
+                                            tabindex="0"
+                                            aria-label=T::ViewQuoted.text()
+                                            on:click=move |_| scroll_to_message(quoted_id)
+                                            on:keydown=move |e: web_sys::KeyboardEvent| if e.key() == "Enter" { scroll_to_message(quoted_id) }
+                                        >
+                                            <b>{sender_label(&r.sender_type)}</b>
+                                            <span dir="auto">{if r.deleted { T::QuoteDeleted.text().to_string() } else { r.snippet }}</span>
+                                        </div>
+                                    }
+                                });
+                                view! {
+                                    {separator}
+                                    <div class=class id=format!("tc-msg-{}", id) role="article" tabindex="0" aria-label=T::MessageFrom.fill(&[&sender_label(&m.sender), &format_clock(ts)])>
+                                        {quote}
+                                        <span dir="auto" inner_html=render_markdown(&m.text)></span>
+                                        {attachments_view(m.attachments.clone())}
+                                        {m.edited.then(|| view! { <span class="turbochat-edited">{T::Edited.text()}</span> })}
+                                        {m.notice.map(|n| view! { <span class="turbochat-notice">{n}</span> })}
+                                        <span class="turbochat-time" title=format_datetime(ts)>{format_clock(ts)}</span>
+                                        {(!m.pending).then(|| view! {
+                                            <span class="turbochat-actions">
+                                                <button title=T::Reply.text() aria-label=T::Reply.text() on:click=move |_| set_replying_to.set(Some(preview.clone()))>"↩️"</button>
+                                                {own.then(|| view! {
+                                                    <button title=T::Edit.text() aria-label=T::Edit.text() on:click=move |_| edit_message(id, text.clone())>"✏️"</button>
+                                                    <button title=T::Delete.text() aria-label=T::Delete.text() on:click=move |_| delete_message(id)>"🗑️"</button>
+                                                })}
+                                            </span>
+                                        })}
+                                    </div>
+                                }.into_any()
                             }
                         />
+                        // Lựa chọn nhanh của tin cuối (khách trả lời rồi thì ẩn)
+                        {move || messages.with(|m| {
+                            m.last().filter(|m| m.sender != "guest" && !m.deleted).map(|m| m.quick_replies.clone())
+                        }).filter(|options| !options.is_empty()).map(|options| view! {
+                            <div class="turbochat-quick-replies" role="group" aria-label=T::QuickReplies.text()>
+                                {options.into_iter().map(|q| {
+                                    let payload = q.payload.clone();
+                                    view! {
+                                        <button on:click=move |_| { send_text(payload.clone()); }>{q.label}</button>
+                                    }
+                                }).collect_view()}
+                            </div>
+                        })}
                     </div>
                     
-                    <div class="turbochat-input">
+                    {move || replying_to.get().map(|r| view! {
+                        <div class="turbochat-reply-bar">
+                            <span>{T::ReplyingTo.text()} <b>{sender_label(&r.sender_type)}</b> ": " <bdi>{r.snippet}</bdi></span>
+                            <button aria-label=T::CancelReply.text() on:click=move |_| set_replying_to.set(None)>"✕"</button>
+                        </div>
+                    })}
+                    
+                    {move || voice_status.get().map(|status| view! {
+                        <div class="turbochat-voice-status" role="status">{status}</div>
+                    })}
+                    <Show when=move || recording_since.get().is_some()>
+                        <div class="turbochat-input turbochat-recording">
+                            <span class="turbochat-recording-time" role="timer">{recording_label}</span>
+                            <button class="turbochat-recording-cancel" title=T::CancelRecording.text() aria-label=T::CancelRecording.text() on:click=move |_| stop_recording(false)>"✕"</button>
+                            <button title=T::SendVoice.text() aria-label=T::SendVoice.text() on:click=move |_| stop_recording(true)>{T::Send.text()}</button>
+                        </div>
+                    </Show>
+                    <div class="turbochat-input" class:turbochat-hidden=move || recording_since.get().is_some()>
+                        <EmojiPicker
+                            input=input_ref
+                            on_input=move |value| set_input.set(value)
+                            storage_key="turbochat_recent_emojis"
+                            button_class="turbochat-emoji-button"
+                        />
                         <input 
                             type="text" 
-                            placeholder="Nhập tin nhắn..."
+                            node_ref=input_ref
+                            dir="auto"
+                            aria-label=T::InputLabel.text()
+                            placeholder=move || if is_offline() { T::InputPlaceholderOffline.text() } else { T::InputPlaceholder.text() }
                             prop:value=move || input.get()
                             on:input=move |e| set_input.set(event_target_value(&e))
                             on:keypress=move |e: web_sys::KeyboardEvent| { 
@@ -244,12 +1279,66 @@ pub fn Widget(shop_id: String) -> impl IntoView {
                                 } 
                             }
                         />
-                        <button on:click=move |_| set_send_trigger.set(js_sys::Date::now() as u64)>
-                            "Gửi"
+                        {move || (shop_flags.with(|f| f.voice_notes) && voice::supported()).then(|| view! {
+                            <button
+                                class="turbochat-mic-button"
+                                title=T::RecordVoice.text()
+                                aria-label=T::RecordVoice.text()
+                                prop:disabled=move || !conn_state.get().can_queue()
+                                on:click=move |_| start_recording()
+                            >
+                                "🎤"
+                            </button>
+                        })}
+                        <button
+                            prop:disabled=move || !conn_state.get().is_connected()
+                            title=move || if conn_state.get().is_connected() { T::Send.text() } else { T::SendQueued.text() }
+                            on:click=move |_| set_send_trigger.set(js_sys::Date::now() as u64)
+                        >
+                            {T::Send.text()}
                         </button>
                     </div>
                 </div>
             </Show>
         </div>
     }
-}
\ No newline at end of file
+}
+
+// Đánh dấu tin mở đầu mỗi ngày (chèn phân cách ngày)
+fn with_day_breaks(messages: Vec<WidgetMessage>) -> Vec<(bool, WidgetMessage)> {
+    let mut prev = None;
+    messages.into_iter().map(|m| {
+        let new_day = is_new_day(prev, m.timestamp_us);
+        prev = Some(m.timestamp_us);
+        (new_day, m)
+    }).collect()
+}
+
+// Bong bóng chỉ hiện đoạn đầu của tin
+fn teaser_text(text: &str) -> String {
+    const MAX_CHARS: usize = 120;
+    match text.char_indices().nth(MAX_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+fn badge_label(count: u32) -> String {
+    if count > 9 { "9+".to_string() } else { count.to_string() }
+}
+
+// Thông báo ngoài giờ: lời nhắn của shop + giờ mở cửa kế tiếp
+fn offline_notice(presence: Option<Presence>) -> String {
+    let Some(p) = presence else { return String::new() };
+    let mut text = if p.offline_message.is_empty() {
+        T::OfflineNotice.text().to_string()
+    } else {
+        p.offline_message
+    };
+    if p.next_open_us > 0 {
+        let date = js_sys::Date::new(&JsValue::from_f64((p.next_open_us / 1000) as f64));
+        let when: String = date.to_locale_string(turbochat_shared::i18n::locale().bcp47(), &JsValue::UNDEFINED).into();
+        text.push_str(&T::ReopensAt.fill(&[&when]));
+    }
+    text
+}
@@ -0,0 +1,19 @@
+// Message từ mạng/DB: decode, encode lại, và các hàm chạy trên nội dung tin (preview, markdown của widget)
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use prost::Message as _;
+use turbochat_shared::{render_markdown, Message, ReplyPreview};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(mut msg) = Message::decode(data) else { return };
+    let encoded = msg.encode_to_vec();
+    // So byte thay vì PartialEq (float NaN)
+    let again = Message::decode(&encoded[..]).expect("re-decode");
+    assert_eq!(again.encode_to_vec(), encoded);
+
+    let _ = msg.verify_content();
+    let _ = ReplyPreview::from_message(&msg);
+    let _ = render_markdown(&String::from_utf8_lossy(&msg.content));
+    msg.redact();
+    assert!(msg.verify_content().is_ok());
+});
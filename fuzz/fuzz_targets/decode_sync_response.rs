@@ -0,0 +1,12 @@
+// SyncResponse client nhận: decode + kiểm CRC không được panic, finalize xong thì CRC phải khớp
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use prost::Message as _;
+use turbochat_shared::SyncResponse;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(mut resp) = SyncResponse::decode(data) else { return };
+    let _ = resp.verify_crc();
+    resp.finalize();
+    assert!(resp.verify_crc().is_ok());
+});
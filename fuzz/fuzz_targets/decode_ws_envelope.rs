@@ -0,0 +1,17 @@
+// WsEnvelope server broadcast: decode, encode lại, lọc theo kết nối
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use prost::Message as _;
+use turbochat_shared::WsEnvelope;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(envelope) = WsEnvelope::decode(data) else { return };
+    let encoded = envelope.encode_to_vec();
+    // So byte thay vì PartialEq (float NaN)
+    let again = WsEnvelope::decode(&encoded[..]).expect("re-decode");
+    assert_eq!(again.encode_to_vec(), encoded);
+
+    let shop_id = envelope.shop_id.clone();
+    let _ = envelope.is_visible_to(&shop_id, None);
+    let _ = envelope.is_visible_to(&shop_id, Some(envelope.guest_id));
+});
@@ -0,0 +1,30 @@
+// Frame WS client gửi lên (websocket::decode_client_frame) - byte đầu chọn kết nối guest/admin
+#![no_main]
+use backend::contract::ws_envelope::Frame;
+use backend::websocket::{decode_client_frame, MAX_CONTENT_BYTES};
+use libfuzzer_sys::fuzz_target;
+
+const GUEST_ID: u64 = 42;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&selector, frame)) = data.split_first() else { return };
+    let guest_id = (selector & 1 == 0).then_some(GUEST_ID);
+
+    match decode_client_frame(frame, "shop", guest_id).map(|(frame, _)| frame) {
+        Some(Frame::Message(msg)) => {
+            assert_eq!(msg.shop_id, "shop");
+            assert!(msg.content.len() <= MAX_CONTENT_BYTES);
+            assert!(msg.verify_content().is_ok());
+            assert_eq!(msg.message_id, 0);
+            match guest_id {
+                Some(gid) => assert!(msg.guest_id == gid && msg.sender_type == "guest"),
+                None => assert!(msg.guest_id != 0 && msg.sender_type == "admin"),
+            }
+        }
+        Some(Frame::Edit(edit)) => assert_eq!((edit.shop_id.as_str(), Some(edit.guest_id)), ("shop", guest_id)),
+        Some(Frame::Delete(delete)) => assert_eq!((delete.shop_id.as_str(), Some(delete.guest_id)), ("shop", guest_id)),
+        Some(Frame::AgentStatus(_)) => assert!(guest_id.is_none()),
+        Some(_) => assert!(guest_id.is_some()),
+        None => {}
+    }
+});
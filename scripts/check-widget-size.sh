@@ -0,0 +1,42 @@
+#!/usr/bin/env bash
+# Kiểm tra kích thước bundle wasm của widget (nhúng vào mọi trang của shop → phải nhỏ).
+#   scripts/check-widget-size.sh            # build release rồi so với ngân sách
+#   WIDGET_WASM=path/to.wasm scripts/...    # đo file có sẵn (vd. output của trunk)
+# Vượt ngân sách → exit 1. Tăng ngân sách có chủ đích thì sửa hai số dưới kèm lý do trong commit.
+set -euo pipefail
+
+# Byte - wasm release chưa qua wasm-opt
+MAX_RAW_BYTES=2500000
+MAX_GZIP_BYTES=600000
+# Feature shared mà widget không được bật (serde/hmac/validation chỉ dùng phía server)
+FORBIDDEN_FEATURES="json crypto validation"
+
+cd "$(dirname "$0")/.."
+TARGET=wasm32-unknown-unknown
+
+# Feature của turbochat-shared thật sự bật khi build widget
+features=$(cargo tree -p turbochat-widget --target "$TARGET" -e features -i turbochat-shared \
+    | sed -n 's/.*turbochat-shared feature "\([a-z_-]*\)".*/\1/p' | sort -u)
+for f in $FORBIDDEN_FEATURES; do
+    if grep -qx "$f" <<< "$features"; then
+        echo "❌ Widget enables turbochat-shared feature \"$f\""
+        exit 1
+    fi
+done
+
+wasm=${WIDGET_WASM:-}
+if [ -z "$wasm" ]; then
+    cargo build --release --target "$TARGET" -p turbochat-widget
+    wasm="${CARGO_TARGET_DIR:-target}/$TARGET/release/turbochat-widget.wasm"
+fi
+
+raw=$(wc -c < "$wasm")
+gz=$(gzip -9 -c "$wasm" | wc -c)
+echo "📦 $wasm: $raw bytes raw (budget $MAX_RAW_BYTES), $gz bytes gzip (budget $MAX_GZIP_BYTES)"
+echo "   shared features: $(echo $features | tr ' ' ',')"
+
+if [ "$raw" -gt "$MAX_RAW_BYTES" ] || [ "$gz" -gt "$MAX_GZIP_BYTES" ]; then
+    echo "❌ Widget bundle over budget"
+    exit 1
+fi
+echo "✅ Widget bundle within budget"
@@ -0,0 +1,83 @@
+// Đường nóng của giao thức: encode + CRC tin nhắn, CRC của SyncResponse, vòng lọc broadcast trên server.
+// cargo bench -p turbochat-shared [-- <lọc theo tên>]
+use bytes::Bytes;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use prost::Message as _;
+use turbochat_shared::{Message, MessageId, SyncResponse, WsEnvelope};
+
+const SHOP_ID: &str = "demo123";
+
+fn message(guest_id: u64, seq: u64, size: usize) -> Message {
+    let id = MessageId::from_parts(MessageId::EPOCH_MS + seq, 1, 0).0;
+    let content = Bytes::from("Xin chào shop, áo còn size L không ạ? ".repeat(size / 40 + 1).into_bytes()[..size].to_vec());
+    Message::new(SHOP_ID.to_string(), guest_id, id, "guest".to_string(), content, id / 1000)
+}
+
+// Message::new (tính CRC nội dung) + encode - mỗi tin client gửi và server broadcast
+fn message_encode_crc(c: &mut Criterion) {
+    let mut group = c.benchmark_group("message_encode_crc");
+    for size in [64usize, 1024, 16 * 1024] {
+        let content = message(1, 1, size).content;
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &content, |b, content| {
+            b.iter(|| {
+                let msg = Message::new(SHOP_ID.to_string(), 1, 1, "guest".to_string(), content.clone(), 1);
+                black_box(msg.encode_to_vec())
+            })
+        });
+    }
+    group.finish();
+}
+
+// compute_crc encode lại từng tin - chạy mỗi lần trả /sync (server) và kiểm tra (client)
+fn sync_response_crc(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sync_response_crc");
+    for count in [100usize, 1000] {
+        let resp = SyncResponse {
+            messages: (0..count as u64).map(|i| message(1, i, 200)).collect(),
+            server_timestamp_us: 1_735_400_000_000_000,
+            has_more: true,
+            page_state: "123456789".to_string(),
+            crc32: 0,
+        };
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &resp, |b, resp| b.iter(|| black_box(resp.compute_crc())));
+    }
+    group.finish();
+}
+
+// Một frame Redis → mọi kết nối trên node tự decode rồi lọc (websocket.rs send_task).
+// Kết nối: 10 shop × (1 admin + N/10 - 1 guest), chỉ kết nối đúng shop/guest nhận.
+fn broadcast_filter(c: &mut Criterion) {
+    let mut group = c.benchmark_group("broadcast_filter");
+    let frame = WsEnvelope::message(message(7, 1, 200)).encode_to_vec();
+    for connections in [100u64, 1000, 10_000] {
+        let per_shop = connections / 10;
+        let conns: Vec<(String, Option<u64>)> = (0..connections)
+            .map(|i| {
+                let shop = if i / per_shop == 0 { SHOP_ID.to_string() } else { format!("shop{}", i / per_shop) };
+                let guest = (i % per_shop != 0).then_some(i % per_shop);
+                (shop, guest)
+            })
+            .collect();
+        group.throughput(Throughput::Elements(connections));
+        group.bench_with_input(BenchmarkId::from_parameter(connections), &conns, |b, conns| {
+            b.iter(|| {
+                let mut delivered = 0;
+                for (shop, guest) in conns {
+                    let Ok(envelope) = WsEnvelope::decode(&frame[..]) else { continue };
+                    if envelope.is_visible_to(shop, *guest) {
+                        delivered += 1;
+                    }
+                }
+                // admin demo123 + guest 7
+                assert_eq!(delivered, 2);
+                delivered
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, message_encode_crc, sync_response_crc, broadcast_filter);
+criterion_main!(benches);
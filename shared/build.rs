@@ -1,6 +1,11 @@
 fn main() {
     let mut config = prost_build::Config::new();
     config.bytes(&["."]); 
+    // Guest lớn hơn hẳn các frame khác → box để WsEnvelope không phình
+    config.boxed(".turbochat.v1.WsEnvelope.frame.guest");
+    // Feature `json`: serde cho mọi message/enum/oneof, field thiếu → giá trị mặc định như proto
+    config.type_attribute(".", "#[cfg_attr(feature = \"json\", derive(serde::Serialize, serde::Deserialize))]");
+    config.message_attribute(".", "#[cfg_attr(feature = \"json\", serde(default))]");
     
     config.compile_protos(&["proto/chat.proto"], &["proto/"]).unwrap();
-}
\ No newline at end of file
+}
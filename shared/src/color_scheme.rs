@@ -0,0 +1,101 @@
+// ============================================================================
+// CHẾ ĐỘ TỐI - dùng chung cho widget và admin (feature `ui`)
+// Mặc định theo prefers-color-scheme của hệ điều hành; người dùng chọn tay thì lưu localStorage.
+// Giao diện chỉ đổi bộ biến CSS theo data-theme="dark" / "light", không có stylesheet thứ hai
+// ============================================================================
+
+use leptos::prelude::*;
+use leptos::wasm_bindgen::{closure::Closure, JsCast};
+
+const DARK_QUERY: &str = "(prefers-color-scheme: dark)";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorScheme {
+    #[default]
+    Auto,
+    Light,
+    Dark,
+}
+
+impl ColorScheme {
+    pub const ALL: [ColorScheme; 3] = [ColorScheme::Auto, ColorScheme::Light, ColorScheme::Dark];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            ColorScheme::Auto => "auto",
+            ColorScheme::Light => "light",
+            ColorScheme::Dark => "dark",
+        }
+    }
+
+    /// "dark" / "Light" / "auto"; giá trị lạ → None
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        Self::ALL.into_iter().find(|s| s.code().eq_ignore_ascii_case(value))
+    }
+}
+
+/// Chế độ màu đang dùng: lựa chọn (Auto/Light/Dark) + kết quả sau khi xét hệ điều hành
+#[derive(Clone, Copy)]
+pub struct ThemeMode {
+    preference: RwSignal<ColorScheme>,
+    dark: Memo<bool>,
+    storage_key: StoredValue<&'static str>,
+}
+
+impl ThemeMode {
+    /// `fallback` = lựa chọn khi người dùng chưa chọn tay (vd. cài đặt của shop cho widget)
+    pub fn new(storage_key: &'static str, fallback: ColorScheme) -> Self {
+        let saved = storage()
+            .and_then(|s| s.get_item(storage_key).ok().flatten())
+            .and_then(|v| ColorScheme::parse(&v));
+        let preference = RwSignal::new(saved.unwrap_or(fallback));
+        let system_dark = RwSignal::new(false);
+
+        // Theo dõi hệ điều hành đổi sáng/tối khi trang đang mở
+        if let Some(query) = web_sys::window().and_then(|w| w.match_media(DARK_QUERY).ok().flatten()) {
+            system_dark.set(query.matches());
+            let watched = query.clone();
+            let on_change = Closure::wrap(Box::new(move || system_dark.set(watched.matches())) as Box<dyn FnMut()>);
+            query.set_onchange(Some(on_change.as_ref().unchecked_ref()));
+            on_change.forget();
+        }
+
+        let dark = Memo::new(move |_| match preference.get() {
+            ColorScheme::Auto => system_dark.get(),
+            ColorScheme::Light => false,
+            ColorScheme::Dark => true,
+        });
+        Self { preference, dark, storage_key: StoredValue::new(storage_key) }
+    }
+
+    pub fn preference(&self) -> ColorScheme {
+        self.preference.get()
+    }
+
+    pub fn is_dark(&self) -> bool {
+        self.dark.get()
+    }
+
+    /// Giá trị cho data-theme
+    pub fn attr(&self) -> &'static str {
+        if self.is_dark() { "dark" } else { "light" }
+    }
+
+    /// Người dùng chọn tay → lưu lại cho lần sau
+    pub fn set(&self, scheme: ColorScheme) {
+        self.preference.set(scheme);
+        if let Some(storage) = storage() {
+            let _ = storage.set_item(self.storage_key.get_value(), scheme.code());
+        }
+    }
+
+    /// Nút chuyển nhanh: đang tối → sáng, đang sáng → tối
+    pub fn toggle(&self) {
+        self.set(if self.dark.get_untracked() { ColorScheme::Light } else { ColorScheme::Dark });
+    }
+}
+
+fn storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok().flatten()
+}
@@ -0,0 +1,23 @@
+// ============================================================================
+// HMAC-SHA256 dạng hex - ký link tải file, chữ ký gửi kèm request
+// ============================================================================
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Chữ ký hex của `data` với `key`
+pub fn sign_hex(key: &[u8], data: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Kiểm chữ ký hex (so sánh hằng thời gian); hex hỏng → false
+pub fn verify_hex(key: &[u8], data: &[u8], signature: &str) -> bool {
+    let Ok(signature) = hex::decode(signature) else { return false };
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.verify_slice(&signature).is_ok()
+}
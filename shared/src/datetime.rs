@@ -0,0 +1,147 @@
+// ============================================================================
+// THỜI GIAN HIỂN THỊ - dùng chung cho widget và admin
+// Trên trình duyệt: giờ địa phương + định dạng theo locale (Intl);
+// ngoài wasm (backend/test): UTC, định dạng số đơn giản
+// ============================================================================
+
+use crate::i18n::Common;
+
+const US_PER_DAY: i64 = 86_400_000_000;
+
+/// Ngày địa phương (số ngày từ 1970-01-01) của timestamp
+pub fn local_day(timestamp_us: u64) -> i64 {
+    (timestamp_us as i64 + offset_us(timestamp_us)).div_euclid(US_PER_DAY)
+}
+
+/// Tin này mở đầu một ngày mới so với tin trước → chèn phân cách ngày
+pub fn is_new_day(prev_us: Option<u64>, timestamp_us: u64) -> bool {
+    prev_us.is_none_or(|prev| local_day(prev) != local_day(timestamp_us))
+}
+
+/// "14:05"
+pub fn format_clock(timestamp_us: u64) -> String {
+    #[cfg(target_arch = "wasm32")]
+    {
+        locale_string(timestamp_us, &[("hour", "2-digit"), ("minute", "2-digit")])
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let minutes = local_minute(timestamp_us);
+        format!("{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+}
+
+/// Phút trong ngày theo giờ địa phương (0..1440)
+pub fn local_minute(timestamp_us: u64) -> u32 {
+    (timestamp_us as i64 + offset_us(timestamp_us)).div_euclid(60_000_000).rem_euclid(24 * 60) as u32
+}
+
+/// "Hôm nay" / "Hôm qua" / "3 tháng 3" (khác năm: "3 tháng 3, 2023")
+pub fn format_day_label(timestamp_us: u64, now_us: u64) -> String {
+    let (day, today) = (local_day(timestamp_us), local_day(now_us));
+    if day == today {
+        return Common::Today.text().to_string();
+    }
+    if day == today - 1 {
+        return Common::Yesterday.text().to_string();
+    }
+    let same_year = civil_from_days(day).0 == civil_from_days(today).0;
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        if same_year {
+            locale_string(timestamp_us, &[("day", "numeric"), ("month", "long")])
+        } else {
+            locale_string(timestamp_us, &[("day", "numeric"), ("month", "long"), ("year", "numeric")])
+        }
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let (year, month, d) = civil_from_days(day);
+        if same_year { Common::DayMonth.fill(&[&d, &month]) } else { Common::DayMonthYear.fill(&[&d, &month, &year]) }
+    }
+}
+
+/// Giờ nếu là hôm nay, ngược lại nhãn ngày (danh sách hội thoại)
+pub fn format_list_time(timestamp_us: u64, now_us: u64) -> String {
+    if local_day(timestamp_us) == local_day(now_us) {
+        format_clock(timestamp_us)
+    } else {
+        format_day_label(timestamp_us, now_us)
+    }
+}
+
+/// "14:05 3/3/2024" - tooltip, hồ sơ khách, ...
+pub fn format_datetime(timestamp_us: u64) -> String {
+    #[cfg(target_arch = "wasm32")]
+    {
+        locale_string(timestamp_us, &[
+            ("hour", "2-digit"), ("minute", "2-digit"),
+            ("day", "numeric"), ("month", "numeric"), ("year", "numeric"),
+        ])
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let (year, month, day) = civil_from_days(local_day(timestamp_us));
+        format!("{} {}/{}/{}", format_clock(timestamp_us), day, month, year)
+    }
+}
+
+/// Thời điểm hiện tại (µs)
+pub fn now_us() -> u64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        js_sys::Date::now() as u64 * 1000
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_micros() as u64)
+            .unwrap_or(0)
+    }
+}
+
+// Lệch múi giờ địa phương so với UTC (µs) tại thời điểm đó
+#[cfg(target_arch = "wasm32")]
+fn js_date(timestamp_us: u64) -> js_sys::Date {
+    let date = js_sys::Date::new_0();
+    date.set_time((timestamp_us / 1000) as f64);
+    date
+}
+
+#[cfg(target_arch = "wasm32")]
+fn offset_us(timestamp_us: u64) -> i64 {
+    let date = js_date(timestamp_us);
+    // getTimezoneOffset = UTC - local (phút)
+    -(date.get_timezone_offset() as i64) * 60_000_000
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn offset_us(_timestamp_us: u64) -> i64 {
+    0
+}
+
+#[cfg(target_arch = "wasm32")]
+fn locale_string(timestamp_us: u64, options: &[(&str, &str)]) -> String {
+    let date = js_date(timestamp_us);
+    let opts = js_sys::Object::new();
+    for (key, value) in options {
+        let _ = js_sys::Reflect::set(&opts, &js_sys::JsString::from(*key), &js_sys::JsString::from(*value));
+    }
+    date.to_locale_string(crate::i18n::locale().bcp47(), &opts).into()
+}
+
+// Số ngày từ 1970-01-01 → (năm, tháng, ngày) - thuật toán của Howard Hinnant
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
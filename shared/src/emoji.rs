@@ -0,0 +1,247 @@
+// ============================================================================
+// EMOJI PICKER - dùng chung cho widget và ô soạn tin của admin (feature `ui`)
+// Nhóm theo chủ đề, tìm theo từ khoá (Việt/Anh), "Gần đây" lưu localStorage.
+// Chèn tại con trỏ của ô nhập (thay phần đang bôi đen), không nối vào cuối
+// ============================================================================
+
+use leptos::html::{Button, Input};
+use leptos::prelude::*;
+
+use crate::i18n::Common;
+
+const STYLE: &str = include_str!("emoji.css");
+const MAX_RECENT: usize = 24;
+const RECENT_TAB: usize = usize::MAX;
+
+// (biểu tượng tab, tên nhóm, [(emoji, từ khoá)])
+type Category = (&'static str, Common, &'static [(&'static str, &'static str)]);
+
+const CATEGORIES: &[Category] = &[
+    ("😀", Common::EmojiSmileys, &[
+        ("😀", "cuoi cười smile grin"), ("😁", "cuoi cười grin"), ("😂", "khoc cuoi cười joy lol"),
+        ("🤣", "lăn cười rofl"), ("😊", "vui blush smile"), ("😍", "yêu yeu love heart eyes"),
+        ("😘", "hôn hon kiss"), ("😉", "nháy mắt wink"), ("😎", "ngầu cool sunglasses"),
+        ("🤔", "suy nghĩ think hmm"), ("😅", "ngại sweat smile"), ("🙂", "mỉm cười slight smile"),
+        ("😐", "bình thường neutral"), ("😴", "ngủ sleep"), ("😢", "buồn buon khóc cry sad"),
+        ("😭", "khóc khoc sob cry"), ("😡", "giận gian angry"), ("😱", "sợ so scream shock"),
+        ("🥰", "thương love"), ("🤗", "ôm om hug"), ("🙏", "cảm ơn cam on xin pray thanks please"),
+        ("😇", "thiên thần angel"), ("🥳", "tiệc party"), ("😬", "ngượng grimace"),
+    ]),
+    ("👍", Common::EmojiGestures, &[
+        ("👍", "ok đồng ý dong y like thumbs up"), ("👎", "không khong dislike thumbs down"),
+        ("👌", "ok"), ("👏", "vỗ tay vo tay clap"), ("🙌", "hoan hô yay raise hands"),
+        ("👋", "chào chao hello wave bye"), ("🤝", "bắt tay bat tay deal handshake"),
+        ("✌️", "hòa bình peace victory"), ("🤞", "may mắn may man luck fingers crossed"),
+        ("💪", "mạnh manh strong muscle"), ("👉", "chỉ chi point right"), ("👀", "nhìn nhin eyes look"),
+    ]),
+    ("❤️", Common::EmojiHearts, &[
+        ("❤️", "tim yêu yeu love heart red"), ("🧡", "tim cam orange heart"), ("💛", "tim vàng yellow heart"),
+        ("💚", "tim xanh green heart"), ("💙", "tim xanh blue heart"), ("💜", "tim tím purple heart"),
+        ("🖤", "tim đen black heart"), ("💔", "tan vỡ broken heart"), ("💕", "yêu two hearts"),
+        ("💯", "trăm tram hundred perfect"), ("✨", "lấp lánh sparkles"), ("🔥", "cháy chay hot fire"),
+    ]),
+    ("🐶", Common::EmojiAnimals, &[
+        ("🐶", "chó cho dog"), ("🐱", "mèo meo cat"), ("🐭", "chuột chuot mouse"), ("🐰", "thỏ tho rabbit"),
+        ("🦊", "cáo cao fox"), ("🐻", "gấu gau bear"), ("🐼", "gấu trúc panda"), ("🐷", "heo lợn pig"),
+        ("🐸", "ếch ech frog"), ("🐵", "khỉ khi monkey"), ("🐔", "gà ga chicken"), ("🐟", "cá ca fish"),
+    ]),
+    ("🍔", Common::EmojiFood, &[
+        ("🍎", "táo tao apple"), ("🍌", "chuối chuoi banana"), ("🍉", "dưa hấu dua watermelon"),
+        ("🍔", "burger"), ("🍕", "pizza"), ("🍜", "phở pho mì mi noodle"), ("🍚", "cơm com rice"),
+        ("🍰", "bánh banh cake"), ("☕", "cà phê ca phe coffee"), ("🧋", "trà sữa tra sua bubble tea"),
+        ("🍺", "bia beer"), ("🎂", "sinh nhật sinh nhat birthday cake"),
+    ]),
+    ("⚽", Common::EmojiActivities, &[
+        ("⚽", "bóng đá bong da football soccer"), ("🏀", "bóng rổ basketball"), ("🎮", "game"),
+        ("🎵", "nhạc nhac music"), ("🎉", "chúc mừng chuc mung party tada"), ("🎁", "quà qua gift"),
+        ("🏆", "cúp cup trophy win"), ("🚗", "xe ô tô car"), ("✈️", "máy bay may bay plane travel"),
+        ("🏖️", "biển bien beach"), ("📚", "sách sach books"), ("💼", "công việc cong viec work"),
+    ]),
+    ("💡", Common::EmojiObjects, &[
+        ("📱", "điện thoại dien thoai phone"), ("💻", "máy tính may tinh laptop"), ("📦", "hàng hang đơn don package order"),
+        ("🛒", "giỏ hàng gio hang cart shop"), ("💳", "thẻ the card pay"), ("💰", "tiền tien money"),
+        ("📧", "email thư thu mail"), ("📞", "gọi goi call"), ("⏰", "giờ gio clock alarm"),
+        ("📅", "lịch lich calendar"), ("💡", "ý tưởng y tuong idea"), ("🔒", "khoá khoa lock"),
+    ]),
+    ("✅", Common::EmojiSymbols, &[
+        ("✅", "xong done check yes"), ("❌", "sai không khong no cross"), ("⚠️", "chú ý chu y warning"),
+        ("❓", "hỏi hoi question"), ("❗", "quan trọng important"), ("⭐", "sao star"),
+        ("🆗", "ok"), ("🆕", "mới moi new"), ("➡️", "tiếp tiep next arrow"), ("🔔", "chuông chuong bell"),
+        ("🕐", "một giờ one clock"), ("♻️", "tái chế recycle"),
+    ]),
+];
+
+// Chèn text tại con trỏ của ô nhập (thay phần đang chọn); trả về (giá trị mới, vị trí con trỏ UTF-16)
+fn insert_at_cursor(input: &web_sys::HtmlInputElement, text: &str) -> (String, u32) {
+    let value: Vec<u16> = input.value().encode_utf16().collect();
+    let len = value.len() as u32;
+    let start = input.selection_start().ok().flatten().unwrap_or(len).min(len) as usize;
+    let end = input.selection_end().ok().flatten().unwrap_or(len).clamp(start as u32, len) as usize;
+    let next = format!(
+        "{}{}{}",
+        String::from_utf16_lossy(&value[..start]),
+        text,
+        String::from_utf16_lossy(&value[end..]),
+    );
+    (next, (start + text.encode_utf16().count()) as u32)
+}
+
+fn storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok().flatten()
+}
+
+// Lưu dạng "😀 👍 ❤️" (emoji không chứa khoảng trắng)
+fn load_recent(key: &str) -> Vec<String> {
+    storage()
+        .and_then(|s| s.get_item(key).ok().flatten())
+        .map(|v| v.split_whitespace().map(str::to_string).take(MAX_RECENT).collect())
+        .unwrap_or_default()
+}
+
+fn save_recent(key: &str, recent: &[String]) {
+    if let Some(storage) = storage() {
+        let _ = storage.set_item(key, &recent.join(" "));
+    }
+}
+
+// Emoji khớp mọi từ trong truy vấn (theo từ khoá hoặc chính emoji)
+fn search(query: &str) -> Vec<&'static str> {
+    let words: Vec<String> = query.split_whitespace().map(|w| w.to_lowercase()).collect();
+    CATEGORIES.iter()
+        .flat_map(|(_, _, emojis)| emojis.iter())
+        .filter(|(emoji, keywords)| words.iter().all(|w| keywords.contains(w.as_str()) || *emoji == w))
+        .map(|(emoji, _)| *emoji)
+        .collect()
+}
+
+#[component]
+pub fn EmojiPicker(
+    /// Ô nhập nhận emoji
+    input: NodeRef<Input>,
+    /// Giá trị mới của ô nhập sau khi chèn (cập nhật signal gắn với prop:value)
+    on_input: impl Fn(String) + 'static + Send + Sync,
+    /// Khoá localStorage lưu emoji dùng gần đây
+    #[prop(into)]
+    storage_key: String,
+    /// Class thêm cho nút mở (khớp toolbar của từng frontend)
+    #[prop(optional, into)]
+    button_class: String,
+) -> impl IntoView {
+    let (open, set_open) = signal(false);
+    let (query, set_query) = signal(String::new());
+    let recent = RwSignal::new(load_recent(&storage_key));
+    // Chưa có emoji gần đây → mở nhóm đầu
+    let (tab, set_tab) = signal(if recent.with_untracked(|r| r.is_empty()) { 0 } else { RECENT_TAB });
+    let storage_key = StoredValue::new(storage_key);
+    let on_input = StoredValue::new(on_input);
+    let button_ref = NodeRef::<Button>::new();
+    let search_ref = NodeRef::<Input>::new();
+
+    // Mở bảng → con trỏ vào ô tìm (bàn phím dùng được ngay)
+    Effect::new(move |_| {
+        if open.get() {
+            if let Some(el) = search_ref.get() {
+                let _ = el.focus();
+            }
+        }
+    });
+    // Esc: đóng bảng, trả focus về nút mở; prevent_default để popup chứa bảng không đóng theo
+    let on_keydown = move |e: web_sys::KeyboardEvent| {
+        if e.key() == "Escape" {
+            e.prevent_default();
+            set_open.set(false);
+            if let Some(el) = button_ref.get_untracked() {
+                let _ = el.focus();
+            }
+        }
+    };
+
+    let pick = move |emoji: String| {
+        let Some(el) = input.get_untracked() else { return };
+        let (value, caret) = insert_at_cursor(&el, &emoji);
+        el.set_value(&value);
+        let _ = el.set_selection_range(caret, caret);
+        let _ = el.focus();
+        on_input.with_value(|f| f(value));
+        recent.update(|r| {
+            r.retain(|e| *e != emoji);
+            r.insert(0, emoji);
+            r.truncate(MAX_RECENT);
+            save_recent(&storage_key.get_value(), r);
+        });
+    };
+
+    // Danh sách đang hiện: kết quả tìm / gần đây / nhóm đang chọn
+    let shown = move || -> Vec<String> {
+        let q = query.get();
+        if !q.trim().is_empty() {
+            return search(&q).into_iter().map(str::to_string).collect();
+        }
+        match tab.get() {
+            RECENT_TAB => recent.get(),
+            i => CATEGORIES.get(i).map(|(_, _, emojis)| emojis.iter().map(|(e, _)| e.to_string()).collect()).unwrap_or_default(),
+        }
+    };
+
+    view! {
+        <style>{STYLE}</style>
+        <span class="tc-emoji">
+            <button
+                class=button_class
+                node_ref=button_ref
+                title=Common::EmojiButton.text()
+                aria-label=Common::EmojiOpen.text()
+                aria-haspopup="dialog"
+                aria-expanded=move || open.get().to_string()
+                on:click=move |_| set_open.update(|o| *o = !*o)
+            >
+                "😊"
+            </button>
+            <Show when=move || open.get()>
+                <div class="tc-emoji-panel" role="dialog" aria-label=Common::EmojiButton.text() on:keydown=on_keydown>
+                    <input
+                        type="text"
+                        class="tc-emoji-search"
+                        node_ref=search_ref
+                        aria-label=Common::EmojiSearchLabel.text()
+                        placeholder=Common::EmojiSearch.text()
+                        prop:value=move || query.get()
+                        on:input=move |e| set_query.set(event_target_value(&e))
+                    />
+                    <div class="tc-emoji-tabs" role="tablist">
+                        <button
+                            title=Common::EmojiRecent.text()
+                            role="tab"
+                            aria-label=Common::EmojiRecent.text()
+                            aria-selected=move || (tab.get() == RECENT_TAB).to_string()
+                            class:active=move || tab.get() == RECENT_TAB
+                            on:click=move |_| { set_query.set(String::new()); set_tab.set(RECENT_TAB); }
+                        >"🕘"</button>
+                        {CATEGORIES.iter().enumerate().map(|(i, (icon, name, _))| view! {
+                            <button
+                                title=name.text()
+                                role="tab"
+                                aria-label=name.text()
+                                aria-selected=move || (tab.get() == i).to_string()
+                                class:active=move || tab.get() == i
+                                on:click=move |_| { set_query.set(String::new()); set_tab.set(i); }
+                            >{*icon}</button>
+                        }).collect_view()}
+                    </div>
+                    <div class="tc-emoji-grid" role="group" aria-label=Common::EmojiList.text()>
+                        {move || {
+                            let emojis = shown();
+                            if emojis.is_empty() {
+                                return view! { <div class="tc-emoji-empty">{Common::EmojiEmpty.text()}</div> }.into_any();
+                            }
+                            emojis.into_iter().map(|emoji| {
+                                let value = emoji.clone();
+                                view! { <button on:click=move |_| pick(value.clone())>{emoji}</button> }
+                            }).collect_view().into_any()
+                        }}
+                    </div>
+                </div>
+            </Show>
+        </span>
+    }
+}
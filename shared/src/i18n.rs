@@ -0,0 +1,219 @@
+// ============================================================================
+// ĐA NGÔN NGỮ (i18n) - bảng chuỗi có kiểu cho widget + admin
+// Mỗi frontend khai bảng bằng string_table!: khoá nào cũng có bản vi (ngôn ngữ gốc), en có thể thiếu.
+// Locale chọn một lần lúc khởi động (data-locale / ngôn ngữ trình duyệt / cài đặt admin) rồi giữ toàn cục.
+// Thiếu bản dịch → đi theo chuỗi dự phòng (en → vi). Tham số trong chuỗi: {0}, {1}... (điền bằng fill)
+// Backend không đặt locale → luôn là vi như trước.
+// ar/he viết từ phải sang trái: giao diện lật theo Locale::direction() (thuộc tính dir), chưa dịch thì dùng bản en
+// ============================================================================
+
+use std::cell::Cell;
+use std::fmt::Display;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Locale {
+    #[default]
+    Vi,
+    En,
+    Ar,
+    He,
+}
+
+/// Hướng viết của giao diện (giá trị thuộc tính dir)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Direction {
+    #[default]
+    Ltr,
+    Rtl,
+}
+
+impl Direction {
+    pub fn attr(self) -> &'static str {
+        match self {
+            Direction::Ltr => "ltr",
+            Direction::Rtl => "rtl",
+        }
+    }
+
+    pub fn is_rtl(self) -> bool {
+        self == Direction::Rtl
+    }
+}
+
+impl Locale {
+    pub const ALL: [Locale; 4] = [Locale::Vi, Locale::En, Locale::Ar, Locale::He];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::Vi => "vi",
+            Locale::En => "en",
+            Locale::Ar => "ar",
+            Locale::He => "he",
+        }
+    }
+
+    /// Thẻ BCP 47 cho Intl (định dạng ngày giờ)
+    pub fn bcp47(self) -> &'static str {
+        match self {
+            Locale::Vi => "vi-VN",
+            Locale::En => "en-US",
+            Locale::Ar => "ar",
+            Locale::He => "he-IL",
+        }
+    }
+
+    /// Tên ngôn ngữ viết bằng chính ngôn ngữ đó (ô chọn ngôn ngữ)
+    pub fn native_name(self) -> &'static str {
+        match self {
+            Locale::Vi => "Tiếng Việt",
+            Locale::En => "English",
+            Locale::Ar => "العربية",
+            Locale::He => "עברית",
+        }
+    }
+
+    pub fn direction(self) -> Direction {
+        match self {
+            Locale::Vi | Locale::En => Direction::Ltr,
+            Locale::Ar | Locale::He => Direction::Rtl,
+        }
+    }
+
+    /// "en", "en-GB", "EN_us" → En; "iw" (mã cũ của tiếng Do Thái) → He; ngôn ngữ chưa hỗ trợ → None
+    pub fn parse(tag: &str) -> Option<Self> {
+        let language = tag.trim().split(['-', '_']).next()?.to_ascii_lowercase();
+        if language == "iw" {
+            return Some(Locale::He);
+        }
+        Self::ALL.into_iter().find(|l| l.code() == language)
+    }
+
+    /// Ngôn ngữ kế tiếp trong chuỗi dự phòng khi thiếu bản dịch
+    pub fn fallback(self) -> Option<Self> {
+        match self {
+            Locale::Vi => None,
+            Locale::En => Some(Locale::Vi),
+            Locale::Ar | Locale::He => Some(Locale::En),
+        }
+    }
+
+    /// Locale hỗ trợ đầu tiên theo thứ tự ưu tiên (data-locale, cài đặt, navigator.languages...); không có → mặc định
+    pub fn negotiate<'a>(preferred: impl IntoIterator<Item = &'a str>) -> Self {
+        preferred.into_iter().find_map(Self::parse).unwrap_or_default()
+    }
+}
+
+thread_local! {
+    static CURRENT: Cell<Locale> = const { Cell::new(Locale::Vi) };
+}
+
+/// Đặt locale cho toàn bộ giao diện (gọi trước khi mount)
+pub fn set_locale(locale: Locale) {
+    CURRENT.with(|c| c.set(locale));
+}
+
+pub fn locale() -> Locale {
+    CURRENT.with(|c| c.get())
+}
+
+/// Bản dịch theo locale hiện tại, đi theo chuỗi dự phòng
+pub fn resolve(lookup: impl Fn(Locale) -> Option<&'static str>) -> &'static str {
+    let mut current = Some(locale());
+    while let Some(l) = current {
+        if let Some(text) = lookup(l) {
+            return text;
+        }
+        current = l.fallback();
+    }
+    // Bản vi luôn có (string_table! bắt buộc) - không tới đây
+    ""
+}
+
+/// Điền tham số: fill("Còn {0}/{1}", &[&3, &10]) → "Còn 3/10"
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = template.to_string();
+    for (i, arg) in args.iter().enumerate() {
+        out = out.replace(&format!("{{{}}}", i), &arg.to_string());
+    }
+    out
+}
+
+/// Khai bảng chuỗi: mỗi khoá một biến thể enum, `vi` bắt buộc, `en`/`ar`/`he` tuỳ chọn (theo thứ tự đó).
+/// ```ignore
+/// string_table! {
+///     pub enum T {
+///         Send { vi: "Gửi", en: "Send" }
+///         Retry { vi: "Thử lại ({0})", en: "Retry ({0})" }
+///     }
+/// }
+/// T::Send.text(); T::Retry.fill(&[&3]);
+/// ```
+#[macro_export]
+macro_rules! string_table {
+    ($(#[$meta:meta])* $vis:vis enum $name:ident { $($key:ident { vi: $vi:literal $(, en: $en:literal)? $(, ar: $ar:literal)? $(, he: $he:literal)? $(,)? })* }) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        $vis enum $name {
+            $($key,)*
+        }
+
+        impl $name {
+            /// Bản dịch đúng locale (None = chưa dịch)
+            pub fn lookup(self, locale: $crate::i18n::Locale) -> Option<&'static str> {
+                match locale {
+                    $crate::i18n::Locale::Vi => Some(match self { $($name::$key => $vi,)* }),
+                    $crate::i18n::Locale::En => match self { $($name::$key => $crate::__i18n_opt!($($en)?),)* },
+                    $crate::i18n::Locale::Ar => match self { $($name::$key => $crate::__i18n_opt!($($ar)?),)* },
+                    $crate::i18n::Locale::He => match self { $($name::$key => $crate::__i18n_opt!($($he)?),)* },
+                }
+            }
+
+            /// Chuỗi theo locale hiện tại
+            pub fn text(self) -> &'static str {
+                $crate::i18n::resolve(|l| self.lookup(l))
+            }
+
+            /// Chuỗi có tham số {0}, {1}...
+            pub fn fill(self, args: &[&dyn ::std::fmt::Display]) -> String {
+                $crate::i18n::fill(self.text(), args)
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __i18n_opt {
+    () => { None };
+    ($text:literal) => { Some($text) };
+}
+
+// Chuỗi của phần dùng chung (ngày, tên khách mặc định, emoji picker, banner bảo trì)
+string_table! {
+    pub enum Common {
+        Today { vi: "Hôm nay", en: "Today" }
+        Yesterday { vi: "Hôm qua", en: "Yesterday" }
+        DayMonth { vi: "{0} tháng {1}", en: "{1}/{0}" }
+        DayMonthYear { vi: "{0} tháng {1}, {2}", en: "{1}/{0}/{2}" }
+        GuestFallback { vi: "Khách #{0}", en: "Guest #{0}" }
+        MoodPositive { vi: "Tích cực", en: "Positive" }
+        MoodNeutral { vi: "Bình thường", en: "Neutral" }
+        MoodNegative { vi: "Tiêu cực", en: "Negative" }
+        MaintenanceUntil { vi: "🛠️ {0} (dự kiến xong: {1})", en: "🛠️ {0} (expected back: {1})" }
+        EmojiButton { vi: "Biểu tượng cảm xúc", en: "Emoji" }
+        EmojiOpen { vi: "Chọn biểu tượng cảm xúc", en: "Choose an emoji" }
+        EmojiSearch { vi: "Tìm emoji...", en: "Search emoji..." }
+        EmojiSearchLabel { vi: "Tìm emoji", en: "Search emoji" }
+        EmojiRecent { vi: "Gần đây", en: "Recent" }
+        EmojiList { vi: "Danh sách emoji", en: "Emoji list" }
+        EmojiEmpty { vi: "Không có emoji", en: "No emoji found" }
+        EmojiSmileys { vi: "Mặt cười", en: "Smileys" }
+        EmojiGestures { vi: "Cử chỉ", en: "Gestures" }
+        EmojiHearts { vi: "Trái tim", en: "Hearts" }
+        EmojiAnimals { vi: "Động vật", en: "Animals" }
+        EmojiFood { vi: "Đồ ăn", en: "Food" }
+        EmojiActivities { vi: "Hoạt động", en: "Activities" }
+        EmojiObjects { vi: "Đồ vật", en: "Objects" }
+        EmojiSymbols { vi: "Ký hiệu", en: "Symbols" }
+    }
+}
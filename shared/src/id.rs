@@ -0,0 +1,125 @@
+// ============================================================================
+// SNOWFLAKE MESSAGE ID
+// 41 bit ms (tính từ 2024-01-01) | 10 bit node | 12 bit sequence
+// Bit cao nhất luôn = 0 → vừa với bigint (i64) của Cassandra
+// ============================================================================
+
+use std::fmt;
+use std::sync::Mutex;
+
+const NODE_BITS: u32 = 10;
+const SEQUENCE_BITS: u32 = 12;
+const TIMESTAMP_BITS: u32 = 41;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct MessageId(pub u64);
+
+impl MessageId {
+    /// 2024-01-01T00:00:00Z (ms)
+    pub const EPOCH_MS: u64 = 1_704_067_200_000;
+    pub const MAX_NODE: u16 = (1 << NODE_BITS) - 1;
+    pub const MAX_SEQUENCE: u16 = (1 << SEQUENCE_BITS) - 1;
+    /// Id cũ (trước Snowflake) là timestamp µs, luôn nhỏ hơn mốc này;
+    /// Snowflake id sinh sau 2024-01-25 luôn lớn hơn
+    pub const LEGACY_MAX: u64 = 1 << 53;
+
+    pub fn from_parts(timestamp_ms: u64, node_id: u16, sequence: u16) -> Self {
+        let elapsed = timestamp_ms.saturating_sub(Self::EPOCH_MS) & ((1 << TIMESTAMP_BITS) - 1);
+        Self(
+            (elapsed << (NODE_BITS + SEQUENCE_BITS))
+                | (((node_id & Self::MAX_NODE) as u64) << SEQUENCE_BITS)
+                | (sequence & Self::MAX_SEQUENCE) as u64,
+        )
+    }
+
+    /// Id nhỏ nhất sinh tại thời điểm này - dùng làm mốc cắt theo thời gian
+    pub fn lower_bound(timestamp_ms: u64) -> Self {
+        Self::from_parts(timestamp_ms, 0, 0)
+    }
+
+    /// Unix timestamp (ms) lúc sinh id
+    pub fn timestamp(self) -> u64 {
+        if self.is_legacy() {
+            return self.0 / 1000;
+        }
+        (self.0 >> (NODE_BITS + SEQUENCE_BITS)) + Self::EPOCH_MS
+    }
+
+    pub fn node_id(self) -> u16 {
+        ((self.0 >> SEQUENCE_BITS) as u16) & Self::MAX_NODE
+    }
+
+    pub fn sequence(self) -> u16 {
+        (self.0 as u16) & Self::MAX_SEQUENCE
+    }
+
+    pub fn is_legacy(self) -> bool {
+        self.0 < Self::LEGACY_MAX
+    }
+}
+
+impl From<u64> for MessageId {
+    fn from(id: u64) -> Self {
+        Self(id)
+    }
+}
+
+impl From<MessageId> for u64 {
+    fn from(id: MessageId) -> Self {
+        id.0
+    }
+}
+
+impl fmt::Display for MessageId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Bộ sinh id tăng dần trên một node (kể cả khi đồng hồ chạy lùi)
+pub struct MessageIdGenerator {
+    node_id: u16,
+    // (ms của id gần nhất, sequence gần nhất)
+    last: Mutex<(u64, u16)>,
+}
+
+impl MessageIdGenerator {
+    pub fn new(node_id: u16) -> Self {
+        Self { node_id: node_id & MessageId::MAX_NODE, last: Mutex::new((0, 0)) }
+    }
+
+    pub fn node_id(&self) -> u16 {
+        self.node_id
+    }
+
+    pub fn next_id(&self) -> MessageId {
+        let mut last = self.last.lock().unwrap();
+        let (last_ms, last_seq) = *last;
+        let now = now_ms().max(MessageId::EPOCH_MS);
+
+        let next = if now > last_ms {
+            (now, 0)
+        } else if last_seq < MessageId::MAX_SEQUENCE {
+            // Cùng ms hoặc đồng hồ lùi → tăng sequence trên ms cũ
+            (last_ms, last_seq + 1)
+        } else {
+            // Hết sequence → mượn ms kế tiếp
+            (last_ms + 1, 0)
+        };
+        *last = next;
+        MessageId::from_parts(next.0, self.node_id, next.1)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now_ms() -> u64 {
+    js_sys::Date::now() as u64
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -1,9 +1,43 @@
+// Feature: `client` (giờ, markdown - widget/admin), `validation` (làm sạch input - backend/admin),
+// `json` (serde cho kiểu proto), `crypto` (HMAC-SHA256), `ui` (component Leptos dùng chung). Mặc định chỉ có proto + CRC + MessageId.
 mod proto {
     include!(concat!(env!("OUT_DIR"), "/turbochat.v1.rs"));
 }
 
 pub use proto::*;
 
+#[cfg(feature = "client")]
+mod datetime;
+#[cfg(feature = "client")]
+pub use datetime::{format_clock, format_datetime, format_day_label, format_list_time, is_new_day, local_day, local_minute, now_us};
+
+mod id;
+pub use id::{MessageId, MessageIdGenerator};
+
+pub mod i18n;
+pub use i18n::{Direction, Locale};
+use i18n::Common;
+
+mod protocol;
+pub use protocol::{negotiate, supported_range, CLOSE_SERVER_DRAINING, CLOSE_TOO_MANY_CONNECTIONS, CLOSE_UNSUPPORTED_PROTOCOL, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION};
+
+#[cfg(feature = "crypto")]
+pub mod crypto;
+
+#[cfg(feature = "client")]
+mod markdown;
+#[cfg(feature = "client")]
+pub use markdown::{is_safe_url, render_markdown};
+
+#[cfg(feature = "ui")]
+mod emoji;
+#[cfg(feature = "ui")]
+pub use emoji::EmojiPicker;
+#[cfg(feature = "ui")]
+mod color_scheme;
+#[cfg(feature = "ui")]
+pub use color_scheme::{ColorScheme, ThemeMode};
+
 use bytes::Bytes;
 use thiserror::Error;
 
@@ -18,6 +52,9 @@ pub enum ContractError {
     #[error("Database error: {0}")]
     DbError(String),
 
+    #[error("Database timeout: {0}")]
+    Timeout(String),
+
     #[error("Auth error: {0}")]
     AuthError(String),
 }
@@ -40,9 +77,26 @@ impl Message {
             content,
             timestamp_us,
             content_crc,
+            ..Default::default()
         }
     }
 
+    /// content của dòng hệ thống khi bot chuyển hội thoại cho nhân viên
+    pub const SYSTEM_HANDOFF: &'static str = "handoff";
+
+    /// Dòng hệ thống (sender_type "system") - client hiện thành dòng giữa khung chat, content là mã sự kiện
+    pub fn is_system(&self) -> bool {
+        self.sender_type == "system"
+    }
+
+    /// Tin đã xoá → chỉ còn tombstone (id, thời gian, người xoá), bỏ nội dung
+    pub fn redact(&mut self) {
+        self.content = Bytes::new();
+        self.content_crc = crc32c::crc32c(&[]);
+        self.quick_replies.clear();
+        self.attachments.clear();
+    }
+
     pub fn verify_content(&self) -> Result<(), ContractError> {
         let computed = crc32c::crc32c(&self.content);
         if computed != self.content_crc {
@@ -64,6 +118,7 @@ impl SyncResponse {
         }
         buf.extend_from_slice(&self.server_timestamp_us.to_le_bytes());
         if self.has_more { buf.push(1); }
+        buf.extend_from_slice(self.page_state.as_bytes());
         crc32c::crc32c(&buf)
     }
 
@@ -81,4 +136,633 @@ impl SyncResponse {
     pub fn finalize(&mut self) {
         self.crc32 = self.compute_crc();
     }
-}
\ No newline at end of file
+}
+impl WsEnvelope {
+    /// Bọc tin nhắn chat - gửi tới admin và đúng guest của tin
+    pub fn message(msg: Message) -> Self {
+        Self {
+            shop_id: msg.shop_id.clone(),
+            guest_id: msg.guest_id,
+            admin_only: false,
+            protocol_version: 0,
+            request_id: String::new(),
+            traceparent: String::new(),
+            frame: Some(ws_envelope::Frame::Message(msg)),
+        }
+    }
+
+    /// Trạng thái trực tuyến - gửi tới mọi kết nối của shop
+    pub fn presence(shop_id: String, presence: Presence) -> Self {
+        Self {
+            shop_id,
+            guest_id: 0,
+            admin_only: false,
+            protocol_version: 0,
+            request_id: String::new(),
+            traceparent: String::new(),
+            frame: Some(ws_envelope::Frame::Presence(presence)),
+        }
+    }
+
+    /// Sửa tin - gửi tới admin và guest của cuộc chat
+    pub fn edit(edit: EditMessage) -> Self {
+        Self {
+            shop_id: edit.shop_id.clone(),
+            guest_id: edit.guest_id,
+            admin_only: false,
+            protocol_version: 0,
+            request_id: String::new(),
+            traceparent: String::new(),
+            frame: Some(ws_envelope::Frame::Edit(edit)),
+        }
+    }
+
+    /// Xoá tin - gửi tới admin và guest của cuộc chat
+    pub fn delete(delete: DeleteMessage) -> Self {
+        Self {
+            shop_id: delete.shop_id.clone(),
+            guest_id: delete.guest_id,
+            admin_only: false,
+            protocol_version: 0,
+            request_id: String::new(),
+            traceparent: String::new(),
+            frame: Some(ws_envelope::Frame::Delete(delete)),
+        }
+    }
+
+    /// Thông tin khách cập nhật - chỉ admin nhận
+    pub fn guest(guest: Guest) -> Self {
+        Self {
+            shop_id: guest.shop_id.clone(),
+            guest_id: guest.guest_id,
+            admin_only: true,
+            protocol_version: 0,
+            request_id: String::new(),
+            traceparent: String::new(),
+            frame: Some(ws_envelope::Frame::Guest(Box::new(guest))),
+        }
+    }
+
+    /// Ghi chú nội bộ thêm/sửa/xoá - chỉ admin nhận
+    pub fn note(shop_id: String, note: GuestNote) -> Self {
+        Self {
+            shop_id,
+            guest_id: note.guest_id,
+            admin_only: true,
+            protocol_version: 0,
+            request_id: String::new(),
+            traceparent: String::new(),
+            frame: Some(ws_envelope::Frame::Note(note)),
+        }
+    }
+
+    /// Trạng thái vắng mặt của admin - chỉ admin nhận
+    pub fn agent_status(shop_id: String, status: AgentStatus) -> Self {
+        Self {
+            shop_id,
+            guest_id: 0,
+            admin_only: true,
+            protocol_version: 0,
+            request_id: String::new(),
+            traceparent: String::new(),
+            frame: Some(ws_envelope::Frame::AgentStatus(status)),
+        }
+    }
+
+    /// Admin đang xem / gõ trong hội thoại - chỉ admin nhận
+    pub fn agent_activity(shop_id: String, activity: AgentActivity) -> Self {
+        Self {
+            shop_id,
+            guest_id: activity.guest_id,
+            admin_only: true,
+            protocol_version: 0,
+            request_id: String::new(),
+            traceparent: String::new(),
+            frame: Some(ws_envelope::Frame::AgentActivity(activity)),
+        }
+    }
+
+    /// Tin hẹn giờ thêm/huỷ/đã gửi - chỉ admin nhận
+    pub fn scheduled(shop_id: String, scheduled: ScheduledMessage) -> Self {
+        Self {
+            shop_id,
+            guest_id: scheduled.guest_id,
+            admin_only: true,
+            protocol_version: 0,
+            request_id: String::new(),
+            traceparent: String::new(),
+            frame: Some(ws_envelope::Frame::Scheduled(scheduled)),
+        }
+    }
+
+    /// Lời nhắc hội thoại đặt/huỷ/tới hạn - chỉ admin nhận
+    pub fn reminder(shop_id: String, reminder: Reminder) -> Self {
+        Self {
+            shop_id,
+            guest_id: reminder.guest_id,
+            admin_only: true,
+            protocol_version: 0,
+            request_id: String::new(),
+            traceparent: String::new(),
+            frame: Some(ws_envelope::Frame::Reminder(reminder)),
+        }
+    }
+
+    /// Bot chuyển hội thoại cho nhân viên - chỉ admin nhận
+    pub fn handoff(shop_id: String, handoff: Handoff) -> Self {
+        Self {
+            shop_id,
+            guest_id: handoff.guest_id,
+            admin_only: true,
+            protocol_version: 0,
+            request_id: String::new(),
+            traceparent: String::new(),
+            frame: Some(ws_envelope::Frame::Handoff(handoff)),
+        }
+    }
+
+    /// Cảnh báo SLA phản hồi đầu - chỉ admin nhận
+    pub fn sla_alert(shop_id: String, alert: SlaAlert) -> Self {
+        Self {
+            shop_id,
+            guest_id: alert.guest_id,
+            admin_only: true,
+            protocol_version: 0,
+            request_id: String::new(),
+            traceparent: String::new(),
+            frame: Some(ws_envelope::Frame::SlaAlert(alert)),
+        }
+    }
+
+    /// Báo tin vượt hạn mức - tới khách gửi tin, hoặc chỉ admin nếu admin gửi
+    pub fn quota_exceeded(shop_id: String, guest_id: u64, admin_only: bool, exceeded: QuotaExceeded) -> Self {
+        Self {
+            shop_id,
+            guest_id,
+            admin_only,
+            protocol_version: 0,
+            request_id: String::new(),
+            traceparent: String::new(),
+            frame: Some(ws_envelope::Frame::QuotaExceeded(exceeded)),
+        }
+    }
+
+    /// Tính năng của shop - mọi kết nối của shop
+    pub fn flags(shop_id: String, flags: ShopFlags) -> Self {
+        Self {
+            shop_id,
+            guest_id: 0,
+            admin_only: false,
+            protocol_version: 0,
+            request_id: String::new(),
+            traceparent: String::new(),
+            frame: Some(ws_envelope::Frame::Flags(flags)),
+        }
+    }
+
+    /// Phiên của kết nối khách - chỉ gửi thẳng cho kết nối đó
+    pub fn session(shop_id: String, guest_id: u64, session: Session) -> Self {
+        Self {
+            shop_id,
+            guest_id,
+            admin_only: false,
+            protocol_version: 0,
+            request_id: String::new(),
+            traceparent: String::new(),
+            frame: Some(ws_envelope::Frame::Session(session)),
+        }
+    }
+
+    /// Thông báo hệ thống - shop_id rỗng = mọi shop; kèm guest_id khi chỉ báo cho người gửi tin bị từ chối
+    pub fn server_notice(shop_id: String, guest_id: u64, admin_only: bool, notice: ServerNotice) -> Self {
+        Self {
+            shop_id,
+            guest_id,
+            admin_only,
+            protocol_version: 0,
+            request_id: String::new(),
+            traceparent: String::new(),
+            frame: Some(ws_envelope::Frame::ServerNotice(notice)),
+        }
+    }
+
+    /// Guest `from` đã gộp vào guest gốc - tới kết nối của `from` và admin
+    pub fn guest_merged(shop_id: String, merged: GuestMerged) -> Self {
+        Self {
+            shop_id,
+            guest_id: merged.from_guest_id,
+            admin_only: false,
+            protocol_version: 0,
+            request_id: String::new(),
+            traceparent: String::new(),
+            frame: Some(ws_envelope::Frame::GuestMerged(merged)),
+        }
+    }
+
+    /// Trả lời ping - server gửi thẳng cho kết nối đã ping (không qua broker)
+    pub fn pong(ping: Ping) -> Self {
+        Self { frame: Some(ws_envelope::Frame::Pong(ping)), ..Default::default() }
+    }
+
+    /// Frame client gửi lên (server tự điền shop/guest theo kết nối)
+    pub fn from_client(frame: ws_envelope::Frame) -> Self {
+        Self { frame: Some(frame), protocol_version: PROTOCOL_VERSION, ..Default::default() }
+    }
+
+    /// Gắn id lần theo frame qua log server
+    pub fn with_request_id(mut self, request_id: String) -> Self {
+        self.request_id = request_id;
+        self
+    }
+
+    /// Kết nối (guest_id = None là admin) có được nhận envelope này không
+    pub fn is_visible_to(&self, shop_id: &str, guest_id: Option<u64>) -> bool {
+        if !self.shop_id.is_empty() && self.shop_id != shop_id {
+            return false;
+        }
+        match guest_id {
+            None => true,
+            Some(gid) => !self.admin_only && (self.guest_id == 0 || self.guest_id == gid),
+        }
+    }
+}
+
+impl ConversationStatus {
+    /// Giá trị lưu trong cột guests.status
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            ConversationStatus::Open => "open",
+            ConversationStatus::Closed => "closed",
+            ConversationStatus::Archived => "archived",
+        }
+    }
+
+    /// Cột rỗng (guest cũ) hoặc lạ → coi như đang mở
+    pub fn from_db_str(s: &str) -> Self {
+        match s {
+            "closed" => ConversationStatus::Closed,
+            "archived" => ConversationStatus::Archived,
+            _ => ConversationStatus::Open,
+        }
+    }
+}
+
+/// Mức SLA phản hồi đầu của một hội thoại
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SlaLevel {
+    Ok,
+    Warning,
+    Breached,
+}
+
+impl ShopSettings {
+    /// Ngưỡng "sắp quá hạn": phần trăm thời gian SLA đã trôi qua
+    pub const SLA_WARNING_PERCENT: u64 = 80;
+
+    /// Mức SLA của hội thoại đang chờ từ `awaiting_since` (µs)
+    pub fn sla_level(&self, awaiting_since: u64, now_us: u64) -> SlaLevel {
+        if self.sla_first_response_secs == 0 || awaiting_since == 0 {
+            return SlaLevel::Ok;
+        }
+        let limit_us = self.sla_first_response_secs as u64 * 1_000_000;
+        let waited = now_us.saturating_sub(awaiting_since);
+        if waited >= limit_us {
+            SlaLevel::Breached
+        } else if waited * 100 >= limit_us * Self::SLA_WARNING_PERCENT {
+            SlaLevel::Warning
+        } else {
+            SlaLevel::Ok
+        }
+    }
+
+    /// Hạn phản hồi (µs) của hội thoại chờ từ `awaiting_since`
+    pub fn sla_deadline(&self, awaiting_since: u64) -> u64 {
+        awaiting_since + self.sla_first_response_secs as u64 * 1_000_000
+    }
+
+    /// Origin (vd. "https://www.shop.com") có thuộc tên miền được phép không; danh sách rỗng = mọi nơi
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        if self.allowed_domains.is_empty() {
+            return true;
+        }
+        let Some(host) = origin_host(origin) else { return false };
+        self.allowed_domains.iter().any(|pattern| {
+            let pattern = pattern.trim().trim_end_matches('.').to_ascii_lowercase();
+            match pattern.strip_prefix("*.") {
+                // "*.shop.com" khớp cả shop.com lẫn mọi tên miền con
+                Some(base) => host == base || host.ends_with(&format!(".{}", base)),
+                None => !pattern.is_empty() && host == pattern,
+            }
+        })
+    }
+}
+
+/// Host (chữ thường, bỏ cổng) của origin / URL: "https://Shop.com:8443/x" → "shop.com"
+pub fn origin_host(origin: &str) -> Option<String> {
+    let rest = origin.trim().split_once("://").map(|(_, rest)| rest)?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let authority = authority.rsplit_once('@').map(|(_, host)| host).unwrap_or(authority);
+    let host = match authority.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or(""),
+        None => authority.split(':').next().unwrap_or(""),
+    };
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    (!host.is_empty()).then_some(host)
+}
+
+/// Tìm trong nội dung tin: `query` (đã trim) có nằm trong `text` không, không phân biệt hoa thường
+pub fn text_matches(text: &str, query: &str) -> bool {
+    let query = query.trim();
+    !query.is_empty() && text.to_lowercase().contains(&query.to_lowercase())
+}
+
+impl ShopFlags {
+    /// Tên lưu trong cột shops.flags (set<text>)
+    pub const NAMES: [&'static str; 5] = ["e2ee", "ai_reply", "attachments", "translations", "voice_notes"];
+    /// Độ dài tin thoại khi shop chưa đặt / tối đa cho phép đặt (giây)
+    pub const DEFAULT_VOICE_MAX_SECS: u32 = 60;
+    pub const MAX_VOICE_MAX_SECS: u32 = 300;
+
+    /// Tên lạ bị bỏ qua
+    pub fn from_names<S: AsRef<str>>(names: impl IntoIterator<Item = S>) -> Self {
+        let mut flags = Self::default();
+        for name in names {
+            match name.as_ref() {
+                "e2ee" => flags.e2ee = true,
+                "ai_reply" => flags.ai_reply = true,
+                "attachments" => flags.attachments = true,
+                "translations" => flags.translations = true,
+                "voice_notes" => flags.voice_notes = true,
+                _ => {}
+            }
+        }
+        flags
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        let enabled = [self.e2ee, self.ai_reply, self.attachments, self.translations, self.voice_notes];
+        Self::NAMES.iter().zip(enabled).filter(|(_, on)| *on).map(|(name, _)| *name).collect()
+    }
+
+    /// Độ dài tối đa tin thoại đang áp dụng (giây)
+    pub fn voice_limit_secs(&self) -> u32 {
+        match self.voice_max_secs {
+            0 => Self::DEFAULT_VOICE_MAX_SECS,
+            secs => secs.min(Self::MAX_VOICE_MAX_SECS),
+        }
+    }
+}
+
+impl AdminPreferences {
+    /// Đang trong giờ yên lặng ở phút `minute` của ngày; start == end = không có giờ yên lặng
+    pub fn is_quiet_at(&self, minute: u32) -> bool {
+        let (start, end) = (self.quiet_start_minute, self.quiet_end_minute);
+        if !self.quiet_hours || start == end {
+            return false;
+        }
+        if start < end {
+            minute >= start && minute < end
+        } else {
+            minute >= start || minute < end
+        }
+    }
+
+    /// Hội thoại giao cho `assigned_to` có nằm trong phạm vi thông báo của admin này
+    pub fn covers(&self, assigned_to: &str) -> bool {
+        match self.scope() {
+            NotifyScope::All => true,
+            NotifyScope::Assigned => !self.agent_id.is_empty() && assigned_to == self.agent_id,
+        }
+    }
+}
+
+/// Cảm xúc hội thoại (để phân loại nhanh ở sidebar)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mood {
+    Positive,
+    Neutral,
+    Negative,
+}
+
+impl Mood {
+    /// Ngưỡng điểm để coi là tích cực / tiêu cực
+    pub const THRESHOLD: f32 = 0.25;
+
+    pub fn from_score(score: f32) -> Self {
+        if score >= Self::THRESHOLD {
+            Mood::Positive
+        } else if score <= -Self::THRESHOLD {
+            Mood::Negative
+        } else {
+            Mood::Neutral
+        }
+    }
+
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Mood::Positive => "🙂",
+            Mood::Neutral => "😐",
+            Mood::Negative => "😠",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Mood::Positive => Common::MoodPositive.text(),
+            Mood::Neutral => Common::MoodNeutral.text(),
+            Mood::Negative => Common::MoodNegative.text(),
+        }
+    }
+}
+
+impl Guest {
+    /// Cảm xúc hiện tại; None nếu chưa chấm tin nào
+    pub fn mood(&self) -> Option<Mood> {
+        (self.sentiment_at > 0).then(|| Mood::from_score(self.sentiment))
+    }
+
+    /// Tên hiển thị: tên đã identify, nếu chưa có thì tên mặc định theo guest_id
+    pub fn display_name(&self) -> String {
+        let name = self.guest_name.trim();
+        if name.is_empty() {
+            Self::fallback_name(self.guest_id)
+        } else {
+            name.to_string()
+        }
+    }
+
+    /// "Khách #3FA2C1" - trộn bit của id (snowflake) để hai khách gần nhau không trùng mã
+    pub fn fallback_name(guest_id: u64) -> String {
+        let mut x = guest_id;
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        x ^= x >> 31;
+        Common::GuestFallback.fill(&[&format!("{:06X}", x & 0xFF_FFFF)])
+    }
+}
+
+impl GeoLocation {
+    /// Cờ emoji từ mã quốc gia ("VN" → 🇻🇳)
+    pub fn flag(&self) -> String {
+        let code = self.country_code.to_ascii_uppercase();
+        if code.len() != 2 || !code.bytes().all(|b| b.is_ascii_uppercase()) {
+            return "🌐".to_string();
+        }
+        code.chars().filter_map(|c| char::from_u32(0x1F1E6 + (c as u32 - 'A' as u32))).collect()
+    }
+
+    /// "Hà Nội, Vietnam" (thiếu thành phố thì chỉ tên nước)
+    pub fn label(&self) -> String {
+        let country = if self.country.is_empty() { self.country_code.as_str() } else { self.country.as_str() };
+        if self.city.is_empty() {
+            country.to_string()
+        } else {
+            format!("{}, {}", self.city, country)
+        }
+    }
+}
+
+impl VisitorContext {
+    /// "https://shop.vn/pricing?x=1" → "/pricing?x=1"
+    pub fn page_path(&self) -> &str {
+        let rest = self.page_url.split_once("://").map_or(self.page_url.as_str(), |(_, r)| r);
+        rest.find('/').map_or("/", |i| &rest[i..])
+    }
+}
+
+impl EditMessage {
+    pub fn new(shop_id: String, guest_id: u64, message_id: u64, content: Bytes) -> Self {
+        let content_crc = crc32c::crc32c(&content);
+        Self {
+            shop_id,
+            guest_id,
+            message_id,
+            content,
+            content_crc,
+            edited_at_us: 0,
+        }
+    }
+
+    pub fn verify_content(&self) -> Result<(), ContractError> {
+        let computed = crc32c::crc32c(&self.content);
+        if computed != self.content_crc {
+            return Err(ContractError::CrcMismatch {
+                expected: self.content_crc,
+                actual: computed,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl QuickReply {
+    /// Số lựa chọn tối đa trong một tin
+    pub const MAX_PER_MESSAGE: usize = 10;
+    pub const MAX_LABEL_CHARS: usize = 40;
+    pub const MAX_PAYLOAD_CHARS: usize = 200;
+
+    /// Cắt bớt / bỏ lựa chọn rỗng (payload rỗng → dùng label)
+    #[cfg(feature = "validation")]
+    pub fn sanitize(options: Vec<QuickReply>) -> Vec<QuickReply> {
+        options.into_iter()
+            .filter_map(|q| {
+                let label: String = q.label.trim().chars().take(Self::MAX_LABEL_CHARS).collect();
+                if label.is_empty() {
+                    return None;
+                }
+                let payload = q.payload.trim();
+                let payload = if payload.is_empty() { label.clone() } else { payload.chars().take(Self::MAX_PAYLOAD_CHARS).collect() };
+                Some(QuickReply { label, payload })
+            })
+            .take(Self::MAX_PER_MESSAGE)
+            .collect()
+    }
+}
+
+impl Attachment {
+    /// Số file tối đa trong một tin
+    pub const MAX_PER_MESSAGE: usize = 10;
+    pub const MAX_FILE_NAME_CHARS: usize = 120;
+
+    /// Chỉ giữ file đã upload vào đúng cuộc chat (key có tiền tố shop/guest), bỏ url client gửi lên
+    #[cfg(feature = "validation")]
+    pub fn sanitize(attachments: Vec<Attachment>, shop_id: &str, guest_id: u64) -> Vec<Attachment> {
+        let prefix = format!("{}/{}/", shop_id, guest_id);
+        attachments.into_iter()
+            .filter(|a| a.key.starts_with(&prefix) && !a.key.contains(".."))
+            .map(|a| Attachment {
+                file_name: a.file_name.trim().chars().take(Self::MAX_FILE_NAME_CHARS).collect(),
+                url: String::new(),
+                thumbnail_url: String::new(),
+                ..a
+            })
+            .take(Self::MAX_PER_MESSAGE)
+            .collect()
+    }
+
+    pub fn is_image(&self) -> bool {
+        self.content_type.starts_with("image/")
+    }
+
+    pub fn is_audio(&self) -> bool {
+        self.content_type.starts_with("audio/")
+    }
+}
+
+impl ScanStatus {
+    /// Giá trị lưu trong JSON attachments
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            ScanStatus::Skipped => "skipped",
+            ScanStatus::Clean => "clean",
+            ScanStatus::Infected => "infected",
+        }
+    }
+
+    pub fn from_db_str(s: &str) -> Self {
+        match s {
+            "clean" => ScanStatus::Clean,
+            "infected" => ScanStatus::Infected,
+            _ => ScanStatus::Skipped,
+        }
+    }
+}
+
+impl ReplyPreview {
+    /// Số ký tự tối đa của trích đoạn
+    pub const SNIPPET_CHARS: usize = 80;
+
+    pub fn from_message(msg: &Message) -> Self {
+        let deleted = msg.deleted_at_us > 0;
+        let snippet = if deleted {
+            String::new()
+        } else if msg.content.is_empty() && !msg.attachments.is_empty() {
+            // Tin chỉ có file
+            format!("📎 {}", msg.attachments[0].file_name)
+        } else {
+            let text = String::from_utf8_lossy(&msg.content);
+            let mut snippet: String = text.chars().take(Self::SNIPPET_CHARS).collect();
+            if text.chars().count() > Self::SNIPPET_CHARS {
+                snippet.push('…');
+            }
+            snippet
+        };
+        Self {
+            message_id: msg.message_id,
+            sender_type: msg.sender_type.clone(),
+            snippet,
+            deleted,
+        }
+    }
+}
+
+impl ServerNotice {
+    /// Nội dung banner kèm giờ dự kiến xong (nếu có)
+    pub fn banner_text(&self) -> String {
+        if self.until_us > 0 {
+            Common::MaintenanceUntil.fill(&[&self.text, &format_datetime(self.until_us)])
+        } else {
+            format!("🛠️ {}", self.text)
+        }
+    }
+}
@@ -0,0 +1,227 @@
+// ============================================================================
+// MARKDOWN - tập con an toàn: **đậm**, *nghiêng* / _nghiêng_, `code`, [link](url)
+// Mọi ký tự đều được escape; chỉ sinh ra các thẻ strong/em/code/a/br.
+// Link chỉ chấp nhận http(s) và mailto.
+// ============================================================================
+
+// Giới hạn lồng nhau (đậm trong nghiêng trong link...)
+const MAX_DEPTH: usize = 4;
+
+/// Chuyển tin nhắn sang HTML đã sanitize (dùng với inner_html)
+pub fn render_markdown(input: &str) -> String {
+    let mut out = String::with_capacity(input.len() + 16);
+    render_inline(input, &mut out, 0);
+    out
+}
+
+fn render_inline(s: &str, out: &mut String, depth: usize) {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    // Đầu đoạn text thường chưa ghi ra
+    let mut plain_start = 0;
+
+    while i < bytes.len() {
+        let rest = &s[i..];
+        // Marker đều là ASCII nên cắt chuỗi theo byte vẫn đúng ranh giới UTF-8
+        let rendered = match bytes[i] {
+            b'`' => code_span(rest),
+            b'*' if rest.starts_with("**") => delimited(rest, "**", "strong", depth),
+            b'*' => delimited(rest, "*", "em", depth),
+            b'_' if !prev_is_word(s, i) => delimited(rest, "_", "em", depth),
+            b'[' => link(rest, depth),
+            b'h' if !prev_is_word(s, i) => bare_url(rest),
+            b'\n' => Some(("<br>".to_string(), 1)),
+            _ => None,
+        };
+
+        match rendered {
+            Some((html, len)) => {
+                escape_into(&s[plain_start..i], out);
+                out.push_str(&html);
+                i += len;
+                plain_start = i;
+            }
+            None => i += utf8_len(bytes[i]),
+        }
+    }
+    escape_into(&s[plain_start..], out);
+}
+
+// `code` - nội dung giữ nguyên (chỉ escape)
+fn code_span(rest: &str) -> Option<(String, usize)> {
+    let end = rest[1..].find('`')? + 1;
+    let inner = &rest[1..end];
+    if inner.is_empty() || inner.contains('\n') {
+        return None;
+    }
+    let mut html = String::from("<code>");
+    escape_into(inner, &mut html);
+    html.push_str("</code>");
+    Some((html, end + 1))
+}
+
+// **đậm** / *nghiêng* / _nghiêng_
+fn delimited(rest: &str, marker: &str, tag: &str, depth: usize) -> Option<(String, usize)> {
+    if depth >= MAX_DEPTH {
+        return None;
+    }
+    let body = &rest[marker.len()..];
+    let end = body.find(marker)?;
+    let inner = &body[..end];
+    // Không nhận "* a *" hay "**" rỗng (tránh nhầm với phép nhân, gạch đầu dòng)
+    if inner.is_empty()
+        || inner.starts_with(char::is_whitespace)
+        || inner.ends_with(char::is_whitespace)
+        || inner.contains('\n')
+    {
+        return None;
+    }
+    // _x_ phải kết thúc ở ranh giới từ (snake_case giữ nguyên)
+    if marker == "_" && body[end + 1..].starts_with(|c: char| c.is_alphanumeric()) {
+        return None;
+    }
+    let mut html = format!("<{}>", tag);
+    render_inline(inner, &mut html, depth + 1);
+    html.push_str(&format!("</{}>", tag));
+    Some((html, marker.len() * 2 + end))
+}
+
+// [text](url)
+fn link(rest: &str, depth: usize) -> Option<(String, usize)> {
+    if depth >= MAX_DEPTH {
+        return None;
+    }
+    let text_end = rest.find("](")?;
+    let text = &rest[1..text_end];
+    let url_start = text_end + 2;
+    let url_len = rest[url_start..].find(')')?;
+    let url = &rest[url_start..url_start + url_len];
+    if text.is_empty() || text.contains('\n') || !is_safe_url(url) {
+        return None;
+    }
+    let mut html = anchor_open(url);
+    render_inline(text, &mut html, depth + 1);
+    html.push_str("</a>");
+    Some((html, url_start + url_len + 1))
+}
+
+// Link trần http(s)://... (bỏ dấu câu ở cuối)
+fn bare_url(rest: &str) -> Option<(String, usize)> {
+    if !rest.starts_with("http://") && !rest.starts_with("https://") {
+        return None;
+    }
+    let end = rest.find(|c: char| c.is_whitespace() || c == '<' || c == '>').unwrap_or(rest.len());
+    let url = rest[..end].trim_end_matches(['.', ',', '!', '?', ')', ';', ':']);
+    if !is_safe_url(url) || url.ends_with("://") {
+        return None;
+    }
+    let mut html = anchor_open(url);
+    escape_into(url, &mut html);
+    html.push_str("</a>");
+    Some((html, url.len()))
+}
+
+fn anchor_open(url: &str) -> String {
+    let mut html = String::from("<a href=\"");
+    escape_into(url.trim(), &mut html);
+    html.push_str("\" target=\"_blank\" rel=\"noopener noreferrer nofollow\">");
+    html
+}
+
+/// Chỉ cho phép http(s)/mailto, không có khoảng trắng hay ký tự điều khiển
+pub fn is_safe_url(url: &str) -> bool {
+    let url = url.trim();
+    let lower = url.to_ascii_lowercase();
+    (lower.starts_with("http://") || lower.starts_with("https://") || lower.starts_with("mailto:"))
+        && !url.chars().any(|c| c.is_whitespace() || c.is_control() || matches!(c, '"' | '\'' | '<' | '>' | '`'))
+}
+
+fn prev_is_word(s: &str, i: usize) -> bool {
+    s[..i].chars().next_back().is_some_and(|c| c.is_alphanumeric())
+}
+
+fn utf8_len(first_byte: u8) -> usize {
+    match first_byte {
+        b if b < 0x80 => 1,
+        b if b >= 0xF0 => 4,
+        b if b >= 0xE0 => 3,
+        _ => 2,
+    }
+}
+
+fn escape_into(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_html() {
+        assert_eq!(render_markdown("<script>alert('x')</script> & \"y\""), "&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt; &amp; &quot;y&quot;");
+    }
+
+    #[test]
+    fn renders_inline_formatting() {
+        assert_eq!(render_markdown("**đậm** *nghiêng* _nghiêng_ `a<b`"), "<strong>đậm</strong> <em>nghiêng</em> <em>nghiêng</em> <code>a&lt;b</code>");
+        assert_eq!(render_markdown("dòng 1\ndòng 2"), "dòng 1<br>dòng 2");
+        assert_eq!(render_markdown("**a *b* c**"), "<strong>a <em>b</em> c</strong>");
+    }
+
+    #[test]
+    fn leaves_plain_markers_alone() {
+        assert_eq!(render_markdown("2 * 3 * 4"), "2 * 3 * 4");
+        assert_eq!(render_markdown("snake_case_name"), "snake_case_name");
+        assert_eq!(render_markdown("**"), "**");
+        assert_eq!(render_markdown("`a\nb`"), "`a<br>b`");
+    }
+
+    #[test]
+    fn renders_safe_links() {
+        assert_eq!(
+            render_markdown("[shop](https://example.com/a?b=1&c=2)"),
+            "<a href=\"https://example.com/a?b=1&amp;c=2\" target=\"_blank\" rel=\"noopener noreferrer nofollow\">shop</a>",
+        );
+        assert_eq!(
+            render_markdown("xem https://example.com."),
+            "xem <a href=\"https://example.com\" target=\"_blank\" rel=\"noopener noreferrer nofollow\">https://example.com</a>.",
+        );
+    }
+
+    #[test]
+    fn drops_unsafe_links() {
+        assert_eq!(render_markdown("[x](javascript:alert(1))"), "[x](javascript:alert(1))");
+        assert_eq!(render_markdown("[x](https://a.com\" onclick=\"y)"), "[x](https://a.com&quot; onclick=&quot;y)");
+        assert!(!render_markdown("[x](data:text/html,<b>)").contains("<a"));
+    }
+
+    #[test]
+    fn deep_nesting_stops_at_max_depth() {
+        let html = render_markdown("*_*_*_x_*_*_*");
+        assert_eq!(html.matches("<em>").count(), MAX_DEPTH);
+        assert_eq!(html.matches("<em>").count(), html.matches("</em>").count());
+    }
+
+    #[test]
+    fn safe_url_schemes() {
+        assert!(is_safe_url("https://example.com"));
+        assert!(is_safe_url("HTTP://EXAMPLE.COM"));
+        assert!(is_safe_url(" mailto:a@b.com "));
+        assert!(!is_safe_url("javascript:alert(1)"));
+        assert!(!is_safe_url("data:text/html;base64,AAAA"));
+        assert!(!is_safe_url("//example.com"));
+        assert!(!is_safe_url("https://a.com/x y"));
+        assert!(!is_safe_url("https://a.com/\"x"));
+        assert!(!is_safe_url("https://a.com/\u{0}"));
+    }
+}
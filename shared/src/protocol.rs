@@ -0,0 +1,52 @@
+// ============================================================================
+// PHIÊN BẢN PROTOCOL WS
+// Client gửi ?v=<n> khi kết nối (không có = 1, widget cũ đang chạy trên trang shop).
+// Server chỉ chuyển cho kết nối những frame mà phiên bản đó hiểu → thêm frame mới không làm hỏng client cũ
+// ============================================================================
+
+use crate::{ws_envelope::Frame, WsEnvelope};
+
+/// Phiên bản mới nhất. Thêm frame / đổi nghĩa field → tăng số này và ghi vào `since_version`
+pub const PROTOCOL_VERSION: u32 = 9;
+/// Phiên bản cũ nhất server còn phục vụ
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+/// Close code khi phiên bản client ngoài khoảng hỗ trợ; reason = "<min>-<max>"
+pub const CLOSE_UNSUPPORTED_PROTOCOL: u16 = 4010;
+/// Close code khi vượt số kết nối đồng thời cho phép; reason = "ip_limit"
+pub const CLOSE_TOO_MANY_CONNECTIONS: u16 = 4008;
+/// Close code khi node đang xả để deploy - client kết nối lại ngay (load balancer chuyển sang node khác)
+pub const CLOSE_SERVER_DRAINING: u16 = 4012;
+
+// v1: tới ShopFlags. v2: Session (nối lại phiên), WsEnvelope.protocol_version. v3: ServerNotice. v4: GuestMerged. v5: Ping/Pong. v6: AgentActivity. v7: Reminder. v8: ReadReceipt. v9: Handoff, tin sender_type "system"
+fn since_version(frame: &Frame) -> u32 {
+    match frame {
+        Frame::Session(_) => 2,
+        Frame::ServerNotice(_) => 3,
+        Frame::GuestMerged(_) => 4,
+        Frame::Ping(_) | Frame::Pong(_) => 5,
+        Frame::AgentActivity(_) => 6,
+        Frame::Reminder(_) => 7,
+        Frame::ReadReceipt(_) => 8,
+        Frame::Handoff(_) => 9,
+        Frame::Message(m) if m.is_system() => 9,
+        _ => 1,
+    }
+}
+
+/// Phiên bản dùng cho kết nối. None = client quá cũ hoặc mới hơn server
+pub fn negotiate(requested: Option<u32>) -> Option<u32> {
+    let version = requested.unwrap_or(1);
+    (MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version).then_some(version)
+}
+
+/// Khoảng phiên bản server hỗ trợ, dạng "<min>-<max>" (reason của close frame)
+pub fn supported_range() -> String {
+    format!("{}-{}", MIN_PROTOCOL_VERSION, PROTOCOL_VERSION)
+}
+
+impl WsEnvelope {
+    /// Client dùng `version` có hiểu frame này không (không hiểu → server bỏ qua, không gửi)
+    pub fn supported_by(&self, version: u32) -> bool {
+        self.frame.as_ref().is_none_or(|frame| since_version(frame) <= version)
+    }
+}